@@ -1,8 +1,10 @@
 //! Benchmarks for core IR operations.
 
-use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use std::hint::black_box;
 
-use convert_core::book::{BookDocument, ManifestData, ManifestItem, SpineItem, TocEntry};
+use criterion::{criterion_group, criterion_main, Criterion};
+
+use convert_core::book::{BookDocument, ManifestData, ManifestItem, TocEntry};
 
 fn bench_manifest_operations(c: &mut Criterion) {
     let mut group = c.benchmark_group("Manifest");
@@ -103,12 +105,12 @@ fn bench_spine_toc(c: &mut Criterion) {
             let mut book = BookDocument::new();
             for i in 0..100 {
                 let mut entry =
-                    TocEntry::new(format!("Chapter {}", i), &format!("chapter{}.xhtml", i));
+                    TocEntry::new(format!("Chapter {}", i), format!("chapter{}.xhtml", i));
                 // Add some children for depth
                 for j in 0..3 {
                     entry.add_child(TocEntry::new(
                         format!("Section {}.{}", i, j),
-                        &format!("chapter{}.xhtml#sec{}", i, j),
+                        format!("chapter{}.xhtml#sec{}", i, j),
                     ));
                 }
                 book.toc.add(entry);
@@ -120,11 +122,11 @@ fn bench_spine_toc(c: &mut Criterion) {
     group.bench_function("iterate_toc_depth_first_100", |b| {
         let mut book = BookDocument::new();
         for i in 0..100 {
-            let mut entry = TocEntry::new(format!("Chapter {}", i), &format!("chapter{}.xhtml", i));
+            let mut entry = TocEntry::new(format!("Chapter {}", i), format!("chapter{}.xhtml", i));
             for j in 0..3 {
                 entry.add_child(TocEntry::new(
                     format!("Section {}.{}", i, j),
-                    &format!("chapter{}.xhtml#sec{}", i, j),
+                    format!("chapter{}.xhtml#sec{}", i, j),
                 ));
             }
             book.toc.add(entry);
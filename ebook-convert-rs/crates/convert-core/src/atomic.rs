@@ -0,0 +1,165 @@
+//! Atomic output writing — never leave a truncated file at the target path.
+//!
+//! Output plugins write into a temporary file next to the target and only
+//! rename it into place once writing succeeded. If the writer returns an
+//! error or panics, the temporary file is removed and any existing file at
+//! the target is left untouched.
+
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::error::{ConvertError, Result};
+
+/// Run `write` against a temporary path in the target's directory, then
+/// rename the result to `target`.
+///
+/// The temporary file lives in the same directory so the final rename stays
+/// on one filesystem, and keeps the target's extension in case the writer
+/// looks at it.
+pub fn write_atomically<F>(target: &Path, write: F) -> Result<()>
+where
+    F: FnOnce(&Path) -> Result<()>,
+{
+    let mut temp = TempFile {
+        path: temp_path(target),
+        keep: false,
+    };
+    // Errors on the temporary file are reported against the real target
+    write(&temp.path).map_err(|e| match e {
+        ConvertError::Io { path, source } if path == temp.path => ConvertError::io(target, source),
+        other => other,
+    })?;
+    std::fs::rename(&temp.path, target).map_err(|e| ConvertError::io(target, e))?;
+    temp.keep = true;
+    Ok(())
+}
+
+/// Per-process counter that keeps concurrent writes to the same target
+/// (e.g. batch conversions from rayon workers) off each other's temp files.
+static NEXT_TEMP: AtomicUsize = AtomicUsize::new(0);
+
+/// `dir/.name.<pid>-<n>.partial.ext` for `dir/name.ext`, unique per call.
+fn temp_path(target: &Path) -> PathBuf {
+    let stem = target
+        .file_stem()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let n = NEXT_TEMP.fetch_add(1, Ordering::Relaxed);
+    let mut name = format!(".{}.{}-{}.partial", stem, std::process::id(), n);
+    if let Some(ext) = target.extension() {
+        name.push('.');
+        name.push_str(&ext.to_string_lossy());
+    }
+    target.with_file_name(name)
+}
+
+/// Removes the temporary file on drop unless it was renamed into place.
+struct TempFile {
+    path: PathBuf,
+    keep: bool,
+}
+
+impl Drop for TempFile {
+    fn drop(&mut self) {
+        if !self.keep {
+            let _ = std::fs::remove_file(&self.path);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    fn test_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(name);
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn entries(dir: &Path) -> Vec<String> {
+        std::fs::read_dir(dir)
+            .unwrap()
+            .map(|e| e.unwrap().file_name().to_string_lossy().into_owned())
+            .collect()
+    }
+
+    #[test]
+    fn test_success_renames_into_place() {
+        let dir = test_dir("atomic_write_ok");
+        let target = dir.join("book.epub");
+        write_atomically(&target, |tmp| {
+            assert_ne!(tmp, target.as_path());
+            assert_eq!(tmp.extension().unwrap(), "epub");
+            std::fs::write(tmp, b"complete").map_err(|e| ConvertError::io(tmp, e))?;
+            Ok(())
+        })
+        .unwrap();
+
+        assert_eq!(std::fs::read(&target).unwrap(), b"complete");
+        assert_eq!(entries(&dir), vec!["book.epub"]);
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_mid_write_failure_leaves_no_file() {
+        let dir = test_dir("atomic_write_fail");
+        let target = dir.join("book.pdf");
+        let result = write_atomically(&target, |tmp| {
+            let mut file = std::fs::File::create(tmp).map_err(|e| ConvertError::io(tmp, e))?;
+            file.write_all(b"%PDF-1.7 truncated")
+                .map_err(|e| ConvertError::io(tmp, e))?;
+            Err(ConvertError::Pdf("image decode failed".into()))
+        });
+
+        assert!(result.is_err());
+        assert!(!target.exists());
+        assert!(entries(&dir).is_empty());
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_panic_keeps_existing_target() {
+        let dir = test_dir("atomic_write_panic");
+        let target = dir.join("book.mobi");
+        std::fs::write(&target, b"previous").unwrap();
+
+        let result = std::panic::catch_unwind(|| {
+            write_atomically(&target, |tmp| {
+                std::fs::write(tmp, b"partial").map_err(|e| ConvertError::io(tmp, e))?;
+                panic!("writer crashed");
+            })
+        });
+
+        assert!(result.is_err());
+        assert_eq!(std::fs::read(&target).unwrap(), b"previous");
+        assert_eq!(entries(&dir), vec!["book.mobi"]);
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_temp_paths_are_unique_per_call() {
+        let target = Path::new("out/book.epub");
+        let a = temp_path(target);
+        let b = temp_path(target);
+        assert_ne!(a, b);
+        assert_eq!(a.parent(), target.parent());
+        assert_eq!(a.extension().unwrap(), "epub");
+    }
+
+    #[test]
+    fn test_temp_file_errors_name_the_target() {
+        let target = std::env::temp_dir()
+            .join("atomic_write_missing_dir")
+            .join("book.txt");
+        let result = write_atomically(&target, |tmp| {
+            std::fs::write(tmp, b"text").map_err(|e| ConvertError::io(tmp, e))
+        });
+        match result {
+            Err(ConvertError::Io { path, .. }) => assert_eq!(path, target),
+            other => panic!("expected Io error, got {:?}", other),
+        }
+    }
+}
@@ -6,9 +6,13 @@
 use std::collections::HashMap;
 use std::path::PathBuf;
 
+use once_cell::sync::Lazy;
 use rayon::prelude::*;
+use regex::Regex;
 use serde::{Deserialize, Serialize};
 
+use crate::error::ConvertError;
+
 /// The central intermediate representation for an ebook.
 /// Equivalent to Python's `OEBBook`.
 #[derive(Debug, Clone)]
@@ -44,6 +48,49 @@ impl BookDocument {
             base_path: None,
         }
     }
+
+    /// Number of whitespace-separated words in the text of the spine documents.
+    pub fn word_count(&self) -> usize {
+        let xhtmls: Vec<&str> = self
+            .spine
+            .iter()
+            .filter_map(|si| self.manifest.by_id(&si.idref))
+            .filter_map(|item| item.data.as_xhtml())
+            .collect();
+        xhtmls
+            .par_iter()
+            .map(|xhtml| {
+                TAG_RE
+                    .replace_all(body_of(xhtml), " ")
+                    .split_whitespace()
+                    .count()
+            })
+            .sum()
+    }
+
+    /// Minutes needed to read the book at `wpm` words per minute, rounded up.
+    pub fn estimated_reading_minutes(&self, wpm: u32) -> u32 {
+        let wpm = wpm.max(1) as usize;
+        self.word_count().div_ceil(wpm) as u32
+    }
+}
+
+static TAG_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"<[^>]*>").unwrap());
+
+/// The content of an XHTML document's `<body>`, or the whole text if it has none.
+fn body_of(xhtml: &str) -> &str {
+    let lower = xhtml.to_ascii_lowercase();
+    let Some(start) = lower.find("<body") else {
+        return xhtml;
+    };
+    let Some(open_end) = xhtml[start..].find('>') else {
+        return xhtml;
+    };
+    let body_start = start + open_end + 1;
+    let end = lower[body_start..]
+        .rfind("</body>")
+        .map_or(xhtml.len(), |i| body_start + i);
+    &xhtml[body_start..end]
 }
 
 impl Default for BookDocument {
@@ -174,6 +221,11 @@ impl Metadata {
     pub fn date(&self) -> Option<&str> {
         self.get_first_value("date")
     }
+
+    /// Whether the book is fixed-layout (`rendition:layout` is `pre-paginated`).
+    pub fn is_fixed_layout(&self) -> bool {
+        self.get_first_value("rendition:layout") == Some("pre-paginated")
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -253,6 +305,22 @@ impl Manifest {
             .map(move |idx| &mut self.items[idx])
     }
 
+    /// Change an item's href, keeping the href index in sync.
+    /// Returns false if the id is unknown or the href is already taken.
+    pub fn set_href(&mut self, id: &str, href: impl Into<String>) -> bool {
+        let href = href.into();
+        let Some(&idx) = self.id_index.get(id) else {
+            return false;
+        };
+        if self.href_index.contains_key(&href) {
+            return false;
+        }
+        let old = std::mem::replace(&mut self.items[idx].href, href.clone());
+        self.href_index.remove(&old);
+        self.href_index.insert(href, idx);
+        true
+    }
+
     /// Iterate over all items.
     pub fn iter(&self) -> impl Iterator<Item = &ManifestItem> {
         self.items.iter()
@@ -339,6 +407,8 @@ pub struct ManifestItem {
     pub data: ManifestData,
     /// Fallback item id for unsupported types
     pub fallback: Option<String>,
+    /// Id of the SMIL media overlay that syncs audio with this document
+    pub media_overlay: Option<String>,
 }
 
 impl ManifestItem {
@@ -354,6 +424,7 @@ impl ManifestItem {
             media_type: media_type.into(),
             data,
             fallback: None,
+            media_overlay: None,
         }
     }
 
@@ -457,6 +528,16 @@ pub enum PageDirection {
     Rtl,
 }
 
+impl PageDirection {
+    /// The OPF `page-progression-direction` value.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PageDirection::Ltr => "ltr",
+            PageDirection::Rtl => "rtl",
+        }
+    }
+}
+
 impl Spine {
     pub fn new() -> Self {
         Self {
@@ -517,6 +598,13 @@ impl Spine {
             .filter(|i| i.linear)
             .map(|i| i.idref.as_str())
     }
+
+    /// Items in the order a single-flow output presents them: the linear
+    /// items first, then the auxiliary (`linear="no"`) ones such as notes.
+    pub fn reading_order(&self) -> impl Iterator<Item = &SpineItem> {
+        let (linear, auxiliary): (Vec<_>, Vec<_>) = self.items.iter().partition(|i| i.linear);
+        linear.into_iter().chain(auxiliary)
+    }
 }
 
 // ---------------------------------------------------------------------------
@@ -550,6 +638,66 @@ impl Toc {
         }
     }
 
+    /// Number of levels in the tree; 0 when there are no entries.
+    pub fn depth(&self) -> usize {
+        fn depth_of(entries: &[TocEntry]) -> usize {
+            entries
+                .iter()
+                .map(|e| 1 + depth_of(&e.children))
+                .max()
+                .unwrap_or(0)
+        }
+        depth_of(&self.entries)
+    }
+
+    /// Drop entries nested deeper than `depth` levels; top-level entries are
+    /// at depth 1.
+    pub fn limit_depth(&mut self, depth: usize) {
+        fn prune(entries: &mut [TocEntry], depth: usize) {
+            for entry in entries {
+                if depth <= 1 {
+                    entry.children.clear();
+                } else {
+                    prune(&mut entry.children, depth - 1);
+                }
+            }
+        }
+        if depth == 0 {
+            self.entries.clear();
+        } else {
+            prune(&mut self.entries, depth);
+        }
+    }
+
+    /// Limit the tree to `depth` levels (at least 1) without losing entries:
+    /// everything below an entry at the deepest kept level becomes its
+    /// sibling, following it in reading order.
+    pub fn hoist_below(&mut self, depth: usize) {
+        fn flatten(entries: Vec<TocEntry>, out: &mut Vec<TocEntry>) {
+            for mut entry in entries {
+                let children = std::mem::take(&mut entry.children);
+                out.push(entry);
+                flatten(children, out);
+            }
+        }
+        fn hoist(entries: Vec<TocEntry>, depth: usize) -> Vec<TocEntry> {
+            let mut out = Vec::with_capacity(entries.len());
+            for mut entry in entries {
+                let children = std::mem::take(&mut entry.children);
+                if depth <= 1 {
+                    out.push(entry);
+                    flatten(children, &mut out);
+                } else {
+                    entry.children = hoist(children, depth - 1);
+                    out.push(entry);
+                }
+            }
+            out
+        }
+        let entries = std::mem::take(&mut self.entries);
+        self.entries = hoist(entries, depth);
+    }
+
     /// Rationalize play orders (assign sequential numbers).
     pub fn rationalize_play_orders(&mut self) {
         let mut order = 1;
@@ -688,10 +836,15 @@ pub enum EbookFormat {
     Xhtml,
     Txt,
     Markdown,
+    Rst,
+    Textile,
     Docx,
     Fb2,
     Rtf,
     Odt,
+    Cbz,
+    Pdb,
+    Tcr,
 }
 
 impl EbookFormat {
@@ -707,14 +860,47 @@ impl EbookFormat {
             "xhtml" | "xhtm" => Some(Self::Xhtml),
             "txt" => Some(Self::Txt),
             "md" | "markdown" => Some(Self::Markdown),
+            "rst" | "rest" => Some(Self::Rst),
+            "textile" => Some(Self::Textile),
             "docx" => Some(Self::Docx),
             "fb2" => Some(Self::Fb2),
             "rtf" => Some(Self::Rtf),
             "odt" => Some(Self::Odt),
+            "cbz" => Some(Self::Cbz),
+            "pdb" => Some(Self::Pdb),
+            "tcr" => Some(Self::Tcr),
             _ => None,
         }
     }
 
+    /// An error explaining that `ext` belongs to a format that is recognized
+    /// but not supported yet (e.g. `lrf`, `lit`), with a suggested way
+    /// around it. `None` for supported and unknown extensions, so callers can
+    /// tell "known, unimplemented" apart from "unknown".
+    pub fn unsupported_extension(ext: &str) -> Option<ConvertError> {
+        const VIA_CALIBRE: &str = "convert it to EPUB with Calibre first";
+        let (name, hint) = match ext.to_lowercase().as_str() {
+            "lrf" | "lrx" => ("LRF (Sony BBeB)", VIA_CALIBRE),
+            "lit" => ("LIT (Microsoft Reader)", VIA_CALIBRE),
+            "snb" => ("SNB (Shanda Bambook)", VIA_CALIBRE),
+            "pml" | "pmlz" => ("PML (eReader markup)", VIA_CALIBRE),
+            "rb" => ("RB (Rocket eBook)", VIA_CALIBRE),
+            "chm" => ("CHM (Compiled HTML Help)", VIA_CALIBRE),
+            "azw4" => ("AZW4 (Kindle Print Replica)", VIA_CALIBRE),
+            "tpz" | "azw1" => ("Topaz", VIA_CALIBRE),
+            "djvu" | "djv" => ("DjVu", "convert it to PDF first"),
+            "cbr" | "cb7" => (
+                "CBR/CB7 comic archive",
+                "repack the images as a CBZ (zip) archive",
+            ),
+            _ => return None,
+        };
+        Some(ConvertError::UnsupportedFormat(format!(
+            "{} is recognized but not supported yet; {}",
+            name, hint
+        )))
+    }
+
     /// Get the canonical file extension.
     pub fn extension(&self) -> &'static str {
         match self {
@@ -727,10 +913,15 @@ impl EbookFormat {
             Self::Xhtml => "xhtml",
             Self::Txt => "txt",
             Self::Markdown => "md",
+            Self::Rst => "rst",
+            Self::Textile => "textile",
             Self::Docx => "docx",
             Self::Fb2 => "fb2",
             Self::Rtf => "rtf",
             Self::Odt => "odt",
+            Self::Cbz => "cbz",
+            Self::Pdb => "pdb",
+            Self::Tcr => "tcr",
         }
     }
 
@@ -742,11 +933,14 @@ impl EbookFormat {
             Self::Mobi | Self::Azw => "application/x-mobipocket-ebook",
             Self::Azw3 => "application/x-mobi8-ebook",
             Self::Html | Self::Xhtml => "text/html",
-            Self::Txt | Self::Markdown => "text/plain",
+            Self::Txt | Self::Markdown | Self::Rst | Self::Textile => "text/plain",
             Self::Docx => "application/vnd.openxmlformats-officedocument.wordprocessingml.document",
             Self::Fb2 => "application/x-fictionbook+xml",
             Self::Rtf => "application/rtf",
             Self::Odt => "application/vnd.oasis.opendocument.text",
+            Self::Cbz => "application/vnd.comicbook+zip",
+            Self::Pdb => "application/vnd.palm",
+            Self::Tcr => "application/x-tcr-ebook",
         }
     }
 }
@@ -761,6 +955,31 @@ impl std::fmt::Display for EbookFormat {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_word_count_and_reading_time() {
+        let mut book = BookDocument::new();
+        let docs = [
+            ("ch1", "<html><head><title>Ignored title</title></head><body><h1>Chapter One</h1><p>It was a <em>dark</em> and stormy night.</p></body></html>"),
+            ("ch2", "<html><body class=\"x\"><p>The end.</p><br/><p>Really</p></body></html>"),
+            ("notes", "<html><body><p>Not in the spine.</p></body></html>"),
+        ];
+        for (id, xhtml) in docs {
+            book.manifest.add(ManifestItem::new(
+                id,
+                format!("{}.xhtml", id),
+                "application/xhtml+xml",
+                ManifestData::Xhtml(xhtml.to_string()),
+            ));
+        }
+        book.spine.push("ch1", true);
+        book.spine.push("ch2", true);
+
+        assert_eq!(book.word_count(), 12);
+        assert_eq!(book.estimated_reading_minutes(5), 3);
+        assert_eq!(book.estimated_reading_minutes(250), 1);
+        assert_eq!(BookDocument::new().estimated_reading_minutes(250), 0);
+    }
+
     #[test]
     fn test_book_document_creation() {
         let book = BookDocument::new();
@@ -798,6 +1017,25 @@ mod tests {
         assert!(manifest.by_id("ch1").unwrap().is_xhtml());
     }
 
+    #[test]
+    fn test_manifest_set_href() {
+        let mut manifest = Manifest::new();
+        for (id, href) in [("a", "a.png"), ("b", "b.png")] {
+            manifest.add(ManifestItem::new(
+                id,
+                href,
+                "image/png",
+                ManifestData::Binary(vec![]),
+            ));
+        }
+
+        assert!(manifest.set_href("a", "a.jpg"));
+        assert!(manifest.by_href("a.png").is_none());
+        assert_eq!(manifest.by_href("a.jpg").unwrap().id, "a");
+        assert!(!manifest.set_href("a", "b.png"));
+        assert!(!manifest.set_href("missing", "c.png"));
+    }
+
     #[test]
     fn test_manifest_generate_id() {
         let mut manifest = Manifest::new();
@@ -815,6 +1053,10 @@ mod tests {
 
         assert_eq!(spine.len(), 3);
         assert_eq!(spine.linear_items().count(), 2);
+
+        spine.insert(1, "popup", false);
+        let order: Vec<&str> = spine.reading_order().map(|i| i.idref.as_str()).collect();
+        assert_eq!(order, ["ch1", "ch2", "popup", "notes"]);
     }
 
     #[test]
@@ -836,6 +1078,61 @@ mod tests {
         assert_eq!(orders, vec![1, 2, 3, 4]);
     }
 
+    fn nested_toc() -> Toc {
+        let mut section = TocEntry::new("Section 1.1", "ch1.xhtml#s1");
+        section.add_child(TocEntry::new("Point 1.1.1", "ch1.xhtml#p1"));
+        let mut ch1 = TocEntry::new("Chapter 1", "ch1.xhtml");
+        ch1.add_child(section);
+        ch1.add_child(TocEntry::new("Section 1.2", "ch1.xhtml#s2"));
+        let mut part = TocEntry::new("Part One", "part1.xhtml");
+        part.add_child(ch1);
+        let mut toc = Toc::new();
+        toc.add(part);
+        toc
+    }
+
+    fn titles(toc: &Toc) -> Vec<&str> {
+        toc.iter_depth_first().map(|e| e.title.as_str()).collect()
+    }
+
+    #[test]
+    fn test_toc_limit_depth() {
+        let mut toc = nested_toc();
+        assert_eq!(toc.depth(), 4);
+        toc.limit_depth(3);
+        assert_eq!(toc.depth(), 3);
+        assert_eq!(
+            titles(&toc),
+            vec!["Part One", "Chapter 1", "Section 1.1", "Section 1.2"]
+        );
+        toc.limit_depth(2);
+        assert_eq!(toc.depth(), 2);
+        assert_eq!(titles(&toc), vec!["Part One", "Chapter 1"]);
+    }
+
+    #[test]
+    fn test_toc_hoist_below() {
+        let mut toc = nested_toc();
+        toc.hoist_below(2);
+        assert_eq!(toc.depth(), 2);
+        assert_eq!(
+            titles(&toc),
+            vec![
+                "Part One",
+                "Chapter 1",
+                "Section 1.1",
+                "Point 1.1.1",
+                "Section 1.2"
+            ]
+        );
+        let children: Vec<&str> = toc.entries[0]
+            .children
+            .iter()
+            .map(|e| e.title.as_str())
+            .collect();
+        assert_eq!(children.len(), 4);
+    }
+
     #[test]
     fn test_guide() {
         let mut guide = Guide::new();
@@ -854,4 +1151,18 @@ mod tests {
         assert_eq!(EbookFormat::from_extension("unknown"), None);
         assert_eq!(EbookFormat::Epub.extension(), "epub");
     }
+
+    #[test]
+    fn test_unsupported_extension() {
+        let err = EbookFormat::unsupported_extension("LIT").unwrap();
+        assert!(matches!(err, ConvertError::UnsupportedFormat(_)));
+        assert_eq!(
+            err.to_string(),
+            "Unsupported format: LIT (Microsoft Reader) is recognized but not supported yet; \
+             convert it to EPUB with Calibre first"
+        );
+        assert!(EbookFormat::from_extension("lit").is_none());
+        assert!(EbookFormat::unsupported_extension("epub").is_none());
+        assert!(EbookFormat::unsupported_extension("xyz").is_none());
+    }
 }
@@ -1,9 +1,16 @@
+use std::path::PathBuf;
+
 use thiserror::Error;
 
 #[derive(Error, Debug)]
 pub enum ConvertError {
-    #[error("I/O error: {0}")]
-    Io(#[from] std::io::Error),
+    /// Reading or writing `path` failed.
+    #[error("I/O error on {}: {source}", path.display())]
+    Io {
+        path: PathBuf,
+        #[source]
+        source: std::io::Error,
+    },
 
     #[error("XML parsing error: {0}")]
     Xml(String),
@@ -35,6 +42,12 @@ pub enum ConvertError {
     #[error("ODT error: {0}")]
     Odt(String),
 
+    #[error("CBZ error: {0}")]
+    Cbz(String),
+
+    #[error("PDB error: {0}")]
+    Pdb(String),
+
     #[error("Invalid manifest: {0}")]
     Manifest(String),
 
@@ -60,4 +73,14 @@ pub enum ConvertError {
     Other(String),
 }
 
+impl ConvertError {
+    /// An [`ConvertError::Io`] for a failed operation on `path`.
+    pub fn io(path: impl Into<PathBuf>, source: std::io::Error) -> Self {
+        ConvertError::Io {
+            path: path.into(),
+            source,
+        }
+    }
+}
+
 pub type Result<T> = std::result::Result<T, ConvertError>;
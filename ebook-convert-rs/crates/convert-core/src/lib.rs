@@ -1,5 +1,7 @@
+pub mod atomic;
 pub mod book;
 pub mod error;
+pub mod opf;
 pub mod options;
 pub mod pipeline;
 pub mod plugin;
@@ -0,0 +1,474 @@
+//! OPF package documents.
+//!
+//! [`BookDocument::to_opf`] serializes the metadata, manifest, spine and
+//! guide as an OPF 2.0 or 3.0 package. The EPUB writer uses it for
+//! `content.opf`, and `--debug-pipeline` dumps it next to the other
+//! snapshots of the book.
+
+use convert_utils::xml::XmlBuilder;
+
+use crate::book::BookDocument;
+use crate::options::EpubVersion;
+
+/// OPF rendition properties written for fixed-layout books, with the values
+/// used when the source did not specify them.
+const RENDITION_PROPERTIES: &[(&str, &str)] = &[
+    ("rendition:layout", "pre-paginated"),
+    ("rendition:orientation", "auto"),
+    ("rendition:spread", "auto"),
+];
+
+/// Navigation documents written alongside the book, listed in the package
+/// manifest when present.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct OpfNavigation<'a> {
+    /// The EPUB 2 NCX, which the spine's `toc` attribute points at
+    pub ncx_href: Option<&'a str>,
+    /// The EPUB 3 navigation document
+    pub nav_href: Option<&'a str>,
+}
+
+impl BookDocument {
+    /// The book's package document, as OPF 3.0 when its `version` is 3.x and
+    /// OPF 2.0 otherwise.
+    pub fn to_opf(&self) -> String {
+        let version = if self.version.starts_with('3') {
+            EpubVersion::V3
+        } else {
+            EpubVersion::V2
+        };
+        self.to_opf_with(version, OpfNavigation::default())
+    }
+
+    /// The book's package document for `version`, listing the given
+    /// navigation documents in the manifest.
+    pub fn to_opf_with(&self, version: EpubVersion, navigation: OpfNavigation) -> String {
+        let epub3 = version == EpubVersion::V3;
+        let uid = self
+            .uid
+            .as_deref()
+            .unwrap_or("urn:uuid:00000000-0000-0000-0000-000000000000");
+        let title = self.metadata.title().unwrap_or("Untitled");
+        let language = self.metadata.language().unwrap_or("en");
+
+        let mut xml = XmlBuilder::new();
+        xml.open_tag(
+            "package",
+            &[
+                ("xmlns", "http://www.idpf.org/2007/opf"),
+                ("unique-identifier", "bookid"),
+                ("version", if epub3 { "3.0" } else { "2.0" }),
+            ],
+        );
+
+        // Metadata
+        xml.open_tag(
+            "metadata",
+            &[
+                ("xmlns:dc", "http://purl.org/dc/elements/1.1/"),
+                ("xmlns:opf", "http://www.idpf.org/2007/opf"),
+            ],
+        );
+        write_titles(&mut xml, self, title, epub3);
+        xml.text_element("dc:language", language, &[]);
+        xml.text_element("dc:identifier", uid, &[("id", "bookid")]);
+
+        write_creators(&mut xml, self, epub3);
+
+        if let Some(desc) = self.metadata.description() {
+            xml.text_element("dc:description", desc, &[]);
+        }
+        if let Some(publisher) = self.metadata.publisher() {
+            xml.text_element("dc:publisher", publisher, &[]);
+        }
+        if let Some(date) = self.metadata.date() {
+            xml.text_element("dc:date", date, &[]);
+        }
+        if let Some(rights) = self.metadata.get_first_value("rights") {
+            xml.text_element("dc:rights", rights, &[]);
+        }
+        if let Some(cover_id) = cover_image_id(self) {
+            xml.empty_tag("meta", &[("name", "cover"), ("content", cover_id)]);
+        }
+        if let Some(series) = self.metadata.get_first_value("series") {
+            xml.empty_tag("meta", &[("name", "calibre:series"), ("content", series)]);
+            if let Some(index) = self.metadata.get_first_value("series_index") {
+                xml.empty_tag(
+                    "meta",
+                    &[("name", "calibre:series_index"), ("content", index)],
+                );
+            }
+        }
+
+        if epub3 {
+            let modified = self
+                .metadata
+                .get_first_value("dcterms:modified")
+                .map_or_else(utc_timestamp, str::to_string);
+            xml.text_element("meta", &modified, &[("property", "dcterms:modified")]);
+        }
+
+        // Fixed-layout rendition properties (EPUB 3 only); readers treat the
+        // book as reflowable without them
+        if epub3 && self.metadata.is_fixed_layout() {
+            for &(property, default) in RENDITION_PROPERTIES {
+                let value = self.metadata.get_first_value(property).unwrap_or(default);
+                xml.text_element("meta", value, &[("property", property)]);
+            }
+        }
+
+        // Media overlay properties (SMIL durations, active class), EPUB 3 only
+        let mut media_terms: Vec<(&str, _)> = self
+            .metadata
+            .iter()
+            .filter(|(term, _)| epub3 && term.starts_with("media:"))
+            .collect();
+        media_terms.sort_by_key(|(term, _)| *term);
+        for (term, items) in media_terms {
+            for item in items {
+                let mut attrs = vec![("property", term)];
+                if let Some(refines) = item.attributes.get("refines") {
+                    attrs.push(("refines", refines));
+                }
+                xml.text_element("meta", &item.value, &attrs);
+            }
+        }
+
+        xml.close_tag("metadata");
+
+        // Manifest
+        xml.open_tag("manifest", &[]);
+        if let Some(ncx_href) = navigation.ncx_href {
+            xml.empty_tag(
+                "item",
+                &[
+                    ("id", "ncx"),
+                    ("href", ncx_href),
+                    ("media-type", "application/x-dtbncx+xml"),
+                ],
+            );
+        }
+        if let Some(nav_href) = navigation.nav_href {
+            xml.empty_tag(
+                "item",
+                &[
+                    ("id", "nav"),
+                    ("href", nav_href),
+                    ("media-type", "application/xhtml+xml"),
+                    ("properties", "nav"),
+                ],
+            );
+        }
+
+        for item in self.manifest.iter() {
+            let mut attrs = vec![
+                ("id", item.id.as_str()),
+                ("href", item.href.as_str()),
+                ("media-type", item.media_type.as_str()),
+            ];
+            if let Some(overlay) = item.media_overlay.as_deref().filter(|_| epub3) {
+                attrs.push(("media-overlay", overlay));
+            }
+            if epub3 && item.data.as_xhtml().is_some_and(contains_mathml) {
+                attrs.push(("properties", "mathml"));
+            }
+            xml.empty_tag("item", &attrs);
+        }
+        xml.close_tag("manifest");
+
+        // Spine
+        let mut spine_attrs = Vec::new();
+        if navigation.ncx_href.is_some() {
+            spine_attrs.push(("toc", "ncx"));
+        }
+        if let Some(direction) = self.spine.page_progression_direction {
+            spine_attrs.push(("page-progression-direction", direction.as_str()));
+        }
+        xml.open_tag("spine", &spine_attrs);
+        for spine_item in self.spine.iter() {
+            if spine_item.linear {
+                xml.empty_tag("itemref", &[("idref", &spine_item.idref)]);
+            } else {
+                xml.empty_tag("itemref", &[("idref", &spine_item.idref), ("linear", "no")]);
+            }
+        }
+        xml.close_tag("spine");
+
+        // Guide
+        if !self.guide.is_empty() {
+            xml.open_tag("guide", &[]);
+            for guide_ref in self.guide.iter() {
+                xml.empty_tag(
+                    "reference",
+                    &[
+                        ("type", &guide_ref.ref_type),
+                        ("title", &guide_ref.title),
+                        ("href", &guide_ref.href),
+                    ],
+                );
+            }
+            xml.close_tag("guide");
+        }
+
+        xml.close_tag("package");
+        xml.build()
+    }
+}
+
+/// Manifest id of the image the guide `cover` reference points at.
+fn cover_image_id(book: &BookDocument) -> Option<&str> {
+    let href = book.guide.get("cover")?.href.split('#').next()?;
+    book.manifest
+        .by_href(href)
+        .filter(|item| item.is_image())
+        .map(|item| item.id.as_str())
+}
+
+/// Whether a content document contains MathML, which EPUB 3 requires its
+/// manifest item to declare.
+fn contains_mathml(xhtml: &str) -> bool {
+    xhtml.contains("<math") || xhtml.contains("<mml:math")
+}
+
+/// Write `dc:title` elements: the main title, then any others (subtitles and
+/// the like). EPUB 3 books get their `title-type` refinements back.
+fn write_titles(xml: &mut XmlBuilder, book: &BookDocument, main: &str, epub3: bool) {
+    let titles = book.metadata.get("title").unwrap_or_default();
+    if titles.is_empty() {
+        xml.text_element("dc:title", main, &[]);
+        return;
+    }
+    for (i, title) in titles.iter().enumerate() {
+        let title_type = title.attributes.get("title-type").filter(|_| epub3);
+        let Some(title_type) = title_type else {
+            xml.text_element("dc:title", &title.value, &[]);
+            continue;
+        };
+        let id = format!("title{}", i + 1);
+        xml.text_element("dc:title", &title.value, &[("id", id.as_str())]);
+        let refines = format!("#{}", id);
+        xml.text_element(
+            "meta",
+            title_type,
+            &[("refines", refines.as_str()), ("property", "title-type")],
+        );
+    }
+}
+
+/// Write `dc:creator` elements with their roles and sort names: as `opf:`
+/// attributes in EPUB 2, and as refining metas in EPUB 3, which dropped
+/// those attributes.
+fn write_creators(xml: &mut XmlBuilder, book: &BookDocument, epub3: bool) {
+    for (i, creator) in book
+        .metadata
+        .get("creator")
+        .unwrap_or_default()
+        .iter()
+        .enumerate()
+    {
+        let role = creator.attributes.get("role").map_or("aut", |r| r.as_str());
+        let file_as = creator.attributes.get("file-as");
+        if !epub3 {
+            let mut attrs = vec![("opf:role", role)];
+            if let Some(file_as) = file_as {
+                attrs.push(("opf:file-as", file_as));
+            }
+            xml.text_element("dc:creator", &creator.value, &attrs);
+            continue;
+        }
+
+        let id = format!("creator{}", i + 1);
+        xml.text_element("dc:creator", &creator.value, &[("id", id.as_str())]);
+        let refines = format!("#{}", id);
+        xml.text_element(
+            "meta",
+            role,
+            &[
+                ("refines", refines.as_str()),
+                ("property", "role"),
+                ("scheme", "marc:relators"),
+            ],
+        );
+        if let Some(file_as) = file_as {
+            xml.text_element(
+                "meta",
+                file_as,
+                &[("refines", refines.as_str()), ("property", "file-as")],
+            );
+        }
+    }
+}
+
+/// The current UTC time as `CCYY-MM-DDThh:mm:ssZ`, for `dcterms:modified`.
+fn utc_timestamp() -> String {
+    let secs = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs());
+    let (days, rem) = (secs / 86_400, secs % 86_400);
+
+    // Civil date from days since 1970-01-01 (Howard Hinnant's algorithm)
+    let z = days as i64 + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year,
+        month,
+        day,
+        rem / 3600,
+        rem % 3600 / 60,
+        rem % 60
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::book::{GuideRef, ManifestData, ManifestItem, PageDirection};
+
+    fn make_book() -> BookDocument {
+        let mut book = BookDocument::new();
+        book.metadata.set_title("Debug <Dump>");
+        for id in ["intro", "ch1", "ch2"] {
+            book.manifest.add(ManifestItem::new(
+                id,
+                format!("{}.xhtml", id),
+                "application/xhtml+xml",
+                ManifestData::Xhtml("<html><body><p>x</p></body></html>".to_string()),
+            ));
+        }
+        book.manifest.add(ManifestItem::new(
+            "css",
+            "style.css",
+            "text/css",
+            ManifestData::Css(String::new()),
+        ));
+        for id in ["ch2", "intro", "ch1"] {
+            book.spine.push(id, id != "intro");
+        }
+        book.guide.add(GuideRef::new("text", "Start", "ch2.xhtml"));
+        book
+    }
+
+    #[test]
+    fn test_to_opf() {
+        let opf = make_book().to_opf();
+        assert!(opf.contains(r#"version="2.0""#));
+        assert!(opf.contains("<dc:title>Debug &lt;Dump&gt;</dc:title>"));
+        for href in ["intro.xhtml", "ch1.xhtml", "ch2.xhtml", "style.css"] {
+            assert!(opf.contains(&format!(r#"href="{}""#, href)), "{}", href);
+        }
+        let ch2 = opf.find(r#"<itemref idref="ch2"/>"#).unwrap();
+        let intro = opf.find(r#"<itemref idref="intro" linear="no"/>"#).unwrap();
+        let ch1 = opf.find(r#"<itemref idref="ch1"/>"#).unwrap();
+        assert!(ch2 < intro && intro < ch1);
+        assert!(opf.contains("<spine>"));
+        assert!(!opf.contains("toc.ncx"));
+        assert!(opf.contains(r#"<reference type="text" title="Start" href="ch2.xhtml"/>"#));
+    }
+
+    #[test]
+    fn test_to_opf_follows_book_version() {
+        let mut book = make_book();
+        book.version = "3.0".to_string();
+        let opf = book.to_opf();
+        assert!(opf.contains(r#"version="3.0""#));
+        assert!(opf.contains(r#"property="dcterms:modified""#));
+    }
+
+    #[test]
+    fn test_to_opf_with_navigation() {
+        let navigation = OpfNavigation {
+            ncx_href: Some("toc.ncx"),
+            nav_href: Some("nav.xhtml"),
+        };
+        let opf = make_book().to_opf_with(EpubVersion::V3, navigation);
+        assert!(opf.contains(r#"<spine toc="ncx">"#));
+        assert!(opf.contains(r#"href="toc.ncx""#));
+        assert!(opf.contains(r#"properties="nav""#));
+    }
+
+    #[test]
+    fn test_to_opf_page_progression() {
+        let mut book = make_book();
+        book.spine.page_progression_direction = Some(PageDirection::Rtl);
+        let opf = book.to_opf();
+        assert!(opf.contains(r#"<spine page-progression-direction="rtl">"#));
+    }
+
+    #[test]
+    fn test_creator_refinements() {
+        let mut book = make_book();
+        let attrs = HashMap::from([
+            ("role".to_string(), "edt".to_string()),
+            ("file-as".to_string(), "Itor, Ed".to_string()),
+        ]);
+        book.metadata.add("creator", "Ann Writer");
+        book.metadata.add_with_attrs("creator", "Ed Itor", attrs);
+
+        let opf = book.to_opf_with(EpubVersion::V2, OpfNavigation::default());
+        assert!(opf.contains(r#"<dc:creator opf:role="aut">Ann Writer</dc:creator>"#));
+        assert!(opf
+            .contains(r#"<dc:creator opf:role="edt" opf:file-as="Itor, Ed">Ed Itor</dc:creator>"#));
+
+        let opf = book.to_opf_with(EpubVersion::V3, OpfNavigation::default());
+        assert!(!opf.contains("opf:role") && !opf.contains("opf:file-as"));
+        assert!(opf.contains(r#"<dc:creator id="creator1">Ann Writer</dc:creator>"#));
+        assert!(opf.contains(
+            r##"<meta refines="#creator1" property="role" scheme="marc:relators">aut</meta>"##
+        ));
+        assert!(opf.contains(r#"<dc:creator id="creator2">Ed Itor</dc:creator>"#));
+        assert!(opf.contains(
+            r##"<meta refines="#creator2" property="role" scheme="marc:relators">edt</meta>"##
+        ));
+        assert!(opf.contains(r##"<meta refines="#creator2" property="file-as">Itor, Ed</meta>"##));
+    }
+
+    #[test]
+    fn test_mathml_property() {
+        let mut book = make_book();
+        book.manifest.by_id_mut("ch1").unwrap().data = ManifestData::Xhtml(
+            r#"<html><body><p><math xmlns="http://www.w3.org/1998/Math/MathML"><mi>x</mi></math></p></body></html>"#
+                .to_string(),
+        );
+        let opf = book.to_opf_with(EpubVersion::V3, OpfNavigation::default());
+        assert!(opf.contains(
+            r#"<item id="ch1" href="ch1.xhtml" media-type="application/xhtml+xml" properties="mathml"/>"#
+        ));
+        assert_eq!(opf.matches("properties=").count(), 1);
+        let opf = book.to_opf_with(EpubVersion::V2, OpfNavigation::default());
+        assert!(!opf.contains("mathml"));
+    }
+
+    #[test]
+    fn test_rendition_properties_only_in_epub3() {
+        let mut book = make_book();
+        book.metadata.set("rendition:layout", "pre-paginated");
+        let opf = book.to_opf_with(EpubVersion::V2, OpfNavigation::default());
+        assert!(!opf.contains("rendition:"));
+        let opf = book.to_opf_with(EpubVersion::V3, OpfNavigation::default());
+        assert!(opf.contains(r#"<meta property="rendition:layout">pre-paginated</meta>"#));
+    }
+
+    #[test]
+    fn test_media_overlays_only_in_epub3() {
+        let mut book = make_book();
+        book.manifest.by_id_mut("ch1").unwrap().media_overlay = Some("ch1_overlay".into());
+        book.metadata.set("media:active-class", "active");
+        let opf = book.to_opf_with(EpubVersion::V2, OpfNavigation::default());
+        assert!(!opf.contains("media-overlay"));
+        assert!(!opf.contains("media:"));
+        let opf = book.to_opf_with(EpubVersion::V3, OpfNavigation::default());
+        assert!(opf.contains(r#"media-overlay="ch1_overlay""#));
+        assert!(opf.contains(r#"<meta property="media:active-class">active</meta>"#));
+    }
+}
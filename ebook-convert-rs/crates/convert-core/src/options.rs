@@ -14,14 +14,54 @@ pub struct ConversionOptions {
     // -- General --
     pub verbose: u8,
     pub debug_pipeline: Option<PathBuf>,
+    /// Cap on worker threads (rayon pool and pdftohtml processes). Defaults
+    /// to one per core.
+    pub max_threads: Option<usize>,
+    /// Transforms to skip, by name (e.g. `"LinearizeTables"`). Matching ignores
+    /// case and punctuation, so `"linearize-tables"` works too.
+    pub disabled_transforms: Vec<String>,
 
     // -- Input --
     pub input_encoding: Option<String>,
+    /// Convert `$...$` and `$$...$$` TeX math in Markdown input to MathML.
+    pub markdown_math: bool,
+    /// How plain-text input marks paragraph boundaries.
+    pub txt_paragraph_mode: TxtParagraphMode,
+    /// Download remote (`http`/`https`) images and stylesheets referenced by
+    /// HTML input and point the document at the local copies.
+    pub fetch_remote: bool,
+    /// Most remote resources fetched for one document.
+    pub fetch_remote_max_count: usize,
+    /// Total bytes that remote fetches may download for one document.
+    pub fetch_remote_max_bytes: u64,
+    /// How EPUB input references to missing or remote images and
+    /// stylesheets are repaired. They are only logged when unset.
+    pub missing_resources: Option<MissingResources>,
+    /// Keep DOCX review comments as numbered notes after the body, linked
+    /// from where they were made. They are dropped otherwise.
+    pub docx_comments: bool,
+    /// Take the title, and a subtitle from its second line, from the first
+    /// page's header of a DOCX document whose properties have no title.
+    pub docx_header_title: bool,
+
+    // -- Metadata overrides (replace what the input provided) --
+    pub title: Option<String>,
+    /// Replaces all `creator` entries when non-empty.
+    pub authors: Vec<String>,
+    pub language: Option<String>,
+    pub publisher: Option<String>,
+    pub series: Option<String>,
+    pub series_index: Option<f64>,
+    /// Image file (JPEG, PNG, GIF or WebP) that replaces the input's cover.
+    pub cover: Option<PathBuf>,
 
     // -- Look & Feel --
+    /// Body text size in pt; `0` keeps each output's default. Headings
+    /// scale with it.
     pub base_font_size: f64,
     pub font_size_mapping: Option<Vec<f64>>,
     pub minimum_line_height: f64,
+    /// Line height as a multiple of the font size (e.g. `1.4`).
     pub line_height: Option<f64>,
     pub embed_font_family: Option<String>,
     pub embed_all_fonts: bool,
@@ -30,20 +70,56 @@ pub struct ConversionOptions {
     pub filter_css: Option<String>,
     pub smarten_punctuation: bool,
     pub unsmarten_punctuation: bool,
+    /// Collapse runs of spaces and remove spaces before `.,;:!?` outside
+    /// `<pre>` and code (PDF and OCR artifacts).
+    pub clean_spacing: bool,
+    /// Wrap the first letter of the first paragraph after each heading in a
+    /// `dropcap` span styled as a decorative initial.
+    pub drop_caps: bool,
+    /// Insert soft hyphens into long words using hyphenation patterns for
+    /// the book's language, so justified text can break mid-word.
+    pub enable_hyphenation: bool,
+    /// Words with fewer letters than this are never hyphenated.
+    pub hyphenation_min_word_length: usize,
 
     // -- Page Setup --
-    pub margin_top: f64,
-    pub margin_bottom: f64,
-    pub margin_left: f64,
-    pub margin_right: f64,
+    /// Page margins in pt. `None` uses the output profile's margin.
+    pub margin_top: Option<f64>,
+    pub margin_bottom: Option<f64>,
+    pub margin_left: Option<f64>,
+    pub margin_right: Option<f64>,
 
     // -- Structure --
     pub chapter_mark: ChapterMark,
     pub chapter_regex: Option<String>,
+    /// Split documents at block elements whose text matches this pattern
+    /// (e.g. `^\* \* \*$`) instead of at `<h1>`/`<h2>` headings.
+    pub chapter_detection_regex: Option<String>,
+    /// Literal marker line (e.g. `---PAGEBREAK---`) that starts a new chapter.
+    /// The marker itself is removed. Takes precedence over `chapter_detection_regex`.
+    pub chapter_marker: Option<String>,
+    /// Split content documents larger than this many bytes at block
+    /// boundaries. `None` leaves documents whole.
+    pub max_flow_size: Option<usize>,
+    /// Text file listing document hrefs, one per line, in the desired reading
+    /// order. Unlisted spine documents follow in their original order.
+    pub spine_order: Option<PathBuf>,
+    /// Page progression direction. Taken from the input, or right to left
+    /// for Arabic, Hebrew, Persian and Urdu books, when unset.
+    pub page_progression: Option<PageProgression>,
     pub page_breaks_before: Option<String>,
     pub remove_first_image: bool,
     pub insert_metadata: bool,
     pub linearize_tables: bool,
+    /// Unwrap headings with no visible text from the content. Empty headings
+    /// are always left out of the TOC; this also removes the tags themselves.
+    pub remove_empty_headings: bool,
+    /// Drop paragraphs with no visible text and spine documents with no
+    /// visible content.
+    pub remove_blank: bool,
+    /// Renumber headings so the shallowest level used becomes `<h1>` and
+    /// levels are contiguous (e.g. h2/h4 become h1/h2).
+    pub normalize_headings: bool,
 
     // -- Table of Contents --
     pub no_default_epub_cover: bool,
@@ -53,6 +129,15 @@ pub struct ConversionOptions {
     pub level1_toc: Option<String>,
     pub level2_toc: Option<String>,
     pub level3_toc: Option<String>,
+    /// Collapse the TOC into a single level, in reading order.
+    pub flatten_toc: bool,
+    /// When flattening, prefix child titles with their parent's title.
+    pub flatten_toc_prefix: bool,
+    /// Deepest TOC level kept (top level is 1); deeper entries are dropped.
+    pub max_toc_depth: Option<usize>,
+    /// With `max_toc_depth`, move deeper entries up to the deepest kept
+    /// level instead of dropping them.
+    pub hoist_deep_toc: bool,
 
     // -- Image --
     #[serde(
@@ -63,19 +148,64 @@ pub struct ConversionOptions {
     pub no_images: bool,
     /// JPEG quality (1-100). Used when transcoding images (e.g., JP2→JPEG).
     pub jpeg_quality: u8,
+    /// Transcode opaque photographic PNG/BMP images larger than this many
+    /// bytes to JPEG at `jpeg_quality`.
+    pub image_transcode_threshold: Option<usize>,
+    /// Drop images smaller than this many pixels in both dimensions
+    /// (tracking pixels, spacer GIFs) along with their `<img>` references.
+    pub min_image_dimension: Option<u32>,
+    /// Collapse byte-identical images into one manifest item.
+    pub dedup_images: bool,
+    /// Fit the cover image to the output profile's screen. `None` leaves it as-is.
+    pub cover_fit: Option<CoverFit>,
+    /// Pick a cover when the input's guide has none. `None` leaves the book
+    /// without one.
+    pub ensure_cover: Option<EnsureCover>,
 
     // -- Output format --
     pub output_profile: OutputProfile,
     pub input_profile: InputProfile,
+    /// Keep output readable for debugging: stylesheets are not minified.
     pub pretty_print: bool,
+    /// Reindent spine XHTML before output: block elements on their own
+    /// lines, text and `<pre>` content unchanged.
+    pub pretty_xhtml: bool,
 
     // -- Format-specific --
     pub epub_version: EpubVersion,
+    /// How EPUB entries are compressed. `None` uses the deflate default;
+    /// `mimetype` is always stored.
+    pub epub_compression: Option<EpubCompression>,
+    /// Unix timestamp written as the creation and modification date of
+    /// MOBI output, for reproducible builds. The dates are zero when unset.
+    pub source_date_epoch: Option<u64>,
     pub epub_flatten: bool,
     pub pdf_page_size: Option<String>,
     pub pdf_serif_family: Option<String>,
     pub pdf_engine: PdfEngine,
     pub pdf_dpi: u16,
+    /// User password for encrypted PDF input.
+    pub pdf_password: Option<String>,
+    /// PDF input pages to convert, e.g. `"3-4,7"`. All pages when unset.
+    pub pdf_pages: Option<String>,
+    /// Render this 1-based PDF input page as the cover image, whether or
+    /// not it is within `pdf_pages`.
+    pub cover_from_page: Option<u32>,
+    /// Make the first converted PDF input page the cover, reusing its page
+    /// image when it was rendered as one. The page stays in the spine.
+    pub pdf_first_page_cover: bool,
+    /// Whether PDF output opens with the cover image as a full page.
+    pub pdf_cover_page: PdfCoverPage,
+    /// Fill colour (`#RRGGBB`) for the parts of the PDF cover page the
+    /// image leaves uncovered. White when unset.
+    pub pdf_cover_background: Option<String>,
+    /// Resolution assumed for PDF output images that do not record their
+    /// own, which sets their printed size.
+    pub pdf_image_dpi: u16,
+    /// Footer drawn at the bottom of each PDF output page except the cover.
+    /// `{page}` is replaced by the page number and `{title}` by the book
+    /// title. No footer when unset.
+    pub pdf_footer: Option<String>,
 
     // -- Formats (CLI/extension only, not from config file) --
     #[serde(skip)]
@@ -89,7 +219,24 @@ impl Default for ConversionOptions {
         Self {
             verbose: 0,
             debug_pipeline: None,
+            max_threads: None,
+            disabled_transforms: Vec::new(),
             input_encoding: None,
+            markdown_math: false,
+            txt_paragraph_mode: TxtParagraphMode::default(),
+            fetch_remote: false,
+            fetch_remote_max_count: 100,
+            fetch_remote_max_bytes: 50 * 1024 * 1024,
+            missing_resources: None,
+            docx_comments: false,
+            docx_header_title: false,
+            title: None,
+            authors: Vec::new(),
+            language: None,
+            publisher: None,
+            series: None,
+            series_index: None,
+            cover: None,
             base_font_size: 0.0,
             font_size_mapping: None,
             minimum_line_height: 120.0,
@@ -101,16 +248,28 @@ impl Default for ConversionOptions {
             filter_css: None,
             smarten_punctuation: false,
             unsmarten_punctuation: false,
-            margin_top: 5.0,
-            margin_bottom: 5.0,
-            margin_left: 5.0,
-            margin_right: 5.0,
+            clean_spacing: false,
+            drop_caps: false,
+            enable_hyphenation: false,
+            hyphenation_min_word_length: 6,
+            margin_top: None,
+            margin_bottom: None,
+            margin_left: None,
+            margin_right: None,
             chapter_mark: ChapterMark::PageBreak,
             chapter_regex: None,
+            chapter_detection_regex: None,
+            chapter_marker: None,
+            max_flow_size: None,
+            spine_order: None,
+            page_progression: None,
             page_breaks_before: None,
             remove_first_image: false,
             insert_metadata: false,
             linearize_tables: false,
+            remove_empty_headings: false,
+            remove_blank: false,
+            normalize_headings: false,
             no_default_epub_cover: false,
             max_toc_links: 50,
             toc_threshold: 6,
@@ -118,24 +277,62 @@ impl Default for ConversionOptions {
             level1_toc: None,
             level2_toc: None,
             level3_toc: None,
+            flatten_toc: false,
+            flatten_toc_prefix: false,
+            max_toc_depth: None,
+            hoist_deep_toc: false,
             max_image_size: None,
             no_images: false,
             jpeg_quality: 80,
+            image_transcode_threshold: None,
+            min_image_dimension: None,
+            dedup_images: false,
+            cover_fit: None,
+            ensure_cover: None,
             output_profile: OutputProfile::default(),
             input_profile: InputProfile::default(),
             pretty_print: false,
+            pretty_xhtml: false,
             epub_version: EpubVersion::V2,
+            epub_compression: None,
+            source_date_epoch: None,
             epub_flatten: false,
             pdf_page_size: None,
             pdf_serif_family: None,
             pdf_engine: PdfEngine::Auto,
             pdf_dpi: 200,
+            pdf_password: None,
+            pdf_pages: None,
+            cover_from_page: None,
+            pdf_first_page_cover: false,
+            pdf_cover_page: PdfCoverPage::default(),
+            pdf_cover_background: None,
+            pdf_image_dpi: 96,
+            pdf_footer: None,
             input_format: None,
             output_format: None,
         }
     }
 }
 
+impl ConversionOptions {
+    /// Whether the transform called `name` was disabled by the user.
+    pub fn is_transform_disabled(&self, name: &str) -> bool {
+        let name = normalize_transform_name(name);
+        self.disabled_transforms
+            .iter()
+            .any(|d| normalize_transform_name(d) == name)
+    }
+}
+
+/// Lowercase a transform name and drop everything but letters and digits.
+pub(crate) fn normalize_transform_name(name: &str) -> String {
+    name.chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .map(|c| c.to_ascii_lowercase())
+        .collect()
+}
+
 /// Serialize `Option<(u32, u32)>` as `"WxH"` string.
 fn serialize_image_size<S>(val: &Option<(u32, u32)>, s: S) -> Result<S::Ok, S::Error>
 where
@@ -183,6 +380,56 @@ pub enum PdfEngine {
     TextOnly,
 }
 
+/// Cover page handling for PDF output, used when the book has a cover image.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum PdfCoverPage {
+    /// Only the text title page.
+    Off,
+    /// The cover image page in place of the text title page.
+    Replace,
+    /// The cover image page, followed by the text title page.
+    #[default]
+    BeforeTitle,
+}
+
+/// How plain-text input separates paragraphs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum TxtParagraphMode {
+    /// Blank lines separate paragraphs; single newlines are kept as line breaks.
+    BlankLine,
+    /// Lines are hard-wrapped: single newlines are joined into the paragraph,
+    /// which ends at a blank line or an indented line.
+    HardWrap,
+    /// Pick `HardWrap` when most wrapped lines are of prose length,
+    /// otherwise `BlankLine`.
+    #[default]
+    Auto,
+}
+
+/// How a cover image is fitted to the output screen.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum CoverFit {
+    /// Scale the whole cover into the frame, letterboxing the spare space.
+    #[default]
+    Contain,
+    /// Scale the cover to fill the frame, cropping whatever overflows.
+    Cover,
+}
+
+/// How a cover is chosen for a book that doesn't mark one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum EnsureCover {
+    /// Promote a full-page first image, else the largest image.
+    #[default]
+    Image,
+    /// As `Image`, but generate a title page when no image is usable.
+    TitlePage,
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "kebab-case")]
 pub enum ChapterMark {
@@ -192,6 +439,37 @@ pub enum ChapterMark {
     None,
 }
 
+/// Direction pages turn in, and text runs in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum PageProgression {
+    Ltr,
+    Rtl,
+}
+
+/// Repair of references to resources missing from EPUB input.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum MissingResources {
+    /// Replace broken images with their alt text; remove broken stylesheet
+    /// links.
+    Placeholder,
+    /// Remove broken images and stylesheet links.
+    Strip,
+}
+
+/// Compression applied to EPUB archive entries.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum EpubCompression {
+    /// Store every entry uncompressed, for the fastest writes.
+    Stored,
+    /// Deflate at the fastest level.
+    Fast,
+    /// Deflate at the smallest-output level.
+    Best,
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum EpubVersion {
     V2,
@@ -236,6 +514,12 @@ pub struct OutputProfile {
     pub dpi: f64,
     pub fbase: f64,
     pub fsizes: Vec<f64>,
+    /// Images larger than this are scaled down when the user did not set
+    /// `max_image_size`. `None` leaves images alone.
+    pub max_image_size: Option<(u32, u32)>,
+    /// Page margin in pt on every side, used where the user did not set
+    /// `margin_top`/`margin_bottom`/`margin_left`/`margin_right`.
+    pub margin: f64,
 }
 
 impl Default for OutputProfile {
@@ -247,6 +531,82 @@ impl Default for OutputProfile {
             dpi: 166.0,
             fbase: 12.0,
             fsizes: vec![7.5, 9.0, 10.0, 12.0, 15.5, 20.0, 22.0, 24.0],
+            max_image_size: None,
+            margin: 5.0,
+        }
+    }
+}
+
+/// Named output devices selectable with `--output-profile`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum DeviceProfile {
+    Default,
+    KindlePaperwhite,
+    KindleOasis,
+    KindleScribe,
+    KoboClara,
+    KoboLibra,
+    GenericTablet,
+    GenericPhone,
+}
+
+impl DeviceProfile {
+    /// Every profile, in the order shown to users.
+    pub const ALL: &'static [DeviceProfile] = &[
+        DeviceProfile::Default,
+        DeviceProfile::KindlePaperwhite,
+        DeviceProfile::KindleOasis,
+        DeviceProfile::KindleScribe,
+        DeviceProfile::KoboClara,
+        DeviceProfile::KoboLibra,
+        DeviceProfile::GenericTablet,
+        DeviceProfile::GenericPhone,
+    ];
+
+    /// Kebab-case name, as accepted by `--output-profile`.
+    pub fn name(self) -> &'static str {
+        match self {
+            DeviceProfile::Default => "default",
+            DeviceProfile::KindlePaperwhite => "kindle-paperwhite",
+            DeviceProfile::KindleOasis => "kindle-oasis",
+            DeviceProfile::KindleScribe => "kindle-scribe",
+            DeviceProfile::KoboClara => "kobo-clara",
+            DeviceProfile::KoboLibra => "kobo-libra",
+            DeviceProfile::GenericTablet => "generic-tablet",
+            DeviceProfile::GenericPhone => "generic-phone",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.iter().copied().find(|p| p.name() == name)
+    }
+
+    /// Screen geometry, base font size and image limit for the device.
+    pub fn output_profile(self) -> OutputProfile {
+        // (width, height, dpi, base font size in pt, margin in pt). E-ink
+        // readers add their own margins around the page, so books get none.
+        let (screen_width, screen_height, dpi, fbase, margin) = match self {
+            DeviceProfile::Default => return OutputProfile::default(),
+            DeviceProfile::KindlePaperwhite => (1236, 1648, 300.0, 12.0, 0.0),
+            DeviceProfile::KindleOasis => (1264, 1680, 300.0, 12.0, 0.0),
+            DeviceProfile::KindleScribe => (1860, 2480, 300.0, 12.0, 0.0),
+            DeviceProfile::KoboClara => (1072, 1448, 300.0, 12.0, 0.0),
+            DeviceProfile::KoboLibra => (1264, 1680, 300.0, 12.0, 0.0),
+            DeviceProfile::GenericTablet => (1200, 1920, 224.0, 14.0, 10.0),
+            DeviceProfile::GenericPhone => (1080, 1920, 400.0, 16.0, 4.0),
+        };
+        let base = OutputProfile::default();
+        let scale = fbase / base.fbase;
+        OutputProfile {
+            name: self.name().to_string(),
+            screen_width,
+            screen_height,
+            dpi,
+            fbase,
+            fsizes: base.fsizes.iter().map(|size| size * scale).collect(),
+            max_image_size: Some((screen_width, screen_height)),
+            margin,
         }
     }
 }
@@ -291,7 +651,7 @@ mod tests {
             max_image_size: Some((1200, 1600)),
             extra_css: Some("body { font-size: 14px; }".to_string()),
             unsmarten_punctuation: true,
-            margin_top: 10.0,
+            margin_top: Some(10.0),
             ..Default::default()
         };
 
@@ -309,7 +669,7 @@ mod tests {
             Some("body { font-size: 14px; }")
         );
         assert!(parsed.unsmarten_punctuation);
-        assert_eq!(parsed.margin_top, 10.0);
+        assert_eq!(parsed.margin_top, Some(10.0));
     }
 
     #[test]
@@ -323,10 +683,27 @@ jpeg_quality = 90
         assert_eq!(opts.jpeg_quality, 90);
         // Defaults filled in
         assert_eq!(opts.pdf_engine, PdfEngine::Auto);
-        assert_eq!(opts.margin_top, 5.0);
+        assert_eq!(opts.margin_top, None);
         assert_eq!(opts.epub_version, EpubVersion::V2);
     }
 
+    #[test]
+    fn test_pdf_cover_page_serde() {
+        let opts: ConversionOptions = toml::from_str(r#"pdf_cover_page = "replace""#).unwrap();
+        assert_eq!(opts.pdf_cover_page, PdfCoverPage::Replace);
+        let opts: ConversionOptions = toml::from_str("").unwrap();
+        assert_eq!(opts.pdf_cover_page, PdfCoverPage::BeforeTitle);
+    }
+
+    #[test]
+    fn test_txt_paragraph_mode_serde() {
+        let opts: ConversionOptions =
+            toml::from_str(r#"txt_paragraph_mode = "hard-wrap""#).unwrap();
+        assert_eq!(opts.txt_paragraph_mode, TxtParagraphMode::HardWrap);
+        let opts: ConversionOptions = toml::from_str("").unwrap();
+        assert_eq!(opts.txt_paragraph_mode, TxtParagraphMode::Auto);
+    }
+
     #[test]
     fn test_pdf_engine_serde() {
         // Test via ConversionOptions (TOML requires table at top level)
@@ -376,6 +753,13 @@ jpeg_quality = 90
         assert_eq!(parsed.chapter_mark, ChapterMark::Rule);
     }
 
+    #[test]
+    fn test_epub_compression_serde() {
+        let opts: ConversionOptions = toml::from_str(r#"epub_compression = "best""#).unwrap();
+        assert_eq!(opts.epub_compression, Some(EpubCompression::Best));
+        assert_eq!(ConversionOptions::default().epub_compression, None);
+    }
+
     #[test]
     fn test_epub_version_serde() {
         let toml_str = r#"epub_version = "2""#;
@@ -429,7 +813,40 @@ epub_version = "2"
         assert_eq!(opts.extra_css.as_deref(), Some("body { font-size: 14px; }"));
         assert!(opts.unsmarten_punctuation);
         assert_eq!(opts.max_image_size, Some((1200, 1600)));
-        assert_eq!(opts.margin_top, 10.0);
+        assert_eq!(opts.margin_top, Some(10.0));
         assert_eq!(opts.epub_version, EpubVersion::V2);
     }
+
+    #[test]
+    fn test_disabled_transforms_from_toml() {
+        let opts: ConversionOptions =
+            toml::from_str(r#"disabled_transforms = ["CSSFlattener", "linearize_tables"]"#)
+                .unwrap();
+        assert!(opts.is_transform_disabled("CSSFlattener"));
+        assert!(opts.is_transform_disabled("LinearizeTables"));
+        assert!(!opts.is_transform_disabled("PageMargin"));
+    }
+
+    #[test]
+    fn test_device_profiles() {
+        let kindle = DeviceProfile::from_name("kindle-paperwhite")
+            .unwrap()
+            .output_profile();
+        assert_eq!(kindle.name, "kindle-paperwhite");
+        assert_eq!(kindle.max_image_size, Some((1236, 1648)));
+        assert_eq!(kindle.dpi, 300.0);
+
+        let phone = DeviceProfile::GenericPhone.output_profile();
+        assert_eq!(phone.fbase, 16.0);
+        assert_eq!(phone.fsizes[3], 16.0);
+
+        assert!(DeviceProfile::Default
+            .output_profile()
+            .max_image_size
+            .is_none());
+        assert!(DeviceProfile::from_name("kindle-dx").is_none());
+        for profile in DeviceProfile::ALL {
+            assert_eq!(DeviceProfile::from_name(profile.name()), Some(*profile));
+        }
+    }
 }
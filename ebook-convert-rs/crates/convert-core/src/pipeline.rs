@@ -8,11 +8,11 @@
 
 use std::path::Path;
 
-use log::info;
+use log::{info, warn};
 
 use crate::book::BookDocument;
 use crate::error::{ConvertError, Result};
-use crate::options::ConversionOptions;
+use crate::options::{normalize_transform_name, ConversionOptions};
 use crate::plugin::{InputPlugin, OutputPlugin, ProgressReporter, Transform};
 
 /// The conversion pipeline orchestrator.
@@ -45,11 +45,35 @@ impl Pipeline {
     }
 
     /// Run the full conversion pipeline.
+    ///
+    /// With `options.max_threads` set, the whole conversion runs inside a
+    /// rayon pool of that size, so every parallel stage is capped.
     pub fn run(
         &self,
         input_path: &Path,
         output_path: &Path,
         options: &ConversionOptions,
+    ) -> Result<()> {
+        match options.max_threads {
+            Some(threads) => {
+                let pool = rayon::ThreadPoolBuilder::new()
+                    .num_threads(threads.max(1))
+                    .build()
+                    .map_err(|e| {
+                        ConvertError::Pipeline(format!("Failed to create thread pool: {}", e))
+                    })?;
+                info!("Limiting conversion to {} threads", threads.max(1));
+                pool.install(|| self.run_phases(input_path, output_path, options))
+            }
+            None => self.run_phases(input_path, output_path, options),
+        }
+    }
+
+    fn run_phases(
+        &self,
+        input_path: &Path,
+        output_path: &Path,
+        options: &ConversionOptions,
     ) -> Result<()> {
         // Phase 1: Input
         self.report_progress(0.0, "Starting conversion...");
@@ -76,6 +100,7 @@ impl Pipeline {
         // Postprocess
         info!("Running postprocess...");
         self.input_plugin.postprocess(&mut book, options)?;
+        apply_metadata_overrides(&mut book, options);
         self.report_progress(0.25, "Postprocessing complete");
 
         // Specialize for output format
@@ -95,19 +120,35 @@ impl Pipeline {
         }
 
         // Phase 2: Transforms
-        let transform_count = self
-            .transforms
-            .iter()
-            .filter(|t| t.should_run(options))
-            .count();
-        let mut transform_idx = 0;
-
-        for transform in &self.transforms {
-            if !transform.should_run(options) {
-                info!("Skipping transform: {}", transform.name());
-                continue;
+        for name in &options.disabled_transforms {
+            let known = self
+                .transforms
+                .iter()
+                .any(|t| normalize_transform_name(t.name()) == normalize_transform_name(name));
+            if !known {
+                warn!("Cannot disable unknown transform '{}'", name);
             }
+        }
 
+        let active: Vec<&dyn Transform> = self
+            .transforms
+            .iter()
+            .map(|t| t.as_ref())
+            .filter(|t| {
+                if options.is_transform_disabled(t.name()) {
+                    info!("Skipping disabled transform: {}", t.name());
+                    false
+                } else if !t.should_run(options) {
+                    info!("Skipping transform: {}", t.name());
+                    false
+                } else {
+                    true
+                }
+            })
+            .collect();
+        let transform_count = active.len();
+
+        for (transform_idx, transform) in active.into_iter().enumerate() {
             let progress = 0.34 + (0.56 * transform_idx as f64 / transform_count.max(1) as f64);
             info!("Running transform: {}", transform.name());
             self.report_progress(progress, &format!("Running {}", transform.name()));
@@ -115,8 +156,6 @@ impl Pipeline {
             transform.apply(&mut book, options).map_err(|e| {
                 ConvertError::Pipeline(format!("Transform '{}' failed: {}", transform.name(), e))
             })?;
-
-            transform_idx += 1;
         }
 
         self.report_progress(0.90, "All transforms complete");
@@ -151,7 +190,36 @@ impl Pipeline {
     }
 }
 
-/// Dump book metadata for debug purposes.
+/// Replace input metadata with the values the user set explicitly.
+///
+/// Runs right after input, outside the transform list, so the overrides
+/// can't be disabled and `--stats` sees the same metadata as the output.
+pub fn apply_metadata_overrides(book: &mut BookDocument, options: &ConversionOptions) {
+    let metadata = &mut book.metadata;
+    if let Some(ref title) = options.title {
+        metadata.set_title(title);
+    }
+    if !options.authors.is_empty() {
+        metadata.remove("creator");
+        for author in &options.authors {
+            metadata.add("creator", author);
+        }
+    }
+    if let Some(ref language) = options.language {
+        metadata.set("language", language);
+    }
+    if let Some(ref publisher) = options.publisher {
+        metadata.set("publisher", publisher);
+    }
+    if let Some(ref series) = options.series {
+        metadata.set("series", series);
+    }
+    if let Some(index) = options.series_index {
+        metadata.set("series_index", index.to_string());
+    }
+}
+
+/// Dump book metadata, the manifest listing and the OPF for debug purposes.
 fn dump_book_debug(book: &BookDocument, dir: &Path) {
     // Write metadata summary
     let mut meta_lines = Vec::new();
@@ -174,6 +242,9 @@ fn dump_book_debug(book: &BookDocument, dir: &Path) {
     }
     let manifest_path = dir.join("manifest.txt");
     std::fs::write(manifest_path, manifest_lines.join("\n")).ok();
+
+    // The package document as the EPUB writer would serialize it
+    std::fs::write(dir.join("content.opf"), book.to_opf()).ok();
 }
 
 /// Builder for constructing a pipeline with the standard transform ordering.
@@ -295,6 +366,21 @@ mod tests {
         }
     }
 
+    /// Records its name in a shared log when applied.
+    struct RecordingTransform {
+        name: &'static str,
+        log: Arc<Mutex<Vec<String>>>,
+    }
+    impl Transform for RecordingTransform {
+        fn name(&self) -> &str {
+            self.name
+        }
+        fn apply(&self, _book: &mut BookDocument, _opts: &ConversionOptions) -> Result<()> {
+            self.log.lock().unwrap().push(self.name.to_string());
+            Ok(())
+        }
+    }
+
     #[test]
     fn test_pipeline_builder() {
         let pipeline = PipelineBuilder::new()
@@ -343,4 +429,140 @@ mod tests {
         // Last progress should be 1.0
         assert_eq!(progress.last().unwrap().0, 1.0);
     }
+
+    #[test]
+    fn test_disabled_transform_does_not_run() {
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let mut builder = PipelineBuilder::new()
+            .input(Box::new(TestInput))
+            .output(Box::new(TestOutput));
+        for name in ["DataURL", "LinearizeTables", "PageMargin"] {
+            builder = builder.transform(Box::new(RecordingTransform {
+                name,
+                log: log.clone(),
+            }));
+        }
+        let pipeline = builder.build().unwrap();
+
+        let tmp_dir = std::env::temp_dir().join("ebook_convert_test_disabled");
+        std::fs::create_dir_all(&tmp_dir).ok();
+        let input = tmp_dir.join("test.txt");
+        let output = tmp_dir.join("test.epub");
+        std::fs::write(&input, "test").ok();
+
+        let opts = ConversionOptions {
+            disabled_transforms: vec!["linearize-tables".to_string(), "NoSuchThing".to_string()],
+            ..Default::default()
+        };
+        pipeline.run(&input, &output, &opts).unwrap();
+
+        assert_eq!(*log.lock().unwrap(), vec!["DataURL", "PageMargin"]);
+    }
+
+    /// Records the size of the rayon pool it runs in.
+    struct ThreadCountTransform {
+        threads: Arc<Mutex<Option<usize>>>,
+    }
+    impl Transform for ThreadCountTransform {
+        fn name(&self) -> &str {
+            "ThreadCount"
+        }
+        fn apply(&self, _book: &mut BookDocument, _opts: &ConversionOptions) -> Result<()> {
+            *self.threads.lock().unwrap() = Some(rayon::current_num_threads());
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_max_threads_limits_pool() {
+        let threads = Arc::new(Mutex::new(None));
+        let pipeline = PipelineBuilder::new()
+            .input(Box::new(TestInput))
+            .output(Box::new(TestOutput))
+            .transform(Box::new(ThreadCountTransform {
+                threads: threads.clone(),
+            }))
+            .build()
+            .unwrap();
+
+        let tmp_dir = std::env::temp_dir().join("ebook_convert_test_threads");
+        std::fs::create_dir_all(&tmp_dir).ok();
+        let input = tmp_dir.join("test.txt");
+        let output = tmp_dir.join("test.epub");
+        std::fs::write(&input, "test").ok();
+
+        let opts = ConversionOptions {
+            max_threads: Some(1),
+            ..Default::default()
+        };
+        pipeline.run(&input, &output, &opts).unwrap();
+        assert_eq!(*threads.lock().unwrap(), Some(1));
+    }
+
+    #[test]
+    fn test_metadata_overrides() {
+        let mut book = BookDocument::new();
+        book.metadata.set_title("Old Title");
+        book.metadata.add("creator", "Old Author");
+        book.metadata.set("language", "fr");
+        book.metadata.set("publisher", "Old House");
+
+        let opts = ConversionOptions {
+            title: Some("New".to_string()),
+            authors: vec!["Ann One".to_string(), "Bob Two".to_string()],
+            series: Some("Saga".to_string()),
+            series_index: Some(2.0),
+            ..Default::default()
+        };
+        apply_metadata_overrides(&mut book, &opts);
+
+        assert_eq!(book.metadata.title(), Some("New"));
+        assert_eq!(book.metadata.authors(), vec!["Ann One", "Bob Two"]);
+        assert_eq!(book.metadata.get_first_value("series"), Some("Saga"));
+        assert_eq!(book.metadata.get_first_value("series_index"), Some("2"));
+        // Fields without an override are kept
+        assert_eq!(book.metadata.language(), Some("fr"));
+        assert_eq!(book.metadata.publisher(), Some("Old House"));
+    }
+
+    /// Records the title of the book it is applied to.
+    struct TitleTransform {
+        title: Arc<Mutex<Option<String>>>,
+    }
+    impl Transform for TitleTransform {
+        fn name(&self) -> &str {
+            "Title"
+        }
+        fn apply(&self, book: &mut BookDocument, _opts: &ConversionOptions) -> Result<()> {
+            *self.title.lock().unwrap() = book.metadata.title().map(str::to_string);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_metadata_overrides_precede_transforms() {
+        let title = Arc::new(Mutex::new(None));
+        let pipeline = PipelineBuilder::new()
+            .input(Box::new(TestInput))
+            .output(Box::new(TestOutput))
+            .transform(Box::new(TitleTransform {
+                title: title.clone(),
+            }))
+            .build()
+            .unwrap();
+
+        let tmp_dir = std::env::temp_dir().join("ebook_convert_test_overrides");
+        std::fs::create_dir_all(&tmp_dir).ok();
+        let input = tmp_dir.join("test.txt");
+        let output = tmp_dir.join("test.epub");
+        std::fs::write(&input, "test").ok();
+
+        let opts = ConversionOptions {
+            title: Some("Override".to_string()),
+            disabled_transforms: vec!["MergeMetadata".to_string()],
+            ..Default::default()
+        };
+        pipeline.run(&input, &output, &opts).unwrap();
+        assert_eq!(title.lock().unwrap().as_deref(), Some("Override"));
+    }
 }
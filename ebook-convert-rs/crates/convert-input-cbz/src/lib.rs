@@ -0,0 +1,208 @@
+//! CBZ (comic book archive) input plugin — reads CBZ files into BookDocument.
+//!
+//! A CBZ file is a ZIP archive of page images. Pages are ordered by file name
+//! using natural order (`page2.jpg` before `page10.jpg`), and each image gets
+//! its own XHTML page in the spine.
+
+use std::io::Read;
+use std::path::Path;
+
+use convert_core::book::{
+    BookDocument, EbookFormat, GuideRef, ManifestData, ManifestItem, TocEntry,
+};
+use convert_core::error::{ConvertError, Result};
+use convert_core::options::ConversionOptions;
+use convert_core::plugin::InputPlugin;
+use convert_utils::sort::natural_sort;
+
+/// File extensions recognized as comic pages.
+const IMAGE_EXTENSIONS: &[&str] = &["jpg", "jpeg", "png", "gif", "webp"];
+
+pub struct CbzInputPlugin;
+
+impl InputPlugin for CbzInputPlugin {
+    fn name(&self) -> &str {
+        "CBZ Input"
+    }
+
+    fn supported_formats(&self) -> &[EbookFormat] {
+        &[EbookFormat::Cbz]
+    }
+
+    fn convert(&self, input_path: &Path, _options: &ConversionOptions) -> Result<BookDocument> {
+        log::info!("Reading CBZ: {}", input_path.display());
+        parse_cbz(input_path)
+    }
+}
+
+fn parse_cbz(path: &Path) -> Result<BookDocument> {
+    let file = std::fs::File::open(path)
+        .map_err(|e| ConvertError::Cbz(format!("Cannot open {}: {}", path.display(), e)))?;
+
+    let mut archive = zip::ZipArchive::new(file)
+        .map_err(|e| ConvertError::Cbz(format!("Invalid CBZ (ZIP) file: {}", e)))?;
+
+    let mut book = BookDocument::new();
+    book.base_path = path.parent().map(|p| p.to_path_buf());
+
+    let title = path
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or("Untitled")
+        .to_string();
+    book.metadata.set_title(&title);
+
+    let mut image_files: Vec<String> = archive
+        .file_names()
+        .filter(|name| is_page_image(name))
+        .map(String::from)
+        .collect();
+    natural_sort(&mut image_files);
+
+    if image_files.is_empty() {
+        return Err(ConvertError::Cbz(format!(
+            "No images found in {}",
+            path.display()
+        )));
+    }
+
+    let digits = image_files.len().to_string().len();
+    for (i, name) in image_files.iter().enumerate() {
+        let mut entry = archive
+            .by_name(name)
+            .map_err(|e| ConvertError::Cbz(format!("{}: {}", name, e)))?;
+        let mut data = Vec::new();
+        entry
+            .read_to_end(&mut data)
+            .map_err(|e| ConvertError::Cbz(format!("{}: {}", name, e)))?;
+
+        let num = format!("{:0width$}", i + 1, width = digits);
+        let ext = extension(name).unwrap_or("jpg");
+        let image_href = format!("images/page{}.{}", num, ext);
+        let page_href = format!("page{}.xhtml", num);
+        let page_id = format!("page{}", num);
+
+        book.manifest.add(ManifestItem::new(
+            format!("img{}", num),
+            &image_href,
+            convert_utils::mime::mime_from_extension(ext),
+            ManifestData::Binary(data),
+        ));
+
+        let page_title = format!("Page {}", i + 1);
+        let body = format!(
+            "<div class=\"page\"><img src=\"{}\" alt=\"{}\"/></div>",
+            image_href, page_title
+        );
+        let xhtml =
+            convert_utils::xml::xhtml11_document(&page_title, "en", Some("style.css"), &body);
+        book.manifest.add(ManifestItem::new(
+            &page_id,
+            &page_href,
+            "application/xhtml+xml",
+            ManifestData::Xhtml(xhtml),
+        ));
+        book.spine.push(&page_id, true);
+        book.toc.add(TocEntry::new(&page_title, &page_href));
+
+        if i == 0 {
+            book.guide.add(GuideRef::new("cover", "Cover", &image_href));
+        }
+    }
+
+    let css = r#"body { margin: 0; padding: 0; text-align: center; }
+.page { margin: 0; padding: 0; }
+img { max-width: 100%; max-height: 100%; }"#;
+    book.manifest.add(ManifestItem::new(
+        "style",
+        "style.css",
+        "text/css",
+        ManifestData::Css(css.to_string()),
+    ));
+
+    log::info!("Parsed CBZ: \"{}\" with {} pages", title, image_files.len());
+
+    Ok(book)
+}
+
+/// Whether a ZIP entry is a page image, skipping directories and
+/// macOS resource-fork and hidden files.
+fn is_page_image(name: &str) -> bool {
+    if name.ends_with('/') || name.starts_with("__MACOSX/") {
+        return false;
+    }
+    let file_name = name.rsplit('/').next().unwrap_or(name);
+    if file_name.starts_with('.') {
+        return false;
+    }
+    extension(name).is_some()
+}
+
+/// Lowercased extension of a ZIP entry, if it is one of `IMAGE_EXTENSIONS`.
+fn extension(name: &str) -> Option<&'static str> {
+    let ext = name.rsplit_once('.')?.1.to_lowercase();
+    IMAGE_EXTENSIONS.iter().copied().find(|known| *known == ext)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_page_image() {
+        assert!(is_page_image("ch1/001.JPG"));
+        assert!(is_page_image("cover.png"));
+        assert!(!is_page_image("ComicInfo.xml"));
+        assert!(!is_page_image("__MACOSX/ch1/._001.jpg"));
+        assert!(!is_page_image("ch1/.thumb.jpg"));
+        assert!(!is_page_image("images/"));
+    }
+
+    #[test]
+    fn test_parse_cbz_three_pages() {
+        let path = std::env::temp_dir().join("test_cbz_three_pages.cbz");
+        {
+            let mut zip = convert_utils::archive::ZipBuilder::new(&path).unwrap();
+            zip.add_file("10.png", b"\x89PNG third").unwrap();
+            zip.add_file("2.png", b"\x89PNG second").unwrap();
+            zip.add_file("1.jpg", b"\xFF\xD8 first").unwrap();
+            zip.add_file("ComicInfo.xml", b"<ComicInfo/>").unwrap();
+            zip.finish().unwrap();
+        }
+
+        let book = parse_cbz(&path).unwrap();
+        let _ = std::fs::remove_file(&path);
+
+        assert_eq!(book.spine.len(), 3);
+        assert_eq!(book.toc.entries.len(), 3);
+        assert_eq!(book.metadata.title(), Some("test_cbz_three_pages"));
+
+        let first = book.manifest.by_href("images/page1.jpg").unwrap();
+        assert_eq!(first.media_type, "image/jpeg");
+        assert_eq!(first.data.as_binary(), Some(&b"\xFF\xD8 first"[..]));
+        let third = book.manifest.by_href("images/page3.png").unwrap();
+        assert_eq!(third.data.as_binary(), Some(&b"\x89PNG third"[..]));
+
+        let page = book.manifest.by_href("page2.xhtml").unwrap();
+        assert!(page
+            .data
+            .as_xhtml()
+            .unwrap()
+            .contains(r#"src="images/page2.png""#));
+        assert_eq!(book.guide.get("cover").unwrap().href, "images/page1.jpg");
+    }
+
+    #[test]
+    fn test_parse_cbz_without_images() {
+        let path = std::env::temp_dir().join("test_cbz_empty.cbz");
+        {
+            let mut zip = convert_utils::archive::ZipBuilder::new(&path).unwrap();
+            zip.add_file("readme.txt", b"nothing here").unwrap();
+            zip.finish().unwrap();
+        }
+
+        let result = parse_cbz(&path);
+        let _ = std::fs::remove_file(&path);
+        assert!(matches!(result, Err(ConvertError::Cbz(_))));
+    }
+}
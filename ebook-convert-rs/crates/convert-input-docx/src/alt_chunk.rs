@@ -0,0 +1,114 @@
+//! Resolve `w:altChunk` parts — external HTML or RTF content embedded in a DOCX.
+//!
+//! Word stores the imported content as a separate part referenced through
+//! `word/_rels/document.xml.rels`. Each supported part is converted to an
+//! HTML fragment keyed by relationship ID, ready to be spliced into the body.
+
+use std::collections::HashMap;
+use std::io::{Read, Seek};
+
+/// Convert every HTML/RTF part referenced from the relationship map.
+///
+/// Parts that are missing from the archive (e.g. external hyperlink targets)
+/// or fail to convert are skipped.
+pub fn load_alt_chunks<R: Read + Seek>(
+    archive: &mut zip::ZipArchive<R>,
+    rels: &HashMap<String, String>,
+) -> HashMap<String, String> {
+    let mut chunks = HashMap::new();
+
+    for (rid, target) in rels {
+        let lower = target.to_ascii_lowercase();
+        let is_html = [".htm", ".html", ".xhtml"]
+            .iter()
+            .any(|e| lower.ends_with(e));
+        let is_rtf = lower.ends_with(".rtf");
+        if !is_html && !is_rtf {
+            continue;
+        }
+
+        let name = part_name(target);
+        let Ok(mut file) = archive.by_name(&name) else {
+            continue;
+        };
+        let mut data = Vec::new();
+        if file.read_to_end(&mut data).is_err() {
+            continue;
+        }
+        let (text, _) = convert_utils::encoding::decode_to_utf8(&data);
+
+        let html = if is_html {
+            Some(html_fragment(&text))
+        } else {
+            match convert_input_rtf::rtf_to_html(&text) {
+                Ok(html) => Some(html),
+                Err(e) => {
+                    log::warn!("Skipping altChunk {}: {}", name, e);
+                    None
+                }
+            }
+        };
+        if let Some(html) = html {
+            chunks.insert(rid.clone(), html);
+        }
+    }
+
+    chunks
+}
+
+/// ZIP entry name of a relationship target (relative to `word/` unless absolute).
+pub(crate) fn part_name(target: &str) -> String {
+    match target.strip_prefix('/') {
+        Some(absolute) => absolute.to_string(),
+        None => convert_utils::href::resolve("word/document.xml", target),
+    }
+}
+
+/// Body content of an HTML document, made well-formed XHTML.
+fn html_fragment(html: &str) -> String {
+    let lower = html.to_lowercase();
+    let body = match lower.find("<body") {
+        Some(start) => {
+            let after = html[start..]
+                .find('>')
+                .map_or(html.len(), |i| start + i + 1);
+            let end = lower
+                .rfind("</body>")
+                .filter(|&e| e >= after)
+                .unwrap_or(html.len());
+            &html[after..end]
+        }
+        None => html,
+    };
+
+    let body = convert_utils::xml::html_to_xhtml(body);
+    format!("{}\n", body.trim())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_part_name() {
+        assert_eq!(part_name("afchunk.htm"), "word/afchunk.htm");
+        assert_eq!(part_name("/word/chunk.rtf"), "word/chunk.rtf");
+    }
+
+    #[test]
+    fn test_html_fragment() {
+        let html = "<html><head><title>x</title></head><body class=\"a\">\
+            <p>One<br>Two</p><hr></body></html>";
+        assert_eq!(html_fragment(html), "<p>One<br/>Two</p><hr/>\n");
+    }
+
+    #[test]
+    fn test_html_fragment_sanitized() {
+        let html = "<body><p class=x>A &amp; B<p>C & D<script>x()</script>\
+            <o:p></o:p></body>";
+        assert_eq!(
+            html_fragment(html),
+            "<p class=\"x\">A &amp; B</p><p>C &amp; D</p>\n"
+        );
+    }
+}
@@ -0,0 +1,120 @@
+//! Parse review comments from `word/comments.xml`.
+//!
+//! ```text
+//! <w:comment w:id="0" w:author="Ann"><w:p><w:r><w:annotationRef/></w:r>
+//! <w:r><w:t>Check this date.</w:t></w:r></w:p></w:comment>
+//! ```
+//!
+//! Only the text of each comment paragraph is kept; comments are rendered as
+//! notes after the body, so their formatting is not carried over.
+
+use std::collections::HashMap;
+
+use quick_xml::events::Event;
+use quick_xml::Reader;
+
+use convert_utils::xml::escape_xml_text;
+
+/// A review comment, with its paragraphs as escaped HTML text.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Comment {
+    pub author: String,
+    pub paragraphs: Vec<String>,
+}
+
+/// Parse `word/comments.xml` into comments by `w:id`.
+pub fn parse_comments(xml: &str) -> HashMap<String, Comment> {
+    let mut comments = HashMap::new();
+    let mut reader = Reader::from_str(xml);
+
+    let mut current: Option<(String, Comment)> = None;
+    let mut paragraph = String::new();
+    let mut in_text = false;
+
+    loop {
+        match reader.read_event() {
+            Ok(Event::Start(ref e)) => match e.local_name().as_ref() {
+                b"comment" => {
+                    let mut id = String::new();
+                    let mut comment = Comment::default();
+                    for attr in e.attributes().flatten() {
+                        match attr.key.local_name().as_ref() {
+                            b"id" => id = String::from_utf8_lossy(&attr.value).to_string(),
+                            b"author" => {
+                                comment.author = attr
+                                    .unescape_value()
+                                    .map(|v| v.to_string())
+                                    .unwrap_or_default()
+                            }
+                            _ => {}
+                        }
+                    }
+                    current = Some((id, comment));
+                }
+                b"p" => paragraph.clear(),
+                b"t" => in_text = true,
+                _ => {}
+            },
+            Ok(Event::Empty(ref e)) if current.is_some() => match e.local_name().as_ref() {
+                b"tab" => paragraph.push(' '),
+                b"br" => paragraph.push_str("<br/>"),
+                _ => {}
+            },
+            Ok(Event::Text(ref e)) if in_text && current.is_some() => {
+                if let Ok(text) = e.unescape() {
+                    paragraph.push_str(&escape_xml_text(&text));
+                }
+            }
+            Ok(Event::End(ref e)) => match e.local_name().as_ref() {
+                b"t" => in_text = false,
+                b"p" => {
+                    if let Some((_, comment)) = current.as_mut() {
+                        if !paragraph.trim().is_empty() {
+                            comment.paragraphs.push(paragraph.trim().to_string());
+                        }
+                    }
+                }
+                b"comment" => {
+                    if let Some((id, comment)) = current.take() {
+                        comments.insert(id, comment);
+                    }
+                }
+                _ => {}
+            },
+            Ok(Event::Eof) => break,
+            Err(_) => break,
+            _ => {}
+        }
+    }
+
+    comments
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_comments() {
+        let xml = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<w:comments xmlns:w="http://schemas.openxmlformats.org/wordprocessingml/2006/main">
+  <w:comment w:id="0" w:author="Ann &amp; Bo" w:initials="AB">
+    <w:p><w:r><w:annotationRef/></w:r><w:r><w:t>Check the</w:t></w:r><w:r><w:t xml:space="preserve"> date &lt;here&gt;.</w:t></w:r></w:p>
+    <w:p><w:r><w:t>Second thought.</w:t></w:r></w:p>
+  </w:comment>
+  <w:comment w:id="3"><w:p/></w:comment>
+</w:comments>"#;
+        let comments = parse_comments(xml);
+        assert_eq!(
+            comments["0"],
+            Comment {
+                author: "Ann & Bo".to_string(),
+                paragraphs: vec![
+                    "Check the date &lt;here&gt;.".to_string(),
+                    "Second thought.".to_string()
+                ],
+            }
+        );
+        assert!(comments["3"].paragraphs.is_empty());
+    }
+}
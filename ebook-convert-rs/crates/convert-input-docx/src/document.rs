@@ -2,20 +2,32 @@
 //!
 //! Handles Word Open XML elements:
 //! - `w:p` (paragraphs) → `<p>`, `<h1>`-`<h6>`, `<li>`
-//! - `w:r` (runs) → `<span>`, `<b>`, `<i>`, `<u>`, `<sub>`, `<sup>`
+//! - `w:r` (runs) → `<span>`, `<b>`, `<i>`, `<u>`, `<sub>`, `<sup>`, with
+//!   `w:color` / `w:highlight` as inline `color` / `background-color`
 //! - `w:t` (text)
-//! - `w:tbl` (tables) → `<table>`
+//! - `w:tbl` (tables) → `<table>`, with `w:gridCol` widths as a `<colgroup>`
 //! - `w:drawing` / `w:pict` (images) → `<img>`
 //! - `w:hyperlink` → `<a>`
-//! - `w:br` (breaks) → `<br/>`
+//! - `w:br` (breaks) → `<br/>`; page breaks (`w:br w:type="page"`,
+//!   `w:lastRenderedPageBreak`) → `<div class="mbp_pagebreak">` between blocks,
+//!   the marker SplitChapters and the EPUB page list look for
 //! - `w:tab` → tab space
+//! - `w:sym` (symbol font characters) → the Unicode character
+//! - `m:oMath` (equations) → `<span class="math">` holding the equation's
+//!   text, with `<sub>`/`<sup>` for scripts and `/` before denominators
+//! - `w:altChunk` → the referenced HTML/RTF part, converted ahead of time
+//! - `w:commentRangeStart`/`w:commentRangeEnd` → dropped; `w:commentReference`
+//!   → dropped, or a numbered link to the comment's note at the end of the
+//!   body when comments are kept
 
 use std::collections::HashMap;
 
 use quick_xml::events::Event;
 use quick_xml::Reader;
 
+use crate::comments::Comment;
 use crate::styles::{self, NumberingInfo, StyleInfo};
+use crate::symbols;
 
 /// Parse `word/_rels/document.xml.rels` into a relationship map (rId → target).
 pub fn parse_relationships(xml: &str) -> HashMap<String, String> {
@@ -52,17 +64,28 @@ pub fn parse_relationships(xml: &str) -> HashMap<String, String> {
     rels
 }
 
+/// Package parts that `word/document.xml` refers to.
+#[derive(Debug, Default)]
+pub struct DocumentContext {
+    /// Relationship map (rId → target).
+    pub rels: HashMap<String, String>,
+    /// Paragraph styles by style ID.
+    pub styles: HashMap<String, StyleInfo>,
+    /// List definitions by numbering ID.
+    pub numbering: HashMap<String, NumberingInfo>,
+    /// Converted `w:altChunk` content by relationship ID.
+    pub alt_chunks: HashMap<String, String>,
+    /// Review comments to render as notes, by comment ID. Empty unless
+    /// comments are kept.
+    pub comments: HashMap<String, Comment>,
+}
+
 /// Convert the main document XML into HTML body content.
-pub fn convert_document(
-    xml: &str,
-    rels: &HashMap<String, String>,
-    styles: &HashMap<String, StyleInfo>,
-    numbering: &HashMap<String, NumberingInfo>,
-) -> String {
+pub fn convert_document(xml: &str, ctx: &DocumentContext) -> String {
     let mut html = String::new();
     let mut reader = Reader::from_str(xml);
 
-    let mut state = ConvertState::new(rels, styles, numbering);
+    let mut state = ConvertState::new(ctx);
 
     loop {
         match reader.read_event() {
@@ -74,12 +97,10 @@ pub fn convert_document(
                 let local = String::from_utf8_lossy(e.local_name().as_ref()).to_string();
                 state.handle_empty(&local, e, &mut html);
             }
-            Ok(Event::Text(ref e)) => {
-                if state.in_text {
-                    if let Ok(text) = e.unescape() {
-                        state.para_buffer.push_str(&escape_html(&text));
-                        state.para_has_content = true;
-                    }
+            Ok(Event::Text(ref e)) if state.in_text => {
+                if let Ok(text) = e.unescape() {
+                    state.para_buffer.push_str(&escape_html(&text));
+                    state.para_has_content = true;
                 }
             }
             Ok(Event::End(ref e)) => {
@@ -94,14 +115,13 @@ pub fn convert_document(
 
     // Close any remaining open list
     state.close_list(&mut html);
+    state.write_comments(&mut html);
 
     html
 }
 
 struct ConvertState<'a> {
-    rels: &'a HashMap<String, String>,
-    styles: &'a HashMap<String, StyleInfo>,
-    numbering: &'a HashMap<String, NumberingInfo>,
+    ctx: &'a DocumentContext,
 
     // Current paragraph state
     in_paragraph: bool,
@@ -110,6 +130,11 @@ struct ConvertState<'a> {
     in_table: bool,
     in_row: bool,
     in_cell: bool,
+    // Inside an `m:oMath` equation
+    in_math: bool,
+
+    // Column widths (twips) from the current table's `w:tblGrid`
+    table_grid: Vec<f64>,
 
     // Run formatting
     is_bold: bool,
@@ -118,11 +143,20 @@ struct ConvertState<'a> {
     is_strike: bool,
     is_superscript: bool,
     is_subscript: bool,
+    // CSS colors from `w:color` / `w:highlight`, empty when unset
+    run_color: String,
+    run_highlight: String,
 
     // Current paragraph style
     para_style_id: String,
     para_alignment: String,
     para_num_id: String,
+    // Language of the paragraph mark (`w:pPr/w:rPr/w:lang`)
+    para_lang: String,
+    in_para_props: bool,
+
+    // Length of `html` right after the last page-break marker
+    page_break_at: Option<usize>,
 
     // Hyperlink state
     in_hyperlink: bool,
@@ -134,38 +168,43 @@ struct ConvertState<'a> {
     // Buffering paragraph content to wrap in correct tag
     para_buffer: String,
     para_has_content: bool,
+
+    // IDs of the comments referenced so far, in note order
+    comment_refs: Vec<String>,
 }
 
 impl<'a> ConvertState<'a> {
-    fn new(
-        rels: &'a HashMap<String, String>,
-        styles: &'a HashMap<String, StyleInfo>,
-        numbering: &'a HashMap<String, NumberingInfo>,
-    ) -> Self {
+    fn new(ctx: &'a DocumentContext) -> Self {
         Self {
-            rels,
-            styles,
-            numbering,
+            ctx,
             in_paragraph: false,
             in_run: false,
             in_text: false,
             in_table: false,
             in_row: false,
             in_cell: false,
+            in_math: false,
+            table_grid: Vec::new(),
             is_bold: false,
             is_italic: false,
             is_underline: false,
             is_strike: false,
             is_superscript: false,
             is_subscript: false,
+            run_color: String::new(),
+            run_highlight: String::new(),
             para_style_id: String::new(),
             para_alignment: String::new(),
             para_num_id: String::new(),
+            para_lang: String::new(),
+            in_para_props: false,
+            page_break_at: None,
             in_hyperlink: false,
             hyperlink_href: String::new(),
             current_list_type: None,
             para_buffer: String::new(),
             para_has_content: false,
+            comment_refs: Vec::new(),
         }
     }
 
@@ -176,10 +215,11 @@ impl<'a> ConvertState<'a> {
                 self.para_style_id.clear();
                 self.para_alignment.clear();
                 self.para_num_id.clear();
+                self.para_lang.clear();
                 self.para_buffer.clear();
                 self.para_has_content = false;
             }
-            "pPr" => {}
+            "pPr" => self.in_para_props = true,
             "pStyle" => {
                 for attr in e.attributes().flatten() {
                     let key = String::from_utf8_lossy(attr.key.local_name().as_ref()).to_string();
@@ -213,6 +253,8 @@ impl<'a> ConvertState<'a> {
                 self.is_strike = false;
                 self.is_superscript = false;
                 self.is_subscript = false;
+                self.run_color.clear();
+                self.run_highlight.clear();
             }
             "rPr" => {}
             "t" => {
@@ -225,6 +267,9 @@ impl<'a> ConvertState<'a> {
                 self.in_table = true;
                 html.push_str("<table>\n");
             }
+            "tblGrid" => self.table_grid.clear(),
+            "gridCol" => self.push_grid_col(e),
+            "altChunk" => self.insert_alt_chunk(e, html),
             "tr" => {
                 self.in_row = true;
                 html.push_str("<tr>");
@@ -240,7 +285,7 @@ impl<'a> ConvertState<'a> {
                     let key = String::from_utf8_lossy(attr.key.local_name().as_ref()).to_string();
                     if key == "id" {
                         let rid = String::from_utf8_lossy(&attr.value).to_string();
-                        if let Some(target) = self.rels.get(&rid) {
+                        if let Some(target) = self.ctx.rels.get(&rid) {
                             self.hyperlink_href = target.clone();
                         }
                     }
@@ -252,6 +297,17 @@ impl<'a> ConvertState<'a> {
                     ));
                 }
             }
+            "oMath" => {
+                self.in_math = true;
+                self.para_buffer.push_str("<span class=\"math\">");
+            }
+            "sub" | "sup" if self.in_math => {
+                self.para_buffer.push_str(&format!("<{}>", local));
+            }
+            "den" if self.in_math => {
+                self.para_buffer.push('/');
+                self.para_has_content = true;
+            }
             "drawing" | "pict" => {
                 // Image — look for relationship ID in child elements
                 // We handle this in empty elements (blip)
@@ -279,10 +335,38 @@ impl<'a> ConvertState<'a> {
                     }
                 }
             }
+            "color" if self.in_run => {
+                for attr in e.attributes().flatten() {
+                    let key = String::from_utf8_lossy(attr.key.local_name().as_ref()).to_string();
+                    if key == "val" {
+                        let val = String::from_utf8_lossy(&attr.value).to_string();
+                        self.run_color = hex_color(&val).unwrap_or_default();
+                    }
+                }
+            }
+            "highlight" if self.in_run => {
+                for attr in e.attributes().flatten() {
+                    let key = String::from_utf8_lossy(attr.key.local_name().as_ref()).to_string();
+                    if key == "val" {
+                        let val = String::from_utf8_lossy(&attr.value).to_string();
+                        self.run_highlight = highlight_color(&val).unwrap_or_default().to_string();
+                    }
+                }
+            }
             "br" if self.in_run => {
-                self.para_buffer.push_str("<br/>");
-                self.para_has_content = true;
+                let is_page = e
+                    .attributes()
+                    .flatten()
+                    .any(|a| a.key.local_name().as_ref() == b"type" && a.value.as_ref() == b"page");
+                if is_page {
+                    self.page_break(html, false);
+                } else {
+                    self.para_buffer.push_str("<br/>");
+                    self.para_has_content = true;
+                }
             }
+            "lastRenderedPageBreak" => self.page_break(html, true),
+            "sym" if self.in_run => self.push_symbol(e),
             "tab" if self.in_run => {
                 self.para_buffer.push_str("&#160;&#160;&#160;&#160;");
                 self.para_has_content = true;
@@ -311,13 +395,26 @@ impl<'a> ConvertState<'a> {
                     }
                 }
             }
+            "lang" if self.in_para_props => {
+                for attr in e.attributes().flatten() {
+                    let key = String::from_utf8_lossy(attr.key.local_name().as_ref()).to_string();
+                    if key == "val" {
+                        self.para_lang = String::from_utf8_lossy(&attr.value).to_string();
+                    }
+                }
+            }
+            "gridCol" => self.push_grid_col(e),
+            "altChunk" => self.insert_alt_chunk(e, html),
+            "commentReference" if self.in_run => self.push_comment_reference(e),
+            // Running heads repeat on every page and stay out of the body
+            "headerReference" | "footerReference" => {}
             "blip" => {
                 // Image embed reference
                 for attr in e.attributes().flatten() {
                     let key = String::from_utf8_lossy(attr.key.local_name().as_ref()).to_string();
                     if key == "embed" {
                         let rid = String::from_utf8_lossy(&attr.value).to_string();
-                        if let Some(target) = self.rels.get(&rid) {
+                        if let Some(target) = self.ctx.rels.get(&rid) {
                             // target is relative to word/ dir (e.g., "media/image1.png")
                             self.para_buffer.push_str(&format!(
                                 "<img src=\"{}\" alt=\"\"/>",
@@ -355,6 +452,14 @@ impl<'a> ConvertState<'a> {
             "r" => {
                 self.in_run = false;
             }
+            "pPr" => self.in_para_props = false,
+            "oMath" => {
+                self.para_buffer.push_str("</span>");
+                self.in_math = false;
+            }
+            "sub" | "sup" if self.in_math => {
+                self.para_buffer.push_str(&format!("</{}>", local));
+            }
             "p" => {
                 self.flush_paragraph(html);
                 self.in_paragraph = false;
@@ -363,6 +468,12 @@ impl<'a> ConvertState<'a> {
                 html.push_str("</table>\n");
                 self.in_table = false;
             }
+            "tblGrid" => {
+                if let Some(colgroup) = convert_utils::xml::colgroup(&self.table_grid) {
+                    html.push_str(&colgroup);
+                }
+                self.table_grid.clear();
+            }
             "tr" => {
                 html.push_str("</tr>\n");
                 self.in_row = false;
@@ -382,7 +493,108 @@ impl<'a> ConvertState<'a> {
         }
     }
 
+    /// Append the character of a `w:sym`, with the run's formatting.
+    fn push_symbol(&mut self, e: &quick_xml::events::BytesStart) {
+        let mut font = String::new();
+        let mut code = String::new();
+        for attr in e.attributes().flatten() {
+            match attr.key.local_name().as_ref() {
+                b"font" => font = String::from_utf8_lossy(&attr.value).to_string(),
+                b"char" => code = String::from_utf8_lossy(&attr.value).to_string(),
+                _ => {}
+            }
+        }
+        let Some(c) = symbols::symbol_char(&font, &code) else {
+            return;
+        };
+        self.open_run_formatting();
+        self.para_buffer.push_str(&escape_html(&c.to_string()));
+        self.close_run_formatting();
+        self.para_has_content = true;
+    }
+
+    /// Link a `w:commentReference` to its note, numbering notes in the order
+    /// they are referenced. Comments that are not kept leave nothing behind.
+    fn push_comment_reference(&mut self, e: &quick_xml::events::BytesStart) {
+        let Some(id) = e
+            .attributes()
+            .flatten()
+            .find(|a| a.key.local_name().as_ref() == b"id")
+            .map(|a| String::from_utf8_lossy(&a.value).to_string())
+        else {
+            return;
+        };
+        if !self.ctx.comments.contains_key(&id) {
+            return;
+        }
+        self.comment_refs.push(id);
+        let n = self.comment_refs.len();
+        self.para_buffer.push_str(&format!(
+            "<sup class=\"docx-comment-ref\"><a id=\"comment-ref-{}\" href=\"#comment-{}\">[{}]</a></sup>",
+            n, n, n
+        ));
+        self.para_has_content = true;
+    }
+
+    /// Append the referenced comments as numbered notes linking back to
+    /// their references.
+    fn write_comments(&self, html: &mut String) {
+        if self.comment_refs.is_empty() {
+            return;
+        }
+        html.push_str("<div class=\"docx-comments\">\n<hr/>\n");
+        for (i, id) in self.comment_refs.iter().enumerate() {
+            let n = i + 1;
+            let comment = &self.ctx.comments[id];
+            html.push_str(&format!(
+                "<p id=\"comment-{}\"><a href=\"#comment-ref-{}\">[{}]</a> ",
+                n, n, n
+            ));
+            if !comment.author.is_empty() {
+                html.push_str(&format!("<b>{}:</b> ", escape_html(&comment.author)));
+            }
+            html.push_str(&comment.paragraphs.join("<br/>"));
+            html.push_str("</p>\n");
+        }
+        html.push_str("</div>\n");
+    }
+
+    /// Splice in the converted content of the part referenced by a `w:altChunk`.
+    fn insert_alt_chunk(&mut self, e: &quick_xml::events::BytesStart, html: &mut String) {
+        let chunk = e
+            .attributes()
+            .flatten()
+            .find(|a| a.key.local_name().as_ref() == b"id")
+            .and_then(|a| {
+                self.ctx
+                    .alt_chunks
+                    .get(String::from_utf8_lossy(&a.value).as_ref())
+            });
+        match chunk {
+            Some(chunk) => {
+                self.close_list(html);
+                html.push_str(chunk);
+            }
+            None => log::warn!("Unsupported or missing altChunk part"),
+        }
+    }
+
+    /// Record the width of a `w:gridCol` (missing widths count as zero).
+    fn push_grid_col(&mut self, e: &quick_xml::events::BytesStart) {
+        let width = e
+            .attributes()
+            .flatten()
+            .find(|a| a.key.local_name().as_ref() == b"w")
+            .and_then(|a| String::from_utf8_lossy(&a.value).parse().ok())
+            .unwrap_or(0.0);
+        self.table_grid.push(width);
+    }
+
     fn open_run_formatting(&mut self) {
+        if let Some(style) = self.run_color_style() {
+            self.para_buffer
+                .push_str(&format!("<span style=\"{}\">", style));
+        }
         if self.is_superscript {
             self.para_buffer.push_str("<sup>");
         }
@@ -422,12 +634,27 @@ impl<'a> ConvertState<'a> {
         if self.is_superscript {
             self.para_buffer.push_str("</sup>");
         }
+        if self.run_color_style().is_some() {
+            self.para_buffer.push_str("</span>");
+        }
+    }
+
+    /// Inline `style` for the run's text and highlight colors, if any.
+    fn run_color_style(&self) -> Option<String> {
+        let mut decls = Vec::new();
+        if !self.run_color.is_empty() {
+            decls.push(format!("color:{}", self.run_color));
+        }
+        if !self.run_highlight.is_empty() {
+            decls.push(format!("background-color:{}", self.run_highlight));
+        }
+        (!decls.is_empty()).then(|| decls.join(";"))
     }
 
     fn flush_paragraph(&mut self, html: &mut String) {
         // Determine tag type: heading, list item, or regular paragraph
         let heading_level = if !self.para_style_id.is_empty() {
-            styles::heading_level(&self.para_style_id, self.styles)
+            styles::heading_level(&self.para_style_id, &self.ctx.styles)
         } else {
             None
         };
@@ -441,17 +668,18 @@ impl<'a> ConvertState<'a> {
             "both" | "distribute" => " class=\"docx-justify\"",
             _ => "",
         };
+        let lang = convert_utils::xml::lang_attrs(&self.para_lang);
 
         if let Some(level) = heading_level {
             // Close any open list before a heading
             self.close_list(html);
             let tag = format!("h{}", level);
-            html.push_str(&format!("<{}{}>", tag, class));
+            html.push_str(&format!("<{}{}{}>", tag, class, lang));
             html.push_str(&self.para_buffer);
             html.push_str(&format!("</{}>\n", tag));
         } else if is_list {
             // Determine list type
-            let list_type = if let Some(info) = self.numbering.get(&self.para_num_id) {
+            let list_type = if let Some(info) = self.ctx.numbering.get(&self.para_num_id) {
                 if info.format == "bullet" {
                     "ul"
                 } else {
@@ -476,7 +704,7 @@ impl<'a> ConvertState<'a> {
                 }
             }
 
-            html.push_str("<li>");
+            html.push_str(&format!("<li{}>", lang));
             html.push_str(&self.para_buffer);
             html.push_str("</li>\n");
         } else {
@@ -488,12 +716,42 @@ impl<'a> ConvertState<'a> {
                 return;
             }
 
-            html.push_str(&format!("<p{}>", class));
+            html.push_str(&format!("<p{}{}>", class, lang));
             html.push_str(&self.para_buffer);
             html.push_str("</p>\n");
         }
     }
 
+    /// Emit a page-break marker between blocks, splitting the current
+    /// paragraph around it. Breaks Word recorded from its own layout
+    /// (`rendered`) are dropped at the start of the document and right after
+    /// another marker, so each page is marked once.
+    fn page_break(&mut self, html: &mut String, rendered: bool) {
+        let at_marker = self.page_break_at == Some(html.len()) || html.is_empty();
+        if rendered && at_marker && !self.para_has_content {
+            return;
+        }
+
+        if self.para_has_content {
+            let link = self.in_hyperlink && !self.hyperlink_href.is_empty();
+            if link {
+                self.para_buffer.push_str("</a>");
+            }
+            self.flush_paragraph(html);
+            self.para_buffer.clear();
+            self.para_has_content = false;
+            if link {
+                self.para_buffer.push_str(&format!(
+                    "<a href=\"{}\">",
+                    escape_attr(&self.hyperlink_href)
+                ));
+            }
+        }
+        self.close_list(html);
+        html.push_str("<div class=\"mbp_pagebreak\"></div>\n");
+        self.page_break_at = Some(html.len());
+    }
+
     fn close_list(&mut self, html: &mut String) {
         if let Some(ref list_type) = self.current_list_type.take() {
             html.push_str(&format!("</{}>\n", list_type));
@@ -501,6 +759,35 @@ impl<'a> ConvertState<'a> {
     }
 }
 
+/// CSS color for a `w:color` value (`RRGGBB`); `None` for `auto` or junk.
+fn hex_color(val: &str) -> Option<String> {
+    (val.len() == 6 && val.chars().all(|c| c.is_ascii_hexdigit()))
+        .then(|| format!("#{}", val.to_ascii_uppercase()))
+}
+
+/// CSS color for a named `w:highlight` value; `None` for `none`.
+fn highlight_color(val: &str) -> Option<&'static str> {
+    Some(match val {
+        "black" => "#000000",
+        "blue" => "#0000FF",
+        "cyan" => "#00FFFF",
+        "green" => "#00FF00",
+        "magenta" => "#FF00FF",
+        "red" => "#FF0000",
+        "yellow" => "#FFFF00",
+        "white" => "#FFFFFF",
+        "darkBlue" => "#000080",
+        "darkCyan" => "#008080",
+        "darkGreen" => "#008000",
+        "darkMagenta" => "#800080",
+        "darkRed" => "#800000",
+        "darkYellow" => "#808000",
+        "darkGray" => "#808080",
+        "lightGray" => "#C0C0C0",
+        _ => return None,
+    })
+}
+
 fn escape_html(s: &str) -> String {
     s.replace('&', "&amp;")
         .replace('<', "&lt;")
@@ -544,7 +831,7 @@ mod tests {
 </w:body>
 </w:document>"#;
 
-        let html = convert_document(xml, &HashMap::new(), &HashMap::new(), &HashMap::new());
+        let html = convert_document(xml, &DocumentContext::default());
         assert!(html.contains("<p>Hello World</p>"));
     }
 
@@ -562,7 +849,7 @@ mod tests {
 </w:body>
 </w:document>"#;
 
-        let html = convert_document(xml, &HashMap::new(), &HashMap::new(), &HashMap::new());
+        let html = convert_document(xml, &DocumentContext::default());
         assert!(html.contains("<b><i>Bold Italic</i></b>"));
     }
 
@@ -588,7 +875,13 @@ mod tests {
             },
         );
 
-        let html = convert_document(xml, &HashMap::new(), &styles, &HashMap::new());
+        let html = convert_document(
+            xml,
+            &DocumentContext {
+                styles,
+                ..Default::default()
+            },
+        );
         assert!(html.contains("<h1>Chapter Title</h1>"));
     }
 
@@ -606,7 +899,7 @@ mod tests {
 </w:body>
 </w:document>"#;
 
-        let html = convert_document(xml, &HashMap::new(), &HashMap::new(), &HashMap::new());
+        let html = convert_document(xml, &DocumentContext::default());
         assert!(html.contains("<table>"));
         assert!(html.contains("<td>"));
         assert!(html.contains("A"));
@@ -614,6 +907,33 @@ mod tests {
         assert!(html.contains("</table>"));
     }
 
+    #[test]
+    fn test_table_column_widths() {
+        let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+<w:document xmlns:w="http://schemas.openxmlformats.org/wordprocessingml/2006/main">
+<w:body>
+  <w:tbl>
+    <w:tblPr><w:tblW w:w="0" w:type="auto"/></w:tblPr>
+    <w:tblGrid>
+      <w:gridCol w:w="6000"/>
+      <w:gridCol w:w="2000"/>
+    </w:tblGrid>
+    <w:tr>
+      <w:tc><w:p><w:r><w:t>Wide</w:t></w:r></w:p></w:tc>
+      <w:tc><w:p><w:r><w:t>Narrow</w:t></w:r></w:p></w:tc>
+    </w:tr>
+  </w:tbl>
+</w:body>
+</w:document>"#;
+
+        let html = convert_document(xml, &DocumentContext::default());
+        let colgroup =
+            r#"<colgroup><col style="width: 75.0%"/><col style="width: 25.0%"/></colgroup>"#;
+        assert!(html.contains(colgroup));
+        // The colgroup precedes the first row
+        assert!(html.find(colgroup).unwrap() < html.find("<tr>").unwrap());
+    }
+
     #[test]
     fn test_convert_image_reference() {
         let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
@@ -634,7 +954,13 @@ mod tests {
         let mut rels = HashMap::new();
         rels.insert("rId1".to_string(), "media/image1.png".to_string());
 
-        let html = convert_document(xml, &rels, &HashMap::new(), &HashMap::new());
+        let html = convert_document(
+            xml,
+            &DocumentContext {
+                rels,
+                ..Default::default()
+            },
+        );
         assert!(html.contains(r#"<img src="media/image1.png""#));
     }
 
@@ -650,8 +976,143 @@ mod tests {
 </w:body>
 </w:document>"#;
 
-        let html = convert_document(xml, &HashMap::new(), &HashMap::new(), &HashMap::new());
+        let html = convert_document(xml, &DocumentContext::default());
         assert!(html.contains("docx-center"));
         assert!(html.contains("Centered text"));
     }
+
+    #[test]
+    fn test_convert_run_colors() {
+        let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+<w:document xmlns:w="http://schemas.openxmlformats.org/wordprocessingml/2006/main">
+<w:body>
+  <w:p>
+    <w:r><w:rPr><w:b/><w:color w:val="FF0000"/></w:rPr><w:t>Red</w:t></w:r>
+    <w:r><w:rPr><w:highlight w:val="yellow"/></w:rPr><w:t>Marked</w:t></w:r>
+    <w:r><w:rPr><w:color w:val="auto"/><w:highlight w:val="none"/></w:rPr><w:t>Plain</w:t></w:r>
+  </w:p>
+</w:body>
+</w:document>"#;
+
+        let html = convert_document(xml, &DocumentContext::default());
+        assert!(html.contains(r#"<span style="color:#FF0000"><b>Red</b></span>"#));
+        assert!(html.contains(r#"<span style="background-color:#FFFF00">Marked</span>"#));
+        assert!(html.contains("Plain</p>"));
+        assert_eq!(html.matches("<span").count(), 2);
+    }
+
+    #[test]
+    fn test_convert_page_breaks() {
+        let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+<w:document xmlns:w="http://schemas.openxmlformats.org/wordprocessingml/2006/main">
+<w:body>
+  <w:p><w:r><w:lastRenderedPageBreak/><w:t>One</w:t><w:br w:type="page"/><w:lastRenderedPageBreak/><w:t>Two</w:t></w:r></w:p>
+  <w:p><w:r><w:t>Still two</w:t><w:br/><w:t>line</w:t></w:r></w:p>
+  <w:p><w:r><w:t>End of two</w:t></w:r></w:p>
+  <w:p><w:r><w:lastRenderedPageBreak/><w:t>Three</w:t></w:r></w:p>
+</w:body>
+</w:document>"#;
+
+        let html = convert_document(xml, &DocumentContext::default());
+        let marker = "<div class=\"mbp_pagebreak\"></div>\n";
+        assert_eq!(
+            html,
+            format!(
+                "<p>One</p>\n{0}<p>Two</p>\n<p>Still two<br/>line</p>\n\
+                 <p>End of two</p>\n{0}<p>Three</p>\n",
+                marker
+            )
+        );
+    }
+
+    #[test]
+    fn test_convert_symbol_run() {
+        let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+<w:document xmlns:w="http://schemas.openxmlformats.org/wordprocessingml/2006/main">
+<w:body>
+  <w:p><w:r><w:t xml:space="preserve">Angle </w:t></w:r><w:r><w:rPr><w:b/></w:rPr><w:sym w:font="Symbol" w:char="F061"/></w:r><w:r><w:sym w:font="Wingdings" w:char="F0FC"/></w:r></w:p>
+</w:body>
+</w:document>"#;
+
+        let html = convert_document(xml, &DocumentContext::default());
+        assert_eq!(html, "<p>Angle <b>\u{3b1}</b>\u{2713}</p>\n");
+    }
+
+    #[test]
+    fn test_convert_equation_text() {
+        let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+<w:document xmlns:w="http://schemas.openxmlformats.org/wordprocessingml/2006/main"
+            xmlns:m="http://schemas.openxmlformats.org/officeDocument/2006/math">
+<w:body>
+  <w:p>
+    <w:r><w:t xml:space="preserve">So </w:t></w:r>
+    <m:oMath>
+      <m:sSup><m:e><m:r><m:t>x</m:t></m:r></m:e><m:sup><m:r><m:t>2</m:t></m:r></m:sup></m:sSup>
+      <m:r><m:t>=</m:t></m:r>
+      <m:f><m:num><m:r><m:t>a</m:t></m:r></m:num><m:den><m:r><m:t>b</m:t></m:r></m:den></m:f>
+    </m:oMath>
+  </w:p>
+</w:body>
+</w:document>"#;
+
+        let html = convert_document(xml, &DocumentContext::default());
+        assert_eq!(
+            html,
+            "<p>So <span class=\"math\">x<sup>2</sup>=a/b</span></p>\n"
+        );
+    }
+
+    #[test]
+    fn test_convert_paragraph_language() {
+        let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+<w:document xmlns:w="http://schemas.openxmlformats.org/wordprocessingml/2006/main">
+<w:body>
+  <w:p>
+    <w:pPr><w:rPr><w:lang w:val="fr-FR"/></w:rPr></w:pPr>
+    <w:r><w:rPr><w:lang w:val="de-DE"/></w:rPr><w:t>Bonjour</w:t></w:r>
+  </w:p>
+  <w:p><w:r><w:t>Hello</w:t></w:r></w:p>
+</w:body>
+</w:document>"#;
+
+        let html = convert_document(xml, &DocumentContext::default());
+        assert!(html.contains(r#"<p lang="fr-FR" xml:lang="fr-FR">Bonjour</p>"#));
+        assert!(html.contains("<p>Hello</p>"));
+    }
+
+    const COMMENTED: &str = r#"<w:document xmlns:w="http://schemas.openxmlformats.org/wordprocessingml/2006/main">
+<w:body><w:p>
+  <w:commentRangeStart w:id="0"/>
+  <w:r><w:t>Published in 1851.</w:t></w:r>
+  <w:commentRangeEnd w:id="0"/>
+  <w:r><w:rPr><w:rStyle w:val="CommentReference"/></w:rPr><w:commentReference w:id="0"/></w:r>
+</w:p></w:body></w:document>"#;
+
+    #[test]
+    fn test_comment_markup_dropped() {
+        let html = convert_document(COMMENTED, &DocumentContext::default());
+        assert_eq!(html, "<p>Published in 1851.</p>\n");
+    }
+
+    #[test]
+    fn test_comments_as_notes() {
+        let ctx = DocumentContext {
+            comments: HashMap::from([(
+                "0".to_string(),
+                Comment {
+                    author: "Ann".to_string(),
+                    paragraphs: vec!["Check the date.".to_string()],
+                },
+            )]),
+            ..Default::default()
+        };
+        let html = convert_document(COMMENTED, &ctx);
+        assert!(html.starts_with(concat!(
+            "<p>Published in 1851.<sup class=\"docx-comment-ref\">",
+            "<a id=\"comment-ref-1\" href=\"#comment-1\">[1]</a></sup></p>\n"
+        )));
+        assert!(html.contains(
+            "<p id=\"comment-1\"><a href=\"#comment-ref-1\">[1]</a> <b>Ann:</b> Check the date.</p>"
+        ));
+    }
 }
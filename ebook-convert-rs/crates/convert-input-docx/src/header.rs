@@ -0,0 +1,158 @@
+//! Page headers and footers.
+//!
+//! Word keeps running heads in parts of their own (`word/header1.xml`,
+//! `word/footer1.xml`), linked from each section's `w:sectPr`:
+//!
+//! ```text
+//! <w:sectPr><w:headerReference w:type="first" r:id="rId8"/><w:titlePg/></w:sectPr>
+//! ```
+//!
+//! They repeat on every page, so they are never converted into the body.
+//! The first page's header often names the document, though, and with
+//! `docx_header_title` it stands in for a missing title.
+
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+
+/// Relationship ID of the header on the first page: the first section's
+/// `first` header when the section has a distinct title page, else its
+/// `default` header.
+pub fn first_page_header_id(doc_xml: &str) -> Option<String> {
+    let mut reader = Reader::from_str(doc_xml);
+    let mut in_sect_pr = false;
+    let mut title_page = false;
+    let (mut first, mut default) = (None, None);
+
+    loop {
+        match reader.read_event() {
+            Ok(Event::Start(ref e)) | Ok(Event::Empty(ref e)) => match e.local_name().as_ref() {
+                b"sectPr" => in_sect_pr = true,
+                b"headerReference" if in_sect_pr => {
+                    let id = attr(e, b"id");
+                    match attr(e, b"type").as_deref() {
+                        Some("first") => first = id,
+                        Some("default") | None => default = id,
+                        _ => {}
+                    }
+                }
+                b"titlePg" if in_sect_pr => {
+                    title_page = !matches!(attr(e, b"val").as_deref(), Some("0" | "false"));
+                }
+                _ => {}
+            },
+            Ok(Event::End(ref e)) if e.local_name().as_ref() == b"sectPr" => break,
+            Ok(Event::Eof) => break,
+            Err(_) => break,
+            _ => {}
+        }
+    }
+
+    if title_page {
+        first
+    } else {
+        default
+    }
+}
+
+/// Text of a header part, one line per non-empty paragraph. Field results
+/// such as page numbers are left out.
+pub fn header_lines(xml: &str) -> Vec<String> {
+    let mut reader = Reader::from_str(xml);
+    let mut lines = Vec::new();
+    let mut line = String::new();
+    let mut in_text = false;
+    // Depth of the fields being skipped
+    let mut field_depth = 0usize;
+
+    loop {
+        match reader.read_event() {
+            Ok(Event::Start(ref e)) => match e.local_name().as_ref() {
+                b"p" => line.clear(),
+                b"t" => in_text = true,
+                b"fldSimple" => field_depth += 1,
+                b"fldChar" => field_char(e, &mut field_depth),
+                _ => {}
+            },
+            Ok(Event::Empty(ref e)) => match e.local_name().as_ref() {
+                b"fldChar" => field_char(e, &mut field_depth),
+                b"tab" if field_depth == 0 => line.push(' '),
+                _ => {}
+            },
+            Ok(Event::Text(ref e)) if in_text && field_depth == 0 => {
+                if let Ok(text) = e.unescape() {
+                    line.push_str(&text);
+                }
+            }
+            Ok(Event::End(ref e)) => match e.local_name().as_ref() {
+                b"t" => in_text = false,
+                b"fldSimple" => field_depth = field_depth.saturating_sub(1),
+                b"p" => {
+                    let text = line.split_whitespace().collect::<Vec<_>>().join(" ");
+                    if !text.is_empty() {
+                        lines.push(text);
+                    }
+                }
+                _ => {}
+            },
+            Ok(Event::Eof) => break,
+            Err(_) => break,
+            _ => {}
+        }
+    }
+
+    lines
+}
+
+/// Track a complex field's `begin`/`end` markers.
+fn field_char(e: &BytesStart, depth: &mut usize) {
+    match attr(e, b"fldCharType").as_deref() {
+        Some("begin") => *depth += 1,
+        Some("end") => *depth = depth.saturating_sub(1),
+        _ => {}
+    }
+}
+
+/// Value of the attribute with local name `name`.
+fn attr(e: &BytesStart, name: &[u8]) -> Option<String> {
+    e.attributes()
+        .flatten()
+        .find(|a| a.key.local_name().as_ref() == name)
+        .map(|a| String::from_utf8_lossy(&a.value).to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_first_page_header_id() {
+        let section = |props: &str| {
+            format!(
+                r#"<w:document xmlns:w="w" xmlns:r="r"><w:body><w:p/><w:sectPr>{}</w:sectPr></w:body></w:document>"#,
+                props
+            )
+        };
+        let refs = r#"<w:headerReference w:type="default" r:id="rId1"/><w:headerReference w:type="first" r:id="rId2"/><w:footerReference w:type="default" r:id="rId3"/>"#;
+        assert_eq!(
+            first_page_header_id(&section(refs)).as_deref(),
+            Some("rId1")
+        );
+        let title_page = format!("{}<w:titlePg/>", refs);
+        assert_eq!(
+            first_page_header_id(&section(&title_page)).as_deref(),
+            Some("rId2")
+        );
+        assert_eq!(first_page_header_id(&section("")), None);
+    }
+
+    #[test]
+    fn test_header_lines_skip_fields() {
+        let xml = r#"<w:hdr xmlns:w="w">
+  <w:p><w:r><w:t>The Long</w:t></w:r><w:r><w:tab/><w:t>Road</w:t></w:r></w:p>
+  <w:p><w:r><w:t>A Memoir</w:t></w:r></w:p>
+  <w:p><w:r><w:fldChar w:fldCharType="begin"/></w:r><w:r><w:instrText>PAGE</w:instrText></w:r><w:r><w:fldChar w:fldCharType="separate"/></w:r><w:r><w:t>1</w:t></w:r><w:r><w:fldChar w:fldCharType="end"/></w:r></w:p>
+  <w:p><w:fldSimple w:instr="NUMPAGES"><w:r><w:t>9</w:t></w:r></w:fldSimple></w:p>
+</w:hdr>"#;
+        assert_eq!(header_lines(xml), vec!["The Long Road", "A Memoir"]);
+    }
+}
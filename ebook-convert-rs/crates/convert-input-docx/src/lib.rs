@@ -6,10 +6,20 @@
 //! - Extracts images from `word/media/`
 //! - Handles basic styling (bold, italic, underline, headings, lists)
 //! - Converts tables to HTML tables
-
+//! - Keeps symbol-font characters and the text of equations
+//! - Splices in `w:altChunk` HTML/RTF parts at their position
+//! - Drops review comments, or with `docx_comments` renders them as
+//!   numbered notes after the body
+//! - Leaves page headers and footers out of the body; with
+//!   `docx_header_title` the first page's header can supply the title
+
+mod alt_chunk;
+mod comments;
 mod document;
+mod header;
 mod metadata;
 mod styles;
+mod symbols;
 
 use std::io::Read;
 use std::path::Path;
@@ -30,13 +40,13 @@ impl InputPlugin for DocxInputPlugin {
         &[EbookFormat::Docx]
     }
 
-    fn convert(&self, input_path: &Path, _options: &ConversionOptions) -> Result<BookDocument> {
+    fn convert(&self, input_path: &Path, options: &ConversionOptions) -> Result<BookDocument> {
         log::info!("Reading DOCX: {}", input_path.display());
-        parse_docx(input_path)
+        parse_docx(input_path, options)
     }
 }
 
-fn parse_docx(path: &Path) -> Result<BookDocument> {
+fn parse_docx(path: &Path, options: &ConversionOptions) -> Result<BookDocument> {
     let file = std::fs::File::open(path)
         .map_err(|e| ConvertError::Docx(format!("Cannot open {}: {}", path.display(), e)))?;
 
@@ -51,18 +61,6 @@ fn parse_docx(path: &Path) -> Result<BookDocument> {
         metadata::parse_core_metadata(&meta, &mut book);
     }
 
-    // Fallback title from filename
-    if book.metadata.title().is_none() {
-        let title = path
-            .file_stem()
-            .and_then(|s| s.to_str())
-            .unwrap_or("Untitled");
-        book.metadata.set_title(title);
-    }
-    if !book.metadata.contains("language") {
-        book.metadata.set("language", "en");
-    }
-
     // -- Read relationships for image mapping --
     let rels = if let Ok(rels_xml) = read_zip_string(&mut archive, "word/_rels/document.xml.rels") {
         document::parse_relationships(&rels_xml)
@@ -70,6 +68,9 @@ fn parse_docx(path: &Path) -> Result<BookDocument> {
         std::collections::HashMap::new()
     };
 
+    // -- Convert altChunk parts (embedded HTML/RTF) referenced by relationships --
+    let alt_chunks = alt_chunk::load_alt_chunks(&mut archive, &rels);
+
     // -- Read styles for heading detection --
     let style_map = if let Ok(styles_xml) = read_zip_string(&mut archive, "word/styles.xml") {
         styles::parse_styles(&styles_xml)
@@ -84,12 +85,22 @@ fn parse_docx(path: &Path) -> Result<BookDocument> {
         std::collections::HashMap::new()
     };
 
+    // -- Read review comments when they are kept --
+    let comments = if options.docx_comments {
+        read_zip_string(&mut archive, "word/comments.xml")
+            .map(|xml| comments::parse_comments(&xml))
+            .unwrap_or_default()
+    } else {
+        std::collections::HashMap::new()
+    };
+
     // -- Extract images from word/media/ --
-    let image_names: Vec<String> = archive
+    let mut image_names: Vec<String> = archive
         .file_names()
         .filter(|n| n.starts_with("word/media/"))
         .map(|n| n.to_string())
         .collect();
+    convert_utils::sort::natural_sort(&mut image_names);
 
     for img_name in &image_names {
         if let Ok(data) = read_zip_binary(&mut archive, img_name) {
@@ -105,7 +116,47 @@ fn parse_docx(path: &Path) -> Result<BookDocument> {
     let doc_xml = read_zip_string(&mut archive, "word/document.xml")
         .map_err(|e| ConvertError::Docx(format!("Missing word/document.xml: {}", e)))?;
 
-    let body_html = document::convert_document(&doc_xml, &rels, &style_map, &numbering_map);
+    // Title from the first page's header, when asked for
+    if book.metadata.title().is_none() && options.docx_header_title {
+        let lines = header::first_page_header_id(&doc_xml)
+            .and_then(|rid| rels.get(&rid))
+            .and_then(|target| read_zip_string(&mut archive, &alt_chunk::part_name(target)).ok())
+            .map(|xml| header::header_lines(&xml))
+            .unwrap_or_default();
+        let mut lines = lines.into_iter();
+        if let Some(title) = lines.next() {
+            log::info!("Title from first page header: {}", title);
+            book.metadata.set_title(&title);
+            if let Some(subtitle) = lines.next() {
+                let attrs = std::collections::HashMap::from([(
+                    "title-type".to_string(),
+                    "subtitle".to_string(),
+                )]);
+                book.metadata.add_with_attrs("title", subtitle, attrs);
+            }
+        }
+    }
+
+    // Fallback title from filename
+    if book.metadata.title().is_none() {
+        let title = path
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .unwrap_or("Untitled");
+        book.metadata.set_title(title);
+    }
+    if !book.metadata.contains("language") {
+        book.metadata.set("language", "en");
+    }
+
+    let ctx = document::DocumentContext {
+        rels,
+        styles: style_map,
+        numbering: numbering_map,
+        alt_chunks,
+        comments,
+    };
+    let body_html = document::convert_document(&doc_xml, &ctx);
 
     let title = book.metadata.title().unwrap_or("Untitled").to_string();
     let xhtml = convert_utils::xml::xhtml11_document(&title, "en", Some("style.css"), &body_html);
@@ -134,7 +185,8 @@ ul, ol { margin: 0.5em 0; padding-left: 2em; }
 blockquote { margin: 0.5em 1em; padding-left: 1em; border-left: 3px solid #ccc; }
 .docx-center { text-align: center; }
 .docx-right { text-align: right; }
-.docx-justify { text-align: justify; }"#;
+.docx-justify { text-align: justify; }
+.docx-comments { font-size: 0.9em; }"#;
     let css_item = ManifestItem::new(
         "style",
         "style.css",
@@ -153,8 +205,6 @@ blockquote { margin: 0.5em 1em; padding-left: 1em; border-left: 3px solid #ccc;
 }
 
 fn build_toc(html: &str, title: &str, book: &mut BookDocument) {
-    let tag_re = regex::Regex::new(r"<[^>]+>").unwrap();
-
     // Rust regex doesn't support backreferences, so use separate patterns per level
     let patterns = [
         regex::Regex::new(r"(?i)<h1[^>]*>(.*?)</h1>").unwrap(),
@@ -167,8 +217,7 @@ fn build_toc(html: &str, title: &str, book: &mut BookDocument) {
     for pattern in &patterns {
         for cap in pattern.captures_iter(html) {
             let pos = cap.get(0).unwrap().start();
-            let text = tag_re.replace_all(&cap[1], "").trim().to_string();
-            if !text.is_empty() {
+            if let Some(text) = convert_utils::xml::heading_text(&cap[1]) {
                 headings.push((pos, text));
             }
         }
@@ -191,10 +240,10 @@ fn read_zip_string(
     let mut file = archive
         .by_name(name)
         .map_err(|e| format!("{}: {}", name, e))?;
-    let mut s = String::new();
-    file.read_to_string(&mut s)
+    let mut bytes = Vec::new();
+    file.read_to_end(&mut bytes)
         .map_err(|e| format!("{}: {}", name, e))?;
-    Ok(s)
+    convert_utils::encoding::decode_text(&bytes).map_err(|e| format!("{}: {}", name, e))
 }
 
 fn read_zip_binary(
@@ -231,4 +280,198 @@ mod tests {
         assert_eq!(book.toc.entries.len(), 1);
         assert_eq!(book.toc.entries[0].title, "My Document");
     }
+
+    #[test]
+    fn test_html_alt_chunk_spliced() {
+        let path = std::env::temp_dir().join("test_docx_altchunk.docx");
+        {
+            let mut zip = convert_utils::archive::ZipBuilder::new(&path).unwrap();
+            zip.add_file(
+                "word/document.xml",
+                br#"<?xml version="1.0" encoding="UTF-8"?>
+<w:document xmlns:w="http://schemas.openxmlformats.org/wordprocessingml/2006/main"
+    xmlns:r="http://schemas.openxmlformats.org/officeDocument/2006/relationships">
+<w:body>
+  <w:p><w:r><w:t>Before</w:t></w:r></w:p>
+  <w:altChunk r:id="rIdChunk"/>
+  <w:p><w:r><w:t>After</w:t></w:r></w:p>
+</w:body>
+</w:document>"#,
+            )
+            .unwrap();
+            zip.add_file(
+                "word/_rels/document.xml.rels",
+                br#"<?xml version="1.0" encoding="UTF-8"?>
+<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships">
+  <Relationship Id="rIdChunk" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/aFChunk" Target="afchunk.htm"/>
+</Relationships>"#,
+            )
+            .unwrap();
+            zip.add_file(
+                "word/afchunk.htm",
+                b"<html><body><p>Imported <b>HTML</b> chunk<br>line two</p></body></html>",
+            )
+            .unwrap();
+            zip.finish().unwrap();
+        }
+
+        let book = parse_docx(&path, &ConversionOptions::default()).unwrap();
+        let _ = std::fs::remove_file(&path);
+
+        let xhtml = book
+            .manifest
+            .by_id("content")
+            .unwrap()
+            .data
+            .as_xhtml()
+            .unwrap();
+        let chunk = "<p>Imported <b>HTML</b> chunk<br/>line two</p>";
+        let before = xhtml.find("<p>Before</p>").unwrap();
+        let spliced = xhtml.find(chunk).unwrap();
+        let after = xhtml.find("<p>After</p>").unwrap();
+        assert!(before < spliced && spliced < after);
+    }
+
+    #[test]
+    fn test_comments_dropped_unless_kept() {
+        let path = std::env::temp_dir().join("test_docx_comments.docx");
+        {
+            let mut zip = convert_utils::archive::ZipBuilder::new(&path).unwrap();
+            zip.add_file(
+                "word/document.xml",
+                br#"<?xml version="1.0" encoding="UTF-8"?>
+<w:document xmlns:w="http://schemas.openxmlformats.org/wordprocessingml/2006/main">
+<w:body>
+  <w:p><w:commentRangeStart w:id="1"/><w:r><w:t>Call me Ishmael.</w:t></w:r><w:commentRangeEnd w:id="1"/><w:r><w:commentReference w:id="1"/></w:r></w:p>
+</w:body>
+</w:document>"#,
+            )
+            .unwrap();
+            zip.add_file(
+                "word/comments.xml",
+                br#"<?xml version="1.0" encoding="UTF-8"?>
+<w:comments xmlns:w="http://schemas.openxmlformats.org/wordprocessingml/2006/main">
+  <w:comment w:id="1" w:author="Editor"><w:p><w:r><w:t>Strong opening.</w:t></w:r></w:p></w:comment>
+</w:comments>"#,
+            )
+            .unwrap();
+            zip.finish().unwrap();
+        }
+
+        let content = |options: &ConversionOptions| {
+            let book = parse_docx(&path, options).unwrap();
+            let item = book.manifest.by_id("content").unwrap();
+            item.data.as_xhtml().unwrap().to_string()
+        };
+        let dropped = content(&ConversionOptions::default());
+        let kept = content(&ConversionOptions {
+            docx_comments: true,
+            ..Default::default()
+        });
+        let _ = std::fs::remove_file(&path);
+
+        assert!(dropped.contains("<p>Call me Ishmael.</p>"), "{}", dropped);
+        assert!(!dropped.contains("docx-comment"), "{}", dropped);
+        assert!(!dropped.contains("Strong opening."));
+        assert!(
+            kept.contains(r##"href="#comment-1">[1]</a></sup></p>"##),
+            "{}",
+            kept
+        );
+        assert!(
+            kept.contains("<b>Editor:</b> Strong opening.</p>"),
+            "{}",
+            kept
+        );
+    }
+
+    #[test]
+    fn test_header_and_footer_kept_out_of_body() {
+        let path = std::env::temp_dir().join("test_docx_header.docx");
+        {
+            let mut zip = convert_utils::archive::ZipBuilder::new(&path).unwrap();
+            zip.add_file(
+                "word/document.xml",
+                br#"<?xml version="1.0" encoding="UTF-8"?>
+<w:document xmlns:w="http://schemas.openxmlformats.org/wordprocessingml/2006/main"
+    xmlns:r="http://schemas.openxmlformats.org/officeDocument/2006/relationships">
+<w:body>
+  <w:p><w:r><w:t>It was a bright cold day.</w:t></w:r></w:p>
+  <w:sectPr>
+    <w:headerReference w:type="default" r:id="rIdHeader"/>
+    <w:footerReference w:type="default" r:id="rIdFooter"/>
+  </w:sectPr>
+</w:body>
+</w:document>"#,
+            )
+            .unwrap();
+            zip.add_file(
+                "word/_rels/document.xml.rels",
+                br#"<?xml version="1.0" encoding="UTF-8"?>
+<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships">
+  <Relationship Id="rIdHeader" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/header" Target="header1.xml"/>
+  <Relationship Id="rIdFooter" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/footer" Target="footer1.xml"/>
+</Relationships>"#,
+            )
+            .unwrap();
+            zip.add_file(
+                "word/header1.xml",
+                br#"<?xml version="1.0" encoding="UTF-8"?>
+<w:hdr xmlns:w="http://schemas.openxmlformats.org/wordprocessingml/2006/main">
+  <w:p><w:r><w:t>Running Head</w:t></w:r></w:p>
+  <w:p><w:r><w:t>A Novel</w:t></w:r></w:p>
+</w:hdr>"#,
+            )
+            .unwrap();
+            zip.add_file(
+                "word/footer1.xml",
+                br#"<?xml version="1.0" encoding="UTF-8"?>
+<w:ftr xmlns:w="http://schemas.openxmlformats.org/wordprocessingml/2006/main">
+  <w:p><w:r><w:t>Confidential draft</w:t></w:r></w:p>
+</w:ftr>"#,
+            )
+            .unwrap();
+            zip.finish().unwrap();
+        }
+
+        let book = parse_docx(&path, &ConversionOptions::default()).unwrap();
+        let titled = parse_docx(
+            &path,
+            &ConversionOptions {
+                docx_header_title: true,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        let _ = std::fs::remove_file(&path);
+
+        let xhtml = book
+            .manifest
+            .by_id("content")
+            .unwrap()
+            .data
+            .as_xhtml()
+            .unwrap();
+        assert!(
+            xhtml.contains("<p>It was a bright cold day.</p>"),
+            "{}",
+            xhtml
+        );
+        assert!(!xhtml.contains("Running Head"), "{}", xhtml);
+        assert!(!xhtml.contains("Confidential draft"), "{}", xhtml);
+        assert_eq!(book.metadata.title(), Some("test_docx_header"));
+
+        assert_eq!(titled.metadata.title(), Some("Running Head"));
+        let titles = titled.metadata.get("title").unwrap();
+        assert_eq!(titles[1].value, "A Novel");
+        assert_eq!(titles[1].attributes["title-type"], "subtitle");
+        let xhtml = titled
+            .manifest
+            .by_id("content")
+            .unwrap()
+            .data
+            .as_xhtml()
+            .unwrap();
+        assert!(!xhtml.contains("A Novel"), "{}", xhtml);
+    }
 }
@@ -23,38 +23,34 @@ pub fn parse_core_metadata(xml: &str, book: &mut BookDocument) {
                 current_tag = local;
                 in_element = true;
             }
-            Ok(Event::Text(ref e)) => {
-                if in_element {
-                    if let Ok(text) = e.unescape() {
-                        let text = text.trim().to_string();
-                        if text.is_empty() {
-                            continue;
+            Ok(Event::Text(ref e)) if in_element => {
+                if let Ok(text) = e.unescape() {
+                    let text = text.trim().to_string();
+                    if text.is_empty() {
+                        continue;
+                    }
+                    match current_tag.as_str() {
+                        "title" => book.metadata.set_title(&text),
+                        "creator" => book.metadata.add("creator", &text),
+                        "description" => book.metadata.set("description", &text),
+                        "subject" => book.metadata.add("subject", &text),
+                        "language" => book.metadata.set("language", &text),
+                        "created" | "modified" if !book.metadata.contains("date") => {
+                            book.metadata.set("date", &text);
                         }
-                        match current_tag.as_str() {
-                            "title" => book.metadata.set_title(&text),
-                            "creator" => book.metadata.add("creator", &text),
-                            "description" => book.metadata.set("description", &text),
-                            "subject" => book.metadata.add("subject", &text),
-                            "language" => book.metadata.set("language", &text),
-                            "created" | "modified" => {
-                                if !book.metadata.contains("date") {
-                                    book.metadata.set("date", &text);
-                                }
-                            }
-                            "keywords" => {
-                                // Keywords may be comma-separated
-                                for kw in text.split(',') {
-                                    let kw = kw.trim();
-                                    if !kw.is_empty() {
-                                        book.metadata.add("subject", kw);
-                                    }
+                        "keywords" => {
+                            // Keywords may be comma-separated
+                            for kw in text.split(',') {
+                                let kw = kw.trim();
+                                if !kw.is_empty() {
+                                    book.metadata.add("subject", kw);
                                 }
                             }
-                            "lastModifiedBy" => {
-                                book.metadata.add("contributor", &text);
-                            }
-                            _ => {}
                         }
+                        "lastModifiedBy" => {
+                            book.metadata.add("contributor", &text);
+                        }
+                        _ => {}
                     }
                 }
             }
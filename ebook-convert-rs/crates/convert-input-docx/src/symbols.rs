@@ -0,0 +1,202 @@
+//! Map `w:sym` symbol characters to Unicode.
+//!
+//! Word inserts characters from symbol fonts as `<w:sym w:font="Symbol"
+//! w:char="F061"/>`. The character code is the font's own byte, usually
+//! offset into the private use area at U+F000. Symbol and Wingdings glyphs
+//! are translated to their Unicode equivalents; codes from other fonts are
+//! taken as Unicode already.
+
+/// The Unicode character for `code` (hex, as in `w:char`) in `font`.
+pub fn symbol_char(font: &str, code: &str) -> Option<char> {
+    let code = u32::from_str_radix(code.trim(), 16).ok()?;
+    let byte = match code {
+        0xF020..=0xF0FF => code - 0xF000,
+        _ => code,
+    };
+    let mapped = match font.trim().to_ascii_lowercase().as_str() {
+        "symbol" => u8::try_from(byte).ok().and_then(symbol_font),
+        "wingdings" => u8::try_from(byte).ok().and_then(wingdings_font),
+        _ => None,
+    };
+    mapped.or_else(|| char::from_u32(code))
+}
+
+/// Adobe Symbol encoding. Bracket and brace pieces are left out.
+fn symbol_font(byte: u8) -> Option<char> {
+    let c = match byte {
+        0x22 => '\u{2200}',
+        0x24 => '\u{2203}',
+        0x27 => '\u{220B}',
+        0x2A => '\u{2217}',
+        0x2D => '\u{2212}',
+        0x40 => '\u{2245}',
+        0x41 => 'Α',
+        0x42 => 'Β',
+        0x43 => 'Χ',
+        0x44 => 'Δ',
+        0x45 => 'Ε',
+        0x46 => 'Φ',
+        0x47 => 'Γ',
+        0x48 => 'Η',
+        0x49 => 'Ι',
+        0x4A => 'ϑ',
+        0x4B => 'Κ',
+        0x4C => 'Λ',
+        0x4D => 'Μ',
+        0x4E => 'Ν',
+        0x4F => 'Ο',
+        0x50 => 'Π',
+        0x51 => 'Θ',
+        0x52 => 'Ρ',
+        0x53 => 'Σ',
+        0x54 => 'Τ',
+        0x55 => 'Υ',
+        0x56 => 'ς',
+        0x57 => 'Ω',
+        0x58 => 'Ξ',
+        0x59 => 'Ψ',
+        0x5A => 'Ζ',
+        0x5C => '\u{2234}',
+        0x5E => '\u{22A5}',
+        0x60 => '\u{203E}',
+        0x61 => 'α',
+        0x62 => 'β',
+        0x63 => 'χ',
+        0x64 => 'δ',
+        0x65 => 'ε',
+        0x66 => 'φ',
+        0x67 => 'γ',
+        0x68 => 'η',
+        0x69 => 'ι',
+        0x6A => 'ϕ',
+        0x6B => 'κ',
+        0x6C => 'λ',
+        0x6D => 'μ',
+        0x6E => 'ν',
+        0x6F => 'ο',
+        0x70 => 'π',
+        0x71 => 'θ',
+        0x72 => 'ρ',
+        0x73 => 'σ',
+        0x74 => 'τ',
+        0x75 => 'υ',
+        0x76 => 'ϖ',
+        0x77 => 'ω',
+        0x78 => 'ξ',
+        0x79 => 'ψ',
+        0x7A => 'ζ',
+        0x7E => '\u{223C}',
+        0xA0 => '€',
+        0xA1 => 'ϒ',
+        0xA2 => '\u{2032}',
+        0xA3 => '\u{2264}',
+        0xA4 => '\u{2044}',
+        0xA5 => '\u{221E}',
+        0xA6 => 'ƒ',
+        0xA7 => '\u{2663}',
+        0xA8 => '\u{2666}',
+        0xA9 => '\u{2665}',
+        0xAA => '\u{2660}',
+        0xAB => '\u{2194}',
+        0xAC => '\u{2190}',
+        0xAD => '\u{2191}',
+        0xAE => '\u{2192}',
+        0xAF => '\u{2193}',
+        0xB0 => '°',
+        0xB1 => '±',
+        0xB2 => '\u{2033}',
+        0xB3 => '\u{2265}',
+        0xB4 => '×',
+        0xB5 => '\u{221D}',
+        0xB6 => '\u{2202}',
+        0xB7 => '\u{2022}',
+        0xB8 => '÷',
+        0xB9 => '\u{2260}',
+        0xBA => '\u{2261}',
+        0xBB => '\u{2248}',
+        0xBC => '\u{2026}',
+        0xBF => '\u{21B5}',
+        0xC0 => '\u{2135}',
+        0xC1 => '\u{2111}',
+        0xC2 => '\u{211C}',
+        0xC3 => '\u{2118}',
+        0xC4 => '\u{2297}',
+        0xC5 => '\u{2295}',
+        0xC6 => '\u{2205}',
+        0xC7 => '\u{2229}',
+        0xC8 => '\u{222A}',
+        0xC9 => '\u{2283}',
+        0xCA => '\u{2287}',
+        0xCB => '\u{2284}',
+        0xCC => '\u{2282}',
+        0xCD => '\u{2286}',
+        0xCE => '\u{2208}',
+        0xCF => '\u{2209}',
+        0xD0 => '\u{2220}',
+        0xD1 => '\u{2207}',
+        0xD2 | 0xE2 => '®',
+        0xD3 | 0xE3 => '©',
+        0xD4 | 0xE4 => '\u{2122}',
+        0xD5 => '\u{220F}',
+        0xD6 => '\u{221A}',
+        0xD7 => '\u{22C5}',
+        0xD8 => '¬',
+        0xD9 => '\u{2227}',
+        0xDA => '\u{2228}',
+        0xDB => '\u{21D4}',
+        0xDC => '\u{21D0}',
+        0xDD => '\u{21D1}',
+        0xDE => '\u{21D2}',
+        0xDF => '\u{21D3}',
+        0xE0 => '\u{25CA}',
+        0xE1 => '\u{2329}',
+        0xE5 => '\u{2211}',
+        0xF1 => '\u{232A}',
+        0xF2 => '\u{222B}',
+        // The rest of the printable ASCII range is ASCII
+        0x20..=0x7E => byte as char,
+        _ => return None,
+    };
+    Some(c)
+}
+
+/// The Wingdings glyphs Word commonly uses for bullets and check boxes.
+fn wingdings_font(byte: u8) -> Option<char> {
+    let c = match byte {
+        0x4A => '\u{263A}',
+        0x4C => '\u{2639}',
+        0x6C => '\u{25CF}',
+        0x6E => '\u{25A0}',
+        0x71 => '\u{2751}',
+        0x76 => '\u{2756}',
+        0x9F => '\u{2022}',
+        0xA7 => '\u{25AA}',
+        0xD8 => '\u{27A2}',
+        0xE8 => '\u{2794}',
+        0xFB => '\u{2717}',
+        0xFC => '\u{2713}',
+        0xFD => '\u{2612}',
+        0xFE => '\u{2611}',
+        _ => return None,
+    };
+    Some(c)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_symbol_font_mapping() {
+        assert_eq!(symbol_char("Symbol", "F061"), Some('α'));
+        assert_eq!(symbol_char("Symbol", "F0B7"), Some('\u{2022}'));
+        assert_eq!(symbol_char("Symbol", "F028"), Some('('));
+        assert_eq!(symbol_char("Wingdings", "F0FC"), Some('\u{2713}'));
+    }
+
+    #[test]
+    fn test_other_fonts_use_the_code_as_unicode() {
+        assert_eq!(symbol_char("Cambria Math", "2211"), Some('\u{2211}'));
+        assert_eq!(symbol_char("Symbol", "zz"), None);
+    }
+}
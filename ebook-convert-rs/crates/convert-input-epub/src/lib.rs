@@ -1,5 +1,6 @@
 //! EPUB input plugin — reads EPUB 2/3 files into BookDocument.
 
+mod missing;
 mod parser;
 
 use std::path::Path;
@@ -9,6 +10,8 @@ use convert_core::error::Result;
 use convert_core::options::ConversionOptions;
 use convert_core::plugin::InputPlugin;
 
+pub use missing::{find_missing, MissingReason, MissingResource};
+
 pub struct EpubInputPlugin;
 
 impl InputPlugin for EpubInputPlugin {
@@ -20,8 +23,26 @@ impl InputPlugin for EpubInputPlugin {
         &[EbookFormat::Epub]
     }
 
-    fn convert(&self, input_path: &Path, _options: &ConversionOptions) -> Result<BookDocument> {
+    fn convert(&self, input_path: &Path, options: &ConversionOptions) -> Result<BookDocument> {
         log::info!("Reading EPUB: {}", input_path.display());
-        parser::parse_epub(input_path)
+        let mut book = parser::parse_epub(input_path)?;
+
+        let missing = find_missing(&book);
+        for resource in &missing {
+            log::warn!(
+                "Missing EPUB resource: href={} id={} reason={}",
+                resource.href,
+                resource.id.as_deref().unwrap_or("-"),
+                resource.reason
+            );
+        }
+        if let (Some(mode), false) = (options.missing_resources, missing.is_empty()) {
+            let repaired = missing::repair_references(&mut book, mode);
+            log::info!(
+                "Repaired missing resource references in {} documents",
+                repaired
+            );
+        }
+        Ok(book)
     }
 }
@@ -0,0 +1,261 @@
+//! Missing resources — files the book refers to but cannot supply.
+//!
+//! The parser stores manifest items it cannot read as `ManifestData::Empty`,
+//! and documents may point at files the OPF never lists or at remote URLs.
+//! Left alone, the output keeps `<img>` and `<link>` tags whose targets it
+//! does not contain. Every missing resource is logged; with
+//! `missing_resources` set, the broken tags are also replaced or removed and
+//! the empty items dropped from the manifest and spine.
+
+use std::collections::HashSet;
+use std::fmt;
+
+use regex::{Captures, Regex};
+
+use convert_core::book::{BookDocument, ManifestData};
+use convert_core::options::MissingResources;
+use convert_utils::href;
+
+use crate::parser::percent_decode;
+
+/// A resource the book refers to but cannot supply.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MissingResource {
+    /// Manifest href, the resolved reference for files outside the
+    /// manifest, or the URL of a remote resource.
+    pub href: String,
+    /// Manifest id, when the OPF lists the resource.
+    pub id: Option<String>,
+    pub reason: MissingReason,
+}
+
+/// Why a resource is unavailable.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MissingReason {
+    /// Listed in the OPF but absent from the archive, unreadable or empty.
+    Unreadable,
+    /// Referenced by a document but not listed in the OPF.
+    NotInManifest,
+    /// An `http(s)` reference, which is not fetched.
+    Remote,
+}
+
+impl fmt::Display for MissingReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            MissingReason::Unreadable => "unreadable",
+            MissingReason::NotInManifest => "not-in-manifest",
+            MissingReason::Remote => "remote",
+        })
+    }
+}
+
+fn img_re() -> Regex {
+    Regex::new(r#"(?is)<img\b[^>]*?\bsrc\s*=\s*["']([^"']+)["'][^>]*>(?:\s*</img\s*>)?"#).unwrap()
+}
+
+fn link_re() -> Regex {
+    Regex::new(r#"(?is)<link\b[^>]*?\bhref\s*=\s*["']([^"']+)["'][^>]*>(?:\s*</link\s*>)?"#)
+        .unwrap()
+}
+
+/// Missing resources of `book`, in manifest order followed by broken
+/// `<img>` and `<link>` references in document order. Each href is listed
+/// once.
+pub fn find_missing(book: &BookDocument) -> Vec<MissingResource> {
+    let mut missing: Vec<MissingResource> = book
+        .manifest
+        .iter()
+        .filter(|item| matches!(item.data, ManifestData::Empty))
+        .map(|item| MissingResource {
+            href: item.href.clone(),
+            id: Some(item.id.clone()),
+            reason: MissingReason::Unreadable,
+        })
+        .collect();
+    let mut seen: HashSet<String> = missing.iter().map(|m| m.href.clone()).collect();
+
+    let (img_re, link_re) = (img_re(), link_re());
+    for item in book.manifest.iter() {
+        let Some(xhtml) = item.data.as_xhtml() else {
+            continue;
+        };
+        for cap in img_re
+            .captures_iter(xhtml)
+            .chain(link_re.captures_iter(xhtml))
+        {
+            if let Some((href, reason)) = broken_reference(book, &item.href, &cap[1]) {
+                if seen.insert(href.clone()) {
+                    missing.push(MissingResource {
+                        href,
+                        id: None,
+                        reason,
+                    });
+                }
+            }
+        }
+    }
+    missing
+}
+
+/// Replace or remove `<img>` and `<link>` tags pointing at missing
+/// resources, then drop the empty items so the OPF lists only files the
+/// output contains. Returns the number of documents changed.
+pub(crate) fn repair_references(book: &mut BookDocument, mode: MissingResources) -> usize {
+    let (img_re, link_re) = (img_re(), link_re());
+    let alt_re = Regex::new(r#"(?is)\balt\s*=\s*["']([^"']*)["']"#).unwrap();
+
+    let mut repaired: Vec<(String, String)> = Vec::new();
+    for item in book.manifest.iter() {
+        let Some(xhtml) = item.data.as_xhtml() else {
+            continue;
+        };
+        let broken = |src: &str| broken_reference(book, &item.href, src).is_some();
+        let content = img_re.replace_all(xhtml, |cap: &Captures| {
+            if !broken(&cap[1]) {
+                return cap[0].to_string();
+            }
+            let alt = alt_re
+                .captures(&cap[0])
+                .map(|alt| alt[1].trim().to_string())
+                .filter(|alt| !alt.is_empty());
+            match (mode, alt) {
+                (MissingResources::Placeholder, Some(alt)) => {
+                    format!("<span class=\"missing-image\">{}</span>", alt)
+                }
+                _ => String::new(),
+            }
+        });
+        let content = link_re.replace_all(&content, |cap: &Captures| {
+            if broken(&cap[1]) {
+                String::new()
+            } else {
+                cap[0].to_string()
+            }
+        });
+        if content != xhtml {
+            repaired.push((item.id.clone(), content.into_owned()));
+        }
+    }
+
+    let count = repaired.len();
+    for (id, xhtml) in repaired {
+        if let Some(item) = book.manifest.by_id_mut(&id) {
+            item.data = ManifestData::Xhtml(xhtml);
+        }
+    }
+
+    let empty: Vec<String> = book
+        .manifest
+        .iter()
+        .filter(|item| matches!(item.data, ManifestData::Empty))
+        .map(|item| item.id.clone())
+        .collect();
+    for id in &empty {
+        book.manifest.remove_by_id(id);
+        book.spine.remove(id);
+    }
+    count
+}
+
+/// The href `src` stands for in the document at `base` and why it is
+/// missing, or `None` when it resolves to a manifest item with content.
+fn broken_reference(book: &BookDocument, base: &str, src: &str) -> Option<(String, MissingReason)> {
+    let src = src.trim();
+    if src.starts_with("data:") {
+        return None;
+    }
+    if src.starts_with("http://") || src.starts_with("https://") || src.starts_with("//") {
+        return Some((src.to_string(), MissingReason::Remote));
+    }
+    let target = href::resolve(base, &percent_decode(src));
+    match book.manifest.by_href(&target) {
+        None => Some((target, MissingReason::NotInManifest)),
+        Some(item) if matches!(item.data, ManifestData::Empty) => {
+            Some((target, MissingReason::Unreadable))
+        }
+        Some(_) => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use convert_core::book::ManifestItem;
+
+    fn book_with_gaps() -> BookDocument {
+        let mut book = BookDocument::new();
+        book.manifest.add(ManifestItem::new(
+            "map",
+            "images/map.png",
+            "image/png",
+            ManifestData::Empty,
+        ));
+        book.manifest.add(ManifestItem::new(
+            "photo",
+            "images/photo.png",
+            "image/png",
+            ManifestData::Binary(vec![1, 2, 3]),
+        ));
+        book.manifest.add(ManifestItem::new(
+            "ch1",
+            "text/ch1.xhtml",
+            "application/xhtml+xml",
+            ManifestData::Xhtml(
+                concat!(
+                    r#"<html><head><link rel="stylesheet" href="../css/gone.css"/></head><body>"#,
+                    r#"<p><img src="../images/map.png" alt="A map"/></p>"#,
+                    r#"<p><img src="https://example.com/ad.png"/></p>"#,
+                    r#"<p><img src="../images/photo.png" alt="Photo"/></p></body></html>"#,
+                )
+                .to_string(),
+            ),
+        ));
+        book.spine.push("ch1", true);
+        book
+    }
+
+    #[test]
+    fn test_find_missing() {
+        let missing = find_missing(&book_with_gaps());
+        let found: Vec<(&str, Option<&str>, MissingReason)> = missing
+            .iter()
+            .map(|m| (m.href.as_str(), m.id.as_deref(), m.reason))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                ("images/map.png", Some("map"), MissingReason::Unreadable),
+                ("https://example.com/ad.png", None, MissingReason::Remote),
+                ("css/gone.css", None, MissingReason::NotInManifest),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_repair_with_placeholder() {
+        let mut book = book_with_gaps();
+        assert_eq!(
+            repair_references(&mut book, MissingResources::Placeholder),
+            1
+        );
+
+        let xhtml = book.manifest.by_id("ch1").unwrap().data.as_xhtml().unwrap();
+        assert!(xhtml.contains(r#"<p><span class="missing-image">A map</span></p>"#));
+        // No alt text to stand in for the remote image
+        assert!(xhtml.contains("<p></p>"));
+        assert!(!xhtml.contains("gone.css"));
+        assert!(xhtml.contains(r#"<img src="../images/photo.png" alt="Photo"/>"#));
+        assert!(book.manifest.by_id("map").is_none());
+        assert!(find_missing(&book).is_empty());
+    }
+
+    #[test]
+    fn test_repair_with_strip() {
+        let mut book = book_with_gaps();
+        repair_references(&mut book, MissingResources::Strip);
+        let xhtml = book.manifest.by_id("ch1").unwrap().data.as_xhtml().unwrap();
+        assert!(!xhtml.contains("A map"));
+        assert_eq!(xhtml.matches("<img").count(), 1);
+    }
+}
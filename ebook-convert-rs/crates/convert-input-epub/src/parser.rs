@@ -10,7 +10,9 @@ use quick_xml::Reader;
 use rayon::prelude::*;
 use zip::read::ZipArchive;
 
-use convert_core::book::{BookDocument, GuideRef, ManifestData, ManifestItem, TocEntry};
+use convert_core::book::{
+    BookDocument, GuideRef, ManifestData, ManifestItem, PageDirection, TocEntry,
+};
 use convert_core::error::{ConvertError, Result};
 use convert_utils::mime;
 
@@ -30,7 +32,7 @@ pub fn parse_epub(path: &Path) -> Result<BookDocument> {
 
     // 2. Parse the OPF file
     let opf_content = read_archive_entry(&mut archive, &opf_path)?;
-    let opf_str = String::from_utf8_lossy(&opf_content).to_string();
+    let opf_str = convert_utils::encoding::decode_text_lossy(&opf_content);
 
     let mut book = BookDocument::new();
 
@@ -40,7 +42,7 @@ pub fn parse_epub(path: &Path) -> Result<BookDocument> {
     // Parse manifest
     let manifest_map = parse_opf_manifest(&opf_str, opf_dir);
 
-    // Parse spine
+    // Parse spine (idref, linear)
     let spine_idrefs = parse_opf_spine(&opf_str);
 
     // Parse guide
@@ -59,7 +61,7 @@ pub fn parse_epub(path: &Path) -> Result<BookDocument> {
             match read_archive_entry(&mut archive, &full_path) {
                 Ok(bytes) => (id.clone(), href.clone(), media_type.clone(), bytes),
                 Err(e) => {
-                    log::warn!("Failed to read {}: {}", full_path, e);
+                    log::debug!("Failed to read {}: {}", full_path, e);
                     (id.clone(), href.clone(), media_type.clone(), Vec::new())
                 }
             }
@@ -73,7 +75,7 @@ pub fn parse_epub(path: &Path) -> Result<BookDocument> {
             let data = if bytes.is_empty() {
                 ManifestData::Empty
             } else if mime::is_text_mime(&media_type) {
-                let text = String::from_utf8_lossy(&bytes).to_string();
+                let text = convert_utils::encoding::decode_text_lossy(&bytes);
                 if media_type == "application/xhtml+xml"
                     || media_type == "text/html"
                     || media_type.contains("xml")
@@ -92,16 +94,24 @@ pub fn parse_epub(path: &Path) -> Result<BookDocument> {
         })
         .collect();
 
-    for item in manifest_items {
+    let media_overlays = parse_opf_media_overlays(&opf_str);
+    for mut item in manifest_items {
+        item.media_overlay = media_overlays.get(&item.id).cloned();
         book.manifest.add(item);
     }
 
     // 4. Build spine from idrefs
-    for idref in &spine_idrefs {
+    for (idref, linear) in &spine_idrefs {
         if manifest_map.contains_key(idref) {
-            book.spine.push(idref, true);
+            book.spine.push(idref, *linear);
         }
     }
+    book.spine.page_progression_direction =
+        match spine_attribute(&opf_str, "page-progression-direction").as_deref() {
+            Some("rtl") => Some(PageDirection::Rtl),
+            Some("ltr") => Some(PageDirection::Ltr),
+            _ => None,
+        };
 
     // 5. Try to parse NCX for TOC
     if let Some(ncx_id) = find_ncx_id(&opf_str) {
@@ -112,7 +122,7 @@ pub fn parse_epub(path: &Path) -> Result<BookDocument> {
                 format!("{}{}", opf_dir, ncx_href)
             };
             if let Ok(ncx_data) = read_archive_entry(&mut archive, &ncx_path) {
-                let ncx_str = String::from_utf8_lossy(&ncx_data).to_string();
+                let ncx_str = convert_utils::encoding::decode_text_lossy(&ncx_data);
                 parse_ncx(&ncx_str, &mut book);
             }
         }
@@ -133,7 +143,7 @@ pub fn parse_epub(path: &Path) -> Result<BookDocument> {
 /// Read META-INF/container.xml and return the OPF file path.
 fn read_container_xml(archive: &mut ZipArchive<File>) -> Result<String> {
     let data = read_archive_entry(archive, "META-INF/container.xml")?;
-    let xml = String::from_utf8_lossy(&data);
+    let xml = convert_utils::encoding::decode_text_lossy(&data);
 
     let mut reader = Reader::from_str(&xml);
     loop {
@@ -166,12 +176,30 @@ fn read_container_xml(archive: &mut ZipArchive<File>) -> Result<String> {
     ))
 }
 
+/// Refinements kept on titles and creators, as EPUB 2 `opf:` attributes or
+/// EPUB 3 `<meta refines>` properties.
+const REFINEMENTS: &[&str] = &["role", "file-as", "title-type"];
+
+/// A title or creator awaiting its `<meta refines>` refinements.
+struct Refinable {
+    term: String,
+    id: Option<String>,
+    value: String,
+    attrs: HashMap<String, String>,
+}
+
 /// Parse OPF metadata section.
+///
+/// Titles and creators keep their role, file-as and title-type refinements
+/// as item attributes; the main title comes first.
 fn parse_opf_metadata(opf: &str, book: &mut BookDocument) {
     let mut reader = Reader::from_str(opf);
     let mut in_metadata = false;
     let mut current_tag = String::new();
     let mut current_attrs: HashMap<String, String> = HashMap::new();
+    let mut refinable: Vec<Refinable> = Vec::new();
+    // (refined id, property, value) from EPUB 3 `<meta refines>`
+    let mut refinements: Vec<(String, String, String)> = Vec::new();
 
     loop {
         match reader.read_event() {
@@ -190,32 +218,85 @@ fn parse_opf_metadata(opf: &str, book: &mut BookDocument) {
                     }
                 }
             }
-            Ok(Event::Text(ref t)) => {
-                if in_metadata && !current_tag.is_empty() {
-                    if let Ok(text) = t.unescape() {
-                        let text = text.trim().to_string();
-                        if !text.is_empty() {
-                            match current_tag.as_str() {
-                                "title" => book.metadata.set_title(&text),
-                                "creator" => book.metadata.add("creator", &text),
-                                "language" => book.metadata.set("language", &text),
-                                "identifier" => {
-                                    book.metadata.add("identifier", &text);
-                                    if current_attrs.get("id").map(|s| s.as_str()) == Some("bookid")
-                                        || book.uid.is_none()
-                                    {
-                                        book.uid = Some(text.clone());
-                                    }
-                                }
-                                "description" => book.metadata.set("description", &text),
-                                "publisher" => book.metadata.set("publisher", &text),
-                                "date" => book.metadata.set("date", &text),
-                                "subject" => book.metadata.add("subject", &text),
-                                "rights" => book.metadata.set("rights", &text),
-                                _ => {
-                                    book.metadata.add(&current_tag, &text);
+            Ok(Event::Text(ref t)) if in_metadata && !current_tag.is_empty() => {
+                if let Ok(text) = t.unescape() {
+                    let text = text.trim().to_string();
+                    if !text.is_empty() {
+                        match current_tag.as_str() {
+                            "title" | "creator" => {
+                                let attrs = current_attrs
+                                    .iter()
+                                    .filter(|(k, _)| REFINEMENTS.contains(&k.as_str()))
+                                    .map(|(k, v)| (k.clone(), v.clone()))
+                                    .collect();
+                                refinable.push(Refinable {
+                                    term: current_tag.clone(),
+                                    id: current_attrs.get("id").cloned(),
+                                    value: text,
+                                    attrs,
+                                });
+                            }
+                            "language" => book.metadata.set("language", &text),
+                            "identifier" => {
+                                book.metadata.add("identifier", &text);
+                                if current_attrs.get("id").map(|s| s.as_str()) == Some("bookid")
+                                    || book.uid.is_none()
+                                {
+                                    book.uid = Some(text.clone());
                                 }
                             }
+                            "description" => book.metadata.set("description", &text),
+                            "publisher" => book.metadata.set("publisher", &text),
+                            "date" => book.metadata.set("date", &text),
+                            "subject" => book.metadata.add("subject", &text),
+                            "rights" => book.metadata.set("rights", &text),
+                            "meta"
+                                if current_attrs.get("property").map(|s| s.as_str())
+                                    == Some("dcterms:modified") =>
+                            {
+                                book.metadata.set("dcterms:modified", &text)
+                            }
+                            "meta"
+                                if current_attrs.contains_key("refines")
+                                    && current_attrs
+                                        .get("property")
+                                        .is_some_and(|p| REFINEMENTS.contains(&p.as_str())) =>
+                            {
+                                refinements.push((
+                                    current_attrs["refines"].trim_start_matches('#').to_string(),
+                                    current_attrs["property"].clone(),
+                                    text,
+                                ));
+                            }
+                            // EPUB 3 fixed-layout properties, kept for the writer
+                            "meta"
+                                if current_attrs
+                                    .get("property")
+                                    .is_some_and(|p| p.starts_with("rendition:")) =>
+                            {
+                                book.metadata.set(&current_attrs["property"], &text)
+                            }
+                            // EPUB 3 media overlay properties (durations, active class);
+                            // `refines` ties a duration to its SMIL item
+                            "meta"
+                                if current_attrs
+                                    .get("property")
+                                    .is_some_and(|p| p.starts_with("media:")) =>
+                            {
+                                let attrs = current_attrs
+                                    .iter()
+                                    .filter(|(k, _)| k.as_str() == "refines")
+                                    .map(|(k, v)| (k.clone(), v.clone()))
+                                    .collect();
+                                book.metadata.add_with_attrs(
+                                    &current_attrs["property"],
+                                    &text,
+                                    attrs,
+                                )
+                            }
+                            _ => {
+                                book.metadata.add(&current_tag, &text);
+                            }
                         }
                     }
                 }
@@ -234,6 +315,23 @@ fn parse_opf_metadata(opf: &str, book: &mut BookDocument) {
             _ => {}
         }
     }
+
+    for (id, property, value) in refinements {
+        if let Some(item) = refinable
+            .iter_mut()
+            .find(|item| item.id.as_deref() == Some(id.as_str()))
+        {
+            item.attrs.insert(property, value);
+        }
+    }
+    // Stable sort: the title marked main (or else the first) leads
+    refinable.sort_by_key(|item| {
+        item.term == "title" && item.attrs.get("title-type").map(|s| s.as_str()) != Some("main")
+    });
+    for item in refinable {
+        book.metadata
+            .add_with_attrs(item.term, item.value, item.attrs);
+    }
 }
 
 /// Parse OPF manifest section. Returns map of id -> (href, media-type).
@@ -287,8 +385,57 @@ fn parse_opf_manifest(opf: &str, _opf_dir: &str) -> HashMap<String, (String, Str
     items
 }
 
-/// Parse OPF spine section. Returns ordered list of idrefs.
-fn parse_opf_spine(opf: &str) -> Vec<String> {
+/// Parse `media-overlay` attributes from the OPF manifest. Returns map of
+/// content document id -> SMIL item id.
+fn parse_opf_media_overlays(opf: &str) -> HashMap<String, String> {
+    let mut overlays = HashMap::new();
+    let mut reader = Reader::from_str(opf);
+    let mut in_manifest = false;
+
+    loop {
+        match reader.read_event() {
+            Ok(Event::Start(ref e)) | Ok(Event::Empty(ref e)) => {
+                let local = String::from_utf8_lossy(e.local_name().as_ref()).to_string();
+                if local == "manifest" {
+                    in_manifest = true;
+                } else if local == "item" && in_manifest {
+                    let mut id = String::new();
+                    let mut overlay = String::new();
+
+                    for attr in e.attributes().flatten() {
+                        let key =
+                            String::from_utf8_lossy(attr.key.local_name().as_ref()).to_string();
+                        let val = String::from_utf8_lossy(&attr.value).to_string();
+                        match key.as_str() {
+                            "id" => id = val,
+                            "media-overlay" => overlay = val,
+                            _ => {}
+                        }
+                    }
+
+                    if !id.is_empty() && !overlay.is_empty() {
+                        overlays.insert(id, overlay);
+                    }
+                }
+            }
+            Ok(Event::End(ref e)) => {
+                let local = String::from_utf8_lossy(e.local_name().as_ref()).to_string();
+                if local == "manifest" {
+                    in_manifest = false;
+                }
+            }
+            Ok(Event::Eof) => break,
+            Err(_) => break,
+            _ => {}
+        }
+    }
+
+    overlays
+}
+
+/// Parse OPF spine section. Returns ordered (idref, linear) pairs; only
+/// `linear="no"` marks an item non-linear.
+fn parse_opf_spine(opf: &str) -> Vec<(String, bool)> {
     let mut idrefs = Vec::new();
     let mut reader = Reader::from_str(opf);
     let mut in_spine = false;
@@ -300,13 +447,22 @@ fn parse_opf_spine(opf: &str) -> Vec<String> {
                 if local == "spine" {
                     in_spine = true;
                 } else if local == "itemref" && in_spine {
+                    let mut idref = None;
+                    let mut linear = true;
                     for attr in e.attributes().flatten() {
                         let key =
                             String::from_utf8_lossy(attr.key.local_name().as_ref()).to_string();
-                        if key == "idref" {
-                            idrefs.push(String::from_utf8_lossy(&attr.value).to_string());
+                        match key.as_str() {
+                            "idref" => {
+                                idref = Some(String::from_utf8_lossy(&attr.value).to_string())
+                            }
+                            "linear" => linear = attr.value.as_ref() != b"no",
+                            _ => {}
                         }
                     }
+                    if let Some(idref) = idref {
+                        idrefs.push((idref, linear));
+                    }
                 }
             }
             Ok(Event::End(ref e)) => {
@@ -378,6 +534,11 @@ fn parse_opf_guide(opf: &str, opf_dir: &str, book: &mut BookDocument) {
 
 /// Find the NCX manifest item id from the spine toc attribute.
 fn find_ncx_id(opf: &str) -> Option<String> {
+    spine_attribute(opf, "toc")
+}
+
+/// The value of attribute `name` on the OPF `<spine>` element.
+fn spine_attribute(opf: &str, name: &str) -> Option<String> {
     let mut reader = Reader::from_str(opf);
 
     loop {
@@ -388,7 +549,7 @@ fn find_ncx_id(opf: &str) -> Option<String> {
                     for attr in e.attributes().flatten() {
                         let key =
                             String::from_utf8_lossy(attr.key.local_name().as_ref()).to_string();
-                        if key == "toc" {
+                        if key == name {
                             return Some(String::from_utf8_lossy(&attr.value).to_string());
                         }
                     }
@@ -445,11 +606,9 @@ fn parse_ncx(ncx: &str, book: &mut BookDocument) {
                     }
                 }
             }
-            Ok(Event::Text(ref t)) => {
-                if in_text {
-                    if let Ok(text) = t.unescape() {
-                        current_title.push_str(text.trim());
-                    }
+            Ok(Event::Text(ref t)) if in_text => {
+                if let Ok(text) = t.unescape() {
+                    current_title.push_str(text.trim());
                 }
             }
             Ok(Event::End(ref e)) => {
@@ -491,7 +650,7 @@ fn read_archive_entry(archive: &mut ZipArchive<File>, name: &str) -> Result<Vec<
 }
 
 /// Simple percent-decoding for EPUB hrefs.
-fn percent_decode(s: &str) -> String {
+pub(crate) fn percent_decode(s: &str) -> String {
     let mut result = String::with_capacity(s.len());
     let bytes = s.as_bytes();
     let mut i = 0;
@@ -534,6 +693,63 @@ mod tests {
         assert_eq!(book.uid, Some("urn:uuid:12345".to_string()));
     }
 
+    #[test]
+    fn test_parse_opf_rendition_metadata() {
+        let opf = r#"<package xmlns="http://www.idpf.org/2007/opf" version="3.0">
+  <metadata xmlns:dc="http://purl.org/dc/elements/1.1/">
+    <dc:title>Comic</dc:title>
+    <meta property="rendition:layout">pre-paginated</meta>
+    <meta property="rendition:spread">landscape</meta>
+    <meta property="dcterms:modified">2024-01-01T00:00:00Z</meta>
+  </metadata>
+</package>"#;
+
+        let mut book = BookDocument::new();
+        parse_opf_metadata(opf, &mut book);
+
+        assert!(book.metadata.is_fixed_layout());
+        assert_eq!(
+            book.metadata.get_first_value("rendition:spread"),
+            Some("landscape")
+        );
+        assert!(!book.metadata.contains("rendition:orientation"));
+    }
+
+    #[test]
+    fn test_parse_opf_refinements() {
+        let opf = r##"<package xmlns="http://www.idpf.org/2007/opf" version="3.0">
+  <metadata xmlns:dc="http://purl.org/dc/elements/1.1/">
+    <dc:title id="sub">A Subtitle</dc:title>
+    <dc:title id="main">The Title</dc:title>
+    <meta refines="#main" property="title-type">main</meta>
+    <meta refines="#sub" property="title-type">subtitle</meta>
+    <dc:creator id="c1">Ann Writer</dc:creator>
+    <meta refines="#c1" property="role" scheme="marc:relators">aut</meta>
+    <meta refines="#c1" property="file-as">Writer, Ann</meta>
+    <dc:creator id="c2">Ill Ustrator</dc:creator>
+    <meta refines="#c2" property="role" scheme="marc:relators">ill</meta>
+    <meta property="dcterms:modified">2024-01-01T00:00:00Z</meta>
+  </metadata>
+</package>"##;
+
+        let mut book = BookDocument::new();
+        parse_opf_metadata(opf, &mut book);
+
+        assert_eq!(book.metadata.title(), Some("The Title"));
+        let titles = book.metadata.get("title").unwrap();
+        assert_eq!(titles.len(), 2);
+        assert_eq!(titles[1].attributes["title-type"], "subtitle");
+        let creators = book.metadata.get("creator").unwrap();
+        assert_eq!(creators[0].attributes["role"], "aut");
+        assert_eq!(creators[0].attributes["file-as"], "Writer, Ann");
+        assert_eq!(creators[1].attributes["role"], "ill");
+        assert!(!creators[1].attributes.contains_key("id"));
+        assert_eq!(
+            book.metadata.get_first_value("dcterms:modified"),
+            Some("2024-01-01T00:00:00Z")
+        );
+    }
+
     #[test]
     fn test_parse_opf_manifest() {
         let opf = r#"<?xml version="1.0"?>
@@ -553,6 +769,36 @@ mod tests {
         assert_eq!(items["img1"].0, "images/cover.jpg");
     }
 
+    #[test]
+    fn test_parse_opf_media_overlays() {
+        let opf = r##"<package xmlns="http://www.idpf.org/2007/opf" version="3.0">
+  <metadata xmlns:dc="http://purl.org/dc/elements/1.1/">
+    <meta property="media:duration" refines="#ch1_overlay">0:01:30.500</meta>
+    <meta property="media:duration">0:01:30.500</meta>
+    <meta property="media:active-class">-epub-media-overlay-active</meta>
+  </metadata>
+  <manifest>
+    <item id="ch1" href="ch1.xhtml" media-type="application/xhtml+xml" media-overlay="ch1_overlay"/>
+    <item id="ch1_overlay" href="ch1.smil" media-type="application/smil+xml"/>
+  </manifest>
+</package>"##;
+
+        let overlays = parse_opf_media_overlays(opf);
+        assert_eq!(overlays.len(), 1);
+        assert_eq!(overlays["ch1"], "ch1_overlay");
+
+        let mut book = BookDocument::new();
+        parse_opf_metadata(opf, &mut book);
+        let durations = book.metadata.get("media:duration").unwrap();
+        assert_eq!(durations.len(), 2);
+        assert_eq!(durations[0].attributes["refines"], "#ch1_overlay");
+        assert!(durations[1].attributes.is_empty());
+        assert_eq!(
+            book.metadata.get_first_value("media:active-class"),
+            Some("-epub-media-overlay-active")
+        );
+    }
+
     #[test]
     fn test_parse_opf_spine() {
         let opf = r#"<?xml version="1.0"?>
@@ -561,11 +807,21 @@ mod tests {
     <itemref idref="ch1"/>
     <itemref idref="ch2"/>
     <itemref idref="ch3"/>
+    <itemref idref="notes" linear="no"/>
   </spine>
 </package>"#;
 
         let spine = parse_opf_spine(opf);
-        assert_eq!(spine, vec!["ch1", "ch2", "ch3"]);
+        let idrefs: Vec<&str> = spine.iter().map(|(idref, _)| idref.as_str()).collect();
+        assert_eq!(idrefs, vec!["ch1", "ch2", "ch3", "notes"]);
+
+        let mut book = BookDocument::new();
+        for (idref, linear) in spine {
+            book.spine.push(idref, linear);
+        }
+        assert_eq!(book.spine.len(), 4);
+        assert_eq!(book.spine.linear_items().count(), 3);
+        assert!(!book.spine.linear_items().any(|idref| idref == "notes"));
     }
 
     #[test]
@@ -574,6 +830,16 @@ mod tests {
         assert_eq!(find_ncx_id(opf), Some("ncx".to_string()));
     }
 
+    #[test]
+    fn test_spine_attribute() {
+        let opf = r#"<package><spine toc="ncx" page-progression-direction="rtl"/></package>"#;
+        assert_eq!(
+            spine_attribute(opf, "page-progression-direction").as_deref(),
+            Some("rtl")
+        );
+        assert_eq!(spine_attribute(opf, "id"), None);
+    }
+
     #[test]
     fn test_parse_ncx() {
         let ncx = r#"<?xml version="1.0"?>
@@ -1,11 +1,14 @@
 //! FB2 (FictionBook) input plugin — reads FictionBook XML files into BookDocument.
 //!
 //! FB2 is an XML-based ebook format popular in Russia. It stores text content,
-//! metadata, and base64-encoded images in a single XML file.
+//! metadata, and base64-encoded images in a single XML file. Books are often
+//! distributed zipped as `.fb2.zip`; such archives are unpacked transparently.
 
 use std::path::Path;
 
-use convert_core::book::{BookDocument, EbookFormat, ManifestData, ManifestItem, TocEntry};
+use convert_core::book::{
+    BookDocument, EbookFormat, GuideRef, ManifestData, ManifestItem, TocEntry,
+};
 use convert_core::error::{ConvertError, Result};
 use convert_core::options::ConversionOptions;
 use convert_core::plugin::InputPlugin;
@@ -29,9 +32,11 @@ impl InputPlugin for Fb2InputPlugin {
     }
 }
 
+/// Local file header signature that starts every ZIP archive.
+const ZIP_SIGNATURE: &[u8] = b"PK\x03\x04";
+
 fn parse_fb2(path: &Path) -> Result<BookDocument> {
-    let data = std::fs::read_to_string(path)
-        .map_err(|e| ConvertError::Fb2(format!("Cannot read {}: {}", path.display(), e)))?;
+    let data = read_fb2_source(path)?;
 
     let mut book = BookDocument::new();
     book.base_path = path.parent().map(|p| p.to_path_buf());
@@ -49,6 +54,8 @@ fn parse_fb2(path: &Path) -> Result<BookDocument> {
                 state.path.push(name.clone());
 
                 match name.as_str() {
+                    // The blurb becomes description metadata, not body content
+                    _ if is_in_path(&state.path, "title-info/annotation") => {}
                     "section" => {
                         state.section_depth += 1;
                         state.in_section = true;
@@ -74,9 +81,14 @@ fn parse_fb2(path: &Path) -> Result<BookDocument> {
                             if key == "l:href" || key == "href" {
                                 let href = String::from_utf8_lossy(&attr.value).to_string();
                                 let id = href.trim_start_matches('#');
-                                state
-                                    .html
-                                    .push_str(&format!(r#"<img src="images/{}" alt=""/>"#, id));
+                                if is_in_path(&state.path, "title-info/coverpage") {
+                                    // The cover is marked in the guide, not shown inline
+                                    state.cover_id = Some(id.to_string());
+                                } else {
+                                    state
+                                        .html
+                                        .push_str(&format!(r#"<img src="images/{}" alt=""/>"#, id));
+                                }
                             }
                         }
                     }
@@ -135,8 +147,18 @@ fn parse_fb2(path: &Path) -> Result<BookDocument> {
             }
             Ok(Event::End(ref e)) => {
                 let name = String::from_utf8_lossy(e.name().as_ref()).to_string();
+                let in_annotation = is_in_path(&state.path, "title-info/annotation");
 
                 match name.as_str() {
+                    "annotation" if in_annotation => {
+                        state.flush_annotation_paragraph();
+                        if !state.annotation.is_empty() {
+                            book.metadata
+                                .set("description", state.annotation.join("\n"));
+                        }
+                    }
+                    "p" if in_annotation => state.flush_annotation_paragraph(),
+                    _ if in_annotation => {}
                     "section" => {
                         state.section_depth -= 1;
                         if state.section_depth == 0 {
@@ -180,12 +202,11 @@ fn parse_fb2(path: &Path) -> Result<BookDocument> {
                             state.section_titles.push(title_text);
                         }
                     }
-                    "p" if state.in_title => {
-                        // Space between title paragraphs
-                        if !state.title_buf.is_empty() {
-                            state.title_buf.push(' ');
-                        }
+                    // Space between title paragraphs
+                    "p" if state.in_title && !state.title_buf.is_empty() => {
+                        state.title_buf.push(' ');
                     }
+                    "p" if state.in_title => {}
                     "p" if state.in_para => {
                         state.html.push_str("</p>\n");
                         state.in_para = false;
@@ -232,12 +253,13 @@ fn parse_fb2(path: &Path) -> Result<BookDocument> {
                         }
                         state.text_buf.clear();
                     }
-                    "author" => {
-                        if is_in_path(&state.path, "title-info") && !state.author_parts.is_empty() {
-                            let author = state.author_parts.join(" ");
-                            book.metadata.add("creator", &author);
-                            state.author_parts.clear();
-                        }
+                    "author"
+                        if is_in_path(&state.path, "title-info")
+                            && !state.author_parts.is_empty() =>
+                    {
+                        let author = state.author_parts.join(" ");
+                        book.metadata.add("creator", &author);
+                        state.author_parts.clear();
                     }
                     "genre" => {
                         if is_in_path(&state.path, "title-info") {
@@ -288,6 +310,11 @@ fn parse_fb2(path: &Path) -> Result<BookDocument> {
                     state.text_buf.push_str(&text);
                 } else if state.in_title {
                     state.title_buf.push_str(&text);
+                } else if is_in_path(&state.path, "title-info/annotation") {
+                    if !state.text_buf.is_empty() {
+                        state.text_buf.push(' ');
+                    }
+                    state.text_buf.push_str(&text);
                 } else if state.in_para || state.in_link {
                     state
                         .html
@@ -345,6 +372,13 @@ code { font-family: monospace; }"#;
     );
     book.manifest.add(css_item);
 
+    if let Some(ref cover_id) = state.cover_id {
+        match book.manifest.by_id(cover_id).map(|item| item.href.clone()) {
+            Some(href) => book.guide.add(GuideRef::new("cover", "Cover", href)),
+            None => log::warn!("FB2 coverpage references missing binary '{}'", cover_id),
+        }
+    }
+
     // Build TOC from section titles
     for title_text in &state.section_titles {
         book.toc.add(TocEntry::new(title_text, "content.xhtml"));
@@ -368,6 +402,52 @@ code { font-family: monospace; }"#;
     Ok(book)
 }
 
+/// Read the FB2 XML at `path`, unpacking it first if the file is a ZIP
+/// archive (`.fb2.zip`) holding a single `.fb2` entry.
+fn read_fb2_source(path: &Path) -> Result<String> {
+    let mut bytes = std::fs::read(path)
+        .map_err(|e| ConvertError::Fb2(format!("Cannot read {}: {}", path.display(), e)))?;
+
+    let zip_extension = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| e.eq_ignore_ascii_case("zip"));
+    if bytes.starts_with(ZIP_SIGNATURE) || zip_extension {
+        bytes = extract_fb2_entry(path)?;
+    }
+
+    convert_utils::encoding::decode_text(&bytes)
+        .map_err(|e| ConvertError::Fb2(format!("Cannot read {}: {}", path.display(), e)))
+}
+
+/// Bytes of the only `.fb2` entry in the ZIP archive at `path`.
+fn extract_fb2_entry(path: &Path) -> Result<Vec<u8>> {
+    let entries = convert_utils::archive::list_zip_entries(path).map_err(|e| {
+        ConvertError::Fb2(format!("Invalid FB2 ZIP archive {}: {}", path.display(), e))
+    })?;
+    let fb2_entries: Vec<&String> = entries
+        .iter()
+        .filter(|name| name.to_lowercase().ends_with(".fb2"))
+        .collect();
+
+    match fb2_entries.as_slice() {
+        [entry] => {
+            log::debug!("Extracting {} from {}", entry, path.display());
+            convert_utils::archive::read_zip_entry(path, entry)
+                .map_err(|e| ConvertError::Fb2(format!("Cannot read {}: {}", entry, e)))
+        }
+        [] => Err(ConvertError::Fb2(format!(
+            "No .fb2 file found in {}",
+            path.display()
+        ))),
+        many => Err(ConvertError::Fb2(format!(
+            "Expected one .fb2 file in {}, found {}",
+            path.display(),
+            many.len()
+        ))),
+    }
+}
+
 #[derive(Default)]
 struct ParseState {
     path: Vec<String>,
@@ -382,10 +462,25 @@ struct ParseState {
     in_link: bool,
     binary_id: Option<String>,
     binary_mime: Option<String>,
+    /// Binary id referenced by `title-info/coverpage`.
+    cover_id: Option<String>,
+    /// Paragraphs of the `title-info/annotation` blurb.
+    annotation: Vec<String>,
     author_parts: Vec<String>,
     section_titles: Vec<String>,
 }
 
+impl ParseState {
+    /// Move the annotation text collected so far into its own paragraph.
+    fn flush_annotation_paragraph(&mut self) {
+        let para = self.text_buf.trim().to_string();
+        if !para.is_empty() {
+            self.annotation.push(para);
+        }
+        self.text_buf.clear();
+    }
+}
+
 fn is_in_path(path: &[String], target: &str) -> bool {
     let parts: Vec<&str> = target.split('/').collect();
     if parts.len() > path.len() {
@@ -508,6 +603,46 @@ mod tests {
         let _ = std::fs::remove_dir_all(&dir);
     }
 
+    #[test]
+    fn test_parse_fb2_multi_paragraph_title() {
+        let fb2 = r#"<?xml version="1.0" encoding="UTF-8"?>
+<FictionBook xmlns="http://www.gribuser.ru/xml/fictionbook/2.0">
+  <description>
+    <title-info>
+      <book-title>Test</book-title>
+    </title-info>
+  </description>
+  <body>
+    <section>
+      <title><p>Chapter One</p><p>The Beginning</p></title>
+      <p>Text.</p>
+    </section>
+  </body>
+</FictionBook>"#;
+
+        let dir = std::env::temp_dir().join("test_fb2_title");
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("test.fb2");
+        std::fs::write(&path, fb2).unwrap();
+
+        let result = parse_fb2(&path).unwrap();
+        let xhtml = result
+            .manifest
+            .by_id("content")
+            .unwrap()
+            .data
+            .as_xhtml()
+            .unwrap();
+        assert!(
+            xhtml.contains("<h1>Chapter One The Beginning</h1>"),
+            "{}",
+            xhtml
+        );
+        assert_eq!(xhtml.matches("<p>").count(), xhtml.matches("</p>").count());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
     #[test]
     fn test_parse_fb2_with_binary() {
         // Tiny 1x1 PNG as base64
@@ -597,4 +732,154 @@ mod tests {
 
         let _ = std::fs::remove_dir_all(&dir);
     }
+
+    #[test]
+    fn test_parse_fb2_zip() {
+        let fb2 = r#"<?xml version="1.0" encoding="UTF-8"?>
+<FictionBook xmlns="http://www.gribuser.ru/xml/fictionbook/2.0">
+  <description>
+    <title-info>
+      <book-title>Zipped</book-title>
+      <language>en</language>
+    </title-info>
+  </description>
+  <body><section><title><p>One</p></title><p>Packed text</p></section></body>
+</FictionBook>"#;
+
+        let dir = std::env::temp_dir().join("test_fb2_zip");
+        std::fs::create_dir_all(&dir).unwrap();
+        let plain_path = dir.join("book.fb2");
+        std::fs::write(&plain_path, fb2).unwrap();
+        let zip_path = dir.join("book.fb2.zip");
+        {
+            let mut zip = convert_utils::archive::ZipBuilder::new(&zip_path).unwrap();
+            zip.add_file("book.fb2", fb2.as_bytes()).unwrap();
+            zip.finish().unwrap();
+        }
+
+        let plain = parse_fb2(&plain_path).unwrap();
+        let zipped = parse_fb2(&zip_path).unwrap();
+        assert_eq!(zipped.metadata.title(), plain.metadata.title());
+        assert_eq!(zipped.toc.entries.len(), plain.toc.entries.len());
+        assert_eq!(
+            zipped.manifest.by_id("content").unwrap().data.as_xhtml(),
+            plain.manifest.by_id("content").unwrap().data.as_xhtml()
+        );
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_parse_fb2_zip_entry_count() {
+        let dir = std::env::temp_dir().join("test_fb2_zip_count");
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let empty = dir.join("empty.fb2.zip");
+        {
+            let mut zip = convert_utils::archive::ZipBuilder::new(&empty).unwrap();
+            zip.add_file("readme.txt", b"no book").unwrap();
+            zip.finish().unwrap();
+        }
+        let many = dir.join("many.fb2.zip");
+        {
+            let mut zip = convert_utils::archive::ZipBuilder::new(&many).unwrap();
+            zip.add_file("a.fb2", b"<FictionBook/>").unwrap();
+            zip.add_file("b.fb2", b"<FictionBook/>").unwrap();
+            zip.finish().unwrap();
+        }
+
+        assert!(matches!(parse_fb2(&empty), Err(ConvertError::Fb2(_))));
+        assert!(matches!(parse_fb2(&many), Err(ConvertError::Fb2(_))));
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_parse_fb2_coverpage() {
+        let png_b64 = "iVBORw0KGgoAAAANSUhEUgAAAAEAAAABCAYAAAAfFcSJAAAADUlEQVR42mNk+M9QDwADhgGAWjR9awAAAABJRU5ErkJggg==";
+        let fb2 = format!(
+            r##"<?xml version="1.0" encoding="UTF-8"?>
+<FictionBook xmlns="http://www.gribuser.ru/xml/fictionbook/2.0" xmlns:l="http://www.w3.org/1999/xlink">
+  <description>
+    <title-info>
+      <book-title>Covered</book-title>
+      <coverpage><image l:href="#cover.png"/></coverpage>
+      <language>en</language>
+    </title-info>
+  </description>
+  <body><section><p>Story</p></section></body>
+  <binary id="cover.png" content-type="image/png">{}</binary>
+</FictionBook>"##,
+            png_b64
+        );
+
+        let dir = std::env::temp_dir().join("test_fb2_coverpage");
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("test.fb2");
+        std::fs::write(&path, fb2).unwrap();
+
+        let result = parse_fb2(&path).unwrap();
+        let cover_ref = result.guide.get("cover").unwrap();
+        let cover = result.manifest.by_href(&cover_ref.href).unwrap();
+        assert_eq!(cover.id, "cover.png");
+        assert_eq!(cover.media_type, "image/png");
+
+        let xhtml = result
+            .manifest
+            .by_id("content")
+            .unwrap()
+            .data
+            .as_xhtml()
+            .unwrap();
+        assert!(!xhtml.contains("<img"));
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_parse_fb2_annotation() {
+        let fb2 = r#"<?xml version="1.0" encoding="UTF-8"?>
+<FictionBook xmlns="http://www.gribuser.ru/xml/fictionbook/2.0">
+  <description>
+    <title-info>
+      <book-title>Blurbed</book-title>
+      <annotation>
+        <p>A tale of <emphasis>two</emphasis> cities.</p>
+        <p>Second paragraph.</p>
+      </annotation>
+      <language>en</language>
+    </title-info>
+  </description>
+  <body>
+    <section>
+      <epigraph><p>Not the blurb</p></epigraph>
+      <annotation><p>Section note</p></annotation>
+      <p>Story</p>
+    </section>
+  </body>
+</FictionBook>"#;
+
+        let dir = std::env::temp_dir().join("test_fb2_annotation");
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("test.fb2");
+        std::fs::write(&path, fb2).unwrap();
+
+        let result = parse_fb2(&path).unwrap();
+        assert_eq!(
+            result.metadata.get_first_value("description").unwrap(),
+            "A tale of two cities.\nSecond paragraph."
+        );
+
+        let xhtml = result
+            .manifest
+            .by_id("content")
+            .unwrap()
+            .data
+            .as_xhtml()
+            .unwrap();
+        assert!(!xhtml.contains("A tale of"));
+        assert!(xhtml.contains("Not the blurb"));
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
 }
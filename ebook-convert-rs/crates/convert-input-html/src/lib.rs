@@ -1,5 +1,8 @@
 //! HTML input plugin — reads a single HTML file into BookDocument.
 
+mod remote;
+
+use std::collections::HashMap;
 use std::path::Path;
 
 use convert_core::book::{BookDocument, EbookFormat, ManifestData, ManifestItem, TocEntry};
@@ -18,10 +21,10 @@ impl InputPlugin for HtmlInputPlugin {
         &[EbookFormat::Html, EbookFormat::Xhtml]
     }
 
-    fn convert(&self, input_path: &Path, _options: &ConversionOptions) -> Result<BookDocument> {
+    fn convert(&self, input_path: &Path, options: &ConversionOptions) -> Result<BookDocument> {
         log::info!("Reading HTML: {}", input_path.display());
 
-        let content = std::fs::read_to_string(input_path).map_err(|e| {
+        let mut content = convert_utils::encoding::read_text_file(input_path).map_err(|e| {
             ConvertError::Html(format!("Cannot read {}: {}", input_path.display(), e))
         })?;
 
@@ -44,11 +47,44 @@ impl InputPlugin for HtmlInputPlugin {
             collect_resources(&base_dir, &content, &mut book);
         }
 
+        if options.fetch_remote {
+            content = remote::fetch_remote_resources(&content, options, &mut book);
+        }
+
+        // Move <style> blocks and style="" attributes into a stylesheet
+        let (mut content, inline_css) = extract_inline_css(&content);
+        let inline_href = inline_css.map(|css| {
+            let href = if book.manifest.by_href(INLINE_CSS_HREF).is_none() {
+                INLINE_CSS_HREF.to_string()
+            } else {
+                format!("{}.css", book.manifest.generate_id("inline"))
+            };
+            let id = book.manifest.generate_id("css");
+            book.manifest.add(ManifestItem::new(
+                id,
+                &href,
+                "text/css",
+                ManifestData::Css(css),
+            ));
+            href
+        });
+
         // Wrap in proper XHTML 1.1 if needed
         let xhtml = if content.contains("<html") {
-            content
+            if let Some(href) = &inline_href {
+                let link = format!(
+                    "<link rel=\"stylesheet\" type=\"text/css\" href=\"{}\"/>\n",
+                    href
+                );
+                match content.to_lowercase().find("</head>") {
+                    Some(pos) => content.insert_str(pos, &link),
+                    None => content = content.replacen(INLINE_CSS_MARKER, &link, 1),
+                }
+            }
+            content.replace(INLINE_CSS_MARKER, "")
         } else {
-            convert_utils::xml::xhtml11_document(&title, "en", None, &content)
+            let content = content.replace(INLINE_CSS_MARKER, "");
+            convert_utils::xml::xhtml11_document(&title, "en", inline_href.as_deref(), &content)
         };
 
         let item = ManifestItem::new(
@@ -65,6 +101,107 @@ impl InputPlugin for HtmlInputPlugin {
     }
 }
 
+/// Href of the stylesheet built from the document's inline CSS.
+const INLINE_CSS_HREF: &str = "inline.css";
+
+/// Left where the first `<style>` block was, for documents without a `</head>`.
+const INLINE_CSS_MARKER: &str = "\u{0}inline-css\u{0}";
+
+/// Move the CSS of `<style>` blocks and `style="..."` attributes out of
+/// `html`. Each distinct `style` attribute becomes a generated class.
+/// Returns the rewritten HTML and the collected CSS, if there was any.
+fn extract_inline_css(html: &str) -> (String, Option<String>) {
+    let style_re = regex::Regex::new(r"(?is)<style\b([^>]*)>(.*?)</style\s*>").unwrap();
+    let media_re = regex::Regex::new(r#"(?i)\smedia\s*=\s*["']([^"']*)["']"#).unwrap();
+    let tag_re = regex::Regex::new(r"<[a-zA-Z][^>]*>").unwrap();
+    let style_attr_re = regex::Regex::new(r#"(?i)\sstyle\s*=\s*(?:"([^"]*)"|'([^']*)')"#).unwrap();
+    let class_attr_re = regex::Regex::new(r#"(?i)\sclass\s*=\s*(?:"([^"]*)"|'([^']*)')"#).unwrap();
+
+    let mut css = String::new();
+    let mut first = true;
+    let html = style_re.replace_all(html, |cap: &regex::Captures| {
+        let body = cap[2].trim();
+        let body = body.strip_prefix("<!--").unwrap_or(body);
+        let body = body.strip_suffix("-->").unwrap_or(body).trim();
+        if !body.is_empty() {
+            match media_re.captures(&cap[1]) {
+                Some(media) if !media[1].trim().eq_ignore_ascii_case("all") => {
+                    css.push_str(&format!("@media {} {{\n{}\n}}\n", media[1].trim(), body))
+                }
+                _ => {
+                    css.push_str(body);
+                    css.push('\n');
+                }
+            }
+        }
+        if std::mem::take(&mut first) {
+            INLINE_CSS_MARKER.to_string()
+        } else {
+            String::new()
+        }
+    });
+
+    let mut classes: HashMap<String, String> = HashMap::new();
+    let html = tag_re.replace_all(&html, |tag: &regex::Captures| {
+        let tag = &tag[0];
+        let Some(style) = style_attr_re.captures(tag) else {
+            return tag.to_string();
+        };
+        let declarations = style
+            .get(1)
+            .or_else(|| style.get(2))
+            .map_or("", |m| m.as_str())
+            .replace("&quot;", "\"")
+            .replace("&amp;", "&");
+        let declarations = declarations.trim().trim_end_matches(';').trim();
+        let without_style = tag.replacen(&style[0], "", 1);
+        if declarations.is_empty() {
+            return without_style;
+        }
+
+        let next = classes.len() + 1;
+        let class = classes
+            .entry(declarations.to_string())
+            .or_insert_with(|| {
+                let class = format!("inline-style-{}", next);
+                css.push_str(&format!(".{} {{ {} }}\n", class, declarations));
+                class
+            })
+            .clone();
+        match class_attr_re.captures(&without_style) {
+            Some(existing) => {
+                let value = existing
+                    .get(1)
+                    .or_else(|| existing.get(2))
+                    .unwrap()
+                    .as_str();
+                without_style.replacen(&existing[0], &format!(" class=\"{} {}\"", value, class), 1)
+            }
+            None => {
+                let end = if without_style.ends_with("/>") {
+                    without_style.len() - 2
+                } else {
+                    without_style.len() - 1
+                };
+                format!(
+                    "{} class=\"{}\"{}",
+                    without_style[..end].trim_end(),
+                    class,
+                    &without_style[end..]
+                )
+            }
+        }
+    });
+
+    let css = (!css.is_empty()).then_some(css);
+    let html = if css.is_some() {
+        html.into_owned()
+    } else {
+        html.replace(INLINE_CSS_MARKER, "")
+    };
+    (html, css)
+}
+
 fn extract_title(html: &str) -> Option<String> {
     let lower = html.to_lowercase();
     let start = lower.find("<title>")?;
@@ -86,7 +223,7 @@ fn collect_resources(base_dir: &Path, html: &str, book: &mut BookDocument) {
         let href = &cap[1];
         let file_path = base_dir.join(href);
         if file_path.exists() {
-            if let Ok(css_content) = std::fs::read_to_string(&file_path) {
+            if let Ok(css_content) = convert_utils::encoding::read_text_file(&file_path) {
                 let id = book.manifest.generate_id("css");
                 let item = ManifestItem::new(id, href, "text/css", ManifestData::Css(css_content));
                 book.manifest.add(item);
@@ -98,7 +235,7 @@ fn collect_resources(base_dir: &Path, html: &str, book: &mut BookDocument) {
     let img_re = regex::Regex::new(r#"(?i)<img[^>]+src\s*=\s*["']([^"']+)["']"#).unwrap();
     for cap in img_re.captures_iter(html) {
         let src = &cap[1];
-        // Skip data URIs and remote URLs
+        // Skip data URIs and remote URLs (see `fetch_remote`)
         if src.starts_with("data:") || src.starts_with("http") {
             continue;
         }
@@ -127,4 +264,56 @@ mod tests {
         assert_eq!(extract_title("<html><head></head>"), None);
         assert_eq!(extract_title("<title></title>"), None);
     }
+
+    #[test]
+    fn test_extract_inline_css() {
+        let html = r#"<html><head><style type="text/css">
+<!--
+p { margin: 0 }
+-->
+</style><style media="print">h1 { color: black }</style></head>
+<body><p style="color: red;">A</p><p class="x" style='color:red'>B</p><img style="width: 50%" src="a.png"/></body></html>"#;
+        let (html, css) = extract_inline_css(html);
+        let css = css.unwrap();
+
+        assert!(!html.contains("<style"));
+        assert!(!html.contains("style="));
+        assert!(css.contains("p { margin: 0 }"));
+        assert!(css.contains("@media print {\nh1 { color: black }\n}"));
+        assert!(css.contains(".inline-style-1 { color: red }"));
+        assert!(css.contains(".inline-style-2 { color:red }"));
+        assert!(css.contains(".inline-style-3 { width: 50% }"));
+        assert!(html.contains(r#"<p class="inline-style-1">A</p>"#));
+        assert!(html.contains(r#"<p class="x inline-style-2">B</p>"#));
+        assert!(html.contains(r#"<img src="a.png" class="inline-style-3"/>"#));
+
+        let (unchanged, css) = extract_inline_css("<p>Plain</p>");
+        assert_eq!(unchanged, "<p>Plain</p>");
+        assert!(css.is_none());
+    }
+
+    #[test]
+    fn test_style_block_becomes_css_item() {
+        let dir = std::env::temp_dir().join("test_html_style_block");
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("styled.html");
+        std::fs::write(
+            &path,
+            "<html><head><title>Styled</title><style>p { text-indent: 1em }</style></head><body><p>Text</p></body></html>",
+        )
+        .unwrap();
+
+        let book = HtmlInputPlugin
+            .convert(&path, &ConversionOptions::default())
+            .unwrap();
+
+        let css = book.manifest.by_href("inline.css").unwrap();
+        assert!(css.is_css());
+        assert!(css.data.as_css().unwrap().contains("text-indent: 1em"));
+        let xhtml = book.manifest.by_href("content.xhtml").unwrap();
+        let xhtml = xhtml.data.as_xhtml().unwrap();
+        assert!(xhtml.contains(r#"href="inline.css""#));
+        assert!(!xhtml.contains("<style"));
+        std::fs::remove_dir_all(&dir).ok();
+    }
 }
@@ -0,0 +1,252 @@
+//! Downloading of remote images and stylesheets (`fetch_remote`).
+//!
+//! Saved web pages usually reference their images and CSS by absolute
+//! `http(s)` URL. With `fetch_remote` set, each distinct URL is downloaded
+//! once, added to the manifest under `remote/`, and the `<img src>` or
+//! `<link href>` pointing at it is rewritten to the local copy. Fetches stop
+//! at `fetch_remote_max_count` resources or `fetch_remote_max_bytes` bytes;
+//! anything not fetched keeps its remote URL.
+
+use std::collections::HashMap;
+use std::io::Read;
+use std::time::Duration;
+
+use regex::{Captures, Regex};
+
+use convert_core::book::{BookDocument, ManifestData, ManifestItem};
+use convert_core::options::ConversionOptions;
+
+/// Give up on a resource that takes longer than this to download.
+const FETCH_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Largest single resource that is downloaded.
+const MAX_RESOURCE_BYTES: u64 = 10 * 1024 * 1024;
+
+/// Download the remote resources `html` references into `book` and return
+/// `html` with those references pointing at the local copies.
+pub(crate) fn fetch_remote_resources(
+    html: &str,
+    options: &ConversionOptions,
+    book: &mut BookDocument,
+) -> String {
+    let tag_re = Regex::new(r"(?is)<(img|link)\b[^>]*>").unwrap();
+    let attr_re = Regex::new(r#"(?is)\s(src|href|rel)\s*=\s*(?:"([^"]*)"|'([^']*)')"#).unwrap();
+
+    // Remote URLs in document order, as written in the attribute
+    let mut urls: Vec<String> = Vec::new();
+    for tag in tag_re.captures_iter(html) {
+        if let Some(url) = remote_reference(&tag, &attr_re) {
+            if !urls.contains(&url) {
+                urls.push(url);
+            }
+        }
+    }
+    if urls.is_empty() {
+        return html.to_string();
+    }
+
+    let agent = ureq::AgentBuilder::new().timeout(FETCH_TIMEOUT).build();
+    let mut local: HashMap<String, String> = HashMap::new();
+    let mut budget = options.fetch_remote_max_bytes;
+    for url in &urls {
+        if local.len() >= options.fetch_remote_max_count {
+            log::warn!(
+                "Fetched {} remote resources; leaving the rest remote",
+                local.len()
+            );
+            break;
+        }
+        let limit = budget.min(MAX_RESOURCE_BYTES);
+        let (media_type, data) = match fetch(&agent, &url.replace("&amp;", "&"), limit) {
+            Ok(fetched) => fetched,
+            Err(e) => {
+                log::warn!("Not fetching {}: {}", url, e);
+                continue;
+            }
+        };
+        budget -= data.len() as u64;
+
+        let n = local.len() + 1;
+        let href = format!(
+            "remote/{}.{}",
+            n,
+            convert_utils::mime::extension_from_mime(&media_type)
+        );
+        let data = if media_type == "text/css" {
+            ManifestData::Css(convert_utils::encoding::decode_text_lossy(&data))
+        } else {
+            ManifestData::Binary(data)
+        };
+        let id = book.manifest.generate_id("remote");
+        book.manifest
+            .add(ManifestItem::new(id, href.clone(), media_type, data));
+        log::debug!("Fetched {} as {}", url, href);
+        local.insert(url.clone(), href);
+    }
+
+    if !local.is_empty() {
+        log::info!("Fetched {} remote resources", local.len());
+    }
+
+    tag_re
+        .replace_all(html, |tag: &Captures| {
+            let Some(href) = remote_reference(tag, &attr_re).and_then(|url| local.get(&url)) else {
+                return tag[0].to_string();
+            };
+            let target = if tag[1].eq_ignore_ascii_case("img") {
+                "src"
+            } else {
+                "href"
+            };
+            attr_re
+                .replace_all(&tag[0], |attr: &Captures| {
+                    if !attr[1].eq_ignore_ascii_case(target) {
+                        attr[0].to_string()
+                    } else {
+                        let quote = if attr.get(2).is_some() { '"' } else { '\'' };
+                        let space = &attr[0][..1];
+                        format!("{}{}={}{}{}", space, &attr[1], quote, href, quote)
+                    }
+                })
+                .to_string()
+        })
+        .to_string()
+}
+
+/// The remote URL an `<img src>` or stylesheet `<link href>` points at.
+fn remote_reference(tag: &Captures, attr_re: &Regex) -> Option<String> {
+    let is_img = tag[1].eq_ignore_ascii_case("img");
+    let mut url = None;
+    let mut stylesheet = false;
+    for attr in attr_re.captures_iter(&tag[0]) {
+        let name = attr[1].to_ascii_lowercase();
+        let value = attr
+            .get(2)
+            .or_else(|| attr.get(3))
+            .map_or("", |m| m.as_str());
+        match name.as_str() {
+            "src" if is_img => url = Some(value.trim()),
+            "href" if !is_img => url = Some(value.trim()),
+            "rel" => {
+                stylesheet = value
+                    .split_whitespace()
+                    .any(|r| r.eq_ignore_ascii_case("stylesheet"))
+            }
+            _ => {}
+        }
+    }
+    let url = url?;
+    let lower = url.to_ascii_lowercase();
+    let remote = lower.starts_with("http://") || lower.starts_with("https://");
+    (remote && (is_img || stylesheet)).then(|| url.to_string())
+}
+
+/// Download `url`, refusing bodies over `limit` bytes. Returns the media
+/// type (from `Content-Type`, else the URL's extension) and the body.
+fn fetch(agent: &ureq::Agent, url: &str, limit: u64) -> Result<(String, Vec<u8>), String> {
+    let response = agent.get(url).call().map_err(|e| e.to_string())?;
+    let media_type = response
+        .header("Content-Type")
+        .and_then(|ct| ct.split(';').next())
+        .map(|ct| ct.trim().to_ascii_lowercase())
+        .filter(|ct| !ct.is_empty() && ct != "application/octet-stream")
+        .unwrap_or_else(|| {
+            let path = url.split(['?', '#']).next().unwrap_or(url);
+            convert_utils::mime::mime_from_path(std::path::Path::new(path)).to_string()
+        });
+
+    let mut data = Vec::new();
+    response
+        .into_reader()
+        .take(limit + 1)
+        .read_to_end(&mut data)
+        .map_err(|e| e.to_string())?;
+    if data.len() as u64 > limit {
+        return Err(format!("larger than the {} byte limit", limit));
+    }
+    Ok((media_type, data))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use std::net::TcpListener;
+
+    /// Serve `body` as `content_type` to every request on a local port, and
+    /// return that port.
+    fn serve(content_type: &'static str, body: &'static [u8]) -> u16 {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut stream = stream.unwrap();
+                let mut request = [0u8; 4096];
+                let _ = stream.read(&mut request);
+                let head = format!(
+                    "HTTP/1.1 200 OK\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                    content_type,
+                    body.len()
+                );
+                let _ = stream.write_all(head.as_bytes());
+                let _ = stream.write_all(body);
+            }
+        });
+        port
+    }
+
+    #[test]
+    fn test_fetch_and_rewrite_remote_image() {
+        let port = serve("image/png", b"\x89PNG fake image");
+        let url = format!("http://127.0.0.1:{}/pics/photo.png", port);
+        let html = format!(
+            r#"<p><img alt="a" src="{url}"/><img src='{url}'/><a href="{url}">link</a></p>"#
+        );
+        let opts = ConversionOptions {
+            fetch_remote: true,
+            ..Default::default()
+        };
+        let mut book = BookDocument::new();
+
+        let rewritten = fetch_remote_resources(&html, &opts, &mut book);
+
+        let item = book.manifest.by_href("remote/1.png").unwrap();
+        assert_eq!(item.media_type, "image/png");
+        assert_eq!(item.data.as_binary(), Some(&b"\x89PNG fake image"[..]));
+        assert_eq!(book.manifest.len(), 1);
+        assert_eq!(
+            rewritten,
+            format!(
+                r#"<p><img alt="a" src="remote/1.png"/><img src='remote/1.png'/><a href="{url}">link</a></p>"#
+            )
+        );
+    }
+
+    #[test]
+    fn test_fetch_respects_budget() {
+        let port = serve("text/css", b"p { color: red }");
+        let html = format!(
+            r#"<link rel="stylesheet" href="http://127.0.0.1:{port}/a.css"/><link rel="stylesheet" href="http://127.0.0.1:{port}/b.css"/>"#
+        );
+
+        let opts = ConversionOptions {
+            fetch_remote: true,
+            fetch_remote_max_count: 1,
+            ..Default::default()
+        };
+        let mut book = BookDocument::new();
+        let rewritten = fetch_remote_resources(&html, &opts, &mut book);
+        assert!(rewritten.contains(r#"href="remote/1.css""#));
+        assert!(rewritten.contains("/b.css"));
+        assert!(book.manifest.by_href("remote/1.css").unwrap().is_css());
+
+        let opts = ConversionOptions {
+            fetch_remote: true,
+            fetch_remote_max_bytes: 4,
+            ..Default::default()
+        };
+        let mut book = BookDocument::new();
+        assert_eq!(fetch_remote_resources(&html, &opts, &mut book), html);
+        assert!(book.manifest.is_empty());
+    }
+}
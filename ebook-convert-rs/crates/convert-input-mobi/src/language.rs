@@ -0,0 +1,216 @@
+//! MOBI locale codes to BCP-47 language tags.
+//!
+//! The MOBI header stores the book's locale as a Windows language ID: the
+//! low byte is the primary language and the next byte holds the sublanguage
+//! (usually the region) shifted left by two bits. EXTH record 524, when
+//! present, already carries a tag and takes precedence.
+
+/// The BCP-47 tag for a MOBI book, from its EXTH 524 record or else from
+/// the header's language and sublanguage codes. Unknown locales give `None`.
+pub fn language_tag(exth: Option<&[u8]>, language: u8, locale: u8) -> Option<String> {
+    let exth = exth
+        .map(|raw| String::from_utf8_lossy(raw).trim().to_string())
+        .filter(|tag| {
+            !tag.is_empty() && tag.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+        });
+    exth.or_else(|| locale_tag(language, locale >> 2))
+}
+
+/// The tag for a Windows primary language and sublanguage ID.
+fn locale_tag(language: u8, sublanguage: u8) -> Option<String> {
+    let primary = primary_language(language)?;
+    let tag = match (primary, sublanguage) {
+        // Chinese regions imply the script
+        ("zh", 1) => "zh-Hant-TW",
+        ("zh", 2) => "zh-Hans-CN",
+        ("zh", 3) => "zh-Hant-HK",
+        ("zh", 4) => "zh-Hans-SG",
+        ("zh", 5) => "zh-Hant-MO",
+        // Norwegian's sublanguages are its two written standards
+        ("no", 1) => "nb",
+        ("no", 2) => "nn",
+        _ => {
+            return Some(match region(primary, sublanguage) {
+                Some(region) => format!("{}-{}", primary, region),
+                None => primary.to_string(),
+            });
+        }
+    };
+    Some(tag.to_string())
+}
+
+/// ISO 639 code of a Windows primary language ID.
+fn primary_language(id: u8) -> Option<&'static str> {
+    let code = match id {
+        0x01 => "ar",
+        0x02 => "bg",
+        0x03 => "ca",
+        0x04 => "zh",
+        0x05 => "cs",
+        0x06 => "da",
+        0x07 => "de",
+        0x08 => "el",
+        0x09 => "en",
+        0x0A => "es",
+        0x0B => "fi",
+        0x0C => "fr",
+        0x0D => "he",
+        0x0E => "hu",
+        0x0F => "is",
+        0x10 => "it",
+        0x11 => "ja",
+        0x12 => "ko",
+        0x13 => "nl",
+        0x14 => "no",
+        0x15 => "pl",
+        0x16 => "pt",
+        0x17 => "rm",
+        0x18 => "ro",
+        0x19 => "ru",
+        0x1A => "hr",
+        0x1B => "sk",
+        0x1C => "sq",
+        0x1D => "sv",
+        0x1E => "th",
+        0x1F => "tr",
+        0x20 => "ur",
+        0x21 => "id",
+        0x22 => "uk",
+        0x23 => "be",
+        0x24 => "sl",
+        0x25 => "et",
+        0x26 => "lv",
+        0x27 => "lt",
+        0x29 => "fa",
+        0x2A => "vi",
+        0x2B => "hy",
+        0x2C => "az",
+        0x2D => "eu",
+        0x2F => "mk",
+        0x32 => "tn",
+        0x34 => "xh",
+        0x35 => "zu",
+        0x36 => "af",
+        0x37 => "ka",
+        0x38 => "fo",
+        0x39 => "hi",
+        0x3A => "mt",
+        0x3B => "se",
+        0x3C => "ga",
+        0x3E => "ms",
+        0x3F => "kk",
+        0x41 => "sw",
+        0x43 => "uz",
+        0x44 => "tt",
+        0x45 => "bn",
+        0x46 => "pa",
+        0x47 => "gu",
+        0x48 => "or",
+        0x49 => "ta",
+        0x4A => "te",
+        0x4B => "kn",
+        0x4C => "ml",
+        0x4D => "as",
+        0x4E => "mr",
+        0x4F => "sa",
+        0x57 => "kok",
+        0x61 => "ne",
+        _ => return None,
+    };
+    Some(code)
+}
+
+/// Region subtag of a sublanguage ID, for languages spoken in several
+/// regions. Sublanguage 0 is language-neutral.
+fn region(primary: &str, sublanguage: u8) -> Option<&'static str> {
+    let regions: &[&str] = match primary {
+        "en" => &[
+            "US", "GB", "AU", "CA", "NZ", "IE", "ZA", "JM", "029", "BZ", "TT", "ZW", "PH",
+        ],
+        "fr" => &["FR", "BE", "CA", "CH", "LU", "MC"],
+        "de" => &["DE", "CH", "AT", "LU", "LI"],
+        "es" => &[
+            "ES", "MX", "ES", "GT", "CR", "PA", "DO", "VE", "CO", "PE", "AR", "EC", "CL", "UY",
+            "PY", "BO", "SV", "HN", "NI", "PR",
+        ],
+        "pt" => &["BR", "PT"],
+        "it" => &["IT", "CH"],
+        "nl" => &["NL", "BE"],
+        "sv" => &["SE", "FI"],
+        "ar" => &[
+            "SA", "IQ", "EG", "LY", "DZ", "MA", "TN", "OM", "YE", "SY", "JO", "LB", "KW", "AE",
+            "BH", "QA",
+        ],
+        "ko" => &["KR"],
+        "ru" => &["RU"],
+        "ms" => &["MY", "BN"],
+        "ur" => &["PK", "IN"],
+        _ => &[],
+    };
+    regions
+        .get(usize::from(sublanguage).checked_sub(1)?)
+        .copied()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The header's locale byte for a sublanguage ID.
+    fn locale(sublanguage: u8) -> u8 {
+        sublanguage << 2
+    }
+
+    #[test]
+    fn test_portuguese_regions() {
+        assert_eq!(
+            language_tag(None, 0x16, locale(1)).as_deref(),
+            Some("pt-BR")
+        );
+        assert_eq!(
+            language_tag(None, 0x16, locale(2)).as_deref(),
+            Some("pt-PT")
+        );
+        assert_eq!(language_tag(None, 0x16, 0).as_deref(), Some("pt"));
+    }
+
+    #[test]
+    fn test_chinese_variants() {
+        assert_eq!(
+            language_tag(None, 0x04, locale(2)).as_deref(),
+            Some("zh-Hans-CN")
+        );
+        assert_eq!(
+            language_tag(None, 0x04, locale(1)).as_deref(),
+            Some("zh-Hant-TW")
+        );
+        assert_eq!(
+            language_tag(None, 0x04, locale(3)).as_deref(),
+            Some("zh-Hant-HK")
+        );
+        assert_eq!(language_tag(None, 0x04, 0).as_deref(), Some("zh"));
+    }
+
+    #[test]
+    fn test_other_locales() {
+        assert_eq!(
+            language_tag(None, 0x09, locale(2)).as_deref(),
+            Some("en-GB")
+        );
+        assert_eq!(language_tag(None, 0x14, locale(2)).as_deref(), Some("nn"));
+        assert_eq!(language_tag(None, 0x07, locale(9)).as_deref(), Some("de"));
+        assert_eq!(language_tag(None, 0x00, 0), None);
+    }
+
+    #[test]
+    fn test_exth_language_wins() {
+        assert_eq!(
+            language_tag(Some(b"pt-BR"), 0x09, locale(1)).as_deref(),
+            Some("pt-BR")
+        );
+        assert_eq!(
+            language_tag(Some(b" "), 0x0C, locale(3)).as_deref(),
+            Some("fr-CA")
+        );
+    }
+}
@@ -3,9 +3,13 @@
 //! Uses the `mobi` crate for header parsing, decompression, and content extraction.
 //! MOBI files contain HTML content with embedded images stored as PDB records.
 
+mod language;
+
 use std::path::Path;
 
-use convert_core::book::{BookDocument, EbookFormat, ManifestData, ManifestItem, TocEntry};
+use convert_core::book::{
+    BookDocument, EbookFormat, GuideRef, ManifestData, ManifestItem, TocEntry,
+};
 use convert_core::error::{ConvertError, Result};
 use convert_core::options::ConversionOptions;
 use convert_core::plugin::InputPlugin;
@@ -70,8 +74,19 @@ fn parse_mobi(path: &Path) -> Result<BookDocument> {
         book.metadata.set("date", date);
     }
 
-    let lang = format!("{:?}", mobi.language());
-    book.metadata.set("language", lang_to_code(&lang));
+    let header = &mobi.metadata.mobi;
+    let exth_language = mobi
+        .metadata
+        .exth
+        .get_record(mobi::headers::ExthRecord::Language)
+        .and_then(|values| values.first());
+    let language = language::language_tag(
+        exth_language.map(|v| v.as_slice()),
+        header.language_code.into(),
+        header.locale,
+    );
+    book.metadata
+        .set("language", language.as_deref().unwrap_or("en"));
 
     // -- Extract HTML content (can panic on malformed records) --
     // Try strict first, fall back to lossy
@@ -113,6 +128,22 @@ fn parse_mobi(path: &Path) -> Result<BookDocument> {
         image_map.push((id, href));
     }
 
+    // -- Cover: EXTH 201 (or the 202 thumbnail) indexes the image records --
+    if let Some(href) = cover_offset(&mobi).and_then(|offset| {
+        let id = format!("img_{}", offset + 1);
+        image_map
+            .iter()
+            .find(|(i, _)| *i == id)
+            .map(|(_, h)| h.clone())
+    }) {
+        book.guide.add(GuideRef::new("cover", "Cover", href));
+    }
+
+    // -- Navigation: the guide's TOC page, with filepos links resolved to ids --
+    // Both need the original text, since filepos values are byte offsets into it
+    let toc_entries = toc_from_guide(&html_content);
+    let html_content = resolve_filepos_links(&html_content);
+
     // -- Process HTML: fix image references --
     // MOBI uses <img recindex="NNNN"> for image references
     let processed_html = fix_image_references(&html_content, &image_map);
@@ -148,14 +179,170 @@ img { max-width: 100%; height: auto; }
     );
     book.manifest.add(css_item);
 
-    // Build basic TOC from headings
-    build_toc_from_headings(&cleaned_html, &mut book);
+    // The book's own TOC page, else a basic TOC from headings
+    if toc_entries.is_empty() {
+        build_toc_from_headings(&cleaned_html, &mut book);
+    } else {
+        for entry in toc_entries {
+            book.toc.add(entry);
+        }
+    }
 
     log::info!("Parsed MOBI: \"{}\" with {} images", title, image_map.len());
 
     Ok(book)
 }
 
+/// Matches a `filepos` attribute, quoted or not, capturing the offset.
+const FILEPOS_ATTR: &str = r#"(?i)\bfilepos\s*=\s*["']?(\d+)["']?"#;
+
+/// Anchor id marking the text at byte offset `pos`.
+fn filepos_id(pos: usize) -> String {
+    format!("filepos{}", pos)
+}
+
+/// Insert an anchor at every offset a `<a filepos=N>` link targets, and
+/// point those links at the anchors.
+fn resolve_filepos_links(html: &str) -> String {
+    let link_re = Regex::new(&format!(r"(?i)<a\s[^>]*?{}[^>]*>", FILEPOS_ATTR)).unwrap();
+    let attr_re = Regex::new(FILEPOS_ATTR).unwrap();
+
+    let mut targets: Vec<usize> = link_re
+        .captures_iter(html)
+        .filter_map(|cap| cap[1].parse().ok())
+        .filter(|&pos| pos <= html.len())
+        .collect();
+    if targets.is_empty() {
+        return html.to_string();
+    }
+    targets.sort_unstable();
+    targets.dedup();
+
+    // Anchors go in from the end so earlier offsets stay valid; an offset
+    // inside a tag moves back to the tag's start
+    let mut out = html.to_string();
+    for &pos in targets.iter().rev() {
+        let mut at = pos;
+        while !out.is_char_boundary(at) {
+            at -= 1;
+        }
+        if let Some(open) = out[..at].rfind('<') {
+            if !out[open..at].contains('>') {
+                at = open;
+            }
+        }
+        out.insert_str(at, &format!(r#"<span id="{}"></span>"#, filepos_id(pos)));
+    }
+
+    link_re
+        .replace_all(&out, |cap: &regex::Captures| {
+            let pos: usize = cap[1].parse().unwrap_or(0);
+            if targets.binary_search(&pos).is_err() {
+                return cap[0].to_string();
+            }
+            let href = format!(r##"href="#{}""##, filepos_id(pos));
+            attr_re.replace(&cap[0], href.as_str()).to_string()
+        })
+        .to_string()
+}
+
+/// The cover's offset from the first image record, from EXTH 201 or, failing
+/// that, the thumbnail's in EXTH 202. `0xFFFFFFFF` means no cover.
+fn cover_offset(mobi: &mobi::Mobi) -> Option<usize> {
+    use mobi::headers::ExthRecord;
+
+    [ExthRecord::CoverOffset, ExthRecord::ThumbOffset]
+        .into_iter()
+        .find_map(|record| {
+            let value = mobi.metadata.exth.get_record(record)?.first()?;
+            let bytes: [u8; 4] = value.get(..4)?.try_into().ok()?;
+            let offset = u32::from_be_bytes(bytes);
+            (offset != u32::MAX).then_some(offset as usize)
+        })
+}
+
+/// Reconstruct the table of contents from the page the guide's `toc`
+/// reference points at: each `<a filepos=N>` there becomes an entry, nested
+/// by the lists and blockquotes around it. Empty when there is no such page.
+fn toc_from_guide(html: &str) -> Vec<TocEntry> {
+    let guide_re = Regex::new(&format!(
+        r#"(?i)<reference\s[^>]*?type\s*=\s*["']?toc["']?[^>]*?{}"#,
+        FILEPOS_ATTR
+    ))
+    .unwrap();
+    let guide_re_reversed = Regex::new(&format!(
+        r#"(?i)<reference\s[^>]*?{}[^>]*?type\s*=\s*["']?toc\b"#,
+        FILEPOS_ATTR
+    ))
+    .unwrap();
+    let Some(start) = guide_re
+        .captures(html)
+        .or_else(|| guide_re_reversed.captures(html))
+        .and_then(|cap| cap[1].parse::<usize>().ok())
+        .filter(|&pos| pos < html.len() && html.is_char_boundary(pos))
+    else {
+        return Vec::new();
+    };
+
+    // The TOC page runs to the next page break
+    let page = &html[start..];
+    let end = page
+        .to_ascii_lowercase()
+        .find("<mbp:pagebreak")
+        .filter(|&end| end > 0)
+        .unwrap_or(page.len());
+    let page = &page[..end];
+
+    let token_re = Regex::new(&format!(
+        r"(?is)<a\s[^>]*?{}[^>]*>(.*?)</a\s*>|<(/?)(ul|ol|dl|blockquote)\b[^>]*>",
+        FILEPOS_ATTR
+    ))
+    .unwrap();
+    let mut depth = 0usize;
+    let mut links: Vec<(usize, TocEntry)> = Vec::new();
+    for cap in token_re.captures_iter(page) {
+        if let Some(pos) = cap.get(1) {
+            let Some(title) = convert_utils::xml::heading_text(&cap[2]) else {
+                continue;
+            };
+            let href = format!(
+                "content.xhtml#{}",
+                filepos_id(pos.as_str().parse().unwrap_or(0))
+            );
+            links.push((depth, TocEntry::new(&title, &href)));
+        } else if &cap[3] == "/" {
+            depth = depth.saturating_sub(1);
+        } else {
+            depth += 1;
+        }
+    }
+    nest_toc(links)
+}
+
+/// Build a tree from entries tagged with their nesting depth, in order.
+fn nest_toc(entries: Vec<(usize, TocEntry)>) -> Vec<TocEntry> {
+    fn attach(stack: &mut [(usize, TocEntry)], roots: &mut Vec<TocEntry>, entry: TocEntry) {
+        match stack.last_mut() {
+            Some((_, parent)) => parent.add_child(entry),
+            None => roots.push(entry),
+        }
+    }
+
+    let mut roots = Vec::new();
+    let mut stack: Vec<(usize, TocEntry)> = Vec::new();
+    for (depth, entry) in entries {
+        while stack.last().is_some_and(|(d, _)| *d >= depth) {
+            let (_, done) = stack.pop().unwrap();
+            attach(&mut stack, &mut roots, done);
+        }
+        stack.push((depth, entry));
+    }
+    while let Some((_, done)) = stack.pop() {
+        attach(&mut stack, &mut roots, done);
+    }
+    roots
+}
+
 /// Fix MOBI image references: `<img recindex="N">` → `<img src="images/image_NNNN.ext">`
 fn fix_image_references(html: &str, image_map: &[(String, String)]) -> String {
     let recindex_re = Regex::new(r#"<img\s[^>]*recindex\s*=\s*["']?(\d+)["']?[^>]*>"#).unwrap();
@@ -218,8 +405,6 @@ fn clean_mobi_html(html: &str) -> String {
 
 /// Build a basic TOC from heading elements in the HTML.
 fn build_toc_from_headings(html: &str, book: &mut BookDocument) {
-    let tag_re = Regex::new(r"<[^>]+>").unwrap();
-
     // Rust regex doesn't support backreferences; use separate patterns per level
     let patterns = [
         Regex::new(r"(?i)<h1[^>]*>(.*?)</h1>").unwrap(),
@@ -231,8 +416,7 @@ fn build_toc_from_headings(html: &str, book: &mut BookDocument) {
     for pattern in &patterns {
         for cap in pattern.captures_iter(html) {
             let pos = cap.get(0).unwrap().start();
-            let text = tag_re.replace_all(&cap[1], "").trim().to_string();
-            if !text.is_empty() {
+            if let Some(text) = convert_utils::xml::heading_text(&cap[1]) {
                 headings.push((pos, text));
             }
         }
@@ -268,33 +452,6 @@ fn detect_image_type(data: &[u8]) -> (&str, &str) {
     }
 }
 
-/// Map Language debug name to ISO 639-1 code.
-fn lang_to_code(lang: &str) -> &str {
-    match lang {
-        "English" => "en",
-        "French" => "fr",
-        "German" => "de",
-        "Spanish" => "es",
-        "Italian" => "it",
-        "Portuguese" => "pt",
-        "Dutch" => "nl",
-        "Russian" => "ru",
-        "Japanese" => "ja",
-        "Chinese" => "zh",
-        "Korean" => "ko",
-        "Arabic" => "ar",
-        "Hindi" => "hi",
-        "Swedish" => "sv",
-        "Danish" => "da",
-        "Norwegian" => "no",
-        "Finnish" => "fi",
-        "Polish" => "pl",
-        "Turkish" => "tr",
-        "Czech" => "cs",
-        _ => "en",
-    }
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -332,6 +489,43 @@ mod tests {
         assert!(result.contains("<p>Hello</p>"));
     }
 
+    /// MOBI text whose TOC page (found through the guide) links to chapters
+    /// that have no heading tags.
+    fn mobi_text_with_toc_page() -> String {
+        let template = r#"<html><head><guide><reference type="toc" title="Contents" filepos=TTTTTTTTTT /></guide></head><body><div>Contents</div><blockquote><a filepos=AAAAAAAAAA>Part One</a><blockquote><a filepos=BBBBBBBBBB>The Beginning</a></blockquote></blockquote><a filepos=CCCCCCCCCC><b>Epilogue</b></a><mbp:pagebreak/><p>Part one starts.</p><p class="big">It begins.</p><mbp:pagebreak/><p>The end.</p></body></html>"#;
+        let offset = |marker: &str| format!("{:010}", template.find(marker).unwrap());
+        let toc = template.find("<div>Contents").unwrap();
+        template
+            .replace("TTTTTTTTTT", &format!("{:010}", toc))
+            .replace("AAAAAAAAAA", &offset("<p>Part one"))
+            .replace("BBBBBBBBBB", &offset("<p class=\"big\">"))
+            .replace("CCCCCCCCCC", &offset("<p>The end"))
+    }
+
+    #[test]
+    fn test_toc_from_guide() {
+        let html = mobi_text_with_toc_page();
+        let toc = toc_from_guide(&html);
+        assert_eq!(toc.len(), 2);
+        assert_eq!(toc[0].title, "Part One");
+        assert_eq!(toc[0].children.len(), 1);
+        assert_eq!(toc[0].children[0].title, "The Beginning");
+        assert_eq!(toc[1].title, "Epilogue");
+
+        // Each entry's anchor sits right before its target
+        let resolved = resolve_filepos_links(&html);
+        for (entry, text) in [
+            (&toc[0], "<p>Part one starts."),
+            (&toc[0].children[0], "<p class=\"big\">It begins."),
+            (&toc[1], "<p>The end."),
+        ] {
+            let id = entry.href.split('#').nth(1).unwrap();
+            assert!(resolved.contains(&format!(r#"<span id="{}"></span>{}"#, id, text)));
+            assert!(resolved.contains(&format!(r##"href="#{}""##, id)));
+        }
+        assert!(toc_from_guide("<p>No guide</p>").is_empty());
+    }
+
     #[test]
     fn test_detect_image_type() {
         assert_eq!(
@@ -346,13 +540,6 @@ mod tests {
         assert_eq!(detect_image_type(b"\x00\x00"), ("image/jpeg", "jpg")); // fallback
     }
 
-    #[test]
-    fn test_lang_to_code() {
-        assert_eq!(lang_to_code("English"), "en");
-        assert_eq!(lang_to_code("French"), "fr");
-        assert_eq!(lang_to_code("Unknown"), "en");
-    }
-
     #[test]
     fn test_build_toc_from_headings() {
         let html =
@@ -374,4 +561,45 @@ mod tests {
         assert_eq!(book.toc.entries.len(), 1);
         assert_eq!(book.toc.entries[0].title, "Fallback Title");
     }
+
+    #[test]
+    fn test_exth_cover_offset_sets_guide_cover() {
+        use convert_core::plugin::OutputPlugin;
+
+        let mut book = BookDocument::new();
+        book.metadata.set_title("Covered");
+        book.manifest.add(ManifestItem::new(
+            "ch1",
+            "ch1.xhtml",
+            "application/xhtml+xml",
+            ManifestData::Xhtml("<html><body><p>Text</p></body></html>".into()),
+        ));
+        book.spine.push("ch1", true);
+        let map = b"\x89PNG\r\n\x1a\nmap".to_vec();
+        let cover = b"\xff\xd8\xff\xe0cover".to_vec();
+        for (id, href, mime, data) in [
+            ("map", "map.png", "image/png", &map),
+            ("cover", "cover.jpg", "image/jpeg", &cover),
+        ] {
+            book.manifest.add(ManifestItem::new(
+                id,
+                href,
+                mime,
+                ManifestData::Binary(data.clone()),
+            ));
+        }
+        book.guide.add(GuideRef::new("cover", "Cover", "cover.jpg"));
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("book.mobi");
+        convert_output_mobi::MobiOutputPlugin
+            .convert(&book, &path, &ConversionOptions::default())
+            .unwrap();
+
+        let parsed = parse_mobi(&path).unwrap();
+        let href = &parsed.guide.get("cover").unwrap().href;
+        assert_eq!(href, "images/image_0002.jpg");
+        let image = parsed.manifest.by_href(href).unwrap();
+        assert_eq!(image.data.as_binary().unwrap(), cover.as_slice());
+    }
 }
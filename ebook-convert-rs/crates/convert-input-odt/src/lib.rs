@@ -3,6 +3,7 @@
 //! ODT files are ZIP archives containing XML content (similar to DOCX).
 //! Main content is in `content.xml`, metadata in `meta.xml`, styles in `styles.xml`.
 
+use std::collections::HashMap;
 use std::io::Read;
 use std::path::Path;
 
@@ -46,25 +47,24 @@ fn parse_odt(path: &Path) -> Result<BookDocument> {
         parse_metadata(&meta_xml, &mut book);
     }
 
-    // Parse styles to detect heading levels
-    let heading_styles = if let Ok(styles_xml) = read_zip_string(&mut archive, "styles.xml") {
-        parse_heading_styles(&styles_xml)
-    } else {
-        Vec::new()
-    };
+    // Parse styles to detect heading levels and paragraph languages
+    let styles_xml = read_zip_string(&mut archive, "styles.xml").unwrap_or_default();
+    let heading_styles = parse_heading_styles(&styles_xml);
+    let mut paragraph_langs = parse_paragraph_languages(&styles_xml);
 
     // Also check content.xml for automatic styles
     let content_xml = read_zip_string(&mut archive, "content.xml")
         .map_err(|e| ConvertError::Odt(format!("Missing content.xml: {}", e)))?;
 
     let auto_heading_styles = parse_heading_styles(&content_xml);
+    paragraph_langs.extend(parse_paragraph_languages(&content_xml));
     let all_heading_styles: Vec<String> = heading_styles
         .into_iter()
         .chain(auto_heading_styles)
         .collect();
 
-    // Extract images from Pictures/ directory
-    let image_files: Vec<String> = (0..archive.len())
+    // Extract images from Pictures/ directory, in natural order
+    let mut image_files: Vec<String> = (0..archive.len())
         .filter_map(|i| {
             let file = archive.by_index(i).ok()?;
             let name = file.name().to_string();
@@ -75,6 +75,7 @@ fn parse_odt(path: &Path) -> Result<BookDocument> {
             }
         })
         .collect();
+    convert_utils::sort::natural_sort(&mut image_files);
 
     for img_name in &image_files {
         if let Ok(data) = read_zip_binary(&mut archive, img_name) {
@@ -95,7 +96,13 @@ fn parse_odt(path: &Path) -> Result<BookDocument> {
     }
 
     // Convert content.xml to HTML
-    let html = convert_content_xml(&content_xml, &all_heading_styles);
+    let column_widths = parse_column_widths(&content_xml);
+    let html = convert_content_xml(
+        &content_xml,
+        &all_heading_styles,
+        &column_widths,
+        &paragraph_langs,
+    );
 
     // Set title from filename if not in metadata
     if book.metadata.title().is_none() {
@@ -231,8 +238,161 @@ fn parse_heading_styles(xml: &str) -> Vec<String> {
     styles
 }
 
+/// Map `table-column` style names to their widths.
+///
+/// Relative widths (`style:rel-column-width="3000*"`) are preferred; absolute
+/// ones (`style:column-width="2.5in"`) are converted to points. Either way only
+/// the proportions matter.
+fn parse_column_widths(xml: &str) -> HashMap<String, f64> {
+    let mut widths = HashMap::new();
+    let mut reader = Reader::from_str(xml);
+    reader.config_mut().trim_text(true);
+    let mut buf = Vec::new();
+    let mut current_style: Option<String> = None;
+
+    loop {
+        match reader.read_event_into(&mut buf) {
+            Ok(Event::Start(ref e)) | Ok(Event::Empty(ref e)) => {
+                let name = String::from_utf8_lossy(e.name().as_ref()).to_string();
+                if name == "style:style" {
+                    let mut style_name = None;
+                    let mut is_column = false;
+                    for attr in e.attributes().flatten() {
+                        let key = String::from_utf8_lossy(attr.key.as_ref()).to_string();
+                        let val = String::from_utf8_lossy(&attr.value).to_string();
+                        match key.as_str() {
+                            "style:name" => style_name = Some(val),
+                            "style:family" => is_column = val == "table-column",
+                            _ => {}
+                        }
+                    }
+                    current_style = if is_column { style_name } else { None };
+                } else if name == "style:table-column-properties" {
+                    let Some(style_name) = current_style.clone() else {
+                        continue;
+                    };
+                    let mut relative = None;
+                    let mut absolute = None;
+                    for attr in e.attributes().flatten() {
+                        let key = String::from_utf8_lossy(attr.key.as_ref()).to_string();
+                        let val = String::from_utf8_lossy(&attr.value).to_string();
+                        match key.as_str() {
+                            "style:rel-column-width" => {
+                                relative = val.trim_end_matches('*').parse::<f64>().ok()
+                            }
+                            "style:column-width" => absolute = length_to_points(&val),
+                            _ => {}
+                        }
+                    }
+                    if let Some(width) = relative.or(absolute) {
+                        widths.insert(style_name, width);
+                    }
+                }
+            }
+            Ok(Event::End(ref e)) if e.name().as_ref() == b"style:style" => {
+                current_style = None;
+            }
+            Ok(Event::Eof) => break,
+            Err(_) => break,
+            _ => {}
+        }
+        buf.clear();
+    }
+
+    widths
+}
+
+/// Map paragraph style names to the language their text properties set,
+/// e.g. `fo:language="fr" fo:country="FR"` becomes `fr-FR`.
+fn parse_paragraph_languages(xml: &str) -> HashMap<String, String> {
+    let mut langs = HashMap::new();
+    let mut reader = Reader::from_str(xml);
+    reader.config_mut().trim_text(true);
+    let mut buf = Vec::new();
+    let mut current_style: Option<String> = None;
+
+    loop {
+        match reader.read_event_into(&mut buf) {
+            Ok(Event::Start(ref e)) | Ok(Event::Empty(ref e)) => {
+                let name = String::from_utf8_lossy(e.name().as_ref()).to_string();
+                if name == "style:style" {
+                    let mut style_name = None;
+                    let mut is_paragraph = false;
+                    for attr in e.attributes().flatten() {
+                        let key = String::from_utf8_lossy(attr.key.as_ref()).to_string();
+                        let val = String::from_utf8_lossy(&attr.value).to_string();
+                        match key.as_str() {
+                            "style:name" => style_name = Some(val),
+                            "style:family" => is_paragraph = val == "paragraph",
+                            _ => {}
+                        }
+                    }
+                    current_style = if is_paragraph { style_name } else { None };
+                } else if name == "style:text-properties" {
+                    let Some(style_name) = current_style.clone() else {
+                        continue;
+                    };
+                    let mut language = None;
+                    let mut country = None;
+                    for attr in e.attributes().flatten() {
+                        let key = String::from_utf8_lossy(attr.key.as_ref()).to_string();
+                        let val = String::from_utf8_lossy(&attr.value).to_string();
+                        match key.as_str() {
+                            "fo:language" => language = Some(val),
+                            "fo:country" => country = Some(val),
+                            _ => {}
+                        }
+                    }
+                    // "zxx" and "none" mean "no linguistic content"
+                    let Some(language) = language.filter(|l| l != "zxx" && l != "none") else {
+                        continue;
+                    };
+                    let lang = match country.filter(|c| c != "none") {
+                        Some(country) => format!("{}-{}", language, country),
+                        None => language,
+                    };
+                    langs.insert(style_name, lang);
+                }
+            }
+            Ok(Event::End(ref e)) if e.name().as_ref() == b"style:style" => {
+                current_style = None;
+            }
+            Ok(Event::Eof) => break,
+            Err(_) => break,
+            _ => {}
+        }
+        buf.clear();
+    }
+
+    langs
+}
+
+/// Convert an ODF length such as `2.5in` or `4.2cm` to points.
+fn length_to_points(value: &str) -> Option<f64> {
+    let split = value
+        .find(|c: char| c.is_ascii_alphabetic())
+        .unwrap_or(value.len());
+    let (number, unit) = value.split_at(split);
+    let number: f64 = number.trim().parse().ok()?;
+    let factor = match unit {
+        "in" => 72.0,
+        "cm" => 72.0 / 2.54,
+        "mm" => 72.0 / 25.4,
+        "pc" => 12.0,
+        "px" => 0.75,
+        "pt" | "" => 1.0,
+        _ => return None,
+    };
+    Some(number * factor)
+}
+
 /// Convert content.xml to HTML.
-fn convert_content_xml(xml: &str, heading_styles: &[String]) -> String {
+fn convert_content_xml(
+    xml: &str,
+    heading_styles: &[String],
+    column_widths: &HashMap<String, f64>,
+    paragraph_langs: &HashMap<String, String>,
+) -> String {
     let mut reader = Reader::from_str(xml);
     reader.config_mut().trim_text(false);
 
@@ -241,10 +401,15 @@ fn convert_content_xml(xml: &str, heading_styles: &[String]) -> String {
     let mut in_text_body = false;
     let mut in_para = false;
     let mut current_tag = String::new(); // "p" or "h1"-"h6"
+    let mut current_lang = String::new();
     let mut para_buf = String::new();
+    // Ids of the bookmarks in the current paragraph
+    let mut para_ids: Vec<String> = Vec::new();
     let mut span_stack: Vec<SpanFormat> = Vec::new();
     let mut in_list = false;
     let mut list_depth = 0;
+    // Column widths of the current table, emitted before its first row
+    let mut table_columns: Vec<f64> = Vec::new();
 
     loop {
         match reader.read_event_into(&mut buf) {
@@ -286,8 +451,13 @@ fn convert_content_xml(xml: &str, heading_styles: &[String]) -> String {
                         } else {
                             current_tag = "p".to_string();
                         }
+                        current_lang = paragraph_langs
+                            .get(&style_name)
+                            .cloned()
+                            .unwrap_or_default();
                         in_para = true;
                         para_buf.clear();
+                        para_ids.clear();
                     }
                     "text:span" if in_para => {
                         // Check for bold/italic in style
@@ -330,16 +500,27 @@ fn convert_content_xml(xml: &str, heading_styles: &[String]) -> String {
                             para_buf.push_str("<ul>\n");
                         }
                     }
-                    "text:list-item" if in_list => {
-                        if list_depth == 1 && !in_para {
-                            html.push_str("<li>");
+                    "text:list-item" if in_list && list_depth == 1 && !in_para => {
+                        html.push_str("<li>");
+                    }
+                    "text:list-item" if in_list => {}
+                    "text:bookmark" | "text:bookmark-start" if in_para => {
+                        for attr in e.attributes().flatten() {
+                            if attr.key.as_ref() == b"text:name" {
+                                let name = String::from_utf8_lossy(&attr.value);
+                                para_ids.push(bookmark_id(&name));
+                            }
                         }
                     }
                     "text:a" if in_para => {
                         for attr in e.attributes().flatten() {
                             let key = String::from_utf8_lossy(attr.key.as_ref()).to_string();
                             if key == "xlink:href" {
-                                let href = String::from_utf8_lossy(&attr.value).to_string();
+                                let mut href = String::from_utf8_lossy(&attr.value).to_string();
+                                // Internal links point at bookmarks, which become ids
+                                if let Some(name) = href.strip_prefix('#') {
+                                    href = format!("#{}", bookmark_id(name));
+                                }
                                 para_buf.push_str(&format!(
                                     r#"<a href="{}">"#,
                                     convert_utils::xml::escape_xml_attr(&href)
@@ -362,8 +543,31 @@ fn convert_content_xml(xml: &str, heading_styles: &[String]) -> String {
                     }
                     "table:table" if in_text_body => {
                         html.push_str("<table>\n");
+                        table_columns.clear();
+                    }
+                    "table:table-column" => {
+                        let mut width = 0.0;
+                        let mut repeat = 1usize;
+                        for attr in e.attributes().flatten() {
+                            let key = String::from_utf8_lossy(attr.key.as_ref()).to_string();
+                            let val = String::from_utf8_lossy(&attr.value).to_string();
+                            match key.as_str() {
+                                "table:style-name" => {
+                                    width = column_widths.get(&val).copied().unwrap_or(0.0)
+                                }
+                                "table:number-columns-repeated" => {
+                                    repeat = val.parse().unwrap_or(1)
+                                }
+                                _ => {}
+                            }
+                        }
+                        table_columns.extend(std::iter::repeat_n(width, repeat.min(1024)));
                     }
                     "table:table-row" => {
+                        if let Some(colgroup) = convert_utils::xml::colgroup(&table_columns) {
+                            html.push_str(&colgroup);
+                        }
+                        table_columns.clear();
                         html.push_str("<tr>");
                     }
                     "table:table-cell" => {
@@ -379,9 +583,23 @@ fn convert_content_xml(xml: &str, heading_styles: &[String]) -> String {
                         in_text_body = false;
                     }
                     "text:p" | "text:h" if in_para => {
+                        // The first bookmark names the element, any others
+                        // become empty anchors inside it
+                        let mut ids = para_ids.drain(..);
+                        let id_attr = ids
+                            .next()
+                            .map(|id| format!(r#" id="{}""#, id))
+                            .unwrap_or_default();
+                        let anchors: String =
+                            ids.map(|id| format!(r#"<a id="{}"></a>"#, id)).collect();
                         html.push_str(&format!(
-                            "<{}>{}</{}>\n",
-                            current_tag, para_buf, current_tag
+                            "<{}{}{}>{}{}</{}>\n",
+                            current_tag,
+                            id_attr,
+                            convert_utils::xml::lang_attrs(&current_lang),
+                            anchors,
+                            para_buf,
+                            current_tag
                         ));
                         in_para = false;
                         para_buf.clear();
@@ -410,11 +628,9 @@ fn convert_content_xml(xml: &str, heading_styles: &[String]) -> String {
                     _ => {}
                 }
             }
-            Ok(Event::Text(ref e)) => {
-                if in_para {
-                    let text = e.unescape().unwrap_or_default().to_string();
-                    para_buf.push_str(&convert_utils::xml::escape_xml_text(&text));
-                }
+            Ok(Event::Text(ref e)) if in_para => {
+                let text = e.unescape().unwrap_or_default().to_string();
+                para_buf.push_str(&convert_utils::xml::escape_xml_text(&text));
             }
             Ok(Event::Eof) => break,
             Err(_) => break,
@@ -426,6 +642,25 @@ fn convert_content_xml(xml: &str, heading_styles: &[String]) -> String {
     html
 }
 
+/// XML id for an ODT bookmark name: characters not allowed in an id become
+/// `_`, and a name not starting with a letter or `_` is prefixed with `_`.
+fn bookmark_id(name: &str) -> String {
+    let mut id: String = name
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || matches!(c, '_' | '-' | '.') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    if !id.starts_with(|c: char| c.is_alphabetic() || c == '_') {
+        id.insert(0, '_');
+    }
+    id
+}
+
 #[derive(Default)]
 struct SpanFormat {
     bold: bool,
@@ -476,7 +711,6 @@ fn extract_heading_level(style_name: &str) -> Option<u8> {
 
 /// Build TOC from heading elements in HTML.
 fn build_toc(html: &str, book: &mut BookDocument) {
-    let tag_re = Regex::new(r"<[^>]+>").unwrap();
     let patterns = [
         Regex::new(r"(?i)<h1[^>]*>(.*?)</h1>").unwrap(),
         Regex::new(r"(?i)<h2[^>]*>(.*?)</h2>").unwrap(),
@@ -487,8 +721,7 @@ fn build_toc(html: &str, book: &mut BookDocument) {
     for pattern in &patterns {
         for cap in pattern.captures_iter(html) {
             let pos = cap.get(0).unwrap().start();
-            let text = tag_re.replace_all(&cap[1], "").trim().to_string();
-            if !text.is_empty() {
+            if let Some(text) = convert_utils::xml::heading_text(&cap[1]) {
                 headings.push((pos, text));
             }
         }
@@ -513,10 +746,10 @@ fn read_zip_string(
     let mut file = archive
         .by_name(name)
         .map_err(|e| format!("{}: {}", name, e))?;
-    let mut s = String::new();
-    file.read_to_string(&mut s)
+    let mut bytes = Vec::new();
+    file.read_to_end(&mut bytes)
         .map_err(|e| format!("{}: {}", name, e))?;
-    Ok(s)
+    convert_utils::encoding::decode_text(&bytes).map_err(|e| format!("{}: {}", name, e))
 }
 
 fn read_zip_binary(
@@ -584,12 +817,32 @@ mod tests {
   </office:body>
 </office:document-content>"#;
 
-        let html = convert_content_xml(xml, &[]);
+        let html = convert_content_xml(xml, &[], &HashMap::new(), &HashMap::new());
         assert!(html.contains("<h1>Chapter One</h1>"));
         assert!(html.contains("<p>First paragraph of text.</p>"));
         assert!(html.contains("<p>Second paragraph.</p>"));
     }
 
+    #[test]
+    fn test_internal_link_to_bookmark() {
+        let xml = r##"<?xml version="1.0"?>
+<office:document-content xmlns:office="urn:oasis:names:tc:opendocument:xmlns:office:1.0" xmlns:text="urn:oasis:names:tc:opendocument:xmlns:text:1.0" xmlns:xlink="http://www.w3.org/1999/xlink">
+  <office:body>
+    <office:text>
+      <text:p>See <text:a xlink:type="simple" xlink:href="#__RefHeading__123_456">the chapter</text:a> and <text:a xlink:href="#2 notes">notes</text:a>.</text:p>
+      <text:h text:outline-level="1"><text:bookmark-start text:name="__RefHeading__123_456"/>Chapter One<text:bookmark-end text:name="__RefHeading__123_456"/></text:h>
+      <text:p><text:bookmark text:name="2 notes"/>Some notes.</text:p>
+    </office:text>
+  </office:body>
+</office:document-content>"##;
+
+        let html = convert_content_xml(xml, &[], &HashMap::new(), &HashMap::new());
+        assert!(html.contains(r##"<a href="#__RefHeading__123_456">the chapter</a>"##));
+        assert!(html.contains(r#"<h1 id="__RefHeading__123_456">Chapter One</h1>"#));
+        assert!(html.contains(r##"<a href="#_2_notes">notes</a>"##));
+        assert!(html.contains(r#"<p id="_2_notes">Some notes.</p>"#));
+    }
+
     #[test]
     fn test_convert_content_xml_with_list() {
         let xml = r#"<?xml version="1.0"?>
@@ -609,12 +862,35 @@ mod tests {
   </office:body>
 </office:document-content>"#;
 
-        let html = convert_content_xml(xml, &[]);
+        let html = convert_content_xml(xml, &[], &HashMap::new(), &HashMap::new());
         assert!(html.contains("<ul>"));
         assert!(html.contains("Item 1"));
         assert!(html.contains("Item 2"));
     }
 
+    #[test]
+    fn test_convert_content_xml_nested_list_balanced() {
+        let xml = r#"<?xml version="1.0"?>
+<office:document-content xmlns:office="urn:oasis:names:tc:opendocument:xmlns:office:1.0" xmlns:text="urn:oasis:names:tc:opendocument:xmlns:text:1.0">
+  <office:body>
+    <office:text>
+      <text:list>
+        <text:list-item>
+          <text:p>Outer</text:p>
+          <text:list>
+            <text:list-item><text:p>Inner</text:p></text:list-item>
+          </text:list>
+        </text:list-item>
+      </text:list>
+    </office:text>
+  </office:body>
+</office:document-content>"#;
+
+        let html = convert_content_xml(xml, &[], &HashMap::new(), &HashMap::new());
+        assert!(html.contains("Outer") && html.contains("Inner"));
+        assert_eq!(html.matches("<li>").count(), html.matches("</li>").count());
+    }
+
     #[test]
     fn test_build_toc() {
         let html = "<h1>Title</h1><p>text</p><h2>Section</h2>";
@@ -624,4 +900,61 @@ mod tests {
         assert_eq!(book.toc.entries.len(), 2);
         assert_eq!(book.toc.entries[0].title, "Title");
     }
+
+    #[test]
+    fn test_table_column_widths() {
+        let xml = r#"<office:document-content>
+<office:automatic-styles>
+  <style:style style:name="Table1.A" style:family="table-column">
+    <style:table-column-properties style:column-width="4.5in"/>
+  </style:style>
+  <style:style style:name="Table1.B" style:family="table-column">
+    <style:table-column-properties style:column-width="1.5in"/>
+  </style:style>
+</office:automatic-styles>
+<office:body><office:text>
+<table:table table:name="Table1">
+  <table:table-column table:style-name="Table1.A"/>
+  <table:table-column table:style-name="Table1.B" table:number-columns-repeated="2"/>
+  <table:table-row>
+    <table:table-cell><text:p>Wide</text:p></table:table-cell>
+    <table:table-cell><text:p>Narrow</text:p></table:table-cell>
+    <table:table-cell><text:p>Narrow</text:p></table:table-cell>
+  </table:table-row>
+</table:table>
+</office:text></office:body>
+</office:document-content>"#;
+
+        let widths = parse_column_widths(xml);
+        assert_eq!(widths["Table1.A"], 324.0);
+        let html = convert_content_xml(xml, &[], &widths, &HashMap::new());
+        assert!(html.contains(
+            r#"<colgroup><col style="width: 60.0%"/><col style="width: 20.0%"/><col style="width: 20.0%"/></colgroup>"#
+        ));
+    }
+
+    #[test]
+    fn test_paragraph_language() {
+        let xml = r#"<office:document-content>
+<office:automatic-styles>
+  <style:style style:name="P1" style:family="paragraph" style:parent-style-name="Standard">
+    <style:text-properties fo:language="fr" fo:country="FR"/>
+  </style:style>
+  <style:style style:name="T1" style:family="text">
+    <style:text-properties fo:language="de" fo:country="DE"/>
+  </style:style>
+</office:automatic-styles>
+<office:body><office:text>
+  <text:p text:style-name="P1">Bonjour</text:p>
+  <text:p text:style-name="Standard">Hello</text:p>
+</office:text></office:body>
+</office:document-content>"#;
+
+        let langs = parse_paragraph_languages(xml);
+        assert_eq!(langs.get("P1").map(String::as_str), Some("fr-FR"));
+        assert!(!langs.contains_key("T1"));
+        let html = convert_content_xml(xml, &[], &HashMap::new(), &langs);
+        assert!(html.contains(r#"<p lang="fr-FR" xml:lang="fr-FR">Bonjour</p>"#));
+        assert!(html.contains("<p>Hello</p>"));
+    }
 }
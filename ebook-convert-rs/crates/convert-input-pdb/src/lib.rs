@@ -0,0 +1,127 @@
+//! PDB/TCR input plugin — reads PalmDOC text databases and TCR files into
+//! BookDocument.
+//!
+//! Both formats hold nothing but compressed plain text, which is decoded and
+//! split into paragraphs the same way the TXT input does.
+
+mod palmdoc;
+mod tcr;
+
+use std::path::Path;
+
+use convert_core::book::{BookDocument, EbookFormat};
+use convert_core::error::{ConvertError, Result};
+use convert_core::options::ConversionOptions;
+use convert_core::plugin::InputPlugin;
+
+pub struct PdbInputPlugin;
+
+impl InputPlugin for PdbInputPlugin {
+    fn name(&self) -> &str {
+        "PDB Input"
+    }
+
+    fn supported_formats(&self) -> &[EbookFormat] {
+        &[EbookFormat::Pdb, EbookFormat::Tcr]
+    }
+
+    fn convert(&self, input_path: &Path, options: &ConversionOptions) -> Result<BookDocument> {
+        log::info!("Reading PDB: {}", input_path.display());
+
+        let data = std::fs::read(input_path).map_err(|e| ConvertError::io(input_path, e))?;
+
+        let fallback_title = input_path
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .unwrap_or("Untitled")
+            .to_string();
+
+        let (name, text) = if data.starts_with(tcr::MAGIC) {
+            (None, tcr::decompress(&data)?)
+        } else {
+            let doc = palmdoc::parse(&data)?;
+            (Some(doc.name), doc.text)
+        };
+        let title = name
+            .filter(|n| !n.trim().is_empty())
+            .unwrap_or(fallback_title);
+
+        let (text, encoding) = convert_utils::encoding::decode_to_utf8(&text);
+        log::debug!("Decoded {} bytes of {} text", text.len(), encoding);
+
+        let mut book = convert_input_txt::plaintext_book(&title, &text, options);
+        book.base_path = input_path.parent().map(|p| p.to_path_buf());
+        Ok(book)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// An uncompressed PalmDOC database holding `text` in records of `record_size`.
+    fn build_palmdoc(name: &str, text: &[u8], record_size: usize) -> Vec<u8> {
+        let records: Vec<&[u8]> = text.chunks(record_size).collect();
+
+        let mut header = Vec::new();
+        header.extend_from_slice(&1u16.to_be_bytes()); // compression: none
+        header.extend_from_slice(&0u16.to_be_bytes());
+        header.extend_from_slice(&(text.len() as u32).to_be_bytes());
+        header.extend_from_slice(&(records.len() as u16).to_be_bytes());
+        header.extend_from_slice(&(record_size as u16).to_be_bytes());
+        header.extend_from_slice(&0u32.to_be_bytes());
+
+        let mut pdb = vec![0u8; 78];
+        pdb[..name.len()].copy_from_slice(name.as_bytes());
+        pdb[60..68].copy_from_slice(b"TEXtREAd");
+        pdb[76..78].copy_from_slice(&(records.len() as u16 + 1).to_be_bytes());
+
+        let mut offset = 78 + (records.len() + 1) * 8 + 2;
+        for (i, record) in std::iter::once(header.as_slice())
+            .chain(records.iter().copied())
+            .enumerate()
+        {
+            pdb.extend_from_slice(&(offset as u32).to_be_bytes());
+            pdb.extend_from_slice(&(i as u32).to_be_bytes());
+            offset += record.len();
+        }
+        pdb.extend_from_slice(&[0, 0]);
+        pdb.extend_from_slice(&header);
+        for record in records {
+            pdb.extend_from_slice(record);
+        }
+        pdb
+    }
+
+    #[test]
+    fn test_uncompressed_palmdoc_text_recovered() {
+        let text = "The first paragraph, long enough to span records.\n\nThe second & last one.";
+        let pdb = build_palmdoc("Palm Tales", text.as_bytes(), 16);
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("tales.pdb");
+        std::fs::write(&path, &pdb).unwrap();
+
+        let book = PdbInputPlugin
+            .convert(&path, &ConversionOptions::default())
+            .unwrap();
+        assert_eq!(book.metadata.title(), Some("Palm Tales"));
+        let xhtml = book
+            .manifest
+            .by_id("content")
+            .unwrap()
+            .data
+            .as_xhtml()
+            .unwrap();
+        assert!(xhtml.contains("<p>The first paragraph, long enough to span records.</p>"));
+        assert!(xhtml.contains("<p>The second &amp; last one.</p>"));
+    }
+
+    #[test]
+    fn test_mobi_database_rejected() {
+        let mut pdb = build_palmdoc("Book", b"text", 4096);
+        pdb[60..68].copy_from_slice(b"BOOKMOBI");
+        let err = palmdoc::parse(&pdb).err().unwrap();
+        assert!(err.to_string().contains("MOBI"), "{}", err);
+    }
+}
@@ -0,0 +1,166 @@
+//! PalmDOC text databases (`TEXtREAd`).
+//!
+//! A PDB file is a 78-byte header, a list of 8-byte record entries giving
+//! each record's offset, then the records themselves. Record 0 of a PalmDOC
+//! database describes the text, which follows in records 1..=N, each
+//! optionally compressed with PalmDOC's LZ77 variant.
+
+use convert_core::error::{ConvertError, Result};
+
+/// Size of the PDB header preceding the record list.
+const PDB_HEADER_SIZE: usize = 78;
+
+/// Compression value for uncompressed text records.
+const NO_COMPRESSION: u16 = 1;
+
+/// Compression value for PalmDOC LZ77 records.
+const PALMDOC_COMPRESSION: u16 = 2;
+
+/// Text recovered from a PalmDOC database.
+pub(crate) struct PalmDoc {
+    /// Database name from the PDB header, usually the book title.
+    pub name: String,
+    /// The decompressed text, in the file's original encoding.
+    pub text: Vec<u8>,
+}
+
+/// Parse a PalmDOC database and decompress its text records.
+pub(crate) fn parse(data: &[u8]) -> Result<PalmDoc> {
+    if data.len() < PDB_HEADER_SIZE {
+        return Err(ConvertError::Pdb("File too short for a PDB header".into()));
+    }
+
+    let type_creator = &data[60..68];
+    match type_creator {
+        b"TEXtREAd" => {}
+        b"BOOKMOBI" => {
+            return Err(ConvertError::Pdb(
+                "This is a MOBI book; convert it as .mobi".into(),
+            ))
+        }
+        other => {
+            return Err(ConvertError::Pdb(format!(
+                "Unsupported PDB type '{}'",
+                String::from_utf8_lossy(other)
+            )))
+        }
+    }
+
+    let name_end = data[..32].iter().position(|&b| b == 0).unwrap_or(32);
+    let name = String::from_utf8_lossy(&data[..name_end])
+        .trim()
+        .to_string();
+
+    let records = record_ranges(data)?;
+    let header = records
+        .first()
+        .map(|r| &data[r.clone()])
+        .filter(|h| h.len() >= 10)
+        .ok_or_else(|| ConvertError::Pdb("Missing PalmDOC header record".into()))?;
+    let compression = u16::from_be_bytes([header[0], header[1]]);
+    let text_length = u32::from_be_bytes([header[4], header[5], header[6], header[7]]) as usize;
+    let text_records = u16::from_be_bytes([header[8], header[9]]) as usize;
+
+    let mut text = Vec::with_capacity(text_length);
+    for range in records.iter().skip(1).take(text_records) {
+        let record = &data[range.clone()];
+        match compression {
+            NO_COMPRESSION => text.extend_from_slice(record),
+            PALMDOC_COMPRESSION => decompress(record, &mut text),
+            other => {
+                return Err(ConvertError::Pdb(format!(
+                    "Unsupported PalmDOC compression {}",
+                    other
+                )))
+            }
+        }
+    }
+    if text.len() > text_length {
+        text.truncate(text_length);
+    }
+
+    Ok(PalmDoc { name, text })
+}
+
+/// Byte ranges of each record, from the record list after the header.
+fn record_ranges(data: &[u8]) -> Result<Vec<std::ops::Range<usize>>> {
+    let count = u16::from_be_bytes([data[76], data[77]]) as usize;
+    let list_end = PDB_HEADER_SIZE + count * 8;
+    if data.len() < list_end {
+        return Err(ConvertError::Pdb("Truncated PDB record list".into()));
+    }
+
+    let offsets: Vec<usize> = data[PDB_HEADER_SIZE..list_end]
+        .chunks(8)
+        .map(|entry| u32::from_be_bytes([entry[0], entry[1], entry[2], entry[3]]) as usize)
+        .collect();
+    offsets
+        .iter()
+        .enumerate()
+        .map(|(i, &start)| {
+            let end = offsets.get(i + 1).copied().unwrap_or(data.len());
+            if start < list_end || start > end || end > data.len() {
+                return Err(ConvertError::Pdb(format!("Record {} is out of bounds", i)));
+            }
+            Ok(start..end)
+        })
+        .collect()
+}
+
+/// Decompress one PalmDOC LZ77 record, appending to `out`. Back-references
+/// only reach within the record, so each record is decoded independently.
+fn decompress(record: &[u8], out: &mut Vec<u8>) {
+    let start = out.len();
+    let mut i = 0;
+    while i < record.len() {
+        let byte = record[i];
+        i += 1;
+        match byte {
+            // The next 1-8 bytes are literals
+            0x01..=0x08 => {
+                let end = (i + byte as usize).min(record.len());
+                out.extend_from_slice(&record[i..end]);
+                i = end;
+            }
+            // Two-byte back-reference: 11-bit distance, 3-bit length - 3
+            0x80..=0xBF => {
+                let Some(&next) = record.get(i) else { break };
+                i += 1;
+                let pair = u16::from_be_bytes([byte, next]);
+                let distance = ((pair >> 3) & 0x07FF) as usize;
+                let length = (pair & 0x07) as usize + 3;
+                if distance == 0 || distance > out.len() - start {
+                    continue;
+                }
+                for _ in 0..length {
+                    out.push(out[out.len() - distance]);
+                }
+            }
+            // A space followed by a character
+            0xC0..=0xFF => {
+                out.push(b' ');
+                out.push(byte ^ 0x80);
+            }
+            _ => out.push(byte),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decompress_palmdoc_record() {
+        // "abc" literally, a back-reference copying "abcabc", a literal run
+        // holding 0x80, and " x" encoded as a single byte
+        let pair = (3u16 << 3) | (6 - 3);
+        let mut record = b"abc".to_vec();
+        record.extend_from_slice(&(0x8000 | pair).to_be_bytes());
+        record.extend_from_slice(&[0x02, 0x80, b'!', b'x' ^ 0x80]);
+
+        let mut out = Vec::new();
+        decompress(&record, &mut out);
+        assert_eq!(out, b"abcabcabc\x80! x");
+    }
+}
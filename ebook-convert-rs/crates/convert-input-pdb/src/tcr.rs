@@ -0,0 +1,51 @@
+//! TCR compressed text (Psion).
+//!
+//! After the `!!8-Bit!!` signature come 256 dictionary entries, each a length
+//! byte and that many bytes of text. Every following byte is an index into
+//! the dictionary.
+
+use convert_core::error::{ConvertError, Result};
+
+/// Signature at the start of every TCR file.
+pub(crate) const MAGIC: &[u8] = b"!!8-Bit!!";
+
+/// Expand a TCR file into its text, in the file's original encoding.
+pub(crate) fn decompress(data: &[u8]) -> Result<Vec<u8>> {
+    let truncated = || ConvertError::Pdb("Truncated TCR dictionary".into());
+
+    let mut pos = MAGIC.len();
+    let mut dictionary: Vec<&[u8]> = Vec::with_capacity(256);
+    for _ in 0..256 {
+        let len = *data.get(pos).ok_or_else(truncated)? as usize;
+        let entry = data.get(pos + 1..pos + 1 + len).ok_or_else(truncated)?;
+        dictionary.push(entry);
+        pos += 1 + len;
+    }
+
+    let mut text = Vec::with_capacity((data.len() - pos) * 2);
+    for &code in &data[pos..] {
+        text.extend_from_slice(dictionary[code as usize]);
+    }
+    Ok(text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decompress_tcr() {
+        let mut data = MAGIC.to_vec();
+        for code in 0..=255u8 {
+            // Entry 0 is a word, the rest map to single bytes
+            match code {
+                0 => data.extend_from_slice(b"\x05Hello"),
+                _ => data.extend_from_slice(&[1, code]),
+            }
+        }
+        data.extend_from_slice(&[0, b',', b' ', b'w', b'o', b'r', b'l', b'd']);
+        assert_eq!(decompress(&data).unwrap(), b"Hello, world");
+
+        assert!(decompress(&data[..20]).is_err());
+    }
+}
@@ -1,15 +1,17 @@
 //! Hybrid PDF extraction orchestrator.
 //!
 //! Uses `pdftohtml -xml` as primary extraction for text-based pages,
-//! falling back to `pdftoppm` for scanned/composited pages.
+//! falling back to `pdftoppm` for scanned/composited pages. Both tools apply
+//! each page's `/Rotate`, so rendered images and text positions already
+//! follow the displayed orientation.
 
 use std::collections::HashMap;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 use lopdf::Document;
 use rayon::prelude::*;
 
-use convert_core::book::{BookDocument, ManifestData, ManifestItem, Metadata, TocEntry};
+use convert_core::book::{BookDocument, GuideRef, ManifestData, ManifestItem, Metadata, TocEntry};
 use convert_core::error::{ConvertError, Result};
 use convert_core::options::{ConversionOptions, PdfEngine};
 
@@ -21,12 +23,29 @@ use crate::toc;
 
 /// Extract text and images from a PDF file into a BookDocument.
 pub fn extract_pdf(path: &Path, options: &ConversionOptions) -> Result<BookDocument> {
-    let doc = Document::load(path)
-        .map_err(|e| ConvertError::Pdf(format!("Failed to load PDF: {}", e)))?;
+    let mut doc = load_document(path, options)?;
 
     let mut book = BookDocument::new();
     book.base_path = path.parent().map(|p| p.to_path_buf());
 
+    // Poppler tools read a decrypted copy, so the password never appears on
+    // a command line where other users could see it
+    let decrypted = match options.pdf_password {
+        Some(_) => Some(write_decrypted_copy(&mut doc)?),
+        None => None,
+    };
+    let unlocked_options;
+    let (path, options) = match &decrypted {
+        Some((_dir, copy)) => {
+            unlocked_options = ConversionOptions {
+                pdf_password: None,
+                ..options.clone()
+            };
+            (copy.as_path(), &unlocked_options)
+        }
+        None => (path, options),
+    };
+
     // Extract metadata from PDF info dictionary
     extract_metadata(&doc, &mut book.metadata);
 
@@ -38,6 +57,11 @@ pub fn extract_pdf(path: &Path, options: &ConversionOptions) -> Result<BookDocum
 
     log::info!("PDF has {} pages", num_pages);
 
+    if let Some(spec) = options.pdf_pages.as_deref() {
+        page_numbers = parse_page_ranges(spec, num_pages)?;
+        log::info!("Converting {} of {} pages", page_numbers.len(), num_pages);
+    }
+
     match options.pdf_engine {
         PdfEngine::ImageOnly => {
             extract_image_only(path, &page_numbers, num_pages, options, &mut book)?;
@@ -57,18 +81,157 @@ pub fn extract_pdf(path: &Path, options: &ConversionOptions) -> Result<BookDocum
     );
     book.manifest.add(css_item);
 
+    if let Some(cover_page) = options.cover_from_page {
+        add_page_cover(path, cover_page, num_pages, options, &mut book)?;
+    } else if options.pdf_first_page_cover {
+        match page_numbers.first() {
+            Some(&first) => {
+                if !use_page_image_as_cover(first, &mut book) {
+                    add_page_cover(path, first, num_pages, options, &mut book)?;
+                }
+            }
+            None => log::warn!("PDF has no pages; not adding a first-page cover"),
+        }
+    }
+
     Ok(book)
 }
 
-/// Image-only extraction: render all pages with pdftoppm (legacy behavior).
+/// Parse a page selection such as `"3-4,7"` into sorted, deduplicated
+/// 1-based page numbers, checking them against `num_pages`.
+fn parse_page_ranges(spec: &str, num_pages: u32) -> Result<Vec<u32>> {
+    let invalid = || ConvertError::Pdf(format!("Invalid page range '{}'", spec));
+    let mut pages = Vec::new();
+    for part in spec.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+        let (first, last) = match part.split_once('-') {
+            Some((first, last)) => (first.trim(), last.trim()),
+            None => (part, part),
+        };
+        let first: u32 = first.parse().map_err(|_| invalid())?;
+        let last: u32 = last.parse().map_err(|_| invalid())?;
+        if first == 0 || first > last {
+            return Err(invalid());
+        }
+        if last > num_pages {
+            return Err(ConvertError::Pdf(format!(
+                "Page range '{}' is past the end of the PDF ({} pages)",
+                part, num_pages
+            )));
+        }
+        pages.extend(first..=last);
+    }
+    if pages.is_empty() {
+        return Err(invalid());
+    }
+    pages.sort_unstable();
+    pages.dedup();
+    Ok(pages)
+}
+
+/// Render `page_num` with pdftoppm and make it the book's cover image.
+fn add_page_cover(
+    pdf_path: &Path,
+    page_num: u32,
+    num_pages: u32,
+    options: &ConversionOptions,
+    book: &mut BookDocument,
+) -> Result<()> {
+    if page_num == 0 || page_num > num_pages {
+        return Err(ConvertError::Pdf(format!(
+            "Cover page {} is outside the PDF ({} pages)",
+            page_num, num_pages
+        )));
+    }
+
+    let jpeg_data = render::render_page_ranges(pdf_path, &[page_num], num_pages, options)?
+        .remove(&page_num)
+        .filter(|data| !data.is_empty())
+        .ok_or_else(|| ConvertError::Pdf(format!("Failed to render cover page {}", page_num)))?;
+
+    let href = "images/cover.jpg";
+    book.manifest.add(ManifestItem::new(
+        "cover_image",
+        href,
+        "image/jpeg",
+        ManifestData::Binary(jpeg_data),
+    ));
+    book.guide.add(GuideRef::new("cover", "Cover", href));
+    log::info!("Using page {} as the cover", page_num);
+    Ok(())
+}
+
+/// Point the guide `cover` at the image `page_num` was rendered to, if it was
+/// rendered whole (image-only or scanned). Returns whether it was.
+fn use_page_image_as_cover(page_num: u32, book: &mut BookDocument) -> bool {
+    let rendered = [
+        format!("images/page{}.jpg", page_num),
+        format!("images/scan_page{}.jpg", page_num),
+    ];
+    let Some(href) = rendered
+        .into_iter()
+        .find(|href| book.manifest.by_href(href).is_some())
+    else {
+        return false;
+    };
+    book.guide.add(GuideRef::new("cover", "Cover", &href));
+    log::info!("Using the image of page {} as the cover", page_num);
+    true
+}
+
+/// Load a PDF with lopdf, decrypting it with `options.pdf_password` if set.
+///
+/// Encrypted files that cannot be opened with an empty user password are
+/// rejected up front with a clear error instead of failing later on garbage.
+fn load_document(path: &Path, options: &ConversionOptions) -> Result<Document> {
+    let doc = match options.pdf_password.as_deref() {
+        Some(password) => Document::load_with_password(path, password).map_err(|e| match e {
+            lopdf::Error::InvalidPassword => {
+                ConvertError::Pdf("Incorrect password for encrypted PDF".to_string())
+            }
+            e => ConvertError::Pdf(format!("Failed to load PDF: {}", e)),
+        })?,
+        None => Document::load(path)
+            .map_err(|e| ConvertError::Pdf(format!("Failed to load PDF: {}", e)))?,
+    };
+
+    // lopdf removes the Encrypt entry once the document has been decrypted
+    if doc.is_encrypted() {
+        return Err(ConvertError::Pdf(
+            "password required: PDF is encrypted, supply one with --pdf-password".to_string(),
+        ));
+    }
+
+    Ok(doc)
+}
+
+/// Save an already decrypted document to a temporary file for the poppler
+/// tools. The directory is removed when the returned guard is dropped.
+fn write_decrypted_copy(doc: &mut Document) -> Result<(tempfile::TempDir, PathBuf)> {
+    let dir = tempfile::TempDir::new()
+        .map_err(|e| ConvertError::Pdf(format!("Failed to create temp dir: {}", e)))?;
+    let copy = dir.path().join("decrypted.pdf");
+    doc.save(&copy)
+        .map_err(|e| ConvertError::Pdf(format!("Failed to write decrypted PDF: {}", e)))?;
+    Ok((dir, copy))
+}
+
+/// Image-only extraction: render the selected pages with pdftoppm (legacy behavior).
 fn extract_image_only(
     pdf_path: &Path,
-    _page_numbers: &[u32],
+    page_numbers: &[u32],
     num_pages: u32,
     options: &ConversionOptions,
     book: &mut BookDocument,
 ) -> Result<()> {
-    let rendered = render::render_all_pages(pdf_path, num_pages, options)?;
+    let rendered = if page_numbers.len() as u32 == num_pages {
+        render::render_all_pages(pdf_path, num_pages, options)?
+    } else {
+        let mut by_page = render::render_page_ranges(pdf_path, page_numbers, num_pages, options)?;
+        page_numbers
+            .iter()
+            .map(|n| (*n, by_page.remove(n).unwrap_or_default()))
+            .collect()
+    };
 
     log::info!(
         "Rendered {} page images ({} bytes total)",
@@ -77,8 +240,7 @@ fn extract_image_only(
     );
 
     // Extract text per page using lopdf (for searchability in image-only mode)
-    let doc = Document::load(pdf_path)
-        .map_err(|e| ConvertError::Pdf(format!("Failed to load PDF: {}", e)))?;
+    let doc = load_document(pdf_path, options)?;
 
     for (page_num, jpeg_data) in &rendered {
         let img_id = format!("img{}", page_num);
@@ -138,7 +300,11 @@ fn extract_hybrid(
     };
 
     let fonts = &pdftohtml_result.fonts;
-    let html_pages = &pdftohtml_result.pages;
+    let html_pages: Vec<&pdftohtml::PdfPage> = pdftohtml_result
+        .pages
+        .iter()
+        .filter(|page| page_numbers.binary_search(&page.number).is_ok())
+        .collect();
 
     // Step 2: Classify each page (parallel)
     let classifications: Vec<(u32, PageType)> = html_pages
@@ -607,4 +773,281 @@ mod tests {
         assert!(css.contains("font-family: serif"));
         assert!(css.contains(".page-image"));
     }
+
+    fn write_encrypted_pdf(path: &Path, user_password: &str) {
+        use lopdf::content::{Content, Operation};
+        use lopdf::{dictionary, EncryptionState, EncryptionVersion, Permissions, Stream};
+
+        let mut doc = Document::with_version("1.5");
+        let pages_id = doc.new_object_id();
+        let font_id = doc.add_object(dictionary! {
+            "Type" => "Font",
+            "Subtype" => "Type1",
+            "BaseFont" => "Helvetica",
+        });
+        let content = Content {
+            operations: vec![
+                Operation::new("BT", vec![]),
+                Operation::new("Tf", vec!["F1".into(), 12.into()]),
+                Operation::new("Td", vec![72.into(), 720.into()]),
+                Operation::new("Tj", vec![lopdf::Object::string_literal("Secret")]),
+                Operation::new("ET", vec![]),
+            ],
+        };
+        let content_id = doc.add_object(Stream::new(dictionary! {}, content.encode().unwrap()));
+        let page_id = doc.add_object(dictionary! {
+            "Type" => "Page",
+            "Parent" => pages_id,
+            "Contents" => content_id,
+            "Resources" => dictionary! { "Font" => dictionary! { "F1" => font_id } },
+            "MediaBox" => vec![0.into(), 0.into(), 612.into(), 792.into()],
+        });
+        doc.objects.insert(
+            pages_id,
+            lopdf::Object::Dictionary(dictionary! {
+                "Type" => "Pages",
+                "Kids" => vec![page_id.into()],
+                "Count" => 1,
+            }),
+        );
+        let catalog_id = doc.add_object(dictionary! {
+            "Type" => "Catalog",
+            "Pages" => pages_id,
+        });
+        doc.trailer.set("Root", catalog_id);
+        doc.trailer.set(
+            "ID",
+            vec![
+                lopdf::Object::string_literal("0123456789abcdef"),
+                lopdf::Object::string_literal("0123456789abcdef"),
+            ],
+        );
+
+        let version = EncryptionVersion::V2 {
+            document: &doc,
+            owner_password: "owner",
+            user_password,
+            key_length: 128,
+            permissions: Permissions::all(),
+        };
+        let state = EncryptionState::try_from(version).unwrap();
+        doc.encrypt(&state).unwrap();
+        doc.save(path).unwrap();
+    }
+
+    #[test]
+    fn test_encrypted_pdf_without_password() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("locked.pdf");
+        write_encrypted_pdf(&path, "secret");
+
+        let err = extract_pdf(&path, &ConversionOptions::default()).unwrap_err();
+        match err {
+            ConvertError::Pdf(msg) => assert!(msg.starts_with("password required"), "{}", msg),
+            other => panic!("Expected Pdf error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_encrypted_pdf_with_password() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("locked.pdf");
+        write_encrypted_pdf(&path, "secret");
+
+        let opts = ConversionOptions {
+            pdf_password: Some("secret".to_string()),
+            ..Default::default()
+        };
+        let mut doc = load_document(&path, &opts).unwrap();
+        assert_eq!(doc.get_pages().len(), 1);
+
+        // The copy handed to poppler opens without any password
+        let (_dir, copy) = write_decrypted_copy(&mut doc).unwrap();
+        let unlocked = load_document(&copy, &ConversionOptions::default()).unwrap();
+        assert_eq!(unlocked.get_pages().len(), 1);
+
+        let opts = ConversionOptions {
+            pdf_password: Some("wrong".to_string()),
+            ..Default::default()
+        };
+        match load_document(&path, &opts).unwrap_err() {
+            ConvertError::Pdf(msg) => assert_eq!(msg, "Incorrect password for encrypted PDF"),
+            other => panic!("Expected Pdf error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_parse_page_ranges() {
+        assert_eq!(parse_page_ranges("3-4", 5).unwrap(), vec![3, 4]);
+        assert_eq!(parse_page_ranges("5, 1-2,2", 5).unwrap(), vec![1, 2, 5]);
+        assert!(parse_page_ranges("4-3", 5).is_err());
+        assert!(parse_page_ranges("0-2", 5).is_err());
+        assert!(parse_page_ranges("4-6", 5).is_err());
+        assert!(parse_page_ranges("x", 5).is_err());
+        assert!(parse_page_ranges("", 5).is_err());
+    }
+
+    /// A PDF with one page per entry in `texts`, each showing that text.
+    fn write_text_pdf(path: &Path, texts: &[&str]) {
+        use lopdf::content::{Content, Operation};
+        use lopdf::{dictionary, Stream};
+
+        let mut doc = Document::with_version("1.5");
+        let pages_id = doc.new_object_id();
+        let font_id = doc.add_object(dictionary! {
+            "Type" => "Font",
+            "Subtype" => "Type1",
+            "BaseFont" => "Helvetica",
+        });
+        let mut kids = Vec::new();
+        for text in texts {
+            let content = Content {
+                operations: vec![
+                    Operation::new("BT", vec![]),
+                    Operation::new("Tf", vec!["F1".into(), 48.into()]),
+                    Operation::new("Td", vec![72.into(), 600.into()]),
+                    Operation::new("Tj", vec![lopdf::Object::string_literal(*text)]),
+                    Operation::new("ET", vec![]),
+                ],
+            };
+            let content_id = doc.add_object(Stream::new(dictionary! {}, content.encode().unwrap()));
+            let page_id = doc.add_object(dictionary! {
+                "Type" => "Page",
+                "Parent" => pages_id,
+                "Contents" => content_id,
+                "Resources" => dictionary! { "Font" => dictionary! { "F1" => font_id } },
+                "MediaBox" => vec![0.into(), 0.into(), 612.into(), 792.into()],
+            });
+            kids.push(page_id.into());
+        }
+        doc.objects.insert(
+            pages_id,
+            lopdf::Object::Dictionary(dictionary! {
+                "Type" => "Pages",
+                "Count" => kids.len() as i64,
+                "Kids" => kids,
+            }),
+        );
+        let catalog_id = doc.add_object(dictionary! {
+            "Type" => "Catalog",
+            "Pages" => pages_id,
+        });
+        doc.trailer.set("Root", catalog_id);
+        doc.save(path).unwrap();
+    }
+
+    #[test]
+    fn test_cover_from_page_outside_page_range() {
+        if render::check_pdftoppm().is_err() {
+            eprintln!("pdftoppm not installed; skipping");
+            return;
+        }
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("five.pdf");
+        write_text_pdf(&path, &["One", "Two", "Three", "Four", "Five"]);
+
+        let opts = ConversionOptions {
+            pdf_engine: PdfEngine::ImageOnly,
+            pdf_pages: Some("3-4".to_string()),
+            cover_from_page: Some(2),
+            ..Default::default()
+        };
+        let book = extract_pdf(&path, &opts).unwrap();
+
+        let spine: Vec<&str> = book.spine.iter().map(|s| s.idref.as_str()).collect();
+        assert_eq!(spine, vec!["page3", "page4"]);
+
+        let cover = book.guide.get("cover").unwrap();
+        let cover_item = book.manifest.by_href(&cover.href).unwrap();
+        let page2 = render::render_page_ranges(&path, &[2], 5, &opts).unwrap();
+        assert_eq!(cover_item.data.as_binary(), Some(page2[&2].as_slice()));
+    }
+
+    #[test]
+    fn test_first_page_cover_without_pages() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("empty.pdf");
+        write_text_pdf(&path, &[]);
+
+        let opts = ConversionOptions {
+            pdf_engine: PdfEngine::ImageOnly,
+            pdf_first_page_cover: true,
+            ..Default::default()
+        };
+        let book = extract_pdf(&path, &opts).unwrap();
+        assert!(book.guide.get("cover").is_none());
+    }
+
+    #[test]
+    fn test_page_image_as_cover() {
+        let mut book = BookDocument::new();
+        book.manifest.add(ManifestItem::new(
+            "img_scan4",
+            "images/scan_page4.jpg",
+            "image/jpeg",
+            ManifestData::Binary(vec![0xFF, 0xD8]),
+        ));
+        assert!(!use_page_image_as_cover(1, &mut book));
+        assert!(book.guide.get("cover").is_none());
+
+        assert!(use_page_image_as_cover(4, &mut book));
+        assert_eq!(
+            book.guide.get("cover").unwrap().href,
+            "images/scan_page4.jpg"
+        );
+    }
+
+    #[test]
+    fn test_first_page_cover() {
+        if render::check_pdftoppm().is_err() {
+            eprintln!("pdftoppm not installed; skipping");
+            return;
+        }
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("three.pdf");
+        write_text_pdf(&path, &["One", "Two", "Three"]);
+
+        let opts = ConversionOptions {
+            pdf_engine: PdfEngine::ImageOnly,
+            pdf_pages: Some("2-3".to_string()),
+            pdf_first_page_cover: true,
+            ..Default::default()
+        };
+        let book = extract_pdf(&path, &opts).unwrap();
+
+        let cover = book.guide.get("cover").unwrap();
+        assert_eq!(cover.href, "images/page2.jpg");
+        assert!(book.manifest.by_href(&cover.href).unwrap().is_image());
+        assert!(book.manifest.by_href("images/cover.jpg").is_none());
+        let spine: Vec<&str> = book.spine.iter().map(|s| s.idref.as_str()).collect();
+        assert_eq!(spine, vec!["page2", "page3"]);
+
+        // Text pages have no page image, so the first page is rendered
+        let opts = ConversionOptions {
+            pdf_first_page_cover: true,
+            ..Default::default()
+        };
+        let book = extract_pdf(&path, &opts).unwrap();
+        assert_eq!(book.guide.get("cover").unwrap().href, "images/cover.jpg");
+        assert_eq!(book.spine.len(), 3);
+    }
+
+    #[test]
+    fn test_page_selection_out_of_bounds() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("two.pdf");
+        write_text_pdf(&path, &["One", "Two"]);
+
+        let opts = ConversionOptions {
+            pdf_pages: Some("2-3".to_string()),
+            ..Default::default()
+        };
+        let err = extract_pdf(&path, &opts).unwrap_err();
+        assert!(matches!(err, ConvertError::Pdf(ref msg) if msg.contains("past the end")));
+
+        let mut book = BookDocument::new();
+        let err =
+            add_page_cover(&path, 3, 2, &ConversionOptions::default(), &mut book).unwrap_err();
+        assert!(matches!(err, ConvertError::Pdf(ref msg) if msg.contains("outside the PDF")));
+    }
 }
@@ -168,15 +168,23 @@ const PARALLEL_MIN_PAGES: u32 = 50;
 /// Maximum number of parallel pdftohtml worker processes.
 const PARALLEL_MAX_WORKERS: usize = 8;
 
+/// Number of parallel pdftohtml workers: the size of the current rayon pool
+/// (capped by `max_threads` when set), at most [`PARALLEL_MAX_WORKERS`].
+fn worker_count() -> usize {
+    rayon::current_num_threads().clamp(1, PARALLEL_MAX_WORKERS)
+}
+
 /// Run `pdftohtml -xml` in parallel by splitting into page-range chunks.
 ///
 /// For documents with more than [`PARALLEL_MIN_PAGES`] pages, spawns multiple
 /// `pdftohtml` processes (one per chunk) using `std::thread::scope`, then merges
 /// their results. The outline is extracted separately from the full document.
 ///
-/// For small documents (≤50 pages), delegates to [`run_pdftohtml_xml`].
+/// For small documents (≤50 pages), or when limited to a single worker,
+/// delegates to [`run_pdftohtml_xml`].
 pub fn run_pdftohtml_xml_parallel(pdf_path: &Path, num_pages: u32) -> Result<PdfToHtmlResult> {
-    if num_pages <= PARALLEL_MIN_PAGES {
+    let num_workers = worker_count();
+    if num_pages <= PARALLEL_MIN_PAGES || num_workers == 1 {
         return run_pdftohtml_xml(pdf_path);
     }
 
@@ -194,9 +202,6 @@ pub fn run_pdftohtml_xml_parallel(pdf_path: &Path, num_pages: u32) -> Result<Pdf
         ));
     }
 
-    let num_workers = std::thread::available_parallelism()
-        .map(|n| n.get().min(PARALLEL_MAX_WORKERS))
-        .unwrap_or(4);
     let chunk_size = (num_pages as usize).div_ceil(num_workers);
 
     log::info!(
@@ -371,6 +376,20 @@ fn extract_outline_only(pdf_path: &Path) -> Result<Vec<OutlineItem>> {
     Ok(outline)
 }
 
+/// The EPUB href for a link in pdftohtml output. Links to a page of the
+/// document itself (`output.html#5`) point at that page's file; others are
+/// kept as-is.
+fn link_target(href: &str) -> String {
+    let page = href.rsplit_once('#').and_then(|(file, fragment)| {
+        let internal = file.is_empty() || (file.ends_with(".html") && !file.contains(':'));
+        internal.then(|| fragment.parse::<u32>().ok()).flatten()
+    });
+    match page {
+        Some(n) => format!("page{}.xhtml", n),
+        None => href.to_string(),
+    }
+}
+
 /// Parse the pdftohtml XML output.
 fn parse_pdftohtml_xml(xml: &str) -> Result<(Vec<FontSpec>, Vec<PdfPage>, Vec<OutlineItem>)> {
     let mut reader = Reader::from_str(xml);
@@ -451,7 +470,9 @@ fn parse_pdftohtml_xml(xml: &str) -> Result<(Vec<FontSpec>, Vec<PdfPage>, Vec<Ou
                             let attrs = parse_attrs(e);
                             if let Some(href) = attrs.get("href") {
                                 text_html.push_str(" href=\"");
-                                text_html.push_str(&convert_utils::xml::escape_xml_attr(href));
+                                text_html.push_str(&convert_utils::xml::escape_xml_attr(
+                                    &link_target(href),
+                                ));
                                 text_html.push('"');
                             }
                         }
@@ -510,7 +531,7 @@ fn parse_pdftohtml_xml(xml: &str) -> Result<(Vec<FontSpec>, Vec<PdfPage>, Vec<Ou
                 if in_text {
                     if let Ok(text) = e.unescape() {
                         // Escape for embedding in our inner_html
-                        text_html.push_str(&text);
+                        text_html.push_str(&convert_utils::xml::escape_xml_text(&text));
                     }
                 }
                 // Handle outline item text
@@ -612,11 +633,9 @@ fn parse_outline_section(xml: &str) -> Vec<OutlineItem> {
                     _ => {}
                 }
             }
-            Ok(Event::Text(ref e)) => {
-                if current_item_page.is_some() {
-                    if let Ok(text) = e.unescape() {
-                        item_text.push_str(&text);
-                    }
+            Ok(Event::Text(ref e)) if current_item_page.is_some() => {
+                if let Ok(text) = e.unescape() {
+                    item_text.push_str(&text);
                 }
             }
             Ok(Event::End(ref e)) => {
@@ -658,7 +677,10 @@ fn parse_attrs(e: &quick_xml::events::BytesStart) -> std::collections::HashMap<S
     let mut map = std::collections::HashMap::new();
     for attr in e.attributes().flatten() {
         let key = String::from_utf8_lossy(attr.key.local_name().as_ref()).to_string();
-        let value = String::from_utf8_lossy(&attr.value).to_string();
+        let value = attr
+            .unescape_value()
+            .map(|v| v.into_owned())
+            .unwrap_or_else(|_| String::from_utf8_lossy(&attr.value).to_string());
         map.insert(key, value);
     }
     map
@@ -668,6 +690,47 @@ fn parse_attrs(e: &quick_xml::events::BytesStart) -> std::collections::HashMap<S
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_worker_count_follows_thread_pool() {
+        let single = rayon::ThreadPoolBuilder::new()
+            .num_threads(1)
+            .build()
+            .unwrap();
+        assert_eq!(single.install(worker_count), 1);
+
+        let wide = rayon::ThreadPoolBuilder::new()
+            .num_threads(32)
+            .build()
+            .unwrap();
+        assert_eq!(wide.install(worker_count), PARALLEL_MAX_WORKERS);
+    }
+
+    #[test]
+    fn test_links_reach_page_xhtml() {
+        let xml = r##"<?xml version="1.0" encoding="UTF-8"?>
+<pdf2xml>
+<page number="1" width="612" height="792">
+<fontspec id="0" size="12" family="Times" color="#000000"/>
+<text top="100" left="50" width="200" height="14" font="0">See <a href="http://example.com/?a=1&amp;b=2">the site</a> &amp; <a href="output.html#3">chapter 2</a></text>
+</page>
+</pdf2xml>"##;
+        let (fonts, pages, _outline) = parse_pdftohtml_xml(xml).unwrap();
+        let xhtml = crate::text_builder::build_text_page_xhtml(
+            &pages[0],
+            &fonts,
+            &std::collections::HashMap::new(),
+        );
+        assert!(xhtml.contains(
+            r#"<p>See <a href="http://example.com/?a=1&amp;b=2">the site</a> &amp; <a href="page3.xhtml">chapter 2</a></p>"#
+        ));
+
+        assert_eq!(link_target("#12"), "page12.xhtml");
+        assert_eq!(
+            link_target("https://example.com/x.html#2"),
+            "https://example.com/x.html#2"
+        );
+    }
+
     #[test]
     fn test_parse_simple_xml() {
         let xml = r##"<?xml version="1.0" encoding="UTF-8"?>
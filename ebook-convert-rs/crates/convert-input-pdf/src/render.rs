@@ -35,6 +35,10 @@ pub fn render_all_pages(
     num_pages: u32,
     options: &ConversionOptions,
 ) -> Result<Vec<(u32, Vec<u8>)>> {
+    if num_pages == 0 {
+        return Ok(Vec::new());
+    }
+
     check_pdftoppm()?;
 
     let tmp_dir = tempfile::TempDir::new()
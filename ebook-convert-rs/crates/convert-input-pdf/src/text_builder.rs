@@ -187,7 +187,7 @@ fn build_content_blocks(
                 if let Some(prev_bottom) = last_line_bottom {
                     let gap = line.top - prev_bottom;
                     if gap > para_threshold && !current_para_lines.is_empty() {
-                        blocks.push(ContentBlock::Paragraph(current_para_lines.join(" ")));
+                        blocks.push(ContentBlock::Paragraph(join_lines(&current_para_lines)));
                         current_para_lines.clear();
                     }
                 }
@@ -199,6 +199,7 @@ fn build_content_blocks(
                     .map(|(_, html)| html.as_str())
                     .collect::<Vec<_>>()
                     .join(" ");
+                let line_html = expand_ligatures(&line_html);
 
                 current_para_lines.push(line_html);
                 last_line_bottom = Some(line.top + line.height);
@@ -206,7 +207,7 @@ fn build_content_blocks(
             Item::Image(img) => {
                 // Flush current paragraph before image
                 if !current_para_lines.is_empty() {
-                    blocks.push(ContentBlock::Paragraph(current_para_lines.join(" ")));
+                    blocks.push(ContentBlock::Paragraph(join_lines(&current_para_lines)));
                     current_para_lines.clear();
                     last_line_bottom = None;
                 }
@@ -223,12 +224,112 @@ fn build_content_blocks(
 
     // Flush remaining paragraph
     if !current_para_lines.is_empty() {
-        blocks.push(ContentBlock::Paragraph(current_para_lines.join(" ")));
+        blocks.push(ContentBlock::Paragraph(join_lines(&current_para_lines)));
     }
 
     blocks
 }
 
+/// Typographic ligatures emitted by pdftohtml and their plain-letter expansions.
+const LIGATURES: &[(char, &str)] = &[
+    ('\u{FB00}', "ff"),
+    ('\u{FB01}', "fi"),
+    ('\u{FB02}', "fl"),
+    ('\u{FB03}', "ffi"),
+    ('\u{FB04}', "ffl"),
+    ('\u{FB05}', "st"),
+    ('\u{FB06}', "st"),
+];
+
+/// Expand ligature characters (e.g. `ﬁ` → `fi`).
+fn expand_ligatures(text: &str) -> String {
+    if !text.chars().any(|c| ('\u{FB00}'..='\u{FB06}').contains(&c)) {
+        return text.to_string();
+    }
+    let mut result = String::with_capacity(text.len() + 8);
+    for ch in text.chars() {
+        match LIGATURES.iter().find(|(lig, _)| *lig == ch) {
+            Some((_, expansion)) => result.push_str(expansion),
+            None => result.push(ch),
+        }
+    }
+    result
+}
+
+/// Join the lines of a paragraph, undoing end-of-line hyphenation.
+///
+/// A line ending in a hyphen (or soft hyphen) after a letter is joined to the
+/// next line without a space. The hyphen is dropped when the next line
+/// continues in lowercase ("hyph-" + "enated" → "hyphenated") and kept
+/// otherwise ("Anglo-" + "Saxon" → "Anglo-Saxon"). Soft hyphens elsewhere
+/// are removed.
+fn join_lines(lines: &[String]) -> String {
+    let mut result = String::new();
+    for line in lines {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        if result.is_empty() {
+            result.push_str(line);
+            continue;
+        }
+
+        // The hyphen may sit before inline end tags, as in `hy-</i>`
+        let text_end = end_before_closing_tags(&result);
+        let text = &result[..text_end];
+        let hyphen = text
+            .strip_suffix('-')
+            .map(|rest| (rest, '-'))
+            .or_else(|| text.strip_suffix('\u{AD}').map(|rest| (rest, '\u{AD}')));
+        let broken_word = hyphen
+            .filter(|(rest, _)| rest.chars().last().is_some_and(char::is_alphabetic))
+            .map(|(_, h)| h);
+
+        match broken_word {
+            Some(h) => {
+                let closing_tags = result.split_off(text_end);
+                result.pop();
+                let continues_lowercase = first_text_char(line).is_some_and(char::is_lowercase);
+                if h == '-' && !continues_lowercase {
+                    result.push('-');
+                }
+                result.push_str(&closing_tags);
+            }
+            None => result.push(' '),
+        }
+        result.push_str(line);
+    }
+    result.replace('\u{AD}', "")
+}
+
+/// Byte offset where trailing end tags (`</i></b>`) begin in `html`, or its
+/// length if it does not end with one.
+fn end_before_closing_tags(html: &str) -> usize {
+    let mut end = html.len();
+    while html[..end].ends_with('>') {
+        match html[..end].rfind("</") {
+            Some(start) if !html[start..end - 1].contains('>') => end = start,
+            _ => break,
+        }
+    }
+    end
+}
+
+/// First character outside of markup in an HTML fragment.
+fn first_text_char(html: &str) -> Option<char> {
+    let mut in_tag = false;
+    for ch in html.chars() {
+        match ch {
+            '<' => in_tag = true,
+            '>' => in_tag = false,
+            _ if !in_tag => return Some(ch),
+            _ => {}
+        }
+    }
+    None
+}
+
 /// Compute the average gap between consecutive lines.
 fn compute_avg_line_gap(lines: &[TextLine]) -> f64 {
     if lines.len() < 2 {
@@ -366,4 +467,95 @@ mod tests {
 
         assert!(xhtml.contains("[Page 5]"));
     }
+
+    #[test]
+    fn test_expand_ligatures() {
+        assert_eq!(expand_ligatures("\u{FB01}rst"), "first");
+        assert_eq!(
+            expand_ligatures("a\u{FB02}oat, o\u{FB03}ce"),
+            "afloat, office"
+        );
+        assert_eq!(expand_ligatures("plain"), "plain");
+    }
+
+    #[test]
+    fn test_ligature_in_page_xhtml() {
+        let page = PdfPage {
+            number: 1,
+            width: 612.0,
+            height: 792.0,
+            text_elements: vec![make_text(
+                100.0,
+                50.0,
+                200.0,
+                14.0,
+                "The \u{FB01}nal chapter",
+            )],
+            images: vec![],
+        };
+        let xhtml = build_text_page_xhtml(&page, &[], &HashMap::new());
+        assert!(xhtml.contains("<p>The final chapter</p>"));
+    }
+
+    #[test]
+    fn test_dehyphenation_across_text_elements() {
+        let elements = vec![
+            make_text(100.0, 50.0, 200.0, 14.0, "This word is hy-"),
+            make_text(116.0, 50.0, 200.0, 14.0, "phenated across lines."),
+        ];
+        let lines = group_into_lines(&elements, &[]);
+        let blocks = build_content_blocks(&lines, &[], &HashMap::new());
+
+        assert_eq!(blocks.len(), 1);
+        match &blocks[0] {
+            ContentBlock::Paragraph(text) => {
+                assert_eq!(text, "This word is hyphenated across lines.")
+            }
+            _ => panic!("Expected paragraph"),
+        }
+    }
+
+    #[test]
+    fn test_join_lines_keeps_compounds() {
+        // Hyphen inside a line is untouched
+        assert_eq!(
+            join_lines(&["a well-known fact".to_string(), "follows".to_string()]),
+            "a well-known fact follows"
+        );
+        // Capitalized continuation keeps the hyphen
+        assert_eq!(
+            join_lines(&["the Anglo-".to_string(), "Saxon era".to_string()]),
+            "the Anglo-Saxon era"
+        );
+        // A dash on its own is not a broken word
+        assert_eq!(
+            join_lines(&["wait -".to_string(), "what".to_string()]),
+            "wait - what"
+        );
+        // Soft hyphens are removed
+        assert_eq!(
+            join_lines(&[
+                "soft\u{AD}".to_string(),
+                "ware and hy\u{AD}phen".to_string()
+            ]),
+            "software and hyphen"
+        );
+    }
+
+    #[test]
+    fn test_join_lines_hyphen_before_end_tags() {
+        assert_eq!(
+            join_lines(&["<i>a hy-</i>".to_string(), "phen".to_string()]),
+            "<i>a hy</i>phen"
+        );
+        assert_eq!(
+            join_lines(&["<b><i>Anglo-</i></b>".to_string(), "Saxon".to_string()]),
+            "<b><i>Anglo-</i></b>Saxon"
+        );
+        // No hyphen before the tag: lines are joined with a space
+        assert_eq!(
+            join_lines(&["<i>word</i>".to_string(), "next".to_string()]),
+            "<i>word</i> next"
+        );
+    }
 }
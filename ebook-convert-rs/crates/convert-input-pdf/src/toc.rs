@@ -33,12 +33,17 @@ fn outline_to_toc(
     let mut entries = Vec::new();
 
     for item in items {
-        let href = page_href_map
-            .get(&item.page)
-            .cloned()
-            .unwrap_or_else(|| format!("page{}.xhtml", item.page));
+        // Untitled bookmarks, and bookmarks to pages that weren't converted,
+        // get no entry of their own; keep their children
+        let href = match page_href_map.get(&item.page) {
+            Some(href) if !item.title.trim().is_empty() => href,
+            _ => {
+                entries.extend(outline_to_toc(&item.children, page_href_map));
+                continue;
+            }
+        };
 
-        let mut entry = TocEntry::new(&item.title, &href);
+        let mut entry = TocEntry::new(item.title.trim(), href);
 
         // Recursively add children
         if !item.children.is_empty() {
@@ -138,4 +143,61 @@ mod tests {
 
         assert_eq!(toc.len(), 3);
     }
+
+    #[test]
+    fn test_empty_outline_titles_skipped() {
+        let outline = vec![
+            OutlineItem {
+                title: "Chapter 1".to_string(),
+                page: 1,
+                children: vec![],
+            },
+            OutlineItem {
+                title: "  ".to_string(),
+                page: 2,
+                children: vec![OutlineItem {
+                    title: "Section 2.1".to_string(),
+                    page: 3,
+                    children: vec![],
+                }],
+            },
+            OutlineItem {
+                title: "Chapter 3".to_string(),
+                page: 4,
+                children: vec![],
+            },
+        ];
+
+        let toc = build_toc(&outline, &make_page_map(5), 5, 3);
+
+        let titles: Vec<&str> = toc.iter().map(|e| e.title.as_str()).collect();
+        assert_eq!(titles, vec!["Chapter 1", "Section 2.1", "Chapter 3"]);
+    }
+
+    #[test]
+    fn test_outline_to_unconverted_pages_skipped() {
+        let outline = vec![
+            OutlineItem {
+                title: "Front Matter".to_string(),
+                page: 1,
+                children: vec![OutlineItem {
+                    title: "Preface".to_string(),
+                    page: 3,
+                    children: vec![],
+                }],
+            },
+            OutlineItem {
+                title: "Chapter 1".to_string(),
+                page: 4,
+                children: vec![],
+            },
+        ];
+        let page_map: HashMap<u32, String> =
+            (3..=4).map(|i| (i, format!("page{}.xhtml", i))).collect();
+
+        let toc = build_toc(&outline, &page_map, 5, 1);
+
+        let titles: Vec<&str> = toc.iter().map(|e| e.title.as_str()).collect();
+        assert_eq!(titles, vec!["Preface", "Chapter 1"]);
+    }
 }
@@ -0,0 +1,347 @@
+//! Decoding of RTF character escapes ahead of `rtf-parser`.
+//!
+//! `rtf-parser` reads `\'XX` as a Latin-1 code point and doesn't skip the
+//! fallback characters after `\uN`, so Cyrillic, Greek or CJK text comes
+//! through garbled. This pass replaces both escapes with the characters they
+//! stand for: `\'XX` bytes are decoded with the codepage of the current
+//! font's `\fcharset`, else the document's `\ansicpg`, and `\uN` (a signed
+//! 16-bit UTF-16 unit) drops the `\ucN` fallback characters that follow it.
+//!
+//! `rtf-parser` also drops table control words, so `\trowd`, `\cellxN`,
+//! `\cell` and `\row` are replaced with the private-use markers below for
+//! `blocks_to_html` to rebuild the table from.
+
+use std::collections::HashMap;
+
+use encoding_rs::{Encoding, WINDOWS_1252};
+
+/// Start of a table row definition (`\trowd`).
+pub(crate) const ROW_DEF: char = '\u{E000}';
+/// A cell's right boundary (`\cellxN`), followed by `N;`.
+pub(crate) const CELL_X: char = '\u{E001}';
+/// End of a cell (`\cell`).
+pub(crate) const CELL_END: char = '\u{E002}';
+/// End of a row (`\row`).
+pub(crate) const ROW_END: char = '\u{E003}';
+
+/// Formatting state scoped to an RTF group.
+#[derive(Clone, Copy)]
+struct GroupState {
+    /// Fallback characters following each `\uN` (`\ucN`, default 1).
+    uc: usize,
+    /// Current font number (`\fN`).
+    font: Option<i32>,
+}
+
+/// Rewrite `rtf` with `\'XX` and `\uN` escapes replaced by the characters
+/// they encode. Other control words are kept, each followed by a space.
+pub(crate) fn decode_escapes(rtf: &str) -> String {
+    let chars: Vec<char> = rtf.chars().collect();
+    let mut out = String::with_capacity(rtf.len());
+
+    let mut encoding = WINDOWS_1252;
+    let mut font_encodings: HashMap<i32, &'static Encoding> = HashMap::new();
+    let mut fonttbl_depth: Option<usize> = None;
+    let mut defining_font: Option<i32> = None;
+
+    let mut stack: Vec<GroupState> = Vec::new();
+    let mut state = GroupState { uc: 1, font: None };
+    let mut skip = 0usize;
+    let mut bytes: Vec<u8> = Vec::new();
+    let mut units: Vec<u16> = Vec::new();
+
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        match c {
+            '\\' if chars.get(i + 1) == Some(&'\'') => {
+                let hex: String = chars.iter().skip(i + 2).take(2).collect();
+                let Ok(byte) = u8::from_str_radix(&hex, 16) else {
+                    i += 2;
+                    continue;
+                };
+                i += 4;
+                if skip > 0 {
+                    skip -= 1;
+                    continue;
+                }
+                flush_units(&mut units, &mut out);
+                bytes.push(byte);
+            }
+            '\\' if chars.get(i + 1).is_some_and(|c| c.is_ascii_alphabetic()) => {
+                let start = i + 1;
+                let mut end = start;
+                while end < chars.len() && chars[end].is_ascii_alphabetic() {
+                    end += 1;
+                }
+                let word: String = chars[start..end].iter().collect();
+                let param_start = end;
+                if chars.get(end) == Some(&'-') {
+                    end += 1;
+                }
+                while end < chars.len() && chars[end].is_ascii_digit() {
+                    end += 1;
+                }
+                let param_text: String = chars[param_start..end].iter().collect();
+                let param: Option<i32> = param_text.parse().ok();
+                if chars.get(end) == Some(&' ') {
+                    end += 1;
+                }
+                i = end;
+
+                if skip > 0 {
+                    skip -= 1;
+                    continue;
+                }
+                if word == "u" {
+                    if let Some(n) = param {
+                        flush_bytes(
+                            &mut bytes,
+                            encoding_for(&state, &font_encodings, encoding),
+                            &mut out,
+                        );
+                        units.push(n as i16 as u16);
+                        skip = state.uc;
+                        continue;
+                    }
+                }
+
+                flush(
+                    &mut bytes,
+                    &mut units,
+                    encoding_for(&state, &font_encodings, encoding),
+                    &mut out,
+                );
+                let in_fonttbl = fonttbl_depth.is_some_and(|d| stack.len() >= d);
+                match (word.as_str(), param) {
+                    ("uc", Some(n)) => state.uc = n.max(0) as usize,
+                    ("ansicpg", Some(cp)) => {
+                        encoding = codepage_encoding(cp).unwrap_or(WINDOWS_1252);
+                    }
+                    ("fonttbl", _) => fonttbl_depth = Some(stack.len()),
+                    ("f", Some(n)) if in_fonttbl => defining_font = Some(n),
+                    ("f", Some(n)) => state.font = Some(n),
+                    ("fcharset", Some(charset)) if in_fonttbl => {
+                        let enc = charset_codepage(charset).and_then(codepage_encoding);
+                        if let (Some(font), Some(enc)) = (defining_font, enc) {
+                            font_encodings.insert(font, enc);
+                        }
+                    }
+                    ("trowd", _) => {
+                        out.push(ROW_DEF);
+                        continue;
+                    }
+                    ("cellx", Some(x)) => {
+                        out.push(CELL_X);
+                        out.push_str(&format!("{};", x));
+                        continue;
+                    }
+                    ("cell", _) => {
+                        out.push(CELL_END);
+                        continue;
+                    }
+                    ("row", _) => {
+                        out.push(ROW_END);
+                        continue;
+                    }
+                    _ => {}
+                }
+                out.push('\\');
+                out.push_str(&word);
+                out.push_str(&param_text);
+                out.push(' ');
+            }
+            '\\' => {
+                let end = (i + 2).min(chars.len());
+                if skip > 0 {
+                    skip -= 1;
+                } else {
+                    flush(
+                        &mut bytes,
+                        &mut units,
+                        encoding_for(&state, &font_encodings, encoding),
+                        &mut out,
+                    );
+                    out.extend(&chars[i..end]);
+                }
+                i = end;
+            }
+            '{' | '}' => {
+                flush(
+                    &mut bytes,
+                    &mut units,
+                    encoding_for(&state, &font_encodings, encoding),
+                    &mut out,
+                );
+                skip = 0;
+                if c == '{' {
+                    stack.push(state);
+                } else {
+                    if fonttbl_depth == Some(stack.len()) {
+                        fonttbl_depth = None;
+                    }
+                    state = stack.pop().unwrap_or(state);
+                }
+                out.push(c);
+                i += 1;
+            }
+            // Line breaks are not content and never split an escape run
+            '\r' | '\n' => {
+                out.push(c);
+                i += 1;
+            }
+            _ => {
+                if skip > 0 {
+                    skip -= 1;
+                } else {
+                    flush(
+                        &mut bytes,
+                        &mut units,
+                        encoding_for(&state, &font_encodings, encoding),
+                        &mut out,
+                    );
+                    out.push(c);
+                }
+                i += 1;
+            }
+        }
+    }
+    flush(
+        &mut bytes,
+        &mut units,
+        encoding_for(&state, &font_encodings, encoding),
+        &mut out,
+    );
+    out
+}
+
+/// The encoding for `\'XX` bytes in the current font.
+fn encoding_for(
+    state: &GroupState,
+    font_encodings: &HashMap<i32, &'static Encoding>,
+    document: &'static Encoding,
+) -> &'static Encoding {
+    state
+        .font
+        .and_then(|f| font_encodings.get(&f).copied())
+        .unwrap_or(document)
+}
+
+fn flush(bytes: &mut Vec<u8>, units: &mut Vec<u16>, encoding: &'static Encoding, out: &mut String) {
+    flush_bytes(bytes, encoding, out);
+    flush_units(units, out);
+}
+
+/// Decode pending `\'XX` bytes into `out`.
+fn flush_bytes(bytes: &mut Vec<u8>, encoding: &'static Encoding, out: &mut String) {
+    if !bytes.is_empty() {
+        let (text, _) = encoding.decode_without_bom_handling(bytes);
+        push_escaped(&text, out);
+        bytes.clear();
+    }
+}
+
+/// Decode pending `\uN` UTF-16 units (possibly surrogate pairs) into `out`.
+fn flush_units(units: &mut Vec<u16>, out: &mut String) {
+    if !units.is_empty() {
+        let text: String = char::decode_utf16(units.iter().copied())
+            .map(|r| r.unwrap_or(char::REPLACEMENT_CHARACTER))
+            .collect();
+        push_escaped(&text, out);
+        units.clear();
+    }
+}
+
+/// Append decoded text, escaping the characters RTF treats as syntax.
+fn push_escaped(text: &str, out: &mut String) {
+    for c in text.chars() {
+        if matches!(c, '\\' | '{' | '}') {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+}
+
+/// Windows codepage for an RTF `\fcharsetN` value.
+fn charset_codepage(charset: i32) -> Option<i32> {
+    Some(match charset {
+        77 => 10000,
+        128 => 932,
+        129 => 949,
+        134 => 936,
+        136 => 950,
+        161 => 1253,
+        162 => 1254,
+        163 => 1258,
+        177 => 1255,
+        178 => 1256,
+        186 => 1257,
+        204 => 1251,
+        222 => 874,
+        238 => 1250,
+        _ => return None,
+    })
+}
+
+/// The `encoding_rs` encoding for a Windows codepage number.
+fn codepage_encoding(codepage: i32) -> Option<&'static Encoding> {
+    let label = match codepage {
+        866 => "ibm866".to_string(),
+        874 => "windows-874".to_string(),
+        932 => "shift_jis".to_string(),
+        936 => "gbk".to_string(),
+        949 => "euc-kr".to_string(),
+        950 => "big5".to_string(),
+        1250..=1258 => format!("windows-{}", codepage),
+        10000 => "macintosh".to_string(),
+        20866 => "koi8-r".to_string(),
+        21866 => "koi8-u".to_string(),
+        28591..=28606 => format!("iso-8859-{}", codepage - 28590),
+        65001 => "utf-8".to_string(),
+        _ => return None,
+    };
+    Encoding::for_label(label.as_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unicode_escape_skips_fallback() {
+        assert_eq!(decode_escapes(r"\u1055?\u1088?"), "Пр");
+        assert_eq!(decode_escapes(r"{\uc2\u1055\'3f\'3f}x"), r"{\uc2 П}x");
+        // Negative values are UTF-16 units above 32767, here a surrogate pair
+        assert_eq!(decode_escapes(r"\u-10179?\u-8704?"), "😀");
+    }
+
+    #[test]
+    fn test_codepage_bytes() {
+        let rtf = r"{\rtf1\ansi\ansicpg1251 \'cf\'f0\'e8\'e2\'e5\'f2}";
+        assert_eq!(decode_escapes(rtf), r"{\rtf1 \ansi \ansicpg1251 Привет}");
+        // Without \ansicpg, bytes are Windows-1252
+        assert_eq!(decode_escapes(r"caf\'e9"), "café");
+    }
+
+    #[test]
+    fn test_font_charset_overrides_codepage() {
+        let rtf = r"{\fonttbl{\f0 Times;}{\f1\fcharset204 Arial;}}\f1\'c4\f0\'c4";
+        assert_eq!(
+            decode_escapes(rtf),
+            r"{\fonttbl {\f0 Times;}{\f1 \fcharset204 Arial;}}\f1 Д\f0 Ä"
+        );
+    }
+
+    #[test]
+    fn test_table_markers() {
+        assert_eq!(
+            decode_escapes(r"\trowd\cellx1440 \intbl A\cell B\cell\row"),
+            "\u{E000}\u{E001}1440;\\intbl A\u{E002}B\u{E002}\u{E003}"
+        );
+    }
+
+    #[test]
+    fn test_escaped_syntax_characters_kept() {
+        assert_eq!(decode_escapes(r"a\{b\}\\\'7b"), r"a\{b\}\\\{");
+    }
+}
@@ -0,0 +1,209 @@
+//! Parse document metadata from the RTF `\info` group.
+//!
+//! ```text
+//! {\info{\title Moby Dick}{\author Herman Melville}{\subject Whaling}
+//! {\keywords sea, whales}{\creatim\yr1851\mo10\dy18}}
+//! ```
+//!
+//! Runs on the output of [`decode_escapes`](crate::escapes::decode_escapes),
+//! so character escapes in the values are already decoded. `rtf-parser`
+//! treats the group as body text, so it is removed before conversion.
+
+use std::borrow::Cow;
+use std::ops::Range;
+
+use convert_core::book::BookDocument;
+
+/// Parse the `\info` group of `rtf` and populate book metadata.
+///
+/// - `\title`, `\author`, `\subject`, `\doccomm` (description)
+/// - `\keywords`, comma-separated, become subjects
+/// - `\creatim` becomes the date
+pub(crate) fn parse_info(rtf: &str, book: &mut BookDocument) {
+    let Some(span) = info_span(rtf) else {
+        return;
+    };
+    let info = &rtf[span.start + "{\\info".len()..span.end - 1];
+
+    for (word, content) in subgroups(info) {
+        if word == "creatim" {
+            if let Some(date) = creation_date(content) {
+                book.metadata.set("date", date);
+            }
+            continue;
+        }
+        let text = plain_text(content);
+        if text.is_empty() {
+            continue;
+        }
+        match word {
+            "title" => book.metadata.set_title(&text),
+            "author" => book.metadata.add("creator", &text),
+            "subject" => book.metadata.add("subject", &text),
+            "doccomm" => book.metadata.set("description", &text),
+            "keywords" => {
+                for kw in text.split(',') {
+                    let kw = kw.trim();
+                    if !kw.is_empty() {
+                        book.metadata.add("subject", kw);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// `rtf` without its `\info` group.
+pub(crate) fn without_info(rtf: &str) -> Cow<'_, str> {
+    match info_span(rtf) {
+        Some(span) => Cow::Owned(format!("{}{}", &rtf[..span.start], &rtf[span.end..])),
+        None => Cow::Borrowed(rtf),
+    }
+}
+
+/// Byte range of the `\info` group, braces included.
+fn info_span(rtf: &str) -> Option<Range<usize>> {
+    let start = rtf
+        .match_indices("{\\info")
+        .map(|(i, _)| i)
+        .find(|&i| !rtf[i + "{\\info".len()..].starts_with(|c: char| c.is_ascii_alphabetic()))?;
+    let content = start + "{\\info".len();
+    let end = (content + group_len(&rtf[content..]) + 1).min(rtf.len());
+    Some(start..end)
+}
+
+/// Length of `rtf` up to the brace that closes the enclosing group.
+fn group_len(rtf: &str) -> usize {
+    let mut depth = 0usize;
+    let mut chars = rtf.char_indices();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            // Escaped syntax characters
+            '\\' => {
+                chars.next();
+            }
+            '{' => depth += 1,
+            '}' if depth == 0 => return i,
+            '}' => depth -= 1,
+            _ => {}
+        }
+    }
+    rtf.len()
+}
+
+/// The `{\word content}` groups directly inside `group`.
+fn subgroups(group: &str) -> Vec<(&str, &str)> {
+    let mut groups = Vec::new();
+    let mut rest = group;
+    while let Some(open) = rest.find('{') {
+        if open > 0 && rest[..open].ends_with('\\') {
+            rest = &rest[open + 1..];
+            continue;
+        }
+        let inner = &rest[open + 1..];
+        let len = group_len(inner);
+        let body = inner[..len].trim_start_matches("\\*").trim_start();
+        if let Some(body) = body.strip_prefix('\\') {
+            let word_len = body
+                .find(|c: char| !c.is_ascii_alphabetic())
+                .unwrap_or(body.len());
+            groups.push((&body[..word_len], &body[word_len..]));
+        }
+        rest = inner.get(len + 1..).unwrap_or("");
+    }
+    groups
+}
+
+/// Text of a group with control words and nested groups removed.
+fn plain_text(content: &str) -> String {
+    let mut text = String::new();
+    let mut chars = content.chars().peekable();
+    let mut depth = 0usize;
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.peek() {
+                Some(&escaped @ ('\\' | '{' | '}')) => {
+                    chars.next();
+                    if depth == 0 {
+                        text.push(escaped);
+                    }
+                }
+                Some(c) if c.is_ascii_alphabetic() => {
+                    while chars.next_if(|c| c.is_ascii_alphanumeric()).is_some() {}
+                    chars.next_if_eq(&'-');
+                    while chars.next_if(char::is_ascii_digit).is_some() {}
+                    chars.next_if_eq(&' ');
+                }
+                _ => {
+                    chars.next();
+                }
+            },
+            '{' => depth += 1,
+            '}' => depth = depth.saturating_sub(1),
+            '\r' | '\n' => {}
+            _ if depth == 0 => text.push(c),
+            _ => {}
+        }
+    }
+    text.trim().to_string()
+}
+
+/// `YYYY-MM-DD` from `\yrN\moN\dyN`, or just the year when the rest is missing.
+fn creation_date(content: &str) -> Option<String> {
+    let value = |word: &str| -> Option<u32> {
+        let pos = content.find(&format!("\\{}", word))? + word.len() + 1;
+        let digits: String = content[pos..]
+            .chars()
+            .take_while(char::is_ascii_digit)
+            .collect();
+        digits.parse().ok()
+    };
+    let year = value("yr")?;
+    Some(match (value("mo"), value("dy")) {
+        (Some(month), Some(day)) => format!("{:04}-{:02}-{:02}", year, month, day),
+        _ => format!("{:04}", year),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::escapes::decode_escapes;
+
+    #[test]
+    fn test_parse_info() {
+        let rtf = decode_escapes(
+            r"{\rtf1\ansi{\fonttbl\f0 Arial;}{\info{\title Caf\'e9 \{Stories\}}
+{\author Jane Doe}{\subject Short fiction}{\keywords cafes, coffee}
+{\doccomm A collection.}{\creatim\yr2021\mo3\dy7\hr10\min5}{\nofpages3}}
+\f0 Body text.\par}",
+        );
+        let mut book = BookDocument::new();
+        parse_info(&rtf, &mut book);
+
+        assert_eq!(book.metadata.title(), Some("Café {Stories}"));
+        assert_eq!(book.metadata.authors(), vec!["Jane Doe"]);
+        let subjects: Vec<&str> = book
+            .metadata
+            .get("subject")
+            .unwrap()
+            .iter()
+            .map(|s| s.value.as_str())
+            .collect();
+        assert_eq!(subjects, vec!["Short fiction", "cafes", "coffee"]);
+        assert_eq!(book.metadata.description(), Some("A collection."));
+        assert_eq!(book.metadata.date(), Some("2021-03-07"));
+
+        let body = without_info(&rtf);
+        assert!(!body.contains("Jane Doe"));
+        assert!(body.contains("Body text."));
+    }
+
+    #[test]
+    fn test_no_info_group() {
+        let mut book = BookDocument::new();
+        parse_info(r"{\rtf1\ansi{\*\information x} Text\par}", &mut book);
+        assert_eq!(book.metadata.title(), None);
+    }
+}
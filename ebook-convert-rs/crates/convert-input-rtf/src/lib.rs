@@ -1,7 +1,12 @@
 //! RTF input plugin — reads Rich Text Format files into BookDocument.
 //!
 //! Uses the `rtf-parser` crate for tokenization and parsing, then converts
-//! the styled blocks into HTML content.
+//! the styled blocks into HTML content. Character escapes are decoded
+//! beforehand (see [`escapes`]). Title, author and other metadata come from
+//! the `\info` group (see [`info`]).
+
+mod escapes;
+mod info;
 
 use std::path::Path;
 
@@ -9,7 +14,6 @@ use convert_core::book::{BookDocument, EbookFormat, ManifestData, ManifestItem,
 use convert_core::error::{ConvertError, Result};
 use convert_core::options::ConversionOptions;
 use convert_core::plugin::InputPlugin;
-use regex::Regex;
 use rtf_parser::{Lexer, Parser};
 
 pub struct RtfInputPlugin;
@@ -30,29 +34,29 @@ impl InputPlugin for RtfInputPlugin {
 }
 
 fn parse_rtf(path: &Path) -> Result<BookDocument> {
-    let content = std::fs::read_to_string(path)
+    let content = convert_utils::encoding::read_text_file(path)
         .map_err(|e| ConvertError::Rtf(format!("Cannot read {}: {}", path.display(), e)))?;
 
-    let tokens = Lexer::scan(&content)
-        .map_err(|e| ConvertError::Rtf(format!("RTF lexer error: {:?}", e)))?;
-
-    let doc = Parser::new(tokens)
-        .parse()
-        .map_err(|e| ConvertError::Rtf(format!("RTF parser error: {:?}", e)))?;
+    let content = escapes::decode_escapes(&content);
+    let html = decoded_to_html(&content)?;
 
     let mut book = BookDocument::new();
     book.base_path = path.parent().map(|p| p.to_path_buf());
 
-    // Extract title from filename
-    let title = path
-        .file_stem()
-        .and_then(|s| s.to_str())
-        .unwrap_or("Untitled")
-        .to_string();
-    book.metadata.set_title(&title);
-
-    // Convert styled blocks to HTML
-    let html = blocks_to_html(&doc);
+    // Title from the \info group, else from the filename
+    info::parse_info(&content, &mut book);
+    let title = match book.metadata.title() {
+        Some(title) => title.to_string(),
+        None => {
+            let title = path
+                .file_stem()
+                .and_then(|s| s.to_str())
+                .unwrap_or("Untitled")
+                .to_string();
+            book.metadata.set_title(&title);
+            title
+        }
+    };
 
     // Wrap in XHTML
     let xhtml = convert_utils::xml::xhtml11_document(&title, "en", Some("style.css"), &html);
@@ -82,79 +86,164 @@ h1, h2, h3 { text-indent: 0; margin: 1em 0 0.5em; }
     );
     book.manifest.add(css_item);
 
-    // Build TOC from headings in the HTML
-    build_toc(&html, &mut book);
+    // A title entry; the TocFromHeadings transform builds the rest from headings
+    book.toc.add(TocEntry::new(&title, "content.xhtml"));
 
     log::info!("Parsed RTF: \"{}\"", title);
 
     Ok(book)
 }
 
+/// Convert RTF source into HTML body content (a sequence of `<p>` blocks).
+///
+/// Also used by other input plugins for embedded RTF, e.g. DOCX `altChunk` parts.
+pub fn rtf_to_html(content: &str) -> Result<String> {
+    decoded_to_html(&escapes::decode_escapes(content))
+}
+
+/// [`rtf_to_html`] for source whose escapes are already decoded.
+fn decoded_to_html(content: &str) -> Result<String> {
+    let content = info::without_info(content);
+    let tokens = Lexer::scan(&content)
+        .map_err(|e| ConvertError::Rtf(format!("RTF lexer error: {:?}", e)))?;
+
+    let doc = Parser::new(tokens)
+        .parse()
+        .map_err(|e| ConvertError::Rtf(format!("RTF parser error: {:?}", e)))?;
+
+    Ok(blocks_to_html(&doc))
+}
+
+/// A table row being collected between `\trowd` and `\row`.
+#[derive(Default)]
+struct TableRow {
+    /// Right boundaries of the cells (`\cellx`), in twips.
+    boundaries: Vec<i32>,
+    /// Finished cells' HTML.
+    cells: Vec<String>,
+    /// HTML of the cell being filled.
+    current: String,
+}
+
+impl TableRow {
+    /// The row as a `<tr>`, with cell widths from the `\cellx` boundaries
+    /// when they match the cells.
+    fn to_html(&self) -> String {
+        let mut bounds = self.boundaries.clone();
+        bounds.sort_unstable();
+        let total = bounds.last().copied().unwrap_or(0);
+        let widths = (bounds.len() == self.cells.len() && total > 0).then(|| {
+            let mut left = 0;
+            bounds
+                .iter()
+                .map(|&right| {
+                    let width = (right - left) * 100 / total;
+                    left = right;
+                    width
+                })
+                .collect::<Vec<_>>()
+        });
+
+        let mut html = String::from("<tr>");
+        for (i, cell) in self.cells.iter().enumerate() {
+            match widths.as_ref() {
+                Some(w) => html.push_str(&format!(r#"<td style="width: {}%">"#, w[i])),
+                None => html.push_str("<td>"),
+            }
+            html.push_str(cell.trim());
+            html.push_str("</td>");
+        }
+        html.push_str("</tr>\n");
+        html
+    }
+}
+
 /// Convert rtf-parser's styled blocks into HTML.
+///
+/// Table rows arrive as the markers from [`escapes`] inside the block text
+/// and become `<table>` rows; everything else becomes `<p>` blocks.
 fn blocks_to_html(doc: &rtf_parser::RtfDocument) -> String {
     let mut html = String::new();
     let mut in_para = false;
+    let mut in_table = false;
+    let mut row: Option<TableRow> = None;
+
     for block in &doc.body {
-        let text = &block.text;
-        let painter = &block.painter;
-
-        // Handle paragraph breaks
-        if text == "\n" || text == "\r\n" {
-            if in_para {
-                html.push_str("</p>\n");
-                in_para = false;
+        let mut rest = block.text.as_str();
+        while !rest.is_empty() {
+            let end = rest
+                .find([
+                    escapes::ROW_DEF,
+                    escapes::CELL_X,
+                    escapes::CELL_END,
+                    escapes::ROW_END,
+                ])
+                .unwrap_or(rest.len());
+            let text = &rest[..end];
+            rest = &rest[end..];
+
+            if let Some(row) = row.as_mut() {
+                if !text.trim().is_empty() {
+                    row.current.push_str(&format_run(text, &block.painter));
+                }
+            } else if !text.is_empty() {
+                if in_table && !text.trim().is_empty() {
+                    html.push_str("</table>\n");
+                    in_table = false;
+                }
+                push_text(&mut html, &mut in_para, text, block);
             }
-            continue;
-        }
 
-        if text.trim().is_empty() && !in_para {
-            continue;
-        }
-
-        // Start new paragraph if needed
-        if !in_para {
-            let align_class = match block.paragraph.alignment {
-                rtf_parser::Alignment::Center => " class=\"center\"",
-                rtf_parser::Alignment::RightAligned => " class=\"right\"",
-                rtf_parser::Alignment::Justify => " class=\"justify\"",
-                _ => "",
+            let Some(marker) = rest.chars().next() else {
+                break;
             };
-            html.push_str(&format!("<p{}>", align_class));
-            in_para = true;
+            rest = &rest[marker.len_utf8()..];
+            match marker {
+                escapes::ROW_DEF => {
+                    if in_para {
+                        html.push_str("</p>\n");
+                        in_para = false;
+                    }
+                    row.get_or_insert_with(TableRow::default).boundaries.clear();
+                }
+                escapes::CELL_X => {
+                    let end = rest.find(';').unwrap_or(rest.len());
+                    if let (Some(row), Ok(x)) = (row.as_mut(), rest[..end].parse()) {
+                        row.boundaries.push(x);
+                    }
+                    rest = rest.get(end + 1..).unwrap_or("");
+                }
+                escapes::CELL_END => {
+                    if let Some(row) = row.as_mut() {
+                        let cell = std::mem::take(&mut row.current);
+                        row.cells.push(cell);
+                    }
+                }
+                _ => {
+                    if let Some(row) = row.take() {
+                        if !in_table {
+                            html.push_str("<table>\n");
+                            in_table = true;
+                        }
+                        html.push_str(&row.to_html());
+                    }
+                }
+            }
         }
+    }
 
-        // Apply character formatting
-        let escaped = convert_utils::xml::escape_xml_text(text);
-        let mut formatted = escaped.to_string();
-
-        if painter.bold {
-            formatted = format!("<strong>{}</strong>", formatted);
-        }
-        if painter.italic {
-            formatted = format!("<em>{}</em>", formatted);
-        }
-        if painter.underline {
-            formatted = format!("<u>{}</u>", formatted);
-        }
-        if painter.strike {
-            formatted = format!("<del>{}</del>", formatted);
-        }
-        if painter.superscript {
-            formatted = format!("<sup>{}</sup>", formatted);
-        }
-        if painter.subscript {
-            formatted = format!("<sub>{}</sub>", formatted);
-        }
-        if painter.smallcaps {
-            formatted = format!(
-                r#"<span style="font-variant: small-caps">{}</span>"#,
-                formatted
-            );
+    if let Some(row) = row.take() {
+        if !row.cells.is_empty() {
+            if !in_table {
+                html.push_str("<table>\n");
+                in_table = true;
+            }
+            html.push_str(&row.to_html());
         }
-
-        html.push_str(&formatted);
     }
-
+    if in_table {
+        html.push_str("</table>\n");
+    }
     if in_para {
         html.push_str("</p>\n");
     }
@@ -162,25 +251,66 @@ fn blocks_to_html(doc: &rtf_parser::RtfDocument) -> String {
     html
 }
 
-/// Build TOC from heading-like content in HTML.
-fn build_toc(html: &str, book: &mut BookDocument) {
-    let heading_re = Regex::new(r"(?i)<h([1-3])[^>]*>(.*?)</h[1-3]>").unwrap();
-    let tag_re = Regex::new(r"<[^>]+>").unwrap();
-
-    let mut found = false;
-    for cap in heading_re.captures_iter(html) {
-        let title = tag_re.replace_all(&cap[2], "").trim().to_string();
-        if !title.is_empty() {
-            book.toc.add(TocEntry::new(&title, "content.xhtml"));
-            found = true;
+/// Append a run of paragraph text, opening or closing `<p>` as needed.
+fn push_text(html: &mut String, in_para: &mut bool, text: &str, block: &rtf_parser::StyleBlock) {
+    // Handle paragraph breaks
+    if text == "\n" || text == "\r\n" {
+        if *in_para {
+            html.push_str("</p>\n");
+            *in_para = false;
         }
+        return;
     }
 
-    if !found {
-        if let Some(title) = book.metadata.title() {
-            book.toc.add(TocEntry::new(title, "content.xhtml"));
-        }
+    if text.trim().is_empty() && !*in_para {
+        return;
+    }
+
+    // Start new paragraph if needed
+    if !*in_para {
+        let align_class = match block.paragraph.alignment {
+            rtf_parser::Alignment::Center => " class=\"center\"",
+            rtf_parser::Alignment::RightAligned => " class=\"right\"",
+            rtf_parser::Alignment::Justify => " class=\"justify\"",
+            _ => "",
+        };
+        html.push_str(&format!("<p{}>", align_class));
+        *in_para = true;
+    }
+
+    html.push_str(&format_run(text, &block.painter));
+}
+
+/// Escape a run of text and wrap it in its character formatting.
+fn format_run(text: &str, painter: &rtf_parser::Painter) -> String {
+    let escaped = convert_utils::xml::escape_xml_text(text);
+    let mut formatted = escaped.to_string();
+
+    if painter.bold {
+        formatted = format!("<strong>{}</strong>", formatted);
+    }
+    if painter.italic {
+        formatted = format!("<em>{}</em>", formatted);
+    }
+    if painter.underline {
+        formatted = format!("<u>{}</u>", formatted);
+    }
+    if painter.strike {
+        formatted = format!("<del>{}</del>", formatted);
+    }
+    if painter.superscript {
+        formatted = format!("<sup>{}</sup>", formatted);
     }
+    if painter.subscript {
+        formatted = format!("<sub>{}</sub>", formatted);
+    }
+    if painter.smallcaps {
+        formatted = format!(
+            r#"<span style="font-variant: small-caps">{}</span>"#,
+            formatted
+        );
+    }
+    formatted
 }
 
 #[cfg(test)]
@@ -238,6 +368,41 @@ mod tests {
         let _ = std::fs::remove_dir_all(&dir);
     }
 
+    #[test]
+    fn test_unicode_and_codepage_text() {
+        let rtf = r"{\rtf1\ansi\ansicpg1251\deff0{\fonttbl{\f0\fcharset204 Times New Roman;}}
+\f0\fs24 \u1055?\u1088?\u1080?\u1074?\u1077?\u1090? \'ec\'e8\'f0!
+\par
+}";
+        let html = rtf_to_html(rtf).unwrap();
+        assert!(html.contains("Привет мир!"), "{}", html);
+    }
+
+    #[test]
+    fn test_table() {
+        let rtf = r"{\rtf1\ansi{\fonttbl\f0 Arial;}
+\f0 Before the table.
+\trowd\cellx2000\cellx6000
+\pard\intbl Name\cell \b Value\b0\cell\row
+\trowd\cellx2000\cellx6000
+\pard\intbl Alpha\cell 1\cell\row
+\pard After the table.
+}";
+        let html = rtf_to_html(rtf).unwrap();
+        let cells: Vec<&str> = html.matches("<td").collect();
+        assert_eq!(cells.len(), 4, "{}", html);
+        assert!(html.contains(
+            r#"<tr><td style="width: 33%">Name</td><td style="width: 66%"><strong>Value</strong></td></tr>"#
+        ), "{}", html);
+        assert!(html.contains(
+            r#"<tr><td style="width: 33%">Alpha</td><td style="width: 66%">1</td></tr>"#
+        ));
+        assert_eq!(html.matches("<table>").count(), 1);
+        let table = html.find("<table>").unwrap();
+        assert!(html.find("Before").unwrap() < table);
+        assert!(html.find("After").unwrap() > html.find("</table>").unwrap());
+    }
+
     #[test]
     fn test_blocks_to_html_empty() {
         let doc = rtf_parser::RtfDocument {
@@ -249,21 +414,36 @@ mod tests {
     }
 
     #[test]
-    fn test_build_toc_with_headings() {
-        let html = "<h1>Chapter 1</h1><p>text</p><h2>Section 1.1</h2>";
-        let mut book = BookDocument::new();
-        book.metadata.set_title("Test");
-        build_toc(html, &mut book);
-        assert_eq!(book.toc.entries.len(), 2);
+    fn test_info_metadata() {
+        let rtf = r"{\rtf1\ansi{\info{\title The Real Title}{\author Ann Author}}
+No headings here.\par}";
+        let path = std::env::temp_dir().join("rtf_info_filename.rtf");
+        std::fs::write(&path, rtf).unwrap();
+        let book = parse_rtf(&path).unwrap();
+        std::fs::remove_file(&path).ok();
+
+        assert_eq!(book.metadata.title(), Some("The Real Title"));
+        assert_eq!(book.metadata.authors(), vec!["Ann Author"]);
+        assert_eq!(book.toc.entries[0].title, "The Real Title");
+        let xhtml = book
+            .manifest
+            .by_id("content")
+            .unwrap()
+            .data
+            .as_xhtml()
+            .unwrap();
+        assert!(xhtml.contains("<title>The Real Title</title>"));
+        assert!(!xhtml.contains("Ann Author"), "{}", xhtml);
     }
 
     #[test]
-    fn test_build_toc_fallback() {
-        let html = "<p>No headings here.</p>";
-        let mut book = BookDocument::new();
-        book.metadata.set_title("My Book");
-        build_toc(html, &mut book);
+    fn test_toc_has_title_entry() {
+        let path = std::env::temp_dir().join("My Book.rtf");
+        std::fs::write(&path, r"{\rtf1\ansi No headings here.\par}").unwrap();
+        let book = parse_rtf(&path).unwrap();
+        std::fs::remove_file(&path).ok();
         assert_eq!(book.toc.entries.len(), 1);
         assert_eq!(book.toc.entries[0].title, "My Book");
+        assert_eq!(book.toc.entries[0].href, "content.xhtml");
     }
 }
@@ -0,0 +1,189 @@
+//! YAML front matter at the start of Markdown files.
+//!
+//! ```text
+//! ---
+//! title: "The Long Road"
+//! author: [Ann Smith, Bo Lee]
+//! date: 2024-05-01
+//! tags:
+//!   - travel
+//!   - memoir
+//! ---
+//! ```
+//!
+//! Only the flat `key: value` subset used for document metadata is read:
+//! plain or quoted scalars, `[a, b]` flow lists and `- item` block lists.
+//! A block using anything else is not treated as front matter, since a `---`
+//! line also starts a thematic break in Markdown.
+
+use convert_core::book::Metadata;
+
+/// Metadata from a front matter block.
+#[derive(Debug, Default, PartialEq)]
+pub struct FrontMatter {
+    pub title: Option<String>,
+    pub authors: Vec<String>,
+    pub date: Option<String>,
+    pub tags: Vec<String>,
+}
+
+impl FrontMatter {
+    /// Copy the front matter into `metadata`, replacing the values it sets.
+    pub fn apply(&self, metadata: &mut Metadata) {
+        if let Some(title) = &self.title {
+            metadata.set_title(title.as_str());
+        }
+        if !self.authors.is_empty() {
+            metadata.remove("creator");
+            for author in &self.authors {
+                metadata.add("creator", author.as_str());
+            }
+        }
+        if let Some(date) = &self.date {
+            metadata.set("date", date.as_str());
+        }
+        for tag in &self.tags {
+            metadata.add("subject", tag.as_str());
+        }
+    }
+}
+
+/// Split a leading front matter block from `text`, returning it and the
+/// Markdown after it. Text without front matter is returned unchanged.
+pub fn split(text: &str) -> (Option<FrontMatter>, &str) {
+    let body = text.strip_prefix('\u{feff}').unwrap_or(text);
+    let Some(rest) = strip_delimiter(body, "---") else {
+        return (None, text);
+    };
+
+    let mut yaml = Vec::new();
+    let mut remaining = rest;
+    let markdown = loop {
+        let Some(line) = remaining.split_inclusive('\n').next() else {
+            // No closing delimiter
+            return (None, text);
+        };
+        let after = &remaining[line.len()..];
+        let trimmed = line.trim_end();
+        if trimmed == "---" || trimmed == "..." {
+            break after;
+        }
+        yaml.push(trimmed);
+        remaining = after;
+    };
+
+    match parse(&yaml) {
+        Some(front_matter) => (Some(front_matter), markdown),
+        None => (None, text),
+    }
+}
+
+/// `text` after its first line, when that line is `delimiter`.
+fn strip_delimiter<'a>(text: &'a str, delimiter: &str) -> Option<&'a str> {
+    let (first, rest) = text.split_once('\n').unwrap_or((text, ""));
+    (first.trim_end() == delimiter).then_some(rest)
+}
+
+/// Parse front matter lines, or `None` if they are not the supported subset.
+fn parse(lines: &[&str]) -> Option<FrontMatter> {
+    let mut entries: Vec<(String, Vec<String>)> = Vec::new();
+    for line in lines {
+        let content = line.trim_start();
+        if content.is_empty() || content.starts_with('#') {
+            continue;
+        }
+        if let Some(item) = content
+            .strip_prefix("- ")
+            .or((content == "-").then_some(""))
+        {
+            // Block list item of the previous key
+            let (_, values) = entries.last_mut()?;
+            values.push(scalar(item));
+        } else if !line.starts_with(' ') {
+            let (key, value) = content.split_once(':')?;
+            let key = key.trim();
+            if key.is_empty() || key.contains(' ') {
+                return None;
+            }
+            let value = value.trim();
+            let values = match value.strip_prefix('[').and_then(|v| v.strip_suffix(']')) {
+                Some(list) => list.split(',').map(scalar).collect(),
+                None if value.is_empty() => Vec::new(),
+                None => vec![scalar(value)],
+            };
+            entries.push((key.to_ascii_lowercase(), values));
+        } else {
+            return None;
+        }
+    }
+    if entries.is_empty() {
+        return None;
+    }
+
+    let mut front_matter = FrontMatter::default();
+    for (key, values) in entries {
+        let mut values = values.into_iter().filter(|v| !v.is_empty());
+        match key.as_str() {
+            "title" => front_matter.title = values.next(),
+            "author" | "authors" => front_matter.authors.extend(values),
+            "date" => front_matter.date = values.next(),
+            "tags" | "keywords" => front_matter.tags.extend(values),
+            _ => {}
+        }
+    }
+    Some(front_matter)
+}
+
+/// A scalar value without its quotes and trailing comment.
+fn scalar(value: &str) -> String {
+    let value = value.trim();
+    for quote in ['"', '\''] {
+        if let Some(inner) = value
+            .strip_prefix(quote)
+            .and_then(|v| v.strip_suffix(quote))
+        {
+            return if quote == '"' {
+                inner.replace("\\\"", "\"")
+            } else {
+                inner.replace("''", "'")
+            };
+        }
+    }
+    match value.find(" #") {
+        Some(comment) => value[..comment].trim_end().to_string(),
+        None => value.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_front_matter() {
+        let text = "---\ntitle: \"The Long Road\"\nauthor: [Ann Smith, 'Bo Lee']\ndate: 2024-05-01 # published\ntags:\n  - travel\n  - memoir\nlayout: post\n---\n# Chapter One\n";
+        let (front_matter, markdown) = split(text);
+        assert_eq!(
+            front_matter,
+            Some(FrontMatter {
+                title: Some("The Long Road".to_string()),
+                authors: vec!["Ann Smith".to_string(), "Bo Lee".to_string()],
+                date: Some("2024-05-01".to_string()),
+                tags: vec!["travel".to_string(), "memoir".to_string()],
+            })
+        );
+        assert_eq!(markdown, "# Chapter One\n");
+    }
+
+    #[test]
+    fn test_thematic_breaks_are_not_front_matter() {
+        // A setext heading between two breaks
+        let text = "---\nSome text\n---\n\nMore.";
+        assert_eq!(split(text), (None, text));
+        // Never closed
+        let text = "---\ntitle: x\n\nBody.";
+        assert_eq!(split(text), (None, text));
+        let text = "Intro\n---\ntitle: x\n---\n";
+        assert_eq!(split(text), (None, text));
+    }
+}
@@ -1,10 +1,17 @@
-//! TXT/Markdown input plugin — reads plain text or Markdown into BookDocument.
+//! TXT/Markdown input plugin — reads plain text, Markdown, reStructuredText
+//! or Textile into BookDocument.
 
+mod front_matter;
+mod math;
+mod rst;
+mod textile;
+
+use std::collections::{HashMap, HashSet};
 use std::path::Path;
 
 use convert_core::book::{BookDocument, EbookFormat, ManifestData, ManifestItem, TocEntry};
 use convert_core::error::{ConvertError, Result};
-use convert_core::options::ConversionOptions;
+use convert_core::options::{ConversionOptions, TxtParagraphMode};
 use convert_core::plugin::InputPlugin;
 
 pub struct TxtInputPlugin;
@@ -15,13 +22,18 @@ impl InputPlugin for TxtInputPlugin {
     }
 
     fn supported_formats(&self) -> &[EbookFormat] {
-        &[EbookFormat::Txt, EbookFormat::Markdown]
+        &[
+            EbookFormat::Txt,
+            EbookFormat::Markdown,
+            EbookFormat::Rst,
+            EbookFormat::Textile,
+        ]
     }
 
-    fn convert(&self, input_path: &Path, _options: &ConversionOptions) -> Result<BookDocument> {
+    fn convert(&self, input_path: &Path, options: &ConversionOptions) -> Result<BookDocument> {
         log::info!("Reading text: {}", input_path.display());
 
-        let content = std::fs::read_to_string(input_path).map_err(|e| {
+        let content = convert_utils::encoding::read_text_file(input_path).map_err(|e| {
             ConvertError::Other(format!("Cannot read {}: {}", input_path.display(), e))
         })?;
 
@@ -30,94 +42,429 @@ impl InputPlugin for TxtInputPlugin {
             .and_then(|e| e.to_str())
             .unwrap_or("txt");
 
-        let is_markdown = matches!(ext.to_lowercase().as_str(), "md" | "markdown");
-
-        let title = input_path
+        let mut title = input_path
             .file_stem()
             .and_then(|s| s.to_str())
             .unwrap_or("Untitled")
             .to_string();
 
-        let mut book = BookDocument::new();
-        book.metadata.set_title(&title);
-        book.metadata.set("language", "en");
-
-        let xhtml = if is_markdown {
-            markdown_to_xhtml(&title, &content)
-        } else {
-            plaintext_to_xhtml(&title, &content)
+        let mut front_matter = None;
+        let xhtml = match EbookFormat::from_extension(ext) {
+            Some(EbookFormat::Markdown) => {
+                let (metadata, markdown) = front_matter::split(&content);
+                if let Some(front_title) = metadata.as_ref().and_then(|m| m.title.as_ref()) {
+                    title = front_title.clone();
+                }
+                front_matter = metadata;
+                markdown_to_xhtml(&title, markdown, options.markdown_math)
+            }
+            Some(EbookFormat::Rst) => match rst::to_html(&content) {
+                Ok(body) => {
+                    convert_utils::xml::xhtml11_document(&title, "en", Some("style.css"), &body)
+                }
+                Err(e) => {
+                    log::warn!(
+                        "Reading {} as plain text: not valid reStructuredText ({})",
+                        input_path.display(),
+                        e
+                    );
+                    plaintext_to_xhtml(&title, &content, options.txt_paragraph_mode)
+                }
+            },
+            Some(EbookFormat::Textile) => convert_utils::xml::xhtml11_document(
+                &title,
+                "en",
+                Some("style.css"),
+                &textile::to_html(&content),
+            ),
+            _ => plaintext_to_xhtml(&title, &content, options.txt_paragraph_mode),
         };
 
-        let item = ManifestItem::new(
-            "content",
-            "content.xhtml",
-            "application/xhtml+xml",
-            ManifestData::Xhtml(xhtml),
-        );
-        book.manifest.add(item);
-        book.spine.push("content", true);
-        book.toc.add(TocEntry::new(&title, "content.xhtml"));
-
-        // Add default stylesheet
-        let css =
-            "body { font-family: serif; line-height: 1.6; margin: 1em; }\np { margin: 0.5em 0; }";
-        let css_item = ManifestItem::new(
-            "style",
-            "style.css",
-            "text/css",
-            ManifestData::Css(css.to_string()),
-        );
-        book.manifest.add(css_item);
-
+        let mut book = single_document_book(&title, xhtml);
+        if let Some(front_matter) = front_matter {
+            front_matter.apply(&mut book.metadata);
+        }
         Ok(book)
     }
 }
 
-/// Convert Markdown to XHTML using pulldown-cmark.
-fn markdown_to_xhtml(title: &str, markdown: &str) -> String {
-    use pulldown_cmark::{html, Parser};
+/// Build a book from plain text, split into paragraphs according to
+/// `options.txt_paragraph_mode`. Shared with the other plain-text inputs.
+pub fn plaintext_book(title: &str, text: &str, options: &ConversionOptions) -> BookDocument {
+    single_document_book(
+        title,
+        plaintext_to_xhtml(title, text, options.txt_paragraph_mode),
+    )
+}
+
+/// A book whose content is the single XHTML document `xhtml`.
+fn single_document_book(title: &str, xhtml: String) -> BookDocument {
+    let mut book = BookDocument::new();
+    book.metadata.set_title(title);
+    book.metadata.set("language", "en");
+
+    let item = ManifestItem::new(
+        "content",
+        "content.xhtml",
+        "application/xhtml+xml",
+        ManifestData::Xhtml(xhtml),
+    );
+    book.manifest.add(item);
+    book.spine.push("content", true);
+    book.toc.add(TocEntry::new(title, "content.xhtml"));
 
-    let parser = Parser::new(markdown);
+    // Add default stylesheet
+    let css = "body { font-family: serif; line-height: 1.6; margin: 1em; }\np { margin: 0.5em 0; }";
+    let css_item = ManifestItem::new(
+        "style",
+        "style.css",
+        "text/css",
+        ManifestData::Css(css.to_string()),
+    );
+    book.manifest.add(css_item);
+
+    book
+}
+
+/// Convert Markdown to XHTML using pulldown-cmark, with the GitHub
+/// extensions: tables, footnotes, strikethrough and task lists.
+///
+/// With `math` set, `$...$` and `$$...$$` spans are converted to MathML.
+fn markdown_to_xhtml(title: &str, markdown: &str, math: bool) -> String {
+    use pulldown_cmark::{html, Event, Options, Parser, Tag, TagEnd};
+
+    let mut parser_options = Options::ENABLE_TABLES
+        | Options::ENABLE_FOOTNOTES
+        | Options::ENABLE_STRIKETHROUGH
+        | Options::ENABLE_TASKLISTS;
+    if math {
+        parser_options.insert(Options::ENABLE_MATH);
+    }
+    // Footnotes are numbered in order of first use. pulldown-cmark would use
+    // the labels as IDs, which need not be valid XML names.
+    let mut footnotes: HashMap<String, usize> = HashMap::new();
+    let mut referenced: HashSet<usize> = HashSet::new();
+    let mut number = |label: &str| {
+        let next = footnotes.len() + 1;
+        *footnotes.entry(label.to_string()).or_insert(next)
+    };
+    let parser = Parser::new_ext(markdown, parser_options).map(|event| match event {
+        Event::InlineMath(tex) => Event::InlineHtml(math::render(&tex, false).into()),
+        Event::DisplayMath(tex) => Event::InlineHtml(math::render(&tex, true).into()),
+        Event::FootnoteReference(label) => {
+            let n = number(&label);
+            // Only the first reference gets the ID the note links back to
+            let id = if referenced.insert(n) {
+                format!(" id=\"fnref-{}\"", n)
+            } else {
+                String::new()
+            };
+            Event::InlineHtml(
+                format!(
+                    "<sup class=\"footnote-reference\"><a{} href=\"#fn-{}\">{}</a></sup>",
+                    id, n, n
+                )
+                .into(),
+            )
+        }
+        Event::Start(Tag::FootnoteDefinition(label)) => {
+            let n = number(&label);
+            let label = if referenced.contains(&n) {
+                format!("<a href=\"#fnref-{}\">{}</a>", n, n)
+            } else {
+                n.to_string()
+            };
+            Event::Html(
+                format!(
+                    "<div class=\"footnote-definition\" id=\"fn-{}\"><sup class=\"footnote-definition-label\">{}</sup>\n",
+                    n, label
+                )
+                .into(),
+            )
+        }
+        Event::End(TagEnd::FootnoteDefinition) => Event::Html("</div>\n".into()),
+        other => other,
+    });
     let mut html_output = String::new();
     html::push_html(&mut html_output, parser);
 
     convert_utils::xml::xhtml11_document(title, "en", Some("style.css"), &html_output)
 }
 
+/// Wrapped lines at least this many characters long count as prose when
+/// auto-detecting hard-wrapped text; shorter ones suggest verse or lists.
+const MIN_PROSE_LINE: usize = 40;
+
 /// Convert plain text to XHTML with paragraph detection.
-fn plaintext_to_xhtml(title: &str, text: &str) -> String {
+fn plaintext_to_xhtml(title: &str, text: &str, mode: TxtParagraphMode) -> String {
+    let text = text.replace("\r\n", "\n");
+    let hard_wrap = match mode {
+        TxtParagraphMode::BlankLine => false,
+        TxtParagraphMode::HardWrap => true,
+        TxtParagraphMode::Auto => looks_hard_wrapped(&text),
+    };
+
+    let paragraphs: Vec<String> = if hard_wrap {
+        reflow_paragraphs(&text)
+    } else {
+        text.split("\n\n")
+            .map(|para| para.trim().to_string())
+            .filter(|para| !para.is_empty())
+            .collect()
+    };
+
     let mut body = String::new();
-    for para in text.split("\n\n") {
-        let para = para.trim();
-        if !para.is_empty() {
-            let escaped = para
-                .replace('&', "&amp;")
-                .replace('<', "&lt;")
-                .replace('>', "&gt;")
-                .replace('\n', "<br/>\n");
-            body.push_str(&format!("<p>{}</p>\n", escaped));
-        }
+    for para in &paragraphs {
+        let escaped = para
+            .replace('&', "&amp;")
+            .replace('<', "&lt;")
+            .replace('>', "&gt;")
+            .replace('\n', "<br/>\n");
+        body.push_str(&format!("<p>{}</p>\n", escaped));
     }
 
     convert_utils::xml::xhtml11_document(title, "en", Some("style.css"), &body)
 }
 
+/// Join hard-wrapped lines into paragraphs. A paragraph ends at a blank line
+/// or where an indented line follows an unindented one.
+fn reflow_paragraphs(text: &str) -> Vec<String> {
+    let mut paragraphs = Vec::new();
+    let mut current: Vec<&str> = Vec::new();
+    let mut prev_indented = false;
+
+    for line in text.lines() {
+        let trimmed = line.trim();
+        let indented = line.starts_with([' ', '\t']);
+        let starts_paragraph = trimmed.is_empty() || (indented && !prev_indented);
+        if starts_paragraph && !current.is_empty() {
+            paragraphs.push(current.join(" "));
+            current.clear();
+        }
+        if !trimmed.is_empty() {
+            current.push(trimmed);
+        }
+        prev_indented = indented;
+    }
+    if !current.is_empty() {
+        paragraphs.push(current.join(" "));
+    }
+    paragraphs
+}
+
+/// Whether most lines that are followed by another line of the same block
+/// are prose-length, i.e. the text was wrapped at a fixed column.
+fn looks_hard_wrapped(text: &str) -> bool {
+    let mut wrapped = 0;
+    let mut prose = 0;
+    for block in text.split("\n\n") {
+        let lines: Vec<&str> = block.lines().filter(|l| !l.trim().is_empty()).collect();
+        for line in lines.iter().take(lines.len().saturating_sub(1)) {
+            wrapped += 1;
+            if line.trim().chars().count() >= MIN_PROSE_LINE {
+                prose += 1;
+            }
+        }
+    }
+    wrapped > 0 && prose * 2 > wrapped
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
     #[test]
     fn test_plaintext_to_xhtml() {
-        let xhtml = plaintext_to_xhtml("Test", "Hello World\n\nSecond paragraph");
+        let xhtml = plaintext_to_xhtml(
+            "Test",
+            "Hello World\n\nSecond paragraph",
+            TxtParagraphMode::Auto,
+        );
         assert!(xhtml.contains("<title>Test</title>"));
         assert!(xhtml.contains("<p>Hello World</p>"));
         assert!(xhtml.contains("<p>Second paragraph</p>"));
     }
 
+    const HARD_WRAPPED: &str = "It was the best of times, it was the worst of times, it was\r\n\
+the age of wisdom, it was the age of foolishness.\r\n\
+\r\n\
+There were a king with a large jaw and a queen with a plain\r\n\
+face, on the throne of England.\r\n\
+\tIn both countries it was clearer than crystal to the lords\r\n\
+of the State preserves of loaves and fishes.\r\n";
+
+    #[test]
+    fn test_plaintext_hard_wrap_reflow() {
+        for mode in [TxtParagraphMode::HardWrap, TxtParagraphMode::Auto] {
+            let xhtml = plaintext_to_xhtml("Test", HARD_WRAPPED, mode);
+            assert!(!xhtml.contains("<br"));
+            assert!(xhtml.contains(
+                "<p>It was the best of times, it was the worst of times, it was \
+                 the age of wisdom, it was the age of foolishness.</p>"
+            ));
+            assert!(xhtml.contains(
+                "<p>There were a king with a large jaw and a queen with a plain \
+                 face, on the throne of England.</p>"
+            ));
+            assert!(xhtml.contains("<p>In both countries it was clearer"));
+        }
+    }
+
+    #[test]
+    fn test_plaintext_blank_line_keeps_breaks() {
+        let verse = "Roses are red,\nViolets are blue.\n\nSugar is sweet.";
+        let auto = plaintext_to_xhtml("Test", verse, TxtParagraphMode::Auto);
+        assert!(auto.contains("<p>Roses are red,<br/>\nViolets are blue.</p>"));
+
+        let forced = plaintext_to_xhtml("Test", HARD_WRAPPED, TxtParagraphMode::BlankLine);
+        assert!(forced.contains("<br/>"));
+    }
+
     #[test]
     fn test_markdown_to_xhtml() {
-        let xhtml = markdown_to_xhtml("Test", "# Heading\n\nA **bold** paragraph.");
+        let xhtml = markdown_to_xhtml("Test", "# Heading\n\nA **bold** paragraph.", false);
         assert!(xhtml.contains("<h1>Heading</h1>"));
         assert!(xhtml.contains("<strong>bold</strong>"));
     }
+
+    #[test]
+    fn test_markdown_table() {
+        let md = "| Name | Age |\n|:-----|----:|\n| Ann  | 30  |\n";
+        let xhtml = markdown_to_xhtml("Test", md, false);
+        assert!(xhtml.contains("<table>"), "{}", xhtml);
+        assert!(xhtml.contains("<th style=\"text-align: left\">Name</th>"));
+        assert!(xhtml.contains("<td style=\"text-align: right\">30</td>"));
+        assert!(xhtml.contains("</tbody></table>"));
+    }
+
+    #[test]
+    fn test_markdown_footnotes() {
+        let md =
+            "Text[^note] and more[^2], again[^note].\n\n[^note]: The note.\n\n[^2]: Another.\n";
+        let xhtml = markdown_to_xhtml("Test", md, false);
+        assert!(
+            xhtml.contains(
+                "<sup class=\"footnote-reference\"><a id=\"fnref-1\" href=\"#fn-1\">1</a></sup>"
+            ),
+            "{}",
+            xhtml
+        );
+        assert!(xhtml.contains("<a id=\"fnref-2\" href=\"#fn-2\">2</a>"));
+        assert!(xhtml.contains("again<sup class=\"footnote-reference\"><a href=\"#fn-1\">1</a>"));
+        assert!(xhtml.contains(
+            "<div class=\"footnote-definition\" id=\"fn-1\"><sup class=\"footnote-definition-label\"><a href=\"#fnref-1\">1</a></sup>"
+        ));
+        assert!(xhtml.contains("<p>The note.</p>\n</div>"));
+        assert!(xhtml.contains("id=\"fn-2\""));
+    }
+
+    #[test]
+    fn test_markdown_front_matter() {
+        let dir = std::env::temp_dir().join("convert_input_txt_tests");
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("test_markdown_front_matter.md");
+        std::fs::write(
+            &path,
+            "---\ntitle: The Long Road\nauthor: Ann Smith\ndate: 2024-05-01\ntags: [travel, memoir]\n---\n\n# Chapter One\n\nText.\n",
+        )
+        .unwrap();
+        let book = TxtInputPlugin
+            .convert(&path, &ConversionOptions::default())
+            .unwrap();
+        let _ = std::fs::remove_file(&path);
+
+        assert_eq!(book.metadata.title(), Some("The Long Road"));
+        assert_eq!(book.metadata.authors(), vec!["Ann Smith"]);
+        assert_eq!(book.metadata.get_first_value("date"), Some("2024-05-01"));
+        assert_eq!(book.metadata.get("subject").unwrap().len(), 2);
+
+        let xhtml = book
+            .manifest
+            .by_id("content")
+            .unwrap()
+            .data
+            .as_xhtml()
+            .unwrap();
+        assert!(xhtml.contains("<title>The Long Road</title>"), "{}", xhtml);
+        assert!(xhtml.contains("<h1>Chapter One</h1>"));
+        assert!(!xhtml.contains("Ann Smith"));
+        assert!(!xhtml.contains("<hr"));
+    }
+
+    #[test]
+    fn test_markdown_strikethrough_and_task_list() {
+        let md = "Was ~~wrong~~ right.\n\n- [x] done\n- [ ] todo\n";
+        let xhtml = markdown_to_xhtml("Test", md, false);
+        assert!(xhtml.contains("<del>wrong</del>"));
+        assert!(xhtml.contains("<input disabled=\"\" type=\"checkbox\" checked=\"\"/>"));
+        assert!(xhtml.contains("<input disabled=\"\" type=\"checkbox\"/>"));
+    }
+
+    #[test]
+    fn test_markdown_math_to_mathml() {
+        let xhtml = markdown_to_xhtml("Test", "Area grows as $x^2$ here.", true);
+        assert!(xhtml.contains("<math xmlns=\"http://www.w3.org/1998/Math/MathML\""));
+        assert!(xhtml.contains("<msup><mi>x</mi><mn>2</mn></msup>"));
+        assert!(!xhtml.contains("$x^2$"));
+
+        // Disabled: delimiters pass through literally
+        let plain = markdown_to_xhtml("Test", "Area grows as $x^2$ here.", false);
+        assert!(plain.contains("$x^2$"));
+        assert!(!plain.contains("<math"));
+    }
+
+    #[test]
+    fn test_markdown_display_math_and_fallback() {
+        let xhtml = markdown_to_xhtml(
+            "Test",
+            "$$\\frac{1}{2}$$\n\nand $\\begin{cases} x \\end{cases}$ and `$y$`",
+            true,
+        );
+        assert!(xhtml.contains("display=\"block\""));
+        assert!(xhtml.contains("<mfrac><mn>1</mn><mn>2</mn></mfrac>"));
+        assert!(xhtml.contains("<code class=\"math\">$\\begin{cases} x \\end{cases}$</code>"));
+        // Code spans are left alone
+        assert!(xhtml.contains("<code>$y$</code>"));
+    }
+    /// Convert `content` saved as `name` with the TXT input plugin.
+    fn convert_file(name: &str, content: &str) -> String {
+        let dir = std::env::temp_dir().join("convert_input_txt_tests");
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(name);
+        std::fs::write(&path, content).unwrap();
+        let book = TxtInputPlugin
+            .convert(&path, &ConversionOptions::default())
+            .unwrap();
+        let _ = std::fs::remove_file(&path);
+        let item = book.manifest.by_id("content").unwrap();
+        item.data.as_xhtml().unwrap().to_string()
+    }
+
+    #[test]
+    fn test_rst_input() {
+        let xhtml = convert_file(
+            "test_rst_input.rst",
+            "Chapter One\n===========\n\nIt was **cold**.\n",
+        );
+        assert!(xhtml.contains("<h1>Chapter One</h1>"), "{}", xhtml);
+        assert!(xhtml.contains("<p>It was <strong>cold</strong>.</p>"));
+
+        // Titles that skip a level do not parse; the text is kept as it is
+        let xhtml = convert_file(
+            "test_rst_fallback.rst",
+            "One\n===\n\nTwo\n---\n\nThree\n~~~~~\n\nFour\n^^^^\n\nFive\n~~~~\n\nSix\n++++\n",
+        );
+        assert!(!xhtml.contains("<h1>"), "{}", xhtml);
+        assert!(xhtml.contains("<p>One<br/>\n===</p>"), "{}", xhtml);
+    }
+
+    #[test]
+    fn test_textile_input() {
+        let xhtml = convert_file(
+            "test_textile_input.textile",
+            "h1. Chapter One\n\nIt was *cold*.\n",
+        );
+        assert!(xhtml.contains("<h1>Chapter One</h1>"), "{}", xhtml);
+        assert!(xhtml.contains("<p>It was <strong>cold</strong>.</p>"));
+    }
 }
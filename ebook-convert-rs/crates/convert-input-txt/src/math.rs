@@ -0,0 +1,541 @@
+//! TeX math to MathML for Markdown `$...$` / `$$...$$` spans.
+//!
+//! Covers the constructs common in technical prose: identifiers, numbers,
+//! operators, Greek letters and symbols, sub/superscripts, `\frac`, `\sqrt`,
+//! accents, `\left`/`\right`, `\text` and font commands. Anything else
+//! (environments, alignment, unknown commands) is unsupported and rendered
+//! as a literal code span instead.
+
+use convert_utils::xml::{escape_xml_attr, escape_xml_text};
+
+const MATHML_NS: &str = "http://www.w3.org/1998/Math/MathML";
+
+/// MathML for a math span, or a `<code class="math">` span holding the
+/// source when it cannot be converted.
+pub fn render(tex: &str, display: bool) -> String {
+    tex_to_mathml(tex, display).unwrap_or_else(|| {
+        log::debug!("Unsupported TeX, keeping source: {}", tex);
+        let delimiter = if display { "$$" } else { "$" };
+        format!(
+            r#"<code class="math">{0}{1}{0}</code>"#,
+            delimiter,
+            escape_xml_text(tex.trim())
+        )
+    })
+}
+
+/// Convert a TeX expression to a `<math>` element, or `None` when it uses
+/// constructs this converter does not handle.
+pub fn tex_to_mathml(tex: &str, display: bool) -> Option<String> {
+    let mut parser = TexParser {
+        chars: tex.chars().collect(),
+        pos: 0,
+        display,
+    };
+    let body = parser.parse_row('}')?;
+    if parser.pos < parser.chars.len() {
+        // Unbalanced closing brace
+        return None;
+    }
+    Some(format!(
+        r#"<math xmlns="{}"{} alttext="{}">{}</math>"#,
+        MATHML_NS,
+        if display { r#" display="block""# } else { "" },
+        escape_xml_attr(tex.trim()),
+        body
+    ))
+}
+
+/// Recursive-descent parser producing MathML markup as it goes.
+struct TexParser {
+    chars: Vec<char>,
+    pos: usize,
+    display: bool,
+}
+
+impl TexParser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(char::is_whitespace) {
+            self.pos += 1;
+        }
+    }
+
+    /// Consume `c` (after optional whitespace) or fail.
+    fn expect(&mut self, c: char) -> Option<()> {
+        self.skip_whitespace();
+        if self.peek() == Some(c) {
+            self.pos += 1;
+            Some(())
+        } else {
+            None
+        }
+    }
+
+    /// Nodes up to the end of input or `end`, which is left unconsumed.
+    fn parse_row(&mut self, end: char) -> Option<String> {
+        let mut nodes = Vec::new();
+        loop {
+            self.skip_whitespace();
+            match self.peek() {
+                None => break,
+                Some(c) if c == end => break,
+                Some('}') => return None,
+                _ => nodes.push(self.parse_scripted()?),
+            }
+        }
+        Some(row(nodes))
+    }
+
+    /// `{ ... }`
+    fn parse_group(&mut self) -> Option<String> {
+        self.expect('{')?;
+        let inner = self.parse_row('}')?;
+        self.expect('}')?;
+        Some(inner)
+    }
+
+    /// An atom followed by optional `_` and `^` scripts.
+    fn parse_scripted(&mut self) -> Option<String> {
+        let (base, limits) = self.parse_atom()?;
+        let mut sub = None;
+        let mut sup = None;
+        loop {
+            self.skip_whitespace();
+            match self.peek() {
+                Some('_') if sub.is_none() => {
+                    self.pos += 1;
+                    sub = Some(self.parse_argument()?);
+                }
+                Some('^') if sup.is_none() => {
+                    self.pos += 1;
+                    sup = Some(self.parse_argument()?);
+                }
+                // Double subscript/superscript
+                Some('_' | '^') => return None,
+                _ => break,
+            }
+        }
+
+        // Limits go above/below large operators in display math
+        let under = limits && self.display;
+        Some(match (sub, sup) {
+            (None, None) => base,
+            (Some(b), None) => {
+                let tag = if under { "munder" } else { "msub" };
+                format!("<{0}>{1}{2}</{0}>", tag, base, b)
+            }
+            (None, Some(p)) => {
+                let tag = if under { "mover" } else { "msup" };
+                format!("<{0}>{1}{2}</{0}>", tag, base, p)
+            }
+            (Some(b), Some(p)) => {
+                let tag = if under { "munderover" } else { "msubsup" };
+                format!("<{0}>{1}{2}{3}</{0}>", tag, base, b, p)
+            }
+        })
+    }
+
+    /// A script or command argument: a group, or a single token
+    /// (`x^23` superscripts only the `2`).
+    fn parse_argument(&mut self) -> Option<String> {
+        self.skip_whitespace();
+        match self.peek()? {
+            '{' => self.parse_group(),
+            c if c.is_ascii_digit() => {
+                self.pos += 1;
+                Some(format!("<mn>{}</mn>", c))
+            }
+            _ => self.parse_atom().map(|(node, _)| node),
+        }
+    }
+
+    /// One node, and whether it takes limits (`\sum`, `\lim`, ...).
+    fn parse_atom(&mut self) -> Option<(String, bool)> {
+        self.skip_whitespace();
+        let c = self.peek()?;
+        let next_is_digit = self
+            .chars
+            .get(self.pos + 1)
+            .is_some_and(|n| n.is_ascii_digit());
+        match c {
+            '{' => Some((self.parse_group()?, false)),
+            '\\' => self.parse_command(),
+            c if c.is_ascii_digit() || (c == '.' && next_is_digit) => {
+                let start = self.pos;
+                while self.peek().is_some_and(|d| d.is_ascii_digit() || d == '.') {
+                    self.pos += 1;
+                }
+                let number: String = self.chars[start..self.pos].iter().collect();
+                Some((format!("<mn>{}</mn>", number), false))
+            }
+            c if c.is_alphabetic() => {
+                self.pos += 1;
+                Some((format!("<mi>{}</mi>", c), false))
+            }
+            '\'' => {
+                self.pos += 1;
+                Some(("<mo>\u{2032}</mo>".to_string(), false))
+            }
+            '-' => {
+                self.pos += 1;
+                Some(("<mo>\u{2212}</mo>".to_string(), false))
+            }
+            '+' | '=' | '<' | '>' | ',' | ';' | ':' | '!' | '(' | ')' | '[' | ']' | '|' | '/'
+            | '*' | '.' | '?' => {
+                self.pos += 1;
+                Some((
+                    format!("<mo>{}</mo>", escape_xml_text(&c.to_string())),
+                    false,
+                ))
+            }
+            _ => None,
+        }
+    }
+
+    /// A `\name` or `\c` command, with the backslash at the current position.
+    fn parse_command(&mut self) -> Option<(String, bool)> {
+        self.pos += 1;
+        let start = self.pos;
+        while self.peek().is_some_and(|c| c.is_ascii_alphabetic()) {
+            self.pos += 1;
+        }
+        let name: String = if self.pos == start {
+            let c = self.peek()?;
+            self.pos += 1;
+            c.to_string()
+        } else {
+            self.chars[start..self.pos].iter().collect()
+        };
+
+        let node = match name.as_str() {
+            "frac" | "dfrac" | "tfrac" => {
+                let numerator = self.parse_argument()?;
+                let denominator = self.parse_argument()?;
+                format!("<mfrac>{}{}</mfrac>", numerator, denominator)
+            }
+            "sqrt" => {
+                self.skip_whitespace();
+                if self.peek() == Some('[') {
+                    self.pos += 1;
+                    let index = self.parse_row(']')?;
+                    self.expect(']')?;
+                    let radicand = self.parse_argument()?;
+                    format!("<mroot>{}{}</mroot>", radicand, index)
+                } else {
+                    format!("<msqrt>{}</msqrt>", self.parse_argument()?)
+                }
+            }
+            "left" | "right" => {
+                self.skip_whitespace();
+                if self.peek() == Some('.') {
+                    // Invisible delimiter
+                    self.pos += 1;
+                    String::new()
+                } else {
+                    self.parse_atom()?.0
+                }
+            }
+            "text" | "textrm" | "mbox" => {
+                format!(
+                    "<mtext>{}</mtext>",
+                    escape_xml_text(&self.parse_raw_group()?)
+                )
+            }
+            "mathbf" | "mathit" | "mathrm" | "mathbb" | "mathcal" | "mathsf" | "mathfrak" => {
+                let variant = match name.as_str() {
+                    "mathbf" => "bold",
+                    "mathit" => "italic",
+                    "mathrm" => "normal",
+                    "mathbb" => "double-struck",
+                    "mathcal" => "script",
+                    "mathsf" => "sans-serif",
+                    _ => "fraktur",
+                };
+                let text = self.parse_raw_group()?;
+                if !text.chars().all(|c| c.is_alphanumeric() || c == ' ') {
+                    return None;
+                }
+                format!(
+                    r#"<mi mathvariant="{}">{}</mi>"#,
+                    variant,
+                    text.replace(' ', "")
+                )
+            }
+            "hat" | "widehat" | "bar" | "overline" | "vec" | "dot" | "ddot" | "tilde"
+            | "widetilde" => {
+                let accent = match name.as_str() {
+                    "hat" | "widehat" => "^",
+                    "bar" | "overline" => "\u{AF}",
+                    "vec" => "\u{2192}",
+                    "dot" => "\u{2D9}",
+                    "ddot" => "\u{A8}",
+                    _ => "\u{2DC}",
+                };
+                let base = self.parse_argument()?;
+                format!(
+                    r#"<mover accent="true">{}<mo>{}</mo></mover>"#,
+                    base, accent
+                )
+            }
+            "," | ":" | ";" | " " | "quad" | "qquad" | "!" => {
+                let width = match name.as_str() {
+                    "," => "0.167em",
+                    ":" => "0.222em",
+                    ";" | " " => "0.278em",
+                    "quad" => "1em",
+                    "qquad" => "2em",
+                    _ => "-0.167em",
+                };
+                format!(r#"<mspace width="{}"/>"#, width)
+            }
+            "{" | "}" | "|" | "%" | "#" | "$" | "_" => {
+                let symbol = if name == "|" {
+                    "\u{2016}"
+                } else {
+                    name.as_str()
+                };
+                format!("<mo>{}</mo>", symbol)
+            }
+            "&" => "<mo>&amp;</mo>".to_string(),
+            _ => {
+                if let Some(function) = FUNCTIONS.iter().find(|(f, _)| *f == name) {
+                    return Some((format!("<mi>{}</mi>", function.0), function.1));
+                }
+                let (symbol, kind) = symbol(&name)?;
+                return Some(match kind {
+                    Symbol::Ident => (format!("<mi>{}</mi>", symbol), false),
+                    Symbol::Op => (format!("<mo>{}</mo>", symbol), false),
+                    Symbol::LargeOp => (format!("<mo>{}</mo>", symbol), true),
+                });
+            }
+        };
+        Some((node, false))
+    }
+
+    /// The literal text of a `{...}` argument (no nested groups).
+    fn parse_raw_group(&mut self) -> Option<String> {
+        self.expect('{')?;
+        let start = self.pos;
+        while self.peek().is_some_and(|c| c != '}' && c != '{') {
+            self.pos += 1;
+        }
+        let text: String = self.chars[start..self.pos].iter().collect();
+        self.expect('}')?;
+        Some(text)
+    }
+}
+
+/// Wrap several nodes in an `<mrow>`.
+fn row(nodes: Vec<String>) -> String {
+    let nodes: Vec<String> = nodes.into_iter().filter(|n| !n.is_empty()).collect();
+    if nodes.len() == 1 {
+        nodes.into_iter().next().unwrap_or_default()
+    } else {
+        format!("<mrow>{}</mrow>", nodes.concat())
+    }
+}
+
+/// Upright function names, and whether they take limits (`\lim_{x \to 0}`).
+const FUNCTIONS: &[(&str, bool)] = &[
+    ("sin", false),
+    ("cos", false),
+    ("tan", false),
+    ("cot", false),
+    ("sec", false),
+    ("csc", false),
+    ("arcsin", false),
+    ("arccos", false),
+    ("arctan", false),
+    ("sinh", false),
+    ("cosh", false),
+    ("tanh", false),
+    ("log", false),
+    ("ln", false),
+    ("lg", false),
+    ("exp", false),
+    ("dim", false),
+    ("ker", false),
+    ("deg", false),
+    ("arg", false),
+    ("det", true),
+    ("gcd", true),
+    ("lim", true),
+    ("max", true),
+    ("min", true),
+    ("sup", true),
+    ("inf", true),
+    ("Pr", true),
+];
+
+enum Symbol {
+    Ident,
+    Op,
+    LargeOp,
+}
+
+/// Unicode character for a symbol command.
+fn symbol(name: &str) -> Option<(&'static str, Symbol)> {
+    use Symbol::*;
+    Some(match name {
+        "alpha" => ("\u{3B1}", Ident),
+        "beta" => ("\u{3B2}", Ident),
+        "gamma" => ("\u{3B3}", Ident),
+        "delta" => ("\u{3B4}", Ident),
+        "epsilon" => ("\u{3F5}", Ident),
+        "varepsilon" => ("\u{3B5}", Ident),
+        "zeta" => ("\u{3B6}", Ident),
+        "eta" => ("\u{3B7}", Ident),
+        "theta" => ("\u{3B8}", Ident),
+        "vartheta" => ("\u{3D1}", Ident),
+        "iota" => ("\u{3B9}", Ident),
+        "kappa" => ("\u{3BA}", Ident),
+        "lambda" => ("\u{3BB}", Ident),
+        "mu" => ("\u{3BC}", Ident),
+        "nu" => ("\u{3BD}", Ident),
+        "xi" => ("\u{3BE}", Ident),
+        "pi" => ("\u{3C0}", Ident),
+        "varpi" => ("\u{3D6}", Ident),
+        "rho" => ("\u{3C1}", Ident),
+        "varrho" => ("\u{3F1}", Ident),
+        "sigma" => ("\u{3C3}", Ident),
+        "varsigma" => ("\u{3C2}", Ident),
+        "tau" => ("\u{3C4}", Ident),
+        "upsilon" => ("\u{3C5}", Ident),
+        "phi" => ("\u{3D5}", Ident),
+        "varphi" => ("\u{3C6}", Ident),
+        "chi" => ("\u{3C7}", Ident),
+        "psi" => ("\u{3C8}", Ident),
+        "omega" => ("\u{3C9}", Ident),
+        "Gamma" => ("\u{393}", Ident),
+        "Delta" => ("\u{394}", Ident),
+        "Theta" => ("\u{398}", Ident),
+        "Lambda" => ("\u{39B}", Ident),
+        "Xi" => ("\u{39E}", Ident),
+        "Pi" => ("\u{3A0}", Ident),
+        "Sigma" => ("\u{3A3}", Ident),
+        "Upsilon" => ("\u{3A5}", Ident),
+        "Phi" => ("\u{3A6}", Ident),
+        "Psi" => ("\u{3A8}", Ident),
+        "Omega" => ("\u{3A9}", Ident),
+        "infty" => ("\u{221E}", Ident),
+        "ell" => ("\u{2113}", Ident),
+        "hbar" => ("\u{210F}", Ident),
+        "emptyset" => ("\u{2205}", Ident),
+        "partial" => ("\u{2202}", Op),
+        "nabla" => ("\u{2207}", Op),
+        "times" => ("\u{D7}", Op),
+        "cdot" => ("\u{22C5}", Op),
+        "pm" => ("\u{B1}", Op),
+        "mp" => ("\u{2213}", Op),
+        "div" => ("\u{F7}", Op),
+        "ast" => ("\u{2217}", Op),
+        "circ" => ("\u{2218}", Op),
+        "leq" | "le" => ("\u{2264}", Op),
+        "geq" | "ge" => ("\u{2265}", Op),
+        "neq" | "ne" => ("\u{2260}", Op),
+        "ll" => ("\u{226A}", Op),
+        "gg" => ("\u{226B}", Op),
+        "approx" => ("\u{2248}", Op),
+        "equiv" => ("\u{2261}", Op),
+        "sim" => ("\u{223C}", Op),
+        "simeq" => ("\u{2243}", Op),
+        "cong" => ("\u{2245}", Op),
+        "propto" => ("\u{221D}", Op),
+        "in" => ("\u{2208}", Op),
+        "notin" => ("\u{2209}", Op),
+        "ni" => ("\u{220B}", Op),
+        "subset" => ("\u{2282}", Op),
+        "supset" => ("\u{2283}", Op),
+        "subseteq" => ("\u{2286}", Op),
+        "supseteq" => ("\u{2287}", Op),
+        "cup" => ("\u{222A}", Op),
+        "cap" => ("\u{2229}", Op),
+        "setminus" => ("\u{2216}", Op),
+        "forall" => ("\u{2200}", Op),
+        "exists" => ("\u{2203}", Op),
+        "neg" | "lnot" => ("\u{AC}", Op),
+        "land" | "wedge" => ("\u{2227}", Op),
+        "lor" | "vee" => ("\u{2228}", Op),
+        "to" | "rightarrow" => ("\u{2192}", Op),
+        "leftarrow" | "gets" => ("\u{2190}", Op),
+        "leftrightarrow" => ("\u{2194}", Op),
+        "Rightarrow" | "implies" => ("\u{21D2}", Op),
+        "Leftarrow" => ("\u{21D0}", Op),
+        "Leftrightarrow" | "iff" => ("\u{21D4}", Op),
+        "mapsto" => ("\u{21A6}", Op),
+        "ldots" | "dots" => ("\u{2026}", Op),
+        "cdots" => ("\u{22EF}", Op),
+        "vdots" => ("\u{22EE}", Op),
+        "ddots" => ("\u{22F1}", Op),
+        "langle" => ("\u{27E8}", Op),
+        "rangle" => ("\u{27E9}", Op),
+        "lfloor" => ("\u{230A}", Op),
+        "rfloor" => ("\u{230B}", Op),
+        "lceil" => ("\u{2308}", Op),
+        "rceil" => ("\u{2309}", Op),
+        "mid" => ("\u{2223}", Op),
+        "int" => ("\u{222B}", Op),
+        "iint" => ("\u{222C}", Op),
+        "oint" => ("\u{222E}", Op),
+        "sum" => ("\u{2211}", LargeOp),
+        "prod" => ("\u{220F}", LargeOp),
+        "coprod" => ("\u{2210}", LargeOp),
+        "bigcup" => ("\u{22C3}", LargeOp),
+        "bigcap" => ("\u{22C2}", LargeOp),
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn inner(tex: &str) -> String {
+        let math = tex_to_mathml(tex, false).unwrap();
+        let start = math.find('>').unwrap() + 1;
+        math[start..math.len() - "</math>".len()].to_string()
+    }
+
+    #[test]
+    fn test_superscript() {
+        assert_eq!(inner("x^2"), "<msup><mi>x</mi><mn>2</mn></msup>");
+        assert_eq!(
+            inner("e^{i\\pi} + 1 = 0"),
+            "<mrow><msup><mi>e</mi><mrow><mi>i</mi><mi>\u{3C0}</mi></mrow></msup>\
+             <mo>+</mo><mn>1</mn><mo>=</mo><mn>0</mn></mrow>"
+        );
+    }
+
+    #[test]
+    fn test_fraction_and_root() {
+        assert_eq!(inner("\\frac{a}{b}"), "<mfrac><mi>a</mi><mi>b</mi></mfrac>");
+        assert_eq!(
+            inner("\\sqrt[3]{x_1}"),
+            "<mroot><msub><mi>x</mi><mn>1</mn></msub><mn>3</mn></mroot>"
+        );
+    }
+
+    #[test]
+    fn test_display_sum_takes_limits() {
+        let math = tex_to_mathml("\\sum_{i=1}^n i", true).unwrap();
+        assert!(math.contains(r#"display="block""#));
+        assert!(math.contains("<munderover><mo>\u{2211}</mo>"));
+        let inline = tex_to_mathml("\\sum_{i=1}^n i", false).unwrap();
+        assert!(inline.contains("<msubsup><mo>\u{2211}</mo>"));
+    }
+
+    #[test]
+    fn test_unsupported_falls_back_to_code() {
+        assert!(tex_to_mathml("\\begin{matrix} a & b \\end{matrix}", false).is_none());
+        assert!(tex_to_mathml("\\unknowncommand x", false).is_none());
+        assert!(tex_to_mathml("x}", false).is_none());
+        assert_eq!(
+            render("a < \\foo", false),
+            r#"<code class="math">$a &lt; \foo$</code>"#
+        );
+    }
+}
@@ -0,0 +1,303 @@
+//! reStructuredText to HTML, for the subset found in prose documents.
+//!
+//! Supported: section titles (underlined, or over- and underlined; levels
+//! follow the order adornment styles first appear in), paragraphs, bullet
+//! and enumerated lists, block quotes, `::` literal blocks, transitions, and
+//! inline `*emphasis*`, `**strong**`, ``` ``literals`` ```, `` `links
+//! <url>`_ `` and `` `interpreted text` ``. Comments and directives are
+//! dropped with their content.
+
+use regex::{Captures, Regex};
+
+use convert_utils::xml::escape_xml_text;
+
+/// Convert reStructuredText to HTML body content. Fails when section titles
+/// skip a level, which docutils also rejects.
+pub fn to_html(text: &str) -> Result<String, String> {
+    let text = text.replace("\r\n", "\n").replace('\t', "        ");
+    let lines: Vec<&str> = text.lines().map(str::trim_end).collect();
+    let inline = Inline::new();
+
+    let mut html = String::new();
+    // Adornment styles (character, has overline) in order of first use
+    let mut styles: Vec<(char, bool)> = Vec::new();
+    let mut depth = 0;
+    let mut i = 0;
+
+    while i < lines.len() {
+        let line = lines[i];
+        if line.is_empty() {
+            i += 1;
+            continue;
+        }
+
+        let title = if adornment(line).is_some() {
+            // Overlined title, or a transition
+            match (lines.get(i + 1), lines.get(i + 2)) {
+                (Some(title), Some(under))
+                    if !title.trim().is_empty() && adornment(under) == adornment(line) =>
+                {
+                    Some((title.trim(), (adornment(line).unwrap(), true), 3))
+                }
+                _ => {
+                    html.push_str("<hr/>\n");
+                    i += 1;
+                    continue;
+                }
+            }
+        } else {
+            match lines.get(i + 1) {
+                Some(under)
+                    if !line.starts_with(' ')
+                        && adornment(under).is_some()
+                        && under.chars().count() >= line.chars().count().min(4) =>
+                {
+                    Some((line, (adornment(under).unwrap(), false), 2))
+                }
+                _ => None,
+            }
+        };
+        if let Some((title, style, consumed)) = title {
+            let index = styles.iter().position(|s| *s == style).unwrap_or_else(|| {
+                styles.push(style);
+                styles.len() - 1
+            });
+            if index > depth {
+                return Err(format!(
+                    "line {}: title \"{}\" skips a section level",
+                    i + 1,
+                    title
+                ));
+            }
+            depth = index + 1;
+            let level = depth.min(6);
+            html.push_str(&format!(
+                "<h{}>{}</h{}>\n",
+                level,
+                inline.render(title),
+                level
+            ));
+            i += consumed;
+            continue;
+        }
+
+        // Comments and directives, with their indented content
+        if line == ".." || line.starts_with(".. ") {
+            i = indented_block(&lines, i + 1).1;
+            continue;
+        }
+
+        // Block quote
+        if line.starts_with(' ') {
+            let (block, next) = indented_block(&lines, i);
+            html.push_str("<blockquote>\n");
+            for para in block.split("\n\n").filter(|p| !p.trim().is_empty()) {
+                html.push_str(&format!("<p>{}</p>\n", inline.render(&join_lines(para))));
+            }
+            html.push_str("</blockquote>\n");
+            i = next;
+            continue;
+        }
+
+        // Lists
+        if let Some((tag, _)) = list_item(line) {
+            html.push_str(&format!("<{}>\n", tag));
+            while let Some((item_tag, first)) = lines.get(i).and_then(|l| list_item(l)) {
+                if item_tag != tag {
+                    break;
+                }
+                let (rest, next) = indented_block(&lines, i + 1);
+                let item = format!("{}\n{}", first, rest);
+                html.push_str(&format!("<li>{}</li>\n", inline.render(&join_lines(&item))));
+                i = next;
+                while lines.get(i).is_some_and(|l| l.is_empty()) {
+                    i += 1;
+                }
+            }
+            html.push_str(&format!("</{}>\n", tag));
+            continue;
+        }
+
+        // Paragraph, possibly introducing a literal block
+        let start = i;
+        while lines.get(i).is_some_and(|l| !l.is_empty()) {
+            i += 1;
+        }
+        let para = join_lines(&lines[start..i].join("\n"));
+        let literal = para.ends_with("::");
+        let para = match para.strip_suffix("::") {
+            Some(rest) if rest.is_empty() || rest.ends_with(' ') => rest.trim_end(),
+            Some(rest) => &para[..rest.len() + 1],
+            None => para.as_str(),
+        };
+        if !para.is_empty() {
+            html.push_str(&format!("<p>{}</p>\n", inline.render(para)));
+        }
+        if literal {
+            while lines.get(i).is_some_and(|l| l.is_empty()) {
+                i += 1;
+            }
+            if lines.get(i).is_some_and(|l| l.starts_with(' ')) {
+                let (block, next) = indented_block(&lines, i);
+                html.push_str(&format!(
+                    "<pre>{}</pre>\n",
+                    escape_xml_text(block.trim_end())
+                ));
+                i = next;
+            }
+        }
+    }
+
+    Ok(html)
+}
+
+/// The repeated punctuation character of a section adornment or
+/// transition line.
+fn adornment(line: &str) -> Option<char> {
+    let c = line.chars().next()?;
+    let repeated = line.len() >= 2 && line.chars().all(|ch| ch == c);
+    (repeated && c.is_ascii_punctuation()).then_some(c)
+}
+
+/// The list tag and text of a bullet (`- `, `* `, `+ `) or enumerated
+/// (`1. `, `#. `, `a) `) list item.
+fn list_item(line: &str) -> Option<(&'static str, &str)> {
+    if let Some(text) = ["- ", "* ", "+ "]
+        .iter()
+        .find_map(|bullet| line.strip_prefix(bullet))
+    {
+        return Some(("ul", text));
+    }
+    let (marker, text) = line.split_once(' ')?;
+    let label = marker.strip_suffix(['.', ')'])?;
+    let enumerated = label == "#"
+        || (!label.is_empty() && label.chars().all(|c| c.is_ascii_digit()))
+        || (label.len() == 1 && label.chars().all(|c| c.is_ascii_alphabetic()));
+    enumerated.then_some(("ol", text))
+}
+
+/// The indented lines from `start`, dedented, and the index of the first
+/// line after them. Blank lines inside the block are kept.
+fn indented_block(lines: &[&str], start: usize) -> (String, usize) {
+    let mut end = start;
+    while end < lines.len() && (lines[end].is_empty() || lines[end].starts_with(' ')) {
+        end += 1;
+    }
+    let indent = lines[start..end]
+        .iter()
+        .filter(|l| !l.is_empty())
+        .map(|l| l.len() - l.trim_start().len())
+        .min()
+        .unwrap_or(0);
+    let block = lines[start..end]
+        .iter()
+        .map(|l| l.get(indent..).unwrap_or_default())
+        .collect::<Vec<_>>()
+        .join("\n");
+    (block, end)
+}
+
+/// Lines of a paragraph joined with single spaces.
+fn join_lines(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Inline markup patterns.
+struct Inline {
+    literal: Regex,
+    link: Regex,
+    strong: Regex,
+    emphasis: Regex,
+    interpreted: Regex,
+}
+
+impl Inline {
+    fn new() -> Self {
+        Self {
+            literal: Regex::new(r"``(.+?)``").unwrap(),
+            link: Regex::new(r"`([^`]+?)\s*&lt;([^`\s]+)&gt;`__?").unwrap(),
+            strong: Regex::new(r"\*\*(\S(?:[^*]*?\S)?)\*\*").unwrap(),
+            emphasis: Regex::new(r"\*(\S(?:[^*]*?\S)?)\*").unwrap(),
+            interpreted: Regex::new(r"`([^`]+)`").unwrap(),
+        }
+    }
+
+    /// Escape `text` and convert its inline markup. Literals are left as
+    /// they are.
+    fn render(&self, text: &str) -> String {
+        let text = escape_xml_text(text);
+        let mut html = String::with_capacity(text.len());
+        let mut pos = 0;
+        for cap in self.literal.captures_iter(&text) {
+            let m = cap.get(0).unwrap();
+            html.push_str(&self.markup(&text[pos..m.start()]));
+            html.push_str(&format!("<code>{}</code>", &cap[1]));
+            pos = m.end();
+        }
+        html.push_str(&self.markup(&text[pos..]));
+        html
+    }
+
+    fn markup(&self, text: &str) -> String {
+        let text = self.link.replace_all(text, |cap: &Captures| {
+            format!(
+                "<a href=\"{}\">{}</a>",
+                cap[2].replace('"', "&quot;"),
+                &cap[1]
+            )
+        });
+        let text = self.strong.replace_all(&text, "<strong>$1</strong>");
+        let text = self.emphasis.replace_all(&text, "<em>$1</em>");
+        self.interpreted
+            .replace_all(&text, "<cite>$1</cite>")
+            .into_owned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_section_titles() {
+        let rst = "=====\nTitle\n=====\n\nIntro.\n\nChapter One\n===========\n\nText.\n\nA Section\n---------\n\nMore.\n\nChapter Two\n===========\n";
+        let html = to_html(rst).unwrap();
+        assert_eq!(
+            html,
+            "<h1>Title</h1>\n<p>Intro.</p>\n<h2>Chapter One</h2>\n<p>Text.</p>\n\
+             <h3>A Section</h3>\n<p>More.</p>\n<h2>Chapter Two</h2>\n"
+        );
+    }
+
+    #[test]
+    fn test_skipped_level_fails() {
+        let rst = "One\n===\n\nTwo\n---\n\nThree\n~~~~~\n\nFour\n^^^^\n\nFive\n~~~~\n\nSix\n''''\n";
+        assert!(to_html(rst).is_err());
+    }
+
+    #[test]
+    fn test_inline_markup() {
+        let html = to_html(
+            "Some *emphasis*, **strong** & ``x < *y*``, a `link <https://example.com/>`_\nand `a title`.",
+        )
+        .unwrap();
+        assert_eq!(
+            html,
+            "<p>Some <em>emphasis</em>, <strong>strong</strong> &amp; <code>x &lt; *y*</code>, \
+             a <a href=\"https://example.com/\">link</a> and <cite>a title</cite>.</p>\n"
+        );
+    }
+
+    #[test]
+    fn test_blocks() {
+        let rst = "Example::\n\n    let x = 1;\n      indented\n\n- one\n- two\n  continued\n\n3. three\n\nSaid:\n\n    A quote.\n\n----\n\n.. note:: hidden\n   also hidden\n\nEnd.";
+        let html = to_html(rst).unwrap();
+        assert_eq!(
+            html,
+            "<p>Example:</p>\n<pre>let x = 1;\n  indented</pre>\n\
+             <ul>\n<li>one</li>\n<li>two continued</li>\n</ul>\n\
+             <ol>\n<li>three</li>\n</ol>\n<p>Said:</p>\n\
+             <blockquote>\n<p>A quote.</p>\n</blockquote>\n<hr/>\n<p>End.</p>\n"
+        );
+    }
+}
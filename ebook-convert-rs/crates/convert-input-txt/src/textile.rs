@@ -0,0 +1,215 @@
+//! Textile to HTML, for the subset found in prose documents.
+//!
+//! Supported: the `h1.`–`h6.`, `p.`, `bq.` and `bc.` block signatures
+//! (attributes such as `p(class).` or `p>.` are ignored), `*`/`#` lists
+//! with nesting, `|cell|cell|` tables, and inline `*strong*`, `_emphasis_`,
+//! `**bold**`, `__italic__`, `@code@`, `-deleted-`, `+inserted+`,
+//! `^superscript^`, `~subscript~` and `"links":url`. Line breaks within a
+//! paragraph are kept.
+
+use regex::{Captures, Regex};
+
+use convert_utils::xml::escape_xml_text;
+
+/// Convert Textile to HTML body content.
+pub fn to_html(text: &str) -> String {
+    let text = text.replace("\r\n", "\n");
+    let signature_re =
+        Regex::new(r"^(h[1-6]|p|bq|bc)(?:\([^)]*\)|\{[^}]*\}|\[[^\]]*\]|[<>=]+)*\.\s+").unwrap();
+    let inline = Inline::new();
+
+    let mut html = String::new();
+    for block in text.split("\n\n") {
+        let block = block.trim_matches('\n');
+        if block.trim().is_empty() {
+            continue;
+        }
+        let lines: Vec<&str> = block.lines().collect();
+
+        if let Some(cap) = signature_re.captures(block) {
+            let content = &block[cap.get(0).unwrap().end()..];
+            match &cap[1] {
+                "bc" => html.push_str(&format!(
+                    "<pre><code>{}</code></pre>\n",
+                    escape_xml_text(content)
+                )),
+                "bq" => html.push_str(&format!(
+                    "<blockquote>\n<p>{}</p>\n</blockquote>\n",
+                    inline.render(content)
+                )),
+                tag => html.push_str(&format!("<{0}>{1}</{0}>\n", tag, inline.render(content))),
+            }
+        } else if lines.iter().all(|l| list_depth(l).is_some()) {
+            write_list(&mut html, &lines, &inline);
+        } else if lines.iter().all(|l| l.starts_with('|') && l.ends_with('|')) {
+            html.push_str("<table>\n");
+            for line in lines {
+                html.push_str("<tr>");
+                for cell in line[1..line.len() - 1].split('|') {
+                    html.push_str(&format!("<td>{}</td>", inline.render(cell.trim())));
+                }
+                html.push_str("</tr>\n");
+            }
+            html.push_str("</table>\n");
+        } else {
+            html.push_str(&format!("<p>{}</p>\n", inline.render(block)));
+        }
+    }
+    html
+}
+
+/// The list tag and nesting depth of a list item line (`* item`, `## item`).
+fn list_depth(line: &str) -> Option<(&'static str, usize)> {
+    let marker = line.split_once(' ')?.0;
+    let tag = match marker.chars().next()? {
+        '*' => "ul",
+        '#' => "ol",
+        _ => return None,
+    };
+    let first = marker.chars().next()?;
+    marker
+        .chars()
+        .all(|c| c == first)
+        .then_some((tag, marker.len()))
+}
+
+/// Write list item lines as nested lists.
+fn write_list(html: &mut String, lines: &[&str], inline: &Inline) {
+    // Tags of the open lists, outermost first
+    let mut open: Vec<&str> = Vec::new();
+    for line in lines {
+        let Some((tag, depth)) = list_depth(line) else {
+            continue;
+        };
+        let text = line.split_once(' ').map_or("", |(_, text)| text);
+        while open.len() > depth {
+            html.push_str(&format!("</li>\n</{}>\n", open.pop().unwrap()));
+        }
+        if open.len() == depth {
+            html.push_str("</li>\n");
+        }
+        while open.len() < depth {
+            if !open.is_empty() {
+                html.push('\n');
+            }
+            html.push_str(&format!("<{}>\n", tag));
+            open.push(tag);
+        }
+        html.push_str(&format!("<li>{}", inline.render(text)));
+    }
+    while let Some(tag) = open.pop() {
+        html.push_str(&format!("</li>\n</{}>\n", tag));
+    }
+}
+
+/// Inline markup patterns. Phrase markers only count at word edges, so
+/// hyphenated-words and 2+2 are left alone.
+struct Inline {
+    code: Regex,
+    link: Regex,
+    phrases: Vec<(Regex, &'static str)>,
+}
+
+impl Inline {
+    fn new() -> Self {
+        let phrase = |marker: &str| {
+            let m = regex::escape(marker);
+            Regex::new(&format!(
+                r"(^|[\s(\[>]){m}([^\s{m}](?:[^\n]*?[^\s])?){m}($|[\s.,;:!?)\]<])"
+            ))
+            .unwrap()
+        };
+        Self {
+            code: Regex::new(r"@([^@\n]+)@").unwrap(),
+            link: Regex::new(r#""([^"\n]+)":([^\s"<]*[^\s"<.,;:!?)])"#).unwrap(),
+            phrases: vec![
+                (phrase("**"), "b"),
+                (phrase("__"), "i"),
+                (phrase("*"), "strong"),
+                (phrase("_"), "em"),
+                (phrase("-"), "del"),
+                (phrase("+"), "ins"),
+                (phrase("^"), "sup"),
+                (phrase("~"), "sub"),
+            ],
+        }
+    }
+
+    /// Escape `text` and convert its inline markup, outside `@code@`.
+    fn render(&self, text: &str) -> String {
+        let text = escape_xml_text(text);
+        let mut html = String::with_capacity(text.len());
+        let mut pos = 0;
+        for cap in self.code.captures_iter(&text) {
+            let m = cap.get(0).unwrap();
+            html.push_str(&self.markup(&text[pos..m.start()]));
+            html.push_str(&format!("<code>{}</code>", &cap[1]));
+            pos = m.end();
+        }
+        html.push_str(&self.markup(&text[pos..]));
+        html.replace('\n', "<br/>\n")
+    }
+
+    fn markup(&self, text: &str) -> String {
+        let mut text = self
+            .link
+            .replace_all(text, |cap: &Captures| {
+                format!("<a href=\"{}\">{}</a>", &cap[2], &cap[1])
+            })
+            .into_owned();
+        for (re, tag) in &self.phrases {
+            // Matches consume the space around them, so adjacent phrases
+            // (`*a* *b*`) take another pass
+            loop {
+                let next = re.replace_all(&text, |cap: &Captures| {
+                    format!("{}<{tag}>{}</{tag}>{}", &cap[1], &cap[2], &cap[3])
+                });
+                if next == text {
+                    break;
+                }
+                text = next.into_owned();
+            }
+        }
+        text
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_blocks() {
+        let textile = "pizza. is not a signature\n\nh1. The Title\n\np(intro). First line\nsecond line\n\nbq. Quoted.\n\nbc. a < b && *c*\n\n|x|y|\n|1|2|";
+        assert_eq!(
+            to_html(textile),
+            "<p>pizza. is not a signature</p>\n<h1>The Title</h1>\n<p>First line<br/>\nsecond line</p>\n\
+             <blockquote>\n<p>Quoted.</p>\n</blockquote>\n\
+             <pre><code>a &lt; b &amp;&amp; *c*</code></pre>\n\
+             <table>\n<tr><td>x</td><td>y</td></tr>\n<tr><td>1</td><td>2</td></tr>\n</table>\n"
+        );
+    }
+
+    #[test]
+    fn test_nested_lists() {
+        let html = to_html("* one\n** one.a\n* two\n\n# first\n# second");
+        assert_eq!(
+            html,
+            "<ul>\n<li>one\n<ul>\n<li>one.a</li>\n</ul>\n</li>\n<li>two</li>\n</ul>\n\
+             <ol>\n<li>first</li>\n<li>second</li>\n</ol>\n"
+        );
+    }
+
+    #[test]
+    fn test_inline_markup() {
+        let html = to_html(
+            "A *bold* *strong* and _emphasised_ @x*y@ word, -gone- +added+ H~2~O x^2^, a well-known 2*3*4 \"link\":https://example.com/a.",
+        );
+        assert_eq!(
+            html,
+            "<p>A <strong>bold</strong> <strong>strong</strong> and <em>emphasised</em> <code>x*y</code> word, \
+             <del>gone</del> <ins>added</ins> H~2~O x^2^, a well-known 2*3*4 \
+             <a href=\"https://example.com/a\">link</a>.</p>\n"
+        );
+    }
+}
@@ -0,0 +1,227 @@
+//! CBZ output plugin — packs the book's images into a comic book archive.
+//!
+//! Images are written in reading order: the cover first, then images in the
+//! order spine documents reference them, then any remaining manifest images.
+//! Entries are named with zero-padded numbers so readers sort them correctly.
+
+use std::collections::HashSet;
+use std::path::Path;
+
+use convert_core::atomic::write_atomically;
+use convert_core::book::{BookDocument, EbookFormat, ManifestData, ManifestItem};
+use convert_core::error::{ConvertError, Result};
+use convert_core::options::ConversionOptions;
+use convert_core::plugin::OutputPlugin;
+use convert_utils::archive::ZipBuilder;
+use regex::Regex;
+
+pub struct CbzOutputPlugin;
+
+impl OutputPlugin for CbzOutputPlugin {
+    fn name(&self) -> &str {
+        "CBZ Output"
+    }
+
+    fn output_format(&self) -> EbookFormat {
+        EbookFormat::Cbz
+    }
+
+    fn convert(
+        &self,
+        book: &BookDocument,
+        output_path: &Path,
+        _options: &ConversionOptions,
+    ) -> Result<()> {
+        log::info!("Writing CBZ: {}", output_path.display());
+
+        let pages = page_images(book);
+        if pages.is_empty() {
+            return Err(ConvertError::Cbz("Book contains no images".to_string()));
+        }
+
+        let skipped = book
+            .manifest
+            .iter()
+            .filter(|item| !item.is_image() && !item.is_css())
+            .count();
+        if skipped > 0 {
+            log::warn!(
+                "CBZ output keeps only images; ignoring {} other manifest items",
+                skipped
+            );
+        }
+
+        write_atomically(output_path, |tmp| write_cbz(&pages, tmp))?;
+        log::info!(
+            "CBZ written with {} pages: {}",
+            pages.len(),
+            output_path.display()
+        );
+        Ok(())
+    }
+}
+
+/// Image items in reading order.
+fn page_images(book: &BookDocument) -> Vec<&ManifestItem> {
+    let img_re = Regex::new(
+        r#"(?is)<(?:img|image)\b[^>]*?\s(?:src|xlink:href|href)\s*=\s*["']([^"']+)["']"#,
+    )
+    .unwrap();
+
+    let mut candidates: Vec<&ManifestItem> = Vec::new();
+
+    if let Some(cover) = book.guide.get("cover") {
+        let href = cover.href.split('#').next().unwrap_or_default();
+        candidates.extend(book.manifest.by_href(href));
+    }
+
+    for spine_item in book.spine.iter() {
+        let Some(item) = book.manifest.by_id(&spine_item.idref) else {
+            continue;
+        };
+        if item.is_image() {
+            candidates.push(item);
+        } else if let Some(xhtml) = item.data.as_xhtml() {
+            for cap in img_re.captures_iter(xhtml) {
+                let href = convert_utils::href::resolve(&item.href, &cap[1]);
+                candidates.extend(book.manifest.by_href(&href));
+            }
+        }
+    }
+
+    candidates.extend(book.manifest.iter());
+
+    let mut seen: HashSet<&str> = HashSet::new();
+    candidates
+        .into_iter()
+        .filter(|item| item.is_image() && seen.insert(item.id.as_str()))
+        .collect()
+}
+
+/// Write `pages` as `001.jpg`, `002.png`, ... into a ZIP at `path`.
+fn write_cbz(pages: &[&ManifestItem], path: &Path) -> Result<()> {
+    let mut zip = ZipBuilder::new(path)
+        .map_err(|e| ConvertError::Cbz(format!("Failed to create CBZ: {}", e)))?;
+
+    let width = pages.len().to_string().len().max(3);
+    let mut written = 0;
+    for item in pages {
+        let data = match &item.data {
+            ManifestData::Binary(data) => data.clone(),
+            ManifestData::Lazy(file) => std::fs::read(file).map_err(|e| {
+                ConvertError::Cbz(format!("Failed to read {}: {}", file.display(), e))
+            })?,
+            _ => {
+                log::warn!("Image {} has no data; skipping", item.href);
+                continue;
+            }
+        };
+        written += 1;
+        let name = format!(
+            "{:0width$}.{}",
+            written,
+            convert_utils::mime::extension_from_mime(&item.media_type),
+            width = width
+        );
+        // Images are already compressed; deflating them again wastes time
+        zip.add_stored(&name, &data)
+            .map_err(|e| ConvertError::Cbz(format!("Failed to write {}: {}", name, e)))?;
+    }
+
+    zip.finish()
+        .map_err(|e| ConvertError::Cbz(format!("Failed to finalize CBZ: {}", e)))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use convert_core::book::GuideRef;
+    use convert_utils::archive::{list_zip_entries, read_zip_entry};
+
+    fn image(id: &str, href: &str, media_type: &str) -> ManifestItem {
+        ManifestItem::new(
+            id,
+            href,
+            media_type,
+            ManifestData::Binary(id.as_bytes().to_vec()),
+        )
+    }
+
+    fn page(id: &str, href: &str, body: &str) -> ManifestItem {
+        ManifestItem::new(
+            id,
+            href,
+            "application/xhtml+xml",
+            ManifestData::Xhtml(format!("<html><body>{}</body></html>", body)),
+        )
+    }
+
+    #[test]
+    fn test_cbz_output_in_reading_order() {
+        let mut book = BookDocument::new();
+        book.manifest
+            .add(image("orphan", "images/extra.png", "image/png"));
+        book.manifest.add(image("b", "images/b.png", "image/png"));
+        book.manifest.add(image("a", "images/a.jpg", "image/jpeg"));
+        book.manifest.add(image("cover", "cover.jpg", "image/jpeg"));
+        book.manifest.add(page(
+            "p1",
+            "text/p1.xhtml",
+            r#"<p>Intro</p><img src="../images/a.jpg"/>"#,
+        ));
+        book.manifest.add(page(
+            "p2",
+            "text/p2.xhtml",
+            r#"<svg><image xlink:href="../images/b.png"/></svg><img src="../images/a.jpg"/>"#,
+        ));
+        book.manifest.add(ManifestItem::new(
+            "style",
+            "style.css",
+            "text/css",
+            ManifestData::Css("img { width: 100% }".into()),
+        ));
+        book.spine.push("p1", true);
+        book.spine.push("p2", true);
+        book.guide.add(GuideRef::new("cover", "Cover", "cover.jpg"));
+
+        let tmp = std::env::temp_dir().join("test_output.cbz");
+        CbzOutputPlugin
+            .convert(&book, &tmp, &ConversionOptions::default())
+            .unwrap();
+
+        let entries = list_zip_entries(&tmp).unwrap();
+        assert_eq!(entries, vec!["001.jpg", "002.jpg", "003.png", "004.png"]);
+        let mut sorted = entries.clone();
+        sorted.sort();
+        assert_eq!(entries, sorted);
+
+        let contents: Vec<Vec<u8>> = entries
+            .iter()
+            .map(|name| read_zip_entry(&tmp, name).unwrap())
+            .collect();
+        assert_eq!(
+            contents,
+            vec![
+                b"cover".to_vec(),
+                b"a".to_vec(),
+                b"b".to_vec(),
+                b"orphan".to_vec()
+            ]
+        );
+        std::fs::remove_file(&tmp).ok();
+    }
+
+    #[test]
+    fn test_cbz_output_without_images() {
+        let mut book = BookDocument::new();
+        book.manifest
+            .add(page("p1", "p1.xhtml", "<p>Text only</p>"));
+        book.spine.push("p1", true);
+
+        let tmp = std::env::temp_dir().join("test_output_empty.cbz");
+        let result = CbzOutputPlugin.convert(&book, &tmp, &ConversionOptions::default());
+        assert!(matches!(result, Err(ConvertError::Cbz(_))));
+        assert!(!tmp.exists());
+    }
+}
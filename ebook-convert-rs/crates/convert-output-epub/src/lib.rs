@@ -1,9 +1,11 @@
 //! EPUB output plugin — serializes BookDocument to EPUB 2/3.
 
+mod page_list;
 mod writer;
 
 use std::path::Path;
 
+use convert_core::atomic::write_atomically;
 use convert_core::book::{BookDocument, EbookFormat};
 use convert_core::error::Result;
 use convert_core::options::ConversionOptions;
@@ -27,6 +29,8 @@ impl OutputPlugin for EpubOutputPlugin {
         options: &ConversionOptions,
     ) -> Result<()> {
         log::info!("Writing EPUB: {}", output_path.display());
-        writer::write_epub(book, output_path, options)
+        write_atomically(output_path, |tmp| writer::write_epub(book, tmp, options))?;
+        log::info!("EPUB written successfully: {}", output_path.display());
+        Ok(())
     }
 }
@@ -0,0 +1,133 @@
+//! Page list — maps page-break markers in the content to page targets.
+//!
+//! Content converted from print marks where each page begins with
+//! `epub:type="pagebreak"` elements, and MOBI input leaves
+//! `<div class="mbp_pagebreak">` between pages. Each marker becomes a target
+//! in the NCX `pageList` and the EPUB 3 `page-list` nav, so readers can show
+//! real page numbers. Markers without an id are given one.
+
+use std::collections::HashMap;
+
+use regex::Regex;
+
+use convert_core::book::BookDocument;
+
+/// A page's label and where it starts.
+pub(crate) struct PageTarget {
+    pub label: String,
+    pub href: String,
+}
+
+/// The book's page targets in reading order.
+pub(crate) struct PageList {
+    pub targets: Vec<PageTarget>,
+    /// Spine documents with ids added to their markers, by manifest id.
+    pub documents: HashMap<String, String>,
+}
+
+/// Collect page targets from the page-break markers in spine documents.
+pub(crate) fn collect_page_list(book: &BookDocument) -> PageList {
+    let tag_re = Regex::new(r"<[A-Za-z][^>]*>").unwrap();
+    let attr_re = Regex::new(r#"\s([\w:-]+)\s*=\s*(?:"([^"]*)"|'([^']*)')"#).unwrap();
+
+    let mut list = PageList {
+        targets: Vec::new(),
+        documents: HashMap::new(),
+    };
+    for spine_item in book.spine.iter() {
+        let Some(item) = book.manifest.by_id(&spine_item.idref) else {
+            continue;
+        };
+        let Some(xhtml) = item.data.as_xhtml() else {
+            continue;
+        };
+
+        let mut out = String::with_capacity(xhtml.len());
+        let mut last = 0;
+        let mut rewritten = false;
+        for tag in tag_re.find_iter(xhtml) {
+            let attrs: HashMap<&str, &str> = attr_re
+                .captures_iter(tag.as_str())
+                .map(|c| {
+                    let value = c.get(2).or_else(|| c.get(3)).map_or("", |m| m.as_str());
+                    (c.get(1).unwrap().as_str(), value)
+                })
+                .collect();
+            if !is_page_break(&attrs) {
+                continue;
+            }
+
+            let number = list.targets.len() + 1;
+            let label = attrs
+                .get("title")
+                .or_else(|| attrs.get("aria-label"))
+                .map(|l| l.trim().to_string())
+                .filter(|l| !l.is_empty())
+                .unwrap_or_else(|| number.to_string());
+            let id = match attrs.get("id") {
+                Some(id) => id.to_string(),
+                None => {
+                    let id = format!("page-{}", number);
+                    let name_end = tag.as_str()[1..]
+                        .find(|c: char| c.is_whitespace() || c == '>' || c == '/')
+                        .map_or(tag.len(), |i| i + 1);
+                    out.push_str(&xhtml[last..tag.start() + name_end]);
+                    out.push_str(&format!(" id=\"{}\"", id));
+                    last = tag.start() + name_end;
+                    rewritten = true;
+                    id
+                }
+            };
+            list.targets.push(PageTarget {
+                label,
+                href: format!("{}#{}", item.href, id),
+            });
+        }
+        if rewritten {
+            out.push_str(&xhtml[last..]);
+            list.documents.insert(item.id.clone(), out);
+        }
+    }
+    list
+}
+
+/// Whether a tag's attributes mark a page break.
+fn is_page_break(attrs: &HashMap<&str, &str>) -> bool {
+    let has_token = |name: &str, token: &str| {
+        attrs
+            .get(name)
+            .is_some_and(|v| v.split_whitespace().any(|t| t == token))
+    };
+    has_token("epub:type", "pagebreak") || has_token("class", "mbp_pagebreak")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use convert_core::book::{ManifestData, ManifestItem};
+
+    #[test]
+    fn test_collect_page_list() {
+        let mut book = BookDocument::new();
+        let xhtml = r#"<html><body><p>One</p><div class="mbp_pagebreak"/><p>Two</p><span epub:type="pagebreak" id="p3" title="iii"></span><p>Three</p></body></html>"#;
+        book.manifest.add(ManifestItem::new(
+            "ch1",
+            "text/ch1.xhtml",
+            "application/xhtml+xml",
+            ManifestData::Xhtml(xhtml.into()),
+        ));
+        book.spine.push("ch1", true);
+
+        let list = collect_page_list(&book);
+        let targets: Vec<(&str, &str)> = list
+            .targets
+            .iter()
+            .map(|t| (t.label.as_str(), t.href.as_str()))
+            .collect();
+        assert_eq!(
+            targets,
+            [("1", "text/ch1.xhtml#page-1"), ("iii", "text/ch1.xhtml#p3")]
+        );
+        assert!(list.documents["ch1"].contains(r#"<div id="page-1" class="mbp_pagebreak"/>"#));
+    }
+}
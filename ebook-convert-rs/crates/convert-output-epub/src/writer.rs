@@ -1,14 +1,20 @@
 //! EPUB writer — assembles a valid EPUB file from BookDocument.
 
+use std::borrow::Cow;
+use std::collections::HashSet;
 use std::path::Path;
 
 use rayon::prelude::*;
+use regex::Regex;
 
-use convert_core::book::{BookDocument, ManifestData};
+use convert_core::book::{BookDocument, ManifestData, TocEntry};
 use convert_core::error::{ConvertError, Result};
-use convert_core::options::ConversionOptions;
-use convert_utils::archive::ZipBuilder;
-use convert_utils::xml::XmlBuilder;
+use convert_core::opf::OpfNavigation;
+use convert_core::options::{ConversionOptions, EpubCompression, EpubVersion};
+use convert_utils::archive::{CompressedEntry, ZipBuilder};
+use convert_utils::xml::{escape_xml_attr, escape_xml_text, XmlBuilder};
+
+use crate::page_list::{collect_page_list, PageTarget};
 
 /// Write a BookDocument as an EPUB file.
 pub fn write_epub(
@@ -16,17 +22,38 @@ pub fn write_epub(
     output_path: &Path,
     options: &ConversionOptions,
 ) -> Result<()> {
-    let mut zip = ZipBuilder::new(output_path)
-        .map_err(|e| ConvertError::Epub(format!("Failed to create EPUB: {}", e)))?;
+    let zip = ZipBuilder::new(output_path).map_err(|e| ConvertError::io(output_path, e))?;
+    write_epub_archive(book, zip, output_path, options, true)
+}
+
+/// Write the EPUB entries into `zip`, compressing manifest items on the
+/// rayon pool when `parallel` is set. Both paths produce identical bytes.
+fn write_epub_archive(
+    book: &BookDocument,
+    mut zip: ZipBuilder,
+    output_path: &Path,
+    options: &ConversionOptions,
+    parallel: bool,
+) -> Result<()> {
+    // Failures writing the archive are reported against the EPUB itself
+    let io_error = |e| ConvertError::io(output_path, e);
+    let store_all = options.epub_compression == Some(EpubCompression::Stored);
+    let level = deflate_level(options.epub_compression);
+    zip.set_deflate_level(level);
 
     // 1. mimetype (must be first, stored uncompressed)
     zip.add_stored("mimetype", b"application/epub+zip")
-        .map_err(|e| ConvertError::Epub(format!("Failed to write mimetype: {}", e)))?;
+        .map_err(io_error)?;
 
     // 2. META-INF/container.xml
     let container_xml = generate_container_xml();
-    zip.add_file("META-INF/container.xml", container_xml.as_bytes())
-        .map_err(|e| ConvertError::Epub(format!("Failed to write container.xml: {}", e)))?;
+    add_entry(
+        &mut zip,
+        "META-INF/container.xml",
+        container_xml.as_bytes(),
+        store_all,
+    )
+    .map_err(io_error)?;
 
     // 3. Pre-resolve Lazy items in parallel, then write all content to zip sequentially
     // Collect items that need lazy loading
@@ -46,13 +73,7 @@ pub fn write_epub(
     let lazy_data: Vec<(String, std::result::Result<Vec<u8>, ConvertError>)> = lazy_items
         .into_par_iter()
         .map(|(href, file_path)| {
-            let result = std::fs::read(&file_path).map_err(|e| {
-                ConvertError::Epub(format!(
-                    "Failed to read lazy content {}: {}",
-                    file_path.display(),
-                    e
-                ))
-            });
+            let result = std::fs::read(&file_path).map_err(|e| ConvertError::io(&file_path, e));
             (href, result)
         })
         .collect();
@@ -63,64 +84,156 @@ pub fn write_epub(
         lazy_map.insert(href, result?);
     }
 
-    // Write all content to zip sequentially
+    // Fixed-layout content documents each need a viewport
+    let viewport = if book.metadata.is_fixed_layout() {
+        book_viewport(book)
+    } else {
+        None
+    };
+    let spine_ids: HashSet<&str> = book.spine.iter().map(|s| s.idref.as_str()).collect();
+    let pages = collect_page_list(book);
+
+    // Gather every manifest entry in order; text is always deflated, media
+    // only when it is not already compressed
+    let mut entries: Vec<(String, Cow<[u8]>, bool)> = Vec::new();
     for item in book.manifest.iter() {
         let path = format!("OEBPS/{}", item.href);
         let is_precompressed = is_precompressed_media(&item.media_type);
         match &item.data {
             ManifestData::Xhtml(s) => {
-                zip.add_file(&path, s.as_bytes())
-                    .map_err(|e| ConvertError::Epub(format!("Failed to write {}: {}", path, e)))?;
-            }
-            ManifestData::Css(s) => {
-                zip.add_file(&path, s.as_bytes())
-                    .map_err(|e| ConvertError::Epub(format!("Failed to write {}: {}", path, e)))?;
+                let s = pages.documents.get(&item.id).unwrap_or(s);
+                let content = match viewport.as_deref() {
+                    Some(v) if spine_ids.contains(item.id.as_str()) => with_viewport(s, v),
+                    _ => Cow::Borrowed(s.as_str()),
+                };
+                let content = match content {
+                    Cow::Borrowed(s) => Cow::Borrowed(s.as_bytes()),
+                    Cow::Owned(s) => Cow::Owned(s.into_bytes()),
+                };
+                entries.push((path, content, store_all));
             }
+            ManifestData::Css(s) => entries.push((path, Cow::Borrowed(s.as_bytes()), store_all)),
             ManifestData::Binary(b) => {
-                if is_precompressed {
-                    zip.add_stored(&path, b).map_err(|e| {
-                        ConvertError::Epub(format!("Failed to write {}: {}", path, e))
-                    })?;
-                } else {
-                    zip.add_file(&path, b).map_err(|e| {
-                        ConvertError::Epub(format!("Failed to write {}: {}", path, e))
-                    })?;
-                }
+                entries.push((path, Cow::Borrowed(b), store_all || is_precompressed))
             }
             ManifestData::Lazy(_) => {
                 if let Some(data) = lazy_map.get(&item.href) {
-                    if is_precompressed {
-                        zip.add_stored(&path, data).map_err(|e| {
-                            ConvertError::Epub(format!("Failed to write {}: {}", path, e))
-                        })?;
-                    } else {
-                        zip.add_file(&path, data).map_err(|e| {
-                            ConvertError::Epub(format!("Failed to write {}: {}", path, e))
-                        })?;
-                    }
+                    entries.push((path, Cow::Borrowed(data), store_all || is_precompressed));
                 }
             }
             ManifestData::Empty => continue,
         }
     }
 
+    // Compress entries in parallel, then append them in manifest order since
+    // the archive itself can only be written from one thread
+    if parallel {
+        let compressed = entries
+            .par_iter()
+            .map(|(path, data, stored)| {
+                if *stored {
+                    CompressedEntry::stored(path, data)
+                } else {
+                    CompressedEntry::deflated(path, data, level)
+                }
+            })
+            .collect::<std::io::Result<Vec<_>>>()
+            .map_err(io_error)?;
+        for entry in compressed {
+            zip.add_compressed(entry).map_err(io_error)?;
+        }
+    } else {
+        for (path, data, stored) in &entries {
+            add_entry(&mut zip, path, data, *stored).map_err(io_error)?;
+        }
+    }
+
     // 4. OPF package document
-    let opf = generate_opf(book, options);
-    zip.add_file("OEBPS/content.opf", opf.as_bytes())
-        .map_err(|e| ConvertError::Epub(format!("Failed to write content.opf: {}", e)))?;
+    let nav_href = book.manifest.generate_href("nav", "xhtml");
+    let opf = generate_opf(book, options, &nav_href);
+    add_entry(&mut zip, "OEBPS/content.opf", opf.as_bytes(), store_all).map_err(io_error)?;
 
     // 5. NCX navigation document (EPUB 2)
-    let ncx = generate_ncx(book);
-    zip.add_file("OEBPS/toc.ncx", ncx.as_bytes())
-        .map_err(|e| ConvertError::Epub(format!("Failed to write toc.ncx: {}", e)))?;
+    let ncx = generate_ncx(book, &pages.targets);
+    add_entry(&mut zip, "OEBPS/toc.ncx", ncx.as_bytes(), store_all).map_err(io_error)?;
+
+    // 6. Navigation document (EPUB 3)
+    if package_version(book, options) == EpubVersion::V3 {
+        let nav = generate_nav(book, &pages.targets);
+        let path = format!("OEBPS/{}", nav_href);
+        add_entry(&mut zip, &path, nav.as_bytes(), store_all).map_err(io_error)?;
+    }
 
-    zip.finish()
-        .map_err(|e| ConvertError::Epub(format!("Failed to finalize EPUB: {}", e)))?;
+    zip.finish().map_err(io_error)?;
 
-    log::info!("EPUB written successfully: {}", output_path.display());
     Ok(())
 }
 
+/// Add an entry, deflated unless `stored`.
+fn add_entry(zip: &mut ZipBuilder, path: &str, data: &[u8], stored: bool) -> std::io::Result<()> {
+    if stored {
+        zip.add_stored(path, data)
+    } else {
+        zip.add_file(path, data)
+    }
+}
+
+/// The deflate level for a compression setting; `None` is the deflate default.
+fn deflate_level(compression: Option<EpubCompression>) -> Option<i64> {
+    match compression {
+        Some(EpubCompression::Fast) => Some(1),
+        Some(EpubCompression::Best) => Some(9),
+        Some(EpubCompression::Stored) | None => None,
+    }
+}
+
+/// The viewport for fixed-layout pages: `rendition:viewport` metadata, or
+/// the first viewport declared by a spine document.
+fn book_viewport(book: &BookDocument) -> Option<String> {
+    if let Some(viewport) = book.metadata.get_first_value("rendition:viewport") {
+        return Some(viewport.to_string());
+    }
+    let viewport = book
+        .spine
+        .iter()
+        .filter_map(|s| book.manifest.by_id(&s.idref))
+        .filter_map(|item| item.data.as_xhtml())
+        .find_map(|xhtml| declared_viewport(xhtml).map(str::to_string));
+    if viewport.is_none() {
+        log::warn!("Fixed-layout book has no viewport size; content documents left as-is");
+    }
+    viewport
+}
+
+/// The `content` of a document's `<meta name="viewport">`, if any.
+fn declared_viewport(xhtml: &str) -> Option<&str> {
+    let meta_re = Regex::new(r#"(?is)<meta\s[^>]*\bname\s*=\s*["']viewport["'][^>]*>"#).unwrap();
+    let content_re = Regex::new(r#"(?is)\bcontent\s*=\s*(?:"([^"]*)"|'([^']*)')"#).unwrap();
+    let meta = meta_re.find(xhtml)?;
+    let cap = content_re.captures(meta.as_str())?;
+    let content = cap.get(1).or_else(|| cap.get(2))?;
+    Some(&xhtml[meta.start() + content.start()..meta.start() + content.end()])
+}
+
+/// Add a viewport meta to a document's `<head>` unless it declares one.
+fn with_viewport<'a>(xhtml: &'a str, viewport: &str) -> Cow<'a, str> {
+    if declared_viewport(xhtml).is_some() {
+        return Cow::Borrowed(xhtml);
+    }
+    let Some(pos) = xhtml.to_lowercase().find("</head>") else {
+        return Cow::Borrowed(xhtml);
+    };
+    let meta = format!(
+        "<meta name=\"viewport\" content=\"{}\"/>\n",
+        escape_xml_attr(viewport)
+    );
+    let mut result = String::with_capacity(xhtml.len() + meta.len());
+    result.push_str(&xhtml[..pos]);
+    result.push_str(&meta);
+    result.push_str(&xhtml[pos..]);
+    Cow::Owned(result)
+}
+
 /// Check if a media type is already compressed (deflating would waste CPU).
 fn is_precompressed_media(media_type: &str) -> bool {
     matches!(
@@ -160,107 +273,31 @@ fn generate_container_xml() -> String {
     xml.build()
 }
 
-fn generate_opf(book: &BookDocument, _options: &ConversionOptions) -> String {
-    let uid = book
-        .uid
-        .as_deref()
-        .unwrap_or("urn:uuid:00000000-0000-0000-0000-000000000000");
-    let title = book.metadata.title().unwrap_or("Untitled");
-    let language = book.metadata.language().unwrap_or("en");
-
-    let mut xml = XmlBuilder::new();
-    xml.open_tag(
-        "package",
-        &[
-            ("xmlns", "http://www.idpf.org/2007/opf"),
-            ("unique-identifier", "bookid"),
-            ("version", "2.0"),
-        ],
-    );
-
-    // Metadata
-    xml.open_tag(
-        "metadata",
-        &[
-            ("xmlns:dc", "http://purl.org/dc/elements/1.1/"),
-            ("xmlns:opf", "http://www.idpf.org/2007/opf"),
-        ],
-    );
-    xml.text_element("dc:title", title, &[]);
-    xml.text_element("dc:language", language, &[]);
-    xml.text_element("dc:identifier", uid, &[("id", "bookid")]);
-
-    for author in book.metadata.authors() {
-        xml.text_element("dc:creator", author, &[("opf:role", "aut")]);
-    }
-
-    if let Some(desc) = book.metadata.description() {
-        xml.text_element("dc:description", desc, &[]);
-    }
-    if let Some(publisher) = book.metadata.publisher() {
-        xml.text_element("dc:publisher", publisher, &[]);
-    }
-    if let Some(date) = book.metadata.date() {
-        xml.text_element("dc:date", date, &[]);
+/// The EPUB version to write. Fixed-layout books need EPUB 3's rendition
+/// properties, so they are written as EPUB 3 whatever `epub_version` says.
+fn package_version(book: &BookDocument, options: &ConversionOptions) -> EpubVersion {
+    if book.metadata.is_fixed_layout() {
+        EpubVersion::V3
+    } else {
+        options.epub_version
     }
+}
 
-    xml.close_tag("metadata");
-
-    // Manifest
-    xml.open_tag("manifest", &[]);
-    xml.empty_tag(
-        "item",
-        &[
-            ("id", "ncx"),
-            ("href", "toc.ncx"),
-            ("media-type", "application/x-dtbncx+xml"),
-        ],
-    );
-
-    for item in book.manifest.iter() {
-        xml.empty_tag(
-            "item",
-            &[
-                ("id", &item.id),
-                ("href", &item.href),
-                ("media-type", &item.media_type),
-            ],
-        );
+/// The package document, listing the NCX and (for EPUB 3) the navigation
+/// document written alongside the content.
+fn generate_opf(book: &BookDocument, options: &ConversionOptions, nav_href: &str) -> String {
+    let version = package_version(book, options);
+    if version != options.epub_version {
+        log::info!("Writing fixed-layout book as EPUB 3");
     }
-    xml.close_tag("manifest");
-
-    // Spine
-    xml.open_tag("spine", &[("toc", "ncx")]);
-    for spine_item in book.spine.iter() {
-        if spine_item.linear {
-            xml.empty_tag("itemref", &[("idref", &spine_item.idref)]);
-        } else {
-            xml.empty_tag("itemref", &[("idref", &spine_item.idref), ("linear", "no")]);
-        }
-    }
-    xml.close_tag("spine");
-
-    // Guide
-    if !book.guide.is_empty() {
-        xml.open_tag("guide", &[]);
-        for guide_ref in book.guide.iter() {
-            xml.empty_tag(
-                "reference",
-                &[
-                    ("type", &guide_ref.ref_type),
-                    ("title", &guide_ref.title),
-                    ("href", &guide_ref.href),
-                ],
-            );
-        }
-        xml.close_tag("guide");
-    }
-
-    xml.close_tag("package");
-    xml.build()
+    let navigation = OpfNavigation {
+        ncx_href: Some("toc.ncx"),
+        nav_href: (version == EpubVersion::V3).then_some(nav_href),
+    };
+    book.to_opf_with(version, navigation)
 }
 
-fn generate_ncx(book: &BookDocument) -> String {
+fn generate_ncx(book: &BookDocument, pages: &[PageTarget]) -> String {
     let uid = book
         .uid
         .as_deref()
@@ -281,8 +318,21 @@ fn generate_ncx(book: &BookDocument) -> String {
     xml.open_tag("head", &[]);
     xml.empty_tag("meta", &[("name", "dtb:uid"), ("content", uid)]);
     xml.empty_tag("meta", &[("name", "dtb:depth"), ("content", "1")]);
-    xml.empty_tag("meta", &[("name", "dtb:totalPageCount"), ("content", "0")]);
-    xml.empty_tag("meta", &[("name", "dtb:maxPageNumber"), ("content", "0")]);
+    let page_count = pages.len().to_string();
+    let max_page = pages
+        .iter()
+        .filter_map(|p| p.label.parse::<u32>().ok())
+        .max()
+        .unwrap_or(0)
+        .to_string();
+    xml.empty_tag(
+        "meta",
+        &[("name", "dtb:totalPageCount"), ("content", &page_count)],
+    );
+    xml.empty_tag(
+        "meta",
+        &[("name", "dtb:maxPageNumber"), ("content", &max_page)],
+    );
     xml.close_tag("head");
 
     // Doc title
@@ -298,15 +348,37 @@ fn generate_ncx(book: &BookDocument) -> String {
     }
     xml.close_tag("navMap");
 
+    // Page list: numeric labels are normal pages, others (roman) front matter
+    if !pages.is_empty() {
+        xml.open_tag("pageList", &[]);
+        xml.open_tag("navLabel", &[]);
+        xml.text_element("text", "Pages", &[]);
+        xml.close_tag("navLabel");
+        for (i, page) in pages.iter().enumerate() {
+            let id = format!("pageTarget-{}", i + 1);
+            let po = play_order.to_string();
+            let mut attrs = vec![("id", id.as_str()), ("playOrder", po.as_str())];
+            if page.label.parse::<u32>().is_ok() {
+                attrs.extend([("type", "normal"), ("value", page.label.as_str())]);
+            } else {
+                attrs.push(("type", "front"));
+            }
+            xml.open_tag("pageTarget", &attrs);
+            xml.open_tag("navLabel", &[]);
+            xml.text_element("text", &page.label, &[]);
+            xml.close_tag("navLabel");
+            xml.empty_tag("content", &[("src", &page.href)]);
+            xml.close_tag("pageTarget");
+            play_order += 1;
+        }
+        xml.close_tag("pageList");
+    }
+
     xml.close_tag("ncx");
     xml.build()
 }
 
-fn write_ncx_nav_point(
-    xml: &mut XmlBuilder,
-    entry: &convert_core::book::TocEntry,
-    play_order: &mut u32,
-) {
+fn write_ncx_nav_point(xml: &mut XmlBuilder, entry: &TocEntry, play_order: &mut u32) {
     let id = format!("navPoint-{}", play_order);
     let po = play_order.to_string();
     xml.open_tag("navPoint", &[("id", &id), ("playOrder", &po)]);
@@ -319,10 +391,81 @@ fn write_ncx_nav_point(
     *play_order += 1;
 }
 
+/// EPUB 3 navigation document: the table of contents, plus a `page-list`
+/// nav when the content has page-break markers.
+fn generate_nav(book: &BookDocument, pages: &[PageTarget]) -> String {
+    let title = book.metadata.title().unwrap_or("Untitled");
+    let language = book.metadata.language().unwrap_or("en");
+
+    let mut html = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<!DOCTYPE html>\n");
+    html.push_str(&format!(
+        "<html xmlns=\"http://www.w3.org/1999/xhtml\" xmlns:epub=\"http://www.idpf.org/2007/ops\" lang=\"{0}\" xml:lang=\"{0}\">\n",
+        escape_xml_attr(language)
+    ));
+    html.push_str(&format!(
+        "<head><title>{}</title></head>\n<body>\n",
+        escape_xml_text(title)
+    ));
+
+    html.push_str("<nav epub:type=\"toc\" id=\"toc\">\n<ol>\n");
+    if book.toc.entries.is_empty() {
+        // The toc nav must list something; fall back to the first document
+        let first = book
+            .spine
+            .items()
+            .first()
+            .and_then(|s| book.manifest.by_id(&s.idref));
+        if let Some(item) = first {
+            html.push_str(&nav_item(title, &item.href));
+            html.push_str("</li>\n");
+        }
+    } else {
+        for entry in &book.toc.entries {
+            write_nav_entry(&mut html, entry);
+        }
+    }
+    html.push_str("</ol>\n</nav>\n");
+
+    if !pages.is_empty() {
+        html.push_str("<nav epub:type=\"page-list\" id=\"page-list\" hidden=\"\">\n<ol>\n");
+        for page in pages {
+            html.push_str(&nav_item(&page.label, &page.href));
+            html.push_str("</li>\n");
+        }
+        html.push_str("</ol>\n</nav>\n");
+    }
+
+    html.push_str("</body>\n</html>\n");
+    html
+}
+
+fn write_nav_entry(html: &mut String, entry: &TocEntry) {
+    html.push_str(&nav_item(&entry.title, &entry.href));
+    if !entry.children.is_empty() {
+        html.push_str("\n<ol>\n");
+        for child in &entry.children {
+            write_nav_entry(html, child);
+        }
+        html.push_str("</ol>\n");
+    }
+    html.push_str("</li>\n");
+}
+
+/// An open `<li>` holding a link.
+fn nav_item(label: &str, href: &str) -> String {
+    format!(
+        "<li><a href=\"{}\">{}</a>",
+        escape_xml_attr(href),
+        escape_xml_text(label)
+    )
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use convert_core::book::{BookDocument, ManifestData, ManifestItem, TocEntry};
+    use std::collections::HashMap;
+
+    use convert_core::book::{BookDocument, GuideRef, ManifestData, ManifestItem, TocEntry};
 
     fn make_test_book() -> BookDocument {
         let mut book = BookDocument::new();
@@ -356,21 +499,182 @@ mod tests {
     fn test_generate_opf() {
         let book = make_test_book();
         let opts = ConversionOptions::default();
-        let opf = generate_opf(&book, &opts);
+        let opf = generate_opf(&book, &opts, "nav.xhtml");
         assert!(opf.contains("<dc:title>Test Book</dc:title>"));
         assert!(opf.contains("chapter1.xhtml"));
         assert!(opf.contains("idref=\"ch1\""));
     }
 
+    #[test]
+    fn test_generate_opf_cover_meta() {
+        let mut book = make_test_book();
+        book.manifest.add(ManifestItem::new(
+            "cover1",
+            "cover.png",
+            "image/png",
+            ManifestData::Binary(vec![0x89]),
+        ));
+        let opts = ConversionOptions::default();
+        assert!(!generate_opf(&book, &opts, "nav.xhtml").contains(r#"name="cover""#));
+
+        book.guide.add(GuideRef::new("cover", "Cover", "cover.png"));
+        let opf = generate_opf(&book, &opts, "nav.xhtml");
+        assert!(opf.contains(r#"<meta name="cover" content="cover1"/>"#));
+    }
+
+    #[test]
+    fn test_generate_opf_series() {
+        let mut book = make_test_book();
+        book.metadata.set("series", "Saga");
+        book.metadata.set("series_index", "2");
+        let opf = generate_opf(&book, &ConversionOptions::default(), "nav.xhtml");
+        assert!(opf.contains(r#"<meta name="calibre:series" content="Saga"/>"#));
+        assert!(opf.contains(r#"<meta name="calibre:series_index" content="2"/>"#));
+    }
+
+    #[test]
+    fn test_generate_opf_refinements() {
+        let mut book = make_test_book();
+        book.metadata.remove("title");
+        let main = HashMap::from([("title-type".to_string(), "main".to_string())]);
+        book.metadata.add_with_attrs("title", "Test Book", main);
+        book.metadata.add("title", "Second");
+        let illustrator = HashMap::from([("role".to_string(), "ill".to_string())]);
+        book.metadata
+            .add_with_attrs("creator", "Ill Ustrator", illustrator);
+
+        let opf = generate_opf(&book, &ConversionOptions::default(), "nav.xhtml");
+        assert!(opf.contains("<dc:title>Test Book</dc:title>"));
+        assert!(opf.contains(r#"<dc:creator opf:role="aut">Test Author</dc:creator>"#));
+        assert!(opf.contains(r#"<dc:creator opf:role="ill">Ill Ustrator</dc:creator>"#));
+
+        let opts = ConversionOptions {
+            epub_version: EpubVersion::V3,
+            ..Default::default()
+        };
+        let opf = generate_opf(&book, &opts, "nav.xhtml");
+        assert!(opf.contains(r#"<dc:title id="title1">Test Book</dc:title>"#));
+        assert!(opf.contains(r##"<meta refines="#title1" property="title-type">main</meta>"##));
+        assert!(opf.contains("<dc:title>Second</dc:title>"));
+        assert!(opf.contains(r#"<dc:creator id="creator2">Ill Ustrator</dc:creator>"#));
+        assert!(opf.contains(
+            r##"<meta refines="#creator2" property="role" scheme="marc:relators">ill</meta>"##
+        ));
+    }
+
+    #[test]
+    fn test_generate_opf_fixed_layout() {
+        let mut book = make_test_book();
+        let opts = ConversionOptions::default();
+        assert!(!generate_opf(&book, &opts, "nav.xhtml").contains("rendition:"));
+
+        book.metadata.set("rendition:layout", "pre-paginated");
+        book.metadata.set("rendition:spread", "none");
+        // Rendition properties are EPUB 3 only, so the package is upgraded
+        let opf = generate_opf(&book, &opts, "nav.xhtml");
+        assert!(opf.contains(r#"version="3.0""#));
+        assert!(opf.contains(r#"properties="nav""#));
+        assert!(opf.contains(r#"<meta property="rendition:layout">pre-paginated</meta>"#));
+        assert!(opf.contains(r#"<meta property="rendition:orientation">auto</meta>"#));
+        assert!(opf.contains(r#"<meta property="rendition:spread">none</meta>"#));
+    }
+
+    #[test]
+    fn test_generate_opf_media_overlay() {
+        let mut book = make_test_book();
+        book.manifest.add(ManifestItem::new(
+            "ch1_overlay",
+            "chapter1.smil",
+            "application/smil+xml",
+            ManifestData::Binary(b"<smil/>".to_vec()),
+        ));
+        book.manifest.by_id_mut("ch1").unwrap().media_overlay = Some("ch1_overlay".into());
+        let refines = HashMap::from([("refines".to_string(), "#ch1_overlay".to_string())]);
+        book.metadata
+            .add_with_attrs("media:duration", "0:00:42", refines);
+
+        let opts = ConversionOptions {
+            epub_version: EpubVersion::V3,
+            ..Default::default()
+        };
+        let opf = generate_opf(&book, &opts, "nav.xhtml");
+        assert!(opf.contains(r#"href="chapter1.xhtml" media-type="application/xhtml+xml" media-overlay="ch1_overlay"/>"#));
+        assert!(opf.contains(
+            r##"<meta property="media:duration" refines="#ch1_overlay">0:00:42</meta>"##
+        ));
+    }
+
+    #[test]
+    fn test_with_viewport() {
+        let doc = "<html><head><title>p1</title></head><body/></html>";
+        assert_eq!(
+            with_viewport(doc, "width=600, height=800"),
+            "<html><head><title>p1</title><meta name=\"viewport\" content=\"width=600, height=800\"/>\n</head><body/></html>"
+        );
+
+        let declared =
+            r#"<html><head><meta content='width=100, height=200' name="viewport"/></head></html>"#;
+        assert_eq!(declared_viewport(declared), Some("width=100, height=200"));
+        assert_eq!(with_viewport(declared, "width=600, height=800"), declared);
+    }
+
     #[test]
     fn test_generate_ncx() {
         let book = make_test_book();
-        let ncx = generate_ncx(&book);
+        let ncx = generate_ncx(&book, &[]);
         assert!(ncx.contains("Chapter 1"));
         assert!(ncx.contains("chapter1.xhtml"));
         assert!(ncx.contains("navPoint"));
     }
 
+    #[test]
+    fn test_page_list() {
+        let mut book = make_test_book();
+        let xhtml = r#"<html><body><p>One</p><div class="mbp_pagebreak"></div><p>Two</p><span epub:type="pagebreak" title="3"/><p>Three</p></body></html>"#;
+        book.manifest.by_id_mut("ch1").unwrap().data = ManifestData::Xhtml(xhtml.into());
+        let pages = collect_page_list(&book).targets;
+
+        let ncx = generate_ncx(&book, &pages);
+        assert_eq!(ncx.matches("<pageTarget ").count(), 2);
+        assert!(
+            ncx.contains(r#"<pageTarget id="pageTarget-2" playOrder="3" type="normal" value="3">"#)
+        );
+        assert!(ncx.contains(r#"<content src="chapter1.xhtml#page-1"/>"#));
+        assert!(ncx.contains(r#"<meta name="dtb:totalPageCount" content="2"/>"#));
+
+        let nav = generate_nav(&book, &pages);
+        let page_list = &nav[nav.find(r#"epub:type="page-list""#).unwrap()..];
+        assert_eq!(page_list.matches("<li>").count(), 2);
+        assert!(page_list.contains(r##"<a href="chapter1.xhtml#page-2">3</a>"##));
+
+        // Without markers, neither list is written
+        let book = make_test_book();
+        assert!(!generate_ncx(&book, &[]).contains("pageList"));
+        assert!(!generate_nav(&book, &[]).contains("page-list"));
+    }
+
+    #[test]
+    fn test_generate_opf_epub3() {
+        let book = make_test_book();
+        let opts = ConversionOptions {
+            epub_version: EpubVersion::V3,
+            ..Default::default()
+        };
+        let opf = generate_opf(&book, &opts, "nav.xhtml");
+        assert!(opf.contains(r#"version="3.0""#));
+        assert!(opf.contains(
+            r#"<item id="nav" href="nav.xhtml" media-type="application/xhtml+xml" properties="nav"/>"#
+        ));
+        assert!(Regex::new(
+            r#"<meta property="dcterms:modified">\d{4}-\d\d-\d\dT\d\d:\d\d:\d\dZ</meta>"#
+        )
+        .unwrap()
+        .is_match(&opf));
+        assert!(
+            generate_nav(&book, &[]).contains(r#"<li><a href="chapter1.xhtml">Chapter 1</a></li>"#)
+        );
+    }
+
     #[test]
     fn test_write_epub() {
         let book = make_test_book();
@@ -381,4 +685,91 @@ mod tests {
         assert!(std::fs::metadata(&tmp).unwrap().len() > 0);
         std::fs::remove_file(&tmp).ok();
     }
+
+    #[test]
+    fn test_parallel_compression_matches_sequential() {
+        let mut book = make_test_book();
+        for i in 2..=40 {
+            let id = format!("ch{}", i);
+            let body = format!("<p>Paragraph {} of a long chapter.</p>", i).repeat(200);
+            let xhtml = format!(
+                "<html xmlns=\"http://www.w3.org/1999/xhtml\"><head><title>{}</title></head><body>{}</body></html>",
+                id, body
+            );
+            book.manifest.add(ManifestItem::new(
+                &id,
+                format!("chapter{}.xhtml", i),
+                "application/xhtml+xml",
+                ManifestData::Xhtml(xhtml),
+            ));
+            book.spine.push(&id, true);
+        }
+        book.manifest.add(ManifestItem::new(
+            "img",
+            "images/pic.png",
+            "image/png",
+            ManifestData::Binary(vec![0x89, b'P', b'N', b'G', 0, 1, 2, 3]),
+        ));
+        book.manifest.add(ManifestItem::new(
+            "style",
+            "style.css",
+            "text/css",
+            ManifestData::Css("p { margin: 0; }\n".repeat(100)),
+        ));
+
+        let opts = ConversionOptions::default();
+        let dir = std::env::temp_dir();
+        let modified = zip::DateTime::from_date_and_time(2024, 1, 1, 0, 0, 0).unwrap();
+        let write = |name: &str, parallel: bool| {
+            let path = dir.join(name);
+            let mut zip = ZipBuilder::new(&path).unwrap();
+            zip.set_last_modified(modified);
+            write_epub_archive(&book, zip, &path, &opts, parallel).unwrap();
+            path
+        };
+        let sequential = write("test_epub_sequential.epub", false);
+        let parallel = write("test_epub_parallel.epub", true);
+
+        let bytes = std::fs::read(&parallel).unwrap();
+        assert_eq!(bytes, std::fs::read(&sequential).unwrap());
+        // mimetype stays the first entry, stored uncompressed
+        assert_eq!(&bytes[30..38], b"mimetype");
+        assert_eq!(&bytes[8..10], &[0, 0]);
+
+        std::fs::remove_file(&sequential).ok();
+        std::fs::remove_file(&parallel).ok();
+    }
+
+    #[test]
+    fn test_epub_compression_levels() {
+        let mut book = make_test_book();
+        let body = "<p>The same sentence, over and over again.</p>".repeat(2000);
+        book.manifest.add(ManifestItem::new(
+            "long",
+            "long.xhtml",
+            "application/xhtml+xml",
+            ManifestData::Xhtml(format!("<html><body>{}</body></html>", body)),
+        ));
+        book.spine.push("long", true);
+
+        let size = |compression| {
+            let opts = ConversionOptions {
+                epub_compression: Some(compression),
+                ..Default::default()
+            };
+            let path = std::env::temp_dir().join(format!("test_epub_{:?}.epub", compression));
+            write_epub(&book, &path, &opts).unwrap();
+            let bytes = std::fs::read(&path).unwrap();
+            std::fs::remove_file(&path).ok();
+            // mimetype is always the first entry and stored
+            assert_eq!(&bytes[30..38], b"mimetype");
+            assert_eq!(&bytes[8..10], &[0, 0]);
+            bytes.len()
+        };
+        let stored = size(EpubCompression::Stored);
+        let best = size(EpubCompression::Best);
+        assert!(stored > body.len(), "{}", stored);
+        assert!(best * 10 < stored, "best {} vs stored {}", best, stored);
+        assert!(size(EpubCompression::Fast) < stored);
+    }
 }
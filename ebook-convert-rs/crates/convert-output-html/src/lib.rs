@@ -2,6 +2,7 @@
 
 use std::path::Path;
 
+use convert_core::atomic::write_atomically;
 use convert_core::book::{BookDocument, EbookFormat, ManifestData};
 use convert_core::error::{ConvertError, Result};
 use convert_core::options::ConversionOptions;
@@ -47,8 +48,8 @@ impl OutputPlugin for HtmlOutputPlugin {
         }
         html.push_str("</head>\n<body>\n");
 
-        // Concatenate all spine XHTML content
-        for spine_item in book.spine.iter() {
+        // Concatenate all spine XHTML content, non-linear documents last
+        for spine_item in book.spine.reading_order() {
             if let Some(manifest_item) = book.manifest.by_id(&spine_item.idref) {
                 if let ManifestData::Xhtml(ref xhtml) = manifest_item.data {
                     // Extract body content
@@ -77,10 +78,9 @@ impl OutputPlugin for HtmlOutputPlugin {
             }
         }
 
-        std::fs::write(output_path, html)
-            .map_err(|e| ConvertError::Other(format!("Failed to write HTML: {}", e)))?;
-
-        Ok(())
+        write_atomically(output_path, |tmp| {
+            std::fs::write(tmp, html).map_err(|e| ConvertError::io(tmp, e))
+        })
     }
 }
 
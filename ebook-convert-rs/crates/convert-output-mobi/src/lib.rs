@@ -2,13 +2,16 @@
 //!
 //! Produces a PalmDOC-compatible PDB file with MOBI6 header + EXTH metadata.
 //! Uses no compression for simplicity. Images are appended as PDB records
-//! after text records.
+//! after text records. Output is reproducible: dates are zero unless
+//! `source_date_epoch` is set, and nothing depends on hash map order.
 
+use std::collections::HashMap;
 use std::path::Path;
 
 use rayon::prelude::*;
 
-use convert_core::book::{BookDocument, EbookFormat, ManifestData};
+use convert_core::atomic::write_atomically;
+use convert_core::book::{BookDocument, EbookFormat, GuideRef, ManifestData};
 use convert_core::error::{ConvertError, Result};
 use convert_core::options::ConversionOptions;
 use convert_core::plugin::OutputPlugin;
@@ -33,14 +36,14 @@ impl OutputPlugin for MobiOutputPlugin {
         &self,
         book: &BookDocument,
         output_path: &Path,
-        _options: &ConversionOptions,
+        options: &ConversionOptions,
     ) -> Result<()> {
         log::info!("Writing MOBI: {}", output_path.display());
-        write_mobi(book, output_path)
+        write_mobi(book, output_path, options)
     }
 }
 
-fn write_mobi(book: &BookDocument, output_path: &Path) -> Result<()> {
+fn write_mobi(book: &BookDocument, output_path: &Path, options: &ConversionOptions) -> Result<()> {
     let fallback_title = output_path
         .file_stem()
         .and_then(|s| s.to_str())
@@ -49,7 +52,7 @@ fn write_mobi(book: &BookDocument, output_path: &Path) -> Result<()> {
     let title = book.metadata.title().unwrap_or(&fallback_title);
 
     // Build HTML content
-    let html = build_mobi_html(book);
+    let (html, start_offset) = build_mobi_html(book);
     let html_bytes = html.as_bytes();
 
     // Split text into records
@@ -73,7 +76,11 @@ fn write_mobi(book: &BookDocument, output_path: &Path) -> Result<()> {
     let mut pdb = Vec::new();
 
     // -- PDB Header (78 bytes) --
-    write_pdb_header(&mut pdb, title, total_records);
+    // PDB dates are seconds since the Unix epoch, as Calibre writes them
+    let date = options
+        .source_date_epoch
+        .map_or(0, |secs| u32::try_from(secs).unwrap_or(u32::MAX));
+    write_pdb_header(&mut pdb, title, total_records, date);
 
     // Build all records first to compute offsets
     let mut all_records: Vec<Vec<u8>> = Vec::new();
@@ -85,6 +92,7 @@ fn write_mobi(book: &BookDocument, output_path: &Path) -> Result<()> {
         title,
         book,
         &image_records,
+        start_offset,
     );
     all_records.push(mobi_header);
 
@@ -131,13 +139,12 @@ fn write_mobi(book: &BookDocument, output_path: &Path) -> Result<()> {
         pdb.extend_from_slice(rec);
     }
 
-    std::fs::write(output_path, &pdb)
-        .map_err(|e| ConvertError::Other(format!("Failed to write MOBI: {}", e)))?;
-
-    Ok(())
+    write_atomically(output_path, |tmp| {
+        std::fs::write(tmp, &pdb).map_err(|e| ConvertError::io(tmp, e))
+    })
 }
 
-fn write_pdb_header(pdb: &mut Vec<u8>, title: &str, total_records: usize) {
+fn write_pdb_header(pdb: &mut Vec<u8>, title: &str, total_records: usize, date: u32) {
     let mut name_bytes = [0u8; 32];
     let name = title.as_bytes();
     let copy_len = name.len().min(31);
@@ -146,8 +153,8 @@ fn write_pdb_header(pdb: &mut Vec<u8>, title: &str, total_records: usize) {
 
     pdb.extend_from_slice(&0u16.to_be_bytes()); // 32-33: attributes
     pdb.extend_from_slice(&0u16.to_be_bytes()); // 34-35: version
-    pdb.extend_from_slice(&0u32.to_be_bytes()); // 36-39: creation date
-    pdb.extend_from_slice(&0u32.to_be_bytes()); // 40-43: modification date
+    pdb.extend_from_slice(&date.to_be_bytes()); // 36-39: creation date
+    pdb.extend_from_slice(&date.to_be_bytes()); // 40-43: modification date
     pdb.extend_from_slice(&0u32.to_be_bytes()); // 44-47: last backup date
     pdb.extend_from_slice(&0u32.to_be_bytes()); // 48-51: modification number
     pdb.extend_from_slice(&0u32.to_be_bytes()); // 52-55: app info offset
@@ -159,43 +166,178 @@ fn write_pdb_header(pdb: &mut Vec<u8>, title: &str, total_records: usize) {
     pdb.extend_from_slice(&(total_records as u16).to_be_bytes()); // 76-77: num records
 }
 
-fn build_mobi_html(book: &BookDocument) -> String {
+/// The MOBI text, and the offset of the guide's `text` reference in it when
+/// that lands on a spine document.
+fn build_mobi_html(book: &BookDocument) -> (String, Option<u32>) {
     let tag_re = Regex::new(r"(?i)</?(!DOCTYPE|html|head|meta|link|title|xml)[^>]*>").unwrap();
 
-    // Collect spine XHTMLs
-    let spine_xhtmls: Vec<&str> = book
+    // Collect spine XHTMLs with their hrefs, non-linear documents last
+    let spine_xhtmls: Vec<(&str, &str)> = book
         .spine
-        .iter()
+        .reading_order()
         .filter_map(|si| book.manifest.by_id(&si.idref))
         .filter_map(|item| match &item.data {
-            ManifestData::Xhtml(ref x) => Some(x.as_str()),
+            ManifestData::Xhtml(ref x) => Some((item.href.as_str(), x.as_str())),
             _ => None,
         })
         .collect();
 
+    // The manifest stylesheets go into the one head; class rules that set
+    // text-align are also applied as `align` attributes, which MOBI readers
+    // honour even where they ignore the stylesheet.
+    let css: String = book
+        .manifest
+        .iter()
+        .filter_map(|item| match &item.data {
+            ManifestData::Css(ref c) => Some(c.as_str()),
+            _ => None,
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+    let class_align = class_alignments(&css);
+
     // Extract and clean bodies in parallel
     let bodies: Vec<String> = spine_xhtmls
         .par_iter()
-        .map(|xhtml| {
+        .map(|(_, xhtml)| {
             let body = extract_body(xhtml);
-            tag_re.replace_all(&body, "").to_string()
+            let body = tag_re.replace_all(&body, "");
+            add_align_attrs(&body, &class_align)
         })
         .collect();
 
-    let mut html = String::new();
-    html.push_str("<html><head><title>");
-    html.push_str(&convert_utils::xml::escape_xml_text(
-        book.metadata.title().unwrap_or("Untitled Document"),
-    ));
-    html.push_str("</title></head><body>\n");
-
-    for body in &bodies {
-        html.push_str(body);
-        html.push('\n');
+    // Each spine document starts on a fresh page
+    let mut content = String::new();
+    let mut doc_offsets: HashMap<&str, usize> = HashMap::new();
+    for (i, ((href, _), body)) in spine_xhtmls.iter().zip(&bodies).enumerate() {
+        if i > 0 {
+            content.push_str("<mbp:pagebreak/>\n");
+        }
+        doc_offsets.entry(href).or_insert(content.len());
+        content.push_str(body);
+        content.push('\n');
     }
 
+    // Guide references that land on a spine document, with their body offsets
+    let guide: Vec<(&GuideRef, usize)> = book
+        .guide
+        .iter()
+        .filter_map(|r| {
+            let href = r.href.split('#').next()?;
+            doc_offsets.get(href).map(|&offset| (r, offset))
+        })
+        .collect();
+
+    let title =
+        convert_utils::xml::escape_xml_text(book.metadata.title().unwrap_or("Untitled Document"));
+    // filepos values are fixed-width, so the head's length doesn't depend on them
+    let head_len = mobi_head(&title, &css, &guide, 0).len();
+    let mut html = mobi_head(&title, &css, &guide, head_len);
+    html.push_str(&content);
     html.push_str("</body></html>");
-    html
+
+    let start_offset = guide
+        .iter()
+        .find(|(r, _)| r.ref_type == "text")
+        .map(|(_, offset)| (head_len + offset) as u32);
+    (html, start_offset)
+}
+
+/// `<html><head>...<body>` for the MOBI text, with the book's CSS and a
+/// `<guide>` whose references point `base` bytes past their body offsets.
+/// Guide titles are kept as the source gave them.
+fn mobi_head(title: &str, css: &str, guide: &[(&GuideRef, usize)], base: usize) -> String {
+    let mut head = format!("<html><head><title>{}</title>", title);
+    if !css.trim().is_empty() {
+        head.push_str("<style type=\"text/css\">\n");
+        head.push_str(css.trim());
+        head.push_str("\n</style>");
+    }
+    if !guide.is_empty() {
+        head.push_str("<guide>");
+        for (r, offset) in guide {
+            head.push_str(&format!(
+                "<reference type=\"{}\" title=\"{}\" filepos={:010} />",
+                convert_utils::xml::escape_xml_attr(&r.ref_type),
+                convert_utils::xml::escape_xml_attr(&r.title),
+                base + offset
+            ));
+        }
+        head.push_str("</guide>");
+    }
+    head.push_str("</head><body>\n");
+    head
+}
+
+/// The `text-align` value a CSS declaration block sets, if it is one MOBI's
+/// `align` attribute can express.
+fn text_align(declarations: &str) -> Option<String> {
+    declarations.split(';').find_map(|decl| {
+        let (prop, value) = decl.split_once(':')?;
+        if !prop.trim().eq_ignore_ascii_case("text-align") {
+            return None;
+        }
+        let value = value.trim().trim_end_matches("!important").trim();
+        let value = value.to_ascii_lowercase();
+        matches!(value.as_str(), "center" | "right" | "justify" | "left").then_some(value)
+    })
+}
+
+/// Map class names to the alignment set for them by simple `.class` or
+/// `element.class` rules in `css`.
+fn class_alignments(css: &str) -> HashMap<String, String> {
+    let rule_re = Regex::new(r"([^{}]+)\{([^{}]*)\}").unwrap();
+    let selector_re = Regex::new(r"^[A-Za-z0-9]*\.([\w-]+)$").unwrap();
+    let mut map = HashMap::new();
+    for rule in rule_re.captures_iter(css) {
+        let Some(align) = text_align(&rule[2]) else {
+            continue;
+        };
+        for selector in rule[1].split(',') {
+            if let Some(class) = selector_re.captures(selector.trim()) {
+                map.insert(class[1].to_string(), align.clone());
+            }
+        }
+    }
+    map
+}
+
+/// Give block elements whose inline style or class sets `text-align` a
+/// matching `align` attribute.
+fn add_align_attrs(body: &str, class_align: &HashMap<String, String>) -> String {
+    let block_re = Regex::new(r"(?i)<(p|div|h[1-6])(\s[^>]*?)?(/?)>").unwrap();
+    let attr_re = Regex::new(r#"(?i)\s(style|class|align)\s*=\s*(?:"([^"]*)"|'([^']*)')"#).unwrap();
+    block_re
+        .replace_all(body, |tag: &regex::Captures| {
+            let attrs = tag.get(2).map_or("", |m| m.as_str());
+            let mut align = None;
+            for attr in attr_re.captures_iter(attrs) {
+                let value = attr
+                    .get(2)
+                    .or_else(|| attr.get(3))
+                    .map_or("", |m| m.as_str());
+                match attr[1].to_ascii_lowercase().as_str() {
+                    "align" => return tag[0].to_string(),
+                    "style" => {
+                        if let Some(a) = text_align(value) {
+                            align = Some(a);
+                        }
+                    }
+                    _ => {
+                        if align.is_none() {
+                            align = value
+                                .split_whitespace()
+                                .find_map(|c| class_align.get(c).cloned());
+                        }
+                    }
+                }
+            }
+            match align {
+                Some(align) => format!("<{}{} align=\"{}\"{}>", &tag[1], attrs, align, &tag[3]),
+                None => tag[0].to_string(),
+            }
+        })
+        .to_string()
 }
 
 fn extract_body(xhtml: &str) -> String {
@@ -215,15 +357,29 @@ fn split_into_records(data: &[u8]) -> Vec<Vec<u8>> {
         .collect()
 }
 
-/// Build EXTH header with metadata.
-fn build_exth(book: &BookDocument) -> Vec<u8> {
+/// Position of the guide's cover image among the image records, which are
+/// written in manifest order.
+fn cover_image_index(book: &BookDocument) -> Option<u32> {
+    let href = book.guide.get("cover")?.href.split('#').next()?;
+    book.manifest
+        .iter()
+        .filter(|item| item.is_image() && matches!(item.data, ManifestData::Binary(_)))
+        .position(|item| item.href == href)
+        .map(|i| i as u32)
+}
+
+/// Build EXTH header with metadata and the start reading offset.
+fn build_exth(book: &BookDocument, start_offset: Option<u32>) -> Vec<u8> {
     let mut exth = Vec::new();
     let mut records: Vec<(u32, Vec<u8>)> = Vec::new();
 
     // EXTH record types (Calibre ordering):
     // 524 = language, 503 = updated title, 100 = author,
     // 108 = contributor/source, 101 = publisher, 104 = isbn,
-    // 103 = description, 105 = subject, 106 = published date
+    // 103 = description, 105 = subject, 106 = published date,
+    // 116 = start reading offset, 201 = cover offset
+    // Records are always written in this order, whatever order the metadata
+    // was added in, so the same book gives the same bytes.
 
     // Language (type 524)
     let lang = book.metadata.language().unwrap_or("en");
@@ -257,9 +413,9 @@ fn build_exth(book: &BookDocument) -> Vec<u8> {
         records.push((103, desc.as_bytes().to_vec()));
     }
 
-    // Subject (type 105)
-    if let Some(subject) = book.metadata.get_first_value("subject") {
-        records.push((105, subject.as_bytes().to_vec()));
+    // Subject (type 105), one record per tag
+    for subject in book.metadata.get("subject").into_iter().flatten() {
+        records.push((105, subject.value.as_bytes().to_vec()));
     }
 
     // Date (type 106)
@@ -267,6 +423,16 @@ fn build_exth(book: &BookDocument) -> Vec<u8> {
         records.push((106, date.as_bytes().to_vec()));
     }
 
+    // Start reading offset (type 116), where "Go to Beginning" lands
+    if let Some(offset) = start_offset {
+        records.push((116, offset.to_be_bytes().to_vec()));
+    }
+
+    // Cover offset (type 201), relative to the first image record
+    if let Some(index) = cover_image_index(book) {
+        records.push((201, index.to_be_bytes().to_vec()));
+    }
+
     exth.extend_from_slice(b"EXTH"); // magic
                                      // Compute total length: 12 (header) + sum of (8 + data_len padded)
     let mut record_bytes = Vec::new();
@@ -296,6 +462,7 @@ fn build_mobi_header_record(
     title: &str,
     book: &BookDocument,
     image_records: &[Vec<u8>],
+    start_offset: Option<u32>,
 ) -> Vec<u8> {
     let mut rec = Vec::new();
 
@@ -313,7 +480,7 @@ fn build_mobi_header_record(
     let title_bytes = title.as_bytes();
 
     // Build EXTH first to know its size
-    let exth_data = build_exth(book);
+    let exth_data = build_exth(book, start_offset);
     let has_exth = !book.metadata.authors().is_empty() || book.metadata.title().is_some();
 
     // MOBI header is 232 bytes (from "MOBI" to end of header)
@@ -380,7 +547,7 @@ fn build_mobi_header_record(
     // 180-191: 12 bytes unused
     rec.extend_from_slice(&[0u8; 12]);
 
-    rec.extend_from_slice(&0xFFFFu16.to_be_bytes()); // 192-193: first content record (0xFFFF = use default)
+    rec.extend_from_slice(&1u16.to_be_bytes()); // 192-193: first content record
     rec.extend_from_slice(&(text_record_count + 1).to_be_bytes()); // 194-195: last content record
 
     rec.extend_from_slice(&1u32.to_be_bytes()); // 196-199: unknown (1)
@@ -487,7 +654,7 @@ mod tests {
         book.metadata.set_title("Test Book");
         book.metadata.add("creator", "Author");
 
-        let rec = build_mobi_header_record(5000, 2, "Test Book", &book, &[]);
+        let rec = build_mobi_header_record(5000, 2, "Test Book", &book, &[], None);
         // PalmDOC compression = 1 (no compression)
         assert_eq!(u16::from_be_bytes([rec[0], rec[1]]), 1);
         // Text length at bytes 4-7
@@ -511,7 +678,7 @@ mod tests {
         book.metadata.set_title("Test Book");
         book.metadata.add("creator", "Test Author");
 
-        let rec = build_mobi_header_record(100, 1, "Test Book", &book, &[]);
+        let rec = build_mobi_header_record(100, 1, "Test Book", &book, &[], None);
         // EXTH flags at bytes 128-131 should be 0x50
         let exth_flags = u32::from_be_bytes([rec[128], rec[129], rec[130], rec[131]]);
         assert_eq!(exth_flags, 0x50, "EXTH flags should be 0x50");
@@ -548,6 +715,55 @@ mod tests {
         std::fs::remove_file(&tmp).ok();
     }
 
+    #[test]
+    fn test_mobi_output_reproducible() {
+        // The same metadata, added in a different order
+        let book = |reversed: bool| {
+            let mut book = BookDocument::new();
+            let mut fields = vec![
+                ("title", "Same Book"),
+                ("creator", "Author"),
+                ("publisher", "Press"),
+                ("description", "About it."),
+                ("date", "2020-01-01"),
+                ("language", "fr"),
+            ];
+            if reversed {
+                fields.reverse();
+            }
+            for (term, value) in fields {
+                book.metadata.add(term, value);
+            }
+            book.manifest.add(ManifestItem::new(
+                "ch1",
+                "ch1.xhtml",
+                "application/xhtml+xml",
+                ManifestData::Xhtml("<html><body><p>Hello.</p></body></html>".into()),
+            ));
+            book.spine.push("ch1", true);
+            book
+        };
+        let dir = std::env::temp_dir().join("test_mobi_reproducible");
+        std::fs::create_dir_all(&dir).unwrap();
+        let opts = ConversionOptions {
+            source_date_epoch: Some(1_600_000_000),
+            ..Default::default()
+        };
+        let convert = |book: &BookDocument, name: &str| {
+            let path = dir.join(name);
+            MobiOutputPlugin.convert(book, &path, &opts).unwrap();
+            std::fs::read(path).unwrap()
+        };
+
+        let first = convert(&book(false), "first.mobi");
+        assert_eq!(first, convert(&book(false), "second.mobi"));
+        assert_eq!(first, convert(&book(true), "reversed.mobi"));
+        // Creation and modification dates
+        assert_eq!(&first[36..40], &1_600_000_000u32.to_be_bytes());
+        assert_eq!(&first[40..44], &1_600_000_000u32.to_be_bytes());
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
     #[test]
     fn test_build_mobi_html() {
         let mut book = BookDocument::new();
@@ -562,13 +778,45 @@ mod tests {
         book.manifest.add(item);
         book.spine.push("ch1", true);
 
-        let html = build_mobi_html(&book);
+        let (html, _) = build_mobi_html(&book);
         assert!(html.contains("<title>Test</title>"));
         assert!(html.contains("<p>Content here</p>"));
         assert!(html.starts_with("<html>"));
         assert!(html.ends_with("</html>"));
     }
 
+    #[test]
+    fn test_build_mobi_html_keeps_css_and_alignment() {
+        let mut book = BookDocument::new();
+        book.manifest.add(ManifestItem::new(
+            "css",
+            "style.css",
+            "text/css",
+            ManifestData::Css("p.centered, .title { text-align: center }".to_string()),
+        ));
+        let xhtml = r#"<html><head><link rel="stylesheet" href="style.css"/></head><body><p class="centered">Middle</p><p style="text-align: right">Right</p><p class="plain">Left</p></body></html>"#;
+        book.manifest.add(ManifestItem::new(
+            "ch1",
+            "ch1.xhtml",
+            "application/xhtml+xml",
+            ManifestData::Xhtml(xhtml.to_string()),
+        ));
+        book.spine.push("ch1", true);
+
+        let (html, _) = build_mobi_html(&book);
+        assert!(html.contains(
+            "<style type=\"text/css\">\np.centered, .title { text-align: center }\n</style>"
+        ));
+        assert!(
+            html.contains(r#"<p class="centered" align="center">Middle</p>"#),
+            "{}",
+            html
+        );
+        assert!(html.contains(r#"<p style="text-align: right" align="right">Right</p>"#));
+        assert!(html.contains(r#"<p class="plain">Left</p>"#));
+        assert!(!html.contains("<link"));
+    }
+
     #[test]
     fn test_flis_record() {
         let flis = build_flis_record();
@@ -592,11 +840,119 @@ mod tests {
         book.metadata.set_title("My Book");
         book.metadata.add("creator", "Jane Doe");
         book.metadata.add("publisher", "Test Press");
+        book.metadata.add("subject", "Fiction");
 
-        let exth = build_exth(&book);
+        let exth = build_exth(&book, None);
         assert_eq!(&exth[..4], b"EXTH");
-        // Should have: language(524), title(503), author(100), source(108), publisher(101)
+        // Should have: language(524), title(503), author(100), source(108),
+        // publisher(101), subject(105)
         let count = u32::from_be_bytes([exth[8], exth[9], exth[10], exth[11]]);
-        assert_eq!(count, 5);
+        assert_eq!(count, 6);
+    }
+
+    #[test]
+    fn test_exth_subject_per_tag() {
+        let mut book = BookDocument::new();
+        book.metadata.add("subject", "sf_history");
+        book.metadata.add("subject", "adventure");
+
+        let exth = build_exth(&book, None);
+        let record = |subject: &str| {
+            [
+                &105u32.to_be_bytes()[..],
+                &(8 + subject.len() as u32).to_be_bytes(),
+                subject.as_bytes(),
+            ]
+            .concat()
+        };
+        let history = exth
+            .windows(record("sf_history").len())
+            .position(|w| w == record("sf_history").as_slice())
+            .unwrap();
+        let adventure = exth
+            .windows(record("adventure").len())
+            .position(|w| w == record("adventure").as_slice())
+            .unwrap();
+        assert!(history < adventure);
+    }
+
+    #[test]
+    fn test_exth_start_and_cover_offsets() {
+        let mut book = BookDocument::new();
+        for name in ["map", "cover"] {
+            book.manifest.add(ManifestItem::new(
+                name,
+                format!("images/{}.png", name),
+                "image/png",
+                ManifestData::Binary(vec![0x89, b'P', b'N', b'G']),
+            ));
+        }
+        book.guide
+            .add(GuideRef::new("cover", "Cover", "images/cover.png"));
+
+        let exth = build_exth(&book, Some(4096));
+        let record = [
+            &116u32.to_be_bytes()[..],
+            &12u32.to_be_bytes(),
+            &4096u32.to_be_bytes(),
+        ]
+        .concat();
+        assert!(exth.windows(12).any(|w| w == record.as_slice()));
+        let record = [
+            &201u32.to_be_bytes()[..],
+            &12u32.to_be_bytes(),
+            &1u32.to_be_bytes(),
+        ]
+        .concat();
+        assert!(exth.windows(12).any(|w| w == record.as_slice()));
+    }
+
+    #[test]
+    fn test_build_mobi_html_page_break_between_documents() {
+        let mut book = BookDocument::new();
+        for (id, text) in [("ch1", "First"), ("ch2", "Second")] {
+            let xhtml = format!("<html><body><p>{}</p></body></html>", text);
+            book.manifest.add(ManifestItem::new(
+                id,
+                format!("{}.xhtml", id),
+                "application/xhtml+xml",
+                ManifestData::Xhtml(xhtml),
+            ));
+            book.spine.push(id, true);
+        }
+
+        let (html, _) = build_mobi_html(&book);
+        assert_eq!(html.matches("<mbp:pagebreak/>").count(), 1);
+        let first = html.find("<p>First</p>").unwrap();
+        let brk = html.find("<mbp:pagebreak/>").unwrap();
+        let second = html.find("<p>Second</p>").unwrap();
+        assert!(first < brk && brk < second);
+    }
+
+    #[test]
+    fn test_build_mobi_html_guide() {
+        let mut book = BookDocument::new();
+        for (id, text) in [("toc", "Contents"), ("ch1", "First")] {
+            let xhtml = format!("<html><body><p>{}</p></body></html>", text);
+            book.manifest.add(ManifestItem::new(
+                id,
+                format!("{}.xhtml", id),
+                "application/xhtml+xml",
+                ManifestData::Xhtml(xhtml),
+            ));
+            book.spine.push(id, true);
+        }
+        book.guide
+            .add(GuideRef::new("text", "Beginning", "ch1.xhtml#start"));
+        book.guide
+            .add(GuideRef::new("cover", "Cover", "images/cover.jpg"));
+
+        let (html, start_offset) = build_mobi_html(&book);
+        assert!(!html.contains(r#"type="cover""#));
+        let re =
+            Regex::new(r#"<reference type="text" title="Beginning" filepos=(\d{10}) />"#).unwrap();
+        let filepos: usize = re.captures(&html).unwrap()[1].parse().unwrap();
+        assert!(html[filepos..].starts_with("<p>First</p>"));
+        assert_eq!(start_offset, Some(filepos as u32));
     }
 }
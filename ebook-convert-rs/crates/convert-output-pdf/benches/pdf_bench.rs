@@ -1,6 +1,8 @@
 //! Benchmarks for PDF output plugin.
 
-use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use std::hint::black_box;
+
+use criterion::{criterion_group, criterion_main, Criterion};
 
 use convert_core::book::{BookDocument, ManifestData, ManifestItem, TocEntry};
 use convert_core::options::ConversionOptions;
@@ -0,0 +1,167 @@
+//! Resolution recorded in image files.
+//!
+//! Images are placed at their physical size, so a 300 DPI scan prints
+//! smaller than a 72 DPI screenshot of the same pixel size. The resolution
+//! comes from the EXIF `XResolution` tag (read through the `image` crate),
+//! else from a JPEG's JFIF density or a PNG's `pHYs` chunk.
+
+use std::io::Cursor;
+
+use image::{ImageDecoder, ImageReader};
+
+/// Resolutions outside this range are treated as missing; some encoders
+/// write placeholders like 1 DPI.
+const PLAUSIBLE_DPI: std::ops::RangeInclusive<f32> = 16.0..=4800.0;
+
+/// Horizontal pixels per inch recorded in `data`, if any.
+pub(crate) fn image_dpi(data: &[u8]) -> Option<f32> {
+    exif_dpi(data)
+        .or_else(|| jfif_dpi(data))
+        .or_else(|| png_dpi(data))
+        .filter(|dpi| PLAUSIBLE_DPI.contains(dpi))
+}
+
+/// `XResolution` and `ResolutionUnit` from the image's EXIF block.
+fn exif_dpi(data: &[u8]) -> Option<f32> {
+    let mut decoder = ImageReader::new(Cursor::new(data))
+        .with_guessed_format()
+        .ok()?
+        .into_decoder()
+        .ok()?;
+    let exif = decoder.exif_metadata().ok()??;
+
+    let big_endian = match exif.get(..4)? {
+        [b'I', b'I', 42, 0] => false,
+        [b'M', b'M', 0, 42] => true,
+        _ => return None,
+    };
+    let u16_at = |pos: usize| -> Option<u16> {
+        let bytes = [*exif.get(pos)?, *exif.get(pos + 1)?];
+        Some(if big_endian {
+            u16::from_be_bytes(bytes)
+        } else {
+            u16::from_le_bytes(bytes)
+        })
+    };
+    let u32_at = |pos: usize| -> Option<u32> {
+        let bytes: [u8; 4] = exif.get(pos..pos + 4)?.try_into().ok()?;
+        Some(if big_endian {
+            u32::from_be_bytes(bytes)
+        } else {
+            u32::from_le_bytes(bytes)
+        })
+    };
+
+    // IFD0 entries: tag, type, count, then the value or its offset
+    let ifd = u32_at(4)? as usize;
+    let mut resolution = None;
+    let mut unit = 2;
+    for i in 0..usize::from(u16_at(ifd)?) {
+        let entry = ifd + 2 + i * 12;
+        match u16_at(entry)? {
+            0x011A => {
+                let offset = u32_at(entry + 8)? as usize;
+                let (num, den) = (u32_at(offset)?, u32_at(offset + 4)?);
+                resolution = (den > 0).then(|| num as f32 / den as f32);
+            }
+            0x0128 => unit = u16_at(entry + 8)?,
+            _ => {}
+        }
+    }
+    match unit {
+        2 => resolution,
+        3 => resolution.map(|per_cm| per_cm * 2.54),
+        _ => None,
+    }
+}
+
+/// Density from a JPEG's JFIF `APP0` segment.
+fn jfif_dpi(data: &[u8]) -> Option<f32> {
+    if !data.starts_with(&[0xFF, 0xD8, 0xFF, 0xE0]) || data.get(6..11)? != b"JFIF\0" {
+        return None;
+    }
+    let density = f32::from(u16::from_be_bytes([*data.get(14)?, *data.get(15)?]));
+    match data.get(13)? {
+        1 => Some(density),
+        2 => Some(density * 2.54),
+        // Aspect ratio only
+        _ => None,
+    }
+}
+
+/// Density from a PNG's `pHYs` chunk, which must come before the image data.
+fn png_dpi(data: &[u8]) -> Option<f32> {
+    let mut pos = 8;
+    if !data.starts_with(b"\x89PNG\r\n\x1a\n") {
+        return None;
+    }
+    while let Some(header) = data.get(pos..pos + 8) {
+        let len = u32::from_be_bytes(header[..4].try_into().ok()?) as usize;
+        match &header[4..] {
+            b"pHYs" => {
+                let chunk = data.get(pos + 8..pos + 17)?;
+                let per_metre = u32::from_be_bytes(chunk[..4].try_into().ok()?);
+                // Unit 1 is metres; 0 only gives the aspect ratio
+                return (chunk[8] == 1).then_some(per_metre as f32 * 0.0254);
+            }
+            b"IDAT" | b"IEND" => return None,
+            _ => pos += 12 + len,
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::codecs::jpeg::{JpegEncoder, PixelDensity};
+    use image::{ExtendedColorType, ImageEncoder};
+
+    fn jpeg_at(dpi: u16, size: u32) -> Vec<u8> {
+        let pixels = vec![128u8; (size * size * 3) as usize];
+        let mut data = Vec::new();
+        let mut encoder = JpegEncoder::new(&mut data);
+        encoder.set_pixel_density(PixelDensity::dpi(dpi));
+        encoder
+            .write_image(&pixels, size, size, ExtendedColorType::Rgb8)
+            .unwrap();
+        data
+    }
+
+    #[test]
+    fn test_jfif_density() {
+        assert_eq!(image_dpi(&jpeg_at(300, 8)), Some(300.0));
+        assert_eq!(image_dpi(&jpeg_at(72, 8)), Some(72.0));
+    }
+
+    #[test]
+    fn test_png_density() {
+        let mut png = Vec::new();
+        image::RgbImage::new(4, 4)
+            .write_to(&mut Cursor::new(&mut png), image::ImageFormat::Png)
+            .unwrap();
+        assert_eq!(image_dpi(&png), None);
+
+        // Insert a pHYs chunk of 11811 px/m (300 DPI) after IHDR
+        let mut chunk = Vec::new();
+        chunk.extend_from_slice(&9u32.to_be_bytes());
+        chunk.extend_from_slice(b"pHYs");
+        chunk.extend_from_slice(&11811u32.to_be_bytes());
+        chunk.extend_from_slice(&11811u32.to_be_bytes());
+        chunk.push(1);
+        chunk.extend_from_slice(&[0; 4]);
+        png.splice(33..33, chunk);
+        let dpi = image_dpi(&png).unwrap();
+        assert!((dpi - 300.0).abs() < 0.1, "{}", dpi);
+    }
+
+    #[test]
+    fn test_untagged_jpeg() {
+        let pixels = vec![0u8; 8 * 8 * 3];
+        let mut data = Vec::new();
+        JpegEncoder::new(&mut data)
+            .write_image(&pixels, 8, 8, ExtendedColorType::Rgb8)
+            .unwrap();
+        assert_eq!(image_dpi(&data), None);
+    }
+}
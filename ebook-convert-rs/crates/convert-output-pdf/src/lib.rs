@@ -2,20 +2,28 @@
 //!
 //! Uses printpdf 0.8 with builtin Helvetica fonts and Op-based page construction.
 //! Text is extracted from XHTML spine items and rendered with word wrapping.
-//! Images are embedded as XObjects.
+//! Tables are drawn as bordered grids. Images are embedded as XObjects, sized
+//! by the resolution they record.
+
+mod dpi;
+mod table;
 
 use std::path::Path;
+use std::sync::LazyLock;
 
 use rayon::prelude::*;
 
-use convert_core::book::{BookDocument, EbookFormat, ManifestData};
+use convert_core::atomic::write_atomically;
+use convert_core::book::{BookDocument, EbookFormat, ManifestData, ManifestItem};
 use convert_core::error::{ConvertError, Result};
-use convert_core::options::ConversionOptions;
+use convert_core::options::{ConversionOptions, PdfCoverPage};
 use convert_core::plugin::OutputPlugin;
 
 use printpdf::*;
 use regex::Regex;
 
+use table::Table;
+
 /// A4 page dimensions in mm.
 const PAGE_W: Mm = Mm(210.0);
 const PAGE_H: Mm = Mm(297.0);
@@ -23,18 +31,42 @@ const PAGE_H: Mm = Mm(297.0);
 /// Margins in mm.
 const MARGIN: f32 = 25.0;
 
-/// Font sizes in pt.
+/// Body font size in pt, unless `base_font_size` is set.
 const FONT_SIZE_BODY: f32 = 11.0;
-const FONT_SIZE_H1: f32 = 22.0;
-const FONT_SIZE_H2: f32 = 18.0;
-const FONT_SIZE_H3: f32 = 15.0;
 
-/// Line height multiplier.
+/// Heading sizes (h1, h2, h3 and below) as multiples of the body size.
+const HEADING_SCALE: [f32; 3] = [2.0, 18.0 / 11.0, 15.0 / 11.0];
+
+/// Line height multiplier, unless `line_height` is set.
 const LINE_HEIGHT: f32 = 1.4;
 
+/// Footer font size in pt.
+const FONT_SIZE_FOOTER: f32 = 9.0;
+
+/// Millimetres per inch.
+const MM_PER_INCH: f32 = 25.4;
+
 /// Approximate mm per pt.
 const MM_PER_PT: f32 = 0.353;
 
+/// Zero-width space: an invisible break opportunity, also produced from `<wbr>`.
+const ZWSP: char = '\u{200B}';
+
+/// A heading, paragraph or table of a spine document.
+#[derive(Debug, Clone, PartialEq)]
+enum Block {
+    Heading(u32, String),
+    Paragraph(String),
+    Table(Table),
+}
+
+/// The blocks of one spine document, in document order.
+type ExtractedDocument = Vec<Block>;
+
+static HEADING_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?i)<h([1-6])[^>]*>(.*?)</h[1-6]>").unwrap());
+static PARA_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"(?is)<p[^>]*>(.*?)</p>").unwrap());
+
 pub struct PdfOutputPlugin;
 
 impl OutputPlugin for PdfOutputPlugin {
@@ -50,10 +82,10 @@ impl OutputPlugin for PdfOutputPlugin {
         &self,
         book: &BookDocument,
         output_path: &Path,
-        _options: &ConversionOptions,
+        options: &ConversionOptions,
     ) -> Result<()> {
         log::info!("Writing PDF: {}", output_path.display());
-        write_pdf(book, output_path)
+        write_pdf(book, output_path, options)
     }
 }
 
@@ -63,18 +95,39 @@ struct PageBuilder {
     y_pos: f32, // mm from bottom
     chars_per_line: usize,
     in_text: bool,
+    /// Body font size in pt
+    body_size: f32,
+    /// Line height as a multiple of the font size
+    line_height: f32,
+    /// Resolution assumed for images that do not record one
+    image_dpi: f32,
+    /// Footer template, with `{page}` still to be filled in
+    footer: Option<String>,
 }
 
 impl PageBuilder {
-    fn new() -> Self {
+    fn new(options: &ConversionOptions) -> Self {
+        let body_size = if options.base_font_size > 0.0 {
+            options.base_font_size as f32
+        } else {
+            FONT_SIZE_BODY
+        };
+        let line_height = options
+            .line_height
+            .filter(|lh| *lh > 0.0)
+            .map_or(LINE_HEIGHT, |lh| lh as f32);
         let usable_w = 210.0 - 2.0 * MARGIN;
-        let chars_per_line = (usable_w / (FONT_SIZE_BODY * 0.5 * MM_PER_PT)) as usize;
+        let chars_per_line = (usable_w / (body_size * 0.5 * MM_PER_PT)) as usize;
         let mut pb = PageBuilder {
             pages: Vec::new(),
             current_ops: Vec::new(),
             y_pos: 297.0 - MARGIN,
             chars_per_line,
             in_text: false,
+            body_size,
+            line_height,
+            image_dpi: f32::from(options.pdf_image_dpi.max(1)),
+            footer: None,
         };
         pb.start_text();
         pb
@@ -96,22 +149,61 @@ impl PageBuilder {
 
     fn new_page(&mut self) {
         self.end_text();
+        self.write_footer();
         let ops = std::mem::take(&mut self.current_ops);
         self.pages.push(PdfPage::new(PAGE_W, PAGE_H, ops));
         self.y_pos = 297.0 - MARGIN;
         self.start_text();
     }
 
+    /// Draw the footer, centred in the bottom margin of the current page.
+    fn write_footer(&mut self) {
+        let Some(template) = &self.footer else {
+            return;
+        };
+        let text = template.replace("{page}", &(self.pages.len() + 1).to_string());
+        let width = text.chars().count() as f32 * FONT_SIZE_FOOTER * 0.5 * MM_PER_PT;
+        let font = BuiltinFont::Helvetica;
+        self.current_ops.extend([
+            Op::StartTextSection,
+            Op::SetFontSizeBuiltinFont {
+                size: Pt(FONT_SIZE_FOOTER),
+                font,
+            },
+            Op::SetTextCursor {
+                pos: Point {
+                    x: Mm(((PAGE_W.0 - width) / 2.0).max(MARGIN)).into(),
+                    y: Mm(MARGIN / 2.0).into(),
+                },
+            },
+            Op::WriteTextBuiltinFont {
+                items: vec![TextItem::Text(text)],
+                font,
+            },
+            Op::EndTextSection,
+        ]);
+    }
+
     fn ensure_space(&mut self, needed_mm: f32) {
         if self.y_pos - needed_mm < MARGIN {
             self.new_page();
         }
     }
 
+    /// Font size in pt of a heading at `level`, scaled from the body size.
+    fn heading_size(&self, level: u32) -> f32 {
+        let index = (level.max(1) as usize - 1).min(HEADING_SCALE.len() - 1);
+        self.body_size * HEADING_SCALE[index]
+    }
+
     fn write_line(&mut self, text: &str, font_size: f32, font: BuiltinFont) {
-        let line_h = font_size * LINE_HEIGHT * MM_PER_PT;
+        let line_h = font_size * self.line_height * MM_PER_PT;
         self.ensure_space(line_h);
 
+        self.current_ops.push(Op::SetFontSizeBuiltinFont {
+            size: Pt(font_size),
+            font,
+        });
         self.current_ops.push(Op::SetTextCursor {
             pos: Point {
                 x: Mm(MARGIN).into(),
@@ -127,20 +219,7 @@ impl PageBuilder {
     }
 
     fn write_wrapped(&mut self, text: &str, font_size: f32, font: BuiltinFont) {
-        let words: Vec<&str> = text.split_whitespace().collect();
-        let mut line = String::new();
-
-        for word in words {
-            if line.len() + word.len() + 1 > self.chars_per_line && !line.is_empty() {
-                self.write_line(&line, font_size, font);
-                line.clear();
-            }
-            if !line.is_empty() {
-                line.push(' ');
-            }
-            line.push_str(word);
-        }
-        if !line.is_empty() {
+        for line in wrap_text(text, self.chars_per_line) {
             self.write_line(&line, font_size, font);
         }
     }
@@ -150,14 +229,13 @@ impl PageBuilder {
         let image = RawImage::decode_from_bytes(data, &mut warnings)
             .map_err(|e| format!("Image decode: {}", e))?;
 
-        let (w, h) = (image.width, image.height);
+        let dpi = self.dpi_of(data);
+        let (img_w_mm, img_h_mm) = size_mm(&image, dpi);
         let image_id = doc.add_image(&image);
 
         // Scale to fit usable area
         let max_w = 210.0 - 2.0 * MARGIN;
         let max_h = 297.0 - 2.0 * MARGIN;
-        let img_w_mm = w as f32 * 0.264583; // px to mm at 96 dpi
-        let img_h_mm = h as f32 * 0.264583;
         let scale = (max_w / img_w_mm).min(max_h / img_h_mm).min(1.0);
         let final_h = img_h_mm * scale;
 
@@ -171,7 +249,7 @@ impl PageBuilder {
                 translate_y: Some(Mm(self.y_pos - final_h).into()),
                 scale_x: Some(scale),
                 scale_y: Some(scale),
-                dpi: Some(96.0),
+                dpi: Some(dpi),
                 ..Default::default()
             },
         });
@@ -181,17 +259,78 @@ impl PageBuilder {
         Ok(())
     }
 
+    /// Draw `data` scaled to fit the whole page, centred on `background`,
+    /// and start a new page after it. Must be called before anything else
+    /// is written.
+    fn add_cover_page(
+        &mut self,
+        doc: &mut PdfDocument,
+        data: &[u8],
+        background: Option<Rgb>,
+    ) -> std::result::Result<(), String> {
+        let mut warnings = Vec::new();
+        let image = RawImage::decode_from_bytes(data, &mut warnings)
+            .map_err(|e| format!("Image decode: {}", e))?;
+
+        let dpi = self.dpi_of(data);
+        let (img_w_mm, img_h_mm) = size_mm(&image, dpi);
+        let scale = (PAGE_W.0 / img_w_mm).min(PAGE_H.0 / img_h_mm);
+        let image_id = doc.add_image(&image);
+
+        self.current_ops.clear();
+        self.in_text = false;
+        if let Some(col) = background {
+            self.current_ops.push(Op::SetFillColor {
+                col: Color::Rgb(col),
+            });
+            self.current_ops.push(Op::DrawPolygon {
+                polygon: Rect::from_wh(PAGE_W.into(), PAGE_H.into()).to_polygon(),
+            });
+        }
+        self.current_ops.push(Op::UseXobject {
+            id: image_id,
+            transform: XObjectTransform {
+                translate_x: Some(Mm((PAGE_W.0 - img_w_mm * scale) / 2.0).into()),
+                translate_y: Some(Mm((PAGE_H.0 - img_h_mm * scale) / 2.0).into()),
+                scale_x: Some(scale),
+                scale_y: Some(scale),
+                dpi: Some(dpi),
+                ..Default::default()
+            },
+        });
+        // The cover is counted but not numbered
+        let footer = self.footer.take();
+        self.new_page();
+        self.footer = footer;
+        Ok(())
+    }
+
+    /// The resolution recorded in an image file, or the configured default.
+    fn dpi_of(&self, data: &[u8]) -> f32 {
+        dpi::image_dpi(data).unwrap_or(self.image_dpi)
+    }
+
     fn finish(mut self) -> Vec<PdfPage> {
         self.end_text();
-        let ops = std::mem::take(&mut self.current_ops);
-        if !ops.is_empty() {
+        if !self.current_ops.is_empty() {
+            self.write_footer();
+            let ops = std::mem::take(&mut self.current_ops);
             self.pages.push(PdfPage::new(PAGE_W, PAGE_H, ops));
         }
         self.pages
     }
 }
 
-fn write_pdf(book: &BookDocument, output_path: &Path) -> Result<()> {
+/// Physical size in mm of `image` at `dpi`.
+fn size_mm(image: &RawImage, dpi: f32) -> (f32, f32) {
+    let mm_per_px = MM_PER_INCH / dpi;
+    (
+        image.width as f32 * mm_per_px,
+        image.height as f32 * mm_per_px,
+    )
+}
+
+fn write_pdf(book: &BookDocument, output_path: &Path, options: &ConversionOptions) -> Result<()> {
     let fallback_title = output_path
         .file_stem()
         .and_then(|s| s.to_str())
@@ -200,91 +339,82 @@ fn write_pdf(book: &BookDocument, output_path: &Path) -> Result<()> {
     let title = book.metadata.title().unwrap_or(&fallback_title);
 
     let mut doc = PdfDocument::new(title);
-    let mut builder = PageBuilder::new();
+    let mut builder = PageBuilder::new(options);
+    builder.footer = options
+        .pdf_footer
+        .as_deref()
+        .filter(|footer| !footer.trim().is_empty())
+        .map(|footer| footer.replace("{title}", title));
+
+    // Cover page
+    let cover = match options.pdf_cover_page {
+        PdfCoverPage::Off => None,
+        PdfCoverPage::Replace | PdfCoverPage::BeforeTitle => cover_image(book),
+    };
+    let background = options.pdf_cover_background.as_deref().and_then(|c| {
+        let rgb = parse_hex_color(c);
+        if rgb.is_none() {
+            log::warn!("Ignoring PDF cover background '{}': expected #RRGGBB", c);
+        }
+        rgb
+    });
+    let mut cover_id = None;
+    if let Some(item) = cover {
+        if let Some(data) = item.data.as_binary() {
+            match builder.add_cover_page(&mut doc, data, background) {
+                Ok(()) => cover_id = Some(item.id.as_str()),
+                Err(e) => log::warn!("Failed to render cover {}: {}", item.href, e),
+            }
+        }
+    }
 
     // Title page
-    builder.write_line(title, FONT_SIZE_H1, BuiltinFont::HelveticaBold);
-    builder.y_pos -= 5.0;
+    if cover_id.is_none() || options.pdf_cover_page == PdfCoverPage::BeforeTitle {
+        let title_size = builder.heading_size(1);
+        builder.write_line(title, title_size, BuiltinFont::HelveticaBold);
+        builder.y_pos -= 5.0;
 
-    for author in book.metadata.authors() {
-        builder.write_line(author, FONT_SIZE_BODY, BuiltinFont::HelveticaOblique);
+        for author in book.metadata.authors() {
+            let size = builder.body_size;
+            builder.write_line(author, size, BuiltinFont::HelveticaOblique);
+        }
+        builder.y_pos -= 10.0;
     }
-    builder.y_pos -= 10.0;
 
     // Content — extract text from spine items in parallel, then render sequentially
     let tag_re = Regex::new(r"<[^>]+>").unwrap();
-    let heading_re = Regex::new(r"(?i)<h([1-6])[^>]*>(.*?)</h[1-6]>").unwrap();
-    let para_re = Regex::new(r"(?is)<p[^>]*>(.*?)</p>").unwrap();
 
-    // Collect spine XHTMLs
-    let spine_xhtmls: Vec<&str> = book
+    // Collect spine XHTMLs with their hrefs, non-linear documents last
+    let spine_xhtmls: Vec<(&str, &str)> = book
         .spine
-        .iter()
+        .reading_order()
         .filter_map(|si| book.manifest.by_id(&si.idref))
         .filter_map(|item| match &item.data {
-            ManifestData::Xhtml(ref x) => Some(x.as_str()),
+            ManifestData::Xhtml(ref x) => Some((item.href.as_str(), x.as_str())),
             _ => None,
         })
         .collect();
 
-    // Extract headings and paragraphs in parallel
-    #[allow(clippy::type_complexity)]
-    let extracted: Vec<(Vec<(u32, String)>, Vec<String>)> = spine_xhtmls
+    // Extract headings, paragraphs and tables in parallel
+    let extracted: Vec<ExtractedDocument> = spine_xhtmls
         .par_iter()
-        .map(|xhtml| {
-            let body = extract_body(xhtml);
-            let headings: Vec<(u32, String)> = heading_re
-                .captures_iter(&body)
-                .filter_map(|cap| {
-                    let level: u32 = cap[1].parse().unwrap_or(3);
-                    let text = tag_re.replace_all(&cap[2], "").to_string();
-                    let text = decode_entities(&text);
-                    if text.trim().is_empty() {
-                        None
-                    } else {
-                        Some((level, text.trim().to_string()))
-                    }
-                })
-                .collect();
-            let paragraphs: Vec<String> = para_re
-                .captures_iter(&body)
-                .filter_map(|cap| {
-                    let text = tag_re.replace_all(&cap[1], " ").to_string();
-                    let text = decode_entities(&text);
-                    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
-                    if text.is_empty() {
-                        None
-                    } else {
-                        Some(text)
-                    }
-                })
-                .collect();
-            (headings, paragraphs)
-        })
+        .map(|(_, xhtml)| extract_document(xhtml, &tag_re))
         .collect();
 
     // Render sequentially
-    for (headings, paragraphs) in &extracted {
-        for (level, text) in headings {
-            let font_size = match level {
-                1 => FONT_SIZE_H1,
-                2 => FONT_SIZE_H2,
-                _ => FONT_SIZE_H3,
-            };
-            builder.y_pos -= font_size * MM_PER_PT * 0.5;
-            builder.write_line(text, font_size, BuiltinFont::HelveticaBold);
-            builder.y_pos -= 2.0;
-        }
+    let start_pages = render_documents(&mut builder, &extracted);
 
-        for text in paragraphs {
-            builder.write_wrapped(text, FONT_SIZE_BODY, BuiltinFont::Helvetica);
-            builder.y_pos -= 2.0;
+    // Bookmark guide references under their own titles
+    for reference in book.guide.iter() {
+        let href = reference.href.split('#').next().unwrap_or_default();
+        if let Some(i) = spine_xhtmls.iter().position(|(h, _)| *h == href) {
+            doc.add_bookmark(&reference.title, start_pages[i]);
         }
     }
 
     // Embed images
     for item in book.manifest.iter() {
-        if item.is_image() {
+        if item.is_image() && cover_id != Some(item.id.as_str()) {
             if let ManifestData::Binary(ref data) = item.data {
                 if let Err(e) = builder.add_image(&mut doc, data) {
                     log::warn!("Failed to embed image {}: {}", item.href, e);
@@ -299,10 +429,165 @@ fn write_pdf(book: &BookDocument, output_path: &Path) -> Result<()> {
         .with_pages(pages)
         .save(&PdfSaveOptions::default(), &mut warnings);
 
-    std::fs::write(output_path, pdf_bytes)
-        .map_err(|e| ConvertError::Other(format!("Failed to write PDF: {}", e)))?;
+    write_atomically(output_path, |tmp| {
+        std::fs::write(tmp, pdf_bytes).map_err(|e| ConvertError::io(tmp, e))
+    })
+}
+
+/// The book's cover image: the guide `cover` target, or the first image on
+/// the cover page it points at.
+fn cover_image(book: &BookDocument) -> Option<&ManifestItem> {
+    let href = book.guide.get("cover")?.href.split('#').next()?;
+    let item = book.manifest.by_href(href)?;
+    if item.is_image() {
+        return Some(item);
+    }
+    let img_re =
+        Regex::new(r#"(?is)<(?:img|image)\b[^>]*?\s(?:src|xlink:href)\s*=\s*["']([^"']+)["']"#)
+            .unwrap();
+    let src = &img_re.captures(item.data.as_xhtml()?)?[1];
+    book.manifest
+        .by_href(&convert_utils::href::resolve(&item.href, src))
+        .filter(|img| img.is_image())
+}
+
+/// Parse a `#RRGGBB` colour.
+fn parse_hex_color(s: &str) -> Option<Rgb> {
+    let hex = s.strip_prefix('#')?;
+    if hex.len() != 6 {
+        return None;
+    }
+    let channel = |i: usize| {
+        u8::from_str_radix(hex.get(i..i + 2)?, 16)
+            .ok()
+            .map(|v| v as f32 / 255.0)
+    };
+    Some(Rgb {
+        r: channel(0)?,
+        g: channel(2)?,
+        b: channel(4)?,
+        icc_profile: None,
+    })
+}
+
+/// The headings, paragraphs and tables of a spine document, in document order.
+fn extract_document(xhtml: &str, tag_re: &Regex) -> ExtractedDocument {
+    let (body, tables) = table::extract_tables(&extract_body(xhtml), tag_re);
+
+    // Tables go first so that, after the stable sort, each one precedes the
+    // block that starts where it was cut out
+    let mut blocks: Vec<(usize, Block)> = tables
+        .into_iter()
+        .map(|(pos, table)| (pos, Block::Table(table)))
+        .collect();
+    blocks.extend(HEADING_RE.captures_iter(&body).filter_map(|cap| {
+        let level: u32 = cap[1].parse().unwrap_or(3);
+        let text = tag_re.replace_all(&cap[2], "").to_string();
+        let text = decode_entities(&text).replace(ZWSP, "");
+        let text = text.trim();
+        (!text.is_empty()).then(|| {
+            (
+                cap.get(0).unwrap().start(),
+                Block::Heading(level, text.to_string()),
+            )
+        })
+    }));
+    blocks.extend(PARA_RE.captures_iter(&body).filter_map(|cap| {
+        let text = paragraph_text(&cap[1], tag_re);
+        (!text.is_empty()).then(|| (cap.get(0).unwrap().start(), Block::Paragraph(text)))
+    }));
+    blocks.sort_by_key(|(pos, _)| *pos);
+    blocks.into_iter().map(|(_, block)| block).collect()
+}
+
+/// Render extracted spine documents, starting each one after the first on a new page.
+/// Returns the 1-based page number each document starts on.
+fn render_documents(builder: &mut PageBuilder, extracted: &[ExtractedDocument]) -> Vec<usize> {
+    let mut start_pages = Vec::with_capacity(extracted.len());
+    for (i, blocks) in extracted.iter().enumerate() {
+        if i > 0 {
+            builder.new_page();
+        }
+        start_pages.push(builder.pages.len() + 1);
+
+        for block in blocks {
+            match block {
+                Block::Heading(level, text) => {
+                    let font_size = builder.heading_size(*level);
+                    builder.y_pos -= font_size * MM_PER_PT * 0.5;
+                    builder.write_line(text, font_size, BuiltinFont::HelveticaBold);
+                }
+                Block::Paragraph(text) => {
+                    let size = builder.body_size;
+                    builder.write_wrapped(text, size, BuiltinFont::Helvetica);
+                }
+                Block::Table(table) => builder.write_table(table),
+            }
+            builder.y_pos -= 2.0;
+        }
+    }
+    start_pages
+}
 
-    Ok(())
+/// Plain text of a paragraph's inner HTML with whitespace collapsed.
+/// `<wbr>` becomes a zero-width space so the wrapper may break there.
+fn paragraph_text(html: &str, tag_re: &Regex) -> String {
+    let text = tag_re.replace_all(html, |cap: &regex::Captures| {
+        let name = cap[0]
+            .trim_start_matches('<')
+            .trim_end_matches(['/', '>', ' ']);
+        if name.eq_ignore_ascii_case("wbr") {
+            ZWSP.to_string()
+        } else {
+            " ".to_string()
+        }
+    });
+    let text = decode_entities(&text);
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Greedily break `text` into lines of at most `width` characters.
+///
+/// Lines break at spaces. A word longer than a whole line is broken inside
+/// at its zero-width spaces; shorter words move to the next line intact.
+/// Zero-width spaces are dropped from the output.
+fn wrap_text(text: &str, width: usize) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut line = String::new();
+
+    for word in text.split_whitespace() {
+        let whole: String = word.split(ZWSP).collect();
+        let sep = usize::from(!line.is_empty());
+        if line.len() + sep + whole.len() <= width {
+            if sep == 1 {
+                line.push(' ');
+            }
+            line.push_str(&whole);
+            continue;
+        }
+        if whole.len() <= width || !word.contains(ZWSP) {
+            if !line.is_empty() {
+                lines.push(std::mem::take(&mut line));
+            }
+            line.push_str(&whole);
+            continue;
+        }
+
+        for (i, segment) in word.split(ZWSP).filter(|s| !s.is_empty()).enumerate() {
+            // Only the first segment of a word is separated by a space
+            let sep = if i == 0 && !line.is_empty() { 1 } else { 0 };
+            if !line.is_empty() && line.len() + sep + segment.len() > width {
+                lines.push(std::mem::take(&mut line));
+            } else if sep == 1 {
+                line.push(' ');
+            }
+            line.push_str(segment);
+        }
+    }
+    if !line.is_empty() {
+        lines.push(line);
+    }
+    lines
 }
 
 fn extract_body(xhtml: &str) -> String {
@@ -323,12 +608,19 @@ fn decode_entities(s: &str) -> String {
         .replace("&quot;", "\"")
         .replace("&#39;", "'")
         .replace("&nbsp;", " ")
+        .replace("&#8203;", "\u{200B}")
+        .replace("&#x200B;", "\u{200B}")
+        .replace("&#x200b;", "\u{200B}")
+        // Soft hyphens from hyphenation would render as visible hyphens
+        .replace("&shy;", "")
+        .replace("&#173;", "")
+        .replace('\u{AD}', "")
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use convert_core::book::{ManifestItem, TocEntry};
+    use convert_core::book::{GuideRef, ManifestItem, TocEntry};
 
     #[test]
     fn test_extract_body() {
@@ -341,6 +633,38 @@ mod tests {
         assert_eq!(decode_entities("A &amp; B &lt; C"), "A & B < C");
     }
 
+    #[test]
+    fn test_wrap_text_breaks_at_spaces() {
+        let lines = wrap_text("one two three four", 9);
+        assert_eq!(lines, vec!["one two", "three", "four"]);
+    }
+
+    #[test]
+    fn test_wrap_text_keeps_short_words_whole() {
+        // "bb\u{200B}cccc" fits on a line of its own, so it is not broken
+        let lines = wrap_text("aa bb\u{200B}cccc", 7);
+        assert_eq!(lines, vec!["aa", "bbcccc"]);
+    }
+
+    #[test]
+    fn test_wrap_long_url_at_wbr() {
+        let tag_re = Regex::new(r"<[^>]+>").unwrap();
+        let url = "https://example.com/documentation/<wbr>reference/<wbr>configuration/<wbr>\
+                   advanced-options/<wbr>networking/<wbr>proxy-settings/<wbr/>index.html";
+        let text = paragraph_text(&format!("See {} for details", url), &tag_re);
+
+        let width = PageBuilder::new(&ConversionOptions::default()).chars_per_line;
+        let plain_url = url.replace("<wbr>", "").replace("<wbr/>", "");
+        assert!(plain_url.len() > width);
+
+        let lines = wrap_text(&text, width);
+        assert!(lines.len() > 1);
+        assert!(lines.iter().all(|l| l.len() <= width));
+        // Breaks fall only on <wbr> points: no space is lost between lines
+        assert!(lines[..lines.len() - 1].iter().all(|l| l.ends_with('/')));
+        assert_eq!(lines.concat(), format!("See {} for details", plain_url));
+    }
+
     #[test]
     fn test_pdf_output_basic() {
         let mut book = BookDocument::new();
@@ -367,4 +691,356 @@ mod tests {
         assert_eq!(&data[..5], b"%PDF-");
         std::fs::remove_file(&tmp).ok();
     }
+
+    fn cover_book() -> BookDocument {
+        let mut book = BookDocument::new();
+        book.metadata.set_title("Covered");
+        let mut png = Vec::new();
+        ::image::RgbImage::from_pixel(200, 300, ::image::Rgb([200, 30, 30]))
+            .write_to(
+                &mut std::io::Cursor::new(&mut png),
+                ::image::ImageFormat::Png,
+            )
+            .unwrap();
+        book.manifest.add(ManifestItem::new(
+            "cover",
+            "images/cover.png",
+            "image/png",
+            ManifestData::Binary(png),
+        ));
+        book.manifest.add(ManifestItem::new(
+            "ch1",
+            "ch1.xhtml",
+            "application/xhtml+xml",
+            ManifestData::Xhtml("<html><body><p>Once upon a time.</p></body></html>".into()),
+        ));
+        book.spine.push("ch1", true);
+        book.guide
+            .add(GuideRef::new("cover", "Cover", "images/cover.png"));
+        book
+    }
+
+    /// Page count, the operators of page `n`'s content stream, and the
+    /// names of the image XObjects that page can draw.
+    fn page(pdf: &[u8], n: u32) -> (usize, Vec<lopdf::content::Operation>, Vec<Vec<u8>>) {
+        let doc = lopdf::Document::load_mem(pdf).unwrap();
+        let pages = doc.get_pages();
+        let page_id = pages[&n];
+        let ops = doc.get_and_decode_page_content(page_id).unwrap().operations;
+
+        let mut images = Vec::new();
+        let (inline, referenced) = doc.get_page_resources(page_id).unwrap();
+        let resources = inline.into_iter().chain(
+            referenced
+                .iter()
+                .filter_map(|id| doc.get_dictionary(*id).ok()),
+        );
+        for resources in resources {
+            let Ok(xobjects) = resources.get(b"XObject") else {
+                continue;
+            };
+            let xobjects = doc.dereference(xobjects).unwrap().1.as_dict().unwrap();
+            for (name, obj) in xobjects.iter() {
+                let stream = doc.dereference(obj).unwrap().1.as_stream().unwrap();
+                if stream.dict.get(b"Subtype").unwrap().as_name().unwrap() == b"Image" {
+                    images.push(name.clone());
+                }
+            }
+        }
+        (pages.len(), ops, images)
+    }
+
+    /// Whether any text-showing operator on the page draws `text`.
+    fn shows_text(ops: &[lopdf::content::Operation], text: &str) -> bool {
+        ops.iter()
+            .filter(|op| op.operator == "Tj" || op.operator == "TJ")
+            .any(|op| format!("{:?}", op.operands).contains(text))
+    }
+
+    fn convert_with(book: &BookDocument, opts: &ConversionOptions, name: &str) -> Vec<u8> {
+        let tmp = std::env::temp_dir().join(name);
+        PdfOutputPlugin.convert(book, &tmp, opts).unwrap();
+        let data = std::fs::read(&tmp).unwrap();
+        std::fs::remove_file(&tmp).ok();
+        data
+    }
+
+    #[test]
+    fn test_pdf_cover_page() {
+        let opts = ConversionOptions {
+            pdf_cover_page: PdfCoverPage::Replace,
+            ..Default::default()
+        };
+        let data = convert_with(&cover_book(), &opts, "test_output_pdf_cover.pdf");
+        let (page_count, ops, images) = page(&data, 1);
+        // Cover page replaces the title page; the chapter follows
+        assert_eq!(page_count, 2);
+        assert!(!shows_text(&page(&data, 2).1, "Covered"));
+
+        let do_op = ops.iter().find(|op| op.operator == "Do").unwrap();
+        let name = do_op.operands[0].as_name().unwrap();
+        assert!(images.iter().any(|img| img == name));
+
+        // The image is scaled to the full page height (A4: 841.89pt) and
+        // centred horizontally
+        let cm = ops.iter().find(|op| op.operator == "cm").unwrap();
+        let m: Vec<f32> = cm.operands.iter().map(|o| o.as_float().unwrap()).collect();
+        assert!((m[3] - 841.89).abs() < 0.5, "{:?}", m);
+        assert!((m[0] + 2.0 * m[4] - 595.28).abs() < 0.5, "{:?}", m);
+        assert!(!shows_text(&ops, "Covered"));
+    }
+
+    #[test]
+    fn test_pdf_cover_page_modes() {
+        let book = cover_book();
+
+        // The default keeps the title page after the cover
+        let opts = ConversionOptions {
+            pdf_cover_background: Some("#102030".into()),
+            ..Default::default()
+        };
+        let data = convert_with(&book, &opts, "test_pdf_before.pdf");
+        let (page_count, ops, _) = page(&data, 1);
+        assert_eq!(page_count, 2);
+        assert!(ops.iter().any(|op| op.operator == "rg"));
+        assert!(ops.iter().any(|op| op.operator == "Do"));
+        assert!(shows_text(&page(&data, 2).1, "Covered"));
+
+        let opts = ConversionOptions {
+            pdf_cover_page: PdfCoverPage::Off,
+            ..Default::default()
+        };
+        let (_, ops, _) = page(&convert_with(&book, &opts, "test_pdf_nocover.pdf"), 1);
+        assert!(shows_text(&ops, "Covered"));
+    }
+
+    #[test]
+    fn test_parse_hex_color() {
+        let rgb = parse_hex_color("#ff0080").unwrap();
+        assert_eq!((rgb.r, rgb.g), (1.0, 0.0));
+        assert!((rgb.b - 128.0 / 255.0).abs() < 1e-6);
+        assert!(parse_hex_color("ff0080").is_none());
+        assert!(parse_hex_color("#fff").is_none());
+        assert!(parse_hex_color("#gg0000").is_none());
+    }
+
+    #[test]
+    fn test_base_font_size_and_line_height() {
+        let default = PageBuilder::new(&ConversionOptions::default());
+        let large = PageBuilder::new(&ConversionOptions {
+            base_font_size: 16.0,
+            ..Default::default()
+        });
+        assert!(large.chars_per_line < default.chars_per_line);
+        assert_eq!(large.heading_size(1), 32.0);
+        assert!(large.heading_size(2) > large.heading_size(3));
+        assert_eq!(large.heading_size(6), large.heading_size(3));
+
+        // One body line advances further down the page at 16pt
+        let advance = |mut builder: PageBuilder| {
+            let start = builder.y_pos;
+            let size = builder.body_size;
+            builder.write_line("Line", size, BuiltinFont::Helvetica);
+            start - builder.y_pos
+        };
+        let default_line = advance(default);
+        let large_line = advance(large);
+        assert!(large_line > default_line);
+
+        let loose = PageBuilder::new(&ConversionOptions {
+            line_height: Some(2.0),
+            ..Default::default()
+        });
+        assert!(advance(loose) > default_line);
+    }
+
+    #[test]
+    fn test_image_size_follows_dpi() {
+        use ::image::codecs::jpeg::{JpegEncoder, PixelDensity};
+        use ::image::{ExtendedColorType, ImageEncoder};
+
+        let jpeg = |dpi: Option<u16>| {
+            let pixels = vec![90u8; 150 * 150 * 3];
+            let mut data = Vec::new();
+            let mut encoder = JpegEncoder::new(&mut data);
+            if let Some(dpi) = dpi {
+                encoder.set_pixel_density(PixelDensity::dpi(dpi));
+            }
+            encoder
+                .write_image(&pixels, 150, 150, ExtendedColorType::Rgb8)
+                .unwrap();
+            data
+        };
+        // Height taken up on the page, less the gap after the image
+        let height = |data: &[u8], options: &ConversionOptions| {
+            let mut builder = PageBuilder::new(options);
+            let start = builder.y_pos;
+            builder.add_image(&mut PdfDocument::new("t"), data).unwrap();
+            start - builder.y_pos - 5.0
+        };
+        let defaults = ConversionOptions::default();
+
+        let print = height(&jpeg(Some(300)), &defaults);
+        let screen = height(&jpeg(Some(72)), &defaults);
+        assert!((print - 12.7).abs() < 0.01, "{}", print);
+        assert!((screen - 52.92).abs() < 0.01, "{}", screen);
+        assert!(print < screen);
+
+        // Untagged images use the configured resolution
+        let untagged = jpeg(None);
+        assert!((height(&untagged, &defaults) - 39.69).abs() < 0.01);
+        let options = ConversionOptions {
+            pdf_image_dpi: 150,
+            ..Default::default()
+        };
+        assert!((height(&untagged, &options) - 25.4).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_page_break_between_documents() {
+        let extracted = vec![
+            vec![
+                Block::Heading(1, "Chapter 1".to_string()),
+                Block::Paragraph("First.".to_string()),
+            ],
+            vec![
+                Block::Heading(1, "Chapter 2".to_string()),
+                Block::Paragraph("Second.".to_string()),
+            ],
+        ];
+
+        let mut builder = PageBuilder::new(&ConversionOptions::default());
+        render_documents(&mut builder, &extracted[..1]);
+        assert_eq!(builder.finish().len(), 1);
+
+        let mut builder = PageBuilder::new(&ConversionOptions::default());
+        assert_eq!(render_documents(&mut builder, &extracted), vec![1, 2]);
+        assert_eq!(builder.finish().len(), 2);
+    }
+
+    #[test]
+    fn test_blocks_keep_document_order() {
+        let tag_re = Regex::new(r"<[^>]+>").unwrap();
+        let xhtml = concat!(
+            "<html><body><p>Before</p><table><tr><td>Cell</td></tr></table>",
+            "<h2>Section</h2><table><tr><td>Next</td></tr></table><p>After</p></body></html>",
+        );
+        let table = |text: &str| {
+            Block::Table(vec![vec![table::Cell {
+                text: text.to_string(),
+                header: false,
+            }]])
+        };
+        assert_eq!(
+            extract_document(xhtml, &tag_re),
+            vec![
+                Block::Paragraph("Before".to_string()),
+                table("Cell"),
+                Block::Heading(2, "Section".to_string()),
+                table("Next"),
+                Block::Paragraph("After".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_guide_titles_become_bookmarks() {
+        let mut book = BookDocument::new();
+        book.metadata.set_title("Guided");
+        for (id, text) in [("toc", "Contents"), ("ch1", "It begins.")] {
+            book.manifest.add(ManifestItem::new(
+                id,
+                format!("{}.xhtml", id),
+                "application/xhtml+xml",
+                ManifestData::Xhtml(format!("<html><body><p>{}</p></body></html>", text)),
+            ));
+            book.spine.push(id, true);
+        }
+        book.guide
+            .add(GuideRef::new("text", "Beginning", "ch1.xhtml"));
+
+        let data = convert_with(&book, &ConversionOptions::default(), "test_pdf_guide.pdf");
+        let toc = lopdf::Document::load_mem(&data).unwrap().get_toc().unwrap();
+        let entry = toc.toc.iter().find(|t| t.title == "Beginning").unwrap();
+        assert_eq!(entry.page, 2);
+        assert!(shows_text(&page(&data, 2).1, "It begins."));
+    }
+
+    #[test]
+    fn test_table_cells_rendered() {
+        let mut book = BookDocument::new();
+        book.metadata.set_title("Tabled");
+        book.manifest.add(ManifestItem::new(
+            "ch1",
+            "ch1.xhtml",
+            "application/xhtml+xml",
+            ManifestData::Xhtml(
+                concat!(
+                    "<html><body><p>Results:</p><table>",
+                    "<tr><th>Planet</th><th>Moons</th></tr>",
+                    "<tr><td><p>Mars</p></td><td>2</td></tr>",
+                    "</table></body></html>",
+                )
+                .into(),
+            ),
+        ));
+        book.spine.push("ch1", true);
+
+        let data = convert_with(&book, &ConversionOptions::default(), "test_pdf_table.pdf");
+        let (_, ops, _) = page(&data, 1);
+        for text in ["Planet", "Moons", "Mars", "2"] {
+            assert!(shows_text(&ops, text), "{} missing", text);
+        }
+        // The cell paragraph is drawn once, inside the table
+        let shown = ops
+            .iter()
+            .filter(|op| op.operator == "Tj" || op.operator == "TJ")
+            .filter(|op| format!("{:?}", op.operands).contains("Mars"))
+            .count();
+        assert_eq!(shown, 1);
+        // One stroked border per cell
+        let strokes = ops
+            .iter()
+            .filter(|op| op.operator == "s" || op.operator == "S")
+            .count();
+        assert_eq!(strokes, 4);
+    }
+
+    #[test]
+    fn test_page_number_footer() {
+        let mut book = BookDocument::new();
+        book.metadata.set_title("Numbered");
+        for i in 1..=2 {
+            let id = format!("ch{}", i);
+            book.manifest.add(ManifestItem::new(
+                &id,
+                format!("{}.xhtml", id),
+                "application/xhtml+xml",
+                ManifestData::Xhtml(format!(
+                    "<html><body><h1>Chapter {}</h1><p>Text.</p></body></html>",
+                    i
+                )),
+            ));
+            book.spine.push(&id, true);
+        }
+
+        let opts = ConversionOptions {
+            pdf_footer: Some("{title} - {page}".into()),
+            ..Default::default()
+        };
+        let data = convert_with(&book, &opts, "test_pdf_footer.pdf");
+        for n in 1..=2 {
+            let (_, ops, _) = page(&data, n);
+            assert!(shows_text(&ops, &format!("Numbered - {}", n)), "page {}", n);
+        }
+
+        // No footer unless asked for
+        let data = convert_with(
+            &book,
+            &ConversionOptions::default(),
+            "test_pdf_no_footer.pdf",
+        );
+        let (_, ops, _) = page(&data, 2);
+        assert!(!shows_text(&ops, "Numbered"));
+    }
 }
@@ -0,0 +1,261 @@
+//! Tables for PDF output.
+//!
+//! Tables are laid out on a grid of equal-width columns spanning the text
+//! width, with a border around each cell. Cell text wraps within its column,
+//! each row is as tall as its tallest cell, and a row that does not fit on
+//! the page moves to the next one. `colspan`, `rowspan` and nested tables are
+//! not laid out; their text lands in the cell that holds them.
+
+use std::sync::LazyLock;
+
+use printpdf::*;
+use regex::Regex;
+
+use crate::{paragraph_text, wrap_text, PageBuilder, MARGIN, MM_PER_PT, PAGE_W};
+
+/// Space between a cell's border and its text, in mm.
+const CELL_PADDING: f32 = 1.5;
+
+/// Cell border width in pt.
+const BORDER_WIDTH: f32 = 0.5;
+
+/// A table cell's text, and whether it is a `<th>` header cell.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Cell {
+    pub text: String,
+    pub header: bool,
+}
+
+/// Rows of cells.
+pub(crate) type Table = Vec<Vec<Cell>>;
+
+/// Opening and closing table, row and cell tags.
+static TABLE_PART_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?i)<(/?)(table|tr|td|th)\b[^>]*>").unwrap());
+
+/// Split the outermost `<table>` elements out of `body`, returning the rest
+/// of the body and the parsed tables in document order, each with the
+/// offset in the rest where it stood.
+pub(crate) fn extract_tables(body: &str, tag_re: &Regex) -> (String, Vec<(usize, Table)>) {
+    let table_tag_re = Regex::new(r"(?i)<(/?)table\b[^>]*>").unwrap();
+    let mut rest = String::with_capacity(body.len());
+    let mut tables = Vec::new();
+    let mut depth = 0usize;
+    let (mut pos, mut start) = (0, 0);
+
+    for cap in table_tag_re.captures_iter(body) {
+        let tag = cap.get(0).unwrap();
+        if cap[1].is_empty() {
+            if depth == 0 {
+                rest.push_str(&body[pos..tag.start()]);
+                start = tag.start();
+            }
+            depth += 1;
+        } else if depth > 0 {
+            depth -= 1;
+            if depth == 0 {
+                tables.push((rest.len(), parse_table(&body[start..tag.end()], tag_re)));
+                pos = tag.end();
+            }
+        }
+    }
+    if depth > 0 {
+        // Unclosed table: it runs to the end of the body
+        tables.push((rest.len(), parse_table(&body[start..], tag_re)));
+    } else {
+        rest.push_str(&body[pos..]);
+    }
+    tables.retain(|(_, table)| !table.is_empty());
+    (rest, tables)
+}
+
+/// The rows of a `<table>` element, skipping rows without cells.
+///
+/// Only the table's own rows and cells are split out: tags of a nested table
+/// are left inside the cell that holds it. A cell or row missing its end tag
+/// ends where the next one starts.
+fn parse_table(html: &str, tag_re: &Regex) -> Table {
+    let mut rows = Vec::new();
+    let mut row: Vec<Cell> = Vec::new();
+    // Start of the open cell's content, and whether it is a header cell
+    let mut cell: Option<(usize, bool)> = None;
+    let mut depth = 0usize;
+
+    let end_cell = |row: &mut Vec<Cell>, cell: &mut Option<(usize, bool)>, end: usize| {
+        if let Some((start, header)) = cell.take() {
+            row.push(Cell {
+                text: paragraph_text(&html[start..end], tag_re),
+                header,
+            });
+        }
+    };
+
+    for cap in TABLE_PART_RE.captures_iter(html) {
+        let tag = cap.get(0).unwrap();
+        let closing = !cap[1].is_empty();
+        let name = cap[2].to_ascii_lowercase();
+        if name == "table" {
+            if closing {
+                depth = depth.saturating_sub(1);
+            } else {
+                depth += 1;
+            }
+            if depth == 0 {
+                break;
+            }
+            continue;
+        }
+        if depth != 1 {
+            continue;
+        }
+        end_cell(&mut row, &mut cell, tag.start());
+        if name == "tr" {
+            rows.push(std::mem::take(&mut row));
+        } else if !closing {
+            cell = Some((tag.end(), name == "th"));
+        }
+    }
+    end_cell(&mut row, &mut cell, html.len());
+    rows.push(row);
+    rows.retain(|cells| !cells.is_empty());
+    rows
+}
+
+impl PageBuilder {
+    /// Draw `table` at the body size, one row at a time.
+    pub(crate) fn write_table(&mut self, table: &Table) {
+        let cols = table.iter().map(Vec::len).max().unwrap_or(0);
+        if cols == 0 {
+            return;
+        }
+        let size = self.body_size;
+        let line_h = size * self.line_height * MM_PER_PT;
+        let col_w = (PAGE_W.0 - 2.0 * MARGIN) / cols as f32;
+        let chars_per_line = ((col_w - 2.0 * CELL_PADDING) / (size * 0.5 * MM_PER_PT)).max(1.0);
+
+        for row in table {
+            let lines: Vec<Vec<String>> = row
+                .iter()
+                .map(|cell| wrap_text(&cell.text, chars_per_line as usize))
+                .collect();
+            let line_count = lines.iter().map(Vec::len).max().unwrap_or(0).max(1);
+            let height = line_count as f32 * line_h + 2.0 * CELL_PADDING;
+            self.ensure_space(height);
+            let top = self.y_pos;
+
+            // Borders are drawn outside text sections
+            self.end_text();
+            self.current_ops.push(Op::SetOutlineThickness {
+                pt: Pt(BORDER_WIDTH),
+            });
+            for col in 0..cols {
+                let cell = Rect {
+                    x: Mm(MARGIN + col as f32 * col_w).into(),
+                    y: Mm(top).into(),
+                    width: Mm(col_w).into(),
+                    height: Mm(height).into(),
+                };
+                self.current_ops.push(Op::DrawLine {
+                    line: cell.to_line(),
+                });
+            }
+            self.start_text();
+
+            for (col, (cell, cell_lines)) in row.iter().zip(&lines).enumerate() {
+                let font = if cell.header {
+                    BuiltinFont::HelveticaBold
+                } else {
+                    BuiltinFont::Helvetica
+                };
+                self.current_ops.push(Op::SetFontSizeBuiltinFont {
+                    size: Pt(size),
+                    font,
+                });
+                for (i, line) in cell_lines.iter().enumerate() {
+                    let baseline = top - CELL_PADDING - size * MM_PER_PT - i as f32 * line_h;
+                    self.current_ops.push(Op::SetTextCursor {
+                        pos: Point {
+                            x: Mm(MARGIN + col as f32 * col_w + CELL_PADDING).into(),
+                            y: Mm(baseline).into(),
+                        },
+                    });
+                    self.current_ops.push(Op::WriteTextBuiltinFont {
+                        items: vec![TextItem::Text(line.clone())],
+                        font,
+                    });
+                }
+            }
+            self.y_pos = top - height;
+        }
+        // Text that follows is drawn above its baseline
+        self.y_pos -= size * MM_PER_PT;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use convert_core::options::ConversionOptions;
+
+    fn tag_re() -> Regex {
+        Regex::new(r"<[^>]+>").unwrap()
+    }
+
+    #[test]
+    fn test_extract_tables() {
+        let body = concat!(
+            "<p>Before</p><table><tr><th>Name</th><th>Age</th></tr>",
+            "<tr><td><p>Ann</p></td><td>31</td></tr><tr></tr></table>",
+            "<p>Between</p><table><tr><td>Outer<table><tr><td>inner</td></tr></table></td></tr></table>",
+            "<p>After</p>",
+        );
+        let (rest, tables) = extract_tables(body, &tag_re());
+        assert_eq!(rest, "<p>Before</p><p>Between</p><p>After</p>");
+        assert_eq!(
+            tables.iter().map(|(pos, _)| *pos).collect::<Vec<_>>(),
+            [13, 27]
+        );
+        let tables: Vec<Table> = tables.into_iter().map(|(_, table)| table).collect();
+
+        let cell = |text: &str, header| Cell {
+            text: text.to_string(),
+            header,
+        };
+        assert_eq!(
+            tables[0],
+            vec![
+                vec![cell("Name", true), cell("Age", true)],
+                vec![cell("Ann", false), cell("31", false)],
+            ]
+        );
+        assert_eq!(tables[1][0][0].text, "Outer inner");
+    }
+
+    #[test]
+    fn test_nested_table_rows_stay_in_their_cell() {
+        let html = concat!(
+            "<table><tr><td>Outer<table><tr><td>inner</td></tr><tr><td>more</td></tr></table>",
+            " tail</td><td>B</td></tr><tr><td>Next<td>Last</tr></table>",
+        );
+        let texts: Vec<Vec<String>> = parse_table(html, &tag_re())
+            .into_iter()
+            .map(|row| row.into_iter().map(|cell| cell.text).collect())
+            .collect();
+        assert_eq!(
+            texts,
+            vec![vec!["Outer inner more tail", "B"], vec!["Next", "Last"]]
+        );
+    }
+
+    #[test]
+    fn test_rows_move_to_next_page() {
+        let row = vec![Cell {
+            text: "cell".to_string(),
+            header: false,
+        }];
+        let table: Table = vec![row; 100];
+        let mut builder = PageBuilder::new(&ConversionOptions::default());
+        builder.write_table(&table);
+        assert!(builder.finish().len() > 1);
+    }
+}
@@ -2,6 +2,7 @@
 
 use std::path::Path;
 
+use convert_core::atomic::write_atomically;
 use convert_core::book::{BookDocument, EbookFormat, ManifestData};
 use convert_core::error::{ConvertError, Result};
 use convert_core::options::ConversionOptions;
@@ -54,7 +55,8 @@ impl OutputPlugin for TxtOutputPlugin {
         let tag_re = Regex::new(r"<[^>]+>").unwrap();
         let whitespace_re = Regex::new(r"\n{3,}").unwrap();
 
-        for spine_item in book.spine.iter() {
+        // Auxiliary (non-linear) documents such as notes go after the main text
+        for spine_item in book.spine.reading_order() {
             if let Some(manifest_item) = book.manifest.by_id(&spine_item.idref) {
                 if let ManifestData::Xhtml(ref xhtml) = manifest_item.data {
                     let body = extract_body_text(xhtml);
@@ -67,7 +69,8 @@ impl OutputPlugin for TxtOutputPlugin {
                         .replace("&gt;", ">")
                         .replace("&quot;", "\"")
                         .replace("&#39;", "'")
-                        .replace("&nbsp;", " ");
+                        .replace("&nbsp;", " ")
+                        .replace('\u{AD}', "");
                     let plain = whitespace_re.replace_all(&plain, "\n\n");
                     let plain = plain.trim();
                     if !plain.is_empty() {
@@ -78,10 +81,9 @@ impl OutputPlugin for TxtOutputPlugin {
             }
         }
 
-        std::fs::write(output_path, text.trim_end())
-            .map_err(|e| ConvertError::Other(format!("Failed to write TXT: {}", e)))?;
-
-        Ok(())
+        write_atomically(output_path, |tmp| {
+            std::fs::write(tmp, text.trim_end()).map_err(|e| ConvertError::io(tmp, e))
+        })
     }
 }
 
@@ -99,7 +101,10 @@ fn extract_body_text(xhtml: &str) -> String {
 
     // Insert newlines before/after block elements for readability
     let block_re = Regex::new(r"(?i)</?(p|div|h[1-6]|br|li|tr|blockquote|pre)[^>]*>").unwrap();
-    block_re.replace_all(body, "\n").to_string()
+    let text = block_re.replace_all(body, "\n");
+    // Keep <wbr> break opportunities as zero-width spaces for wrapping viewers
+    let wbr_re = Regex::new(r"(?i)<wbr\s*/?>").unwrap();
+    wbr_re.replace_all(&text, "\u{200B}").to_string()
 }
 
 #[cfg(test)]
@@ -136,4 +141,59 @@ mod tests {
         assert!(content.contains("Hello world."));
         std::fs::remove_file(&tmp).ok();
     }
+
+    #[test]
+    fn test_non_linear_notes_follow_chapters() {
+        let mut book = BookDocument::new();
+        for (id, text) in [
+            ("ch1", "Chapter one."),
+            ("notes", "Endnotes."),
+            ("ch2", "Chapter two."),
+        ] {
+            book.manifest.add(ManifestItem::new(
+                id,
+                format!("{}.xhtml", id),
+                "application/xhtml+xml",
+                ManifestData::Xhtml(format!("<html><body><p>{}</p></body></html>", text)),
+            ));
+        }
+        book.spine.push("ch1", true);
+        book.spine.push("notes", false);
+        book.spine.push("ch2", true);
+
+        let tmp = std::env::temp_dir().join("test_output_non_linear.txt");
+        TxtOutputPlugin
+            .convert(&book, &tmp, &ConversionOptions::default())
+            .unwrap();
+
+        let content = std::fs::read_to_string(&tmp).unwrap();
+        let one = content.find("Chapter one.").unwrap();
+        let two = content.find("Chapter two.").unwrap();
+        let notes = content.find("Endnotes.").unwrap();
+        assert!(one < two && two < notes, "{}", content);
+        std::fs::remove_file(&tmp).ok();
+    }
+
+    #[test]
+    fn test_wbr_becomes_zero_width_space() {
+        let xhtml =
+            "<html><body><p>https://example.com/<wbr>docs/<wbr/>index.html</p></body></html>";
+        assert_eq!(
+            extract_body_text(xhtml).trim(),
+            "https://example.com/\u{200B}docs/\u{200B}index.html"
+        );
+    }
+
+    #[test]
+    fn test_unwritable_path_reports_io_error() {
+        let output = std::env::temp_dir()
+            .join("test_txt_missing_dir")
+            .join("book.txt");
+        let result =
+            TxtOutputPlugin.convert(&BookDocument::new(), &output, &ConversionOptions::default());
+        match result {
+            Err(ConvertError::Io { path, .. }) => assert_eq!(path, output),
+            other => panic!("expected Io error, got {:?}", other),
+        }
+    }
 }
@@ -1,6 +1,8 @@
 //! Benchmarks for ebook conversion transforms.
 
-use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use std::hint::black_box;
+
+use criterion::{criterion_group, criterion_main, Criterion};
 
 use convert_core::book::{BookDocument, ManifestData, ManifestItem, TocEntry};
 use convert_core::options::ConversionOptions;
@@ -346,8 +348,10 @@ fn bench_image_rescale(c: &mut Criterion) {
     group.bench_function("5_images_200x200", |b| {
         b.iter(|| {
             let mut book = make_book_with_large_images(5, 200);
-            let mut opts = ConversionOptions::default();
-            opts.max_image_size = Some((100, 100));
+            let opts = ConversionOptions {
+                max_image_size: Some((100, 100)),
+                ..Default::default()
+            };
             ImageRescale.apply(black_box(&mut book), &opts).unwrap();
         })
     });
@@ -355,8 +359,10 @@ fn bench_image_rescale(c: &mut Criterion) {
     group.bench_function("20_images_200x200", |b| {
         b.iter(|| {
             let mut book = make_book_with_large_images(20, 200);
-            let mut opts = ConversionOptions::default();
-            opts.max_image_size = Some((100, 100));
+            let opts = ConversionOptions {
+                max_image_size: Some((100, 100)),
+                ..Default::default()
+            };
             ImageRescale.apply(black_box(&mut book), &opts).unwrap();
         })
     });
@@ -364,8 +370,10 @@ fn bench_image_rescale(c: &mut Criterion) {
     group.bench_function("50_images_1000x1000", |b| {
         b.iter(|| {
             let mut book = make_book_with_large_images(50, 1000);
-            let mut opts = ConversionOptions::default();
-            opts.max_image_size = Some((600, 800));
+            let opts = ConversionOptions {
+                max_image_size: Some((600, 800)),
+                ..Default::default()
+            };
             ImageRescale.apply(black_box(&mut book), &opts).unwrap();
         })
     });
@@ -426,8 +434,10 @@ fn bench_unsmarten(c: &mut Criterion) {
         group.bench_function(format!("{}_chapters", n), |b| {
             b.iter(|| {
                 let mut book = make_book_with_smart_quotes(n);
-                let mut opts = ConversionOptions::default();
-                opts.unsmarten_punctuation = true;
+                let opts = ConversionOptions {
+                    unsmarten_punctuation: true,
+                    ..Default::default()
+                };
                 UnsmartenPunctuation
                     .apply(black_box(&mut book), &opts)
                     .unwrap();
@@ -445,8 +455,10 @@ fn bench_linearize_tables(c: &mut Criterion) {
         group.bench_function(format!("{}_chapters", n), |b| {
             b.iter(|| {
                 let mut book = make_book_with_tables(n);
-                let mut opts = ConversionOptions::default();
-                opts.linearize_tables = true;
+                let opts = ConversionOptions {
+                    linearize_tables: true,
+                    ..Default::default()
+                };
                 LinearizeTables.apply(black_box(&mut book), &opts).unwrap();
             })
         });
@@ -478,8 +490,10 @@ fn bench_jacket(c: &mut Criterion) {
         group.bench_function(format!("{}_chapters_metadata", n), |b| {
             b.iter(|| {
                 let mut book = make_book(n, 0);
-                let mut opts = ConversionOptions::default();
-                opts.insert_metadata = true;
+                let opts = ConversionOptions {
+                    insert_metadata: true,
+                    ..Default::default()
+                };
                 Jacket.apply(black_box(&mut book), &opts).unwrap();
             })
         });
@@ -0,0 +1,157 @@
+//! CleanSpacing — removes double spaces and spaces before punctuation.
+//!
+//! Text extracted from PDFs or OCR output often has runs of spaces between
+//! words and stray spaces before commas and full stops (`word  ,  next`).
+//! Runs of spaces collapse to one, and spaces directly before `.,;:!?` are
+//! dropped. Only text nodes are touched; `<pre>` and code keep their spacing.
+
+use rayon::prelude::*;
+
+use convert_core::book::{BookDocument, ManifestData};
+use convert_core::error::Result;
+use convert_core::options::ConversionOptions;
+use convert_core::plugin::Transform;
+
+use crate::smarten::{map_text_nodes, Node};
+
+/// Collapses repeated spaces and removes spaces before punctuation.
+pub struct CleanSpacing;
+
+/// Elements whose text content is left untouched.
+const VERBATIM_ELEMENTS: &[&str] = &["pre", "code", "kbd", "samp", "script", "style", "textarea"];
+
+/// Punctuation that never follows a space.
+const CLOSING_PUNCTUATION: &[char] = &['.', ',', ';', ':', '!', '?'];
+
+impl Transform for CleanSpacing {
+    fn name(&self) -> &str {
+        "CleanSpacing"
+    }
+
+    fn should_run(&self, options: &ConversionOptions) -> bool {
+        options.clean_spacing
+    }
+
+    fn apply(&self, book: &mut BookDocument, _options: &ConversionOptions) -> Result<()> {
+        // Collect XHTML items
+        let xhtml_items: Vec<(String, String)> = book
+            .manifest
+            .iter()
+            .filter(|item| item.is_xhtml())
+            .filter_map(|item| {
+                item.data
+                    .as_xhtml()
+                    .map(|x| (item.id.clone(), x.to_string()))
+            })
+            .collect();
+
+        // Process in parallel
+        let results: Vec<(String, String)> = xhtml_items
+            .into_par_iter()
+            .filter_map(|(id, xhtml)| {
+                let new_xhtml = clean_spacing_xhtml(&xhtml);
+                if new_xhtml != xhtml {
+                    Some((id, new_xhtml))
+                } else {
+                    None
+                }
+            })
+            .collect();
+
+        // Apply back sequentially
+        let count = results.len() as u32;
+        for (id, new_xhtml) in results {
+            if let Some(item) = book.manifest.by_id_mut(&id) {
+                item.data = ManifestData::Xhtml(new_xhtml);
+            }
+        }
+
+        if count > 0 {
+            log::info!("Cleaned spacing in {} items", count);
+        }
+        Ok(())
+    }
+}
+
+/// Clean the text nodes of an XHTML document, leaving markup alone.
+fn clean_spacing_xhtml(xhtml: &str) -> String {
+    map_text_nodes(xhtml, VERBATIM_ELEMENTS, |node, out| {
+        if let Node::Text(text) = node {
+            out.push_str(&clean_spacing_text(text));
+        }
+    })
+}
+
+/// Collapse runs of spaces in a text node and drop those before punctuation.
+fn clean_spacing_text(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != ' ' {
+            out.push(c);
+            continue;
+        }
+        while chars.peek() == Some(&' ') {
+            chars.next();
+        }
+        if !chars
+            .peek()
+            .is_some_and(|next| CLOSING_PUNCTUATION.contains(next))
+        {
+            out.push(' ');
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use convert_core::book::ManifestItem;
+
+    fn clean(xhtml: &str) -> String {
+        let mut book = BookDocument::new();
+        book.manifest.add(ManifestItem::new(
+            "ch1",
+            "ch1.xhtml",
+            "application/xhtml+xml",
+            ManifestData::Xhtml(xhtml.to_string()),
+        ));
+        let opts = ConversionOptions {
+            clean_spacing: true,
+            ..Default::default()
+        };
+        CleanSpacing.apply(&mut book, &opts).unwrap();
+        let content = book.manifest.by_id("ch1").unwrap().data.as_xhtml().unwrap();
+        content.to_string()
+    }
+
+    #[test]
+    fn test_clean_spacing() {
+        assert_eq!(
+            clean("<html><body><p>word  ,  next</p></body></html>"),
+            "<html><body><p>word, next</p></body></html>"
+        );
+        assert_eq!(
+            clean(r#"<p class="a  b">One   two .  Three ?</p>"#),
+            r#"<p class="a  b">One two. Three?</p>"#
+        );
+    }
+
+    #[test]
+    fn test_pre_keeps_spacing() {
+        let xhtml = "<p>a  b</p><pre>x  =  1 ;\n  y  ,</pre><p>c  d</p>";
+        assert_eq!(
+            clean(xhtml),
+            "<p>a b</p><pre>x  =  1 ;\n  y  ,</pre><p>c d</p>"
+        );
+    }
+
+    #[test]
+    fn test_should_run() {
+        let mut opts = ConversionOptions::default();
+        assert!(!CleanSpacing.should_run(&opts));
+        opts.clean_spacing = true;
+        assert!(CleanSpacing.should_run(&opts));
+    }
+}
@@ -0,0 +1,324 @@
+//! CoverRescale transform — fits the cover image to the output profile's screen.
+//!
+//! `Contain` letterboxes the whole cover onto a white frame; `Cover` scales
+//! it to fill the frame and crops the overflow from the centre.
+
+use image::imageops::FilterType;
+use image::{DynamicImage, GenericImage, Rgba, RgbaImage};
+
+use convert_core::book::{BookDocument, ManifestData};
+use convert_core::error::Result;
+use convert_core::options::{ConversionOptions, CoverFit};
+use convert_core::plugin::Transform;
+
+/// Resizes the cover image to exactly the output profile's screen size.
+pub struct CoverRescale;
+
+impl Transform for CoverRescale {
+    fn name(&self) -> &str {
+        "CoverRescale"
+    }
+
+    fn should_run(&self, options: &ConversionOptions) -> bool {
+        !options.no_images && options.cover_fit.is_some()
+    }
+
+    fn apply(&self, book: &mut BookDocument, options: &ConversionOptions) -> Result<()> {
+        let Some(mode) = options.cover_fit else {
+            return Ok(());
+        };
+        let Some(cover_id) = find_cover_image(book) else {
+            log::info!("No cover image found, skipping cover fit");
+            return Ok(());
+        };
+
+        let (width, height) = (
+            options.output_profile.screen_width,
+            options.output_profile.screen_height,
+        );
+        let Some(item) = book.manifest.by_id_mut(&cover_id) else {
+            return Ok(());
+        };
+        let Some(data) = item.data.as_binary() else {
+            return Ok(());
+        };
+
+        let quality = options.jpeg_quality;
+        if let Some(fitted) = fit_cover(
+            data,
+            width,
+            height,
+            mode,
+            quality,
+            &item.media_type,
+            &item.href,
+        ) {
+            log::info!(
+                "Fitted cover {} to {}x{} ({:?})",
+                item.href,
+                width,
+                height,
+                mode
+            );
+            item.data = ManifestData::Binary(fitted);
+        }
+        Ok(())
+    }
+}
+
+/// Locate the manifest id of the cover image.
+///
+/// Prefers the guide's `cover` reference (either the image itself or the
+/// first image in a cover page), then any image whose id or href names it
+/// as the cover.
+fn find_cover_image(book: &BookDocument) -> Option<String> {
+    if let Some(guide_ref) = book.guide.get("cover") {
+        let href = guide_ref.href.split('#').next().unwrap_or_default();
+        if let Some(item) = book.manifest.by_href(href) {
+            if item.is_image() {
+                return Some(item.id.clone());
+            }
+            if let Some(xhtml) = item.data.as_xhtml() {
+                if let Some(src) = first_image_src(xhtml) {
+                    let resolved = convert_utils::href::resolve(&item.href, &src);
+                    if let Some(img) = book.manifest.by_href(&resolved) {
+                        if img.is_image() {
+                            return Some(img.id.clone());
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    book.manifest
+        .iter()
+        .filter(|item| item.is_image())
+        .find(|item| {
+            item.id.to_ascii_lowercase().contains("cover")
+                || item.href.to_ascii_lowercase().contains("cover")
+        })
+        .map(|item| item.id.clone())
+}
+
+/// The `src` of the first `<img>` (or `href` of an SVG `<image>`) in a document.
+fn first_image_src(xhtml: &str) -> Option<String> {
+    let re = regex::Regex::new(
+        r#"(?is)<(?:img\s[^>]*?\bsrc|image\s[^>]*?\b(?:xlink:)?href)\s*=\s*["']([^"']+)["']"#,
+    )
+    .ok()?;
+    re.captures(xhtml).map(|caps| caps[1].to_string())
+}
+
+/// Fit an encoded image into a `width`x`height` frame, re-encoding it in its
+/// original format, at `quality` for JPEG.
+///
+/// Returns `None` when the image cannot be decoded or re-encoded.
+fn fit_cover(
+    data: &[u8],
+    width: u32,
+    height: u32,
+    mode: CoverFit,
+    quality: u8,
+    media_type: &str,
+    href: &str,
+) -> Option<Vec<u8>> {
+    let img = match image::load_from_memory(data) {
+        Ok(img) => img,
+        Err(e) => {
+            log::warn!("Failed to decode cover {}: {}", href, e);
+            return None;
+        }
+    };
+
+    let fitted = fit_image(&img, width, height, mode);
+
+    // Keep the source format so the manifest entry's media type and href
+    // stay accurate
+    let format = image::ImageFormat::from_mime_type(media_type)
+        .or_else(|| image::ImageFormat::from_path(href).ok());
+    let Some(format) = format else {
+        log::warn!("Unknown cover format {} for {}", media_type, href);
+        return None;
+    };
+    let mut buf = Vec::new();
+    let result = match format {
+        image::ImageFormat::Jpeg => {
+            let encoder = image::codecs::jpeg::JpegEncoder::new_with_quality(&mut buf, quality);
+            DynamicImage::ImageRgb8(fitted.to_rgb8()).write_with_encoder(encoder)
+        }
+        _ => fitted.write_to(&mut std::io::Cursor::new(&mut buf), format),
+    };
+    if let Err(e) = result {
+        log::warn!("Failed to encode fitted cover {}: {}", href, e);
+        return None;
+    }
+    Some(buf)
+}
+
+/// Scale `img` into exactly `width`x`height` according to `mode`.
+fn fit_image(img: &DynamicImage, width: u32, height: u32, mode: CoverFit) -> DynamicImage {
+    match mode {
+        CoverFit::Cover => img.resize_to_fill(width, height, FilterType::Lanczos3),
+        CoverFit::Contain => {
+            let scaled = img.resize(width, height, FilterType::Lanczos3);
+            let mut canvas = RgbaImage::from_pixel(width, height, Rgba([255, 255, 255, 255]));
+            let x = (width - scaled.width()) / 2;
+            let y = (height - scaled.height()) / 2;
+            // Cannot fail: `scaled` fits within the canvas by construction
+            let _ = canvas.copy_from(&scaled.to_rgba8(), x, y);
+            DynamicImage::ImageRgba8(canvas)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use convert_core::book::{GuideRef, ManifestItem};
+
+    const RED: Rgba<u8> = Rgba([255, 0, 0, 255]);
+    const BLUE: Rgba<u8> = Rgba([0, 0, 255, 255]);
+    const GREEN: Rgba<u8> = Rgba([0, 255, 0, 255]);
+    const WHITE: Rgba<u8> = Rgba([255, 255, 255, 255]);
+
+    /// 100x100 red square with a blue band on the left and green on the right.
+    fn square_cover(format: image::ImageFormat) -> Vec<u8> {
+        let img = RgbaImage::from_fn(100, 100, |x, _| match x {
+            0..=19 => BLUE,
+            80..=99 => GREEN,
+            _ => RED,
+        });
+        let mut buf = Vec::new();
+        DynamicImage::ImageRgba8(img)
+            .write_to(&mut std::io::Cursor::new(&mut buf), format)
+            .unwrap();
+        buf
+    }
+
+    fn fit_book(mode: CoverFit) -> RgbaImage {
+        let data = fit_cover_as(mode, image::ImageFormat::Png);
+        image::load_from_memory(&data).unwrap().to_rgba8()
+    }
+
+    /// Fit a cover stored as `format` and return the rewritten image data.
+    fn fit_cover_as(mode: CoverFit, format: image::ImageFormat) -> Vec<u8> {
+        let ext = format.extensions_str()[0];
+        let mut book = BookDocument::new();
+        book.manifest.add(ManifestItem::new(
+            "cover-image",
+            format!("images/cover.{}", ext),
+            format.to_mime_type(),
+            ManifestData::Binary(square_cover(format)),
+        ));
+        book.manifest.add(ManifestItem::new(
+            "titlepage",
+            "text/titlepage.xhtml",
+            "application/xhtml+xml",
+            ManifestData::Xhtml(format!(
+                r#"<html><body><img src="../images/cover.{}"/></body></html>"#,
+                ext
+            )),
+        ));
+        book.guide
+            .add(GuideRef::new("cover", "Cover", "text/titlepage.xhtml"));
+
+        let mut opts = ConversionOptions {
+            cover_fit: Some(mode),
+            ..Default::default()
+        };
+        opts.output_profile.screen_width = 60;
+        opts.output_profile.screen_height = 120;
+        CoverRescale.apply(&mut book, &opts).unwrap();
+
+        let item = book.manifest.by_id("cover-image").unwrap();
+        assert_eq!(item.media_type, format.to_mime_type());
+        item.data.as_binary().unwrap().to_vec()
+    }
+
+    fn close(a: Rgba<u8>, b: Rgba<u8>) -> bool {
+        a.0.iter().zip(b.0.iter()).all(|(x, y)| x.abs_diff(*y) < 40)
+    }
+
+    #[test]
+    fn test_contain_letterboxes_square_cover() {
+        let out = fit_book(CoverFit::Contain);
+        assert_eq!(out.dimensions(), (60, 120));
+        // Letterbox bands above and below the 60x60 cover
+        assert!(close(*out.get_pixel(30, 5), WHITE));
+        assert!(close(*out.get_pixel(30, 114), WHITE));
+        // Both edge bands survive: nothing was cropped
+        assert!(close(*out.get_pixel(2, 60), BLUE));
+        assert!(close(*out.get_pixel(57, 60), GREEN));
+        assert!(close(*out.get_pixel(30, 60), RED));
+    }
+
+    #[test]
+    fn test_cover_crops_square_cover() {
+        let out = fit_book(CoverFit::Cover);
+        assert_eq!(out.dimensions(), (60, 120));
+        // Frame is filled edge to edge with no letterbox
+        for (x, y) in [(0, 0), (59, 0), (0, 119), (59, 119), (30, 60)] {
+            assert!(close(*out.get_pixel(x, y), RED), "pixel ({x}, {y})");
+        }
+        // The side bands were cropped away
+        assert!(out.pixels().all(|p| !close(*p, BLUE) && !close(*p, GREEN)));
+    }
+
+    #[test]
+    fn test_gif_cover_stays_gif() {
+        let data = fit_cover_as(CoverFit::Contain, image::ImageFormat::Gif);
+        assert_eq!(image::guess_format(&data).unwrap(), image::ImageFormat::Gif);
+        let out = image::load_from_memory(&data).unwrap();
+        assert_eq!((out.width(), out.height()), (60, 120));
+    }
+
+    #[test]
+    fn test_jpeg_cover_uses_jpeg_quality() {
+        let data = square_cover(image::ImageFormat::Jpeg);
+        let fit = |quality| {
+            let href = "cover.jpg";
+            fit_cover(
+                &data,
+                60,
+                120,
+                CoverFit::Contain,
+                quality,
+                "image/jpeg",
+                href,
+            )
+            .unwrap()
+        };
+        let (low, high) = (fit(20), fit(95));
+        assert_eq!(image::guess_format(&low).unwrap(), image::ImageFormat::Jpeg);
+        assert!(low.len() < high.len(), "{} >= {}", low.len(), high.len());
+    }
+
+    #[test]
+    fn test_find_cover_by_name() {
+        let mut book = BookDocument::new();
+        book.manifest.add(ManifestItem::new(
+            "img1",
+            "images/photo.jpg",
+            "image/jpeg",
+            ManifestData::Binary(vec![1]),
+        ));
+        book.manifest.add(ManifestItem::new(
+            "img2",
+            "images/cover.jpg",
+            "image/jpeg",
+            ManifestData::Binary(vec![1]),
+        ));
+        assert_eq!(find_cover_image(&book).as_deref(), Some("img2"));
+    }
+
+    #[test]
+    fn test_should_run() {
+        let mut opts = ConversionOptions::default();
+        assert!(!CoverRescale.should_run(&opts));
+        opts.cover_fit = Some(CoverFit::Contain);
+        assert!(CoverRescale.should_run(&opts));
+        opts.no_images = true;
+        assert!(!CoverRescale.should_run(&opts));
+    }
+}
@@ -4,11 +4,13 @@
 //! of multiple stylesheets and XHTML spine items.
 //!
 //! This handles:
-//! - CSS parsing and minification via lightningcss
-//! - Extra CSS injection from user options
+//! - CSS parsing and minification via lightningcss (skipped with `pretty_print`)
+//! - Base font size / line height rules and extra CSS injection from user options
 //! - Ensuring XHTML documents have proper stylesheet links
 //! - Parallel processing of multiple CSS files with rayon
 
+use std::collections::HashSet;
+
 use convert_core::book::{BookDocument, ManifestData, ManifestItem};
 use convert_core::error::Result;
 use convert_core::options::ConversionOptions;
@@ -24,7 +26,11 @@ impl Transform for CssFlattener {
     }
 
     fn apply(&self, book: &mut BookDocument, options: &ConversionOptions) -> Result<()> {
-        // Step 1: Inject extra CSS if provided
+        // Step 1: Inject typography rules and extra CSS if provided; extra
+        // CSS comes last so it can override the typography rules
+        if let Some(css) = typography_css(options) {
+            inject_extra_css(book, &css);
+        }
         if let Some(extra_css) = &options.extra_css {
             inject_extra_css(book, extra_css);
         }
@@ -43,7 +49,7 @@ impl Transform for CssFlattener {
             })
             .collect();
 
-        if !css_items.is_empty() {
+        if !css_items.is_empty() && !options.pretty_print {
             // Process CSS files in parallel with rayon
             let minified: Vec<(usize, String)> = css_items
                 .into_par_iter()
@@ -113,16 +119,22 @@ impl Transform for CssFlattener {
     }
 }
 
-/// Minify a CSS string using lightningcss.
+/// Minify a CSS string using lightningcss: comments and whitespace are
+/// dropped, colors shortened and exact duplicate style rules removed.
+///
+/// Rules are never merged across selectors: a reader that rejects one
+/// selector in a list drops the whole rule.
 fn minify_css(css: &str, href: &str) -> String {
-    use lightningcss::stylesheet::{MinifyOptions, ParserOptions, PrinterOptions, StyleSheet};
+    use lightningcss::stylesheet::{ParserOptions, PrinterOptions, StyleSheet};
 
     match StyleSheet::parse(css, ParserOptions::default()) {
         Ok(mut stylesheet) => {
-            if let Err(e) = stylesheet.minify(MinifyOptions::default()) {
-                log::warn!("CSS minification warning for {}: {}", href, e);
-            }
-            match stylesheet.to_css(PrinterOptions::default()) {
+            remove_duplicate_rules(&mut stylesheet.rules.0);
+            let printer = PrinterOptions {
+                minify: true,
+                ..Default::default()
+            };
+            match stylesheet.to_css(printer) {
                 Ok(result) => {
                     let saved = css.len() as i64 - result.code.len() as i64;
                     if saved > 0 {
@@ -149,6 +161,38 @@ fn minify_css(css: &str, href: &str) -> String {
     }
 }
 
+/// Drop style rules identical to a later one, which overrides them anyway.
+fn remove_duplicate_rules(rules: &mut Vec<lightningcss::rules::CssRule<'_>>) {
+    use lightningcss::rules::CssRule;
+    use lightningcss::stylesheet::PrinterOptions;
+    use lightningcss::traits::ToCss;
+
+    let mut seen = HashSet::new();
+    let mut keep = vec![true; rules.len()];
+    for (i, rule) in rules.iter().enumerate().rev() {
+        if let CssRule::Style(_) = rule {
+            if let Ok(text) = rule.to_css_string(PrinterOptions::default()) {
+                keep[i] = seen.insert(text);
+            }
+        }
+    }
+    let mut keep = keep.into_iter();
+    rules.retain(|_| keep.next().unwrap_or(true));
+}
+
+/// `body` rule for the `base_font_size` and `line_height` options, if set.
+/// Headings keep their relative (`em`) sizes, so they scale with the body.
+fn typography_css(options: &ConversionOptions) -> Option<String> {
+    let mut decls = Vec::new();
+    if options.base_font_size > 0.0 {
+        decls.push(format!("font-size: {}pt", options.base_font_size));
+    }
+    if let Some(line_height) = options.line_height.filter(|lh| *lh > 0.0) {
+        decls.push(format!("line-height: {}", line_height));
+    }
+    (!decls.is_empty()).then(|| format!("body {{ {}; }}", decls.join("; ")))
+}
+
 /// Inject extra CSS into the book's first stylesheet or create a new one.
 fn inject_extra_css(book: &mut BookDocument, extra_css: &str) {
     let mut found_css = false;
@@ -243,6 +287,33 @@ mod tests {
         assert!(book.manifest.by_href("extra.css").is_some());
     }
 
+    #[test]
+    fn test_typography_rules_precede_extra_css() {
+        let mut book = BookDocument::new();
+        book.manifest.add(ManifestItem::new(
+            "style",
+            "style.css",
+            "text/css",
+            ManifestData::Css("h1 { font-size: 2em; }".to_string()),
+        ));
+        let opts = ConversionOptions {
+            base_font_size: 16.0,
+            line_height: Some(1.5),
+            extra_css: Some("p { margin: 0; }".to_string()),
+            pretty_print: true,
+            ..Default::default()
+        };
+
+        CssFlattener.apply(&mut book, &opts).unwrap();
+
+        let css = book.manifest.by_id("style").unwrap().data.as_css().unwrap();
+        let body = css
+            .find("body { font-size: 16pt; line-height: 1.5; }")
+            .unwrap();
+        assert!(body < css.find("p { margin: 0; }").unwrap());
+        assert!(typography_css(&ConversionOptions::default()).is_none());
+    }
+
     #[test]
     fn test_minify_css() {
         let css = "body {\n  margin: 0;\n  padding: 0;\n}\n\np {\n  color: red;\n}\n";
@@ -253,6 +324,40 @@ mod tests {
         assert!(minified.contains("color"));
     }
 
+    #[test]
+    fn test_minify_css_sample_stylesheet() {
+        let css = "/* Book styles */\nbody {\n  background: #ffffff;\n  color: #000000;\n}\n\n.note { margin: 0 0 1em 0; }\nh1 { font-weight: bold; }\nh2 { font-weight: bold; }\n.note { margin: 0 0 1em 0; }\n";
+        let minified = minify_css(css, "test.css");
+        assert!(minified.len() < css.len());
+        // Same rules in the same cascade order, minus the overridden copy;
+        // h1 and h2 stay separate rules
+        assert_eq!(
+            minified,
+            "body{background:#fff;color:#000}h1{font-weight:700}h2{font-weight:700}.note{margin:0 0 1em}"
+        );
+    }
+
+    #[test]
+    fn test_pretty_print_keeps_css_readable() {
+        let css = "p {\n  color: #ffffff;\n}\n";
+        let mut book = BookDocument::new();
+        book.manifest.add(ManifestItem::new(
+            "style",
+            "style.css",
+            "text/css",
+            ManifestData::Css(css.into()),
+        ));
+        let opts = ConversionOptions {
+            pretty_print: true,
+            ..Default::default()
+        };
+        CssFlattener.apply(&mut book, &opts).unwrap();
+        assert_eq!(
+            book.manifest.by_id("style").unwrap().data.as_css(),
+            Some(css)
+        );
+    }
+
     #[test]
     fn test_ensure_css_links() {
         let xhtml = "<html><head><title>Test</title></head><body></body></html>";
@@ -1,4 +1,6 @@
-//! DataURL resolver — extracts base64-encoded data URIs from XHTML and creates manifest items.
+//! DataURL resolver — extracts data URIs from XHTML and creates manifest items.
+
+use std::collections::HashMap;
 
 use base64::Engine;
 use rayon::prelude::*;
@@ -7,10 +9,15 @@ use convert_core::book::{BookDocument, ManifestData, ManifestItem};
 use convert_core::error::Result;
 use convert_core::options::ConversionOptions;
 use convert_core::plugin::Transform;
+use convert_utils::href;
 use regex::Regex;
 
-/// Finds `<img src="data:...;base64,...">` in XHTML, decodes the data,
-/// creates manifest items, and replaces the src attributes.
+/// Finds `src="data:<mime>[;base64],<data>"` attributes in XHTML, decodes
+/// the data, creates manifest items, and points the `src` at them so later
+/// transforms (rescale, transcode, dedup) see ordinary images.
+///
+/// Both base64 and URL-encoded data URIs are handled. The same URI used in
+/// several places becomes a single manifest item.
 pub struct DataUrl;
 
 impl Transform for DataUrl {
@@ -19,83 +26,79 @@ impl Transform for DataUrl {
     }
 
     fn apply(&self, book: &mut BookDocument, _options: &ConversionOptions) -> Result<()> {
-        let re = Regex::new(r#"src\s*=\s*["'](data:([^;]+);base64,([^"']+))["']"#).unwrap();
+        let re = Regex::new(r#"(?i)\bsrc\s*=\s*(?:"(data:[^"]*)"|'(data:[^']*)')"#).unwrap();
 
         // Collect XHTML items that contain data URIs
-        let xhtml_items: Vec<(String, String)> = book
+        let xhtml_items: Vec<(String, String, String)> = book
             .manifest
             .iter()
             .filter(|item| item.is_xhtml())
-            .filter(|item| {
-                item.data
-                    .as_xhtml()
-                    .map(|x| x.contains("data:"))
-                    .unwrap_or(false)
+            .filter_map(|item| {
+                let xhtml = item.data.as_xhtml()?;
+                xhtml
+                    .contains("data:")
+                    .then(|| (item.id.clone(), item.href.clone(), xhtml.to_string()))
             })
-            .map(|item| (item.id.clone(), item.data.as_xhtml().unwrap().to_string()))
             .collect();
 
-        // Decode data URIs in parallel per XHTML item
-        // Each produces: (id, new_xhtml, Vec<(mime, decoded_data, old_uri)>)
+        // Decode the distinct data URIs of each XHTML item in parallel
         #[allow(clippy::type_complexity)]
-        let decoded_results: Vec<(String, String, Vec<(String, Vec<u8>)>)> = xhtml_items
-            .into_par_iter()
-            .map(|(id, xhtml)| {
-                let mut new_xhtml = xhtml.clone();
-                let mut decoded_items: Vec<(String, Vec<u8>)> = Vec::new();
-
-                for cap in re.captures_iter(&xhtml) {
-                    let full_data_uri = &cap[1];
-                    let mime_type = cap[2].to_string();
-                    let b64_data = &cap[3];
-
-                    let decoded = match base64::engine::general_purpose::STANDARD.decode(b64_data) {
-                        Ok(d) => d,
-                        Err(_) => {
-                            let cleaned: String =
-                                b64_data.chars().filter(|c| !c.is_whitespace()).collect();
-                            match base64::engine::general_purpose::STANDARD.decode(&cleaned) {
-                                Ok(d) => d,
-                                Err(_) => continue,
-                            }
+        let decoded_results: Vec<(String, String, String, Vec<(String, String, Vec<u8>)>)> =
+            xhtml_items
+                .into_par_iter()
+                .map(|(id, base, xhtml)| {
+                    let mut decoded: Vec<(String, String, Vec<u8>)> = Vec::new();
+                    for cap in re.captures_iter(&xhtml) {
+                        let uri = cap.get(1).or_else(|| cap.get(2)).unwrap().as_str();
+                        if decoded.iter().any(|(u, _, _)| u == uri) {
+                            continue;
                         }
-                    };
-
-                    // Use a placeholder that will be replaced with actual href
-                    let placeholder = format!("__dataurl_placeholder_{}__", decoded_items.len());
-                    new_xhtml = new_xhtml.replace(full_data_uri, &placeholder);
-                    decoded_items.push((mime_type, decoded));
-                }
-
-                (id, new_xhtml, decoded_items)
-            })
-            .collect();
+                        match decode_data_uri(uri) {
+                            Some((mime, data)) => decoded.push((uri.to_string(), mime, data)),
+                            None => log::warn!("Skipping malformed data URI in {}", base),
+                        }
+                    }
+                    (id, base, xhtml, decoded)
+                })
+                .collect();
 
-        // Apply results sequentially (needs &mut for generate_id/generate_href)
+        // Create manifest items sequentially (needs &mut for generate_id/generate_href)
+        let mut hrefs: HashMap<String, String> = HashMap::new();
         let mut count = 0u32;
-        for (id, mut new_xhtml, decoded_items) in decoded_results {
-            for (i, (mime_type, decoded)) in decoded_items.into_iter().enumerate() {
+        for (id, base, xhtml, decoded) in decoded_results {
+            for (uri, mime_type, data) in decoded {
+                if hrefs.contains_key(&uri) {
+                    continue;
+                }
                 count += 1;
                 let ext = mime_to_ext(&mime_type);
-                let href = book
+                let item_href = book
                     .manifest
                     .generate_href(&format!("data_image_{}", count), ext);
                 let item_id = book.manifest.generate_id("dataimg");
 
-                let placeholder = format!("__dataurl_placeholder_{}__", i);
-                new_xhtml = new_xhtml.replace(&placeholder, &href);
-
-                log::debug!("Extracted data URI → {}", href);
+                log::debug!("Extracted data URI → {}", item_href);
                 book.manifest.add(ManifestItem::new(
                     item_id,
-                    href,
+                    item_href.clone(),
                     mime_type,
-                    ManifestData::Binary(decoded),
+                    ManifestData::Binary(data),
                 ));
+                hrefs.insert(uri, item_href);
             }
 
-            if let Some(item_mut) = book.manifest.by_id_mut(&id) {
-                item_mut.data = ManifestData::Xhtml(new_xhtml);
+            let new_xhtml = re.replace_all(&xhtml, |cap: &regex::Captures| {
+                let uri = cap.get(1).or_else(|| cap.get(2)).unwrap().as_str();
+                match hrefs.get(uri) {
+                    Some(target) => format!(r#"src="{}""#, href::relative(&base, target)),
+                    None => cap[0].to_string(),
+                }
+            });
+            if new_xhtml != xhtml {
+                let new_xhtml = new_xhtml.into_owned();
+                if let Some(item_mut) = book.manifest.by_id_mut(&id) {
+                    item_mut.data = ManifestData::Xhtml(new_xhtml);
+                }
             }
         }
 
@@ -106,6 +109,46 @@ impl Transform for DataUrl {
     }
 }
 
+/// Decode a `data:` URI as it appears in an XHTML attribute into its media
+/// type and bytes. The media type defaults to `text/plain` as in RFC 2397.
+fn decode_data_uri(uri: &str) -> Option<(String, Vec<u8>)> {
+    let uri = unescape_attr(uri);
+    let (header, payload) = uri.strip_prefix("data:")?.split_once(',')?;
+    let mut params = header.split(';').map(str::trim);
+    let mime = match params.next().unwrap_or_default().to_ascii_lowercase() {
+        m if m.is_empty() => "text/plain".to_string(),
+        m if m == "image/jpg" => "image/jpeg".to_string(),
+        m => m,
+    };
+    let is_base64 = params.any(|p| p.eq_ignore_ascii_case("base64"));
+
+    let data = if is_base64 {
+        let cleaned: String = percent_encoding::percent_decode_str(payload)
+            .decode_utf8_lossy()
+            .chars()
+            .filter(|c| !c.is_whitespace())
+            .collect();
+        base64::engine::general_purpose::STANDARD
+            .decode(&cleaned)
+            .or_else(|_| base64::engine::general_purpose::STANDARD_NO_PAD.decode(&cleaned))
+            .ok()?
+    } else {
+        percent_encoding::percent_decode_str(payload).collect()
+    };
+    Some((mime, data))
+}
+
+/// Undo the XML escaping of an attribute value.
+fn unescape_attr(value: &str) -> String {
+    value
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&#39;", "'")
+        .replace("&amp;", "&")
+}
+
 fn mime_to_ext(mime: &str) -> &str {
     match mime {
         "image/png" => "png",
@@ -152,6 +195,54 @@ mod tests {
         assert!(content.contains("data_image_"));
     }
 
+    #[test]
+    fn test_base64_png_becomes_manifest_image() {
+        let mut book = BookDocument::new();
+        let b64 = "iVBORw0KGgoAAAANSUhEUgAAAAEAAAABCAYAAAAfFcSJAAAADUlEQVR42mP8/5+hHgAHggJ/PchI7wAAAABJRU5ErkJggg==";
+        let xhtml = format!(
+            r#"<html><body><p><img src="data:image/png;base64,{0}" alt="a"/></p><img src='data:image/png;base64,{0}'/></body></html>"#,
+            b64
+        );
+        book.manifest.add(ManifestItem::new(
+            "ch1",
+            "text/ch1.xhtml",
+            "application/xhtml+xml",
+            ManifestData::Xhtml(xhtml),
+        ));
+        book.spine.push("ch1", true);
+
+        DataUrl
+            .apply(&mut book, &ConversionOptions::default())
+            .unwrap();
+
+        // Both references share one image
+        let images = book.manifest.items_of_type("image/");
+        assert_eq!(images.len(), 1);
+        let image = images[0];
+        assert_eq!(image.href, "data_image_1.png");
+        assert_eq!(image.media_type, "image/png");
+        assert!(image.data.as_binary().unwrap().starts_with(b"\x89PNG"));
+
+        let content = book.manifest.by_id("ch1").unwrap().data.as_xhtml().unwrap();
+        assert_eq!(content.matches(r#"src="../data_image_1.png""#).count(), 2);
+        assert!(content.contains(r#"alt="a""#));
+    }
+
+    #[test]
+    fn test_url_encoded_data_uri() {
+        let uri = "data:image/svg+xml,%3Csvg xmlns=&quot;http://www.w3.org/2000/svg&quot;/%3E";
+        let (mime, data) = decode_data_uri(uri).unwrap();
+        assert_eq!(mime, "image/svg+xml");
+        assert_eq!(data, br#"<svg xmlns="http://www.w3.org/2000/svg"/>"#);
+
+        let (mime, data) = decode_data_uri("data:,Hello%2C%20World").unwrap();
+        assert_eq!(mime, "text/plain");
+        assert_eq!(data, b"Hello, World");
+
+        assert!(decode_data_uri("data:image/png;base64,@@@").is_none());
+        assert!(decode_data_uri("data:image/png;base64").is_none());
+    }
+
     #[test]
     fn test_no_data_uris_unchanged() {
         let mut book = BookDocument::new();
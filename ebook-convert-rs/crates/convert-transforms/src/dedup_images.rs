@@ -0,0 +1,158 @@
+//! DedupImages transform — collapses byte-identical images into one item.
+//!
+//! Documents converted from DOCX/ODT often embed the same logo or ornament
+//! once per occurrence. Images are grouped by SHA-256 of their bytes; the
+//! first item in manifest order is kept and references to the copies are
+//! pointed at it before the copies are removed.
+
+use std::collections::HashMap;
+
+use rayon::prelude::*;
+use sha2::{Digest, Sha256};
+
+use convert_core::book::BookDocument;
+use convert_core::error::Result;
+use convert_core::options::ConversionOptions;
+use convert_core::plugin::Transform;
+
+use crate::references::rewrite_references;
+
+/// Removes duplicate binary images, keeping the first copy of each.
+pub struct DedupImages;
+
+impl Transform for DedupImages {
+    fn name(&self) -> &str {
+        "DedupImages"
+    }
+
+    fn should_run(&self, options: &ConversionOptions) -> bool {
+        !options.no_images && options.dedup_images
+    }
+
+    fn apply(&self, book: &mut BookDocument, _options: &ConversionOptions) -> Result<()> {
+        // Hash image data in parallel, keeping manifest order
+        let hashed: Vec<(String, String, [u8; 32])> = book
+            .manifest
+            .par_iter()
+            .filter(|item| item.media_type.starts_with("image/"))
+            .filter_map(|item| {
+                let data = item.data.as_binary()?;
+                let digest: [u8; 32] = Sha256::digest(data).into();
+                Some((item.id.clone(), item.href.clone(), digest))
+            })
+            .collect();
+
+        // Map each duplicate's href to the href of the first identical image
+        let mut canonical: HashMap<[u8; 32], String> = HashMap::new();
+        let mut duplicates: HashMap<String, String> = HashMap::new();
+        let mut duplicate_ids = Vec::new();
+        for (id, item_href, digest) in hashed {
+            match canonical.get(&digest) {
+                Some(keep) => {
+                    duplicates.insert(item_href, keep.clone());
+                    duplicate_ids.push(id);
+                }
+                None => {
+                    canonical.insert(digest, item_href);
+                }
+            }
+        }
+
+        if duplicates.is_empty() {
+            return Ok(());
+        }
+
+        rewrite_references(book, &duplicates);
+        for id in &duplicate_ids {
+            book.manifest.remove_by_id(id);
+        }
+
+        log::info!("Removed {} duplicate images", duplicate_ids.len());
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use convert_core::book::{GuideRef, ManifestData, ManifestItem};
+
+    fn image_item(id: &str, href: &str, data: &[u8]) -> ManifestItem {
+        ManifestItem::new(id, href, "image/png", ManifestData::Binary(data.to_vec()))
+    }
+
+    fn image_count(book: &BookDocument) -> usize {
+        book.manifest
+            .iter()
+            .filter(|item| item.media_type.starts_with("image/"))
+            .count()
+    }
+
+    #[test]
+    fn test_identical_images_collapsed() {
+        let mut book = BookDocument::new();
+        book.manifest
+            .add(image_item("logo1", "images/logo1.png", b"logo bytes"));
+        book.manifest
+            .add(image_item("logo2", "media/logo2.png", b"logo bytes"));
+        book.manifest
+            .add(image_item("photo", "images/photo.png", b"photo bytes"));
+        book.manifest.add(ManifestItem::new(
+            "ch1",
+            "text/ch1.xhtml",
+            "application/xhtml+xml",
+            ManifestData::Xhtml(
+                r#"<html><body><img src="../images/logo1.png"/><img src="../media/logo2.png"/><img src="../images/photo.png"/></body></html>"#
+                    .into(),
+            ),
+        ));
+        book.manifest.add(ManifestItem::new(
+            "css",
+            "style.css",
+            "text/css",
+            ManifestData::Css("p { background: url(media/logo2.png); }".into()),
+        ));
+
+        DedupImages
+            .apply(&mut book, &ConversionOptions::default())
+            .unwrap();
+
+        assert_eq!(image_count(&book), 2);
+        assert!(book.manifest.by_id("logo1").is_some());
+        assert!(book.manifest.by_id("logo2").is_none());
+        assert!(book.manifest.by_id("photo").is_some());
+
+        let xhtml = book.manifest.by_id("ch1").unwrap().data.as_xhtml().unwrap();
+        assert_eq!(xhtml.matches(r#"src="../images/logo1.png""#).count(), 2);
+        assert!(xhtml.contains(r#"src="../images/photo.png""#));
+        assert!(!xhtml.contains("logo2"));
+
+        let css = book.manifest.by_id("css").unwrap().data.as_css().unwrap();
+        assert!(css.contains("url(images/logo1.png)"));
+    }
+
+    #[test]
+    fn test_guide_reference_follows_canonical() {
+        let mut book = BookDocument::new();
+        book.manifest.add(image_item("a", "a.png", b"same"));
+        book.manifest.add(image_item("b", "b.png", b"same"));
+        book.guide.add(GuideRef::new("cover", "Cover", "b.png"));
+
+        DedupImages
+            .apply(&mut book, &ConversionOptions::default())
+            .unwrap();
+
+        assert_eq!(image_count(&book), 1);
+        assert_eq!(book.guide.get("cover").unwrap().href, "a.png");
+    }
+
+    #[test]
+    fn test_should_run() {
+        let mut opts = ConversionOptions::default();
+        assert!(!DedupImages.should_run(&opts));
+        opts.dedup_images = true;
+        assert!(DedupImages.should_run(&opts));
+        opts.no_images = true;
+        assert!(!DedupImages.should_run(&opts));
+    }
+}
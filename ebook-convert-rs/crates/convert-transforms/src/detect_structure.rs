@@ -1,11 +1,15 @@
 //! DetectStructure transform — detects chapters, headings, and builds TOC.
+//!
+//! Also points the guide's `text` reference at the first page of content,
+//! which readers use for "Go to Beginning".
 
 use rayon::prelude::*;
 
-use convert_core::book::{BookDocument, TocEntry};
+use convert_core::book::{BookDocument, GuideRef, ManifestData, TocEntry};
 use convert_core::error::Result;
 use convert_core::options::ConversionOptions;
 use convert_core::plugin::Transform;
+use convert_utils::xml::heading_text;
 
 use regex::Regex;
 
@@ -18,6 +22,11 @@ impl Transform for DetectStructure {
     }
 
     fn apply(&self, book: &mut BookDocument, options: &ConversionOptions) -> Result<()> {
+        if options.remove_empty_headings {
+            remove_empty_headings(book);
+        }
+        set_start_reading(book);
+
         // If TOC already has entries (e.g., from input plugin), skip detection
         if !book.toc.entries.is_empty() {
             log::info!(
@@ -86,11 +95,110 @@ impl Transform for DetectStructure {
     }
 }
 
+/// Guide types of front matter that reading starts after.
+const FRONT_MATTER: &[&str] = &["cover", "title-page", "toc"];
+
+/// Add a guide `text` reference to the first linear spine document that is
+/// not front matter, unless the input already marks where reading starts.
+fn set_start_reading(book: &mut BookDocument) {
+    if book.guide.get("text").is_some() {
+        return;
+    }
+    let front_matter: Vec<&str> = book
+        .guide
+        .iter()
+        .filter(|r| FRONT_MATTER.contains(&r.ref_type.as_str()))
+        .filter_map(|r| r.href.split('#').next())
+        .collect();
+    let start = book
+        .spine
+        .linear_items()
+        .filter_map(|idref| book.manifest.by_id(idref))
+        .filter(|item| !front_matter.contains(&item.href.as_str()))
+        .find(|item| item.data.as_xhtml().is_some_and(is_content_page))
+        .map(|item| item.href.clone());
+
+    if let Some(href) = start {
+        log::info!("Reading starts at {}", href);
+        book.guide.add(GuideRef::new("text", "Beginning", href));
+    }
+}
+
+/// Whether a document has text of its own, rather than being an image-only
+/// cover or a contents page that is mostly links.
+fn is_content_page(xhtml: &str) -> bool {
+    let body_re = Regex::new(r"(?is)<body\b[^>]*>(.*)</body>").unwrap();
+    let link_re = Regex::new(r"(?is)<a\b[^>]*\bhref\s*=[^>]*>(.*?)</a>").unwrap();
+    let body = body_re
+        .captures(xhtml)
+        .map_or(xhtml, |cap| cap.get(1).unwrap().as_str());
+    let Some(text) = heading_text(body) else {
+        return false;
+    };
+    let link_text: usize = link_re
+        .captures_iter(body)
+        .filter_map(|cap| heading_text(&cap[1]))
+        .map(|t| t.len())
+        .sum();
+    link_text * 3 < text.len() * 2
+}
+
+/// Unwrap headings with no visible text from every XHTML item in the manifest.
+fn remove_empty_headings(book: &mut BookDocument) {
+    let xhtml_items: Vec<(String, String)> = book
+        .manifest
+        .iter()
+        .filter(|item| item.is_xhtml())
+        .filter_map(|item| {
+            item.data
+                .as_xhtml()
+                .map(|x| (item.id.clone(), x.to_string()))
+        })
+        .collect();
+
+    let results: Vec<(String, String)> = xhtml_items
+        .into_par_iter()
+        .filter_map(|(id, xhtml)| {
+            let unwrapped = unwrap_empty_headings(&xhtml);
+            if unwrapped != xhtml {
+                Some((id, unwrapped))
+            } else {
+                None
+            }
+        })
+        .collect();
+
+    log::info!("Removed empty headings from {} documents", results.len());
+    for (id, xhtml) in results {
+        if let Some(item) = book.manifest.by_id_mut(&id) {
+            item.data = ManifestData::Xhtml(xhtml);
+        }
+    }
+}
+
+/// Replace each empty heading element with its (whitespace-only) content.
+fn unwrap_empty_headings(xhtml: &str) -> String {
+    let mut result = xhtml.to_string();
+    for level in 1u8..=6 {
+        let pattern = format!(r"(?is)<h{}(?:\s[^>]*)?>(.*?)</h{}>", level, level);
+        let heading_re = Regex::new(&pattern).unwrap();
+        result = heading_re
+            .replace_all(&result, |cap: &regex::Captures| {
+                if heading_text(&cap[1]).is_none() {
+                    cap[1].to_string()
+                } else {
+                    cap[0].to_string()
+                }
+            })
+            .into_owned();
+    }
+    result
+}
+
 /// Extract heading text from XHTML content.
 /// Returns (heading_level, title_text) pairs.
 fn extract_headings(xhtml: &str, chapter_re: Option<&Regex>) -> Vec<(u8, String)> {
     let mut headings = Vec::new();
-    let tag_re = Regex::new(r"<[^>]+>").unwrap();
 
     // Match each heading level separately (regex crate doesn't support backreferences)
     for level in 1u8..=6 {
@@ -98,14 +206,10 @@ fn extract_headings(xhtml: &str, chapter_re: Option<&Regex>) -> Vec<(u8, String)
         let heading_re = Regex::new(&pattern).unwrap();
 
         for cap in heading_re.captures_iter(xhtml) {
-            let raw_title = &cap[1];
-
-            // Strip HTML tags from title
-            let title = tag_re.replace_all(raw_title, "").trim().to_string();
-
-            if title.is_empty() {
+            // Empty and whitespace-only headings never become TOC entries
+            let Some(title) = heading_text(&cap[1]) else {
                 continue;
-            }
+            };
 
             // If chapter regex is set, only include matching headings
             if let Some(re) = chapter_re {
@@ -131,6 +235,7 @@ fn extract_headings(xhtml: &str, chapter_re: Option<&Regex>) -> Vec<(u8, String)
 #[cfg(test)]
 mod tests {
     use super::*;
+    use convert_core::book::ManifestItem;
 
     #[test]
     fn test_extract_headings() {
@@ -157,4 +262,111 @@ mod tests {
         assert_eq!(headings.len(), 1);
         assert_eq!(headings[0].1, "Chapter 1");
     }
+
+    #[test]
+    fn test_extract_headings_skips_empty() {
+        let xhtml = "<html><body><h1>Chapter 1</h1><h2></h2><h2> <br/>&nbsp;</h2></body></html>";
+        let headings = extract_headings(xhtml, None);
+        assert_eq!(headings, vec![(1, "Chapter 1".to_string())]);
+    }
+
+    #[test]
+    fn test_empty_heading_creates_no_toc_entry() {
+        use convert_core::book::ManifestItem;
+
+        let mut book = BookDocument::new();
+        let xhtml = "<html><body><h1>Chapter 1</h1><p>Text</p><h2></h2><p>More</p></body></html>";
+        book.manifest.add(ManifestItem::new(
+            "ch1",
+            "ch1.xhtml",
+            "application/xhtml+xml",
+            ManifestData::Xhtml(xhtml.to_string()),
+        ));
+        book.spine.push("ch1", true);
+
+        DetectStructure
+            .apply(&mut book, &ConversionOptions::default())
+            .unwrap();
+
+        assert_eq!(book.toc.entries.len(), 1);
+        assert_eq!(book.toc.entries[0].title, "Chapter 1");
+        // Content is left alone unless remove_empty_headings is set
+        let content = book.manifest.by_id("ch1").unwrap().data.as_xhtml().unwrap();
+        assert!(content.contains("<h2></h2>"));
+    }
+
+    #[test]
+    fn test_remove_empty_headings_option() {
+        use convert_core::book::ManifestItem;
+
+        let mut book = BookDocument::new();
+        let xhtml = r#"<html><body><h1>Title</h1><h2 class="x"> </h2><h3><br/></h3></body></html>"#;
+        book.manifest.add(ManifestItem::new(
+            "ch1",
+            "ch1.xhtml",
+            "application/xhtml+xml",
+            ManifestData::Xhtml(xhtml.to_string()),
+        ));
+        book.spine.push("ch1", true);
+
+        let opts = ConversionOptions {
+            remove_empty_headings: true,
+            ..Default::default()
+        };
+        DetectStructure.apply(&mut book, &opts).unwrap();
+
+        let content = book.manifest.by_id("ch1").unwrap().data.as_xhtml().unwrap();
+        assert_eq!(content, "<html><body><h1>Title</h1> <br/></body></html>");
+        assert_eq!(book.toc.entries.len(), 1);
+    }
+
+    fn start_reading_book() -> BookDocument {
+        let mut book = BookDocument::new();
+        for (id, body) in [
+            ("cover", r#"<div><img src="cover.jpg" alt=""/></div>"#),
+            (
+                "contents",
+                r#"<h1>Contents</h1><p><a href="ch1.xhtml">Chapter One</a></p><p><a href="ch2.xhtml">Chapter Two</a></p>"#,
+            ),
+            ("ch1", "<h1>Chapter One</h1><p>It begins.</p>"),
+            ("ch2", "<h1>Chapter Two</h1><p>It goes on.</p>"),
+        ] {
+            book.manifest.add(ManifestItem::new(
+                id,
+                format!("{}.xhtml", id),
+                "application/xhtml+xml",
+                ManifestData::Xhtml(format!(
+                    "<html><head><title>{}</title></head><body>{}</body></html>",
+                    id, body
+                )),
+            ));
+            book.spine.push(id, true);
+        }
+        book
+    }
+
+    #[test]
+    fn test_start_reading_skips_cover_and_contents() {
+        let mut book = start_reading_book();
+        DetectStructure
+            .apply(&mut book, &ConversionOptions::default())
+            .unwrap();
+        assert_eq!(book.guide.get("text").unwrap().href, "ch1.xhtml");
+    }
+
+    #[test]
+    fn test_start_reading_skips_guide_front_matter() {
+        let mut book = start_reading_book();
+        book.guide
+            .add(GuideRef::new("toc", "Contents", "ch1.xhtml"));
+        set_start_reading(&mut book);
+        assert_eq!(book.guide.get("text").unwrap().href, "ch2.xhtml");
+
+        // A reference from the input is kept
+        let mut book = start_reading_book();
+        book.guide
+            .add(GuideRef::new("text", "Start", "contents.xhtml"));
+        set_start_reading(&mut book);
+        assert_eq!(book.guide.get("text").unwrap().href, "contents.xhtml");
+    }
 }
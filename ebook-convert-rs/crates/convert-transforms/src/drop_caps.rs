@@ -0,0 +1,210 @@
+//! DropCaps — decorative initial letters at the start of each chapter.
+//!
+//! The first letter of the first paragraph after each heading is wrapped in
+//! `<span class="dropcap">`, and a rule floating it over the next lines is
+//! added to the book's stylesheet. An opening quote is styled together with
+//! the letter it precedes; paragraphs that start with an image are skipped.
+
+use rayon::prelude::*;
+use regex::Regex;
+
+use convert_core::book::{BookDocument, ManifestData, ManifestItem};
+use convert_core::error::Result;
+use convert_core::options::ConversionOptions;
+use convert_core::plugin::Transform;
+
+use crate::smarten::tag_name;
+
+/// Wraps the first letter of each chapter's opening paragraph in a drop cap.
+pub struct DropCaps;
+
+/// Stylesheet rule for the wrapped letters.
+const DROP_CAP_CSS: &str = "span.dropcap {\n  float: left;\n  font-size: 3.2em;\n  line-height: 0.85;\n  margin: 0.05em 0.08em 0 0;\n}\n";
+
+/// Inline elements a paragraph may open with before its first letter.
+const INLINE_ELEMENTS: &[&str] = &["a", "b", "cite", "em", "i", "small", "span", "strong", "u"];
+
+/// Opening quotes kept inside the drop cap with the letter after them.
+const OPENING_QUOTES: &[&str] = &[
+    "\"", "'", "\u{201C}", "\u{2018}", "\u{201E}", "\u{201A}", "\u{00AB}", "\u{2039}", "&quot;",
+    "&ldquo;", "&lsquo;", "&bdquo;", "&laquo;", "&#8220;", "&#8216;", "&#8222;", "&#171;",
+];
+
+impl Transform for DropCaps {
+    fn name(&self) -> &str {
+        "DropCaps"
+    }
+
+    fn should_run(&self, options: &ConversionOptions) -> bool {
+        options.drop_caps
+    }
+
+    fn apply(&self, book: &mut BookDocument, _options: &ConversionOptions) -> Result<()> {
+        // Collect XHTML items
+        let xhtml_items: Vec<(String, String)> = book
+            .manifest
+            .iter()
+            .filter(|item| item.is_xhtml())
+            .filter_map(|item| {
+                item.data
+                    .as_xhtml()
+                    .map(|x| (item.id.clone(), x.to_string()))
+            })
+            .collect();
+
+        // Process in parallel
+        let results: Vec<(String, String)> = xhtml_items
+            .into_par_iter()
+            .filter_map(|(id, xhtml)| {
+                let new_xhtml = drop_caps_xhtml(&xhtml);
+                if new_xhtml != xhtml {
+                    Some((id, new_xhtml))
+                } else {
+                    None
+                }
+            })
+            .collect();
+
+        // Apply back sequentially
+        let count = results.len() as u32;
+        for (id, new_xhtml) in results {
+            if let Some(item) = book.manifest.by_id_mut(&id) {
+                item.data = ManifestData::Xhtml(new_xhtml);
+            }
+        }
+
+        if count > 0 {
+            add_drop_cap_css(book);
+            log::info!("Added drop caps in {} items", count);
+        }
+        Ok(())
+    }
+}
+
+/// Wrap the first letter of the first paragraph after each heading.
+fn drop_caps_xhtml(xhtml: &str) -> String {
+    let heading_re = Regex::new(r"(?is)<h[1-6]\b[^>]*>.*?</h[1-6]\s*>").unwrap();
+    let block_re = Regex::new(r"(?i)<(?:p|h[1-6])\b[^>]*>").unwrap();
+
+    let mut letters: Vec<(usize, usize)> = Vec::new();
+    for heading in heading_re.find_iter(xhtml) {
+        let Some(block) = block_re.find_at(xhtml, heading.end()) else {
+            continue;
+        };
+        // Another heading comes first: it gets the drop cap instead
+        if !block.as_str()[1..].to_ascii_lowercase().starts_with('p') {
+            continue;
+        }
+        if let Some(range) = first_letter(xhtml, block.end()) {
+            letters.push(range);
+        }
+    }
+
+    let mut result = xhtml.to_string();
+    for (start, end) in letters.into_iter().rev() {
+        let wrapped = format!(r#"<span class="dropcap">{}</span>"#, &xhtml[start..end]);
+        result.replace_range(start..end, &wrapped);
+    }
+    result
+}
+
+/// Byte range of the opening quote (if any) and first letter of the
+/// paragraph content starting at `pos`. `None` when the paragraph opens with
+/// an image or other non-text content, or is already a drop cap.
+fn first_letter(xhtml: &str, mut pos: usize) -> Option<(usize, usize)> {
+    loop {
+        let rest = xhtml[pos..].trim_start();
+        pos = xhtml.len() - rest.len();
+        if !rest.starts_with('<') {
+            break;
+        }
+        let tag = &rest[..rest.find('>')? + 1];
+        let (name, closing) = tag_name(tag);
+        if closing || !INLINE_ELEMENTS.contains(&name.as_str()) || tag.contains("dropcap") {
+            return None;
+        }
+        pos += tag.len();
+    }
+
+    let rest = &xhtml[pos..];
+    let quote = OPENING_QUOTES
+        .iter()
+        .find(|q| rest.starts_with(**q))
+        .map_or(0, |q| q.len());
+    let letter = rest[quote..].chars().next()?;
+    letter
+        .is_alphanumeric()
+        .then(|| (pos, pos + quote + letter.len_utf8()))
+}
+
+/// Append the drop cap rule to the first stylesheet, or add one.
+fn add_drop_cap_css(book: &mut BookDocument) {
+    if let Some(item) = book.manifest.iter_mut().find(|item| item.is_css()) {
+        if let Some(existing) = item.data.as_css() {
+            item.data = ManifestData::Css(format!("{}\n\n{}", existing, DROP_CAP_CSS));
+            return;
+        }
+    }
+    let id = book.manifest.generate_id("css");
+    let href = book.manifest.generate_href("dropcaps", "css");
+    book.manifest.add(ManifestItem::new(
+        id,
+        href,
+        "text/css",
+        ManifestData::Css(DROP_CAP_CSS.to_string()),
+    ));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_first_letter_wrapped() {
+        let xhtml = "<body><h1>One</h1>\n<p>It was dark.</p><p>Later.</p><h2>Two</h2><p><em>Morning</em> came.</p></body>";
+        assert_eq!(
+            drop_caps_xhtml(xhtml),
+            "<body><h1>One</h1>\n<p><span class=\"dropcap\">I</span>t was dark.</p><p>Later.</p><h2>Two</h2><p><em><span class=\"dropcap\">M</span>orning</em> came.</p></body>"
+        );
+    }
+
+    #[test]
+    fn test_opening_quote_and_image() {
+        let xhtml = "<h1>A</h1><p>\u{201C}Hello,\u{201D} she said.</p><h1>B</h1><p><img src=\"x.png\"/>Caption</p>";
+        assert_eq!(
+            drop_caps_xhtml(xhtml),
+            "<h1>A</h1><p><span class=\"dropcap\">\u{201C}H</span>ello,\u{201D} she said.</p><h1>B</h1><p><img src=\"x.png\"/>Caption</p>"
+        );
+        // Running twice doesn't nest drop caps
+        let once = drop_caps_xhtml(xhtml);
+        assert_eq!(drop_caps_xhtml(&once), once);
+    }
+
+    #[test]
+    fn test_transform_adds_css() {
+        let mut book = BookDocument::new();
+        book.manifest.add(ManifestItem::new(
+            "ch1",
+            "ch1.xhtml",
+            "application/xhtml+xml",
+            ManifestData::Xhtml("<html><body><h1>One</h1><p>Text</p></body></html>".into()),
+        ));
+        book.manifest.add(ManifestItem::new(
+            "style",
+            "style.css",
+            "text/css",
+            ManifestData::Css("p { margin: 0; }".into()),
+        ));
+        let opts = ConversionOptions {
+            drop_caps: true,
+            ..Default::default()
+        };
+        assert!(DropCaps.should_run(&opts));
+        DropCaps.apply(&mut book, &opts).unwrap();
+
+        let css = book.manifest.by_id("style").unwrap().data.as_css().unwrap();
+        assert!(css.starts_with("p { margin: 0; }") && css.contains("span.dropcap"));
+        let xhtml = book.manifest.by_id("ch1").unwrap().data.as_xhtml().unwrap();
+        assert!(xhtml.contains(r#"<p><span class="dropcap">T</span>ext</p>"#));
+    }
+}
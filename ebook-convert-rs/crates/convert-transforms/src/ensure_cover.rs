@@ -0,0 +1,226 @@
+//! EnsureCover — picks a cover for books whose input doesn't mark one.
+//!
+//! TXT, RTF and PDF input often carry images but no `cover` guide reference,
+//! leaving the EPUB and MOBI writers nothing to show as the cover. When the
+//! first spine document is just an image, that image is promoted; otherwise
+//! the largest image is. With `EnsureCover::TitlePage`, a book with no
+//! usable image gets a title page built from the jacket instead.
+
+use regex::Regex;
+
+use convert_core::book::{BookDocument, GuideRef, ManifestData, ManifestItem};
+use convert_core::error::Result;
+use convert_core::options::{ConversionOptions, EnsureCover as EnsureCoverMode};
+use convert_core::plugin::Transform;
+use convert_utils::href;
+
+use crate::jacket::jacket_document;
+use crate::remove_tiny_images::image_dimensions;
+
+/// Images smaller than this in either dimension are never made the cover.
+const MIN_COVER_DIMENSION: u32 = 200;
+
+/// Sets a guide `cover` reference when `ensure_cover` is set and none exists.
+pub struct EnsureCover;
+
+impl Transform for EnsureCover {
+    fn name(&self) -> &str {
+        "EnsureCover"
+    }
+
+    fn should_run(&self, options: &ConversionOptions) -> bool {
+        options.ensure_cover.is_some()
+    }
+
+    fn apply(&self, book: &mut BookDocument, options: &ConversionOptions) -> Result<()> {
+        if book.guide.get("cover").is_some() {
+            return Ok(());
+        }
+
+        if let Some(href) = full_page_first_image(book).or_else(|| largest_image(book)) {
+            log::info!("Using {} as the cover", href);
+            book.guide.add(GuideRef::new("cover", "Cover", href));
+        } else if options.ensure_cover == Some(EnsureCoverMode::TitlePage) {
+            let id = book.manifest.generate_id("cover");
+            let href = book.manifest.generate_href("cover", "xhtml");
+            book.manifest.add(ManifestItem::new(
+                &id,
+                &href,
+                "application/xhtml+xml",
+                ManifestData::Xhtml(jacket_document(book, false)),
+            ));
+            book.spine.insert(0, &id, true);
+            book.guide.add(GuideRef::new("cover", "Cover", &href));
+            log::info!("No cover image found; generated a title page cover");
+        }
+        Ok(())
+    }
+}
+
+/// Href of the image filling the first spine document, if that document
+/// has no text of its own.
+fn full_page_first_image(book: &BookDocument) -> Option<String> {
+    let item = book.manifest.by_id(&book.spine.items().first()?.idref)?;
+    let xhtml = item.data.as_xhtml()?;
+    let body = match xhtml.find("<body") {
+        Some(start) => &xhtml[start..],
+        None => xhtml,
+    };
+
+    let tag_re = Regex::new(r"<[^>]*>").unwrap();
+    if !tag_re.replace_all(body, "").trim().is_empty() {
+        return None;
+    }
+    let img_re =
+        Regex::new(r#"(?i)<(?:img|image)\b[^>]*?\s(?:src|xlink:href)\s*=\s*["']([^"']+)["']"#)
+            .unwrap();
+    let src = &img_re.captures(body)?[1];
+    let href = href::resolve(&item.href, src);
+    let image = book.manifest.by_href(&href)?;
+    cover_sized(image).then_some(href)
+}
+
+/// Href of the image with the most pixels.
+fn largest_image(book: &BookDocument) -> Option<String> {
+    book.manifest
+        .iter()
+        .filter(|item| cover_sized(item))
+        .filter_map(|item| {
+            let (w, h) = image_dimensions(item.data.as_binary()?)?;
+            Some((u64::from(w) * u64::from(h), item))
+        })
+        .max_by_key(|(area, _)| *area)
+        .map(|(_, item)| item.href.clone())
+}
+
+/// Whether `item` is an image big enough to serve as a cover.
+fn cover_sized(item: &ManifestItem) -> bool {
+    item.is_image()
+        && item
+            .data
+            .as_binary()
+            .and_then(image_dimensions)
+            .is_some_and(|(w, h)| w >= MIN_COVER_DIMENSION && h >= MIN_COVER_DIMENSION)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn png(width: u32, height: u32) -> Vec<u8> {
+        let img = image::RgbImage::new(width, height);
+        let mut data = Vec::new();
+        image::DynamicImage::ImageRgb8(img)
+            .write_to(
+                &mut std::io::Cursor::new(&mut data),
+                image::ImageFormat::Png,
+            )
+            .unwrap();
+        data
+    }
+
+    fn add_page(book: &mut BookDocument, id: &str, body: &str) {
+        book.manifest.add(ManifestItem::new(
+            id,
+            format!("text/{}.xhtml", id),
+            "application/xhtml+xml",
+            ManifestData::Xhtml(format!("<html><body>{}</body></html>", body)),
+        ));
+        book.spine.push(id, true);
+    }
+
+    fn add_image(book: &mut BookDocument, href: &str, width: u32, height: u32) {
+        let id = book.manifest.generate_id("img");
+        book.manifest.add(ManifestItem::new(
+            id,
+            href,
+            "image/png",
+            ManifestData::Binary(png(width, height)),
+        ));
+    }
+
+    fn ensure(book: &mut BookDocument, mode: EnsureCoverMode) {
+        let opts = ConversionOptions {
+            ensure_cover: Some(mode),
+            ..Default::default()
+        };
+        EnsureCover.apply(book, &opts).unwrap();
+    }
+
+    #[test]
+    fn test_single_image_becomes_cover() {
+        let mut book = BookDocument::new();
+        add_page(
+            &mut book,
+            "ch1",
+            r#"<p>Text</p><img src="../images/photo.png"/>"#,
+        );
+        add_image(&mut book, "images/photo.png", 400, 600);
+
+        ensure(&mut book, EnsureCoverMode::Image);
+
+        assert_eq!(book.guide.get("cover").unwrap().href, "images/photo.png");
+    }
+
+    #[test]
+    fn test_full_page_first_image_preferred() {
+        let mut book = BookDocument::new();
+        add_page(
+            &mut book,
+            "front",
+            r#"<div><img src="../images/front.png"/></div>"#,
+        );
+        add_page(
+            &mut book,
+            "ch1",
+            r#"<p>Text</p><img src="../images/map.png"/>"#,
+        );
+        add_image(&mut book, "images/front.png", 300, 450);
+        add_image(&mut book, "images/map.png", 1200, 900);
+
+        ensure(&mut book, EnsureCoverMode::Image);
+        assert_eq!(book.guide.get("cover").unwrap().href, "images/front.png");
+
+        // Without the image-only first page, the largest image wins
+        let mut book = BookDocument::new();
+        add_page(
+            &mut book,
+            "ch1",
+            r#"<p>Text</p><img src="../images/front.png"/>"#,
+        );
+        add_image(&mut book, "images/front.png", 300, 450);
+        add_image(&mut book, "images/map.png", 1200, 900);
+        add_image(&mut book, "images/icon.png", 16, 16);
+
+        ensure(&mut book, EnsureCoverMode::Image);
+        assert_eq!(book.guide.get("cover").unwrap().href, "images/map.png");
+    }
+
+    #[test]
+    fn test_existing_cover_kept() {
+        let mut book = BookDocument::new();
+        add_image(&mut book, "images/photo.png", 400, 600);
+        book.guide.add(GuideRef::new("cover", "Cover", "cover.jpg"));
+
+        ensure(&mut book, EnsureCoverMode::Image);
+
+        assert_eq!(book.guide.get("cover").unwrap().href, "cover.jpg");
+    }
+
+    #[test]
+    fn test_title_page_without_images() {
+        let mut book = BookDocument::new();
+        book.metadata.set_title("Plain Text");
+        add_page(&mut book, "ch1", "<p>Text</p>");
+        add_image(&mut book, "images/icon.png", 16, 16);
+
+        ensure(&mut book, EnsureCoverMode::Image);
+        assert!(book.guide.get("cover").is_none());
+
+        ensure(&mut book, EnsureCoverMode::TitlePage);
+        let href = &book.guide.get("cover").unwrap().href;
+        let page = book.manifest.by_href(href).unwrap();
+        assert_eq!(book.spine.items()[0].idref, page.id);
+        assert!(page.data.as_xhtml().unwrap().contains("Plain Text"));
+    }
+}
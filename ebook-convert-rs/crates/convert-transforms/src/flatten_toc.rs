@@ -0,0 +1,181 @@
+//! FlattenToc — collapses a nested TOC into a single level, or limits its
+//! depth.
+//!
+//! Some reading devices only show the top level of the navigation, or make
+//! nested levels awkward to reach. Entries are hoisted to the top level in
+//! depth-first (reading) order. With `flatten_toc_prefix`, child titles are
+//! prefixed with their ancestors' titles (`Part One: Chapter 1`) so the
+//! hierarchy stays recognizable.
+//!
+//! `max_toc_depth` is gentler: levels below the cutoff are dropped, or with
+//! `hoist_deep_toc` moved up to the deepest kept level.
+
+use convert_core::book::{BookDocument, TocEntry};
+use convert_core::error::Result;
+use convert_core::options::ConversionOptions;
+use convert_core::plugin::Transform;
+
+/// Separator between an ancestor's title and its child's title.
+const PREFIX_SEPARATOR: &str = ": ";
+
+/// Flattens `book.toc` when `flatten_toc` is set, and applies `max_toc_depth`.
+pub struct FlattenToc;
+
+impl Transform for FlattenToc {
+    fn name(&self) -> &str {
+        "FlattenToc"
+    }
+
+    fn should_run(&self, options: &ConversionOptions) -> bool {
+        options.flatten_toc || options.max_toc_depth.is_some()
+    }
+
+    fn apply(&self, book: &mut BookDocument, options: &ConversionOptions) -> Result<()> {
+        if !options.flatten_toc {
+            let depth = options.max_toc_depth.unwrap_or(usize::MAX).max(1);
+            if options.hoist_deep_toc {
+                book.toc.hoist_below(depth);
+            } else {
+                book.toc.limit_depth(depth);
+            }
+            log::info!("Limited TOC to {} levels", book.toc.depth());
+            book.toc.rationalize_play_orders();
+            return Ok(());
+        }
+
+        let entries = std::mem::take(&mut book.toc.entries);
+        let mut flat = Vec::new();
+        for entry in entries {
+            flatten_into(entry, None, options.flatten_toc_prefix, &mut flat);
+        }
+        log::info!("Flattened TOC to {} top-level entries", flat.len());
+        book.toc.entries = flat;
+        book.toc.rationalize_play_orders();
+        Ok(())
+    }
+}
+
+/// Push `entry` and then its descendants onto `out`, depth-first.
+fn flatten_into(mut entry: TocEntry, parent: Option<&str>, prefix: bool, out: &mut Vec<TocEntry>) {
+    let children = std::mem::take(&mut entry.children);
+    if prefix {
+        if let Some(parent) = parent {
+            entry.title = format!("{}{}{}", parent, PREFIX_SEPARATOR, entry.title);
+        }
+    }
+    let title = entry.title.clone();
+    out.push(entry);
+    for child in children {
+        flatten_into(child, Some(&title), prefix, out);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn two_level_book() -> BookDocument {
+        let mut book = BookDocument::new();
+        let mut part1 = TocEntry::new("Part One", "part1.xhtml");
+        part1.add_child(TocEntry::new("Chapter 1", "ch1.xhtml"));
+        part1.add_child(TocEntry::new("Chapter 2", "ch2.xhtml"));
+        let mut part2 = TocEntry::new("Part Two", "part2.xhtml");
+        part2.add_child(TocEntry::new("Chapter 3", "ch3.xhtml"));
+        book.toc.add(part1);
+        book.toc.add(part2);
+        book
+    }
+
+    fn titles(book: &BookDocument) -> Vec<&str> {
+        book.toc.entries.iter().map(|e| e.title.as_str()).collect()
+    }
+
+    #[test]
+    fn test_flatten_two_levels() {
+        let mut book = two_level_book();
+        let opts = ConversionOptions {
+            flatten_toc: true,
+            ..Default::default()
+        };
+        FlattenToc.apply(&mut book, &opts).unwrap();
+
+        assert_eq!(
+            titles(&book),
+            vec![
+                "Part One",
+                "Chapter 1",
+                "Chapter 2",
+                "Part Two",
+                "Chapter 3"
+            ]
+        );
+        assert!(book.toc.entries.iter().all(|e| e.children.is_empty()));
+        let hrefs: Vec<&str> = book.toc.entries.iter().map(|e| e.href.as_str()).collect();
+        assert_eq!(
+            hrefs,
+            vec![
+                "part1.xhtml",
+                "ch1.xhtml",
+                "ch2.xhtml",
+                "part2.xhtml",
+                "ch3.xhtml"
+            ]
+        );
+        assert_eq!(book.toc.entries[4].play_order, Some(5));
+    }
+
+    #[test]
+    fn test_max_toc_depth() {
+        let mut book = two_level_book();
+        book.toc.entries[0].children[0].add_child(TocEntry::new("Scene", "ch1.xhtml#s"));
+        assert_eq!(book.toc.depth(), 3);
+
+        let mut limited = book.clone();
+        let opts = ConversionOptions {
+            max_toc_depth: Some(2),
+            ..Default::default()
+        };
+        assert!(FlattenToc.should_run(&opts));
+        FlattenToc.apply(&mut limited, &opts).unwrap();
+        assert_eq!(limited.toc.depth(), 2);
+        assert_eq!(limited.toc.iter_depth_first().count(), 5);
+        assert!(limited.toc.iter_depth_first().all(|e| e.title != "Scene"));
+
+        let opts = ConversionOptions {
+            max_toc_depth: Some(2),
+            hoist_deep_toc: true,
+            ..Default::default()
+        };
+        FlattenToc.apply(&mut book, &opts).unwrap();
+        assert_eq!(book.toc.depth(), 2);
+        let part1: Vec<&str> = book.toc.entries[0]
+            .children
+            .iter()
+            .map(|e| e.title.as_str())
+            .collect();
+        assert_eq!(part1, vec!["Chapter 1", "Scene", "Chapter 2"]);
+        assert_eq!(book.toc.entries[1].play_order, Some(5));
+    }
+
+    #[test]
+    fn test_flatten_with_parent_prefix() {
+        let mut book = two_level_book();
+        let opts = ConversionOptions {
+            flatten_toc: true,
+            flatten_toc_prefix: true,
+            ..Default::default()
+        };
+        FlattenToc.apply(&mut book, &opts).unwrap();
+
+        assert_eq!(
+            titles(&book),
+            vec![
+                "Part One",
+                "Part One: Chapter 1",
+                "Part One: Chapter 2",
+                "Part Two",
+                "Part Two: Chapter 3"
+            ]
+        );
+    }
+}
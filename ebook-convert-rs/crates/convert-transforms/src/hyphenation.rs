@@ -0,0 +1,251 @@
+//! Hyphenation — inserts soft hyphens into long words for justified text.
+//!
+//! Break points come from the Liang hyphenation patterns for the book's
+//! language. Soft hyphens (U+00AD) are invisible unless a reader breaks the
+//! line there, so the text reads unchanged otherwise.
+
+use hypher::Lang;
+use rayon::prelude::*;
+
+use convert_core::book::{BookDocument, ManifestData};
+use convert_core::error::Result;
+use convert_core::options::ConversionOptions;
+use convert_core::plugin::Transform;
+
+use crate::smarten::{map_text_nodes, Node};
+
+/// Soft hyphen inserted at each break point.
+const SOFT_HYPHEN: &str = "\u{AD}";
+
+/// Elements whose text content is never hyphenated. Headings are left
+/// alone because their text doubles as TOC titles, which later transforms
+/// (e.g. SplitChapters) match against.
+const SKIPPED_ELEMENTS: &[&str] = &[
+    "pre", "code", "kbd", "samp", "script", "style", "head", "title", "h1", "h2", "h3", "h4", "h5",
+    "h6",
+];
+
+/// Inserts soft hyphens into words of at least `hyphenation_min_word_length`
+/// letters, using patterns for `metadata.language()`.
+pub struct Hyphenation;
+
+impl Transform for Hyphenation {
+    fn name(&self) -> &str {
+        "Hyphenation"
+    }
+
+    fn should_run(&self, options: &ConversionOptions) -> bool {
+        options.enable_hyphenation
+    }
+
+    fn apply(&self, book: &mut BookDocument, options: &ConversionOptions) -> Result<()> {
+        let language = book.metadata.language().unwrap_or("en");
+        let Some(lang) = hyphenation_lang(language) else {
+            log::warn!("No hyphenation patterns for language '{}'", language);
+            return Ok(());
+        };
+        let min_len = options.hyphenation_min_word_length;
+
+        // Collect XHTML items
+        let xhtml_items: Vec<(String, String)> = book
+            .manifest
+            .iter()
+            .filter(|item| item.is_xhtml())
+            .filter_map(|item| {
+                item.data
+                    .as_xhtml()
+                    .map(|x| (item.id.clone(), x.to_string()))
+            })
+            .collect();
+
+        // Process in parallel
+        let results: Vec<(String, String)> = xhtml_items
+            .into_par_iter()
+            .filter_map(|(id, xhtml)| {
+                let new_xhtml = hyphenate_xhtml(&xhtml, lang, min_len);
+                if new_xhtml != xhtml {
+                    Some((id, new_xhtml))
+                } else {
+                    None
+                }
+            })
+            .collect();
+
+        // Apply back sequentially
+        let count = results.len() as u32;
+        for (id, new_xhtml) in results {
+            if let Some(item) = book.manifest.by_id_mut(&id) {
+                item.data = ManifestData::Xhtml(new_xhtml);
+            }
+        }
+
+        if count > 0 {
+            log::info!("Hyphenated {} items", count);
+        }
+        Ok(())
+    }
+}
+
+/// Hyphenation patterns for a language tag such as `en` or `en-US`.
+fn hyphenation_lang(language: &str) -> Option<Lang> {
+    let primary = language.split(['-', '_']).next()?.to_ascii_lowercase();
+    let code: [u8; 2] = primary.as_bytes().try_into().ok()?;
+    Lang::from_iso(code)
+}
+
+/// Hyphenate the text nodes of an XHTML document, leaving markup alone.
+fn hyphenate_xhtml(xhtml: &str, lang: Lang, min_len: usize) -> String {
+    map_text_nodes(xhtml, SKIPPED_ELEMENTS, |node, out| {
+        if let Node::Text(text) = node {
+            hyphenate_text(text, lang, min_len, out);
+        }
+    })
+}
+
+/// Hyphenate a single text node into `out`, token by whitespace-separated token.
+fn hyphenate_text(text: &str, lang: Lang, min_len: usize, out: &mut String) {
+    for piece in text.split_inclusive(char::is_whitespace) {
+        let token = piece.trim_end_matches(char::is_whitespace);
+        if is_verbatim_token(token) {
+            out.push_str(token);
+        } else {
+            hyphenate_token(token, lang, min_len, out);
+        }
+        out.push_str(&piece[token.len()..]);
+    }
+}
+
+/// URLs, email addresses, paths and already-hyphenated words stay intact.
+fn is_verbatim_token(token: &str) -> bool {
+    token.contains("://")
+        || token.starts_with("www.")
+        || token.contains(['@', '/', '\\', '\u{AD}'])
+        || token.contains("&shy;")
+        || token.contains("&#173;")
+}
+
+/// Hyphenate each run of letters in a token, copying entities and punctuation.
+fn hyphenate_token(token: &str, lang: Lang, min_len: usize, out: &mut String) {
+    let mut rest = token;
+    while let Some(c) = rest.chars().next() {
+        if c == '&' {
+            // Character references are copied as-is
+            let end = rest.find(';').map(|i| i + 1).unwrap_or(rest.len());
+            out.push_str(&rest[..end]);
+            rest = &rest[end..];
+        } else if c.is_alphabetic() {
+            let end = rest
+                .find(|ch: char| !ch.is_alphabetic())
+                .unwrap_or(rest.len());
+            let word = &rest[..end];
+            if word.chars().count() >= min_len {
+                out.push_str(&hypher::hyphenate(word, lang).join(SOFT_HYPHEN));
+            } else {
+                out.push_str(word);
+            }
+            rest = &rest[end..];
+        } else {
+            out.push(c);
+            rest = &rest[c.len_utf8()..];
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use convert_core::book::ManifestItem;
+
+    fn hyphenate(xhtml: &str) -> String {
+        hyphenate_xhtml(xhtml, Lang::English, 6)
+    }
+
+    #[test]
+    fn test_long_word_gets_soft_hyphens() {
+        let out = hyphenate("<p>An extensive list</p>");
+        assert_eq!(out, "<p>An ex\u{AD}ten\u{AD}sive list</p>");
+    }
+
+    #[test]
+    fn test_short_word_unchanged() {
+        let out = hyphenate("<p>The cat sat on a table.</p>");
+        assert_eq!(out, "<p>The cat sat on a table.</p>");
+    }
+
+    #[test]
+    fn test_code_urls_and_entities_skipped() {
+        let xhtml = "<html><head><title>Extensive</title></head><body>\
+            <p><code>extensive_function</code> see https://example.com/extensive \
+            &amp;extensive&nbsp;words</p></body></html>";
+        let out = hyphenate(xhtml);
+        assert!(out.contains("<title>Extensive</title>"));
+        assert!(out.contains("<code>extensive_function</code>"));
+        assert!(out.contains("https://example.com/extensive"));
+        assert!(out.contains("&amp;ex\u{AD}ten\u{AD}sive&nbsp;words"));
+    }
+
+    #[test]
+    fn test_headings_survive_split_chapters() {
+        use crate::split_chapters::SplitChapters;
+        use convert_core::book::TocEntry;
+
+        let filler = "<p>An extensive paragraph of considerable length.</p>\n".repeat(200);
+        let xhtml = format!(
+            "<html><body><h1>Extraordinary Beginnings</h1>\n{}\
+             <h1>Unforgettable Conclusions</h1>\n{}</body></html>",
+            filler, filler
+        );
+        let mut book = BookDocument::new();
+        book.manifest.add(ManifestItem::new(
+            "content",
+            "content.xhtml",
+            "application/xhtml+xml",
+            ManifestData::Xhtml(xhtml),
+        ));
+        book.spine.push("content", true);
+        book.toc
+            .add(TocEntry::new("Extraordinary Beginnings", "content.xhtml"));
+        book.toc
+            .add(TocEntry::new("Unforgettable Conclusions", "content.xhtml"));
+
+        let opts = ConversionOptions {
+            enable_hyphenation: true,
+            ..Default::default()
+        };
+        Hyphenation.apply(&mut book, &opts).unwrap();
+        SplitChapters.apply(&mut book, &opts).unwrap();
+
+        assert_eq!(book.spine.len(), 2);
+        let hrefs: Vec<&str> = book.toc.entries.iter().map(|e| e.href.as_str()).collect();
+        assert_eq!(hrefs, ["content.xhtml", "content_ch1.xhtml"]);
+        for item in book.manifest.iter().filter(|item| item.is_xhtml()) {
+            let xhtml = item.data.as_xhtml().unwrap();
+            assert!(!xhtml.contains("Extraor\u{AD}") && !xhtml.contains("Un\u{AD}for"));
+            assert!(xhtml.contains("ex\u{AD}ten\u{AD}sive"));
+        }
+    }
+
+    #[test]
+    fn test_language_from_metadata() {
+        assert!(hyphenation_lang("en-US").is_some());
+        assert!(hyphenation_lang("de").is_some());
+        assert!(hyphenation_lang("xx").is_none());
+        assert!(hyphenation_lang("eng").is_none());
+
+        let mut book = BookDocument::new();
+        book.metadata.set("language", "en-GB");
+        book.manifest.add(ManifestItem::new(
+            "ch1",
+            "ch1.xhtml",
+            "application/xhtml+xml",
+            ManifestData::Xhtml("<html><body><p>extensive</p></body></html>".into()),
+        ));
+        let opts = ConversionOptions {
+            enable_hyphenation: true,
+            ..Default::default()
+        };
+        Hyphenation.apply(&mut book, &opts).unwrap();
+        let xhtml = book.manifest.by_id("ch1").unwrap().data.as_xhtml().unwrap();
+        assert!(xhtml.contains("ex\u{AD}ten\u{AD}sive"));
+    }
+}
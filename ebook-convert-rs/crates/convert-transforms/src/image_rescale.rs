@@ -7,9 +7,10 @@ use convert_core::book::{BookDocument, ManifestData};
 use convert_core::error::Result;
 use convert_core::options::ConversionOptions;
 use convert_core::plugin::Transform;
+use image::DynamicImage;
 use rayon::prelude::*;
 
-/// Rescales images to fit within the output profile's screen dimensions.
+/// Rescales images to fit within `max_image_size` or the output profile's limit.
 pub struct ImageRescale;
 
 impl Transform for ImageRescale {
@@ -18,14 +19,14 @@ impl Transform for ImageRescale {
     }
 
     fn should_run(&self, options: &ConversionOptions) -> bool {
-        !options.no_images && options.max_image_size.is_some()
+        !options.no_images && max_size(options).is_some()
     }
 
     fn apply(&self, book: &mut BookDocument, options: &ConversionOptions) -> Result<()> {
-        let (max_w, max_h) = options.max_image_size.unwrap_or((
-            options.output_profile.screen_width,
-            options.output_profile.screen_height,
-        ));
+        let Some((max_w, max_h)) = max_size(options) else {
+            return Ok(());
+        };
+        let quality = options.jpeg_quality.clamp(1, 100);
 
         // Collect image items that need processing: (index, data, media_type, href)
         let work: Vec<(usize, Vec<u8>, String, String)> = book
@@ -63,7 +64,7 @@ impl Transform for ImageRescale {
         let results: Vec<(usize, Option<Vec<u8>>)> = work
             .into_par_iter()
             .map(|(idx, data, media_type, href)| {
-                let resized = resize_image(&data, max_w, max_h, &media_type, &href);
+                let resized = resize_image(&data, max_w, max_h, &media_type, &href, quality);
                 (idx, resized)
             })
             .collect();
@@ -84,7 +85,15 @@ impl Transform for ImageRescale {
     }
 }
 
-/// Resize a single image if it exceeds max dimensions.
+/// The user's `max_image_size`, falling back to the output profile's limit.
+fn max_size(options: &ConversionOptions) -> Option<(u32, u32)> {
+    options
+        .max_image_size
+        .or(options.output_profile.max_image_size)
+}
+
+/// Resize a single image if it exceeds max dimensions, keeping its aspect
+/// ratio. Images that already fit are left alone, never enlarged.
 /// Uses fast_image_resize for SIMD-accelerated Lanczos3 resizing.
 fn resize_image(
     data: &[u8],
@@ -92,10 +101,15 @@ fn resize_image(
     max_h: u32,
     media_type: &str,
     href: &str,
+    quality: u8,
 ) -> Option<Vec<u8>> {
     use fast_image_resize::images::Image;
     use fast_image_resize::{IntoImageView, Resizer};
 
+    let Some(format) = image::ImageFormat::from_mime_type(media_type) else {
+        log::debug!("Not rescaling {}: unsupported type {}", href, media_type);
+        return None;
+    };
     let src_image = match image::load_from_memory(data) {
         Ok(img) => img,
         Err(e) => {
@@ -105,85 +119,49 @@ fn resize_image(
     };
 
     let (w, h) = (src_image.width(), src_image.height());
-    if w <= max_w && h <= max_h {
+    let (new_w, new_h) = fit_dimensions(w, h, max_w, max_h);
+    if (new_w, new_h) == (w, h) {
         return None; // No resize needed
     }
 
-    // Calculate new dimensions preserving aspect ratio
-    let (new_w, new_h) = fit_dimensions(w, h, max_w, max_h);
-
-    // Get pixel type from source
-    let pixel_type = match src_image.pixel_type() {
-        Some(pt) => pt,
-        None => {
-            log::warn!(
-                "Unsupported pixel type for {}, falling back to image crate",
-                href
-            );
-            return resize_fallback(&src_image, new_w, new_h, media_type, href);
-        }
+    // JPEG has no alpha channel; everything else keeps it
+    let src_image = match format {
+        image::ImageFormat::Jpeg => DynamicImage::ImageRgb8(src_image.to_rgb8()),
+        _ => DynamicImage::ImageRgba8(src_image.to_rgba8()),
     };
-
-    // Create destination image
+    let pixel_type = src_image.pixel_type()?;
     let mut dst_image = Image::new(new_w, new_h, pixel_type);
 
     // Resize using SIMD-accelerated Lanczos3
     let mut resizer = Resizer::new();
-    if let Err(e) = resizer.resize(&src_image, &mut dst_image, None) {
-        log::warn!(
-            "fast_image_resize failed for {} ({}x{} → {}x{}): {}, falling back",
-            href,
-            w,
-            h,
-            new_w,
-            new_h,
-            e
-        );
-        return resize_fallback(&src_image, new_w, new_h, media_type, href);
-    }
-
-    // Re-encode
-    let mut buf = Vec::new();
-    let format = if media_type == "image/png" {
-        image::ImageFormat::Png
-    } else {
-        image::ImageFormat::Jpeg
-    };
-
-    // Convert fast_image_resize Image back to DynamicImage for encoding
-    let color_type = src_image.color();
-    let raw_buffer = dst_image.into_vec();
-
-    match image::RgbaImage::from_raw(new_w, new_h, raw_buffer.clone()) {
-        Some(rgba) => {
-            let dynamic = image::DynamicImage::ImageRgba8(rgba);
-            if let Err(e) = dynamic.write_to(&mut std::io::Cursor::new(&mut buf), format) {
-                log::warn!("Failed to encode resized {}: {}", href, e);
-                return None;
-            }
-        }
-        None => {
-            // Try encoding raw buffer directly
-            use image::ImageEncoder;
-            let encoder_result = match format {
-                image::ImageFormat::Png => {
-                    let encoder =
-                        image::codecs::png::PngEncoder::new(std::io::Cursor::new(&mut buf));
-                    encoder.write_image(&raw_buffer, new_w, new_h, color_type.into())
+    let resized = match resizer.resize(&src_image, &mut dst_image, None) {
+        Ok(()) => {
+            let raw = dst_image.into_vec();
+            match format {
+                image::ImageFormat::Jpeg => {
+                    image::RgbImage::from_raw(new_w, new_h, raw).map(DynamicImage::ImageRgb8)
                 }
-                _ => {
-                    let encoder =
-                        image::codecs::jpeg::JpegEncoder::new(std::io::Cursor::new(&mut buf));
-                    encoder.write_image(&raw_buffer, new_w, new_h, color_type.into())
-                }
-            };
-            if let Err(e) = encoder_result {
-                log::warn!("Failed to encode resized {}: {}", href, e);
-                return None;
+                _ => image::RgbaImage::from_raw(new_w, new_h, raw).map(DynamicImage::ImageRgba8),
             }
         }
-    }
+        Err(e) => {
+            log::warn!(
+                "fast_image_resize failed for {} ({}x{} → {}x{}): {}, falling back",
+                href,
+                w,
+                h,
+                new_w,
+                new_h,
+                e
+            );
+            None
+        }
+    };
+    let resized = resized.unwrap_or_else(|| {
+        src_image.resize_exact(new_w, new_h, image::imageops::FilterType::Lanczos3)
+    });
 
+    let buf = encode(&resized, format, quality, href)?;
     log::info!(
         "Resized {} from {}x{} to {}x{} ({} → {} bytes)",
         href,
@@ -197,40 +175,43 @@ fn resize_image(
     Some(buf)
 }
 
-/// Fallback to image crate's resize when fast_image_resize can't handle the format.
-fn resize_fallback(
-    img: &image::DynamicImage,
-    new_w: u32,
-    new_h: u32,
-    media_type: &str,
+/// Encode `img` as `format`, at `quality` for JPEG.
+fn encode(
+    img: &DynamicImage,
+    format: image::ImageFormat,
+    quality: u8,
     href: &str,
 ) -> Option<Vec<u8>> {
-    let resized = img.resize(new_w, new_h, image::imageops::FilterType::Lanczos3);
-
     let mut buf = Vec::new();
-    let format = if media_type == "image/png" {
-        image::ImageFormat::Png
-    } else {
-        image::ImageFormat::Jpeg
+    let result = match format {
+        image::ImageFormat::Jpeg => {
+            let encoder = image::codecs::jpeg::JpegEncoder::new_with_quality(&mut buf, quality);
+            img.write_with_encoder(encoder)
+        }
+        _ => img.write_to(&mut std::io::Cursor::new(&mut buf), format),
     };
-
-    if let Err(e) = resized.write_to(&mut std::io::Cursor::new(&mut buf), format) {
-        log::warn!("Fallback resize encode failed for {}: {}", href, e);
+    if let Err(e) = result {
+        log::warn!("Failed to encode resized {}: {}", href, e);
         return None;
     }
-
-    log::info!("Resized {} to {}x{} (fallback)", href, new_w, new_h);
     Some(buf)
 }
 
-/// Calculate new dimensions that fit within max_w x max_h preserving aspect ratio.
+/// Dimensions that fit `w`x`h` within `max_w`x`max_h` with the same aspect
+/// ratio. The side that hits its limit first sets the scale, and the image
+/// is never scaled up.
 fn fit_dimensions(w: u32, h: u32, max_w: u32, max_h: u32) -> (u32, u32) {
-    let ratio_w = max_w as f64 / w as f64;
-    let ratio_h = max_h as f64 / h as f64;
-    let ratio = ratio_w.min(ratio_h);
-    let new_w = (w as f64 * ratio).round() as u32;
-    let new_h = (h as f64 * ratio).round() as u32;
-    (new_w.max(1), new_h.max(1))
+    if w <= max_w && h <= max_h {
+        return (w, h);
+    }
+    if w as u64 * max_h as u64 >= h as u64 * max_w as u64 {
+        // Width dominates
+        let new_h = (h as f64 * max_w as f64 / w as f64).round() as u32;
+        (max_w, new_h.clamp(1, max_h))
+    } else {
+        let new_w = (w as f64 * max_h as f64 / h as f64).round() as u32;
+        (new_w.clamp(1, max_w), max_h)
+    }
 }
 
 #[cfg(test)]
@@ -241,7 +222,9 @@ mod tests {
     fn test_fit_dimensions() {
         assert_eq!(fit_dimensions(2000, 1000, 1000, 800), (1000, 500));
         assert_eq!(fit_dimensions(500, 1600, 1000, 800), (250, 800));
-        assert_eq!(fit_dimensions(100, 100, 1000, 800), (800, 800)); // scales up (caller checks if resize needed)
+        assert_eq!(fit_dimensions(100, 100, 1000, 800), (100, 100));
+        assert_eq!(fit_dimensions(1200, 100, 1000, 800), (1000, 83));
+        assert_eq!(fit_dimensions(3000, 1, 1000, 1000), (1000, 1));
     }
 
     #[test]
@@ -259,7 +242,7 @@ mod tests {
             .unwrap();
 
         // Image is 2x2, max is 1000x1000 — should not resize
-        let result = resize_image(&buf, 1000, 1000, "image/png", "test.png");
+        let result = resize_image(&buf, 1000, 1000, "image/png", "test.png", 80);
         assert!(result.is_none()); // no resize needed
     }
 
@@ -273,7 +256,7 @@ mod tests {
             .unwrap();
 
         // Resize to max 50x50
-        let result = resize_image(&buf, 50, 50, "image/png", "test.png");
+        let result = resize_image(&buf, 50, 50, "image/png", "test.png", 80);
         assert!(result.is_some());
 
         // Verify the resized image
@@ -281,4 +264,67 @@ mod tests {
         assert!(resized.width() <= 50);
         assert!(resized.height() <= 50);
     }
+
+    #[test]
+    fn test_wide_image_keeps_aspect_ratio() {
+        let img = image::RgbImage::from_pixel(400, 100, image::Rgb([200, 30, 30]));
+        let mut buf = Vec::new();
+        image::DynamicImage::ImageRgb8(img)
+            .write_to(
+                &mut std::io::Cursor::new(&mut buf),
+                image::ImageFormat::Jpeg,
+            )
+            .unwrap();
+
+        let result = resize_image(&buf, 200, 200, "image/jpeg", "wide.jpg", 80).unwrap();
+        assert_eq!(
+            image::guess_format(&result).unwrap(),
+            image::ImageFormat::Jpeg
+        );
+        let resized = image::load_from_memory(&result).unwrap();
+        assert_eq!((resized.width(), resized.height()), (200, 50));
+    }
+
+    #[test]
+    fn test_tiny_image_untouched_by_transform() {
+        use convert_core::book::ManifestItem;
+
+        let mut buf = Vec::new();
+        image::DynamicImage::ImageRgba8(image::RgbaImage::new(16, 8))
+            .write_to(&mut std::io::Cursor::new(&mut buf), image::ImageFormat::Png)
+            .unwrap();
+        let mut book = BookDocument::new();
+        book.manifest.add(ManifestItem::new(
+            "icon",
+            "images/icon.png",
+            "image/png",
+            ManifestData::Binary(buf.clone()),
+        ));
+        let opts = ConversionOptions {
+            max_image_size: Some((800, 600)),
+            ..Default::default()
+        };
+        ImageRescale.apply(&mut book, &opts).unwrap();
+        assert_eq!(
+            book.manifest.by_id("icon").unwrap().data.as_binary(),
+            Some(&buf[..])
+        );
+    }
+
+    #[test]
+    fn test_profile_max_image_size() {
+        use convert_core::options::DeviceProfile;
+
+        let mut opts = ConversionOptions::default();
+        assert!(!ImageRescale.should_run(&opts));
+
+        // Kindle profile limits images to its screen when the user sets nothing
+        opts.output_profile = DeviceProfile::KindlePaperwhite.output_profile();
+        assert!(ImageRescale.should_run(&opts));
+        assert_eq!(max_size(&opts), Some((1236, 1648)));
+
+        // An explicit max_image_size wins
+        opts.max_image_size = Some((800, 600));
+        assert_eq!(max_size(&opts), Some((800, 600)));
+    }
 }
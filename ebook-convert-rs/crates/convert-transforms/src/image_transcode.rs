@@ -0,0 +1,255 @@
+//! ImageTranscode transform — converts large photographic PNG/BMP images to JPEG.
+//!
+//! Photos stored losslessly are often several times larger than the same
+//! image as JPEG. Images with transparency, small icons and flat-colour
+//! artwork (diagrams, screenshots) are left alone since JPEG would degrade
+//! them. Transcoded items get a `.jpg` href and every reference is rewritten.
+
+use std::collections::{HashMap, HashSet};
+
+use rayon::prelude::*;
+
+use convert_core::book::{BookDocument, ManifestData};
+use convert_core::error::Result;
+use convert_core::options::ConversionOptions;
+use convert_core::plugin::Transform;
+
+use crate::references::rewrite_references;
+
+/// Images no larger than this in either dimension count as icons.
+const ICON_MAX_DIMENSION: u32 = 64;
+
+/// Pixels sampled when deciding whether an image is photographic.
+const COLOR_SAMPLE_SIZE: usize = 4096;
+
+/// Distinct sampled colours above which an image counts as photographic.
+const PHOTO_MIN_COLORS: usize = 256;
+
+/// Transcodes opaque photographic PNG/BMP images above the size threshold to JPEG.
+pub struct ImageTranscode;
+
+impl Transform for ImageTranscode {
+    fn name(&self) -> &str {
+        "ImageTranscode"
+    }
+
+    fn should_run(&self, options: &ConversionOptions) -> bool {
+        !options.no_images && options.image_transcode_threshold.is_some()
+    }
+
+    fn apply(&self, book: &mut BookDocument, options: &ConversionOptions) -> Result<()> {
+        let Some(threshold) = options.image_transcode_threshold else {
+            return Ok(());
+        };
+        let quality = options.jpeg_quality.clamp(1, 100);
+
+        // Transcode candidates in parallel
+        let transcoded: Vec<(String, Vec<u8>)> = book
+            .manifest
+            .par_iter()
+            .filter(|item| matches!(item.media_type.as_str(), "image/png" | "image/bmp"))
+            .filter_map(|item| {
+                let data = item.data.as_binary()?;
+                if data.len() < threshold {
+                    return None;
+                }
+                let jpeg = transcode_to_jpeg(data, quality, &item.href)?;
+                Some((item.id.clone(), jpeg))
+            })
+            .collect();
+
+        if transcoded.is_empty() {
+            return Ok(());
+        }
+
+        // Apply back sequentially, renaming each item to a .jpg href
+        let mut renamed: HashMap<String, String> = HashMap::new();
+        for (id, jpeg) in transcoded {
+            let Some(old_href) = book.manifest.by_id(&id).map(|item| item.href.clone()) else {
+                continue;
+            };
+            let stem = match old_href.rfind('.') {
+                Some(dot) if !old_href[dot..].contains('/') => &old_href[..dot],
+                _ => old_href.as_str(),
+            };
+            let new_href = book.manifest.generate_href(stem, "jpg");
+            if !book.manifest.set_href(&id, &new_href) {
+                continue;
+            }
+            if let Some(item) = book.manifest.by_id_mut(&id) {
+                item.media_type = "image/jpeg".to_string();
+                item.data = ManifestData::Binary(jpeg);
+            }
+            log::debug!("Transcoded {} to {}", old_href, new_href);
+            renamed.insert(old_href, new_href);
+        }
+
+        rewrite_references(book, &renamed);
+
+        log::info!("Transcoded {} images to JPEG", renamed.len());
+        Ok(())
+    }
+}
+
+/// Decode an image and re-encode it as JPEG, unless it should stay lossless.
+fn transcode_to_jpeg(data: &[u8], quality: u8, href: &str) -> Option<Vec<u8>> {
+    let img = match image::load_from_memory(data) {
+        Ok(img) => img,
+        Err(e) => {
+            log::warn!("Failed to decode image {}: {}", href, e);
+            return None;
+        }
+    };
+
+    if img.width() <= ICON_MAX_DIMENSION && img.height() <= ICON_MAX_DIMENSION {
+        return None;
+    }
+    if has_transparency(&img) || !is_photographic(&img) {
+        return None;
+    }
+
+    let rgb = img.to_rgb8();
+    let mut buf = Vec::new();
+    let encoder = image::codecs::jpeg::JpegEncoder::new_with_quality(&mut buf, quality);
+    if let Err(e) = rgb.write_with_encoder(encoder) {
+        log::warn!("Failed to encode {} as JPEG: {}", href, e);
+        return None;
+    }
+
+    // Keep the original if JPEG would not actually save space
+    if buf.len() >= data.len() {
+        return None;
+    }
+    Some(buf)
+}
+
+/// Whether any pixel is not fully opaque.
+fn has_transparency(img: &image::DynamicImage) -> bool {
+    img.color().has_alpha() && img.to_rgba8().pixels().any(|p| p.0[3] < 255)
+}
+
+/// Whether a sample of pixels has enough distinct colours to look like a photo.
+fn is_photographic(img: &image::DynamicImage) -> bool {
+    let rgb = img.to_rgb8();
+    let pixels = rgb.as_raw().chunks_exact(3);
+    let step = (pixels.len() / COLOR_SAMPLE_SIZE).max(1);
+    let colors: HashSet<&[u8]> = pixels.step_by(step).collect();
+    colors.len() > PHOTO_MIN_COLORS
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use convert_core::book::{GuideRef, ManifestItem};
+    use std::io::Cursor;
+
+    /// Deterministic noisy image that looks photographic.
+    fn noise_image(size: u32, alpha: u8) -> Vec<u8> {
+        let mut seed: u32 = 12345;
+        let img = image::RgbaImage::from_fn(size, size, |_, _| {
+            seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12345);
+            let [r, g, b, _] = seed.to_be_bytes();
+            image::Rgba([r, g, b, alpha])
+        });
+        let mut buf = Vec::new();
+        let dynamic = if alpha == 255 {
+            image::DynamicImage::ImageRgb8(image::DynamicImage::ImageRgba8(img).to_rgb8())
+        } else {
+            image::DynamicImage::ImageRgba8(img)
+        };
+        dynamic
+            .write_to(&mut Cursor::new(&mut buf), image::ImageFormat::Png)
+            .unwrap();
+        buf
+    }
+
+    fn transcode_book(images: &[(&str, &str, Vec<u8>)]) -> BookDocument {
+        let mut book = BookDocument::new();
+        let mut body = String::new();
+        for (id, href, data) in images {
+            book.manifest.add(ManifestItem::new(
+                *id,
+                *href,
+                "image/png",
+                ManifestData::Binary(data.clone()),
+            ));
+            body.push_str(&format!(r#"<img src="../{}" alt=""/>"#, href));
+        }
+        book.manifest.add(ManifestItem::new(
+            "ch1",
+            "text/ch1.xhtml",
+            "application/xhtml+xml",
+            ManifestData::Xhtml(format!("<html><body>{}</body></html>", body)),
+        ));
+        book.spine.push("ch1", true);
+
+        let opts = ConversionOptions {
+            image_transcode_threshold: Some(10_000),
+            ..Default::default()
+        };
+        ImageTranscode.apply(&mut book, &opts).unwrap();
+        book
+    }
+
+    #[test]
+    fn test_large_opaque_png_becomes_jpeg() {
+        let book = transcode_book(&[("photo", "images/photo.png", noise_image(200, 255))]);
+        let item = book.manifest.by_id("photo").unwrap();
+        assert_eq!(item.media_type, "image/jpeg");
+        assert_eq!(item.href, "images/photo.jpg");
+        let data = item.data.as_binary().unwrap();
+        assert_eq!(image::guess_format(data).unwrap(), image::ImageFormat::Jpeg);
+        assert!(book.manifest.by_href("images/photo.jpg").is_some());
+
+        let xhtml = book.manifest.by_id("ch1").unwrap().data.as_xhtml().unwrap();
+        assert!(xhtml.contains(r#"<img src="../images/photo.jpg" alt=""/>"#));
+        assert!(!xhtml.contains("photo.png"));
+    }
+
+    #[test]
+    fn test_guide_reference_rewritten() {
+        let mut book = BookDocument::new();
+        book.manifest.add(ManifestItem::new(
+            "photo",
+            "photo.png",
+            "image/png",
+            ManifestData::Binary(noise_image(200, 255)),
+        ));
+        book.guide.add(GuideRef::new("cover", "Cover", "photo.png"));
+        let opts = ConversionOptions {
+            image_transcode_threshold: Some(0),
+            ..Default::default()
+        };
+        ImageTranscode.apply(&mut book, &opts).unwrap();
+        assert_eq!(book.guide.get("cover").unwrap().href, "photo.jpg");
+    }
+
+    #[test]
+    fn test_transparent_and_icon_pngs_kept() {
+        let book = transcode_book(&[
+            ("alpha", "images/alpha.png", noise_image(200, 128)),
+            ("icon", "images/icon.png", noise_image(48, 255)),
+        ]);
+        for id in ["alpha", "icon"] {
+            let item = book.manifest.by_id(id).unwrap();
+            assert_eq!(item.media_type, "image/png");
+            assert!(item.href.ends_with(".png"));
+        }
+    }
+
+    #[test]
+    fn test_flat_artwork_kept() {
+        let img = image::RgbImage::from_fn(400, 400, |x, _| {
+            if x < 200 {
+                image::Rgb([0, 0, 0])
+            } else {
+                image::Rgb([255, 255, 255])
+            }
+        });
+        let mut buf = Vec::new();
+        image::DynamicImage::ImageRgb8(img)
+            .write_to(&mut Cursor::new(&mut buf), image::ImageFormat::Bmp)
+            .unwrap();
+        assert!(transcode_to_jpeg(&buf, 80, "diagram.bmp").is_none());
+    }
+}
@@ -53,24 +53,31 @@ fn remove_first_image(book: &mut BookDocument) {
 
 /// Build and insert a jacket XHTML page at spine[0].
 fn insert_jacket(book: &mut BookDocument) {
+    let jacket_xhtml = jacket_document(book, true);
+
+    let jacket_id = book.manifest.generate_id("jacket");
+    let jacket_href = book.manifest.generate_href("jacket", "xhtml");
+
+    let jacket_item = ManifestItem::new(
+        &jacket_id,
+        &jacket_href,
+        "application/xhtml+xml",
+        ManifestData::Xhtml(jacket_xhtml),
+    );
+    book.manifest.add(jacket_item);
+    book.spine.insert(0, &jacket_id, true);
+
+    log::info!("Inserted metadata jacket page at spine[0]");
+}
+
+/// The jacket page as an XHTML document. Without `details` only the title
+/// and authors are shown; with them, a list of the series, publisher, date,
+/// language, ISBN, tags and rating follows, leaving out absent fields.
+pub(crate) fn jacket_document(book: &BookDocument, details: bool) -> String {
     let title = book.metadata.title().unwrap_or("Unknown Title").to_string();
     let authors = book.metadata.authors().join(", ");
-    let publisher = book.metadata.publisher().unwrap_or("").to_string();
-    let date = book.metadata.date().unwrap_or("").to_string();
     let description = book.metadata.description().unwrap_or("").to_string();
 
-    // Build series info
-    let series = book
-        .metadata
-        .get_first_value("series")
-        .unwrap_or("")
-        .to_string();
-    let series_index = book
-        .metadata
-        .get_first_value("series_index")
-        .unwrap_or("")
-        .to_string();
-
     let mut body_parts = Vec::new();
     body_parts.push(format!(
         r#"<h1 class="jacket-title">{}</h1>"#,
@@ -84,30 +91,23 @@ fn insert_jacket(book: &mut BookDocument) {
         ));
     }
 
-    if !series.is_empty() {
-        let series_text = if series_index.is_empty() {
-            escape_html(&series)
-        } else {
-            format!("{} #{}", escape_html(&series), escape_html(&series_index))
-        };
-        body_parts.push(format!(r#"<p class="jacket-series">{}</p>"#, series_text));
-    }
-
-    if !publisher.is_empty() {
-        body_parts.push(format!(
-            r#"<p class="jacket-publisher">{}</p>"#,
-            escape_html(&publisher)
-        ));
-    }
-
-    if !date.is_empty() {
+    let rows = if details {
+        jacket_rows(book)
+    } else {
+        Vec::new()
+    };
+    if !rows.is_empty() {
+        let rows: Vec<String> = rows
+            .iter()
+            .map(|(label, value)| format!("<dt>{}</dt><dd>{}</dd>", label, escape_html(value)))
+            .collect();
         body_parts.push(format!(
-            r#"<p class="jacket-date">{}</p>"#,
-            escape_html(&date)
+            "<dl class=\"jacket-details\">\n      {}\n    </dl>",
+            rows.join("\n      ")
         ));
     }
 
-    if !description.is_empty() {
+    if details && !description.is_empty() {
         body_parts.push(format!(
             r#"<div class="jacket-description">{}</div>"#,
             description // description may contain HTML, pass through
@@ -116,7 +116,7 @@ fn insert_jacket(book: &mut BookDocument) {
 
     let body_html = body_parts.join("\n    ");
 
-    let jacket_xhtml = format!(
+    format!(
         r#"<?xml version="1.0" encoding="UTF-8"?>
 <!DOCTYPE html PUBLIC "-//W3C//DTD XHTML 1.1//EN" "http://www.w3.org/TR/xhtml11/DTD/xhtml11.dtd">
 <html xmlns="http://www.w3.org/1999/xhtml">
@@ -125,10 +125,10 @@ fn insert_jacket(book: &mut BookDocument) {
   <style type="text/css">
     .jacket-title {{ font-size: 1.8em; text-align: center; margin: 1em 0 0.5em; }}
     .jacket-authors {{ font-size: 1.2em; text-align: center; margin: 0.5em 0; }}
-    .jacket-series {{ text-align: center; font-style: italic; margin: 0.5em 0; }}
-    .jacket-publisher {{ text-align: center; margin: 0.5em 0; }}
-    .jacket-date {{ text-align: center; color: #666; margin: 0.5em 0; }}
-    .jacket-description {{ margin: 1.5em 1em; }}
+    .jacket-details {{ margin: 1.5em 1em; }}
+    .jacket-details dt {{ float: left; clear: left; width: 6em; font-weight: bold; color: #666; }}
+    .jacket-details dd {{ margin: 0 0 0.3em 7em; }}
+    .jacket-description {{ margin: 1.5em 1em; clear: left; }}
   </style>
 </head>
 <body>
@@ -137,21 +137,84 @@ fn insert_jacket(book: &mut BookDocument) {
 </html>"#,
         title = escape_html(&title),
         body_html = body_html,
-    );
+    )
+}
 
-    let jacket_id = book.manifest.generate_id("jacket");
-    let jacket_href = book.manifest.generate_href("jacket", "xhtml");
+/// Label and text of each jacket detail row present in the metadata.
+fn jacket_rows(book: &BookDocument) -> Vec<(&'static str, String)> {
+    let metadata = &book.metadata;
+    let values = |term: &str| -> Vec<&str> {
+        metadata
+            .get(term)
+            .unwrap_or_default()
+            .iter()
+            .map(|item| item.value.trim())
+            .filter(|v| !v.is_empty())
+            .collect()
+    };
+    let first = |term: &str| values(term).first().map(|v| v.to_string());
 
-    let jacket_item = ManifestItem::new(
-        &jacket_id,
-        &jacket_href,
-        "application/xhtml+xml",
-        ManifestData::Xhtml(jacket_xhtml),
-    );
-    book.manifest.add(jacket_item);
-    book.spine.insert(0, &jacket_id, true);
+    let mut rows = Vec::new();
+    if let Some(series) = first("series") {
+        let series = match first("series_index") {
+            Some(index) => format!("{} #{}", series, index),
+            None => series,
+        };
+        rows.push(("Series", series));
+    }
+    if let Some(publisher) = first("publisher") {
+        rows.push(("Publisher", publisher));
+    }
+    if let Some(date) = first("date") {
+        rows.push(("Published", date));
+    }
+    if let Some(language) = first("language") {
+        rows.push(("Language", language));
+    }
+    if let Some(isbn) = metadata
+        .get("identifier")
+        .unwrap_or_default()
+        .iter()
+        .find_map(|item| isbn(&item.value))
+    {
+        rows.push(("ISBN", isbn));
+    }
+    let tags = values("subject");
+    if !tags.is_empty() {
+        rows.push(("Tags", tags.join(", ")));
+    }
+    if let Some(stars) = first("rating").and_then(|r| rating_stars(&r)) {
+        rows.push(("Rating", stars));
+    }
+    rows
+}
 
-    log::info!("Inserted metadata jacket page at spine[0]");
+/// The ISBN in an identifier such as `urn:isbn:978-0-00-000000-2`, if it is one.
+fn isbn(identifier: &str) -> Option<String> {
+    let value = identifier.trim();
+    let lower = value.to_ascii_lowercase();
+    let value = ["urn:isbn:", "isbn:", "isbn"]
+        .iter()
+        .find_map(|prefix| lower.starts_with(prefix).then(|| &value[prefix.len()..]))
+        .unwrap_or(value)
+        .trim();
+    let digits: String = value.chars().filter(|c| !matches!(c, '-' | ' ')).collect();
+    let valid = match digits.len() {
+        13 => digits.chars().all(|c| c.is_ascii_digit()),
+        10 => {
+            digits[..9].chars().all(|c| c.is_ascii_digit())
+                && digits.ends_with(|c: char| c.is_ascii_digit() || c == 'X' || c == 'x')
+        }
+        _ => false,
+    };
+    valid.then(|| value.to_string())
+}
+
+/// Stars for a Calibre rating, which runs from 0 to 10 in half-star steps.
+fn rating_stars(rating: &str) -> Option<String> {
+    let rating: f64 = rating.parse().ok()?;
+    let stars = (rating.clamp(0.0, 10.0) / 2.0).round() as usize;
+    (stars > 0).then(|| "\u{2605}".repeat(stars))
 }
 
 /// Simple HTML entity escaping for text content.
@@ -203,6 +266,33 @@ mod tests {
         assert!(content.contains("Test Author"));
     }
 
+    #[test]
+    fn test_jacket_details() {
+        let mut book = BookDocument::new();
+        book.metadata.set_title("Test Book");
+        book.metadata.set("series", "Saga & Sons");
+        book.metadata.set("series_index", "2");
+        book.metadata.set("publisher", "Acme");
+        book.metadata.add("identifier", "urn:uuid:1234");
+        book.metadata
+            .add("identifier", "urn:isbn:978-0-306-40615-7");
+        book.metadata.add("subject", "Fantasy");
+        book.metadata.add("subject", "Adventure");
+        book.metadata.set("rating", "8");
+
+        let content = jacket_document(&book, true);
+        assert!(content.contains("<dt>Series</dt><dd>Saga &amp; Sons #2</dd>"));
+        assert!(content.contains("<dt>Publisher</dt><dd>Acme</dd>"));
+        assert!(content.contains("<dt>ISBN</dt><dd>978-0-306-40615-7</dd>"));
+        assert!(content.contains("<dt>Tags</dt><dd>Fantasy, Adventure</dd>"));
+        assert!(content.contains("<dt>Rating</dt><dd>\u{2605}\u{2605}\u{2605}\u{2605}</dd>"));
+        // Absent fields get no row
+        assert!(!content.contains("<dt>Published</dt>") && !content.contains("<dt>Language</dt>"));
+
+        // The cover page variant leaves the details out
+        assert!(!jacket_document(&book, false).contains("jacket-details\""));
+    }
+
     #[test]
     fn test_remove_first_image() {
         let mut book = BookDocument::new();
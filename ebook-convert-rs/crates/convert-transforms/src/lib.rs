@@ -1,16 +1,33 @@
 //! Ebook conversion transforms — applied between input and output.
 
 pub mod clean_guide;
+pub mod clean_spacing;
+pub mod cover_rescale;
 pub mod css_flattener;
 pub mod data_url;
+pub mod dedup_images;
 pub mod detect_structure;
+pub mod drop_caps;
+pub mod ensure_cover;
+pub mod flatten_toc;
+pub mod hyphenation;
 pub mod image_rescale;
+pub mod image_transcode;
 pub mod jacket;
 pub mod linearize_tables;
 pub mod manifest_trimmer;
 pub mod merge_metadata;
+pub mod normalize_headings;
 pub mod page_margin;
+pub mod pretty_xhtml;
+pub mod reading_direction;
+mod references;
+pub mod remove_blank;
+pub mod remove_tiny_images;
+pub mod smarten;
+pub mod spine_order;
 pub mod split_chapters;
+pub mod toc_from_headings;
 pub mod unsmarten;
 
 use convert_core::plugin::Transform;
@@ -22,28 +39,60 @@ use convert_core::plugin::Transform;
 ///  1. DataURL              (always)
 ///  2. CleanGuide           (always)
 ///  3. MergeMetadata        (always)
-///  4. DetectStructure      (always)
-///  5. Jacket               (conditional: insert_metadata || remove_first_image)
-///  6. LinearizeTables      (conditional: linearize_tables)
-///  7. UnsmartenPunctuation (conditional: unsmarten_punctuation)
-///  8. CSSFlattener         (always)
-///  9. PageMargin           (always)
-/// 10. ImageRescale         (always)
-/// 11. SplitChapters        (always, splits large XHTML at heading boundaries)
-/// 12. ManifestTrimmer      (always)
+///  4. SpineOrder           (conditional: spine_order)
+///  5. NormalizeHeadings    (conditional: normalize_headings)
+///  6. RemoveBlank          (conditional: remove_blank)
+///  7. TocFromHeadings      (always, when the TOC has at most one entry)
+///  8. DetectStructure      (always)
+///  9. EnsureCover          (conditional: ensure_cover)
+/// 10. Jacket               (conditional: insert_metadata || remove_first_image)
+/// 11. LinearizeTables      (conditional: linearize_tables)
+/// 12. SmartenPunctuation   (conditional: smarten_punctuation)
+/// 13. UnsmartenPunctuation (conditional: unsmarten_punctuation)
+/// 14. CleanSpacing         (conditional: clean_spacing)
+/// 15. DropCaps             (conditional: drop_caps)
+/// 16. Hyphenation          (conditional: enable_hyphenation)
+/// 17. CSSFlattener         (always)
+/// 18. PageMargin           (always)
+/// 19. RemoveTinyImages     (conditional: min_image_dimension)
+/// 20. DedupImages          (if dedup_images)
+/// 21. ImageRescale         (always)
+/// 22. ImageTranscode       (conditional: image_transcode_threshold)
+/// 23. CoverRescale         (conditional: cover_fit)
+/// 24. SplitChapters        (always, splits large XHTML at heading boundaries)
+/// 25. ReadingDirection     (always, marks right-to-left books)
+/// 26. FlattenToc           (conditional: flatten_toc || max_toc_depth)
+/// 27. ManifestTrimmer      (always)
+/// 28. PrettyXhtml          (conditional: pretty_xhtml)
 pub fn standard_transforms() -> Vec<Box<dyn Transform>> {
     vec![
         Box::new(data_url::DataUrl),
         Box::new(clean_guide::CleanGuide),
         Box::new(merge_metadata::MergeMetadata),
+        Box::new(spine_order::SpineOrder),
+        Box::new(normalize_headings::NormalizeHeadings),
+        Box::new(remove_blank::RemoveBlank),
+        Box::new(toc_from_headings::TocFromHeadings),
         Box::new(detect_structure::DetectStructure),
+        Box::new(ensure_cover::EnsureCover),
         Box::new(jacket::Jacket),
         Box::new(linearize_tables::LinearizeTables),
+        Box::new(smarten::SmartenPunctuation),
         Box::new(unsmarten::UnsmartenPunctuation),
+        Box::new(clean_spacing::CleanSpacing),
+        Box::new(drop_caps::DropCaps),
+        Box::new(hyphenation::Hyphenation),
         Box::new(css_flattener::CssFlattener),
         Box::new(page_margin::PageMargin),
+        Box::new(remove_tiny_images::RemoveTinyImages),
+        Box::new(dedup_images::DedupImages),
         Box::new(image_rescale::ImageRescale),
+        Box::new(image_transcode::ImageTranscode),
+        Box::new(cover_rescale::CoverRescale),
         Box::new(split_chapters::SplitChapters),
+        Box::new(reading_direction::ReadingDirection),
+        Box::new(flatten_toc::FlattenToc),
         Box::new(manifest_trimmer::ManifestTrimmer),
+        Box::new(pretty_xhtml::PrettyXhtml),
     ]
 }
@@ -1,4 +1,10 @@
 //! LinearizeTables — converts HTML table markup to divs for devices without table support.
+//!
+//! Tags are rewritten in a single pass with a stack of open table elements,
+//! so a table nested inside a cell becomes its own `linearized-table` block
+//! within that cell's div. Omitted end tags (`<td>` without `</td>`) are
+//! closed implicitly and stray end tags are dropped, so every emitted
+//! `<div>` is balanced.
 
 use rayon::prelude::*;
 
@@ -8,6 +14,8 @@ use convert_core::options::ConversionOptions;
 use convert_core::plugin::Transform;
 use regex::Regex;
 
+use crate::smarten::{nodes, tag_name, Node};
+
 /// Replaces `<table>`, `<tr>`, `<td>`, `<th>` elements with styled `<div>`s
 /// for e-readers that lack table rendering support.
 pub struct LinearizeTables;
@@ -22,24 +30,7 @@ impl Transform for LinearizeTables {
     }
 
     fn apply(&self, book: &mut BookDocument, _options: &ConversionOptions) -> Result<()> {
-        let table_open = Regex::new(r"(?i)<table[^>]*>").unwrap();
-        let table_close = Regex::new(r"(?i)</table\s*>").unwrap();
-        let tr_open = Regex::new(r"(?i)<tr[^>]*>").unwrap();
-        let tr_close = Regex::new(r"(?i)</tr\s*>").unwrap();
-        let td_open = Regex::new(r"(?i)<td[^>]*>").unwrap();
-        let td_close = Regex::new(r"(?i)</td\s*>").unwrap();
-        let th_open = Regex::new(r"(?i)<th[^>]*>").unwrap();
-        let th_close = Regex::new(r"(?i)</th\s*>").unwrap();
-        let thead_open = Regex::new(r"(?i)<thead[^>]*>").unwrap();
-        let thead_close = Regex::new(r"(?i)</thead\s*>").unwrap();
-        let tbody_open = Regex::new(r"(?i)<tbody[^>]*>").unwrap();
-        let tbody_close = Regex::new(r"(?i)</tbody\s*>").unwrap();
-        let tfoot_open = Regex::new(r"(?i)<tfoot[^>]*>").unwrap();
-        let tfoot_close = Regex::new(r"(?i)</tfoot\s*>").unwrap();
-        let caption_open = Regex::new(r"(?i)<caption[^>]*>").unwrap();
-        let caption_close = Regex::new(r"(?i)</caption\s*>").unwrap();
-        let colgroup = Regex::new(r"(?i)</?colgroup[^>]*>").unwrap();
-        let col_tag = Regex::new(r"(?i)<col[^>]*>").unwrap();
+        let id_re = Regex::new(r#"(?i)\sid\s*=\s*("[^"]*"|'[^']*')"#).unwrap();
 
         // Collect XHTML items that contain tables
         let xhtml_items: Vec<(String, String)> = book
@@ -60,44 +51,7 @@ impl Transform for LinearizeTables {
         // Process in parallel (Regex is Send + Sync)
         let results: Vec<(String, String)> = xhtml_items
             .into_par_iter()
-            .map(|(id, xhtml)| {
-                let mut s = xhtml;
-                s = table_open
-                    .replace_all(&s, r#"<div class="linearized-table">"#)
-                    .to_string();
-                s = table_close.replace_all(&s, "</div>").to_string();
-                s = tr_open
-                    .replace_all(&s, r#"<div class="linearized-row">"#)
-                    .to_string();
-                s = tr_close.replace_all(&s, "</div>").to_string();
-                s = td_open
-                    .replace_all(&s, r#"<div class="linearized-cell">"#)
-                    .to_string();
-                s = td_close.replace_all(&s, "</div>").to_string();
-                s = th_open
-                    .replace_all(&s, r#"<div class="linearized-cell linearized-header">"#)
-                    .to_string();
-                s = th_close.replace_all(&s, "</div>").to_string();
-                s = thead_open
-                    .replace_all(&s, r#"<div class="linearized-thead">"#)
-                    .to_string();
-                s = thead_close.replace_all(&s, "</div>").to_string();
-                s = tbody_open
-                    .replace_all(&s, r#"<div class="linearized-tbody">"#)
-                    .to_string();
-                s = tbody_close.replace_all(&s, "</div>").to_string();
-                s = tfoot_open
-                    .replace_all(&s, r#"<div class="linearized-tfoot">"#)
-                    .to_string();
-                s = tfoot_close.replace_all(&s, "</div>").to_string();
-                s = caption_open
-                    .replace_all(&s, r#"<div class="linearized-caption">"#)
-                    .to_string();
-                s = caption_close.replace_all(&s, "</div>").to_string();
-                s = colgroup.replace_all(&s, "").to_string();
-                s = col_tag.replace_all(&s, "").to_string();
-                (id, s)
-            })
+            .map(|(id, xhtml)| (id, linearize_xhtml(&xhtml, &id_re)))
             .collect();
 
         // Apply back sequentially
@@ -115,6 +69,107 @@ impl Transform for LinearizeTables {
     }
 }
 
+/// Table elements that become divs, with the class each one gets.
+fn div_class(name: &str) -> Option<&'static str> {
+    match name {
+        "table" => Some("linearized-table"),
+        "tr" => Some("linearized-row"),
+        "td" => Some("linearized-cell"),
+        "th" => Some("linearized-cell linearized-header"),
+        "thead" => Some("linearized-thead"),
+        "tbody" => Some("linearized-tbody"),
+        "tfoot" => Some("linearized-tfoot"),
+        "caption" => Some("linearized-caption"),
+        _ => None,
+    }
+}
+
+/// Open elements that a new `name` start tag implicitly closes, as HTML
+/// does for omitted end tags. Never reaches past the enclosing table.
+fn implicitly_closed(name: &str) -> &'static [&'static str] {
+    match name {
+        "td" | "th" => &["td", "th"],
+        "tr" => &["td", "th", "tr"],
+        "thead" | "tbody" | "tfoot" => &["td", "th", "tr", "thead", "tbody", "tfoot"],
+        _ => &[],
+    }
+}
+
+/// Rewrite the table markup of an XHTML document as nested divs.
+fn linearize_xhtml(xhtml: &str, id_re: &Regex) -> String {
+    let mut out = String::with_capacity(xhtml.len() + xhtml.len() / 8);
+    // Open table elements, innermost last
+    let mut open: Vec<String> = Vec::new();
+
+    for node in nodes(xhtml) {
+        let tag = match node {
+            Node::Tag(tag) if !tag.starts_with("<!--") => tag,
+            Node::Tag(other) | Node::Text(other) => {
+                out.push_str(other);
+                continue;
+            }
+        };
+        let (name, closing) = tag_name(tag);
+
+        if name == "colgroup" || name == "col" {
+            continue;
+        }
+        if name == "body" && closing {
+            close_all(&mut open, &mut out);
+            out.push_str(tag);
+            continue;
+        }
+        let Some(class) = div_class(&name) else {
+            out.push_str(tag);
+            continue;
+        };
+
+        if closing {
+            // A stray end tag for an element that isn't open is dropped
+            if let Some(pos) = open.iter().rposition(|n| *n == name) {
+                while open.len() > pos {
+                    open.pop();
+                    out.push_str("</div>");
+                }
+            }
+            continue;
+        }
+
+        let closes = implicitly_closed(&name);
+        while open.last().is_some_and(|n| closes.contains(&n.as_str())) {
+            open.pop();
+            out.push_str("</div>");
+        }
+
+        out.push_str("<div class=\"");
+        out.push_str(class);
+        if name == "table" && open.iter().any(|n| n == "table") {
+            out.push_str(" linearized-nested");
+        }
+        out.push('"');
+        if let Some(cap) = id_re.captures(tag) {
+            out.push_str(" id=");
+            out.push_str(&cap[1]);
+        }
+        if tag.ends_with("/>") {
+            out.push_str("></div>");
+        } else {
+            out.push('>');
+            open.push(name);
+        }
+    }
+
+    close_all(&mut open, &mut out);
+    out
+}
+
+/// Close every open table div.
+fn close_all(open: &mut Vec<String>, out: &mut String) {
+    for _ in open.drain(..) {
+        out.push_str("</div>");
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -205,4 +260,81 @@ mod tests {
         let content = book.manifest.by_id("ch1").unwrap().data.as_xhtml().unwrap();
         assert_eq!(content, &xhtml);
     }
+
+    /// Every `<div>` and `<p>` start tag has a matching end tag, properly nested.
+    fn is_balanced(html: &str) -> bool {
+        let tag_re = Regex::new(r"<(/?)(div|p)\b[^>]*?(/?)>").unwrap();
+        let mut stack = Vec::new();
+        for cap in tag_re.captures_iter(html) {
+            if &cap[3] == "/" {
+                continue;
+            }
+            if &cap[1] == "/" {
+                if stack.pop().as_deref() != Some(&cap[2]) {
+                    return false;
+                }
+            } else {
+                stack.push(cap[2].to_string());
+            }
+        }
+        stack.is_empty()
+    }
+
+    fn linearize(xhtml: &str) -> String {
+        let mut book = BookDocument::new();
+        book.manifest.add(ManifestItem::new(
+            "ch1",
+            "ch1.xhtml",
+            "application/xhtml+xml",
+            ManifestData::Xhtml(xhtml.to_string()),
+        ));
+        let opts = ConversionOptions {
+            linearize_tables: true,
+            ..Default::default()
+        };
+        LinearizeTables.apply(&mut book, &opts).unwrap();
+        let content = book.manifest.by_id("ch1").unwrap().data.as_xhtml().unwrap();
+        content.to_string()
+    }
+
+    #[test]
+    fn test_linearize_nested_table() {
+        let content = linearize(
+            r#"<html><body><table id="outer"><tbody><tr><td><p>A</p><table><tr><th>B</th><td>C</td></tr></table><p>D</p></td><td>E</td></tr></tbody></table><p>After</p></body></html>"#,
+        );
+
+        assert!(!content.contains("<table"));
+        assert!(!content.contains("<td"));
+        assert!(is_balanced(&content), "{}", content);
+        assert!(content.contains(r#"<div class="linearized-table" id="outer">"#));
+        assert!(content.contains(r#"<div class="linearized-table linearized-nested">"#));
+
+        let text = Regex::new(r"<[^>]+>").unwrap().replace_all(&content, "");
+        assert_eq!(text, "ABCDEAfter");
+        // The inner table sits inside the first outer cell
+        let inner = content.find("linearized-nested").unwrap();
+        let second_cell = content.find(">E<").unwrap();
+        assert!(inner < second_cell);
+    }
+
+    #[test]
+    fn test_linearize_omitted_end_tags() {
+        let content = linearize(
+            "<html><body><table><tr><td>A<td>B<tr><td>C</table></td></tr><p>x</p></body></html>",
+        );
+        assert!(is_balanced(&content), "{}", content);
+        assert_eq!(content.matches("linearized-row").count(), 2);
+        assert_eq!(content.matches("linearized-cell").count(), 3);
+        assert!(content.ends_with("<p>x</p></body></html>"));
+    }
+
+    #[test]
+    fn test_linearize_leaves_similar_tags() {
+        let content = linearize(
+            r#"<html><body><table><thead><tr><th>H</th></tr></thead></table><video><track src="a.vtt"/></video></body></html>"#,
+        );
+        assert!(content.contains(r#"<div class="linearized-thead">"#));
+        assert!(content.contains(r#"<track src="a.vtt"/>"#));
+        assert!(is_balanced(&content), "{}", content);
+    }
 }
@@ -44,6 +44,29 @@ impl Transform for ManifestTrimmer {
             }
         }
 
+        // Media overlays, and the audio and documents their SMIL files point at
+        for item in book.manifest.iter() {
+            if let Some(ref overlay) = item.media_overlay {
+                referenced.insert(overlay.clone());
+            }
+        }
+        let smil_src_re = Regex::new(r#"\bsrc\s*=\s*["']([^"']+)["']"#).unwrap();
+        for item in book.manifest.iter() {
+            if item.media_type != "application/smil+xml" || !referenced.contains(&item.id) {
+                continue;
+            }
+            let Some(bytes) = item.data.as_binary() else {
+                continue;
+            };
+            let smil = String::from_utf8_lossy(bytes);
+            for cap in smil_src_re.captures_iter(&smil) {
+                let href = convert_utils::href::resolve(&item.href, &cap[1]);
+                if let Some(ref_item) = book.manifest.by_href(&href) {
+                    referenced.insert(ref_item.id.clone());
+                }
+            }
+        }
+
         // Scan XHTML and CSS content for referenced resources in parallel
         let href_re = Regex::new(r#"(?:src|href)\s*=\s*["']([^"']+)["']"#).unwrap();
         let url_re = Regex::new(r#"url\s*\(\s*['"]?([^'")\s]+)['"]?\s*\)"#).unwrap();
@@ -156,4 +179,47 @@ mod tests {
         assert!(book.manifest.by_id("style").is_some());
         assert!(book.manifest.by_id("orphan").is_none());
     }
+
+    #[test]
+    fn test_keeps_media_overlays() {
+        let mut book = BookDocument::new();
+        let mut ch1 = ManifestItem::new(
+            "ch1",
+            "text/ch1.xhtml",
+            "application/xhtml+xml",
+            ManifestData::Xhtml("<html><body><p id=\"s1\">Hi</p></body></html>".to_string()),
+        );
+        ch1.media_overlay = Some("ch1_overlay".to_string());
+        book.manifest.add(ch1);
+        book.spine.push("ch1", true);
+        book.manifest.add(ManifestItem::new(
+            "ch1_overlay",
+            "smil/ch1.smil",
+            "application/smil+xml",
+            ManifestData::Binary(
+                br#"<smil><par><text src="../text/ch1.xhtml#s1"/><audio src="../audio/ch1.mp3"/></par></smil>"#
+                    .to_vec(),
+            ),
+        ));
+        book.manifest.add(ManifestItem::new(
+            "ch1_audio",
+            "audio/ch1.mp3",
+            "audio/mpeg",
+            ManifestData::Binary(vec![0]),
+        ));
+        book.manifest.add(ManifestItem::new(
+            "orphan_audio",
+            "audio/unused.mp3",
+            "audio/mpeg",
+            ManifestData::Binary(vec![0]),
+        ));
+
+        ManifestTrimmer
+            .apply(&mut book, &ConversionOptions::default())
+            .unwrap();
+
+        assert!(book.manifest.by_id("ch1_overlay").is_some());
+        assert!(book.manifest.by_id("ch1_audio").is_some());
+        assert!(book.manifest.by_id("orphan_audio").is_none());
+    }
 }
@@ -1,11 +1,17 @@
-//! MergeMetadata transform — ensures metadata is complete.
+//! MergeMetadata transform — applies the user's cover and ensures metadata
+//! is complete. Title, author and other overrides are applied by the
+//! pipeline before any transform runs.
 
-use convert_core::book::BookDocument;
-use convert_core::error::Result;
+use std::path::Path;
+
+use image::ImageFormat;
+
+use convert_core::book::{BookDocument, GuideRef, ManifestData, ManifestItem};
+use convert_core::error::{ConvertError, Result};
 use convert_core::options::ConversionOptions;
 use convert_core::plugin::Transform;
 
-/// Ensures the book has required metadata fields.
+/// Applies the cover given in the options, then fills required fields.
 pub struct MergeMetadata;
 
 impl Transform for MergeMetadata {
@@ -13,7 +19,11 @@ impl Transform for MergeMetadata {
         "MergeMetadata"
     }
 
-    fn apply(&self, book: &mut BookDocument, _options: &ConversionOptions) -> Result<()> {
+    fn apply(&self, book: &mut BookDocument, options: &ConversionOptions) -> Result<()> {
+        if let Some(ref cover) = options.cover {
+            replace_cover(book, cover)?;
+        }
+
         // Ensure title exists
         if book.metadata.title().is_none() {
             book.metadata.set_title("Untitled");
@@ -34,6 +44,92 @@ impl Transform for MergeMetadata {
     }
 }
 
+/// Make the image at `path` the book's cover.
+///
+/// The guide `cover` reference is pointed at the new image. A cover page
+/// the input had in the spine is replaced by one showing the new image; an
+/// old cover image left unreferenced is dropped later by ManifestTrimmer.
+fn replace_cover(book: &mut BookDocument, path: &Path) -> Result<()> {
+    let data = std::fs::read(path).map_err(|e| {
+        ConvertError::Other(format!("Cannot read cover image {}: {}", path.display(), e))
+    })?;
+    let format = image::guess_format(&data)
+        .ok()
+        .filter(|f| {
+            matches!(
+                f,
+                ImageFormat::Jpeg | ImageFormat::Png | ImageFormat::Gif | ImageFormat::WebP
+            )
+        })
+        .ok_or_else(|| {
+            ConvertError::Other(format!(
+                "Unsupported cover image {}: expected JPEG, PNG, GIF or WebP",
+                path.display()
+            ))
+        })?;
+    image::load_from_memory_with_format(&data, format).map_err(|e| {
+        ConvertError::Other(format!("Invalid cover image {}: {}", path.display(), e))
+    })?;
+
+    // Drop the input's cover page, remembering where it sat in the spine
+    let old_page = book
+        .guide
+        .get("cover")
+        .map(|g| g.href.split('#').next().unwrap_or_default().to_string())
+        .and_then(|href| book.manifest.by_href(&href))
+        .filter(|item| item.is_xhtml())
+        .map(|item| (item.id.clone(), item.href.clone()));
+    let page_pos = old_page.as_ref().and_then(|(id, _)| {
+        let pos = book.spine.iter().position(|s| s.idref == *id);
+        book.spine.remove(id);
+        book.manifest.remove_by_id(id);
+        pos
+    });
+
+    let id = book.manifest.generate_id("cover");
+    let href = book
+        .manifest
+        .generate_href("cover", format.extensions_str()[0]);
+    book.manifest.add(ManifestItem::new(
+        &id,
+        &href,
+        format.to_mime_type(),
+        ManifestData::Binary(data),
+    ));
+    book.guide.add(GuideRef::new("cover", "Cover", &href));
+
+    if let Some(pos) = page_pos {
+        let page_id = book.manifest.generate_id("titlepage");
+        let page_href = book.manifest.generate_href("titlepage", "xhtml");
+        let body = format!(
+            r#"<div style="text-align: center"><img src="{}" alt="Cover" style="max-width: 100%; max-height: 100%"/></div>"#,
+            convert_utils::href::relative(&page_href, &href)
+        );
+        let language = book.metadata.language().unwrap_or("en").to_string();
+        book.manifest.add(ManifestItem::new(
+            &page_id,
+            &page_href,
+            "application/xhtml+xml",
+            ManifestData::Xhtml(convert_utils::xml::xhtml11_document(
+                "Cover", &language, None, &body,
+            )),
+        ));
+        book.spine.insert(pos, &page_id, true);
+
+        let old_href = old_page.map(|(_, href)| href).unwrap_or_default();
+        if let Some(title_page) = book.guide.get("title-page") {
+            if title_page.href.split('#').next() == Some(old_href.as_str()) {
+                let title = title_page.title.clone();
+                book.guide
+                    .add(GuideRef::new("title-page", title, &page_href));
+            }
+        }
+    }
+
+    log::info!("Cover replaced with {}", path.display());
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -63,4 +159,99 @@ mod tests {
         assert_eq!(book.metadata.language(), Some("fr"));
         assert_eq!(book.uid.as_deref(), Some("existing-uid"));
     }
+
+    fn write_png(name: &str) -> std::path::PathBuf {
+        let path = std::env::temp_dir().join(name);
+        image::RgbImage::new(4, 6).save(&path).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_cover_option_replaces_cover() {
+        let mut book = BookDocument::new();
+        book.manifest.add(ManifestItem::new(
+            "old_cover",
+            "images/old.jpg",
+            "image/jpeg",
+            ManifestData::Binary(b"old".to_vec()),
+        ));
+        book.manifest.add(ManifestItem::new(
+            "cover_page",
+            "text/cover.xhtml",
+            "application/xhtml+xml",
+            ManifestData::Xhtml(
+                r#"<html><body><img src="../images/old.jpg"/></body></html>"#.into(),
+            ),
+        ));
+        book.manifest.add(ManifestItem::new(
+            "ch1",
+            "text/ch1.xhtml",
+            "application/xhtml+xml",
+            ManifestData::Xhtml("<html><body><p>Text</p></body></html>".into()),
+        ));
+        book.spine.push("cover_page", true);
+        book.spine.push("ch1", true);
+        book.guide
+            .add(GuideRef::new("cover", "Cover", "text/cover.xhtml"));
+
+        let path = write_png("merge_metadata_cover.png");
+        let opts = ConversionOptions {
+            cover: Some(path.clone()),
+            ..Default::default()
+        };
+        MergeMetadata.apply(&mut book, &opts).unwrap();
+        std::fs::remove_file(&path).ok();
+
+        let cover_href = &book.guide.get("cover").unwrap().href;
+        let cover = book.manifest.by_href(cover_href).unwrap();
+        assert_eq!(cover.media_type, "image/png");
+        assert!(cover.data.as_binary().unwrap().starts_with(b"\x89PNG"));
+
+        // The old cover page is replaced in place by one showing the new image
+        assert!(book.manifest.by_id("cover_page").is_none());
+        assert_eq!(book.spine.len(), 2);
+        let page = book.manifest.by_id(&book.spine.items()[0].idref).unwrap();
+        assert!(page
+            .data
+            .as_xhtml()
+            .unwrap()
+            .contains(&format!(r#"src="{}""#, cover_href)));
+    }
+
+    #[test]
+    fn test_cover_option_without_existing_cover() {
+        let mut book = BookDocument::new();
+        let path = write_png("merge_metadata_new_cover.png");
+        let opts = ConversionOptions {
+            cover: Some(path.clone()),
+            ..Default::default()
+        };
+        MergeMetadata.apply(&mut book, &opts).unwrap();
+        std::fs::remove_file(&path).ok();
+
+        assert_eq!(book.guide.get("cover").unwrap().href, "cover.png");
+        assert!(book.spine.is_empty());
+    }
+
+    #[test]
+    fn test_cover_option_rejects_bad_files() {
+        let mut book = BookDocument::new();
+        let missing = ConversionOptions {
+            cover: Some("/nonexistent/cover.jpg".into()),
+            ..Default::default()
+        };
+        let err = MergeMetadata.apply(&mut book, &missing).unwrap_err();
+        assert!(err.to_string().contains("Cannot read cover image"));
+
+        let path = std::env::temp_dir().join("merge_metadata_cover.txt");
+        std::fs::write(&path, "not an image").unwrap();
+        let not_image = ConversionOptions {
+            cover: Some(path.clone()),
+            ..Default::default()
+        };
+        let err = MergeMetadata.apply(&mut book, &not_image).unwrap_err();
+        std::fs::remove_file(&path).ok();
+        assert!(err.to_string().contains("Unsupported cover image"));
+        assert!(book.guide.get("cover").is_none());
+    }
 }
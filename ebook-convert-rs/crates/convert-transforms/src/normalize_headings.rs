@@ -0,0 +1,215 @@
+//! NormalizeHeadings — renumbers heading levels so the book starts at `<h1>`.
+//!
+//! Imported documents often start at `<h2>` or skip levels (h1 then h4),
+//! which produces a lopsided TOC. The distinct levels used across the spine
+//! are mapped in order onto h1, h2, ... so the outline is contiguous. TOC
+//! entries built from headings (class `h2`, `h4`, ...) are renumbered too.
+
+use std::collections::BTreeSet;
+
+use rayon::prelude::*;
+use regex::Regex;
+
+use convert_core::book::{BookDocument, ManifestData, TocEntry};
+use convert_core::error::Result;
+use convert_core::options::ConversionOptions;
+use convert_core::plugin::Transform;
+
+/// Shifts and compacts heading levels across all spine documents.
+pub struct NormalizeHeadings;
+
+impl Transform for NormalizeHeadings {
+    fn name(&self) -> &str {
+        "NormalizeHeadings"
+    }
+
+    fn should_run(&self, options: &ConversionOptions) -> bool {
+        options.normalize_headings
+    }
+
+    fn apply(&self, book: &mut BookDocument, _options: &ConversionOptions) -> Result<()> {
+        let heading_re = Regex::new(r"(?i)<(/?)(h)([1-6])\b").unwrap();
+
+        // Collect spine XHTML items
+        let xhtml_items: Vec<(String, String)> = book
+            .spine
+            .iter()
+            .filter_map(|spine_item| book.manifest.by_id(&spine_item.idref))
+            .filter(|item| item.is_xhtml())
+            .filter_map(|item| {
+                item.data
+                    .as_xhtml()
+                    .map(|x| (item.id.clone(), x.to_string()))
+            })
+            .collect();
+
+        // Distinct levels in use, across the whole book
+        let used: BTreeSet<u8> = xhtml_items
+            .par_iter()
+            .flat_map_iter(|(_, xhtml)| {
+                heading_re
+                    .captures_iter(xhtml)
+                    .filter(|cap| cap[1].is_empty())
+                    .map(|cap| cap[3].parse::<u8>().unwrap_or(1))
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+
+        // Old level -> new level; nothing to do when already contiguous from h1
+        let mut mapping = [0u8; 7];
+        for (new_level, &old_level) in (1u8..).zip(&used) {
+            mapping[old_level as usize] = new_level;
+        }
+        if used.iter().all(|&level| mapping[level as usize] == level) {
+            return Ok(());
+        }
+
+        // Rewrite in parallel
+        let results: Vec<(String, String)> = xhtml_items
+            .into_par_iter()
+            .filter_map(|(id, xhtml)| {
+                let new_xhtml = heading_re.replace_all(&xhtml, |cap: &regex::Captures| {
+                    let old_level: usize = cap[3].parse().unwrap_or(1);
+                    match mapping[old_level] {
+                        // Stray closing tag for a level never opened
+                        0 => cap[0].to_string(),
+                        new_level => format!("<{}{}{}", &cap[1], &cap[2], new_level),
+                    }
+                });
+                if new_xhtml != xhtml {
+                    Some((id, new_xhtml.into_owned()))
+                } else {
+                    None
+                }
+            })
+            .collect();
+
+        // Apply back sequentially
+        for (id, new_xhtml) in results {
+            if let Some(item) = book.manifest.by_id_mut(&id) {
+                item.data = ManifestData::Xhtml(new_xhtml);
+            }
+        }
+        renumber_toc(&mut book.toc.entries, &mapping);
+
+        log::info!("Normalized heading levels {:?} to h1-h{}", used, used.len());
+        Ok(())
+    }
+}
+
+/// Rewrite the `hN` class of TOC entries built from headings to the
+/// heading's new level.
+fn renumber_toc(entries: &mut [TocEntry], mapping: &[u8; 7]) {
+    for entry in entries {
+        let level = entry
+            .klass
+            .as_deref()
+            .and_then(|klass| klass.strip_prefix('h'))
+            .and_then(|level| level.parse::<usize>().ok())
+            .filter(|&level| level <= 6);
+        if let Some(new_level) = level.map(|level| mapping[level]).filter(|&l| l != 0) {
+            entry.klass = Some(format!("h{}", new_level));
+        }
+        renumber_toc(&mut entry.children, mapping);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use convert_core::book::ManifestItem;
+
+    fn book_with(docs: &[&str]) -> BookDocument {
+        let mut book = BookDocument::new();
+        for (i, body) in docs.iter().enumerate() {
+            let id = format!("ch{}", i + 1);
+            book.manifest.add(ManifestItem::new(
+                id.as_str(),
+                format!("{}.xhtml", id),
+                "application/xhtml+xml",
+                ManifestData::Xhtml(format!("<html><body>{}</body></html>", body)),
+            ));
+            book.spine.push(id.as_str(), true);
+        }
+        book
+    }
+
+    fn xhtml(book: &BookDocument, id: &str) -> String {
+        book.manifest
+            .by_id(id)
+            .unwrap()
+            .data
+            .as_xhtml()
+            .unwrap()
+            .to_string()
+    }
+
+    #[test]
+    fn test_h2_h4_become_h1_h2() {
+        let mut book = book_with(&[
+            r#"<h2 class="title">Part</h2><p>Text with <header>h4</header></p>"#,
+            "<H4>Section</H4><hr/><p>More</p>",
+        ]);
+        NormalizeHeadings
+            .apply(&mut book, &ConversionOptions::default())
+            .unwrap();
+
+        assert_eq!(
+            xhtml(&book, "ch1"),
+            r#"<html><body><h1 class="title">Part</h1><p>Text with <header>h4</header></p></body></html>"#
+        );
+        assert_eq!(
+            xhtml(&book, "ch2"),
+            "<html><body><H2>Section</H2><hr/><p>More</p></body></html>"
+        );
+    }
+
+    #[test]
+    fn test_contiguous_levels_untouched() {
+        let mut book = book_with(&["<h1>A</h1><h2>B</h2><h3>C</h3>"]);
+        NormalizeHeadings
+            .apply(&mut book, &ConversionOptions::default())
+            .unwrap();
+        assert_eq!(
+            xhtml(&book, "ch1"),
+            "<html><body><h1>A</h1><h2>B</h2><h3>C</h3></body></html>"
+        );
+    }
+
+    #[test]
+    fn test_gap_collapsed() {
+        let mut book = book_with(&["<h1>A</h1><h4>B</h4><h6>C</h6>"]);
+        NormalizeHeadings
+            .apply(&mut book, &ConversionOptions::default())
+            .unwrap();
+        assert_eq!(
+            xhtml(&book, "ch1"),
+            "<html><body><h1>A</h1><h2>B</h2><h3>C</h3></body></html>"
+        );
+    }
+
+    #[test]
+    fn test_toc_levels_renumbered() {
+        let mut book = book_with(&["<h2>Part</h2><h4>Section</h4>"]);
+        let heading_entry = |title: &str, klass: &str| {
+            let mut entry = TocEntry::new(title, "ch1.xhtml");
+            entry.klass = Some(klass.to_string());
+            entry
+        };
+        let mut part = heading_entry("Part", "h2");
+        part.add_child(heading_entry("Section", "h4"));
+        book.toc.add(part);
+        book.toc.add(TocEntry::new("Notes", "notes.xhtml"));
+
+        NormalizeHeadings
+            .apply(&mut book, &ConversionOptions::default())
+            .unwrap();
+
+        let classes: Vec<Option<&str>> = book
+            .toc
+            .iter_depth_first()
+            .map(|entry| entry.klass.as_deref())
+            .collect();
+        assert_eq!(classes, vec![Some("h1"), Some("h2"), None]);
+    }
+}
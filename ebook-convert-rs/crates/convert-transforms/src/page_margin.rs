@@ -1,4 +1,5 @@
-//! PageMargin — removes fake margins and Adobe page template margins from content.
+//! PageMargin — removes fake margins and Adobe page template margins from
+//! content, then sets the page margins from the options or output profile.
 
 use std::collections::HashMap;
 
@@ -12,6 +13,7 @@ use regex::Regex;
 
 /// Removes artificial margins: Adobe page-template margins and
 /// fake margins (where >95% of paragraphs share the same non-zero margin).
+/// The page margins are then written as an `@page` rule.
 pub struct PageMargin;
 
 impl Transform for PageMargin {
@@ -19,13 +21,40 @@ impl Transform for PageMargin {
         "PageMargin"
     }
 
-    fn apply(&self, book: &mut BookDocument, _options: &ConversionOptions) -> Result<()> {
+    fn apply(&self, book: &mut BookDocument, options: &ConversionOptions) -> Result<()> {
         remove_adobe_margins(book);
         remove_fake_margins(book);
+        add_page_margins(book, options);
         Ok(())
     }
 }
 
+/// Top, right, bottom and left page margins in pt: the user's `margin_*`
+/// options, falling back to the output profile's margin.
+fn page_margins(options: &ConversionOptions) -> [f64; 4] {
+    let default = options.output_profile.margin;
+    [
+        options.margin_top,
+        options.margin_right,
+        options.margin_bottom,
+        options.margin_left,
+    ]
+    .map(|margin| margin.unwrap_or(default))
+}
+
+/// Append an `@page` rule with the page margins to the first stylesheet.
+/// Books without a stylesheet keep the reader's default margins.
+fn add_page_margins(book: &mut BookDocument, options: &ConversionOptions) {
+    let [top, right, bottom, left] = page_margins(options);
+    let rule = format!("@page {{ margin: {top}pt {right}pt {bottom}pt {left}pt; }}");
+    let Some(item) = book.manifest.iter_mut().find(|item| item.is_css()) else {
+        return;
+    };
+    if let Some(existing) = item.data.as_css() {
+        item.data = ManifestData::Css(format!("{}\n\n{}", existing, rule));
+    }
+}
+
 /// Remove margins from Adobe page template items.
 fn remove_adobe_margins(book: &mut BookDocument) {
     let margin_re = Regex::new(r"margin\s*:\s*[^;]+;?").unwrap();
@@ -181,6 +210,7 @@ fn remove_fake_margins(book: &mut BookDocument) {
 mod tests {
     use super::*;
     use convert_core::book::ManifestItem;
+    use convert_core::options::DeviceProfile;
 
     #[test]
     fn test_remove_adobe_margins() {
@@ -267,4 +297,48 @@ mod tests {
         // All margins should remain since none is dominant
         assert!(content.contains("margin-left"));
     }
+
+    #[test]
+    fn test_page_margins_from_profile() {
+        let css_book = || {
+            let mut book = BookDocument::new();
+            book.manifest.add(ManifestItem::new(
+                "css",
+                "style.css",
+                "text/css",
+                ManifestData::Css("p { margin: 0; }".into()),
+            ));
+            book
+        };
+        let css = |book: &BookDocument| {
+            book.manifest
+                .by_id("css")
+                .unwrap()
+                .data
+                .as_css()
+                .unwrap()
+                .to_string()
+        };
+
+        let mut book = css_book();
+        PageMargin
+            .apply(&mut book, &ConversionOptions::default())
+            .unwrap();
+        assert!(css(&book).ends_with("@page { margin: 5pt 5pt 5pt 5pt; }"));
+
+        let mut opts = ConversionOptions {
+            output_profile: DeviceProfile::GenericTablet.output_profile(),
+            ..Default::default()
+        };
+        let mut book = css_book();
+        PageMargin.apply(&mut book, &opts).unwrap();
+        assert!(css(&book).ends_with("@page { margin: 10pt 10pt 10pt 10pt; }"));
+
+        // Margins the user set win over the profile's
+        opts.margin_top = Some(20.0);
+        opts.margin_left = Some(2.5);
+        let mut book = css_book();
+        PageMargin.apply(&mut book, &opts).unwrap();
+        assert!(css(&book).ends_with("@page { margin: 20pt 10pt 10pt 2.5pt; }"));
+    }
 }
@@ -0,0 +1,312 @@
+//! PrettyXhtml — reindents content documents for readability.
+//!
+//! Transforms leave XHTML tightly packed or unevenly indented, which makes
+//! `--debug-pipeline` dumps and unzipped EPUBs hard to read. Block-level tags
+//! are put on their own lines, indented two spaces per open block. Only
+//! whitespace between a block tag and its neighbouring markup is rewritten:
+//! text, inline runs and the inside of `<pre>`, `<script>`, `<style>` and
+//! `<textarea>` are copied unchanged.
+
+use rayon::prelude::*;
+
+use convert_core::book::{BookDocument, ManifestData};
+use convert_core::error::Result;
+use convert_core::options::ConversionOptions;
+use convert_core::plugin::Transform;
+
+use crate::smarten::{nodes, tag_name, Node};
+
+/// Reindents spine XHTML when `pretty_xhtml` is set.
+pub struct PrettyXhtml;
+
+/// Elements that start their own line.
+const BLOCK_ELEMENTS: &[&str] = &[
+    "address",
+    "article",
+    "aside",
+    "base",
+    "blockquote",
+    "body",
+    "caption",
+    "col",
+    "colgroup",
+    "dd",
+    "details",
+    "div",
+    "dl",
+    "dt",
+    "fieldset",
+    "figcaption",
+    "figure",
+    "footer",
+    "form",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "head",
+    "header",
+    "hgroup",
+    "hr",
+    "html",
+    "legend",
+    "li",
+    "link",
+    "main",
+    "meta",
+    "nav",
+    "noscript",
+    "ol",
+    "p",
+    "pre",
+    "script",
+    "section",
+    "style",
+    "summary",
+    "table",
+    "tbody",
+    "td",
+    "textarea",
+    "tfoot",
+    "th",
+    "thead",
+    "title",
+    "tr",
+    "ul",
+];
+
+/// Elements whose content is copied byte for byte.
+const VERBATIM_ELEMENTS: &[&str] = &["pre", "script", "style", "textarea"];
+
+/// Block elements that never have content.
+const VOID_ELEMENTS: &[&str] = &["base", "col", "hr", "link", "meta"];
+
+const INDENT: &str = "  ";
+
+impl Transform for PrettyXhtml {
+    fn name(&self) -> &str {
+        "PrettyXhtml"
+    }
+
+    fn should_run(&self, options: &ConversionOptions) -> bool {
+        options.pretty_xhtml
+    }
+
+    fn apply(&self, book: &mut BookDocument, _options: &ConversionOptions) -> Result<()> {
+        let items: Vec<(String, String)> = book
+            .spine
+            .iter()
+            .filter_map(|s| book.manifest.by_id(&s.idref))
+            .filter_map(|item| {
+                item.data
+                    .as_xhtml()
+                    .map(|x| (item.id.clone(), x.to_string()))
+            })
+            .collect();
+
+        let results: Vec<(String, String)> = items
+            .into_par_iter()
+            .filter_map(|(id, xhtml)| {
+                let pretty = reindent(&xhtml);
+                (pretty != xhtml).then_some((id, pretty))
+            })
+            .collect();
+        log::info!("Reindented {} documents", results.len());
+        for (id, xhtml) in results {
+            if let Some(item) = book.manifest.by_id_mut(&id) {
+                item.data = ManifestData::Xhtml(xhtml);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Put block tags on their own lines with consistent indentation.
+///
+/// Whitespace-only text next to a block tag is replaced by a line break and
+/// indentation; a break is also inserted between a block tag and adjacent
+/// markup. Everything else is copied as is, so reindenting twice gives the
+/// same result.
+fn reindent(xhtml: &str) -> String {
+    let mut out = String::with_capacity(xhtml.len() + xhtml.len() / 8);
+    let mut depth = 0usize;
+    let mut verbatim: Option<String> = None;
+    // Whether the last node written was a tag, and whether it was a block tag
+    let mut after_tag = false;
+    let mut after_block = false;
+    let mut pending_space: Option<&str> = None;
+
+    for node in nodes(xhtml) {
+        match node {
+            Node::Tag(tag) if verbatim.is_some() => {
+                let (name, closing) = tag_name(tag);
+                if closing && verbatim.as_deref() == Some(name.as_str()) {
+                    verbatim = None;
+                    depth = depth.saturating_sub(1);
+                    after_block = true;
+                }
+                out.push_str(tag);
+                after_tag = verbatim.is_none();
+            }
+            Node::Text(text) if verbatim.is_some() => {
+                out.push_str(text);
+                after_tag = false;
+            }
+            Node::Text(text) if text.trim().is_empty() => pending_space = Some(text),
+            Node::Text(text) => {
+                out.push_str(pending_space.take().unwrap_or_default());
+                out.push_str(text);
+                after_tag = false;
+                after_block = false;
+            }
+            Node::Tag(tag) => {
+                let (name, closing) = tag_name(tag);
+                let block = is_block(tag, &name);
+                let container = block
+                    && !closing
+                    && !tag.ends_with("/>")
+                    && !name.is_empty()
+                    && !VOID_ELEMENTS.contains(&name.as_str());
+                if block && closing {
+                    depth = depth.saturating_sub(1);
+                }
+
+                let space = pending_space.take();
+                if after_tag && (after_block || block) {
+                    out.push('\n');
+                    out.push_str(&INDENT.repeat(depth));
+                } else {
+                    out.push_str(space.unwrap_or_default());
+                }
+                out.push_str(tag);
+
+                if container {
+                    depth += 1;
+                    if VERBATIM_ELEMENTS.contains(&name.as_str()) {
+                        verbatim = Some(name);
+                    }
+                }
+                after_tag = verbatim.is_none();
+                after_block = block;
+            }
+        }
+    }
+    out.push_str(pending_space.unwrap_or_default());
+    out
+}
+
+/// Whether `tag` goes on its own line: block elements, the XML declaration
+/// and the doctype. Comments stay where they are.
+fn is_block(tag: &str, name: &str) -> bool {
+    if name.is_empty() {
+        return tag.starts_with("<?") || (tag.starts_with("<!") && !tag.starts_with("<!--"));
+    }
+    BLOCK_ELEMENTS.contains(&name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use convert_core::book::ManifestItem;
+
+    /// Element names and non-blank text in document order, with the text of
+    /// `<pre>` kept exactly.
+    fn structure(xhtml: &str) -> Vec<String> {
+        let mut items = Vec::new();
+        let mut in_pre = false;
+        for node in nodes(xhtml) {
+            match node {
+                Node::Tag(tag) => {
+                    let (name, closing) = tag_name(tag);
+                    if name == "pre" {
+                        in_pre = !closing;
+                    }
+                    items.push(tag.to_string());
+                }
+                Node::Text(text) if in_pre => items.push(text.to_string()),
+                Node::Text(text) if !text.trim().is_empty() => items.push(text.to_string()),
+                Node::Text(_) => {}
+            }
+        }
+        items
+    }
+
+    /// Check that open and close tags pair up.
+    fn assert_well_formed(xhtml: &str) {
+        let mut open: Vec<String> = Vec::new();
+        for node in nodes(xhtml) {
+            let Node::Tag(tag) = node else { continue };
+            let (name, closing) = tag_name(tag);
+            if name.is_empty() || tag.ends_with("/>") {
+                continue;
+            }
+            if closing {
+                assert_eq!(open.pop().as_deref(), Some(name.as_str()), "{}", xhtml);
+            } else {
+                open.push(name);
+            }
+        }
+        assert!(open.is_empty(), "{}", xhtml);
+    }
+
+    const DOCUMENT: &str = concat!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?><!DOCTYPE html>",
+        "<html xmlns=\"http://www.w3.org/1999/xhtml\"><head><title>T</title>",
+        "<link rel=\"stylesheet\" href=\"s.css\"/></head><body>",
+        "<div class=\"chapter\"><h1>One</h1>   <p>Some <b>bold</b> text, ",
+        "<i>and</i> <a href=\"#x\">a link</a>.</p><ul><li>first</li><li>second</li></ul>",
+        "<pre>  let x = 1;\n<b>  </b>\n</pre><p>End</p></div></body></html>",
+    );
+
+    #[test]
+    fn test_reindent_blocks() {
+        let pretty = reindent(DOCUMENT);
+        assert_eq!(
+            pretty,
+            concat!(
+                "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<!DOCTYPE html>\n",
+                "<html xmlns=\"http://www.w3.org/1999/xhtml\">\n",
+                "  <head>\n    <title>T</title>\n",
+                "    <link rel=\"stylesheet\" href=\"s.css\"/>\n  </head>\n",
+                "  <body>\n    <div class=\"chapter\">\n      <h1>One</h1>\n",
+                "      <p>Some <b>bold</b> text, <i>and</i> <a href=\"#x\">a link</a>.</p>\n",
+                "      <ul>\n        <li>first</li>\n        <li>second</li>\n      </ul>\n",
+                "      <pre>  let x = 1;\n<b>  </b>\n</pre>\n",
+                "      <p>End</p>\n    </div>\n  </body>\n</html>",
+            )
+        );
+    }
+
+    #[test]
+    fn test_reindent_keeps_structure() {
+        let pretty = reindent(DOCUMENT);
+        assert_well_formed(&pretty);
+        assert_eq!(structure(&pretty), structure(DOCUMENT));
+        assert_eq!(reindent(&pretty), pretty);
+    }
+
+    #[test]
+    fn test_only_spine_documents_reindented() {
+        let mut book = BookDocument::new();
+        for id in ["ch1", "notes"] {
+            book.manifest.add(ManifestItem::new(
+                id,
+                format!("{}.xhtml", id),
+                "application/xhtml+xml",
+                ManifestData::Xhtml("<body><p>a</p><p>b</p></body>".to_string()),
+            ));
+        }
+        book.spine.push("ch1", true);
+        let opts = ConversionOptions {
+            pretty_xhtml: true,
+            ..Default::default()
+        };
+        PrettyXhtml.apply(&mut book, &opts).unwrap();
+
+        let xhtml = |id| book.manifest.by_id(id).unwrap().data.as_xhtml().unwrap();
+        assert_eq!(xhtml("ch1"), "<body>\n  <p>a</p>\n  <p>b</p>\n</body>");
+        assert_eq!(xhtml("notes"), "<body><p>a</p><p>b</p></body>");
+    }
+}
@@ -0,0 +1,163 @@
+//! ReadingDirection — sets right-to-left page progression and text direction.
+//!
+//! Arabic, Hebrew, Persian and Urdu books read right to left, but most inputs
+//! say so only through their language. The direction comes from
+//! `page_progression` when set, then from the input (an EPUB spine's
+//! `page-progression-direction`), then from the book language. A
+//! right-to-left book gets `dir="rtl"` on every content document's `<body>`
+//! so readers lay out its text correctly as well as turning pages leftwards.
+
+use regex::{Captures, Regex};
+
+use convert_core::book::{BookDocument, ManifestData, PageDirection};
+use convert_core::error::Result;
+use convert_core::options::{ConversionOptions, PageProgression};
+use convert_core::plugin::Transform;
+
+/// Primary language subtags written right to left.
+const RTL_LANGUAGES: &[&str] = &["ar", "he", "iw", "fa", "ur", "yi", "ps"];
+
+/// Sets the spine's page progression and marks right-to-left content.
+pub struct ReadingDirection;
+
+impl Transform for ReadingDirection {
+    fn name(&self) -> &str {
+        "ReadingDirection"
+    }
+
+    fn apply(&self, book: &mut BookDocument, options: &ConversionOptions) -> Result<()> {
+        let direction = match options.page_progression {
+            Some(PageProgression::Ltr) => Some(PageDirection::Ltr),
+            Some(PageProgression::Rtl) => Some(PageDirection::Rtl),
+            None => book.spine.page_progression_direction.or_else(|| {
+                book.metadata
+                    .language()
+                    .filter(|lang| is_rtl_language(lang))
+                    .map(|_| PageDirection::Rtl)
+            }),
+        };
+        book.spine.page_progression_direction = direction;
+        if direction != Some(PageDirection::Rtl) {
+            return Ok(());
+        }
+
+        let body_re = Regex::new(r"(?i)<body\b([^>]*?)(/?)>").unwrap();
+        let dir_re = Regex::new(r#"(?i)\sdir\s*="#).unwrap();
+        let mut marked = 0;
+        for item in book.manifest.iter_mut() {
+            let Some(xhtml) = item.data.as_xhtml() else {
+                continue;
+            };
+            let content = body_re.replace(xhtml, |cap: &Captures| {
+                if dir_re.is_match(&cap[1]) {
+                    cap[0].to_string()
+                } else {
+                    format!("<body{} dir=\"rtl\"{}>", &cap[1], &cap[2])
+                }
+            });
+            if content != xhtml {
+                item.data = ManifestData::Xhtml(content.into_owned());
+                marked += 1;
+            }
+        }
+        log::info!(
+            "Right-to-left page progression; marked {} documents",
+            marked
+        );
+        Ok(())
+    }
+}
+
+/// Whether `lang` (a BCP 47 tag such as `ar-EG`) is written right to left.
+fn is_rtl_language(lang: &str) -> bool {
+    let primary = lang.split(['-', '_']).next().unwrap_or_default();
+    RTL_LANGUAGES
+        .iter()
+        .any(|rtl| primary.eq_ignore_ascii_case(rtl))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use convert_core::book::ManifestItem;
+
+    fn book_in(lang: &str) -> BookDocument {
+        let mut book = BookDocument::new();
+        book.metadata.add("language", lang);
+        for (id, body) in [
+            ("ch1", r#"<body class="text">"#),
+            ("ch2", r#"<body dir="ltr">"#),
+        ] {
+            book.manifest.add(ManifestItem::new(
+                id,
+                format!("{}.xhtml", id),
+                "application/xhtml+xml",
+                ManifestData::Xhtml(format!("<html>{}<p>نص</p></body></html>", body)),
+            ));
+            book.spine.push(id, true);
+        }
+        book
+    }
+
+    fn xhtml<'a>(book: &'a BookDocument, id: &str) -> &'a str {
+        book.manifest.by_id(id).unwrap().data.as_xhtml().unwrap()
+    }
+
+    #[test]
+    fn test_is_rtl_language() {
+        assert!(is_rtl_language("ar"));
+        assert!(is_rtl_language("he-IL"));
+        assert!(is_rtl_language("FA"));
+        assert!(!is_rtl_language("en-US"));
+        assert!(!is_rtl_language("arn"));
+        assert!(is_rtl_language("ur_PK"));
+    }
+
+    #[test]
+    fn test_arabic_book_reads_right_to_left() {
+        let mut book = book_in("ar");
+        ReadingDirection
+            .apply(&mut book, &ConversionOptions::default())
+            .unwrap();
+        assert_eq!(
+            book.spine.page_progression_direction,
+            Some(PageDirection::Rtl)
+        );
+        assert!(xhtml(&book, "ch1").contains(r#"<body class="text" dir="rtl">"#));
+        // An explicit direction is kept
+        assert!(xhtml(&book, "ch2").contains(r#"<body dir="ltr">"#));
+    }
+
+    #[test]
+    fn test_option_overrides_language() {
+        let mut book = book_in("ar");
+        let options = ConversionOptions {
+            page_progression: Some(PageProgression::Ltr),
+            ..Default::default()
+        };
+        ReadingDirection.apply(&mut book, &options).unwrap();
+        assert_eq!(
+            book.spine.page_progression_direction,
+            Some(PageDirection::Ltr)
+        );
+        assert!(!xhtml(&book, "ch1").contains("rtl"));
+
+        let mut book = book_in("en");
+        let options = ConversionOptions {
+            page_progression: Some(PageProgression::Rtl),
+            ..Default::default()
+        };
+        ReadingDirection.apply(&mut book, &options).unwrap();
+        assert!(xhtml(&book, "ch1").contains(r#"dir="rtl""#));
+    }
+
+    #[test]
+    fn test_left_to_right_book_untouched() {
+        let mut book = book_in("en");
+        ReadingDirection
+            .apply(&mut book, &ConversionOptions::default())
+            .unwrap();
+        assert_eq!(book.spine.page_progression_direction, None);
+        assert!(!xhtml(&book, "ch1").contains("rtl"));
+    }
+}
@@ -0,0 +1,87 @@
+//! Rewriting references to manifest items that were renamed or merged.
+
+use std::collections::HashMap;
+use std::sync::LazyLock;
+
+use rayon::prelude::*;
+use regex::Regex;
+
+use convert_core::book::{BookDocument, GuideRef, ManifestData};
+use convert_utils::href;
+
+/// `src`/`href` attributes in XHTML; `\bhref` also matches `xlink:href`.
+static ATTR_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#"(\b(?:src|href)\s*=\s*["'])([^"']+)(["'])"#).unwrap());
+
+/// `url()` values in CSS.
+static URL_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#"(url\s*\(\s*['"]?)([^'")\s]+)(['"]?\s*\))"#).unwrap());
+
+/// Point every reference to a key of `renamed` at its value: `src`/`href`
+/// attributes in XHTML, `url()` in CSS, and guide entries. Hrefs are manifest
+/// hrefs; links keep their query and fragment.
+pub(crate) fn rewrite_references(book: &mut BookDocument, renamed: &HashMap<String, String>) {
+    let items: Vec<(String, String, String, bool)> = book
+        .manifest
+        .iter()
+        .filter_map(|item| {
+            if item.is_xhtml() {
+                item.data
+                    .as_xhtml()
+                    .map(|x| (item.id.clone(), item.href.clone(), x.to_string(), true))
+            } else if item.is_css() {
+                item.data
+                    .as_css()
+                    .map(|c| (item.id.clone(), item.href.clone(), c.to_string(), false))
+            } else {
+                None
+            }
+        })
+        .collect();
+
+    let results: Vec<(String, String, bool)> = items
+        .into_par_iter()
+        .filter_map(|(id, base, content, is_xhtml)| {
+            let re: &Regex = if is_xhtml { &ATTR_RE } else { &URL_RE };
+            let new_content = re.replace_all(&content, |cap: &regex::Captures| {
+                match renamed.get(&href::resolve(&base, &cap[2])) {
+                    Some(target) => format!(
+                        "{}{}{}",
+                        &cap[1],
+                        href::retarget(&base, &cap[2], target),
+                        &cap[3]
+                    ),
+                    None => cap[0].to_string(),
+                }
+            });
+            if new_content != content {
+                Some((id, new_content.into_owned(), is_xhtml))
+            } else {
+                None
+            }
+        })
+        .collect();
+
+    for (id, content, is_xhtml) in results {
+        if let Some(item) = book.manifest.by_id_mut(&id) {
+            item.data = if is_xhtml {
+                ManifestData::Xhtml(content)
+            } else {
+                ManifestData::Css(content)
+            };
+        }
+    }
+
+    let guide_updates: Vec<(String, String, String)> = book
+        .guide
+        .iter()
+        .filter_map(|g| {
+            renamed
+                .get(&g.href)
+                .map(|target| (g.ref_type.clone(), g.title.clone(), target.clone()))
+        })
+        .collect();
+    for (ref_type, title, target) in guide_updates {
+        book.guide.add(GuideRef::new(ref_type, title, target));
+    }
+}
@@ -0,0 +1,249 @@
+//! RemoveBlank — drops empty paragraphs and blank pages.
+//!
+//! PDF and DOCX conversions leave behind `<p></p>`, `<p>&nbsp;</p>` and
+//! whitespace-only documents. Paragraphs with no visible text are removed
+//! unless they hold an image or other media, or carry an `id` that links may
+//! point at. Spine documents whose body has no visible content are removed
+//! too; TOC and guide entries pointing at them move to the next document.
+
+use std::collections::HashMap;
+use std::sync::LazyLock;
+
+use rayon::prelude::*;
+use regex::{Captures, Regex};
+
+use convert_core::book::{BookDocument, GuideRef, ManifestData, TocEntry};
+use convert_core::error::Result;
+use convert_core::options::ConversionOptions;
+use convert_core::plugin::Transform;
+use convert_utils::xml::heading_text;
+
+static PARAGRAPH_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?is)<p(\s[^>]*)?>(.*?)</p\s*>|<p(\s[^>]*)?/>").unwrap());
+static BODY_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?is)<body[^>]*>(.*)</body\s*>").unwrap());
+/// Elements that are content even without any text.
+static MEDIA_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?i)<(?:img|image|svg|object|embed|video|audio|iframe|canvas|math)\b").unwrap()
+});
+static ANCHOR_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#"(?i)\s(?:id|name)\s*=\s*["'][^"']+["']"#).unwrap());
+
+/// Removes blank paragraphs and documents when `remove_blank` is set.
+pub struct RemoveBlank;
+
+impl Transform for RemoveBlank {
+    fn name(&self) -> &str {
+        "RemoveBlank"
+    }
+
+    fn should_run(&self, options: &ConversionOptions) -> bool {
+        options.remove_blank
+    }
+
+    fn apply(&self, book: &mut BookDocument, _options: &ConversionOptions) -> Result<()> {
+        let items: Vec<(String, String)> = book
+            .manifest
+            .iter()
+            .filter(|item| item.is_xhtml())
+            .filter_map(|item| {
+                item.data
+                    .as_xhtml()
+                    .map(|x| (item.id.clone(), x.to_string()))
+            })
+            .collect();
+
+        let cleaned: Vec<(String, String)> = items
+            .into_par_iter()
+            .filter_map(|(id, xhtml)| {
+                let result = remove_blank_paragraphs(&xhtml);
+                (result != xhtml).then_some((id, result))
+            })
+            .collect();
+        log::info!("Removed blank paragraphs from {} documents", cleaned.len());
+        for (id, xhtml) in cleaned {
+            if let Some(item) = book.manifest.by_id_mut(&id) {
+                item.data = ManifestData::Xhtml(xhtml);
+            }
+        }
+
+        remove_blank_documents(book);
+        Ok(())
+    }
+}
+
+/// Remove paragraphs without visible text, media or anchors.
+fn remove_blank_paragraphs(xhtml: &str) -> String {
+    PARAGRAPH_RE
+        .replace_all(xhtml, |cap: &Captures| {
+            let attrs = cap.get(1).or_else(|| cap.get(3)).map_or("", |m| m.as_str());
+            let inner = cap.get(2).map_or("", |m| m.as_str());
+            if is_blank(inner) && !ANCHOR_RE.is_match(attrs) {
+                String::new()
+            } else {
+                cap[0].to_string()
+            }
+        })
+        .into_owned()
+}
+
+/// Whether `html` shows nothing: no text beyond whitespace and `&nbsp;`, no
+/// media and no link targets.
+fn is_blank(html: &str) -> bool {
+    heading_text(html).is_none() && !MEDIA_RE.is_match(html) && !ANCHOR_RE.is_match(html)
+}
+
+/// Drop spine documents whose body is blank, keeping at least one, and
+/// point TOC and guide entries at the document that followed each.
+fn remove_blank_documents(book: &mut BookDocument) {
+    let spine: Vec<(String, String, bool)> = book
+        .spine
+        .iter()
+        .filter_map(|s| book.manifest.by_id(&s.idref))
+        .map(|item| {
+            let blank = item
+                .data
+                .as_xhtml()
+                .and_then(|x| BODY_RE.captures(x))
+                .is_some_and(|cap| is_blank(&cap[1]));
+            (item.id.clone(), item.href.clone(), blank)
+        })
+        .collect();
+    if spine.iter().all(|(_, _, blank)| *blank) {
+        return;
+    }
+
+    // Each blank document's replacement: the next kept one, else the last kept
+    let mut replacements: HashMap<String, String> = HashMap::new();
+    let mut pending: Vec<&str> = Vec::new();
+    for (_, href, blank) in &spine {
+        if *blank {
+            pending.push(href);
+        } else {
+            for removed in pending.drain(..) {
+                replacements.insert(removed.to_string(), href.clone());
+            }
+        }
+    }
+    if let Some((_, last, _)) = spine.iter().rev().find(|(_, _, blank)| !blank) {
+        for removed in pending {
+            replacements.insert(removed.to_string(), last.clone());
+        }
+    }
+
+    for (id, href, blank) in &spine {
+        if *blank {
+            log::debug!("Removing blank document: {}", href);
+            book.spine.remove(id);
+            book.manifest.remove_by_id(id);
+        }
+    }
+    log::info!("Removed {} blank documents", replacements.len());
+
+    for entry in &mut book.toc.entries {
+        retarget_toc_entry(entry, &replacements);
+    }
+    let guide: Vec<GuideRef> = book
+        .guide
+        .iter()
+        .filter_map(|r| {
+            let target = replacements.get(r.href.split('#').next()?)?;
+            Some(GuideRef::new(&r.ref_type, &r.title, target))
+        })
+        .collect();
+    for reference in guide {
+        book.guide.add(reference);
+    }
+}
+
+fn retarget_toc_entry(entry: &mut TocEntry, replacements: &HashMap<String, String>) {
+    let base = entry.href.split('#').next().unwrap_or_default();
+    if let Some(target) = replacements.get(base) {
+        entry.href = target.clone();
+    }
+    for child in &mut entry.children {
+        retarget_toc_entry(child, replacements);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use convert_core::book::ManifestItem;
+
+    fn document(body: &str) -> String {
+        format!(
+            "<html><head><title>t</title></head><body>{}</body></html>",
+            body
+        )
+    }
+
+    fn opts() -> ConversionOptions {
+        ConversionOptions {
+            remove_blank: true,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_blank_paragraphs_removed_but_images_kept() {
+        let xhtml = document(concat!(
+            "<p>Text</p><p></p><p>&nbsp;</p><p class=\"x\"> \u{a0} <br/></p><p/>",
+            "<p><img src=\"pic.png\" alt=\"\"/></p><p id=\"target\"></p>",
+        ));
+        let result = remove_blank_paragraphs(&xhtml);
+        assert_eq!(
+            result,
+            document("<p>Text</p><p><img src=\"pic.png\" alt=\"\"/></p><p id=\"target\"></p>")
+        );
+    }
+
+    #[test]
+    fn test_blank_documents_removed_and_references_moved() {
+        let mut book = BookDocument::new();
+        let bodies = [
+            ("a", "<p>Start</p>"),
+            ("blank", "<p>&nbsp;</p>\n<div> </div>"),
+            ("b", "<p>End</p>"),
+            ("trailing", ""),
+        ];
+        for (id, body) in bodies {
+            book.manifest.add(ManifestItem::new(
+                id,
+                format!("{}.xhtml", id),
+                "application/xhtml+xml",
+                ManifestData::Xhtml(document(body)),
+            ));
+            book.spine.push(id, true);
+        }
+        let mut part = TocEntry::new("Part", "a.xhtml");
+        part.add_child(TocEntry::new("Interlude", "blank.xhtml"));
+        book.toc.add(part);
+        book.toc.add(TocEntry::new("Afterword", "trailing.xhtml#x"));
+        book.guide
+            .add(GuideRef::new("text", "Start", "blank.xhtml"));
+
+        RemoveBlank.apply(&mut book, &opts()).unwrap();
+
+        let spine: Vec<&str> = book.spine.iter().map(|s| s.idref.as_str()).collect();
+        assert_eq!(spine, vec!["a", "b"]);
+        assert!(book.manifest.by_id("blank").is_none());
+        assert_eq!(book.toc.entries[0].children[0].href, "b.xhtml");
+        assert_eq!(book.toc.entries[1].href, "b.xhtml");
+        assert_eq!(book.guide.get("text").unwrap().href, "b.xhtml");
+    }
+
+    #[test]
+    fn test_all_blank_book_is_kept() {
+        let mut book = BookDocument::new();
+        book.manifest.add(ManifestItem::new(
+            "only",
+            "only.xhtml",
+            "application/xhtml+xml",
+            ManifestData::Xhtml(document("<p></p>")),
+        ));
+        book.spine.push("only", true);
+        RemoveBlank.apply(&mut book, &opts()).unwrap();
+        assert_eq!(book.spine.len(), 1);
+    }
+}
@@ -0,0 +1,289 @@
+//! RemoveTinyImages transform — drops tracking pixels and spacer images.
+//!
+//! Images smaller than `min_image_dimension` in both width and height are
+//! removed from the manifest, along with the `<img>` tags and CSS
+//! declarations, in stylesheets and `style` attributes, that reference them.
+
+use std::borrow::Cow;
+use std::collections::HashSet;
+use std::io::Cursor;
+use std::sync::LazyLock;
+
+use rayon::prelude::*;
+use regex::Regex;
+
+use convert_core::book::{BookDocument, ManifestData};
+use convert_core::error::Result;
+use convert_core::options::ConversionOptions;
+use convert_core::plugin::Transform;
+use convert_utils::href;
+
+static IMG_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"(?is)<img\b[^>]*?\bsrc\s*=\s*["']([^"']+)["'][^>]*>(?:\s*</img\s*>)?"#).unwrap()
+});
+
+/// A `url(...)` token; the target is in whichever group matched.
+static URL_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#"url\s*\(\s*(?:"([^"]*)"|'([^']*)'|([^"')\s]*))\s*\)"#).unwrap());
+
+/// A declaration. `url(...)` tokens are matched whole so a `;` inside a
+/// `data:` URL doesn't end the declaration.
+static DECL_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"([\w-]+)\s*:((?:url\s*\(\s*(?:"[^"]*"|'[^']*'|[^"')]*)\s*\)|[^;{}])*);?"#)
+        .unwrap()
+});
+
+/// A `style` attribute, its value in group 1 (double quotes) or 2 (single).
+static STYLE_ATTR_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#"(?is)\sstyle\s*=\s*(?:"([^"]*)"|'([^']*)')"#).unwrap());
+
+/// Removes images below the configured minimum size and their references.
+pub struct RemoveTinyImages;
+
+impl Transform for RemoveTinyImages {
+    fn name(&self) -> &str {
+        "RemoveTinyImages"
+    }
+
+    fn should_run(&self, options: &ConversionOptions) -> bool {
+        !options.no_images && options.min_image_dimension.is_some()
+    }
+
+    fn apply(&self, book: &mut BookDocument, options: &ConversionOptions) -> Result<()> {
+        let Some(min) = options.min_image_dimension else {
+            return Ok(());
+        };
+
+        // Find tiny images in parallel by reading only their headers
+        let tiny: Vec<(String, String)> = book
+            .manifest
+            .par_iter()
+            .filter(|item| item.is_image())
+            .filter_map(|item| {
+                let data = item.data.as_binary()?;
+                let (w, h) = image_dimensions(data)?;
+                if w < min && h < min {
+                    Some((item.id.clone(), item.href.clone()))
+                } else {
+                    None
+                }
+            })
+            .collect();
+
+        if tiny.is_empty() {
+            return Ok(());
+        }
+
+        let tiny_hrefs: HashSet<String> = tiny.iter().map(|(_, href)| href.clone()).collect();
+        for (id, href) in &tiny {
+            log::debug!("Dropping tiny image: {}", href);
+            book.manifest.remove_by_id(id);
+        }
+
+        // Strip references from XHTML and CSS in parallel
+        let items: Vec<(String, String, String, bool)> = book
+            .manifest
+            .iter()
+            .filter_map(|item| {
+                if item.is_xhtml() {
+                    item.data
+                        .as_xhtml()
+                        .map(|x| (item.id.clone(), item.href.clone(), x.to_string(), true))
+                } else if item.is_css() {
+                    item.data
+                        .as_css()
+                        .map(|c| (item.id.clone(), item.href.clone(), c.to_string(), false))
+                } else {
+                    None
+                }
+            })
+            .collect();
+
+        let results: Vec<(String, String, bool)> = items
+            .into_par_iter()
+            .filter_map(|(id, base, content, is_xhtml)| {
+                let is_tiny = |src: &str| tiny_hrefs.contains(&href::resolve(&base, src));
+                let new_content = if is_xhtml {
+                    let content = IMG_RE.replace_all(&content, |cap: &regex::Captures| {
+                        if is_tiny(&cap[1]) {
+                            String::new()
+                        } else {
+                            cap[0].to_string()
+                        }
+                    });
+                    let content = STYLE_ATTR_RE.replace_all(&content, |cap: &regex::Captures| {
+                        let (value, quote) = match cap.get(1) {
+                            Some(value) => (value.as_str(), '"'),
+                            None => (&cap[2], '\''),
+                        };
+                        let style = strip_declarations(value, &is_tiny);
+                        if style == value {
+                            cap[0].to_string()
+                        } else if style.trim().is_empty() {
+                            String::new()
+                        } else {
+                            format!(" style={quote}{style}{quote}")
+                        }
+                    });
+                    Cow::Owned(content.into_owned())
+                } else {
+                    strip_declarations(&content, &is_tiny)
+                };
+                if new_content != content {
+                    Some((id, new_content.into_owned(), is_xhtml))
+                } else {
+                    None
+                }
+            })
+            .collect();
+
+        for (id, content, is_xhtml) in results {
+            if let Some(item) = book.manifest.by_id_mut(&id) {
+                item.data = if is_xhtml {
+                    ManifestData::Xhtml(content)
+                } else {
+                    ManifestData::Css(content)
+                };
+            }
+        }
+
+        log::info!("Removed {} images smaller than {}px", tiny.len(), min);
+        Ok(())
+    }
+}
+
+/// Remove the declarations in `css` that reference an image `is_tiny`
+/// accepts. `none` is only a valid value for the image properties, which
+/// keep their name; anything else (shorthands, `content`, `src`) is dropped.
+fn strip_declarations<'a>(css: &'a str, is_tiny: &dyn Fn(&str) -> bool) -> Cow<'a, str> {
+    DECL_RE.replace_all(css, |cap: &regex::Captures| {
+        let references_tiny = URL_RE.captures_iter(&cap[2]).any(|url| {
+            let target = url.get(1).or(url.get(2)).or(url.get(3));
+            target.is_some_and(|target| is_tiny(target.as_str()))
+        });
+        if !references_tiny {
+            return cap[0].to_string();
+        }
+        match cap[1].to_ascii_lowercase().as_str() {
+            "background-image" | "list-style-image" => format!("{}: none;", &cap[1]),
+            _ => String::new(),
+        }
+    })
+}
+
+/// Width and height of an encoded image, decoded from its header only.
+pub(crate) fn image_dimensions(data: &[u8]) -> Option<(u32, u32)> {
+    image::ImageReader::new(Cursor::new(data))
+        .with_guessed_format()
+        .ok()?
+        .into_dimensions()
+        .ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use convert_core::book::ManifestItem;
+
+    fn encode(width: u32, height: u32, format: image::ImageFormat) -> Vec<u8> {
+        let img = image::RgbaImage::from_pixel(width, height, image::Rgba([0, 0, 0, 255]));
+        let mut buf = Vec::new();
+        image::DynamicImage::ImageRgba8(img)
+            .write_to(&mut Cursor::new(&mut buf), format)
+            .unwrap();
+        buf
+    }
+
+    fn book_with_images() -> BookDocument {
+        let mut book = BookDocument::new();
+        book.manifest.add(ManifestItem::new(
+            "pixel",
+            "images/pixel.gif",
+            "image/gif",
+            ManifestData::Binary(encode(1, 1, image::ImageFormat::Gif)),
+        ));
+        book.manifest.add(ManifestItem::new(
+            "photo",
+            "images/photo.png",
+            "image/png",
+            ManifestData::Binary(encode(64, 48, image::ImageFormat::Png)),
+        ));
+        book.manifest.add(ManifestItem::new(
+            "ch1",
+            "text/ch1.xhtml",
+            "application/xhtml+xml",
+            ManifestData::Xhtml(
+                r#"<html><body><p>Text<img src="../images/pixel.gif" width="1" height="1"/></p><img src="../images/photo.png" alt="Photo"/><div class="a" style="background: url(../images/pixel.gif)">A</div><div style='color: red; background-image: url("../images/pixel.gif")'>B</div></body></html>"#
+                    .to_string(),
+            ),
+        ));
+        book.manifest.add(ManifestItem::new(
+            "css",
+            "styles/style.css",
+            "text/css",
+            ManifestData::Css(
+                "p { color: red; background: #fff url(../images/pixel.gif) repeat-x; }\n\
+                 li { list-style-image: url('../images/pixel.gif'); }\n\
+                 h1 { background-image: url(../images/photo.png); }\n\
+                 h2 { background: url(data:image/png;base64,iVBORw0KGgo=), url(../images/pixel.gif); color: blue; }"
+                    .to_string(),
+            ),
+        ));
+        book.spine.push("ch1", true);
+        book
+    }
+
+    #[test]
+    fn test_drops_tiny_image_and_references() {
+        let mut book = book_with_images();
+        let opts = ConversionOptions {
+            min_image_dimension: Some(8),
+            ..Default::default()
+        };
+        RemoveTinyImages.apply(&mut book, &opts).unwrap();
+
+        assert!(book.manifest.by_id("pixel").is_none());
+        assert!(book.manifest.by_id("photo").is_some());
+
+        let xhtml = book.manifest.by_id("ch1").unwrap().data.as_xhtml().unwrap();
+        assert!(!xhtml.contains("pixel.gif"));
+        assert!(xhtml.contains(r#"<p>Text</p>"#));
+        assert!(xhtml.contains(r#"<img src="../images/photo.png" alt="Photo"/>"#));
+        // Inline styles are cleaned like stylesheets; an emptied one goes
+        assert!(xhtml.contains(r#"<div class="a">A</div>"#), "{}", xhtml);
+        assert!(
+            xhtml.contains("<div style='color: red; background-image: none;'>B</div>"),
+            "{}",
+            xhtml
+        );
+
+        let css = book.manifest.by_id("css").unwrap().data.as_css().unwrap();
+        // The shorthand goes entirely, the image property becomes `none`
+        assert!(css.contains("p { color: red;  }"), "{}", css);
+        assert!(css.contains("li { list-style-image: none; }"), "{}", css);
+        assert!(css.contains("background-image: url(../images/photo.png);"));
+        // A `;` inside a data URL doesn't split its declaration
+        assert!(css.contains("h2 {  color: blue; }"), "{}", css);
+    }
+
+    #[test]
+    fn test_keeps_images_large_in_one_dimension() {
+        let mut book = book_with_images();
+        // The 64x48 photo is below 50 in height but not in width
+        let opts = ConversionOptions {
+            min_image_dimension: Some(50),
+            ..Default::default()
+        };
+        RemoveTinyImages.apply(&mut book, &opts).unwrap();
+        assert!(book.manifest.by_id("photo").is_some());
+        assert!(book.manifest.by_id("pixel").is_none());
+    }
+
+    #[test]
+    fn test_should_run() {
+        let mut opts = ConversionOptions::default();
+        assert!(!RemoveTinyImages.should_run(&opts));
+        opts.min_image_dimension = Some(2);
+        assert!(RemoveTinyImages.should_run(&opts));
+    }
+}
@@ -0,0 +1,328 @@
+//! SmartenPunctuation — converts ASCII quotes/dashes/ellipsis to typographic ones.
+
+use rayon::prelude::*;
+
+use convert_core::book::{BookDocument, ManifestData};
+use convert_core::error::Result;
+use convert_core::options::ConversionOptions;
+use convert_core::plugin::Transform;
+
+/// Replaces straight quotes with curly ones, `--`/`---` with en/em dashes
+/// and `...` with an ellipsis. The inverse of `UnsmartenPunctuation`.
+pub struct SmartenPunctuation;
+
+/// Elements whose text content is left untouched.
+const VERBATIM_ELEMENTS: &[&str] = &["pre", "code", "kbd", "samp", "script", "style"];
+
+/// Block-level elements: quote context does not carry across their boundaries.
+const BLOCK_ELEMENTS: &[&str] = &[
+    "p",
+    "div",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "li",
+    "dt",
+    "dd",
+    "td",
+    "th",
+    "blockquote",
+    "br",
+    "body",
+    "title",
+];
+
+impl Transform for SmartenPunctuation {
+    fn name(&self) -> &str {
+        "SmartenPunctuation"
+    }
+
+    fn should_run(&self, options: &ConversionOptions) -> bool {
+        options.smarten_punctuation
+    }
+
+    fn apply(&self, book: &mut BookDocument, _options: &ConversionOptions) -> Result<()> {
+        // Collect XHTML items
+        let xhtml_items: Vec<(String, String)> = book
+            .manifest
+            .iter()
+            .filter(|item| item.is_xhtml())
+            .filter_map(|item| {
+                item.data
+                    .as_xhtml()
+                    .map(|x| (item.id.clone(), x.to_string()))
+            })
+            .collect();
+
+        // Process in parallel
+        let results: Vec<(String, String)> = xhtml_items
+            .into_par_iter()
+            .filter_map(|(id, xhtml)| {
+                let new_xhtml = smarten_xhtml(&xhtml);
+                if new_xhtml != xhtml {
+                    Some((id, new_xhtml))
+                } else {
+                    None
+                }
+            })
+            .collect();
+
+        // Apply back sequentially
+        let count = results.len() as u32;
+        for (id, new_xhtml) in results {
+            if let Some(item) = book.manifest.by_id_mut(&id) {
+                item.data = ManifestData::Xhtml(new_xhtml);
+            }
+        }
+
+        if count > 0 {
+            log::info!("Smartened punctuation in {} items", count);
+        }
+        Ok(())
+    }
+}
+
+/// Smarten the text nodes of an XHTML document, leaving markup alone.
+fn smarten_xhtml(xhtml: &str) -> String {
+    // Last text character seen, used to decide quote direction across inline tags
+    let mut prev: Option<char> = None;
+    map_text_nodes(xhtml, VERBATIM_ELEMENTS, |node, out| match node {
+        Node::Tag(tag) => {
+            if BLOCK_ELEMENTS.contains(&tag_name(tag).0.as_str()) {
+                prev = None;
+            }
+        }
+        Node::Text(text) => out.push_str(&smarten_text(text, &mut prev)),
+    })
+}
+
+/// A tag (or comment) or a run of text between tags.
+pub(crate) enum Node<'a> {
+    Tag(&'a str),
+    Text(&'a str),
+}
+
+/// Split an XHTML document into tags and text nodes, in document order.
+pub(crate) fn nodes(xhtml: &str) -> impl Iterator<Item = Node<'_>> {
+    let mut rest = xhtml;
+    std::iter::from_fn(move || {
+        if rest.is_empty() {
+            return None;
+        }
+        let end = if rest.starts_with("<!--") {
+            rest.find("-->").map_or(rest.len(), |i| i + 3)
+        } else if rest.starts_with('<') {
+            rest.find('>').map_or(rest.len(), |i| i + 1)
+        } else {
+            rest.find('<').unwrap_or(rest.len())
+        };
+        let (head, tail) = rest.split_at(end);
+        rest = tail;
+        Some(if head.starts_with('<') {
+            Node::Tag(head)
+        } else {
+            Node::Text(head)
+        })
+    })
+}
+
+/// Rewrite the text nodes of an XHTML document outside `verbatim` elements.
+///
+/// Tags and verbatim text are copied unchanged. `visit` sees every tag after
+/// it is copied, and must write each text node it is given to the output.
+pub(crate) fn map_text_nodes(
+    xhtml: &str,
+    verbatim: &[&str],
+    mut visit: impl FnMut(Node<'_>, &mut String),
+) -> String {
+    let mut out = String::with_capacity(xhtml.len() + xhtml.len() / 16);
+    let mut verbatim_depth = 0usize;
+
+    for node in nodes(xhtml) {
+        match node {
+            Node::Tag(tag) => {
+                let (name, closing) = tag_name(tag);
+                if verbatim.contains(&name.as_str()) && !tag.ends_with("/>") {
+                    if closing {
+                        verbatim_depth = verbatim_depth.saturating_sub(1);
+                    } else {
+                        verbatim_depth += 1;
+                    }
+                }
+                out.push_str(tag);
+                visit(node, &mut out);
+            }
+            Node::Text(text) if verbatim_depth > 0 => out.push_str(text),
+            Node::Text(_) => visit(node, &mut out),
+        }
+    }
+
+    out
+}
+
+/// Lowercase local name of a tag and whether it is a closing tag.
+pub(crate) fn tag_name(tag: &str) -> (String, bool) {
+    let inner = tag.trim_start_matches('<');
+    let closing = inner.starts_with('/');
+    let name: String = inner
+        .trim_start_matches('/')
+        .chars()
+        .take_while(|c| c.is_ascii_alphanumeric())
+        .collect();
+    (name.to_ascii_lowercase(), closing)
+}
+
+/// Smarten a single text node. `prev` carries the last character across calls.
+fn smarten_text(text: &str, prev: &mut Option<char>) -> String {
+    // Quote entities are plain characters as far as punctuation is concerned
+    let text = text
+        .replace("&quot;", "\"")
+        .replace("&#34;", "\"")
+        .replace("&apos;", "'")
+        .replace("&#39;", "'");
+    let chars: Vec<char> = text.chars().collect();
+    let mut out = String::with_capacity(text.len());
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        let next = chars.get(i + 1).copied();
+        let emitted = match c {
+            '.' if chars[i..].starts_with(&['.', '.', '.']) => {
+                i += 2;
+                '\u{2026}'
+            }
+            '-' if chars[i..].starts_with(&['-', '-', '-']) => {
+                i += 2;
+                '\u{2014}'
+            }
+            '-' if next == Some('-') => {
+                i += 1;
+                '\u{2013}'
+            }
+            '"' => {
+                if opens_quote(*prev) {
+                    '\u{201c}'
+                } else {
+                    '\u{201d}'
+                }
+            }
+            '\'' => {
+                // Leading apostrophe in elisions like '90s stays an apostrophe
+                if opens_quote(*prev) && !next.is_some_and(|n| n.is_ascii_digit()) {
+                    '\u{2018}'
+                } else {
+                    '\u{2019}'
+                }
+            }
+            _ => c,
+        };
+        out.push(emitted);
+        *prev = Some(emitted);
+        i += 1;
+    }
+
+    out
+}
+
+/// Whether a quote following `prev` opens a quotation.
+fn opens_quote(prev: Option<char>) -> bool {
+    match prev {
+        None => true,
+        Some(p) => {
+            p.is_whitespace()
+                || matches!(
+                    p,
+                    '(' | '[' | '{' | '\u{2013}' | '\u{2014}' | '\u{201c}' | '\u{2018}' | '/'
+                )
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use convert_core::book::ManifestItem;
+
+    fn smarten_book(xhtml: &str) -> String {
+        let mut book = BookDocument::new();
+        let item = ManifestItem::new(
+            "ch1",
+            "ch1.xhtml",
+            "application/xhtml+xml",
+            ManifestData::Xhtml(xhtml.to_string()),
+        );
+        book.manifest.add(item);
+
+        let opts = ConversionOptions {
+            smarten_punctuation: true,
+            ..Default::default()
+        };
+        SmartenPunctuation.apply(&mut book, &opts).unwrap();
+
+        book.manifest
+            .by_id("ch1")
+            .unwrap()
+            .data
+            .as_xhtml()
+            .unwrap()
+            .to_string()
+    }
+
+    #[test]
+    fn test_smarten_quote_direction() {
+        let content = smarten_book(
+            r#"<html><body><p>"Hello," she said. 'World!' It's the dogs' toys.</p></body></html>"#,
+        );
+        assert!(content.contains("\u{201c}Hello,\u{201d} she said."));
+        assert!(content.contains("\u{2018}World!\u{2019}"));
+        assert!(content.contains("It\u{2019}s the dogs\u{2019} toys."));
+    }
+
+    #[test]
+    fn test_smarten_quotes_across_inline_tags() {
+        let content =
+            smarten_book(r#"<html><body><p>"<em>Really</em>" she asked.</p></body></html>"#);
+        assert!(content.contains("\u{201c}<em>Really</em>\u{201d} she asked."));
+    }
+
+    #[test]
+    fn test_smarten_quote_at_block_start() {
+        let content =
+            smarten_book(r#"<html><body><p>End.</p><p>"Next," he said.</p></body></html>"#);
+        assert!(content.contains("<p>\u{201c}Next,\u{201d} he said.</p>"));
+    }
+
+    #[test]
+    fn test_smarten_dashes_and_ellipsis() {
+        let content =
+            smarten_book("<html><body><p>pages 1--5, wait---what... and '90s</p></body></html>");
+        assert!(content.contains("pages 1\u{2013}5"));
+        assert!(content.contains("wait\u{2014}what\u{2026}"));
+        assert!(content.contains("\u{2019}90s"));
+    }
+
+    #[test]
+    fn test_smarten_leaves_markup_and_code_alone() {
+        let xhtml = r#"<html><body><p class="a--b">x</p><pre>"raw" -- code...</pre><code>'q'</code></body></html>"#;
+        let content = smarten_book(xhtml);
+        assert_eq!(content, xhtml);
+    }
+
+    #[test]
+    fn test_smarten_quote_entities() {
+        let content = smarten_book("<html><body><p>&quot;Hi&quot;</p></body></html>");
+        assert!(content.contains("<p>\u{201c}Hi\u{201d}</p>"));
+    }
+
+    #[test]
+    fn test_should_run() {
+        let mut opts = ConversionOptions::default();
+        assert!(!SmartenPunctuation.should_run(&opts));
+        opts.smarten_punctuation = true;
+        assert!(SmartenPunctuation.should_run(&opts));
+    }
+}
@@ -0,0 +1,177 @@
+//! SpineOrder — reorders the spine to match a user-supplied list of hrefs.
+//!
+//! For inputs whose detected reading order is wrong (e.g. an HTML folder
+//! imported in directory order), `spine_order` names a text file with one
+//! document href per line. Listed documents come first, in file order;
+//! anything not listed keeps its relative order after them.
+
+use std::collections::HashMap;
+use std::path::Path;
+
+use convert_core::book::{BookDocument, Spine};
+use convert_core::error::{ConvertError, Result};
+use convert_core::options::ConversionOptions;
+use convert_core::plugin::Transform;
+
+/// Applies the reading order from the `spine_order` file.
+pub struct SpineOrder;
+
+impl Transform for SpineOrder {
+    fn name(&self) -> &str {
+        "SpineOrder"
+    }
+
+    fn should_run(&self, options: &ConversionOptions) -> bool {
+        options.spine_order.is_some()
+    }
+
+    fn apply(&self, book: &mut BookDocument, options: &ConversionOptions) -> Result<()> {
+        let Some(ref path) = options.spine_order else {
+            return Ok(());
+        };
+        let hrefs = read_order_file(path)?;
+
+        // Spine position of each document, by href
+        let positions: HashMap<&str, usize> = book
+            .spine
+            .iter()
+            .enumerate()
+            .filter_map(|(pos, s)| {
+                book.manifest
+                    .by_id(&s.idref)
+                    .map(|item| (item.href.as_str(), pos))
+            })
+            .collect();
+
+        let mut order: Vec<usize> = Vec::with_capacity(book.spine.len());
+        let mut placed = vec![false; book.spine.len()];
+        for href in &hrefs {
+            match positions.get(href.as_str()) {
+                Some(&pos) if !placed[pos] => {
+                    placed[pos] = true;
+                    order.push(pos);
+                }
+                Some(_) => {}
+                None => log::warn!("spine_order: '{}' is not a spine document", href),
+            }
+        }
+        let listed = order.len();
+        order.extend((0..book.spine.len()).filter(|&pos| !placed[pos]));
+
+        let items = book.spine.items().to_vec();
+        let mut spine = Spine::new();
+        spine.page_progression_direction = book.spine.page_progression_direction;
+        for pos in order {
+            spine.push(items[pos].idref.clone(), items[pos].linear);
+        }
+        book.spine = spine;
+
+        log::info!(
+            "Reordered spine from {}: {} listed, {} appended",
+            path.display(),
+            listed,
+            book.spine.len() - listed
+        );
+        Ok(())
+    }
+}
+
+/// Hrefs from the order file, skipping blank lines and `#` comments.
+fn read_order_file(path: &Path) -> Result<Vec<String>> {
+    let content = std::fs::read_to_string(path).map_err(|e| {
+        ConvertError::Other(format!(
+            "Cannot read spine order file {}: {}",
+            path.display(),
+            e
+        ))
+    })?;
+    Ok(content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| line.trim_start_matches("./").to_string())
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use convert_core::book::{ManifestData, ManifestItem};
+
+    fn book_with(hrefs: &[&str]) -> BookDocument {
+        let mut book = BookDocument::new();
+        for (i, href) in hrefs.iter().enumerate() {
+            let id = format!("doc{}", i + 1);
+            book.manifest.add(ManifestItem::new(
+                id.as_str(),
+                *href,
+                "application/xhtml+xml",
+                ManifestData::Xhtml("<html><body/></html>".into()),
+            ));
+            book.spine.push(id.as_str(), true);
+        }
+        book
+    }
+
+    fn spine_hrefs(book: &BookDocument) -> Vec<&str> {
+        book.spine
+            .iter()
+            .map(|s| book.manifest.by_id(&s.idref).unwrap().href.as_str())
+            .collect()
+    }
+
+    fn order_file(name: &str, content: &str) -> std::path::PathBuf {
+        let path = std::env::temp_dir().join(name);
+        std::fs::write(&path, content).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_reverses_two_documents() {
+        let mut book = book_with(&["a.html", "b.html"]);
+        let path = order_file("spine_order_reverse.txt", "b.html\na.html\n");
+        let opts = ConversionOptions {
+            spine_order: Some(path.clone()),
+            ..Default::default()
+        };
+        SpineOrder.apply(&mut book, &opts).unwrap();
+        std::fs::remove_file(&path).ok();
+
+        assert_eq!(spine_hrefs(&book), vec!["b.html", "a.html"]);
+    }
+
+    #[test]
+    fn test_unlisted_documents_go_last() {
+        let mut book = book_with(&["ch1.html", "ch2.html", "ch3.html", "notes.html"]);
+        book.spine.remove("doc4");
+        book.spine.push("doc4", false);
+        let path = order_file(
+            "spine_order_partial.txt",
+            "# fixed order\n./ch3.html\n\nmissing.html\nch1.html\n",
+        );
+        let opts = ConversionOptions {
+            spine_order: Some(path.clone()),
+            ..Default::default()
+        };
+        SpineOrder.apply(&mut book, &opts).unwrap();
+        std::fs::remove_file(&path).ok();
+
+        assert_eq!(
+            spine_hrefs(&book),
+            vec!["ch3.html", "ch1.html", "ch2.html", "notes.html"]
+        );
+        // Linear flags travel with their documents
+        assert!(!book.spine.items()[3].linear);
+    }
+
+    #[test]
+    fn test_missing_order_file() {
+        let mut book = book_with(&["a.html"]);
+        let opts = ConversionOptions {
+            spine_order: Some("/nonexistent/order.txt".into()),
+            ..Default::default()
+        };
+        let err = SpineOrder.apply(&mut book, &opts).unwrap_err();
+        assert!(err.to_string().contains("Cannot read spine order file"));
+    }
+}
@@ -2,11 +2,21 @@
 //!
 //! Calibre splits at `<h1>` and `<h2>` tags (configurable via `split_on_page_break`).
 //! This produces multiple smaller XHTML files for better e-reader performance.
+//! With `chapter_detection_regex` set, splits instead at block elements whose
+//! text matches the pattern, regardless of document size. A `chapter_marker`
+//! line is a hard boundary: documents split there and the marker is dropped.
+//! With `max_flow_size` set, documents still over that many bytes are then
+//! split between block elements, for readers that choke on large files.
+//! TOC entries and in-document links with a `#fragment` are pointed at the
+//! chunk that ends up holding the id.
+
+use std::collections::HashMap;
+use std::sync::LazyLock;
 
 use rayon::prelude::*;
 
 use convert_core::book::{BookDocument, ManifestData, ManifestItem, TocEntry};
-use convert_core::error::Result;
+use convert_core::error::{ConvertError, Result};
 use convert_core::options::ConversionOptions;
 use convert_core::plugin::Transform;
 use regex::Regex;
@@ -22,7 +32,22 @@ impl Transform for SplitChapters {
         "SplitChapters"
     }
 
-    fn apply(&self, book: &mut BookDocument, _options: &ConversionOptions) -> Result<()> {
+    fn apply(&self, book: &mut BookDocument, options: &ConversionOptions) -> Result<()> {
+        // Validate a custom split pattern before touching the book
+        let pattern = match options.chapter_detection_regex.as_deref() {
+            Some(p) => Some(Regex::new(p).map_err(|e| {
+                ConvertError::Other(format!("Invalid chapter_detection_regex '{}': {}", p, e))
+            })?),
+            None => None,
+        };
+        let marker = options
+            .chapter_marker
+            .as_deref()
+            .map(str::trim)
+            .filter(|m| !m.is_empty());
+        let custom = marker.is_some() || pattern.is_some();
+        let min_size = if custom { 0 } else { MIN_SPLIT_SIZE };
+
         // Collect spine items that are candidates for splitting
         let candidates: Vec<(String, String, String)> = book
             .spine
@@ -31,7 +56,7 @@ impl Transform for SplitChapters {
                 book.manifest.by_id(&s.idref).and_then(|item| {
                     if item.is_xhtml() {
                         item.data.as_xhtml().and_then(|x| {
-                            if x.len() >= MIN_SPLIT_SIZE {
+                            if x.len() >= min_size {
                                 Some((s.idref.clone(), item.href.clone(), x.to_string()))
                             } else {
                                 None
@@ -48,7 +73,11 @@ impl Transform for SplitChapters {
         let split_results: Vec<(String, String, Vec<ContentChunk>)> = candidates
             .into_par_iter()
             .filter_map(|(idref, href, xhtml)| {
-                let chunks = split_at_headings(&xhtml);
+                let chunks = match (marker, &pattern) {
+                    (Some(m), _) => split_at_marker(&xhtml, m),
+                    (None, Some(re)) => split_at_pattern(&xhtml, re),
+                    (None, None) => split_at_headings(&xhtml),
+                };
                 if chunks.len() > 1 {
                     Some((idref, href, chunks))
                 } else {
@@ -64,64 +93,251 @@ impl Transform for SplitChapters {
                 original_href,
                 chunks.len()
             );
+            let new_ids = apply_split(book, &idref, &original_href, &chunks, &wrap_body_xhtml);
+            if custom {
+                add_toc_entries(book, &original_href, &chunks, &new_ids);
+            }
+        }
 
-            // Find spine position of this item
-            let spine_pos = book.spine.iter().position(|s| s.idref == *idref).unwrap();
+        if let Some(max_size) = options.max_flow_size {
+            split_oversized(book, max_size);
+        }
 
-            // Remove original from spine (we'll replace it)
-            book.spine.remove(&idref);
+        Ok(())
+    }
+}
 
-            // Create new manifest items for each chunk
-            let mut new_ids: Vec<String> = Vec::new();
-            for (i, chunk) in chunks.iter().enumerate() {
-                let new_id = if i == 0 {
-                    // Reuse the original ID for the first chunk
-                    idref.clone()
-                } else {
-                    book.manifest.generate_id(&format!("{}_ch", idref))
-                };
+/// Replace spine document `idref` with one document per chunk, each built
+/// by `wrap(body, title)`, and point TOC entries and in-document links at
+/// the new files. Returns the chunks' manifest ids.
+fn apply_split(
+    book: &mut BookDocument,
+    idref: &str,
+    original_href: &str,
+    chunks: &[ContentChunk],
+    wrap: &dyn Fn(&str, &str) -> String,
+) -> Vec<String> {
+    // Find spine position of this item
+    let spine_pos = book.spine.iter().position(|s| s.idref == idref).unwrap();
 
-                let new_href = if i == 0 {
-                    original_href.clone()
-                } else {
-                    let base = original_href.trim_end_matches(".xhtml");
-                    book.manifest
-                        .generate_href(&format!("{}_ch{}", base, i), "xhtml")
-                };
+    // Remove original from spine (we'll replace it)
+    book.spine.remove(idref);
 
-                let xhtml_doc = wrap_body_xhtml(&chunk.body, &chunk.title);
+    // Chunk hrefs: the first chunk keeps the original
+    let new_hrefs: Vec<String> = (0..chunks.len())
+        .map(|i| {
+            if i == 0 {
+                original_href.to_string()
+            } else {
+                let base = original_href.trim_end_matches(".xhtml");
+                book.manifest
+                    .generate_href(&format!("{}_ch{}", base, i), "xhtml")
+            }
+        })
+        .collect();
+    let id_hrefs = chunk_ids(chunks, &new_hrefs);
 
-                if i == 0 {
-                    // Update existing manifest item
-                    if let Some(item) = book.manifest.by_id_mut(&new_id) {
-                        item.data = ManifestData::Xhtml(xhtml_doc);
-                    }
-                } else {
-                    let item = ManifestItem::new(
-                        &new_id,
-                        &new_href,
-                        "application/xhtml+xml",
-                        ManifestData::Xhtml(xhtml_doc),
-                    );
-                    book.manifest.add(item);
-                }
+    // Create new manifest items for each chunk
+    let mut new_ids: Vec<String> = Vec::new();
+    for (i, chunk) in chunks.iter().enumerate() {
+        let new_id = if i == 0 {
+            // Reuse the original ID for the first chunk
+            idref.to_string()
+        } else {
+            book.manifest.generate_id(&format!("{}_ch", idref))
+        };
+        let new_href = &new_hrefs[i];
 
-                new_ids.push(new_id);
-            }
+        let body = relink_fragments(&chunk.body, new_href, &id_hrefs);
+        let xhtml_doc = wrap(&body, &chunk.title);
 
-            // Insert all new IDs into spine at the original position
-            for (i, new_id) in new_ids.iter().enumerate() {
-                book.spine.insert(spine_pos + i, new_id, true);
+        if i == 0 {
+            // Update existing manifest item
+            if let Some(item) = book.manifest.by_id_mut(&new_id) {
+                item.data = ManifestData::Xhtml(xhtml_doc);
             }
+        } else {
+            let item = ManifestItem::new(
+                &new_id,
+                new_href,
+                "application/xhtml+xml",
+                ManifestData::Xhtml(xhtml_doc),
+            );
+            book.manifest.add(item);
+        }
+
+        new_ids.push(new_id);
+    }
+
+    // Insert all new IDs into spine at the original position
+    for (i, new_id) in new_ids.iter().enumerate() {
+        book.spine.insert(spine_pos + i, new_id, true);
+    }
+
+    // Update TOC entries: point to correct chapter files
+    update_toc_hrefs(book, original_href, chunks, &new_ids, &id_hrefs);
+    new_ids
+}
+
+/// Split spine documents larger than `max_size` bytes at block boundaries.
+/// Each part keeps the original document's `<head>`.
+fn split_oversized(book: &mut BookDocument, max_size: usize) {
+    let oversized: Vec<(String, String, String)> = book
+        .spine
+        .iter()
+        .filter_map(|s| {
+            let item = book.manifest.by_id(&s.idref)?;
+            let xhtml = item.data.as_xhtml().filter(|x| x.len() > max_size)?;
+            Some((s.idref.clone(), item.href.clone(), xhtml.to_string()))
+        })
+        .collect();
 
-            // Update TOC entries: point to correct chapter files
-            update_toc_hrefs(book, &original_href, &chunks, &new_ids);
+    for (idref, href, xhtml) in oversized {
+        let Some(inner) = BODY_RE.captures(&xhtml).and_then(|cap| cap.get(1)) else {
+            continue;
+        };
+        let (head, tail) = (&xhtml[..inner.start()], &xhtml[inner.end()..]);
+        let budget = max_size.saturating_sub(head.len() + tail.len()).max(1);
+        let chunks: Vec<ContentChunk> = split_html(inner.as_str(), budget)
+            .into_iter()
+            .map(|body| ContentChunk {
+                title: String::new(),
+                body,
+            })
+            .collect();
+        if chunks.len() < 2 {
+            continue;
         }
 
-        Ok(())
+        log::info!(
+            "Splitting '{}' into {} parts of at most {} bytes",
+            href,
+            chunks.len(),
+            max_size
+        );
+        let wrap = |body: &str, _: &str| format!("{}{}{}", head, body, tail);
+        apply_split(book, &idref, &href, &chunks, &wrap);
     }
 }
 
+/// A document's `<body>` and its inner HTML.
+static BODY_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?is)<body[^>]*>(.*)</body>").unwrap());
+
+/// Elements that never have content or a closing tag.
+const VOID_ELEMENTS: &[&str] = &[
+    "area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta", "param", "source",
+    "track", "wbr",
+];
+
+/// Split `html` into parts of at most `max_size` bytes between top-level
+/// elements. An element too big on its own is split inside, each part
+/// wrapped in a copy of it (the id kept on the first copy only).
+fn split_html(html: &str, max_size: usize) -> Vec<String> {
+    let mut parts = Vec::new();
+    let mut current = String::new();
+    for piece in top_level_pieces(html) {
+        if !current.trim().is_empty() && current.len() + piece.len() > max_size {
+            parts.push(std::mem::take(&mut current));
+        }
+        if piece.len() > max_size {
+            if let Some(mut inner_parts) = split_element(piece, max_size) {
+                current.push_str(&inner_parts.remove(0));
+                for part in inner_parts {
+                    parts.push(std::mem::take(&mut current));
+                    current = part;
+                }
+                continue;
+            }
+        }
+        current.push_str(piece);
+    }
+    if !current.trim().is_empty() || parts.is_empty() {
+        parts.push(current);
+    } else if let Some(last) = parts.last_mut() {
+        last.push_str(&current);
+    }
+    parts
+}
+
+/// Split the content of a single element into parts, each wrapped in the
+/// element's tags. `None` if it has no smaller pieces to split between.
+fn split_element(element: &str, max_size: usize) -> Option<Vec<String>> {
+    static OPEN_RE: LazyLock<Regex> =
+        LazyLock::new(|| Regex::new(r"^<([A-Za-z][\w:-]*)[^>]*>").unwrap());
+    static ID_RE: LazyLock<Regex> =
+        LazyLock::new(|| Regex::new(r#"\sid\s*=\s*("[^"]*"|'[^']*')"#).unwrap());
+
+    let open = OPEN_RE.captures(element)?;
+    let close = format!("</{}>", &open[1]);
+    let open_tag = open.get(0)?.as_str();
+    let inner = element
+        .strip_suffix(close.as_str())?
+        .get(open_tag.len()..)?;
+
+    let budget = max_size.saturating_sub(open_tag.len() + close.len()).max(1);
+    let parts = split_html(inner, budget);
+    if parts.len() < 2 {
+        return None;
+    }
+    let repeat_tag = ID_RE.replace(open_tag, "");
+    Some(
+        parts
+            .iter()
+            .enumerate()
+            .map(|(i, part)| {
+                let tag = if i == 0 { open_tag } else { &repeat_tag };
+                format!("{}{}{}", tag, part, close)
+            })
+            .collect(),
+    )
+}
+
+/// The top-level pieces of `html`: whole elements, and the text between them.
+fn top_level_pieces(html: &str) -> Vec<&str> {
+    static TAG_RE: LazyLock<Regex> =
+        LazyLock::new(|| Regex::new(r"(?s)<!--.*?-->|<[^>]+>").unwrap());
+    static NAME_RE: LazyLock<Regex> =
+        LazyLock::new(|| Regex::new(r"^</?([A-Za-z][\w:-]*)").unwrap());
+
+    let mut pieces = Vec::new();
+    let mut start = 0;
+    let mut depth = 0usize;
+    for tag in TAG_RE.find_iter(html) {
+        let text = tag.as_str();
+        let Some(name) = NAME_RE
+            .captures(text)
+            .map(|cap| cap[1].to_ascii_lowercase())
+        else {
+            continue; // comments, processing instructions
+        };
+        if text.starts_with("</") {
+            depth = depth.saturating_sub(1);
+            if depth == 0 {
+                pieces.push(&html[start..tag.end()]);
+                start = tag.end();
+            }
+            continue;
+        }
+        if depth == 0 && tag.start() > start {
+            pieces.push(&html[start..tag.start()]);
+            start = tag.start();
+        }
+        if text.ends_with("/>") || VOID_ELEMENTS.contains(&name.as_str()) {
+            if depth == 0 {
+                pieces.push(text);
+                start = tag.end();
+            }
+        } else {
+            depth += 1;
+        }
+    }
+    if start < html.len() {
+        pieces.push(&html[start..]);
+    }
+    pieces
+}
+
 /// A chunk of content split from a larger document.
 struct ContentChunk {
     /// The heading title (empty for the first chunk before any heading)
@@ -134,10 +350,8 @@ struct ContentChunk {
 /// Returns a list of content chunks. The first chunk contains content before
 /// the first split point (if any).
 fn split_at_headings(xhtml: &str) -> Vec<ContentChunk> {
-    // Extract body content
-    let body_re = Regex::new(r"(?is)<body[^>]*>(.*)</body>").unwrap();
-    let body_content = match body_re.captures(xhtml) {
-        Some(cap) => cap[1].to_string(),
+    let body_content = match extract_body(xhtml) {
+        Some(body) => body,
         None => {
             return vec![ContentChunk {
                 title: String::new(),
@@ -224,6 +438,155 @@ fn split_at_headings(xhtml: &str) -> Vec<ContentChunk> {
     chunks
 }
 
+/// Block elements whose text is tested against `chapter_detection_regex`.
+const SPLIT_BLOCKS: &[&str] = &["p", "div", "h1", "h2", "h3", "h4", "h5", "h6"];
+
+/// A whole element: its name, content and closing name.
+static BLOCK_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?is)^<([a-z][\w:-]*)(?:\s[^>]*)?>(.*)</([a-z][\w:-]*)\s*>$").unwrap()
+});
+
+/// Split XHTML content before each block element whose text matches `pattern`.
+/// The matching text becomes the chunk title.
+fn split_at_pattern(xhtml: &str, pattern: &Regex) -> Vec<ContentChunk> {
+    let body_content = match extract_body(xhtml) {
+        Some(body) => body,
+        None => {
+            return vec![ContentChunk {
+                title: String::new(),
+                body: xhtml.to_string(),
+            }]
+        }
+    };
+
+    // Only top-level blocks are split points: splitting inside a wrapper
+    // would leave its tags unbalanced in both chunks
+    let mut split_positions: Vec<(usize, String)> = Vec::new();
+    let mut pos = 0;
+    for piece in top_level_pieces(&body_content) {
+        if let Some(cap) = BLOCK_RE.captures(piece) {
+            let name = cap[1].to_ascii_lowercase();
+            if SPLIT_BLOCKS.contains(&name.as_str()) && cap[3].eq_ignore_ascii_case(&name) {
+                if let Some(text) = convert_utils::xml::heading_text(&cap[2]) {
+                    if pattern.is_match(&text) {
+                        split_positions.push((pos, text));
+                    }
+                }
+            }
+        }
+        pos += piece.len();
+    }
+
+    let mut chunks = Vec::new();
+    let first_end = split_positions
+        .first()
+        .map(|(pos, _)| *pos)
+        .unwrap_or(body_content.len());
+    let preamble = body_content[..first_end].trim();
+    if !preamble.is_empty() {
+        chunks.push(ContentChunk {
+            title: String::new(),
+            body: preamble.to_string(),
+        });
+    }
+
+    for (i, (start, title)) in split_positions.iter().enumerate() {
+        let end = split_positions
+            .get(i + 1)
+            .map(|(pos, _)| *pos)
+            .unwrap_or(body_content.len());
+        chunks.push(ContentChunk {
+            title: title.clone(),
+            body: body_content[*start..end].trim().to_string(),
+        });
+    }
+
+    chunks
+}
+
+/// Line breaks within a paragraph, which separate marker lines.
+static BR_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"(?i)<br\s*/?>").unwrap());
+
+/// The first heading of a chunk, which becomes its title.
+static HEADING_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?is)<h[1-6][^>]*>(.*?)</h[1-6]\s*>").unwrap());
+
+/// Split XHTML content at lines consisting of exactly `marker`, dropping them.
+///
+/// A marker line is either a whole top-level block (`<p>marker</p>`) or a
+/// line of a top-level paragraph between `<br/>`s, as produced by TXT input.
+/// Markers nested inside other elements are left alone, since splitting
+/// there would leave unbalanced tags in both chunks. Each chunk takes the
+/// text of its first heading, if any, as its title.
+fn split_at_marker(xhtml: &str, marker: &str) -> Vec<ContentChunk> {
+    let body_content = match extract_body(xhtml) {
+        Some(body) => body,
+        None => {
+            return vec![ContentChunk {
+                title: String::new(),
+                body: xhtml.to_string(),
+            }]
+        }
+    };
+
+    let mark = convert_utils::xml::escape_xml_text(marker);
+    let mut bodies = vec![String::new()];
+    for piece in top_level_pieces(&body_content) {
+        let Some(cap) = BLOCK_RE.captures(piece) else {
+            bodies.last_mut().unwrap().push_str(piece);
+            continue;
+        };
+        let name = cap[1].to_ascii_lowercase();
+        if !cap[3].eq_ignore_ascii_case(&name) {
+            bodies.last_mut().unwrap().push_str(piece);
+            continue;
+        }
+        match name.as_str() {
+            // Paragraphs are split line by line, so a marker between other
+            // lines closes the paragraph
+            "p" => {
+                let lines: Vec<&str> = BR_RE.split(&cap[2]).collect();
+                if !lines.iter().any(|line| line.trim() == mark) {
+                    bodies.last_mut().unwrap().push_str(piece);
+                    continue;
+                }
+                let attrs = &piece[2..piece.find('>').unwrap()];
+                for (i, group) in lines.split(|line| line.trim() == mark).enumerate() {
+                    if i > 0 {
+                        bodies.push(String::new());
+                    }
+                    if group.iter().any(|line| !line.trim().is_empty()) {
+                        let para = format!("<p{}>{}</p>", attrs, group.join("<br/>"));
+                        bodies.last_mut().unwrap().push_str(&para);
+                    }
+                }
+            }
+            "div" | "h1" | "h2" | "h3" | "h4" | "h5" | "h6" if cap[2].trim() == mark => {
+                bodies.push(String::new());
+            }
+            _ => bodies.last_mut().unwrap().push_str(piece),
+        }
+    }
+
+    bodies
+        .iter()
+        .map(|body| body.trim())
+        .filter(|body| !body.is_empty())
+        .map(|body| ContentChunk {
+            title: HEADING_RE
+                .captures(body)
+                .and_then(|cap| convert_utils::xml::heading_text(&cap[1]))
+                .unwrap_or_default(),
+            body: body.to_string(),
+        })
+        .collect()
+}
+
+/// The inner HTML of `<body>`, if the document has one.
+fn extract_body(xhtml: &str) -> Option<String> {
+    BODY_RE.captures(xhtml).map(|cap| cap[1].to_string())
+}
+
 /// Wrap body HTML in a minimal XHTML document.
 fn wrap_body_xhtml(body: &str, title: &str) -> String {
     let title_escaped =
@@ -244,12 +607,46 @@ fn wrap_body_xhtml(body: &str, title: &str) -> String {
     )
 }
 
+/// Map each element id in the chunks to the href of the chunk holding it.
+fn chunk_ids<'a>(chunks: &[ContentChunk], hrefs: &'a [String]) -> HashMap<String, &'a str> {
+    let id_re = Regex::new(r#"<[^>]*?\sid\s*=\s*["']([^"']+)["']"#).unwrap();
+    let mut ids = HashMap::new();
+    for (chunk, href) in chunks.iter().zip(hrefs) {
+        for cap in id_re.captures_iter(&chunk.body) {
+            ids.entry(cap[1].to_string()).or_insert(href.as_str());
+        }
+    }
+    ids
+}
+
+/// Point `href="#id"` links in a chunk at `href` to the chunk now holding
+/// the id, when that is another one.
+fn relink_fragments(body: &str, href: &str, id_hrefs: &HashMap<String, &str>) -> String {
+    static LINK_RE: LazyLock<Regex> =
+        LazyLock::new(|| Regex::new(r##"(\shref\s*=\s*["'])#([^"']+)"##).unwrap());
+    LINK_RE
+        .replace_all(body, |cap: &regex::Captures| match id_hrefs.get(&cap[2]) {
+            Some(&target) if target != href => format!(
+                "{}{}#{}",
+                &cap[1],
+                convert_utils::href::relative(href, target),
+                &cap[2]
+            ),
+            _ => cap[0].to_string(),
+        })
+        .into_owned()
+}
+
 /// Update TOC entry hrefs to point to the correct split chapter files.
+///
+/// Entries with a fragment follow the id to its chunk; others are matched
+/// to a chunk by title.
 fn update_toc_hrefs(
     book: &mut BookDocument,
     original_href: &str,
     chunks: &[ContentChunk],
     new_ids: &[String],
+    id_hrefs: &HashMap<String, &str>,
 ) {
     // Build a mapping: heading title → new href
     let mut title_to_href: Vec<(String, String)> = Vec::new();
@@ -263,14 +660,63 @@ fn update_toc_hrefs(
 
     // Update TOC entries that reference the original href
     for entry in &mut book.toc.entries {
-        update_toc_entry(entry, original_href, &title_to_href);
+        update_toc_entry(entry, original_href, &title_to_href, id_hrefs);
     }
 }
 
-fn update_toc_entry(entry: &mut TocEntry, original_href: &str, title_to_href: &[(String, String)]) {
+/// Add a TOC entry for each titled chunk that no existing entry points to,
+/// placed after the last top-level entry for the original document.
+fn add_toc_entries(
+    book: &mut BookDocument,
+    original_href: &str,
+    chunks: &[ContentChunk],
+    new_ids: &[String],
+) {
+    let mut insert_at = book
+        .toc
+        .entries
+        .iter()
+        .rposition(|e| e.href.split('#').next() == Some(original_href))
+        .map(|i| i + 1)
+        .unwrap_or(book.toc.entries.len());
+
+    for (chunk, id) in chunks.iter().zip(new_ids) {
+        if chunk.title.is_empty() {
+            continue;
+        }
+        let Some(href) = book.manifest.by_id(id).map(|item| item.href.clone()) else {
+            continue;
+        };
+        let exists = book
+            .toc
+            .iter_depth_first()
+            .any(|e| e.title == chunk.title && e.href == href);
+        if !exists {
+            book.toc
+                .entries
+                .insert(insert_at, TocEntry::new(&chunk.title, &href));
+            insert_at += 1;
+        }
+    }
+}
+
+fn update_toc_entry(
+    entry: &mut TocEntry,
+    original_href: &str,
+    title_to_href: &[(String, String)],
+    id_hrefs: &HashMap<String, &str>,
+) {
     // Strip fragment from href for comparison
-    let entry_href_base = entry.href.split('#').next().unwrap_or(&entry.href);
-    if entry_href_base == original_href {
+    let (entry_href_base, fragment) = match entry.href.split_once('#') {
+        Some((base, fragment)) => (base, Some(fragment)),
+        None => (entry.href.as_str(), None),
+    };
+    if let Some(target) = fragment
+        .filter(|_| entry_href_base == original_href)
+        .and_then(|f| id_hrefs.get(f))
+    {
+        entry.href = format!("{}#{}", target, fragment.unwrap_or_default());
+    } else if entry_href_base == original_href {
         // Try to match by title
         for (title, new_href) in title_to_href {
             if entry.title == *title {
@@ -282,7 +728,7 @@ fn update_toc_entry(entry: &mut TocEntry, original_href: &str, title_to_href: &[
 
     // Recurse into children
     for child in &mut entry.children {
-        update_toc_entry(child, original_href, title_to_href);
+        update_toc_entry(child, original_href, title_to_href, id_hrefs);
     }
 }
 
@@ -363,6 +809,160 @@ mod tests {
         assert!(chunks[2].body.contains("Chapter 3"));
     }
 
+    fn scene_break_book() -> BookDocument {
+        let mut book = BookDocument::new();
+        let xhtml = r#"<html><body>
+<p>Part one.</p>
+<p>* * *</p>
+<p>Part two.</p>
+<p class="sep"> *  *  * </p>
+<p>Part three.</p>
+</body></html>"#;
+        book.manifest.add(ManifestItem::new(
+            "content",
+            "content.xhtml",
+            "application/xhtml+xml",
+            ManifestData::Xhtml(xhtml.to_string()),
+        ));
+        book.spine.push("content", true);
+        book
+    }
+
+    #[test]
+    fn test_split_at_pattern() {
+        let mut book = scene_break_book();
+        let options = ConversionOptions {
+            chapter_detection_regex: Some(r"^\*\s*\*\s*\*$".to_string()),
+            ..Default::default()
+        };
+        SplitChapters.apply(&mut book, &options).unwrap();
+
+        // Small documents are split too when a pattern is given
+        assert_eq!(book.spine.len(), 3);
+        let bodies: Vec<&str> = book
+            .spine
+            .iter()
+            .map(|s| {
+                book.manifest
+                    .by_id(&s.idref)
+                    .unwrap()
+                    .data
+                    .as_xhtml()
+                    .unwrap()
+            })
+            .collect();
+        assert!(bodies[0].contains("Part one.") && !bodies[0].contains("Part two."));
+        assert!(bodies[1].contains("* * *") && bodies[1].contains("Part two."));
+        assert!(bodies[2].contains("Part three."));
+
+        // One TOC entry per match, pointing at the new files
+        assert_eq!(book.toc.entries.len(), 2);
+        assert!(book.toc.entries.iter().all(|e| e.title == "* * *"));
+        assert_ne!(book.toc.entries[0].href, book.toc.entries[1].href);
+        assert!(book.toc.entries.iter().all(|e| e.href.contains("_ch")));
+    }
+
+    #[test]
+    fn test_split_at_pattern_top_level_only() {
+        let pattern = Regex::new(r"^\*\s*\*\s*\*$").unwrap();
+        let xhtml = "<html><body><p>One.</p><div class=\"scene\"><p>* * *</p><p>Two.</p></div>\
+            <p>* * *</p><p>Three.</p></body></html>";
+
+        let chunks = split_at_pattern(xhtml, &pattern);
+        assert_eq!(chunks.len(), 2);
+        // The wrapper stays whole, its nested marker is not a split point
+        assert!(
+            chunks[0].body.ends_with("<p>Two.</p></div>"),
+            "{}",
+            chunks[0].body
+        );
+        assert_eq!(chunks[1].title, "* * *");
+        assert_eq!(chunks[1].body, "<p>* * *</p><p>Three.</p>");
+    }
+
+    #[test]
+    fn test_split_pattern_unset_keeps_heading_behavior() {
+        let mut book = scene_break_book();
+        SplitChapters
+            .apply(&mut book, &ConversionOptions::default())
+            .unwrap();
+        assert_eq!(book.spine.len(), 1);
+        assert!(book.toc.entries.is_empty());
+    }
+
+    #[test]
+    fn test_invalid_split_pattern() {
+        let mut book = scene_break_book();
+        let options = ConversionOptions {
+            chapter_detection_regex: Some("(unclosed".to_string()),
+            ..Default::default()
+        };
+        let err = SplitChapters.apply(&mut book, &options).unwrap_err();
+        assert!(err.to_string().contains("chapter_detection_regex"));
+        assert_eq!(book.spine.len(), 1);
+    }
+
+    #[test]
+    fn test_split_at_chapter_marker() {
+        let mut book = BookDocument::new();
+        book.manifest.add(ManifestItem::new(
+            "content",
+            "content.xhtml",
+            "application/xhtml+xml",
+            ManifestData::Xhtml(
+                "<html><body><p>Intro text.</p>\n<p>---PAGEBREAK---</p>\n\
+                 <h1>Second</h1>\n<p>Line one<br/>\n---PAGEBREAK---<br/>\nLine two</p>\n\
+                 <div> ---PAGEBREAK--- </div>\n<p>Last part.</p></body></html>"
+                    .into(),
+            ),
+        ));
+        book.spine.push("content", true);
+        let options = ConversionOptions {
+            chapter_marker: Some("---PAGEBREAK---".to_string()),
+            ..Default::default()
+        };
+        SplitChapters.apply(&mut book, &options).unwrap();
+
+        assert_eq!(book.spine.len(), 4);
+        let bodies: Vec<&str> = book
+            .spine
+            .iter()
+            .map(|s| {
+                book.manifest
+                    .by_id(&s.idref)
+                    .unwrap()
+                    .data
+                    .as_xhtml()
+                    .unwrap()
+            })
+            .collect();
+        assert!(bodies.iter().all(|b| !b.contains("PAGEBREAK")));
+        assert!(bodies[0].contains("<p>Intro text.</p>"));
+        assert!(bodies[1].contains("<h1>Second</h1>") && bodies[1].contains("<p>Line one</p>"));
+        assert!(bodies[2].contains("<p>\nLine two</p>"));
+        assert!(bodies[3].contains("<p>Last part.</p>"));
+
+        // Only the chunk with a heading gets a TOC entry
+        assert_eq!(book.toc.entries.len(), 1);
+        assert_eq!(book.toc.entries[0].title, "Second");
+    }
+
+    #[test]
+    fn test_chapter_marker_nested_in_wrapper() {
+        let xhtml = "<html><body><div class=\"part\"><p>One</p><p>***</p><p>Two</p></div>\n\
+                     <p>***</p>\n<blockquote><p>Three<br/>***<br/>Four</p></blockquote></body></html>";
+        let chunks = split_at_marker(xhtml, "***");
+        let bodies: Vec<&str> = chunks.iter().map(|c| c.body.as_str()).collect();
+        // Only the top-level marker splits; the wrappers stay whole
+        assert_eq!(
+            bodies,
+            [
+                "<div class=\"part\"><p>One</p><p>***</p><p>Two</p></div>",
+                "<blockquote><p>Three<br/>***<br/>Four</p></blockquote>",
+            ]
+        );
+    }
+
     #[test]
     fn test_wrap_body_xhtml() {
         let result = wrap_body_xhtml("<p>Hello</p>", "Test Title");
@@ -422,4 +1022,126 @@ mod tests {
         // At least some should point to new chapter files
         assert!(hrefs.iter().any(|h| h.contains("_ch")));
     }
+
+    #[test]
+    fn test_fragments_follow_ids_into_chunks() {
+        let mut book = BookDocument::new();
+        let mut body = String::from(
+            r##"<p><a href="#mark1">One</a> <a href="#mark2">Two</a></p><div class="mbp_pagebreak"></div>"##,
+        );
+        for i in 1..=2 {
+            body.push_str(&format!(r#"<p><span id="mark{}"></span>Part {}</p>"#, i, i));
+            body.push_str(&"<p>Filler text for the part.</p>".repeat(200));
+            body.push_str(r#"<div class="mbp_pagebreak"></div>"#);
+        }
+        book.manifest.add(ManifestItem::new(
+            "content",
+            "content.xhtml",
+            "application/xhtml+xml",
+            ManifestData::Xhtml(format!("<html><body>{}</body></html>", body)),
+        ));
+        book.spine.push("content", true);
+        let mut part1 = TocEntry::new("Part One", "content.xhtml#mark1");
+        part1.add_child(TocEntry::new("Part Two", "content.xhtml#mark2"));
+        book.toc.add(part1);
+
+        SplitChapters
+            .apply(&mut book, &ConversionOptions::default())
+            .unwrap();
+
+        assert_eq!(book.spine.len(), 3);
+        let entry = &book.toc.entries[0];
+        assert_eq!(entry.href, "content_ch1.xhtml#mark1");
+        assert_eq!(entry.children[0].href, "content_ch2.xhtml#mark2");
+        let first = book.manifest.by_id("content").unwrap();
+        let first = first.data.as_xhtml().unwrap();
+        assert!(first.contains(r##"<a href="content_ch1.xhtml#mark1">One</a>"##));
+        assert!(first.contains(r##"<a href="content_ch2.xhtml#mark2">Two</a>"##));
+    }
+
+    #[test]
+    fn test_split_by_max_flow_size() {
+        let mut book = BookDocument::new();
+        let mut body = String::new();
+        let mut i = 0;
+        while body.len() < 2_000_000 {
+            i += 1;
+            if i == 20_000 {
+                body.push_str(r#"<p id="deep">Marked paragraph.</p>"#);
+            }
+            body.push_str(&format!("<p>Paragraph {} of one long chapter.</p>\n", i));
+        }
+        let head = r#"<html xmlns="http://www.w3.org/1999/xhtml"><head><title>Long</title><link rel="stylesheet" href="book.css"/></head><body>"#;
+        book.manifest.add(ManifestItem::new(
+            "long",
+            "long.xhtml",
+            "application/xhtml+xml",
+            ManifestData::Xhtml(format!("{}{}</body></html>", head, body)),
+        ));
+        book.spine.push("long", true);
+        book.toc.add(TocEntry::new("Marked", "long.xhtml#deep"));
+
+        let options = ConversionOptions {
+            max_flow_size: Some(500_000),
+            ..Default::default()
+        };
+        SplitChapters.apply(&mut book, &options).unwrap();
+
+        assert!(book.spine.len() >= 4, "{} parts", book.spine.len());
+        let docs: Vec<&str> = book
+            .spine
+            .iter()
+            .map(|s| {
+                book.manifest
+                    .by_id(&s.idref)
+                    .unwrap()
+                    .data
+                    .as_xhtml()
+                    .unwrap()
+            })
+            .collect();
+        assert!(docs
+            .iter()
+            .all(|d| d.len() <= 500_000 && d.starts_with(head)));
+
+        // Every paragraph survives, whole and in order
+        let rejoined: String = docs
+            .iter()
+            .map(|d| {
+                d.strip_prefix(head)
+                    .unwrap()
+                    .strip_suffix("</body></html>")
+                    .unwrap()
+            })
+            .collect();
+        assert_eq!(rejoined, body);
+
+        // The TOC anchor follows its paragraph into a later part
+        let target = &book.toc.entries[0].href;
+        let (file, fragment) = target.split_once('#').unwrap();
+        assert_ne!(file, "long.xhtml");
+        assert_eq!(fragment, "deep");
+        let holder = book.manifest.by_href(file).unwrap();
+        assert!(holder.data.as_xhtml().unwrap().contains(r#"id="deep""#));
+    }
+
+    #[test]
+    fn test_split_html_inside_wrapper() {
+        let paras = "<p>Some text here.</p>".repeat(10);
+        let html = format!(r#"<div id="w" class="c">{}</div>"#, paras);
+
+        let parts = split_html(&html, 100);
+        assert!(parts.len() > 1);
+        assert!(parts[0].starts_with(r#"<div id="w" class="c"><p>"#));
+        assert!(parts[1..]
+            .iter()
+            .all(|p| p.starts_with(r#"<div class="c"><p>"#) && p.ends_with("</p></div>")));
+        assert!(parts.iter().all(|p| p.len() <= 100));
+        let text: String = parts
+            .iter()
+            .map(|p| p.matches("Some text").count())
+            .sum::<usize>()
+            .to_string();
+        assert_eq!(text, "10");
+    }
 }
@@ -0,0 +1,296 @@
+//! TocFromHeadings — builds a nested TOC from `<h1>`–`<h3>` headings.
+//!
+//! Single-document inputs (TXT, RTF, HTML) come with at most a title entry
+//! in the TOC. When the TOC has no more than one entry, every spine document
+//! is scanned for headings; each gets an `id` (its own, if it has one) and a
+//! TOC entry pointing at it, nested by heading level. Books without such
+//! headings keep their TOC.
+
+use std::collections::HashSet;
+use std::sync::LazyLock;
+
+use rayon::prelude::*;
+use regex::{Captures, Regex};
+
+use convert_core::book::{BookDocument, ManifestData, TocEntry};
+use convert_core::error::Result;
+use convert_core::options::ConversionOptions;
+use convert_core::plugin::Transform;
+use convert_utils::xml::heading_text;
+
+static HEADING_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?is)<h([1-3])(\s[^>]*)?>(.*?)</h[1-3]\s*>").unwrap());
+static ID_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#"(?i)\sid\s*=\s*(?:"([^"]*)"|'([^']*)')"#).unwrap());
+
+/// Prefix of the ids given to headings that lack one.
+const ID_PREFIX: &str = "toc-heading";
+
+/// Replaces a missing or single-entry TOC with one built from headings.
+pub struct TocFromHeadings;
+
+impl Transform for TocFromHeadings {
+    fn name(&self) -> &str {
+        "TocFromHeadings"
+    }
+
+    fn apply(&self, book: &mut BookDocument, _options: &ConversionOptions) -> Result<()> {
+        if book.toc.iter_depth_first().nth(1).is_some() {
+            return Ok(());
+        }
+
+        let documents: Vec<(String, String, String)> = book
+            .spine
+            .iter()
+            .filter_map(|s| book.manifest.by_id(&s.idref))
+            .filter_map(|item| {
+                item.data
+                    .as_xhtml()
+                    .map(|x| (item.id.clone(), item.href.clone(), x.to_string()))
+            })
+            .collect();
+
+        let anchored: Vec<(String, String, Vec<Heading>)> = documents
+            .into_par_iter()
+            .map(|(id, href, xhtml)| {
+                let (xhtml, mut headings) = anchor_headings(&xhtml);
+                for heading in &mut headings {
+                    heading.href = format!("{}#{}", href, heading.href);
+                }
+                (id, xhtml, headings)
+            })
+            .collect();
+
+        let headings: Vec<Heading> = anchored
+            .iter()
+            .flat_map(|(_, _, headings)| headings.iter().cloned())
+            .collect();
+        if headings.is_empty() {
+            return Ok(());
+        }
+
+        for (id, xhtml, headings) in anchored {
+            if headings.is_empty() {
+                continue;
+            }
+            if let Some(item) = book.manifest.by_id_mut(&id) {
+                item.data = ManifestData::Xhtml(xhtml);
+            }
+        }
+
+        log::info!("Built TOC from {} headings", headings.len());
+        book.toc.entries = nest(headings);
+        book.toc.rationalize_play_orders();
+        Ok(())
+    }
+}
+
+/// A heading found in a document; `href` is the anchor it is reached by.
+#[derive(Debug, Clone)]
+struct Heading {
+    level: u8,
+    title: String,
+    href: String,
+}
+
+/// Give every titled `<h1>`–`<h3>` in `xhtml` an id, returning the updated
+/// document and its headings in order, with `href` set to the id.
+fn anchor_headings(xhtml: &str) -> (String, Vec<Heading>) {
+    let mut used: HashSet<String> = ID_RE
+        .captures_iter(xhtml)
+        .filter_map(|cap| cap.get(1).or_else(|| cap.get(2)))
+        .map(|m| m.as_str().to_string())
+        .collect();
+    let mut next = 1;
+    let mut headings = Vec::new();
+
+    let result = HEADING_RE.replace_all(xhtml, |cap: &Captures| {
+        let Some(title) = heading_text(&cap[3]) else {
+            return cap[0].to_string();
+        };
+        let level = cap[1].as_bytes()[0] - b'0';
+        let attrs = cap.get(2).map_or("", |m| m.as_str());
+        let existing = ID_RE
+            .captures(attrs)
+            .and_then(|c| c.get(1).or_else(|| c.get(2)))
+            .map(|m| m.as_str().to_string())
+            .filter(|id| !id.is_empty());
+
+        let (id, tag) = match existing {
+            Some(id) => (id, cap[0].to_string()),
+            None => {
+                let id = loop {
+                    let candidate = format!("{}-{}", ID_PREFIX, next);
+                    next += 1;
+                    if used.insert(candidate.clone()) {
+                        break candidate;
+                    }
+                };
+                let open_end = cap.get(3).unwrap().start() - cap.get(0).unwrap().start();
+                let open = &cap[0][..open_end - 1];
+                let tag = format!("{} id=\"{}\">{}", open, id, &cap[0][open_end..]);
+                (id, tag)
+            }
+        };
+        headings.push(Heading {
+            level,
+            title,
+            href: id,
+        });
+        tag
+    });
+
+    (result.into_owned(), headings)
+}
+
+/// Nest headings into a tree: each heading becomes a child of the closest
+/// preceding heading of a higher level.
+fn nest(headings: Vec<Heading>) -> Vec<TocEntry> {
+    let mut roots: Vec<TocEntry> = Vec::new();
+    let mut stack: Vec<(u8, TocEntry)> = Vec::new();
+
+    fn close(stack: &mut Vec<(u8, TocEntry)>, roots: &mut Vec<TocEntry>) {
+        let (_, entry) = stack.pop().unwrap();
+        match stack.last_mut() {
+            Some((_, parent)) => parent.add_child(entry),
+            None => roots.push(entry),
+        }
+    }
+
+    for heading in headings {
+        while stack
+            .last()
+            .is_some_and(|(level, _)| *level >= heading.level)
+        {
+            close(&mut stack, &mut roots);
+        }
+        let mut entry = TocEntry::new(heading.title, heading.href);
+        entry.klass = Some(format!("h{}", heading.level));
+        stack.push((heading.level, entry));
+    }
+    while !stack.is_empty() {
+        close(&mut stack, &mut roots);
+    }
+    roots
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use convert_core::book::ManifestItem;
+
+    fn book_with(documents: &[(&str, &str)]) -> BookDocument {
+        let mut book = BookDocument::new();
+        for (i, (href, body)) in documents.iter().enumerate() {
+            let id = format!("doc{}", i);
+            let xhtml = format!(
+                "<html><head><title>t</title></head><body>{}</body></html>",
+                body
+            );
+            book.manifest.add(ManifestItem::new(
+                &id,
+                *href,
+                "application/xhtml+xml",
+                ManifestData::Xhtml(xhtml),
+            ));
+            book.spine.push(&id, true);
+        }
+        book
+    }
+
+    #[test]
+    fn test_two_level_toc_from_headings() {
+        let mut book = book_with(&[
+            (
+                "ch1.xhtml",
+                "<h1>Chapter One</h1><p>a</p><h2>Arrival</h2><p>b</p><h2 id=\"dep\">Departure</h2>",
+            ),
+            (
+                "ch2.xhtml",
+                "<h1 class=\"c\">Chapter <em>Two</em></h1><h2>Home</h2>",
+            ),
+        ]);
+        TocFromHeadings
+            .apply(&mut book, &ConversionOptions::default())
+            .unwrap();
+
+        let toc = &book.toc.entries;
+        assert_eq!(toc.len(), 2);
+        assert_eq!(toc[0].title, "Chapter One");
+        assert_eq!(toc[0].href, "ch1.xhtml#toc-heading-1");
+        let children: Vec<(&str, &str)> = toc[0]
+            .children
+            .iter()
+            .map(|e| (e.title.as_str(), e.href.as_str()))
+            .collect();
+        assert_eq!(
+            children,
+            vec![
+                ("Arrival", "ch1.xhtml#toc-heading-2"),
+                ("Departure", "ch1.xhtml#dep")
+            ]
+        );
+        assert_eq!(toc[1].title, "Chapter Two");
+        assert_eq!(toc[1].children[0].href, "ch2.xhtml#toc-heading-2");
+        assert_eq!(book.toc.depth(), 2);
+        assert_eq!(toc[1].children[0].play_order, Some(5));
+
+        let ch2 = book
+            .manifest
+            .by_id("doc1")
+            .unwrap()
+            .data
+            .as_xhtml()
+            .unwrap();
+        assert!(ch2.contains(r#"<h1 class="c" id="toc-heading-1">Chapter <em>Two</em></h1>"#));
+        assert!(ch2.contains(r#"<h2 id="toc-heading-2">Home</h2>"#));
+    }
+
+    #[test]
+    fn test_single_title_entry_is_replaced() {
+        let mut book = book_with(&[("content.xhtml", "<h1>One</h1><h1>Two</h1>")]);
+        book.toc.add(TocEntry::new("My Book", "content.xhtml"));
+        TocFromHeadings
+            .apply(&mut book, &ConversionOptions::default())
+            .unwrap();
+        let titles: Vec<&str> = book.toc.entries.iter().map(|e| e.title.as_str()).collect();
+        assert_eq!(titles, vec!["One", "Two"]);
+    }
+
+    #[test]
+    fn test_existing_toc_and_headingless_books_are_kept() {
+        let mut book = book_with(&[("content.xhtml", "<h1>One</h1><h2>Sub</h2>")]);
+        book.toc.add(TocEntry::new("A", "content.xhtml"));
+        book.toc.add(TocEntry::new("B", "content.xhtml"));
+        TocFromHeadings
+            .apply(&mut book, &ConversionOptions::default())
+            .unwrap();
+        assert_eq!(book.toc.entries.len(), 2);
+        assert!(!book
+            .manifest
+            .by_id("doc0")
+            .unwrap()
+            .data
+            .as_xhtml()
+            .unwrap()
+            .contains("toc-heading"));
+
+        let mut book = book_with(&[("content.xhtml", "<p>No headings</p><h4>Deep</h4>")]);
+        book.toc.add(TocEntry::new("My Book", "content.xhtml"));
+        TocFromHeadings
+            .apply(&mut book, &ConversionOptions::default())
+            .unwrap();
+        assert_eq!(book.toc.entries.len(), 1);
+        assert_eq!(book.toc.entries[0].title, "My Book");
+    }
+
+    #[test]
+    fn test_generated_ids_avoid_existing_ones() {
+        let (xhtml, headings) =
+            anchor_headings(r#"<p id="toc-heading-1">x</p><h1>A</h1><h3>  </h3>"#);
+        assert_eq!(headings.len(), 1);
+        assert_eq!(headings[0].href, "toc-heading-2");
+        assert!(xhtml.contains(r#"<h1 id="toc-heading-2">A</h1>"#));
+        assert!(xhtml.contains("<h3>  </h3>"));
+    }
+}
@@ -1,12 +1,12 @@
 //! ZIP archive utilities for reading/writing EPUB and DOCX files.
 
 use std::fs::File;
-use std::io::{self, Read, Write};
+use std::io::{self, Cursor, Read, Write};
 use std::path::{Path, PathBuf};
 
 use zip::read::ZipArchive;
 use zip::write::SimpleFileOptions;
-use zip::ZipWriter;
+use zip::{CompressionMethod, DateTime, ZipWriter};
 
 /// Extract all files from a ZIP archive to a directory.
 pub fn extract_zip(zip_path: &Path, output_dir: &Path) -> io::Result<Vec<PathBuf>> {
@@ -61,6 +61,8 @@ pub fn list_zip_entries(zip_path: &Path) -> io::Result<Vec<String>> {
 /// Builder for creating ZIP archives (used for EPUB output).
 pub struct ZipBuilder {
     writer: ZipWriter<File>,
+    modified: DateTime,
+    deflate_level: Option<i64>,
 }
 
 impl ZipBuilder {
@@ -69,22 +71,57 @@ impl ZipBuilder {
         let file = File::create(path)?;
         Ok(Self {
             writer: ZipWriter::new(file),
+            modified: DateTime::default_for_write(),
+            deflate_level: None,
         })
     }
 
+    /// Stamp every entry added from now on with the given modification time
+    /// instead of the time the builder was created.
+    pub fn set_last_modified(&mut self, modified: DateTime) {
+        self.modified = modified;
+    }
+
+    /// Deflate entries added with [`add_file`](Self::add_file) at this level
+    /// (1 fastest to 9 smallest) rather than the deflate default.
+    pub fn set_deflate_level(&mut self, level: Option<i64>) {
+        self.deflate_level = level;
+    }
+
     /// Add a file entry with the given content.
     pub fn add_file(&mut self, name: &str, content: &[u8]) -> io::Result<()> {
-        let options =
-            SimpleFileOptions::default().compression_method(zip::CompressionMethod::Deflated);
-        self.writer.start_file(name, options)?;
-        self.writer.write_all(content)?;
-        Ok(())
+        self.add_with(name, content, CompressionMethod::Deflated)
     }
 
     /// Add a file entry stored without compression (used for mimetype in EPUB).
     pub fn add_stored(&mut self, name: &str, content: &[u8]) -> io::Result<()> {
-        let options =
-            SimpleFileOptions::default().compression_method(zip::CompressionMethod::Stored);
+        self.add_with(name, content, CompressionMethod::Stored)
+    }
+
+    /// Add an entry compressed ahead of time, typically on another thread.
+    ///
+    /// The result is identical to adding the same content with
+    /// [`add_file`](Self::add_file) or [`add_stored`](Self::add_stored).
+    pub fn add_compressed(&mut self, entry: CompressedEntry) -> io::Result<()> {
+        let mut archive = ZipArchive::new(Cursor::new(entry.archive))?;
+        let file = archive.by_index_raw(0)?;
+        self.writer.raw_copy_file_touch(file, self.modified, None)?;
+        Ok(())
+    }
+
+    fn add_with(
+        &mut self,
+        name: &str,
+        content: &[u8],
+        method: CompressionMethod,
+    ) -> io::Result<()> {
+        let options = SimpleFileOptions::default()
+            .compression_method(method)
+            .compression_level(
+                self.deflate_level
+                    .filter(|_| method == CompressionMethod::Deflated),
+            )
+            .last_modified_time(self.modified);
         self.writer.start_file(name, options)?;
         self.writer.write_all(content)?;
         Ok(())
@@ -104,6 +141,45 @@ impl ZipBuilder {
     }
 }
 
+/// A ZIP entry whose content has already been compressed.
+///
+/// Compression is the expensive part of writing an archive, and unlike the
+/// archive writer itself it can run on many entries at once. Each entry is
+/// compressed into a single-entry in-memory archive, which
+/// [`ZipBuilder::add_compressed`] then copies without recompressing.
+pub struct CompressedEntry {
+    archive: Vec<u8>,
+}
+
+impl CompressedEntry {
+    /// Deflate `content` for an entry named `name`, at `level` or the
+    /// deflate default.
+    pub fn deflated(name: &str, content: &[u8], level: Option<i64>) -> io::Result<Self> {
+        Self::new(name, content, CompressionMethod::Deflated, level)
+    }
+
+    /// Prepare `content` to be stored uncompressed under `name`.
+    pub fn stored(name: &str, content: &[u8]) -> io::Result<Self> {
+        Self::new(name, content, CompressionMethod::Stored, None)
+    }
+
+    fn new(
+        name: &str,
+        content: &[u8],
+        method: CompressionMethod,
+        level: Option<i64>,
+    ) -> io::Result<Self> {
+        let options = SimpleFileOptions::default()
+            .compression_method(method)
+            .compression_level(level);
+        let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+        writer.start_file(name, options)?;
+        writer.write_all(content)?;
+        let archive = writer.finish()?.into_inner();
+        Ok(Self { archive })
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -132,4 +208,41 @@ mod tests {
 
         std::fs::remove_file(&tmp).ok();
     }
+
+    #[test]
+    fn test_compressed_entries_match_direct_writes() {
+        let dir = std::env::temp_dir();
+        let direct = dir.join("test_zip_direct.zip");
+        let copied = dir.join("test_zip_copied.zip");
+        let text = "chapter text ".repeat(500);
+        let modified = DateTime::from_date_and_time(2024, 5, 1, 12, 0, 0).unwrap();
+
+        let mut builder = ZipBuilder::new(&direct).unwrap();
+        builder.set_last_modified(modified);
+        builder
+            .add_stored("mimetype", b"application/epub+zip")
+            .unwrap();
+        builder.add_file("text.xhtml", text.as_bytes()).unwrap();
+        builder.finish().unwrap();
+
+        let mut builder = ZipBuilder::new(&copied).unwrap();
+        builder.set_last_modified(modified);
+        let stored = CompressedEntry::stored("mimetype", b"application/epub+zip").unwrap();
+        builder.add_compressed(stored).unwrap();
+        let deflated = CompressedEntry::deflated("text.xhtml", text.as_bytes(), None).unwrap();
+        builder.add_compressed(deflated).unwrap();
+        builder.finish().unwrap();
+
+        assert_eq!(
+            std::fs::read(&direct).unwrap(),
+            std::fs::read(&copied).unwrap()
+        );
+        assert_eq!(
+            read_zip_entry(&copied, "text.xhtml").unwrap(),
+            text.as_bytes()
+        );
+
+        std::fs::remove_file(&direct).ok();
+        std::fs::remove_file(&copied).ok();
+    }
 }
@@ -1,5 +1,8 @@
 //! Character encoding detection and conversion.
 
+use std::io;
+use std::path::Path;
+
 use encoding_rs::Encoding;
 
 /// Detect encoding from a byte string and decode to UTF-8.
@@ -29,6 +32,37 @@ pub fn decode_to_utf8(bytes: &[u8]) -> (String, &'static str) {
     }
 }
 
+/// Decode text that may start with a UTF-8 or UTF-16 byte order mark.
+/// The BOM is removed and UTF-16 is decoded; without a BOM the bytes must
+/// be valid UTF-8.
+pub fn decode_text(bytes: &[u8]) -> Result<String, std::str::Utf8Error> {
+    match Encoding::for_bom(bytes) {
+        Some((encoding, bom_len)) => Ok(encoding
+            .decode_without_bom_handling(&bytes[bom_len..])
+            .0
+            .into_owned()),
+        None => std::str::from_utf8(bytes).map(str::to_string),
+    }
+}
+
+/// Like [`decode_text`], but replaces invalid UTF-8 instead of failing.
+pub fn decode_text_lossy(bytes: &[u8]) -> String {
+    match Encoding::for_bom(bytes) {
+        Some((encoding, bom_len)) => encoding
+            .decode_without_bom_handling(&bytes[bom_len..])
+            .0
+            .into_owned(),
+        None => String::from_utf8_lossy(bytes).into_owned(),
+    }
+}
+
+/// Read a text file with [`decode_text`], the BOM-aware counterpart of
+/// `std::fs::read_to_string`.
+pub fn read_text_file(path: &Path) -> io::Result<String> {
+    let bytes = std::fs::read(path)?;
+    decode_text(&bytes).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
 /// Decode bytes using a specific encoding name.
 pub fn decode_with_encoding(bytes: &[u8], encoding_name: &str) -> Option<String> {
     let encoding = Encoding::for_label(encoding_name.as_bytes())?;
@@ -76,6 +110,40 @@ mod tests {
         assert_eq!(enc, "UTF-8");
     }
 
+    #[test]
+    fn test_read_text_file_utf8_bom() {
+        let path = std::env::temp_dir().join("test_encoding_utf8_bom.txt");
+        let mut bytes = vec![0xEF, 0xBB, 0xBF];
+        bytes.extend_from_slice("<?xml version=\"1.0\"?><p>Caf\u{e9}</p>".as_bytes());
+        std::fs::write(&path, &bytes).unwrap();
+
+        let text = read_text_file(&path).unwrap();
+        let _ = std::fs::remove_file(&path);
+        assert_eq!(text, "<?xml version=\"1.0\"?><p>Caf\u{e9}</p>");
+    }
+
+    #[test]
+    fn test_read_text_file_utf16le_bom() {
+        let path = std::env::temp_dir().join("test_encoding_utf16le_bom.txt");
+        let mut bytes = vec![0xFF, 0xFE];
+        for unit in "Stra\u{df}e \u{2014} ok".encode_utf16() {
+            bytes.extend_from_slice(&unit.to_le_bytes());
+        }
+        std::fs::write(&path, &bytes).unwrap();
+
+        let text = read_text_file(&path).unwrap();
+        let _ = std::fs::remove_file(&path);
+        assert_eq!(text, "Stra\u{df}e \u{2014} ok");
+        assert!(!text.starts_with('\u{FEFF}'));
+    }
+
+    #[test]
+    fn test_decode_text_without_bom() {
+        assert_eq!(decode_text(b"plain").unwrap(), "plain");
+        assert!(decode_text(&[0x66, 0xFF]).is_err());
+        assert_eq!(decode_text_lossy(&[0xEF, 0xBB, 0xBF, b'a']), "a");
+    }
+
     #[test]
     fn test_detect_xml_encoding() {
         let xml = b"<?xml version=\"1.0\" encoding=\"iso-8859-1\"?><root/>";
@@ -0,0 +1,100 @@
+//! Helpers for manifest-relative hrefs.
+
+/// Resolve `src` relative to the directory of the document at `base`.
+///
+/// Fragments and query strings on `src` are dropped, and `.`/`..` segments
+/// are collapsed, so the result can be looked up with `Manifest::by_href`.
+pub fn resolve(base: &str, src: &str) -> String {
+    let src = src.split(['#', '?']).next().unwrap_or_default();
+    let mut parts: Vec<&str> = match base.rfind('/') {
+        Some(pos) => base[..pos].split('/').collect(),
+        None => Vec::new(),
+    };
+    for segment in src.split('/') {
+        match segment {
+            "." | "" => {}
+            ".." => {
+                parts.pop();
+            }
+            s => parts.push(s),
+        }
+    }
+    parts.join("/")
+}
+
+/// Express the manifest href `target` relative to the directory of the
+/// document at `base`. The inverse of [`resolve`].
+pub fn relative(base: &str, target: &str) -> String {
+    let base_dir: Vec<&str> = match base.rfind('/') {
+        Some(pos) => base[..pos].split('/').collect(),
+        None => Vec::new(),
+    };
+    let target_parts: Vec<&str> = target.split('/').collect();
+    let common = base_dir
+        .iter()
+        .zip(&target_parts)
+        .take_while(|(a, b)| a == b)
+        .count()
+        .min(target_parts.len() - 1);
+
+    let mut parts = vec![".."; base_dir.len() - common];
+    parts.extend(&target_parts[common..]);
+    parts.join("/")
+}
+
+/// The link from the document at `base` to the manifest href `target`,
+/// replacing the link `src` but keeping its `?query` and `#fragment`.
+pub fn retarget(base: &str, src: &str, target: &str) -> String {
+    let suffix = src.find(['?', '#']).map_or("", |pos| &src[pos..]);
+    format!("{}{}", relative(base, target), suffix)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve() {
+        assert_eq!(
+            resolve("text/titlepage.xhtml", "../images/c.png"),
+            "images/c.png"
+        );
+        assert_eq!(resolve("title.xhtml", "c.png"), "c.png");
+        assert_eq!(resolve("text/ch1.xhtml", "./img/a.gif#x"), "text/img/a.gif");
+    }
+
+    #[test]
+    fn test_relative() {
+        assert_eq!(
+            relative("text/ch1.xhtml", "images/a.png"),
+            "../images/a.png"
+        );
+        assert_eq!(relative("text/ch1.xhtml", "text/img/a.png"), "img/a.png");
+        assert_eq!(relative("ch1.xhtml", "images/a.png"), "images/a.png");
+        assert_eq!(relative("a/b/ch1.xhtml", "a/c.png"), "../c.png");
+        assert_eq!(
+            resolve(
+                "text/ch1.xhtml",
+                &relative("text/ch1.xhtml", "images/a.png")
+            ),
+            "images/a.png"
+        );
+    }
+
+    #[test]
+    fn test_retarget() {
+        assert_eq!(
+            retarget("text/ch1.xhtml", "../images/a.png", "images/a.jpg"),
+            "../images/a.jpg"
+        );
+        assert_eq!(
+            retarget(
+                "text/ch1.xhtml",
+                "../images/a.png#xywh=0,0,9,9",
+                "images/a.jpg"
+            ),
+            "../images/a.jpg#xywh=0,0,9,9"
+        );
+        assert_eq!(retarget("ch1.xhtml", "a.png?v=2", "a.jpg"), "a.jpg?v=2");
+    }
+}
@@ -1,4 +1,6 @@
 pub mod archive;
 pub mod encoding;
+pub mod href;
 pub mod mime;
+pub mod sort;
 pub mod xml;
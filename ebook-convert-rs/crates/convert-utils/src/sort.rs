@@ -0,0 +1,76 @@
+//! Natural ("human") ordering of file names.
+
+use std::cmp::Ordering;
+
+/// Compare file names so that runs of digits sort by numeric value,
+/// e.g. `page2.jpg` < `page10.jpg`. Other characters compare case-insensitively.
+pub fn natural_cmp(a: &str, b: &str) -> Ordering {
+    let mut a = a.chars().peekable();
+    let mut b = b.chars().peekable();
+    loop {
+        match (a.peek().copied(), b.peek().copied()) {
+            (None, None) => return Ordering::Equal,
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(x), Some(y)) if x.is_ascii_digit() && y.is_ascii_digit() => {
+                let num_a = take_digits(&mut a);
+                let num_b = take_digits(&mut b);
+                let ord = num_a
+                    .trim_start_matches('0')
+                    .len()
+                    .cmp(&num_b.trim_start_matches('0').len())
+                    .then_with(|| {
+                        num_a
+                            .trim_start_matches('0')
+                            .cmp(num_b.trim_start_matches('0'))
+                    });
+                if ord != Ordering::Equal {
+                    return ord;
+                }
+            }
+            (Some(x), Some(y)) => {
+                let ord = x.to_lowercase().cmp(y.to_lowercase());
+                if ord != Ordering::Equal {
+                    return ord;
+                }
+                a.next();
+                b.next();
+            }
+        }
+    }
+}
+
+fn take_digits(chars: &mut std::iter::Peekable<std::str::Chars<'_>>) -> String {
+    let mut digits = String::new();
+    while let Some(c) = chars.next_if(|c| c.is_ascii_digit()) {
+        digits.push(c);
+    }
+    digits
+}
+
+/// Sort names in natural order, e.g. `img1`, `img2`, `img10`.
+pub fn natural_sort<S: AsRef<str>>(names: &mut [S]) {
+    names.sort_by(|a, b| natural_cmp(a.as_ref(), b.as_ref()));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_natural_cmp() {
+        let mut names = vec!["page10.jpg", "page2.jpg", "Page1.jpg", "page02b.jpg"];
+        names.sort_by(|a, b| natural_cmp(a, b));
+        assert_eq!(
+            names,
+            vec!["Page1.jpg", "page2.jpg", "page02b.jpg", "page10.jpg"]
+        );
+    }
+
+    #[test]
+    fn test_natural_sort() {
+        let mut names = vec!["img10".to_string(), "img2".to_string(), "img1".to_string()];
+        natural_sort(&mut names);
+        assert_eq!(names, vec!["img1", "img2", "img10"]);
+    }
+}
@@ -2,7 +2,9 @@
 
 use quick_xml::events::Event;
 use quick_xml::Reader;
+use regex::Regex;
 use std::collections::HashMap;
+use std::sync::LazyLock;
 
 /// Parse an XML string and extract text content of a specific element.
 pub fn extract_text(xml: &str, tag_name: &str) -> Vec<String> {
@@ -20,11 +22,9 @@ pub fn extract_text(xml: &str, tag_name: &str) -> Vec<String> {
                     buf.clear();
                 }
             }
-            Ok(Event::Text(ref e)) => {
-                if in_target {
-                    if let Ok(text) = e.unescape() {
-                        buf.push_str(&text);
-                    }
+            Ok(Event::Text(ref e)) if in_target => {
+                if let Ok(text) = e.unescape() {
+                    buf.push_str(&text);
                 }
             }
             Ok(Event::End(ref e)) => {
@@ -221,6 +221,171 @@ pub fn escape_xml_attr(s: &str) -> String {
         .replace('"', "&quot;")
 }
 
+/// Visible text of a heading's inner HTML, for use as a TOC title.
+///
+/// Strips tags, treats `&nbsp;` as whitespace and collapses runs of whitespace.
+/// Returns `None` for empty or whitespace-only headings (e.g. `<h2></h2>` or
+/// `<h2><br/></h2>`), which should never produce a TOC entry.
+pub fn heading_text(inner_html: &str) -> Option<String> {
+    let mut text = String::with_capacity(inner_html.len());
+    let mut in_tag = false;
+    for ch in inner_html.chars() {
+        match ch {
+            '<' => in_tag = true,
+            '>' => in_tag = false,
+            _ if !in_tag => text.push(ch),
+            _ => {}
+        }
+    }
+    let text = text.replace("&nbsp;", " ").replace("&#160;", " ");
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    if text.is_empty() {
+        None
+    } else {
+        Some(text)
+    }
+}
+
+/// A `<colgroup>` giving each column its share of the total width.
+///
+/// `widths` may be in any unit (twips, points, relative weights). Returns
+/// `None` when there are no columns or any width is missing (zero).
+pub fn colgroup(widths: &[f64]) -> Option<String> {
+    if widths.is_empty() || widths.iter().any(|w| w.is_nan() || *w <= 0.0) {
+        return None;
+    }
+    let total: f64 = widths.iter().sum();
+    let mut html = String::from("<colgroup>");
+    for w in widths {
+        html.push_str(&format!(
+            r#"<col style="width: {:.1}%"/>"#,
+            w / total * 100.0
+        ));
+    }
+    html.push_str("</colgroup>\n");
+    Some(html)
+}
+
+/// ` lang="…" xml:lang="…"` attributes marking an element's language, so both
+/// HTML and XHTML readers (TTS, hyphenation) pick it up. Empty for an empty tag.
+pub fn lang_attrs(lang: &str) -> String {
+    if lang.is_empty() {
+        return String::new();
+    }
+    let lang = escape_xml_attr(lang);
+    format!(" lang=\"{}\" xml:lang=\"{}\"", lang, lang)
+}
+
+/// Elements that never have content or a closing tag.
+const VOID_ELEMENTS: &[&str] = &[
+    "area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta", "param", "source",
+    "track", "wbr",
+];
+
+/// Elements whose start tag implicitly closes an open element of the same name.
+const SELF_CLOSING_SIBLINGS: &[&str] = &["p", "li", "dt", "dd", "tr", "td", "th", "option"];
+
+/// Scripts, styles, comments, doctypes and processing instructions.
+static DROPPED_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"(?is)<script\b.*?</script\s*>|<style\b.*?</style\s*>|<!--.*?-->|<![^>]*>|<\?.*?\?>",
+    )
+    .unwrap()
+});
+
+/// Start and end tags.
+static TAG_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"<(/?)([A-Za-z][\w:.-]*)((?:[^>"']|"[^"]*"|'[^']*')*?)(/?)>"#).unwrap()
+});
+
+/// One attribute with an optional quoted or unquoted value.
+static ATTR_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"([^\s=/"']+)(?:\s*=\s*(?:"([^"]*)"|'([^']*)'|([^\s"'>]+)))?"#).unwrap()
+});
+
+/// An ampersand, with the entity or character reference it starts, if any.
+static AMP_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"&([A-Za-z][A-Za-z0-9]*;|#[0-9]+;|#[xX][0-9A-Fa-f]+;)?").unwrap());
+
+/// Make an HTML fragment well-formed XHTML.
+///
+/// Drops scripts, styles, comments and namespaced (e.g. Office `o:p`)
+/// elements, lowercases tag names, quotes attribute values, removes `on*`
+/// event handlers, self-closes void elements, closes elements HTML lets
+/// authors leave open, drops stray end tags and escapes bare `&` and `<`.
+pub fn html_to_xhtml(html: &str) -> String {
+    let html = DROPPED_RE.replace_all(html, "");
+    let mut out = String::with_capacity(html.len());
+    let mut open: Vec<String> = Vec::new();
+    let mut last = 0;
+
+    for cap in TAG_RE.captures_iter(&html) {
+        let whole = cap.get(0).unwrap();
+        out.push_str(&escape_text(&html[last..whole.start()]));
+        last = whole.end();
+
+        let name = cap[2].to_ascii_lowercase();
+        if name.contains(':') {
+            continue;
+        }
+        let is_void = VOID_ELEMENTS.contains(&name.as_str());
+
+        if !cap[1].is_empty() {
+            // Close everything opened since the matching start tag
+            if let Some(pos) = open.iter().rposition(|n| *n == name) {
+                for n in open.drain(pos..).rev() {
+                    out.push_str(&format!("</{}>", n));
+                }
+            }
+            continue;
+        }
+
+        if SELF_CLOSING_SIBLINGS.contains(&name.as_str()) && open.last() == Some(&name) {
+            out.push_str(&format!("</{}>", name));
+            open.pop();
+        }
+
+        out.push('<');
+        out.push_str(&name);
+        for attr in ATTR_RE.captures_iter(&cap[3]) {
+            let attr_name = attr[1].to_ascii_lowercase();
+            if attr_name.starts_with("on") {
+                continue;
+            }
+            let value = attr
+                .get(2)
+                .or_else(|| attr.get(3))
+                .or_else(|| attr.get(4))
+                .map_or(attr_name.as_str(), |m| m.as_str());
+            out.push_str(&format!(
+                " {}=\"{}\"",
+                attr_name,
+                escape_text(value).replace('"', "&quot;")
+            ));
+        }
+        if is_void || !cap[4].is_empty() {
+            out.push_str("/>");
+        } else {
+            out.push('>');
+            open.push(name);
+        }
+    }
+    out.push_str(&escape_text(&html[last..]));
+    for name in open.iter().rev() {
+        out.push_str(&format!("</{}>", name));
+    }
+    out
+}
+
+/// Escape `<`, `>` and any `&` that does not start an entity reference.
+fn escape_text(text: &str) -> String {
+    let text = AMP_RE.replace_all(text, |cap: &regex::Captures| match cap.get(1) {
+        Some(entity) => format!("&{}", entity.as_str()),
+        None => "&amp;".to_string(),
+    });
+    text.replace('<', "&lt;").replace('>', "&gt;")
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -260,4 +425,51 @@ mod tests {
         assert_eq!(escape_xml_text("a < b & c"), "a &lt; b &amp; c");
         assert_eq!(escape_xml_attr("say \"hello\""), "say &quot;hello&quot;");
     }
+
+    #[test]
+    fn test_heading_text() {
+        assert_eq!(
+            heading_text("Sub <em>section</em>").as_deref(),
+            Some("Sub section")
+        );
+        assert_eq!(
+            heading_text("  Two\n  lines ").as_deref(),
+            Some("Two lines")
+        );
+        assert_eq!(heading_text(""), None);
+        assert_eq!(heading_text("   "), None);
+        assert_eq!(heading_text("<br/>&nbsp;\u{a0}"), None);
+        assert_eq!(heading_text("<span></span>"), None);
+    }
+
+    #[test]
+    fn test_colgroup() {
+        assert_eq!(
+            colgroup(&[3000.0, 1000.0]).unwrap(),
+            "<colgroup><col style=\"width: 75.0%\"/><col style=\"width: 25.0%\"/></colgroup>\n"
+        );
+        assert!(colgroup(&[]).is_none());
+        assert!(colgroup(&[100.0, 0.0]).is_none());
+    }
+
+    #[test]
+    fn test_lang_attrs() {
+        assert_eq!(lang_attrs("fr-FR"), r#" lang="fr-FR" xml:lang="fr-FR""#);
+        assert_eq!(lang_attrs(""), "");
+    }
+
+    #[test]
+    fn test_html_to_xhtml() {
+        let html = "<P class=note>One<br>Two &amp; three & four<P>Next\
+            <script>alert(1)</script><img src=a.png alt='x' onclick=\"go()\">\
+            <o:p></o:p><ul><li>a<li>b</ul></div>";
+        assert_eq!(
+            html_to_xhtml(html),
+            "<p class=\"note\">One<br/>Two &amp; three &amp; four</p><p>Next\
+             <img src=\"a.png\" alt=\"x\"/><ul><li>a</li><li>b</li></ul></p>"
+        );
+        // Elements left open at the end are closed
+        assert_eq!(html_to_xhtml("<b>bold <i>both"), "<b>bold <i>both</i></b>");
+        assert_eq!(html_to_xhtml("a < b"), "a &lt; b");
+    }
 }
@@ -1,6 +1,8 @@
 //! End-to-end pipeline benchmarks: input → transforms → output.
 
-use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use std::hint::black_box;
+
+use criterion::{criterion_group, criterion_main, Criterion};
 
 use convert_core::book::EbookFormat;
 use convert_core::options::ConversionOptions;
@@ -55,9 +57,11 @@ fn bench_html_to_epub(c: &mut Criterion) {
             let output_path = std::env::temp_dir().join(format!("bench_output_{}.epub", n));
 
             b.iter(|| {
-                let mut opts = ConversionOptions::default();
-                opts.input_format = Some(EbookFormat::Html);
-                opts.output_format = Some(EbookFormat::Epub);
+                let opts = ConversionOptions {
+                    input_format: Some(EbookFormat::Html),
+                    output_format: Some(EbookFormat::Epub),
+                    ..Default::default()
+                };
 
                 let input_plugin = Box::new(convert_input_html::HtmlInputPlugin)
                     as Box<dyn convert_core::plugin::InputPlugin>;
@@ -98,9 +102,11 @@ fn bench_txt_to_epub(c: &mut Criterion) {
             let output_path = std::env::temp_dir().join(format!("bench_output_{}.epub", n));
 
             b.iter(|| {
-                let mut opts = ConversionOptions::default();
-                opts.input_format = Some(EbookFormat::Txt);
-                opts.output_format = Some(EbookFormat::Epub);
+                let opts = ConversionOptions {
+                    input_format: Some(EbookFormat::Txt),
+                    output_format: Some(EbookFormat::Epub),
+                    ..Default::default()
+                };
 
                 let input_plugin = Box::new(convert_input_txt::TxtInputPlugin)
                     as Box<dyn convert_core::plugin::InputPlugin>;
@@ -0,0 +1,149 @@
+//! Library interface to the converter, for embedding it in other programs.
+//!
+//! [`convert`] does what the command line does for a single file: it picks
+//! the input and output plugins from the file names (or
+//! `options.input_format` / `options.output_format`), runs the standard
+//! transforms and writes the result. Options are taken as given; config
+//! files are only read by the command line tool.
+//!
+//! ```no_run
+//! use std::path::Path;
+//!
+//! use convert_core::options::ConversionOptions;
+//!
+//! let options = ConversionOptions {
+//!     smarten_punctuation: true,
+//!     ..Default::default()
+//! };
+//! ebook_convert::convert(Path::new("book.txt"), Path::new("book.epub"), &options)?;
+//! # Ok::<(), convert_core::error::ConvertError>(())
+//! ```
+
+use std::path::Path;
+
+use convert_core::book::EbookFormat;
+use convert_core::error::{ConvertError, Result};
+use convert_core::options::ConversionOptions;
+use convert_core::pipeline::PipelineBuilder;
+use convert_core::plugin::{InputPlugin, OutputPlugin, Transform};
+
+/// Convert `input` to `output`, detecting both formats from their file
+/// names unless `options` names them.
+pub fn convert(input: &Path, output: &Path, options: &ConversionOptions) -> Result<()> {
+    let input_format = match options.input_format {
+        Some(format) => format,
+        None => detect_format(input)?,
+    };
+    let output_format = match options.output_format {
+        Some(format) => format,
+        None => detect_format(output)?,
+    };
+    log::info!(
+        "Converting {} → {} : {} → {}",
+        input.display(),
+        output.display(),
+        input_format,
+        output_format
+    );
+
+    let options = ConversionOptions {
+        input_format: Some(input_format),
+        output_format: Some(output_format),
+        ..options.clone()
+    };
+    pipeline_builder(input_plugin(input_format)?, input_format, output_format)?
+        .build()?
+        .run(input, output, &options)
+}
+
+/// A pipeline builder with `input_plugin`, the output plugin for
+/// `output_format` and the standard transforms, ready for a progress
+/// reporter to be added.
+pub fn pipeline_builder(
+    input_plugin: Box<dyn InputPlugin>,
+    input_format: EbookFormat,
+    output_format: EbookFormat,
+) -> Result<PipelineBuilder> {
+    let mut builder = PipelineBuilder::new()
+        .input(input_plugin)
+        .output(output_plugin(output_format)?);
+    for t in transforms(input_format, output_format) {
+        builder = builder.transform(t);
+    }
+    Ok(builder)
+}
+
+/// The format implied by `path`, or an error explaining why there is none.
+fn detect_format(path: &Path) -> Result<EbookFormat> {
+    if let Some(format) = format_from_path(path) {
+        return Ok(format);
+    }
+    let ext = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or_default();
+    Err(EbookFormat::unsupported_extension(ext).unwrap_or_else(|| {
+        ConvertError::UnsupportedFormat(format!("Cannot detect format of {}", path.display()))
+    }))
+}
+
+/// Input format implied by a file name. Zipped FictionBook files
+/// (`book.fb2.zip`) are recognized by their double extension.
+pub fn format_from_path(path: &Path) -> Option<EbookFormat> {
+    let name = path.file_name()?.to_str()?.to_lowercase();
+    if name.ends_with(".fb2.zip") {
+        return Some(EbookFormat::Fb2);
+    }
+    path.extension()
+        .and_then(|e| e.to_str())
+        .and_then(EbookFormat::from_extension)
+}
+
+/// The plugin that reads `format`.
+pub fn input_plugin(format: EbookFormat) -> Result<Box<dyn InputPlugin>> {
+    match format {
+        EbookFormat::Pdf => Ok(Box::new(convert_input_pdf::PdfInputPlugin)),
+        EbookFormat::Epub => Ok(Box::new(convert_input_epub::EpubInputPlugin)),
+        EbookFormat::Html | EbookFormat::Xhtml => Ok(Box::new(convert_input_html::HtmlInputPlugin)),
+        EbookFormat::Txt | EbookFormat::Markdown | EbookFormat::Rst | EbookFormat::Textile => {
+            Ok(Box::new(convert_input_txt::TxtInputPlugin))
+        }
+        EbookFormat::Mobi | EbookFormat::Azw | EbookFormat::Azw3 => {
+            Ok(Box::new(convert_input_mobi::MobiInputPlugin))
+        }
+        EbookFormat::Docx => Ok(Box::new(convert_input_docx::DocxInputPlugin)),
+        EbookFormat::Fb2 => Ok(Box::new(convert_input_fb2::Fb2InputPlugin)),
+        EbookFormat::Rtf => Ok(Box::new(convert_input_rtf::RtfInputPlugin)),
+        EbookFormat::Odt => Ok(Box::new(convert_input_odt::OdtInputPlugin)),
+        EbookFormat::Cbz => Ok(Box::new(convert_input_cbz::CbzInputPlugin)),
+        EbookFormat::Pdb | EbookFormat::Tcr => Ok(Box::new(convert_input_pdb::PdbInputPlugin)),
+    }
+}
+
+/// The plugin that writes `format`.
+pub fn output_plugin(format: EbookFormat) -> Result<Box<dyn OutputPlugin>> {
+    match format {
+        EbookFormat::Epub => Ok(Box::new(convert_output_epub::EpubOutputPlugin)),
+        EbookFormat::Html | EbookFormat::Xhtml => {
+            Ok(Box::new(convert_output_html::HtmlOutputPlugin))
+        }
+        EbookFormat::Txt => Ok(Box::new(convert_output_txt::TxtOutputPlugin)),
+        EbookFormat::Pdf => Ok(Box::new(convert_output_pdf::PdfOutputPlugin)),
+        EbookFormat::Mobi | EbookFormat::Azw | EbookFormat::Azw3 => {
+            Ok(Box::new(convert_output_mobi::MobiOutputPlugin))
+        }
+        EbookFormat::Cbz => Ok(Box::new(convert_output_cbz::CbzOutputPlugin)),
+        _ => Err(ConvertError::UnsupportedFormat(format!(
+            "{} is not an output format",
+            format
+        ))),
+    }
+}
+
+/// The transforms run between input and output.
+pub fn transforms(
+    _input_format: EbookFormat,
+    _output_format: EbookFormat,
+) -> Vec<Box<dyn Transform>> {
+    convert_transforms::standard_transforms()
+}
@@ -3,17 +3,33 @@
 //! Supports two CLI modes:
 //! - Legacy: `ebook-convert-rs input.pdf output.epub [--options]`
 //! - Modern: `ebook-convert-rs convert --from pdf --to epub input.pdf -o output.epub`
+//! - Batch: `ebook-convert-rs batch books/ --to epub -o converted/`
+//! - Merge: `ebook-convert-rs convert --merge ch1.txt ch2.txt -o book.epub`
+//! - Pipes: `cat book.txt | ebook-convert-rs convert --from txt --to epub - -o - > book.epub`
 
-use std::path::PathBuf;
+mod merge;
+mod stdio;
+
+use std::collections::HashMap;
+use std::ffi::OsString;
+use std::io::IsTerminal;
+use std::path::{Path, PathBuf};
 use std::process;
 
 use anyhow::{Context, Result};
-use clap::{CommandFactory, Parser, Subcommand};
+use clap::{ArgMatches, CommandFactory, FromArgMatches, Parser, Subcommand};
+use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
+use rayon::prelude::*;
+use serde::Deserialize;
 
-use convert_core::book::EbookFormat;
-use convert_core::options::{ConversionOptions, PdfEngine};
-use convert_core::pipeline::PipelineBuilder;
-use convert_core::plugin::{InputPlugin, OutputPlugin, Transform};
+use convert_core::book::{BookDocument, EbookFormat};
+use convert_core::options::{
+    ConversionOptions, CoverFit, DeviceProfile, EnsureCover, EpubCompression, MissingResources,
+    PageProgression, PdfCoverPage, PdfEngine, TxtParagraphMode,
+};
+use convert_core::pipeline::apply_metadata_overrides;
+use convert_core::plugin::{InputPlugin, ProgressReporter};
+use ebook_convert::{format_from_path, input_plugin, output_plugin, pipeline_builder};
 
 #[derive(Parser)]
 #[command(
@@ -33,7 +49,8 @@ struct Cli {
     #[arg(global = false)]
     output: Option<PathBuf>,
 
-    /// Verbosity level
+    /// Verbosity level; on a terminal, also shows log output in place of the
+    /// progress bar
     #[arg(short, long, action = clap::ArgAction::Count, global = true)]
     verbose: u8,
 
@@ -41,6 +58,11 @@ struct Cli {
     #[arg(long, global = true)]
     extra_css: Option<String>,
 
+    /// Target device: default, kindle-paperwhite, kindle-oasis, kindle-scribe,
+    /// kobo-clara, kobo-libra, generic-tablet, generic-phone
+    #[arg(long, value_name = "NAME", global = true)]
+    output_profile: Option<String>,
+
     /// Maximum image size (WxH). Defaults to output profile screen size.
     #[arg(long, global = true)]
     max_image_size: Option<String>,
@@ -49,6 +71,152 @@ struct Cli {
     #[arg(long, global = true)]
     jpeg_quality: Option<u8>,
 
+    /// Fit the cover to the output screen: contain (letterbox) or cover (crop)
+    #[arg(long, global = true)]
+    cover_fit: Option<String>,
+
+    /// Keep the whole cover visible when fitting it (same as --cover-fit contain)
+    #[arg(long, global = true)]
+    preserve_cover_aspect: bool,
+
+    /// Pick a cover when the input has none: image, or title-page to fall
+    /// back to a generated title page when there are no images
+    #[arg(long, global = true)]
+    ensure_cover: Option<String>,
+
+    /// Skip a named transform (e.g. LinearizeTables); repeatable
+    #[arg(long = "disable-transform", value_name = "NAME", global = true)]
+    disable_transforms: Vec<String>,
+
+    /// Maximum number of worker threads (default: one per core)
+    #[arg(long, global = true)]
+    max_threads: Option<usize>,
+
+    /// Collapse double spaces and remove spaces before punctuation
+    #[arg(long, global = true)]
+    clean_spacing: bool,
+
+    /// Keep stylesheets readable instead of minifying them
+    #[arg(long, global = true)]
+    pretty_print: bool,
+
+    /// Reindent content documents: block elements on their own lines
+    #[arg(long, global = true)]
+    pretty_xhtml: bool,
+
+    /// Start the first paragraph after each heading with a drop cap
+    #[arg(long, global = true)]
+    drop_caps: bool,
+
+    /// Renumber headings so the shallowest level is h1 and levels are contiguous
+    #[arg(long, global = true)]
+    normalize_headings: bool,
+
+    /// Remove empty paragraphs and blank pages
+    #[arg(long, global = true)]
+    remove_blank: bool,
+
+    /// Collapse the table of contents into a single level
+    #[arg(long, global = true)]
+    flatten_toc: bool,
+
+    /// With --flatten-toc, prefix child titles with their parent's title
+    #[arg(long, global = true)]
+    flatten_toc_prefix: bool,
+
+    /// Keep at most this many TOC levels, dropping deeper entries
+    #[arg(long, value_name = "N", global = true)]
+    max_toc_depth: Option<usize>,
+
+    /// With --max-toc-depth, move deeper entries up instead of dropping them
+    #[arg(long, global = true)]
+    hoist_deep_toc: bool,
+
+    /// Body text size in pt for PDF output and EPUB/HTML stylesheets
+    #[arg(long, value_name = "PT", global = true)]
+    base_font_size: Option<f64>,
+
+    /// Line height as a multiple of the font size (e.g. 1.4)
+    #[arg(long, value_name = "RATIO", global = true)]
+    line_height: Option<f64>,
+
+    /// Insert soft hyphens into long words using the book's language patterns
+    #[arg(long, global = true)]
+    enable_hyphenation: bool,
+
+    /// Minimum letters in a word before it is hyphenated (default: 6)
+    #[arg(long, global = true)]
+    hyphenation_min_word_length: Option<usize>,
+
+    /// Split documents at lines consisting of exactly this text, removing them
+    #[arg(long, value_name = "TEXT", global = true)]
+    chapter_marker: Option<String>,
+
+    /// Split content documents larger than this many bytes at block boundaries
+    #[arg(long, value_name = "BYTES", global = true)]
+    max_flow_size: Option<usize>,
+
+    /// Set the book title
+    #[arg(long, global = true)]
+    title: Option<String>,
+
+    /// Set the authors (comma-separated)
+    #[arg(long, global = true)]
+    authors: Option<String>,
+
+    /// Set the book language (e.g. en, fr-CA)
+    #[arg(long, global = true)]
+    language: Option<String>,
+
+    /// Set the publisher
+    #[arg(long, global = true)]
+    publisher: Option<String>,
+
+    /// Set the series name
+    #[arg(long, global = true)]
+    series: Option<String>,
+
+    /// Set the book's position in its series (e.g. 2 or 2.5)
+    #[arg(long, global = true)]
+    series_index: Option<f64>,
+
+    /// Use this image file (JPEG, PNG, GIF or WebP) as the cover
+    #[arg(long, value_name = "PATH", global = true)]
+    cover: Option<PathBuf>,
+
+    /// Convert $...$ / $$...$$ TeX math in Markdown input to MathML
+    #[arg(long, global = true)]
+    markdown_math: bool,
+
+    /// Paragraph style of TXT input: blank-line, hard-wrap, or auto (default: auto)
+    #[arg(long, value_name = "MODE", global = true)]
+    txt_paragraph_mode: Option<String>,
+
+    /// Download remote images and stylesheets referenced by HTML input
+    #[arg(long, global = true)]
+    fetch_remote: bool,
+
+    /// Repair references to missing or remote resources in EPUB input:
+    /// placeholder (alt text) or strip
+    #[arg(long, value_name = "MODE", global = true)]
+    missing_resources: Option<String>,
+
+    /// Keep DOCX review comments as numbered notes after the text
+    #[arg(long, global = true)]
+    docx_comments: bool,
+
+    /// Title untitled DOCX documents from their first page header
+    #[arg(long, global = true)]
+    docx_header_title: bool,
+
+    /// File listing document hrefs in the desired reading order
+    #[arg(long, value_name = "FILE", global = true)]
+    spine_order: Option<PathBuf>,
+
+    /// Page progression: ltr or rtl (default: from the input or language)
+    #[arg(long, value_name = "DIR", global = true)]
+    page_progression: Option<String>,
+
     /// Debug pipeline output directory
     #[arg(long, global = true)]
     debug_pipeline: Option<PathBuf>,
@@ -61,82 +229,169 @@ struct Cli {
     #[arg(long, global = true)]
     pdf_dpi: Option<u16>,
 
+    /// Password for encrypted PDF input
+    #[arg(long, global = true)]
+    pdf_password: Option<String>,
+
+    /// PDF input pages to convert, e.g. 3-4,7 (default: all)
+    #[arg(long, value_name = "RANGES", global = true)]
+    pages: Option<String>,
+
+    /// Render this PDF input page as the cover, even outside --pages
+    #[arg(long, value_name = "N", global = true)]
+    cover_from_page: Option<u32>,
+
+    /// Use the first converted PDF input page as the cover
+    #[arg(long, global = true)]
+    first_page_cover: bool,
+
+    /// PDF output cover page: before-title, replace (cover instead of
+    /// title page), or off (default: before-title)
+    #[arg(long, value_name = "MODE", global = true)]
+    pdf_cover_page: Option<String>,
+
+    /// Background colour (#RRGGBB) around the PDF cover image
+    #[arg(long, value_name = "COLOR", global = true)]
+    pdf_cover_background: Option<String>,
+
+    /// Resolution of PDF output images that do not record one (default: 96)
+    #[arg(long, value_name = "DPI", global = true)]
+    pdf_image_dpi: Option<u16>,
+
+    /// Footer on each PDF output page; {page} and {title} are filled in
+    #[arg(long, value_name = "TEMPLATE", global = true)]
+    pdf_footer: Option<String>,
+
+    /// EPUB output compression: stored (none), fast, or best
+    #[arg(long, value_name = "MODE", global = true)]
+    epub_compression: Option<String>,
+
+    /// Unix timestamp for the creation and modification dates of MOBI output
+    #[arg(long, value_name = "SECONDS", global = true)]
+    source_date_epoch: Option<u64>,
+
     /// Dump effective merged config as TOML and exit
     #[arg(long, global = true)]
     dump_config: bool,
+
+    /// Print book statistics (title, authors, counts, words) and exit
+    /// without writing an output file
+    #[arg(long, global = true)]
+    stats: bool,
 }
 
 #[derive(Subcommand)]
 enum Commands {
     /// Convert an ebook (modern interface)
     Convert {
-        /// Input file
-        input: PathBuf,
+        /// Input file (several with --merge); `-` reads stdin
+        #[arg(required = true)]
+        inputs: Vec<PathBuf>,
 
-        /// Output file
+        /// Output file; `-` writes to stdout
         #[arg(short, long)]
         output: PathBuf,
 
-        /// Input format (auto-detected from extension if omitted)
+        /// Input format (auto-detected from extension if omitted; required
+        /// for stdin)
         #[arg(long)]
         from: Option<String>,
 
-        /// Output format (auto-detected from extension if omitted)
+        /// Output format (auto-detected from extension if omitted; required
+        /// for stdout)
         #[arg(long)]
         to: Option<String>,
+
+        /// Combine all inputs, in order, into one book
+        #[arg(long)]
+        merge: bool,
+    },
+
+    /// Convert every supported file in a directory
+    Batch {
+        /// Directory containing the books to convert
+        input_dir: PathBuf,
+
+        /// Output format for all converted files
+        #[arg(long)]
+        to: String,
+
+        /// Directory to write converted files to (created if missing)
+        #[arg(short, long = "output-dir")]
+        output_dir: PathBuf,
     },
 }
 
 /// Load config from global and project-local TOML files.
-/// Later files override earlier ones. Missing files are silently ignored.
+/// Later files override earlier ones, field by field: a key a file leaves
+/// out keeps its value from the earlier files. Missing files are silently
+/// ignored.
 fn load_config() -> ConversionOptions {
-    let mut opts = ConversionOptions::default();
+    let mut merged = toml::Table::new();
 
     // 1. Global config: ~/.config/ebook-convert-rs/config.toml
     if let Some(config_dir) = dirs::config_dir() {
         let global_path = config_dir.join("ebook-convert-rs").join("config.toml");
-        if let Ok(contents) = std::fs::read_to_string(&global_path) {
-            match toml::from_str::<ConversionOptions>(&contents) {
-                Ok(parsed) => opts = parsed,
-                Err(e) => {
-                    log::warn!("Failed to parse {}: {}", global_path.display(), e);
-                }
-            }
+        if let Some(table) = read_config_table(&global_path) {
+            merge_config(&mut merged, table);
         }
     }
 
     // 2. Project-local config: ./.ebook-convert-rs.toml
     let local_path = PathBuf::from(".ebook-convert-rs.toml");
-    if let Ok(contents) = std::fs::read_to_string(&local_path) {
-        match toml::from_str::<ConversionOptions>(&contents) {
-            Ok(parsed) => {
-                merge_config(&mut opts, &parsed);
-            }
-            Err(e) => {
-                log::warn!("Failed to parse {}: {}", local_path.display(), e);
-            }
+    if let Some(table) = read_config_table(&local_path) {
+        merge_config(&mut merged, table);
+    }
+
+    match ConversionOptions::deserialize(toml::Value::Table(merged)) {
+        Ok(opts) => opts,
+        Err(e) => {
+            log::warn!("Failed to apply merged config: {}", e);
+            ConversionOptions::default()
         }
     }
+}
 
-    opts
+/// Parse a config file into a TOML table, warning (and returning `None`) if
+/// it is not a valid `ConversionOptions` on its own.
+fn read_config_table(path: &Path) -> Option<toml::Table> {
+    let contents = std::fs::read_to_string(path).ok()?;
+    let parsed = contents
+        .parse::<toml::Table>()
+        .map_err(|e| e.to_string())
+        .and_then(|table| {
+            ConversionOptions::deserialize(toml::Value::Table(table.clone()))
+                .map(|_| table)
+                .map_err(|e| e.to_string())
+        });
+    match parsed {
+        Ok(table) => Some(table),
+        Err(e) => {
+            log::warn!("Failed to parse {}: {}", path.display(), e);
+            None
+        }
+    }
 }
 
-/// Merge `from` into `base`, overriding only non-default fields.
-/// Since we can't easily detect which fields were set in the TOML,
-/// the project-local config fully overrides the global config.
-fn merge_config(base: &mut ConversionOptions, from: &ConversionOptions) {
-    // We do a simple full override for project-local since serde(default)
-    // fills in defaults for missing fields. A more granular merge would
-    // require Option<T> wrappers for every field. For now, project-local
-    // fully overrides global.
-    *base = from.clone();
+/// Overlay the keys present in `from` onto `base`. Nested tables (such as
+/// `[output_profile]`) are merged key by key as well.
+fn merge_config(base: &mut toml::Table, from: toml::Table) {
+    for (key, value) in from {
+        match (base.get_mut(&key), value) {
+            (Some(toml::Value::Table(base_table)), toml::Value::Table(table)) => {
+                merge_config(base_table, table);
+            }
+            (_, value) => {
+                base.insert(key, value);
+            }
+        }
+    }
 }
 
 /// Apply CLI flags on top of config-loaded options.
-/// Only overrides when the CLI flag was explicitly provided.
-fn apply_cli_overrides(opts: &mut ConversionOptions, cli: &Cli) {
-    let matches = Cli::command().get_matches_from(std::env::args_os());
-
+/// Only overrides when the CLI flag was explicitly provided; `matches` are
+/// the parsed arguments `cli` was built from.
+fn apply_cli_overrides(opts: &mut ConversionOptions, cli: &Cli, matches: &ArgMatches) {
     if matches.value_source("verbose") == Some(clap::parser::ValueSource::CommandLine) {
         opts.verbose = cli.verbose;
     }
@@ -145,6 +400,17 @@ fn apply_cli_overrides(opts: &mut ConversionOptions, cli: &Cli) {
         opts.extra_css = cli.extra_css.clone();
     }
 
+    if let Some(ref name) = cli.output_profile {
+        match DeviceProfile::from_name(name) {
+            Some(profile) => opts.output_profile = profile.output_profile(),
+            None => log::warn!(
+                "Unknown output profile '{}', keeping {}",
+                name,
+                opts.output_profile.name
+            ),
+        }
+    }
+
     if let Some(ref size_str) = cli.max_image_size {
         if let Some((w, h)) = parse_size(size_str) {
             opts.max_image_size = Some((w, h));
@@ -155,6 +421,169 @@ fn apply_cli_overrides(opts: &mut ConversionOptions, cli: &Cli) {
         opts.jpeg_quality = quality.clamp(1, 100);
     }
 
+    if let Some(ref fit_str) = cli.cover_fit {
+        opts.cover_fit = match fit_str.as_str() {
+            "cover" => Some(CoverFit::Cover),
+            _ => Some(CoverFit::Contain),
+        };
+    } else if cli.preserve_cover_aspect {
+        opts.cover_fit = Some(CoverFit::Contain);
+    }
+
+    if let Some(ref mode) = cli.ensure_cover {
+        opts.ensure_cover = match mode.as_str() {
+            "title-page" => Some(EnsureCover::TitlePage),
+            _ => Some(EnsureCover::Image),
+        };
+    }
+
+    opts.disabled_transforms
+        .extend(cli.disable_transforms.iter().cloned());
+
+    if cli.max_threads.is_some() {
+        opts.max_threads = cli.max_threads;
+    }
+
+    if cli.clean_spacing {
+        opts.clean_spacing = true;
+    }
+
+    if cli.drop_caps {
+        opts.drop_caps = true;
+    }
+
+    if cli.pretty_print {
+        opts.pretty_print = true;
+    }
+    if cli.pretty_xhtml {
+        opts.pretty_xhtml = true;
+    }
+
+    if cli.normalize_headings {
+        opts.normalize_headings = true;
+    }
+
+    if cli.remove_blank {
+        opts.remove_blank = true;
+    }
+
+    if cli.flatten_toc {
+        opts.flatten_toc = true;
+    }
+
+    if cli.flatten_toc_prefix {
+        opts.flatten_toc_prefix = true;
+    }
+
+    if cli.max_toc_depth.is_some() {
+        opts.max_toc_depth = cli.max_toc_depth;
+    }
+
+    if cli.hoist_deep_toc {
+        opts.hoist_deep_toc = true;
+    }
+
+    if cli.enable_hyphenation {
+        opts.enable_hyphenation = true;
+    }
+
+    if let Some(size) = cli.base_font_size {
+        opts.base_font_size = size;
+    }
+
+    if cli.line_height.is_some() {
+        opts.line_height = cli.line_height;
+    }
+
+    if let Some(min_len) = cli.hyphenation_min_word_length {
+        opts.hyphenation_min_word_length = min_len;
+    }
+
+    if cli.chapter_marker.is_some() {
+        opts.chapter_marker = cli.chapter_marker.clone();
+    }
+
+    if cli.max_flow_size.is_some() {
+        opts.max_flow_size = cli.max_flow_size;
+    }
+
+    if cli.title.is_some() {
+        opts.title = cli.title.clone();
+    }
+
+    if let Some(ref authors) = cli.authors {
+        opts.authors = authors
+            .split(',')
+            .map(str::trim)
+            .filter(|a| !a.is_empty())
+            .map(String::from)
+            .collect();
+    }
+
+    if cli.language.is_some() {
+        opts.language = cli.language.clone();
+    }
+
+    if cli.publisher.is_some() {
+        opts.publisher = cli.publisher.clone();
+    }
+
+    if cli.series.is_some() {
+        opts.series = cli.series.clone();
+    }
+
+    if cli.series_index.is_some() {
+        opts.series_index = cli.series_index;
+    }
+
+    if cli.cover.is_some() {
+        opts.cover = cli.cover.clone();
+    }
+
+    if cli.markdown_math {
+        opts.markdown_math = true;
+    }
+
+    if cli.fetch_remote {
+        opts.fetch_remote = true;
+    }
+
+    if cli.docx_comments {
+        opts.docx_comments = true;
+    }
+
+    if cli.docx_header_title {
+        opts.docx_header_title = true;
+    }
+
+    if let Some(ref mode) = cli.missing_resources {
+        opts.missing_resources = match mode.as_str() {
+            "placeholder" => Some(MissingResources::Placeholder),
+            "strip" => Some(MissingResources::Strip),
+            _ => None,
+        };
+    }
+
+    if let Some(ref mode) = cli.txt_paragraph_mode {
+        opts.txt_paragraph_mode = match mode.as_str() {
+            "blank-line" => TxtParagraphMode::BlankLine,
+            "hard-wrap" => TxtParagraphMode::HardWrap,
+            _ => TxtParagraphMode::Auto,
+        };
+    }
+
+    if cli.spine_order.is_some() {
+        opts.spine_order = cli.spine_order.clone();
+    }
+
+    if let Some(ref dir) = cli.page_progression {
+        opts.page_progression = match dir.as_str() {
+            "ltr" => Some(PageProgression::Ltr),
+            "rtl" => Some(PageProgression::Rtl),
+            _ => None,
+        };
+    }
+
     if cli.debug_pipeline.is_some() {
         opts.debug_pipeline = cli.debug_pipeline.clone();
     }
@@ -170,17 +599,74 @@ fn apply_cli_overrides(opts: &mut ConversionOptions, cli: &Cli) {
     if let Some(dpi) = cli.pdf_dpi {
         opts.pdf_dpi = dpi;
     }
+
+    if cli.pdf_password.is_some() {
+        opts.pdf_password = cli.pdf_password.clone();
+    }
+
+    if cli.pages.is_some() {
+        opts.pdf_pages = cli.pages.clone();
+    }
+
+    if cli.cover_from_page.is_some() {
+        opts.cover_from_page = cli.cover_from_page;
+    }
+
+    if cli.first_page_cover {
+        opts.pdf_first_page_cover = true;
+    }
+
+    if let Some(ref mode) = cli.pdf_cover_page {
+        opts.pdf_cover_page = match mode.as_str() {
+            "off" => PdfCoverPage::Off,
+            "replace" => PdfCoverPage::Replace,
+            _ => PdfCoverPage::BeforeTitle,
+        };
+    }
+
+    if cli.pdf_cover_background.is_some() {
+        opts.pdf_cover_background = cli.pdf_cover_background.clone();
+    }
+
+    if let Some(dpi) = cli.pdf_image_dpi {
+        opts.pdf_image_dpi = dpi.max(1);
+    }
+
+    if cli.pdf_footer.is_some() {
+        opts.pdf_footer = cli.pdf_footer.clone();
+    }
+
+    if let Some(ref mode) = cli.epub_compression {
+        opts.epub_compression = match mode.as_str() {
+            "stored" => Some(EpubCompression::Stored),
+            "fast" => Some(EpubCompression::Fast),
+            "best" => Some(EpubCompression::Best),
+            _ => None,
+        };
+    }
+
+    if cli.source_date_epoch.is_some() {
+        opts.source_date_epoch = cli.source_date_epoch;
+    }
 }
 
 fn main() {
-    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info")).init();
+    let matches = Cli::command().get_matches();
+    let cli = Cli::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
 
-    let cli = Cli::parse();
+    // The progress bar replaces routine log output; warnings still show
+    let default_filter = if show_progress_bar(&cli) {
+        "warn"
+    } else {
+        "info"
+    };
+    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or(default_filter))
+        .init();
 
     // Handle --dump-config
     if cli.dump_config {
         let mut opts = load_config();
-        apply_cli_overrides(&mut opts, &cli);
+        apply_cli_overrides(&mut opts, &cli, &matches);
         match toml::to_string_pretty(&opts) {
             Ok(s) => {
                 println!("{}", s);
@@ -195,23 +681,61 @@ fn main() {
 
     let result = match &cli.command {
         Some(Commands::Convert {
-            input,
+            inputs,
             output,
             from,
             to,
-        }) => run_conversion(
-            input.clone(),
-            output.clone(),
-            from.clone(),
-            to.clone(),
-            &cli,
-        ),
+            merge,
+        }) => match inputs.as_slice() {
+            [input] if !merge => run_conversion(
+                input.clone(),
+                output.clone(),
+                from.clone(),
+                to.clone(),
+                &cli,
+                &matches,
+                None,
+            ),
+            _ if *merge => run_merge(
+                inputs,
+                output.clone(),
+                from.clone(),
+                to.clone(),
+                &cli,
+                &matches,
+            ),
+            _ => Err(anyhow::anyhow!(
+                "{} input files given; use --merge to combine them into one book",
+                inputs.len()
+            )),
+        },
+        Some(Commands::Batch {
+            input_dir,
+            to,
+            output_dir,
+        }) => run_batch(input_dir, output_dir, to, &cli, &matches),
         None => {
             // Legacy mode: positional args
             match (&cli.input, &cli.output) {
-                (Some(input), Some(output)) => {
-                    run_conversion(input.clone(), output.clone(), None, None, &cli)
-                }
+                (Some(input), Some(output)) => run_conversion(
+                    input.clone(),
+                    output.clone(),
+                    None,
+                    None,
+                    &cli,
+                    &matches,
+                    None,
+                ),
+                // --stats never writes, so the output path may be left out
+                (Some(input), None) if cli.stats => run_conversion(
+                    input.clone(),
+                    PathBuf::new(),
+                    None,
+                    None,
+                    &cli,
+                    &matches,
+                    None,
+                ),
                 _ => {
                     eprintln!("Usage: ebook-convert-rs <input> <output> [options]");
                     eprintln!("   or: ebook-convert-rs convert <input> -o <output> [options]");
@@ -227,35 +751,113 @@ fn main() {
     }
 }
 
+/// Shared state for the conversions of one `batch` run.
+struct Batch {
+    /// Holds every file's progress bar, so concurrent bars don't draw
+    /// over each other
+    progress: MultiProgress,
+}
+
+/// Convert one file. `batch` is set when the file is part of a batch.
 fn run_conversion(
     input: PathBuf,
     output: PathBuf,
     from: Option<String>,
     to: Option<String>,
     cli: &Cli,
+    matches: &ArgMatches,
+    batch: Option<&Batch>,
 ) -> Result<()> {
-    // Detect formats
-    let input_format = from
-        .as_deref()
-        .and_then(EbookFormat::from_extension)
-        .or_else(|| {
-            input
-                .extension()
-                .and_then(|e| e.to_str())
-                .and_then(EbookFormat::from_extension)
-        })
-        .context("Cannot detect input format. Use --from to specify.")?;
+    if stdio::is_stdio(&input) && from.is_none() {
+        anyhow::bail!("Reading from stdin needs the input format: use `convert --from <FORMAT> -`");
+    }
+    let input_format = detect_format(from.as_deref(), &input, "input", "--from")?;
 
-    let output_format = to
-        .as_deref()
-        .and_then(EbookFormat::from_extension)
-        .or_else(|| {
-            output
-                .extension()
-                .and_then(|e| e.to_str())
-                .and_then(EbookFormat::from_extension)
-        })
-        .context("Cannot detect output format. Use --to to specify.")?;
+    // Keep the spooled stdin alive until the conversion is done
+    let spool;
+    let input = if stdio::is_stdio(&input) {
+        spool = stdio::Spool::new()?;
+        spool.read_stdin(input_format)?
+    } else {
+        input
+    };
+
+    let input_plugin = input_plugin(input_format)?;
+    convert_with(
+        input_plugin,
+        input_format,
+        &input,
+        output,
+        to,
+        cli,
+        matches,
+        batch,
+    )
+}
+
+/// Convert `inputs`, in order, into a single book written to `output`.
+/// Metadata comes from the first file unless overridden by options.
+fn run_merge(
+    inputs: &[PathBuf],
+    output: PathBuf,
+    from: Option<String>,
+    to: Option<String>,
+    cli: &Cli,
+    matches: &ArgMatches,
+) -> Result<()> {
+    let mut parts = Vec::with_capacity(inputs.len());
+    let mut formats = Vec::with_capacity(inputs.len());
+    for input in inputs {
+        let format = detect_format(from.as_deref(), input, "input", "--from")?;
+        parts.push((input.clone(), input_plugin(format)?));
+        formats.push(format);
+    }
+
+    let input_plugin = Box::new(merge::MergedInput::new(parts));
+    convert_with(
+        input_plugin,
+        formats[0],
+        &inputs[0],
+        output,
+        to,
+        cli,
+        matches,
+        None,
+    )
+}
+
+/// Run the conversion pipeline (or `--stats`) with an already chosen input plugin.
+#[allow(clippy::too_many_arguments)]
+fn convert_with(
+    input_plugin: Box<dyn InputPlugin>,
+    input_format: EbookFormat,
+    input: &Path,
+    output: PathBuf,
+    to: Option<String>,
+    cli: &Cli,
+    matches: &ArgMatches,
+    batch: Option<&Batch>,
+) -> Result<()> {
+    if cli.stats {
+        let mut options = load_config();
+        apply_cli_overrides(&mut options, cli, matches);
+        options.input_format = Some(input_format);
+
+        let mut book = input_plugin
+            .convert(input, &options)
+            .map_err(|e| anyhow::anyhow!("{}", e))?;
+        input_plugin
+            .postprocess(&mut book, &options)
+            .map_err(|e| anyhow::anyhow!("{}", e))?;
+        apply_metadata_overrides(&mut book, &options);
+        print!("{}", book_stats(&book));
+        return Ok(());
+    }
+
+    if stdio::is_stdio(&output) && to.is_none() {
+        anyhow::bail!("Writing to stdout needs the output format: use `--to <FORMAT> -o -`");
+    }
+    let output_format = detect_format(to.as_deref(), &output, "output", "--to")?;
 
     log::info!(
         "Converting {} → {} : {} → {}",
@@ -267,78 +869,268 @@ fn run_conversion(
 
     // Build options: config files → CLI overrides
     let mut options = load_config();
-    apply_cli_overrides(&mut options, cli);
+    apply_cli_overrides(&mut options, cli, matches);
     options.input_format = Some(input_format);
     options.output_format = Some(output_format);
-
-    // Get plugins
-    let input_plugin = get_input_plugin(input_format)?;
-    let output_plugin = get_output_plugin(output_format)?;
-    let transforms = get_transforms(input_format, output_format);
-
-    // Build pipeline
-    let mut builder = PipelineBuilder::new()
-        .input(input_plugin)
-        .output(output_plugin);
-
-    for t in transforms {
-        builder = builder.transform(t);
+    if batch.is_some() {
+        // The batch's pool already holds the thread count to max_threads
+        options.max_threads = None;
     }
 
-    let pipeline = builder
-        .progress_reporter(Box::new(|frac, msg| {
-            if frac < 1.0 {
-                log::info!("[{:3.0}%] {}", frac * 100.0, msg);
-            } else {
-                log::info!("Done!");
-            }
-        }))
+    let pipeline = pipeline_builder(input_plugin, input_format, output_format)?
+        .progress_reporter(progress_reporter(cli, batch, input))
         .build()
         .map_err(|e| anyhow::anyhow!("{}", e))?;
 
+    if stdio::is_stdio(&output) {
+        let spool = stdio::Spool::new()?;
+        let spooled = spool.path(output_format);
+        pipeline
+            .run(input, &spooled, &options)
+            .map_err(|e| anyhow::anyhow!("{}", e))?;
+        return stdio::copy_to_stdout(&spooled);
+    }
+
     pipeline
-        .run(&input, &output, &options)
+        .run(input, &output, &options)
         .map_err(|e| anyhow::anyhow!("{}", e))?;
 
     Ok(())
 }
 
-fn get_input_plugin(format: EbookFormat) -> Result<Box<dyn InputPlugin>> {
-    match format {
-        EbookFormat::Pdf => Ok(Box::new(convert_input_pdf::PdfInputPlugin)),
-        EbookFormat::Epub => Ok(Box::new(convert_input_epub::EpubInputPlugin)),
-        EbookFormat::Html | EbookFormat::Xhtml => Ok(Box::new(convert_input_html::HtmlInputPlugin)),
-        EbookFormat::Txt | EbookFormat::Markdown => Ok(Box::new(convert_input_txt::TxtInputPlugin)),
-        EbookFormat::Mobi | EbookFormat::Azw | EbookFormat::Azw3 => {
-            Ok(Box::new(convert_input_mobi::MobiInputPlugin))
-        }
-        EbookFormat::Docx => Ok(Box::new(convert_input_docx::DocxInputPlugin)),
-        EbookFormat::Fb2 => Ok(Box::new(convert_input_fb2::Fb2InputPlugin)),
-        EbookFormat::Rtf => Ok(Box::new(convert_input_rtf::RtfInputPlugin)),
-        EbookFormat::Odt => Ok(Box::new(convert_input_odt::OdtInputPlugin)),
-    }
+/// Whether conversion progress is drawn as a bar: stdout is a terminal and
+/// `-v` hasn't asked for the log output instead.
+fn show_progress_bar(cli: &Cli) -> bool {
+    cli.verbose == 0 && std::io::stdout().is_terminal()
 }
 
-fn get_output_plugin(format: EbookFormat) -> Result<Box<dyn OutputPlugin>> {
-    match format {
-        EbookFormat::Epub => Ok(Box::new(convert_output_epub::EpubOutputPlugin)),
-        EbookFormat::Html | EbookFormat::Xhtml => {
-            Ok(Box::new(convert_output_html::HtmlOutputPlugin))
+/// Report pipeline progress as a terminal bar labelled with the current
+/// stage, or as log lines when no bar is shown. In a batch, each file's bar
+/// joins the batch's group and is labelled with the file's name, as are its
+/// log lines.
+fn progress_reporter(cli: &Cli, batch: Option<&Batch>, input: &Path) -> ProgressReporter {
+    let name = batch.map(|_| {
+        format!(
+            "{}: ",
+            input.file_name().unwrap_or_default().to_string_lossy()
+        )
+    });
+    if !show_progress_bar(cli) {
+        let name = name.unwrap_or_default();
+        return Box::new(move |frac, msg| {
+            if frac < 1.0 {
+                log::info!("[{:3.0}%] {}{}", frac * 100.0, name, msg);
+            } else {
+                log::info!("{}Done!", name);
+            }
+        });
+    }
+
+    let bar = match batch {
+        Some(batch) => batch.progress.add(ProgressBar::new(100)),
+        None => ProgressBar::new(100),
+    };
+    let style = ProgressStyle::with_template("{bar:40.cyan/blue} {pos:>3}% {prefix}{msg}")
+        .expect("valid progress template")
+        .progress_chars("=> ");
+    bar.set_style(style);
+    bar.set_prefix(name.unwrap_or_default());
+    Box::new(move |frac, msg| {
+        bar.set_position((frac.clamp(0.0, 1.0) * 100.0).round() as u64);
+        if frac < 1.0 {
+            bar.set_message(msg.to_string());
+        } else {
+            bar.finish_with_message("Done!");
         }
-        EbookFormat::Txt => Ok(Box::new(convert_output_txt::TxtOutputPlugin)),
-        EbookFormat::Pdf => Ok(Box::new(convert_output_pdf::PdfOutputPlugin)),
-        EbookFormat::Mobi | EbookFormat::Azw | EbookFormat::Azw3 => {
-            Ok(Box::new(convert_output_mobi::MobiOutputPlugin))
+    })
+}
+
+/// Human-readable `--stats` report for `book`, one `Label: value` per line.
+fn book_stats(book: &BookDocument) -> String {
+    let authors = book.metadata.authors();
+    let image_count = book.manifest.iter().filter(|item| item.is_image()).count();
+    let lines = [
+        ("Title", book.metadata.title().unwrap_or("-").to_string()),
+        (
+            "Authors",
+            if authors.is_empty() {
+                "-".to_string()
+            } else {
+                authors.join(", ")
+            },
+        ),
+        (
+            "Language",
+            book.metadata.language().unwrap_or("-").to_string(),
+        ),
+        ("Spine items", book.spine.len().to_string()),
+        ("Images", image_count.to_string()),
+        ("TOC depth", book.toc.depth().to_string()),
+        ("Words", book.word_count().to_string()),
+    ];
+    lines
+        .iter()
+        .map(|(label, value)| format!("{:<12} {}\n", format!("{}:", label), value))
+        .collect()
+}
+
+/// Convert every file in `input_dir` with a recognised extension to `to`,
+/// writing `<stem>.<ext>` files into `output_dir`. Inputs that share a stem
+/// (`book.epub`, `book.pdf`) keep their full name instead (`book.epub.mobi`).
+///
+/// Files are converted in parallel on a rayon pool of `max_threads` workers
+/// (default one per core), which every conversion's own parallel stages
+/// share. A failed file is reported and skipped; the batch fails only at
+/// the end.
+fn run_batch(
+    input_dir: &Path,
+    output_dir: &Path,
+    to: &str,
+    cli: &Cli,
+    matches: &ArgMatches,
+) -> Result<()> {
+    let output_format = match EbookFormat::from_extension(to) {
+        Some(format) => format,
+        None => match EbookFormat::unsupported_extension(to) {
+            Some(e) => return Err(e.into()),
+            None => anyhow::bail!("Unknown output format: {}", to),
+        },
+    };
+    output_plugin(output_format)?;
+
+    let inputs = discover_inputs(input_dir)?;
+    if inputs.is_empty() {
+        anyhow::bail!("No convertible files found in {}", input_dir.display());
+    }
+    std::fs::create_dir_all(output_dir)
+        .with_context(|| format!("Cannot create {}", output_dir.display()))?;
+
+    let mut options = load_config();
+    apply_cli_overrides(&mut options, cli, matches);
+    let pool = rayon::ThreadPoolBuilder::new()
+        // Zero lets rayon pick one thread per core
+        .num_threads(options.max_threads.map_or(0, |threads| threads.max(1)))
+        .build()
+        .context("Failed to create thread pool")?;
+    let batch = Batch {
+        progress: MultiProgress::new(),
+    };
+
+    let results: Vec<(PathBuf, Result<PathBuf>)> = pool.install(|| {
+        batch_outputs(inputs, output_dir, output_format.extension())
+            .into_par_iter()
+            .map(|(input, output)| {
+                let result = if output == input {
+                    Err(anyhow::anyhow!("output would overwrite the input"))
+                } else {
+                    run_conversion(
+                        input.clone(),
+                        output.clone(),
+                        None,
+                        Some(to.to_string()),
+                        cli,
+                        matches,
+                        Some(&batch),
+                    )
+                    .map(|()| output)
+                };
+                (input, result)
+            })
+            .collect()
+    });
+
+    let mut failed = 0;
+    for (input, result) in &results {
+        match result {
+            Ok(output) => println!("OK     {} -> {}", input.display(), output.display()),
+            Err(e) => {
+                failed += 1;
+                eprintln!("FAILED {}: {:#}", input.display(), e);
+            }
         }
-        _ => anyhow::bail!("Unsupported output format: {}", format),
     }
+    println!(
+        "Converted {} of {} files",
+        results.len() - failed,
+        results.len()
+    );
+
+    if failed > 0 {
+        anyhow::bail!("{} of {} conversions failed", failed, results.len());
+    }
+    Ok(())
 }
 
-fn get_transforms(
-    _input_format: EbookFormat,
-    _output_format: EbookFormat,
-) -> Vec<Box<dyn Transform>> {
-    convert_transforms::standard_transforms()
+/// Pair each input with its file in `output_dir`: `<stem>.<ext>`, or
+/// `<file name>.<ext>` when several inputs share a stem.
+fn batch_outputs(inputs: Vec<PathBuf>, output_dir: &Path, ext: &str) -> Vec<(PathBuf, PathBuf)> {
+    let mut stems: HashMap<OsString, usize> = HashMap::new();
+    for input in &inputs {
+        *stems
+            .entry(input.file_stem().unwrap_or_default().to_owned())
+            .or_default() += 1;
+    }
+    inputs
+        .into_iter()
+        .map(|input| {
+            let stem = input.file_stem().unwrap_or_default();
+            let base = if stems[stem] > 1 {
+                input.file_name().unwrap_or_default()
+            } else {
+                stem
+            };
+            let output = output_dir.join(format!("{}.{}", base.to_string_lossy(), ext));
+            (input, output)
+        })
+        .collect()
+}
+
+/// Files directly inside `dir` whose extension maps to a known input format,
+/// sorted by path.
+fn discover_inputs(dir: &Path) -> Result<Vec<PathBuf>> {
+    let entries =
+        std::fs::read_dir(dir).with_context(|| format!("Cannot read {}", dir.display()))?;
+    let mut inputs: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| path.is_file())
+        .filter(|path| format_from_path(path).is_some())
+        .collect();
+    inputs.sort();
+    Ok(inputs)
+}
+
+/// The format named by `explicit` (a `--from`/`--to` value), or else the one
+/// implied by `path`. Formats that are recognized but not supported (such
+/// as `.lit`) get an error saying so instead of a detection failure.
+fn detect_format(
+    explicit: Option<&str>,
+    path: &Path,
+    kind: &str,
+    flag: &str,
+) -> Result<EbookFormat> {
+    let ext = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or_default();
+    if let Some(format) = explicit
+        .and_then(EbookFormat::from_extension)
+        .or_else(|| format_from_path(path))
+    {
+        return Ok(format);
+    }
+    match explicit
+        .into_iter()
+        .chain([ext])
+        .find_map(EbookFormat::unsupported_extension)
+    {
+        Some(e) => Err(e.into()),
+        None => anyhow::bail!(
+            "Cannot detect {} format of {}. Use {} to specify.",
+            kind,
+            path.display(),
+            flag
+        ),
+    }
 }
 
 fn parse_size(s: &str) -> Option<(u32, u32)> {
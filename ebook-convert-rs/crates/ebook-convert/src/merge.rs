@@ -0,0 +1,260 @@
+//! Merging several input files into one book (`convert --merge`).
+//!
+//! Each file is read by its own input plugin, then the books are appended in
+//! order: manifests (renaming clashing ids and hrefs), spines and TOCs.
+//! Metadata, guide and identifier come from the first file.
+
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+use regex::Regex;
+
+use convert_core::book::{BookDocument, EbookFormat, ManifestData, TocEntry};
+use convert_core::error::Result;
+use convert_core::options::ConversionOptions;
+use convert_core::plugin::InputPlugin;
+use convert_utils::href;
+
+/// Input plugin that reads several files and merges them into one book.
+pub struct MergedInput {
+    parts: Vec<(PathBuf, Box<dyn InputPlugin>)>,
+    formats: Vec<EbookFormat>,
+}
+
+impl MergedInput {
+    /// Merge `parts` in order, each read with its paired plugin.
+    pub fn new(parts: Vec<(PathBuf, Box<dyn InputPlugin>)>) -> Self {
+        let mut formats: Vec<EbookFormat> = Vec::new();
+        for (_, plugin) in &parts {
+            for format in plugin.supported_formats() {
+                if !formats.contains(format) {
+                    formats.push(*format);
+                }
+            }
+        }
+        Self { parts, formats }
+    }
+}
+
+impl InputPlugin for MergedInput {
+    fn name(&self) -> &str {
+        "Merged Input"
+    }
+
+    fn supported_formats(&self) -> &[EbookFormat] {
+        &self.formats
+    }
+
+    /// Reads and postprocesses every part; `_input_path` is the first of them.
+    fn convert(&self, _input_path: &Path, options: &ConversionOptions) -> Result<BookDocument> {
+        let mut merged: Option<BookDocument> = None;
+        for (path, plugin) in &self.parts {
+            log::info!("Merging {}", path.display());
+            let mut part = plugin.convert(path, options)?;
+            plugin.postprocess(&mut part, options)?;
+
+            let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or("Part");
+            if part.metadata.title().is_none() {
+                part.metadata.set_title(stem.replace(['_', '-'], " "));
+            }
+
+            match merged.as_mut() {
+                Some(book) => append_book(book, part),
+                None => merged = Some(part),
+            }
+        }
+        Ok(merged.unwrap_or_default())
+    }
+}
+
+/// Append `part` to the end of `book`. Items whose id or href is already
+/// taken are renamed, and the part's links to them are rewritten.
+fn append_book(book: &mut BookDocument, mut part: BookDocument) {
+    // Names the part already uses can't be handed out to its renamed items
+    let mut taken_ids: HashSet<String> = part.manifest.iter().map(|i| i.id.clone()).collect();
+    let mut taken_hrefs: HashSet<String> = part.manifest.iter().map(|i| i.href.clone()).collect();
+
+    let mut ids: HashMap<String, String> = HashMap::new();
+    let mut hrefs: HashMap<String, String> = HashMap::new();
+    for item in part.manifest.iter() {
+        let id = if book.manifest.by_id(&item.id).is_some() {
+            let prefix = format!("{}_", item.id);
+            let id = std::iter::repeat_with(|| book.manifest.generate_id(&prefix))
+                .find(|id| !taken_ids.contains(id))
+                .unwrap();
+            taken_ids.insert(id.clone());
+            id
+        } else {
+            item.id.clone()
+        };
+        ids.insert(item.id.clone(), id);
+
+        if book.manifest.by_href(&item.href).is_some() {
+            let (stem, ext) = item.href.rsplit_once('.').unwrap_or((&item.href, ""));
+            let mut href = book.manifest.generate_href(stem, ext);
+            let mut n = 1;
+            while taken_hrefs.contains(&href) {
+                href = book.manifest.generate_href(&format!("{}_{}", stem, n), ext);
+                n += 1;
+            }
+            taken_hrefs.insert(href.clone());
+            hrefs.insert(item.href.clone(), href);
+        }
+    }
+
+    if !hrefs.is_empty() {
+        rewrite_links(&mut part, &hrefs);
+    }
+
+    let spine: Vec<(String, bool)> = part
+        .spine
+        .iter()
+        .map(|s| (s.idref.clone(), s.linear))
+        .collect();
+    let first_doc = spine
+        .first()
+        .and_then(|(idref, _)| part.manifest.by_id(idref))
+        .map(|item| item.href.clone());
+    let title = part.metadata.title().map(str::to_string);
+
+    let items: Vec<_> = part.manifest.iter().cloned().collect();
+    for mut item in items {
+        item.href = hrefs.get(&item.href).cloned().unwrap_or(item.href);
+        item.id = ids[&item.id].clone();
+        item.fallback = item.fallback.map(|f| ids.get(&f).cloned().unwrap_or(f));
+        item.media_overlay = item
+            .media_overlay
+            .map(|m| ids.get(&m).cloned().unwrap_or(m));
+        book.manifest.add(item);
+    }
+
+    for (idref, linear) in spine {
+        let idref = ids.get(&idref).cloned().unwrap_or(idref);
+        book.spine.push(idref, linear);
+    }
+
+    // A part without a TOC still gets an entry for its first document
+    if part.toc.entries.is_empty() {
+        if let (Some(title), Some(href)) = (title, first_doc) {
+            part.toc.add(TocEntry::new(title, href));
+        }
+    }
+    for mut entry in part.toc.entries {
+        rename_toc_hrefs(&mut entry, &hrefs);
+        book.toc.add(entry);
+    }
+}
+
+/// Point `src`/`href` attributes in XHTML and `url()` in CSS at renamed items.
+fn rewrite_links(part: &mut BookDocument, renamed: &HashMap<String, String>) {
+    let attr_re = Regex::new(r#"(\b(?:src|href|xlink:href)\s*=\s*["'])([^"']+)(["'])"#).unwrap();
+    let url_re = Regex::new(r#"(url\s*\(\s*['"]?)([^'")\s]+)(['"]?\s*\))"#).unwrap();
+
+    for item in part.manifest.iter_mut() {
+        // Links are relative to where the document itself ends up
+        let base = item.href.clone();
+        let new_base = renamed.get(&base).unwrap_or(&base).clone();
+        let rewrite = |re: &Regex, content: &str| {
+            re.replace_all(content, |cap: &regex::Captures| {
+                match renamed.get(&href::resolve(&base, &cap[2])) {
+                    Some(target) => {
+                        let fragment = cap[2].find('#').map_or("", |i| &cap[2][i..]);
+                        let link = href::relative(&new_base, target);
+                        format!("{}{}{}{}", &cap[1], link, fragment, &cap[3])
+                    }
+                    None => cap[0].to_string(),
+                }
+            })
+            .into_owned()
+        };
+        item.data = match &item.data {
+            ManifestData::Xhtml(xhtml) => ManifestData::Xhtml(rewrite(&attr_re, xhtml)),
+            ManifestData::Css(css) => ManifestData::Css(rewrite(&url_re, css)),
+            _ => continue,
+        };
+    }
+}
+
+/// Rename the document part of each TOC entry's href, keeping its fragment.
+fn rename_toc_hrefs(entry: &mut TocEntry, renamed: &HashMap<String, String>) {
+    let (path, fragment) = match entry.href.find('#') {
+        Some(i) => entry.href.split_at(i),
+        None => (entry.href.as_str(), ""),
+    };
+    if let Some(new_path) = renamed.get(path) {
+        entry.href = format!("{}{}", new_path, fragment);
+    }
+    for child in &mut entry.children {
+        rename_toc_hrefs(child, renamed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use convert_core::book::ManifestItem;
+
+    fn part(body: &str) -> BookDocument {
+        let mut book = BookDocument::new();
+        book.manifest.add(ManifestItem::new(
+            "content",
+            "content.xhtml",
+            "application/xhtml+xml",
+            ManifestData::Xhtml(format!(
+                r#"<html><head><link href="style.css" rel="stylesheet"/></head><body>{}</body></html>"#,
+                body
+            )),
+        ));
+        book.manifest.add(ManifestItem::new(
+            "style",
+            "style.css",
+            "text/css",
+            ManifestData::Css("p { margin: 0; }".into()),
+        ));
+        book.spine.push("content", true);
+        book.toc.add(TocEntry::new("Part", "content.xhtml#top"));
+        book
+    }
+
+    #[test]
+    fn test_append_renames_clashing_items() {
+        let mut book = part("<p>One</p>");
+        append_book(
+            &mut book,
+            part(r##"<p><a href="content.xhtml#top">Two</a></p>"##),
+        );
+
+        let idrefs: Vec<&str> = book.spine.iter().map(|s| s.idref.as_str()).collect();
+        assert_eq!(idrefs.len(), 2);
+        assert_eq!(idrefs[0], "content");
+
+        let second = book.manifest.by_id(idrefs[1]).unwrap();
+        assert_eq!(second.href, "content_1.xhtml");
+        let xhtml = second.data.as_xhtml().unwrap();
+        assert!(xhtml.contains(r#"<link href="style_1.css""#));
+        assert!(xhtml.contains(r##"<a href="content_1.xhtml#top">"##));
+        assert!(book.manifest.by_href("style_1.css").is_some());
+
+        // A rename never lands on a name the appended part uses itself
+        let mut third = part("<p>Three</p>");
+        third.manifest.add(ManifestItem::new(
+            "extra",
+            "content_2.xhtml",
+            "application/xhtml+xml",
+            ManifestData::Xhtml("<html><body/></html>".into()),
+        ));
+        append_book(&mut book, third);
+        let hrefs: HashSet<&str> = book.manifest.iter().map(|i| i.href.as_str()).collect();
+        assert_eq!(hrefs.len(), book.manifest.len());
+
+        let toc: Vec<&str> = book.toc.entries.iter().map(|e| e.href.as_str()).collect();
+        assert_eq!(
+            toc,
+            [
+                "content.xhtml#top",
+                "content_1.xhtml#top",
+                "content_1_1.xhtml#top"
+            ]
+        );
+    }
+}
@@ -0,0 +1,70 @@
+//! Standard input and output as conversion endpoints (`-` paths).
+//!
+//! Input and output plugins work on files, so stdin is spooled into a
+//! temporary file before conversion, and output is written to one and then
+//! copied to stdout.
+
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use anyhow::{Context, Result};
+
+use convert_core::book::EbookFormat;
+
+/// Whether `path` is `-`, standing for stdin or stdout.
+pub fn is_stdio(path: &Path) -> bool {
+    path.as_os_str() == "-"
+}
+
+/// Per-process counter keeping spool directories apart.
+static NEXT_SPOOL: AtomicUsize = AtomicUsize::new(0);
+
+/// A temporary directory holding spooled stdin/stdout data, removed on drop.
+pub struct Spool {
+    dir: PathBuf,
+}
+
+impl Spool {
+    /// Create an empty spool directory under the system temp directory.
+    pub fn new() -> Result<Self> {
+        let n = NEXT_SPOOL.fetch_add(1, Ordering::Relaxed);
+        let dir =
+            std::env::temp_dir().join(format!("ebook-convert-rs-{}-{}", std::process::id(), n));
+        std::fs::create_dir_all(&dir)
+            .with_context(|| format!("Cannot create {}", dir.display()))?;
+        Ok(Self { dir })
+    }
+
+    /// Path of the spooled file for `format`.
+    ///
+    /// Input plugins fall back to the file name for a missing title, so the
+    /// file is named like Calibre's placeholder title rather than "stdin".
+    pub fn path(&self, format: EbookFormat) -> PathBuf {
+        self.dir.join(format!("Unknown.{}", format.extension()))
+    }
+
+    /// Copy all of stdin into the spooled file for `format`.
+    pub fn read_stdin(&self, format: EbookFormat) -> Result<PathBuf> {
+        let path = self.path(format);
+        let mut file = std::fs::File::create(&path)
+            .with_context(|| format!("Cannot create {}", path.display()))?;
+        io::copy(&mut io::stdin().lock(), &mut file).context("Cannot read stdin")?;
+        Ok(path)
+    }
+}
+
+impl Drop for Spool {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.dir);
+    }
+}
+
+/// Write the contents of `path` to stdout.
+pub fn copy_to_stdout(path: &Path) -> Result<()> {
+    let data = std::fs::read(path).with_context(|| format!("Cannot read {}", path.display()))?;
+    let mut stdout = io::stdout().lock();
+    stdout.write_all(&data).context("Cannot write stdout")?;
+    stdout.flush().context("Cannot write stdout")?;
+    Ok(())
+}
@@ -0,0 +1,120 @@
+//! End-to-end tests for the `batch` subcommand.
+
+use std::process::Command;
+
+#[test]
+fn test_batch_converts_txt_directory_to_html() {
+    let input_dir = tempfile::tempdir().unwrap();
+    let output_dir = tempfile::tempdir().unwrap();
+    std::fs::write(
+        input_dir.path().join("first.txt"),
+        "First book.\n\nHello there.",
+    )
+    .unwrap();
+    std::fs::write(input_dir.path().join("second.txt"), "Second book.").unwrap();
+    std::fs::write(input_dir.path().join("notes.xyz"), "not a book").unwrap();
+
+    let out = Command::new(env!("CARGO_BIN_EXE_ebook-convert-rs"))
+        .arg("batch")
+        .arg(input_dir.path())
+        .args(["--to", "html", "-o"])
+        .arg(output_dir.path())
+        .output()
+        .unwrap();
+    assert!(
+        out.status.success(),
+        "batch failed: {}",
+        String::from_utf8_lossy(&out.stderr)
+    );
+
+    let first = std::fs::read_to_string(output_dir.path().join("first.html")).unwrap();
+    assert!(first.contains("Hello there."));
+    let second = std::fs::read_to_string(output_dir.path().join("second.html")).unwrap();
+    assert!(second.contains("Second book."));
+    assert!(!output_dir.path().join("notes.html").exists());
+
+    let stdout = String::from_utf8_lossy(&out.stdout);
+    assert!(stdout.contains("Converted 2 of 2 files"));
+}
+
+#[test]
+fn test_batch_continues_past_failures() {
+    let input_dir = tempfile::tempdir().unwrap();
+    let output_dir = tempfile::tempdir().unwrap();
+    std::fs::write(input_dir.path().join("good.txt"), "Fine.").unwrap();
+    std::fs::write(input_dir.path().join("broken.epub"), "not a zip").unwrap();
+
+    let out = Command::new(env!("CARGO_BIN_EXE_ebook-convert-rs"))
+        .arg("batch")
+        .arg(input_dir.path())
+        .args(["--to", "txt", "-o"])
+        .arg(output_dir.path())
+        .output()
+        .unwrap();
+
+    assert!(!out.status.success());
+    assert!(output_dir.path().join("good.txt").exists());
+    assert!(!output_dir.path().join("broken.txt").exists());
+    let stderr = String::from_utf8_lossy(&out.stderr);
+    assert!(stderr.contains("FAILED"));
+    assert!(stderr.contains("broken.epub"));
+}
+
+#[test]
+fn test_batch_keeps_inputs_with_shared_stem_apart() {
+    let input_dir = tempfile::tempdir().unwrap();
+    let output_dir = tempfile::tempdir().unwrap();
+    std::fs::write(input_dir.path().join("book.txt"), "Plain text book.").unwrap();
+    std::fs::write(input_dir.path().join("book.md"), "# Markdown book").unwrap();
+    std::fs::write(input_dir.path().join("other.txt"), "Other book.").unwrap();
+
+    let out = Command::new(env!("CARGO_BIN_EXE_ebook-convert-rs"))
+        .arg("batch")
+        .arg(input_dir.path())
+        .args(["--to", "html", "-o"])
+        .arg(output_dir.path())
+        .output()
+        .unwrap();
+    assert!(
+        out.status.success(),
+        "batch failed: {}",
+        String::from_utf8_lossy(&out.stderr)
+    );
+
+    let txt = std::fs::read_to_string(output_dir.path().join("book.txt.html")).unwrap();
+    assert!(txt.contains("Plain text book."));
+    let md = std::fs::read_to_string(output_dir.path().join("book.md.html")).unwrap();
+    assert!(md.contains("Markdown book"));
+    assert!(!output_dir.path().join("book.html").exists());
+    assert!(output_dir.path().join("other.html").exists());
+}
+
+#[test]
+fn test_batch_honors_max_threads_and_labels_progress() {
+    let input_dir = tempfile::tempdir().unwrap();
+    let output_dir = tempfile::tempdir().unwrap();
+    for name in ["one", "two", "three"] {
+        std::fs::write(input_dir.path().join(format!("{}.txt", name)), name).unwrap();
+    }
+
+    let out = Command::new(env!("CARGO_BIN_EXE_ebook-convert-rs"))
+        .arg("batch")
+        .arg(input_dir.path())
+        .args(["--to", "html", "--max-threads", "1", "-o"])
+        .arg(output_dir.path())
+        .env_remove("RUST_LOG")
+        .output()
+        .unwrap();
+    let stderr = String::from_utf8_lossy(&out.stderr);
+    assert!(out.status.success(), "batch failed: {}", stderr);
+
+    // Each file's progress is labelled with its name
+    for name in ["one", "two", "three"] {
+        assert!(
+            stderr.contains(&format!("{}.txt: Done!", name)),
+            "{}",
+            stderr
+        );
+        assert!(output_dir.path().join(format!("{}.html", name)).exists());
+    }
+}
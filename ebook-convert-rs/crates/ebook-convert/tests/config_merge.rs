@@ -0,0 +1,39 @@
+//! End-to-end test for layering the global and project-local config files.
+
+use std::process::Command;
+
+#[test]
+fn test_local_config_overrides_only_its_fields() {
+    let home = tempfile::tempdir().unwrap();
+    let global_dir = home.path().join("config").join("ebook-convert-rs");
+    std::fs::create_dir_all(&global_dir).unwrap();
+    std::fs::write(
+        global_dir.join("config.toml"),
+        "jpeg_quality = 50\nepub_version = \"3\"\n",
+    )
+    .unwrap();
+
+    let project = tempfile::tempdir().unwrap();
+    std::fs::write(
+        project.path().join(".ebook-convert-rs.toml"),
+        "jpeg_quality = 95\n",
+    )
+    .unwrap();
+
+    let out = Command::new(env!("CARGO_BIN_EXE_ebook-convert-rs"))
+        .arg("--dump-config")
+        .current_dir(project.path())
+        .env("HOME", home.path())
+        .env("XDG_CONFIG_HOME", home.path().join("config"))
+        .output()
+        .unwrap();
+    assert!(
+        out.status.success(),
+        "dump-config failed: {}",
+        String::from_utf8_lossy(&out.stderr)
+    );
+
+    let config = String::from_utf8_lossy(&out.stdout);
+    assert!(config.contains("jpeg_quality = 95"), "{}", config);
+    assert!(config.contains("epub_version = \"3\""), "{}", config);
+}
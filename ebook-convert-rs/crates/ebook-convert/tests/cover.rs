@@ -0,0 +1,63 @@
+//! End-to-end tests for the `--cover` flag.
+
+use std::process::Command;
+
+use convert_utils::archive::read_zip_entry;
+
+#[test]
+fn test_cover_flag_sets_guide_cover() {
+    let dir = tempfile::tempdir().unwrap();
+    let input = dir.path().join("book.txt");
+    let output = dir.path().join("book.epub");
+    let cover = dir.path().join("art.png");
+    std::fs::write(&input, "Chapter text.").unwrap();
+    // Smallest valid PNG: 1x1 grayscale pixel
+    std::fs::write(
+        &cover,
+        b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR\0\0\0\x01\0\0\0\x01\x08\0\0\0\0:~\x9bU\
+          \0\0\0\nIDATx\x9cc`\0\0\0\x02\0\x01H\xaf\xa4q\0\0\0\0IEND\xaeB`\x82",
+    )
+    .unwrap();
+
+    let out = Command::new(env!("CARGO_BIN_EXE_ebook-convert-rs"))
+        .arg(&input)
+        .arg(&output)
+        .arg("--cover")
+        .arg(&cover)
+        .output()
+        .unwrap();
+    assert!(
+        out.status.success(),
+        "conversion failed: {}",
+        String::from_utf8_lossy(&out.stderr)
+    );
+
+    let opf = String::from_utf8(read_zip_entry(&output, "OEBPS/content.opf").unwrap()).unwrap();
+    assert!(
+        opf.contains(r#"<reference type="cover" title="Cover" href="cover.png"/>"#),
+        "{}",
+        opf
+    );
+    assert!(opf.contains(r#"href="cover.png" media-type="image/png""#));
+    assert!(read_zip_entry(&output, "OEBPS/cover.png").is_ok());
+}
+
+#[test]
+fn test_cover_flag_rejects_unreadable_image() {
+    let dir = tempfile::tempdir().unwrap();
+    let input = dir.path().join("book.txt");
+    let output = dir.path().join("book.epub");
+    std::fs::write(&input, "Chapter text.").unwrap();
+
+    let out = Command::new(env!("CARGO_BIN_EXE_ebook-convert-rs"))
+        .arg(&input)
+        .arg(&output)
+        .arg("--cover")
+        .arg(dir.path().join("missing.jpg"))
+        .output()
+        .unwrap();
+
+    assert!(!out.status.success());
+    assert!(String::from_utf8_lossy(&out.stderr).contains("Cannot read cover image"));
+    assert!(!output.exists());
+}
@@ -0,0 +1,91 @@
+//! End-to-end test for fixed-layout EPUB round-tripping.
+
+use std::path::Path;
+use std::process::Command;
+
+use convert_utils::archive::{read_zip_entry, ZipBuilder};
+
+fn write_fixed_layout_epub(path: &Path) {
+    let mut zip = ZipBuilder::new(path).unwrap();
+    zip.add_stored("mimetype", b"application/epub+zip").unwrap();
+    zip.add_file(
+        "META-INF/container.xml",
+        br#"<?xml version="1.0"?>
+<container version="1.0" xmlns="urn:oasis:names:tc:opendocument:xmlns:container">
+  <rootfiles><rootfile full-path="OPS/package.opf" media-type="application/oebps-package+xml"/></rootfiles>
+</container>"#,
+    )
+    .unwrap();
+    zip.add_file(
+        "OPS/package.opf",
+        br#"<?xml version="1.0"?>
+<package xmlns="http://www.idpf.org/2007/opf" version="3.0" unique-identifier="bookid">
+  <metadata xmlns:dc="http://purl.org/dc/elements/1.1/">
+    <dc:title>Picture Book</dc:title>
+    <dc:identifier id="bookid">urn:uuid:fxl-test</dc:identifier>
+    <dc:language>en</dc:language>
+    <meta property="rendition:layout">pre-paginated</meta>
+    <meta property="rendition:orientation">landscape</meta>
+    <meta property="rendition:spread">none</meta>
+  </metadata>
+  <manifest>
+    <item id="p1" href="page1.xhtml" media-type="application/xhtml+xml"/>
+    <item id="p2" href="page2.xhtml" media-type="application/xhtml+xml"/>
+  </manifest>
+  <spine><itemref idref="p1"/><itemref idref="p2"/></spine>
+</package>"#,
+    )
+    .unwrap();
+    zip.add_file(
+        "OPS/page1.xhtml",
+        br#"<html xmlns="http://www.w3.org/1999/xhtml"><head><title>1</title>
+<meta name="viewport" content="width=1024, height=768"/></head><body><p>One</p></body></html>"#,
+    )
+    .unwrap();
+    zip.add_file(
+        "OPS/page2.xhtml",
+        br#"<html xmlns="http://www.w3.org/1999/xhtml"><head><title>2</title></head><body><p>Two</p></body></html>"#,
+    )
+    .unwrap();
+    zip.finish().unwrap();
+}
+
+#[test]
+fn test_fixed_layout_epub_round_trip() {
+    let dir = tempfile::tempdir().unwrap();
+    let input = dir.path().join("fxl.epub");
+    let output = dir.path().join("out.epub");
+    write_fixed_layout_epub(&input);
+
+    let out = Command::new(env!("CARGO_BIN_EXE_ebook-convert-rs"))
+        .arg(&input)
+        .arg(&output)
+        .output()
+        .unwrap();
+    assert!(
+        out.status.success(),
+        "conversion failed: {}",
+        String::from_utf8_lossy(&out.stderr)
+    );
+
+    // Written as EPUB 3 even though the default version is 2, since the
+    // rendition properties only exist in EPUB 3
+    let opf = String::from_utf8(read_zip_entry(&output, "OEBPS/content.opf").unwrap()).unwrap();
+    assert!(opf.contains(r#"version="3.0""#), "{}", opf);
+    assert!(opf.contains(r#"<meta property="rendition:layout">pre-paginated</meta>"#));
+    assert!(opf.contains(r#"<meta property="rendition:orientation">landscape</meta>"#));
+    assert!(opf.contains(r#"<meta property="rendition:spread">none</meta>"#));
+
+    for page in ["OEBPS/page1.xhtml", "OEBPS/page2.xhtml"] {
+        let xhtml = String::from_utf8(read_zip_entry(&output, page).unwrap()).unwrap();
+        assert_eq!(
+            xhtml
+                .matches(r#"name="viewport" content="width=1024, height=768""#)
+                .count(),
+            1,
+            "{} should declare the viewport once:\n{}",
+            page,
+            xhtml
+        );
+    }
+}
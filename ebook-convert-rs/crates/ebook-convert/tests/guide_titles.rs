@@ -0,0 +1,82 @@
+//! End-to-end test that EPUB guide reference titles survive conversion.
+
+use std::path::Path;
+use std::process::Command;
+
+use convert_utils::archive::{read_zip_entry, ZipBuilder};
+
+fn write_guide_epub(path: &Path) {
+    let mut zip = ZipBuilder::new(path).unwrap();
+    zip.add_stored("mimetype", b"application/epub+zip").unwrap();
+    zip.add_file(
+        "META-INF/container.xml",
+        br#"<?xml version="1.0"?>
+<container version="1.0" xmlns="urn:oasis:names:tc:opendocument:xmlns:container">
+  <rootfiles><rootfile full-path="OEBPS/content.opf" media-type="application/oebps-package+xml"/></rootfiles>
+</container>"#,
+    )
+    .unwrap();
+    zip.add_file(
+        "OEBPS/content.opf",
+        br#"<?xml version="1.0"?>
+<package xmlns="http://www.idpf.org/2007/opf" version="2.0" unique-identifier="bookid">
+  <metadata xmlns:dc="http://purl.org/dc/elements/1.1/">
+    <dc:title>Guided</dc:title>
+    <dc:identifier id="bookid">urn:uuid:guide-test</dc:identifier>
+    <dc:language>en</dc:language>
+  </metadata>
+  <manifest>
+    <item id="contents" href="contents.xhtml" media-type="application/xhtml+xml"/>
+    <item id="ch1" href="ch1.xhtml" media-type="application/xhtml+xml"/>
+  </manifest>
+  <spine><itemref idref="contents"/><itemref idref="ch1"/></spine>
+  <guide>
+    <reference type="toc" title="List of Chapters" href="contents.xhtml"/>
+    <reference type="text" title="Beginning" href="ch1.xhtml"/>
+  </guide>
+</package>"#,
+    )
+    .unwrap();
+    zip.add_file(
+        "OEBPS/contents.xhtml",
+        br#"<html xmlns="http://www.w3.org/1999/xhtml"><head><title>Contents</title></head><body><p><a href="ch1.xhtml">Chapter One</a></p></body></html>"#,
+    )
+    .unwrap();
+    zip.add_file(
+        "OEBPS/ch1.xhtml",
+        br#"<html xmlns="http://www.w3.org/1999/xhtml"><head><title>1</title></head><body><h1>Chapter One</h1><p>It begins.</p></body></html>"#,
+    )
+    .unwrap();
+    zip.finish().unwrap();
+}
+
+#[test]
+fn test_guide_titles_survive_epub_round_trip() {
+    let dir = tempfile::tempdir().unwrap();
+    let input = dir.path().join("guided.epub");
+    let output = dir.path().join("out.epub");
+    write_guide_epub(&input);
+
+    let out = Command::new(env!("CARGO_BIN_EXE_ebook-convert-rs"))
+        .arg(&input)
+        .arg(&output)
+        .output()
+        .unwrap();
+    assert!(
+        out.status.success(),
+        "conversion failed: {}",
+        String::from_utf8_lossy(&out.stderr)
+    );
+
+    let opf = String::from_utf8(read_zip_entry(&output, "OEBPS/content.opf").unwrap()).unwrap();
+    assert!(
+        opf.contains(r#"<reference type="text" title="Beginning" href="ch1.xhtml"/>"#),
+        "{}",
+        opf
+    );
+    assert!(
+        opf.contains(r#"<reference type="toc" title="List of Chapters" href="contents.xhtml"/>"#),
+        "{}",
+        opf
+    );
+}
@@ -0,0 +1,33 @@
+//! The library entry point converts without going through the command line.
+
+use convert_core::error::ConvertError;
+use convert_core::options::ConversionOptions;
+use convert_utils::archive::read_zip_entry;
+
+#[test]
+fn test_convert_txt_to_epub() {
+    let dir = tempfile::tempdir().unwrap();
+    let input = dir.path().join("notes.txt");
+    let output = dir.path().join("notes.epub");
+    std::fs::write(&input, "Chapter One\n\nIt was a dark and stormy night.\n").unwrap();
+
+    ebook_convert::convert(&input, &output, &ConversionOptions::default()).unwrap();
+
+    let opf = read_zip_entry(&output, "OEBPS/content.opf").unwrap();
+    assert!(String::from_utf8_lossy(&opf).contains("<spine"));
+}
+
+#[test]
+fn test_convert_rejects_unknown_extension() {
+    let dir = tempfile::tempdir().unwrap();
+    let input = dir.path().join("notes.xyz");
+    std::fs::write(&input, "?").unwrap();
+
+    let err = ebook_convert::convert(
+        &input,
+        &dir.path().join("notes.epub"),
+        &ConversionOptions::default(),
+    )
+    .unwrap_err();
+    assert!(matches!(err, ConvertError::UnsupportedFormat(_)), "{}", err);
+}
@@ -0,0 +1,108 @@
+//! End-to-end test for EPUB 3 media overlay (read-aloud) round-tripping.
+
+use std::path::Path;
+use std::process::Command;
+
+use convert_utils::archive::{read_zip_entry, ZipBuilder};
+
+const SMIL: &[u8] = br##"<?xml version="1.0" encoding="UTF-8"?>
+<smil xmlns="http://www.w3.org/ns/SMIL" version="3.0">
+  <body>
+    <par id="p1">
+      <text src="ch1.xhtml#s1"/>
+      <audio src="audio/ch1.mp3" clipBegin="0:00:00.000" clipEnd="0:00:02.500"/>
+    </par>
+  </body>
+</smil>"##;
+
+fn write_read_aloud_epub(path: &Path) {
+    let mut zip = ZipBuilder::new(path).unwrap();
+    zip.add_stored("mimetype", b"application/epub+zip").unwrap();
+    zip.add_file(
+        "META-INF/container.xml",
+        br#"<?xml version="1.0"?>
+<container version="1.0" xmlns="urn:oasis:names:tc:opendocument:xmlns:container">
+  <rootfiles><rootfile full-path="OPS/package.opf" media-type="application/oebps-package+xml"/></rootfiles>
+</container>"#,
+    )
+    .unwrap();
+    zip.add_file(
+        "OPS/package.opf",
+        br##"<?xml version="1.0"?>
+<package xmlns="http://www.idpf.org/2007/opf" version="3.0" unique-identifier="bookid">
+  <metadata xmlns:dc="http://purl.org/dc/elements/1.1/">
+    <dc:title>Read Aloud</dc:title>
+    <dc:identifier id="bookid">urn:uuid:smil-test</dc:identifier>
+    <dc:language>en</dc:language>
+    <meta property="media:duration" refines="#ch1_overlay">0:00:02.500</meta>
+    <meta property="media:duration">0:00:02.500</meta>
+    <meta property="media:active-class">-epub-media-overlay-active</meta>
+  </metadata>
+  <manifest>
+    <item id="ch1" href="ch1.xhtml" media-type="application/xhtml+xml" media-overlay="ch1_overlay"/>
+    <item id="ch1_overlay" href="ch1.smil" media-type="application/smil+xml"/>
+    <item id="ch1_audio" href="audio/ch1.mp3" media-type="audio/mpeg"/>
+  </manifest>
+  <spine><itemref idref="ch1"/></spine>
+</package>"##,
+    )
+    .unwrap();
+    zip.add_file(
+        "OPS/ch1.xhtml",
+        br#"<html xmlns="http://www.w3.org/1999/xhtml"><head><title>1</title></head><body><p id="s1">Hello there.</p></body></html>"#,
+    )
+    .unwrap();
+    zip.add_file("OPS/ch1.smil", SMIL).unwrap();
+    zip.add_stored("OPS/audio/ch1.mp3", b"ID3 fake audio")
+        .unwrap();
+    zip.finish().unwrap();
+}
+
+#[test]
+fn test_media_overlay_epub_round_trip() {
+    let dir = tempfile::tempdir().unwrap();
+    let input = dir.path().join("read_aloud.epub");
+    let output = dir.path().join("out.epub");
+    write_read_aloud_epub(&input);
+
+    // Media overlays are EPUB 3 only
+    std::fs::write(
+        dir.path().join(".ebook-convert-rs.toml"),
+        "epub_version = \"3\"\n",
+    )
+    .unwrap();
+    let out = Command::new(env!("CARGO_BIN_EXE_ebook-convert-rs"))
+        .arg(&input)
+        .arg(&output)
+        .current_dir(dir.path())
+        .env("HOME", dir.path())
+        .env("XDG_CONFIG_HOME", dir.path().join("config"))
+        .output()
+        .unwrap();
+    assert!(
+        out.status.success(),
+        "conversion failed: {}",
+        String::from_utf8_lossy(&out.stderr)
+    );
+
+    let opf = String::from_utf8(read_zip_entry(&output, "OEBPS/content.opf").unwrap()).unwrap();
+    assert!(
+        opf.contains(r#"media-overlay="ch1_overlay""#),
+        "missing media-overlay attribute:\n{}",
+        opf
+    );
+    assert!(opf
+        .contains(r#"<item id="ch1_overlay" href="ch1.smil" media-type="application/smil+xml"/>"#));
+    assert!(opf.contains(
+        r##"<meta property="media:duration" refines="#ch1_overlay">0:00:02.500</meta>"##
+    ));
+    assert!(
+        opf.contains(r#"<meta property="media:active-class">-epub-media-overlay-active</meta>"#)
+    );
+
+    assert_eq!(read_zip_entry(&output, "OEBPS/ch1.smil").unwrap(), SMIL);
+    assert_eq!(
+        read_zip_entry(&output, "OEBPS/audio/ch1.mp3").unwrap(),
+        b"ID3 fake audio"
+    );
+}
@@ -0,0 +1,63 @@
+//! End-to-end tests for `convert --merge`.
+
+use std::process::Command;
+
+use convert_utils::archive::read_zip_entry;
+
+#[test]
+fn test_merge_two_txt_files_into_epub() {
+    let dir = tempfile::tempdir().unwrap();
+    let first = dir.path().join("opening.txt");
+    let second = dir.path().join("closing.txt");
+    let output = dir.path().join("book.epub");
+    std::fs::write(&first, "It was a dark night.").unwrap();
+    std::fs::write(&second, "And then it was morning.").unwrap();
+
+    let out = Command::new(env!("CARGO_BIN_EXE_ebook-convert-rs"))
+        .args(["convert", "--merge"])
+        .arg(&first)
+        .arg(&second)
+        .arg("-o")
+        .arg(&output)
+        .output()
+        .unwrap();
+    assert!(
+        out.status.success(),
+        "merge failed: {}",
+        String::from_utf8_lossy(&out.stderr)
+    );
+
+    let opf = String::from_utf8(read_zip_entry(&output, "OEBPS/content.opf").unwrap()).unwrap();
+    assert_eq!(opf.matches("<itemref ").count(), 2, "{}", opf);
+    // Metadata comes from the first file
+    assert!(opf.contains("<dc:title>opening</dc:title>"), "{}", opf);
+
+    let one = read_zip_entry(&output, "OEBPS/content.xhtml").unwrap();
+    assert!(String::from_utf8(one)
+        .unwrap()
+        .contains("It was a dark night."));
+    let two = read_zip_entry(&output, "OEBPS/content_1.xhtml").unwrap();
+    assert!(String::from_utf8(two)
+        .unwrap()
+        .contains("And then it was morning."));
+}
+
+#[test]
+fn test_several_inputs_need_merge() {
+    let dir = tempfile::tempdir().unwrap();
+    let first = dir.path().join("a.txt");
+    let second = dir.path().join("b.txt");
+    std::fs::write(&first, "A").unwrap();
+    std::fs::write(&second, "B").unwrap();
+
+    let out = Command::new(env!("CARGO_BIN_EXE_ebook-convert-rs"))
+        .arg("convert")
+        .arg(&first)
+        .arg(&second)
+        .arg("-o")
+        .arg(dir.path().join("out.epub"))
+        .output()
+        .unwrap();
+    assert!(!out.status.success());
+    assert!(String::from_utf8_lossy(&out.stderr).contains("--merge"));
+}
@@ -0,0 +1,31 @@
+//! End-to-end tests for the metadata override flags.
+
+use std::process::Command;
+
+use convert_utils::archive::read_zip_entry;
+
+#[test]
+fn test_title_and_authors_override_input_metadata() {
+    let dir = tempfile::tempdir().unwrap();
+    let input = dir.path().join("Original Title.txt");
+    let output = dir.path().join("out.epub");
+    std::fs::write(&input, "Some text.\n\nMore text.").unwrap();
+
+    let out = Command::new(env!("CARGO_BIN_EXE_ebook-convert-rs"))
+        .arg(&input)
+        .arg(&output)
+        .args(["--title", "New", "--authors", "Ann One, Bob Two"])
+        .output()
+        .unwrap();
+    assert!(
+        out.status.success(),
+        "conversion failed: {}",
+        String::from_utf8_lossy(&out.stderr)
+    );
+
+    let opf = String::from_utf8(read_zip_entry(&output, "OEBPS/content.opf").unwrap()).unwrap();
+    assert!(opf.contains("<dc:title>New</dc:title>"), "{}", opf);
+    assert!(!opf.contains("Original Title"));
+    assert!(opf.contains(r#"<dc:creator opf:role="aut">Ann One</dc:creator>"#));
+    assert!(opf.contains(r#"<dc:creator opf:role="aut">Bob Two</dc:creator>"#));
+}
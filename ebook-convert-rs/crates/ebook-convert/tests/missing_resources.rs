@@ -0,0 +1,90 @@
+//! End-to-end test that EPUB input with missing resources still converts.
+
+use std::path::Path;
+use std::process::Command;
+
+use convert_utils::archive::{read_zip_entry, ZipBuilder};
+
+/// An EPUB whose OPF lists an image the archive does not contain.
+fn write_epub_missing_image(path: &Path) {
+    let mut zip = ZipBuilder::new(path).unwrap();
+    zip.add_stored("mimetype", b"application/epub+zip").unwrap();
+    zip.add_file(
+        "META-INF/container.xml",
+        br#"<?xml version="1.0"?>
+<container version="1.0" xmlns="urn:oasis:names:tc:opendocument:xmlns:container">
+  <rootfiles><rootfile full-path="OEBPS/content.opf" media-type="application/oebps-package+xml"/></rootfiles>
+</container>"#,
+    )
+    .unwrap();
+    zip.add_file(
+        "OEBPS/content.opf",
+        br#"<?xml version="1.0"?>
+<package xmlns="http://www.idpf.org/2007/opf" version="2.0" unique-identifier="bookid">
+  <metadata xmlns:dc="http://purl.org/dc/elements/1.1/">
+    <dc:title>Holes</dc:title>
+    <dc:identifier id="bookid">urn:uuid:missing-test</dc:identifier>
+    <dc:language>en</dc:language>
+  </metadata>
+  <manifest>
+    <item id="ch1" href="ch1.xhtml" media-type="application/xhtml+xml"/>
+    <item id="map" href="images/map.png" media-type="image/png"/>
+  </manifest>
+  <spine><itemref idref="ch1"/></spine>
+</package>"#,
+    )
+    .unwrap();
+    zip.add_file(
+        "OEBPS/ch1.xhtml",
+        br#"<html xmlns="http://www.w3.org/1999/xhtml"><head><title>1</title></head><body><h1>Chapter One</h1><p><img src="images/map.png" alt="A map of the island"/></p><p>It begins.</p></body></html>"#,
+    )
+    .unwrap();
+    zip.finish().unwrap();
+}
+
+fn convert(input: &Path, output: &Path, args: &[&str]) -> String {
+    let out = Command::new(env!("CARGO_BIN_EXE_ebook-convert-rs"))
+        .arg(input)
+        .arg(output)
+        .args(args)
+        .output()
+        .unwrap();
+    let stderr = String::from_utf8_lossy(&out.stderr).into_owned();
+    assert!(out.status.success(), "conversion failed: {}", stderr);
+    stderr
+}
+
+#[test]
+fn test_missing_image_is_logged() {
+    let dir = tempfile::tempdir().unwrap();
+    let input = dir.path().join("holes.epub");
+    let output = dir.path().join("out.epub");
+    write_epub_missing_image(&input);
+
+    let stderr = convert(&input, &output, &[]);
+    assert!(
+        stderr.contains("Missing EPUB resource: href=images/map.png id=map reason=unreadable"),
+        "{}",
+        stderr
+    );
+}
+
+#[test]
+fn test_missing_image_replaced_by_placeholder() {
+    let dir = tempfile::tempdir().unwrap();
+    let input = dir.path().join("holes.epub");
+    let output = dir.path().join("out.epub");
+    write_epub_missing_image(&input);
+
+    convert(&input, &output, &["--missing-resources", "placeholder"]);
+
+    let opf = String::from_utf8(read_zip_entry(&output, "OEBPS/content.opf").unwrap()).unwrap();
+    assert!(!opf.contains("map.png"), "{}", opf);
+    let ch1 = String::from_utf8(read_zip_entry(&output, "OEBPS/ch1.xhtml").unwrap()).unwrap();
+    assert!(!ch1.contains("<img"), "{}", ch1);
+    assert!(
+        ch1.contains(r#"<span class="missing-image">A map of the island</span>"#),
+        "{}",
+        ch1
+    );
+}
@@ -0,0 +1,85 @@
+//! End-to-end test that EPUB creator roles, dates and rights survive
+//! conversion.
+
+use std::path::Path;
+use std::process::Command;
+
+use convert_utils::archive::{read_zip_entry, ZipBuilder};
+
+fn write_epub(path: &Path) {
+    let mut zip = ZipBuilder::new(path).unwrap();
+    zip.add_stored("mimetype", b"application/epub+zip").unwrap();
+    zip.add_file(
+        "META-INF/container.xml",
+        br#"<?xml version="1.0"?>
+<container version="1.0" xmlns="urn:oasis:names:tc:opendocument:xmlns:container">
+  <rootfiles><rootfile full-path="OEBPS/content.opf" media-type="application/oebps-package+xml"/></rootfiles>
+</container>"#,
+    )
+    .unwrap();
+    zip.add_file(
+        "OEBPS/content.opf",
+        br#"<?xml version="1.0"?>
+<package xmlns="http://www.idpf.org/2007/opf" version="2.0" unique-identifier="bookid">
+  <metadata xmlns:dc="http://purl.org/dc/elements/1.1/" xmlns:opf="http://www.idpf.org/2007/opf">
+    <dc:title>Credits</dc:title>
+    <dc:creator opf:role="aut" opf:file-as="Writer, Ann">Ann Writer</dc:creator>
+    <dc:creator opf:role="edt">Ed Itor</dc:creator>
+    <dc:date>2019-03-14</dc:date>
+    <dc:rights>Public domain</dc:rights>
+    <dc:identifier id="bookid">urn:uuid:credits-test</dc:identifier>
+    <dc:language>en</dc:language>
+  </metadata>
+  <manifest>
+    <item id="ch1" href="ch1.xhtml" media-type="application/xhtml+xml"/>
+  </manifest>
+  <spine><itemref idref="ch1"/></spine>
+</package>"#,
+    )
+    .unwrap();
+    zip.add_file(
+        "OEBPS/ch1.xhtml",
+        br#"<html xmlns="http://www.w3.org/1999/xhtml"><head><title>1</title></head><body><p>Text.</p></body></html>"#,
+    )
+    .unwrap();
+    zip.finish().unwrap();
+}
+
+#[test]
+fn test_creator_roles_survive_epub_round_trip() {
+    let dir = tempfile::tempdir().unwrap();
+    let input = dir.path().join("credits.epub");
+    let output = dir.path().join("out.epub");
+    write_epub(&input);
+
+    let out = Command::new(env!("CARGO_BIN_EXE_ebook-convert-rs"))
+        .arg(&input)
+        .arg(&output)
+        .output()
+        .unwrap();
+    assert!(
+        out.status.success(),
+        "conversion failed: {}",
+        String::from_utf8_lossy(&out.stderr)
+    );
+
+    let opf = String::from_utf8(read_zip_entry(&output, "OEBPS/content.opf").unwrap()).unwrap();
+    assert!(
+        opf.contains(
+            r#"<dc:creator opf:role="aut" opf:file-as="Writer, Ann">Ann Writer</dc:creator>"#
+        ),
+        "{}",
+        opf
+    );
+    assert!(
+        opf.contains(r#"<dc:creator opf:role="edt">Ed Itor</dc:creator>"#),
+        "{}",
+        opf
+    );
+    assert!(opf.contains("<dc:date>2019-03-14</dc:date>"), "{}", opf);
+    assert!(
+        opf.contains("<dc:rights>Public domain</dc:rights>"),
+        "{}",
+        opf
+    );
+}
@@ -0,0 +1,29 @@
+//! Progress reporting when stdout is not a terminal.
+
+use std::process::Command;
+
+#[test]
+fn test_piped_output_logs_progress() {
+    let dir = tempfile::tempdir().unwrap();
+    let input = dir.path().join("book.txt");
+    std::fs::write(&input, "Chapter One\n\nIt was a dark night.").unwrap();
+
+    // Output::stdout is a pipe, so the log lines stand in for the bar
+    let out = Command::new(env!("CARGO_BIN_EXE_ebook-convert-rs"))
+        .arg(&input)
+        .arg(dir.path().join("book.epub"))
+        .env_remove("RUST_LOG")
+        .output()
+        .unwrap();
+    let stderr = String::from_utf8_lossy(&out.stderr);
+    assert!(out.status.success(), "{}", stderr);
+
+    assert!(
+        stderr.contains("[  0%] Starting conversion..."),
+        "{}",
+        stderr
+    );
+    assert!(stderr.contains("Running "), "{}", stderr);
+    assert!(stderr.contains("Creating "), "{}", stderr);
+    assert!(stderr.contains("Done!"), "{}", stderr);
+}
@@ -0,0 +1,43 @@
+//! End-to-end test that right-to-left books get RTL page progression.
+
+use std::process::Command;
+
+use convert_utils::archive::{list_zip_entries, read_zip_entry};
+
+#[test]
+fn test_arabic_book_reads_right_to_left() {
+    let dir = tempfile::tempdir().unwrap();
+    let input = dir.path().join("book.txt");
+    let output = dir.path().join("out.epub");
+    std::fs::write(&input, "الفصل الأول\n\nكان يا ما كان.").unwrap();
+
+    let out = Command::new(env!("CARGO_BIN_EXE_ebook-convert-rs"))
+        .arg(&input)
+        .arg(&output)
+        .args(["--language", "ar"])
+        .output()
+        .unwrap();
+    assert!(
+        out.status.success(),
+        "conversion failed: {}",
+        String::from_utf8_lossy(&out.stderr)
+    );
+
+    let opf = String::from_utf8(read_zip_entry(&output, "OEBPS/content.opf").unwrap()).unwrap();
+    assert!(
+        opf.contains(r#"page-progression-direction="rtl""#),
+        "{}",
+        opf
+    );
+
+    let documents: Vec<String> = list_zip_entries(&output)
+        .unwrap()
+        .into_iter()
+        .filter(|name| name.ends_with(".xhtml") && !name.ends_with("nav.xhtml"))
+        .collect();
+    assert!(!documents.is_empty());
+    for name in documents {
+        let xhtml = String::from_utf8(read_zip_entry(&output, &name).unwrap()).unwrap();
+        assert!(xhtml.contains(r#"dir="rtl""#), "{}: {}", name, xhtml);
+    }
+}
@@ -0,0 +1,52 @@
+//! End-to-end test for `--stats`.
+
+use std::process::Command;
+
+#[test]
+fn test_stats_prints_metrics_without_output() {
+    let dir = tempfile::tempdir().unwrap();
+    let input = dir.path().join("book.html");
+    std::fs::write(
+        &input,
+        r#"<html><head><title>Counting Sheep</title></head>
+<body><h1>One</h1><p>Sheep jump over the fence.</p><h2>Two</h2><p>More sheep.</p></body></html>"#,
+    )
+    .unwrap();
+
+    let out = Command::new(env!("CARGO_BIN_EXE_ebook-convert-rs"))
+        .arg(&input)
+        .arg("--stats")
+        .output()
+        .unwrap();
+    assert!(
+        out.status.success(),
+        "stats failed: {}",
+        String::from_utf8_lossy(&out.stderr)
+    );
+
+    let stdout = String::from_utf8_lossy(&out.stdout);
+    assert!(stdout.contains("Title:       Counting Sheep"), "{}", stdout);
+    assert!(stdout.contains("Spine items: 1"), "{}", stdout);
+    assert!(stdout.contains("Words:       9"), "{}", stdout);
+    assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 1);
+}
+
+#[test]
+fn test_stats_shows_metadata_overrides() {
+    let dir = tempfile::tempdir().unwrap();
+    let input = dir.path().join("book.html");
+    std::fs::write(
+        &input,
+        "<html><head><title>Counting Sheep</title></head><body><p>Baa.</p></body></html>",
+    )
+    .unwrap();
+
+    let out = Command::new(env!("CARGO_BIN_EXE_ebook-convert-rs"))
+        .arg(&input)
+        .args(["--stats", "--title", "Counting Goats"])
+        .output()
+        .unwrap();
+    assert!(out.status.success());
+    let stdout = String::from_utf8_lossy(&out.stdout);
+    assert!(stdout.contains("Title:       Counting Goats"), "{}", stdout);
+}
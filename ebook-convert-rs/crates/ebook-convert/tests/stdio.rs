@@ -0,0 +1,50 @@
+//! Reading input from stdin and writing output to stdout (`-` paths).
+
+use std::io::{Cursor, Read, Write};
+use std::process::{Command, Stdio};
+
+#[test]
+fn test_txt_from_stdin_to_epub_on_stdout() {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_ebook-convert-rs"))
+        .args(["convert", "--from", "txt", "--to", "epub", "-", "-o", "-"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(b"Chapter One\n\nIt was a dark and stormy night.")
+        .unwrap();
+    let out = child.wait_with_output().unwrap();
+    assert!(
+        out.status.success(),
+        "{}",
+        String::from_utf8_lossy(&out.stderr)
+    );
+
+    let mut epub = zip::ZipArchive::new(Cursor::new(out.stdout)).unwrap();
+    assert_eq!(epub.by_index(0).unwrap().name(), "mimetype");
+    let mut text = String::new();
+    for i in 0..epub.len() {
+        let mut entry = epub.by_index(i).unwrap();
+        if entry.name().ends_with(".xhtml") {
+            entry.read_to_string(&mut text).unwrap();
+        }
+    }
+    assert!(text.contains("It was a dark and stormy night."), "{}", text);
+}
+
+#[test]
+fn test_stdin_requires_from() {
+    let out = Command::new(env!("CARGO_BIN_EXE_ebook-convert-rs"))
+        .args(["convert", "-", "-o", "book.epub"])
+        .stdin(Stdio::null())
+        .output()
+        .unwrap();
+    assert!(!out.status.success());
+    let stderr = String::from_utf8_lossy(&out.stderr);
+    assert!(stderr.contains("--from"), "{}", stderr);
+}
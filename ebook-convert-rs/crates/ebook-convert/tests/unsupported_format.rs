@@ -0,0 +1,42 @@
+//! Recognized but unsupported formats are reported by name.
+
+use std::process::Command;
+
+#[test]
+fn test_lit_input_reports_unsupported_format() {
+    let dir = tempfile::tempdir().unwrap();
+    let input = dir.path().join("old.lit");
+    std::fs::write(&input, b"ITOLITLS").unwrap();
+
+    let out = Command::new(env!("CARGO_BIN_EXE_ebook-convert-rs"))
+        .arg(&input)
+        .arg(dir.path().join("old.epub"))
+        .output()
+        .unwrap();
+
+    assert!(!out.status.success());
+    let stderr = String::from_utf8_lossy(&out.stderr);
+    assert!(
+        stderr.contains("LIT (Microsoft Reader) is recognized but not supported yet"),
+        "{}",
+        stderr
+    );
+    assert!(!stderr.contains("Cannot detect"));
+}
+
+#[test]
+fn test_unknown_extension_still_asks_for_from() {
+    let dir = tempfile::tempdir().unwrap();
+    let input = dir.path().join("notes.xyz");
+    std::fs::write(&input, b"?").unwrap();
+
+    let out = Command::new(env!("CARGO_BIN_EXE_ebook-convert-rs"))
+        .arg(&input)
+        .arg(dir.path().join("notes.epub"))
+        .output()
+        .unwrap();
+
+    assert!(!out.status.success());
+    let stderr = String::from_utf8_lossy(&out.stderr);
+    assert!(stderr.contains("Cannot detect input format"), "{}", stderr);
+}